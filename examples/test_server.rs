@@ -0,0 +1,220 @@
+// A stand-in HTTP server for trying out `fetch` in compiled scripts, without
+// depending on anything outside of the machine. It only speaks as much
+// HTTP/1.1 as needed for that: every connection handles a single request.
+//
+//     cargo run --example test_server [address] [--verbose]
+//
+// The address defaults to 127.0.0.1:8080, `--verbose` prints every request
+// with the status of its response. Nothing runs this server automatically,
+// `fetch` is only tested by hand against it. Routes:
+//
+//     /text          a plain text body
+//     /json          a JSON document
+//     /headers       the request headers as a JSON object
+//     /echo          the request body, with the method in `x-request-method`
+//     /status/<code> an empty response with the given status
+//     /large?size=n  n bytes of text, 1MB by default
+//     /slow?ms=n     /text after a delay of n milliseconds, 1000 by default
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::thread;
+use std::time::Duration;
+
+struct Request {
+    method: String,
+    path: String,
+    query: String,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+struct Response {
+    status: u16,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+impl Response {
+    fn new(status: u16, content_type: &str, body: impl Into<Vec<u8>>) -> Self {
+        Self {
+            status,
+            headers: vec![("content-type".into(), content_type.into())],
+            body: body.into(),
+        }
+    }
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        201 => "Created",
+        204 => "No Content",
+        301 => "Moved Permanently",
+        302 => "Found",
+        400 => "Bad Request",
+        404 => "Not Found",
+        500 => "Internal Server Error",
+        _ => "",
+    }
+}
+
+fn json_string(value: &str) -> String {
+    let mut escaped = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+fn query_number(query: &str, name: &str, default: u64) -> u64 {
+    query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(key, _)| *key == name)
+        .and_then(|(_, value)| value.parse().ok())
+        .unwrap_or(default)
+}
+
+fn read_request(stream: &mut TcpStream) -> anyhow::Result<Request> {
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    let mut parts = line.split_whitespace();
+    let method = parts.next().unwrap_or_default().to_string();
+    let target = parts.next().unwrap_or("/");
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let (path, query) = (path.to_string(), query.to_string());
+
+    let mut headers = Vec::new();
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            break;
+        }
+        let header = line.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            headers.push((name.trim().to_ascii_lowercase(), value.trim().to_string()));
+        }
+    }
+
+    let length = headers
+        .iter()
+        .find(|(name, _)| name == "content-length")
+        .and_then(|(_, value)| value.parse().ok())
+        .unwrap_or(0);
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body)?;
+
+    Ok(Request {
+        method,
+        path,
+        query,
+        headers,
+        body,
+    })
+}
+
+fn route(request: &Request) -> Response {
+    let path = request.path.as_str();
+    match path {
+        "/text" => Response::new(200, "text/plain", "hello from the test server"),
+        "/json" => Response::new(
+            200,
+            "application/json",
+            r#"{"name":"jaws","numbers":[1,2,3],"nested":{"ok":true}}"#,
+        ),
+        "/headers" => {
+            let fields: Vec<String> = request
+                .headers
+                .iter()
+                .map(|(name, value)| format!("{}:{}", json_string(name), json_string(value)))
+                .collect();
+            Response::new(200, "application/json", format!("{{{}}}", fields.join(",")))
+        }
+        "/echo" => {
+            let content_type = request
+                .headers
+                .iter()
+                .find(|(name, _)| name == "content-type")
+                .map(|(_, value)| value.as_str())
+                .unwrap_or("application/octet-stream");
+            let mut response = Response::new(200, content_type, request.body.clone());
+            response
+                .headers
+                .push(("x-request-method".into(), request.method.clone()));
+            response
+        }
+        "/large" => {
+            let size = query_number(&request.query, "size", 1 << 20) as usize;
+            let body: Vec<u8> = (0..size).map(|i| b'a' + (i % 26) as u8).collect();
+            Response::new(200, "text/plain", body)
+        }
+        "/slow" => {
+            thread::sleep(Duration::from_millis(query_number(
+                &request.query,
+                "ms",
+                1000,
+            )));
+            Response::new(200, "text/plain", "hello from the test server")
+        }
+        _ => match path.strip_prefix("/status/").map(str::parse::<u16>) {
+            Some(Ok(status)) => Response::new(status, "text/plain", ""),
+            _ => Response::new(404, "text/plain", "not found"),
+        },
+    }
+}
+
+fn handle(mut stream: TcpStream, verbose: bool) -> anyhow::Result<()> {
+    let request = read_request(&mut stream)?;
+    let response = route(&request);
+    if verbose {
+        println!("{} {} {}", request.method, request.path, response.status);
+    }
+
+    let mut head = format!(
+        "HTTP/1.1 {} {}\r\n",
+        response.status,
+        reason(response.status)
+    );
+    for (name, value) in &response.headers {
+        head.push_str(&format!("{name}: {value}\r\n"));
+    }
+    head.push_str(&format!(
+        "content-length: {}\r\nconnection: close\r\n\r\n",
+        response.body.len()
+    ));
+    stream.write_all(head.as_bytes())?;
+    stream.write_all(&response.body)?;
+    Ok(())
+}
+
+fn main() -> anyhow::Result<()> {
+    let (flags, args): (Vec<String>, Vec<String>) = std::env::args()
+        .skip(1)
+        .partition(|arg| arg.starts_with("--"));
+    let verbose = flags.iter().any(|flag| flag == "--verbose");
+    let address = args
+        .into_iter()
+        .next()
+        .unwrap_or_else(|| "127.0.0.1:8080".into());
+    let listener = TcpListener::bind(&address)?;
+    println!("listening on http://{address}");
+    for stream in listener.incoming() {
+        let stream = stream?;
+        thread::spawn(move || {
+            if let Err(error) = handle(stream, verbose) {
+                eprintln!("error: {error}");
+            }
+        });
+    }
+    Ok(())
+}
@@ -1,6 +1,9 @@
 let instance,
-  pollables = [],
-  pollableIndex = 0;
+  pollables = new Map(),
+  pollableIndex = 0,
+  // wasi:http and wasi:io/streams resources, by handle
+  resources = new Map(),
+  resourceIndex = 0;
 
 function sleep(ms) {
   return new Promise((resolve) =>
@@ -11,76 +14,688 @@ function sleep(ms) {
 }
 
 class Pollable {
-  constructor(promise, index) {
-    this.promise = promise.then(() => index);
-    this.index = index;
-
-    promise
-      .then(() => {
-        this.ready = true;
-      })
-      .catch(() => {
-        this.ready = true;
+  constructor(promise) {
+    this.isReady = false;
+    this.promise = promise.then(
+      () => {
+        this.isReady = true;
+      },
+      () => {
+        this.isReady = true;
         this.failed = true;
-      });
+      },
+    );
   }
 
   ready() {
-    return this.ready;
+    return this.isReady;
   }
 
-  getIndex() {
-    return this.index;
+  getPromise() {
+    return this.promise;
   }
+}
 
-  getPromise() {
-    return this.promise.then(() => this);
+function addPollable(promise) {
+  const handle = pollableIndex;
+  pollables.set(handle, new Pollable(promise));
+  pollableIndex++;
+  return handle;
+}
+
+function addResource(resource) {
+  const handle = resourceIndex;
+  resources.set(handle, resource);
+  resourceIndex++;
+  return handle;
+}
+
+function dropResource(handle) {
+  resources.delete(handle);
+}
+
+function memoryView() {
+  return new DataView(instance.exports.memory.buffer);
+}
+
+function readBytes(ptr, length) {
+  return new Uint8Array(instance.exports.memory.buffer, ptr, length).slice();
+}
+
+function readString(ptr, length) {
+  return new TextDecoder().decode(readBytes(ptr, length));
+}
+
+// wasi:cli/stdin, fed from Node's stdin. It's paused whenever there's a
+// chunk the module didn't read yet, so a script that doesn't read doesn't
+// keep Node running
+function openStdin() {
+  const stream = { stdin: true, chunks: [], ended: typeof process === "undefined" };
+  if (stream.ended) {
+    return stream;
   }
+  const wake = () => {
+    if (stream.wake) {
+      stream.wake();
+      stream.wake = null;
+    }
+  };
+  process.stdin.on("data", (chunk) => {
+    stream.chunks.push(new Uint8Array(chunk));
+    process.stdin.pause();
+    wake();
+  });
+  process.stdin.on("end", () => {
+    stream.ended = true;
+    wake();
+  });
+  process.stdin.pause();
+  return stream;
 }
 
-function findPollable(id) {
-  for (const pollable of pollables) {
-    if (pollable.getIndex() === id) {
-      return pollable;
+// result<list<u8>, stream-error>, an empty list if nothing arrived yet
+function readStdin(stream, length, returnPtr) {
+  if (!stream.chunks.length && stream.ended) {
+    const dataView = memoryView();
+    dataView.setUint8(returnPtr, 1);
+    dataView.setUint8(returnPtr + 4, 1);
+    return;
+  }
+  let chunk = new Uint8Array(0);
+  if (stream.chunks.length) {
+    chunk = stream.chunks[0].subarray(0, length);
+    if (chunk.length === stream.chunks[0].length) {
+      stream.chunks.shift();
+    } else {
+      stream.chunks[0] = stream.chunks[0].subarray(length);
     }
   }
+  const ptr = writeBytes(chunk);
+  const dataView = memoryView();
+  dataView.setUint8(returnPtr, 0);
+  dataView.setUint32(returnPtr + 4, ptr, true);
+  dataView.setUint32(returnPtr + 8, chunk.length, true);
+}
+
+// copies the bytes to memory allocated by the module, which frees it
+function writeBytes(bytes) {
+  const ptr = instance.exports.cabi_realloc(0, 0, 1, bytes.length);
+  new Uint8Array(instance.exports.memory.buffer, ptr, bytes.length).set(bytes);
+  return ptr;
+}
+
+const httpMethods = [
+  "GET",
+  "HEAD",
+  "POST",
+  "PUT",
+  "DELETE",
+  "CONNECT",
+  "OPTIONS",
+  "TRACE",
+  "PATCH",
+];
+
+// The requests are sent with the fetch() of the host. A response is only
+// reported as ready once its whole body is buffered, so reading the body
+// never has to wait.
+async function sendRequest(request) {
+  const init = {
+    method: request.method,
+    headers: request.headers,
+    redirect: "manual",
+  };
+  if (request.body.length > 0) {
+    init.body = concatBytes(request.body);
+  }
+  const response = await fetch(
+    `${request.scheme}://${request.authority}${request.pathWithQuery}`,
+    init,
+  );
+  return {
+    status: response.status,
+    headers: Array.from(response.headers.entries()),
+    body: new Uint8Array(await response.arrayBuffer()),
+  };
+}
+
+function concatBytes(chunks) {
+  const length = chunks.reduce((sum, chunk) => sum + chunk.length, 0);
+  const bytes = new Uint8Array(length);
+  let offset = 0;
+  for (const chunk of chunks) {
+    bytes.set(chunk, offset);
+    offset += chunk.length;
+  }
+  return bytes;
+}
+
+// writes a list of strings or of tuples of strings, each string takes 8 bytes
+function writeStringList(items, returnPtr) {
+  const encoder = new TextEncoder();
+  const strings = items.flat();
+  const listPtr = instance.exports.cabi_realloc(0, 0, 4, strings.length * 8);
+  strings.forEach((string, i) => {
+    const bytes = encoder.encode(string);
+    const ptr = writeBytes(bytes);
+    const dataView = memoryView();
+    dataView.setUint32(listPtr + i * 8, ptr, true);
+    dataView.setUint32(listPtr + i * 8 + 4, bytes.length, true);
+  });
+  const dataView = memoryView();
+  dataView.setUint32(returnPtr, listPtr, true);
+  dataView.setUint32(returnPtr + 4, items.length, true);
+}
+
+// the wasi:filesystem error-code cases, in order
+const filesystemErrors = [
+  "EACCES",
+  "EAGAIN",
+  "EALREADY",
+  "EBADF",
+  "EBUSY",
+  "EDEADLK",
+  "EDQUOT",
+  "EEXIST",
+  "EFBIG",
+  "EILSEQ",
+  "EINPROGRESS",
+  "EINTR",
+  "EINVAL",
+  "EIO",
+  "EISDIR",
+  "ELOOP",
+  "EMLINK",
+  "EMSGSIZE",
+  "ENAMETOOLONG",
+  "ENODEV",
+  "ENOENT",
+  "ENOLCK",
+  "ENOMEM",
+  "ENOSPC",
+  "ENOTDIR",
+  "ENOTEMPTY",
+  "ENOTRECOVERABLE",
+  "ENOTSUP",
+  "ENOTTY",
+  "ENXIO",
+  "EOVERFLOW",
+  "EPERM",
+  "EPIPE",
+  "EROFS",
+  "ESPIPE",
+  "ETXTBSY",
+  "EXDEV",
+];
+
+function filesystemError(error) {
+  const code = filesystemErrors.indexOf(error.code);
+  return code === -1 ? filesystemErrors.indexOf("EIO") : code;
+}
+
+// calls an fs function, writing result<T, error-code> with the error code at
+// `errorOffset`
+function filesystemCall(returnPtr, errorOffset, call) {
+  try {
+    call();
+    memoryView().setUint8(returnPtr, 0);
+  } catch (error) {
+    if (error.code === undefined) {
+      throw error;
+    }
+    const dataView = memoryView();
+    dataView.setUint8(returnPtr, 1);
+    dataView.setUint8(returnPtr + errorOffset, filesystemError(error));
+  }
 }
 
 const importObject = {
   "wasi:io/poll@0.2.1": {
     poll: async function (ptr, length, returnPtr) {
-      // this is a simplification, ie. arrays do not necessarily take 4 bytes of space
-      // per element, but in here I know we deal with i32 values
-      const pollableIds = new Uint32Array(
-        instance.exports.memory.buffer,
-        ptr,
-        length,
+      // a list of pollable handles, each of them takes 4 bytes
+      const handles = Array.from(
+        new Uint32Array(instance.exports.memory.buffer, ptr, length),
       );
+      const polled = handles.map((handle) => pollables.get(handle));
 
-      const pollablesToWaitFor = [];
-      for (const id of pollableIds) {
-        pollablesToWaitFor.push(findPollable(id).getPromise());
-      }
-
-      let ready = await Promise.race(pollablesToWaitFor);
+      await Promise.race(polled.map((pollable) => pollable.getPromise()));
 
+      // the result is a list of indexes of the ready pollables, the memory
+      // for it has to be allocated by the module
+      const ready = [];
+      polled.forEach((pollable, index) => {
+        if (pollable.ready()) {
+          ready.push(index);
+        }
+      });
+      const readyPtr = instance.exports.cabi_realloc(0, 0, 4, ready.length * 4);
       const dataView = new DataView(instance.exports.memory.buffer);
-      dataView.setInt32(returnPtr, 1, true);
-      dataView.setInt32(returnPtr + 4, ready.getIndex(), true);
+      ready.forEach((index, i) => {
+        dataView.setUint32(readyPtr + i * 4, index, true);
+      });
+      dataView.setUint32(returnPtr, readyPtr, true);
+      dataView.setUint32(returnPtr + 4, ready.length, true);
 
       instance.exports["main_loop"]();
     },
+    "[resource-drop]pollable": function (handle) {
+      pollables.delete(handle);
+    },
+  },
+  "wasi:http/types@0.2.1": {
+    "[constructor]fields": function () {
+      return addResource({ entries: [] });
+    },
+    "[method]fields.append": function (
+      handle,
+      namePtr,
+      nameLength,
+      valuePtr,
+      valueLength,
+      returnPtr,
+    ) {
+      resources
+        .get(handle)
+        .entries.push([
+          readString(namePtr, nameLength),
+          readString(valuePtr, valueLength),
+        ]);
+      memoryView().setUint8(returnPtr, 0);
+    },
+    // a list of (name, value) pairs, each of them takes 16 bytes
+    "[method]fields.entries": function (handle, returnPtr) {
+      const encoder = new TextEncoder();
+      const entries = resources.get(handle).entries;
+      const listPtr = instance.exports.cabi_realloc(0, 0, 4, entries.length * 16);
+      entries.forEach(([name, value], i) => {
+        const nameBytes = encoder.encode(name);
+        const valueBytes = encoder.encode(value);
+        const namePtr = writeBytes(nameBytes);
+        const valuePtr = writeBytes(valueBytes);
+        const dataView = memoryView();
+        dataView.setUint32(listPtr + i * 16, namePtr, true);
+        dataView.setUint32(listPtr + i * 16 + 4, nameBytes.length, true);
+        dataView.setUint32(listPtr + i * 16 + 8, valuePtr, true);
+        dataView.setUint32(listPtr + i * 16 + 12, valueBytes.length, true);
+      });
+      const dataView = memoryView();
+      dataView.setUint32(returnPtr, listPtr, true);
+      dataView.setUint32(returnPtr + 4, entries.length, true);
+    },
+    "[resource-drop]fields": dropResource,
+    "[constructor]outgoing-request": function (fields) {
+      const headers = resources.get(fields).entries;
+      resources.delete(fields);
+      return addResource({
+        method: "GET",
+        scheme: "https",
+        authority: "",
+        pathWithQuery: "/",
+        headers,
+        body: [],
+      });
+    },
+    "[method]outgoing-request.set-method": function (
+      handle,
+      method,
+      ptr,
+      length,
+    ) {
+      resources.get(handle).method =
+        method < httpMethods.length ? httpMethods[method] : readString(ptr, length);
+      return 0;
+    },
+    "[method]outgoing-request.set-scheme": function (
+      handle,
+      isSome,
+      scheme,
+      ptr,
+      length,
+    ) {
+      resources.get(handle).scheme = ["http", "https"][scheme] ?? readString(ptr, length);
+      return 0;
+    },
+    "[method]outgoing-request.set-authority": function (
+      handle,
+      isSome,
+      ptr,
+      length,
+    ) {
+      resources.get(handle).authority = readString(ptr, length);
+      return 0;
+    },
+    "[method]outgoing-request.set-path-with-query": function (
+      handle,
+      isSome,
+      ptr,
+      length,
+    ) {
+      resources.get(handle).pathWithQuery = readString(ptr, length);
+      return 0;
+    },
+    "[method]outgoing-request.body": function (handle, returnPtr) {
+      const body = addResource({ chunks: resources.get(handle).body });
+      const dataView = memoryView();
+      dataView.setUint8(returnPtr, 0);
+      dataView.setUint32(returnPtr + 4, body, true);
+    },
+    "[resource-drop]outgoing-request": dropResource,
+    "[method]outgoing-body.write": function (handle, returnPtr) {
+      const stream = addResource({ chunks: resources.get(handle).chunks });
+      const dataView = memoryView();
+      dataView.setUint8(returnPtr, 0);
+      dataView.setUint32(returnPtr + 4, stream, true);
+    },
+    "[static]outgoing-body.finish": function (
+      handle,
+      hasTrailers,
+      trailers,
+      returnPtr,
+    ) {
+      resources.delete(handle);
+      memoryView().setUint8(returnPtr, 0);
+    },
+    "[resource-drop]outgoing-body": dropResource,
+    "[method]future-incoming-response.subscribe": function (handle) {
+      return addPollable(resources.get(handle).promise);
+    },
+    // option<result<result<incoming-response, error-code>>>, the error code
+    // is always reported as internal-error
+    "[method]future-incoming-response.get": function (handle, returnPtr) {
+      const future = resources.get(handle);
+      const dataView = memoryView();
+      if (!future.done) {
+        dataView.setUint8(returnPtr, 0);
+        return;
+      }
+      dataView.setUint8(returnPtr, 1);
+      dataView.setUint8(returnPtr + 8, 0);
+      if (future.error !== undefined) {
+        dataView.setUint8(returnPtr + 16, 1);
+        dataView.setUint8(returnPtr + 24, 38);
+        dataView.setUint8(returnPtr + 32, 0);
+      } else {
+        dataView.setUint8(returnPtr + 16, 0);
+        dataView.setUint32(returnPtr + 24, addResource(future.response), true);
+      }
+    },
+    "[resource-drop]future-incoming-response": dropResource,
+    "[method]incoming-response.status": function (handle) {
+      return resources.get(handle).status;
+    },
+    "[method]incoming-response.headers": function (handle) {
+      return addResource({ entries: resources.get(handle).headers });
+    },
+    "[method]incoming-response.consume": function (handle, returnPtr) {
+      const body = addResource({ bytes: resources.get(handle).body });
+      const dataView = memoryView();
+      dataView.setUint8(returnPtr, 0);
+      dataView.setUint32(returnPtr + 4, body, true);
+    },
+    "[resource-drop]incoming-response": dropResource,
+    "[method]incoming-body.stream": function (handle, returnPtr) {
+      const stream = addResource({ bytes: resources.get(handle).bytes, offset: 0 });
+      const dataView = memoryView();
+      dataView.setUint8(returnPtr, 0);
+      dataView.setUint32(returnPtr + 4, stream, true);
+    },
+    "[resource-drop]incoming-body": dropResource,
+  },
+  "wasi:http/outgoing-handler@0.2.1": {
+    // the request is sent once the module is done with the current task,
+    // which is after it finished writing the body
+    handle: function (handle, hasOptions, options, returnPtr) {
+      const request = resources.get(handle);
+      resources.delete(handle);
+      const future = { done: false };
+      future.promise = Promise.resolve()
+        .then(() => sendRequest(request))
+        .then(
+          (response) => {
+            future.response = response;
+          },
+          (error) => {
+            future.error = error;
+          },
+        )
+        .finally(() => {
+          future.done = true;
+        });
+      const dataView = memoryView();
+      dataView.setUint8(returnPtr, 0);
+      dataView.setUint32(returnPtr + 8, addResource(future), true);
+    },
+  },
+  "wasi:io/streams@0.2.1": {
+    "[method]output-stream.blocking-write-and-flush": function (
+      handle,
+      ptr,
+      length,
+      returnPtr,
+    ) {
+      resources.get(handle).chunks.push(readBytes(ptr, length));
+      memoryView().setUint8(returnPtr, 0);
+    },
+    "[resource-drop]output-stream": dropResource,
+    // result<list<u8>, stream-error>, with the stream closed once the whole
+    // body is read
+    "[method]input-stream.read": function (handle, length, returnPtr) {
+      const stream = resources.get(handle);
+      if (stream.stdin) {
+        readStdin(stream, Number(length), returnPtr);
+        return;
+      }
+      if (stream.offset >= stream.bytes.length) {
+        const dataView = memoryView();
+        dataView.setUint8(returnPtr, 1);
+        dataView.setUint8(returnPtr + 4, 1);
+        return;
+      }
+      const chunk = stream.bytes.subarray(
+        stream.offset,
+        stream.offset + Number(length),
+      );
+      stream.offset += chunk.length;
+      const ptr = writeBytes(chunk);
+      const dataView = memoryView();
+      dataView.setUint8(returnPtr, 0);
+      dataView.setUint32(returnPtr + 4, ptr, true);
+      dataView.setUint32(returnPtr + 8, chunk.length, true);
+    },
+    "[method]input-stream.subscribe": function (handle) {
+      const stream = resources.get(handle);
+      if (!stream.stdin || stream.chunks.length || stream.ended) {
+        return addPollable(Promise.resolve());
+      }
+      process.stdin.resume();
+      return addPollable(new Promise((resolve) => (stream.wake = resolve)));
+    },
+    "[resource-drop]input-stream": dropResource,
+  },
+  "wasi:io/error@0.2.1": {
+    "[resource-drop]error": dropResource,
+  },
+  // Web Crypto's getRandomValues fills at most 64KiB at a time
+  "wasi:random/random@0.2.1": {
+    "get-random-u64": function () {
+      return crypto.getRandomValues(new BigUint64Array(1))[0];
+    },
+    "get-random-bytes": function (length, returnPtr) {
+      const bytes = new Uint8Array(Number(length));
+      for (let offset = 0; offset < bytes.length; offset += 65536) {
+        crypto.getRandomValues(bytes.subarray(offset, offset + 65536));
+      }
+      const ptr = writeBytes(bytes);
+      const dataView = memoryView();
+      dataView.setUint32(returnPtr, ptr, true);
+      dataView.setUint32(returnPtr + 4, bytes.length, true);
+    },
+  },
+  "wasi:cli/stdin@0.2.1": {
+    "get-stdin": function () {
+      return addResource(openStdin());
+    },
+  },
+  "wasi:cli/environment@0.2.1": {
+    // the arguments after the path of the module
+    "get-arguments": function (returnPtr) {
+      const args = typeof process !== "undefined" ? process.argv.slice(2) : [];
+      writeStringList(args, returnPtr);
+    },
+    "get-environment": function (returnPtr) {
+      const env = typeof process !== "undefined" ? Object.entries(process.env) : [];
+      writeStringList(env, returnPtr);
+    },
+  },
+  // the current directory is preopened as "." and the root as "/"
+  "wasi:filesystem/preopens@0.2.1": {
+    "get-directories": function (returnPtr) {
+      const directories = [
+        [process.cwd(), "."],
+        ["/", "/"],
+      ];
+      const encoder = new TextEncoder();
+      const listPtr = instance.exports.cabi_realloc(
+        0,
+        0,
+        4,
+        directories.length * 12,
+      );
+      directories.forEach(([path, name], i) => {
+        const bytes = encoder.encode(name);
+        const namePtr = writeBytes(bytes);
+        const dataView = memoryView();
+        dataView.setUint32(listPtr + i * 12, addResource({ path }), true);
+        dataView.setUint32(listPtr + i * 12 + 4, namePtr, true);
+        dataView.setUint32(listPtr + i * 12 + 8, bytes.length, true);
+      });
+      const dataView = memoryView();
+      dataView.setUint32(returnPtr, listPtr, true);
+      dataView.setUint32(returnPtr + 4, directories.length, true);
+    },
+  },
+  "wasi:filesystem/types@0.2.1": {
+    "[method]descriptor.open-at": function (
+      handle,
+      pathFlags,
+      ptr,
+      length,
+      openFlags,
+      flags,
+      returnPtr,
+    ) {
+      const fs = require("node:fs");
+      const path = require("node:path").join(
+        resources.get(handle).path,
+        readString(ptr, length),
+      );
+      filesystemCall(returnPtr, 4, () => {
+        const { constants } = fs;
+        let mode =
+          flags & 2
+            ? flags & 1
+              ? constants.O_RDWR
+              : constants.O_WRONLY
+            : constants.O_RDONLY;
+        if (openFlags & 1) mode |= constants.O_CREAT;
+        if (openFlags & 4) mode |= constants.O_EXCL;
+        if (openFlags & 8) mode |= constants.O_TRUNC;
+        const fd = fs.openSync(path, mode);
+        memoryView().setUint32(returnPtr + 4, addResource({ fd }), true);
+      });
+    },
+    // result<tuple<list<u8>, bool>, error-code>, the bool marks the end of
+    // the file
+    "[method]descriptor.read": function (handle, length, offset, returnPtr) {
+      const fs = require("node:fs");
+      filesystemCall(returnPtr, 4, () => {
+        const buffer = new Uint8Array(Number(length));
+        const read = fs.readSync(
+          resources.get(handle).fd,
+          buffer,
+          0,
+          buffer.length,
+          Number(offset),
+        );
+        const ptr = writeBytes(buffer.subarray(0, read));
+        const dataView = memoryView();
+        dataView.setUint32(returnPtr + 4, ptr, true);
+        dataView.setUint32(returnPtr + 8, read, true);
+        dataView.setUint8(returnPtr + 12, read === 0 ? 1 : 0);
+      });
+    },
+    "[method]descriptor.write": function (
+      handle,
+      ptr,
+      length,
+      offset,
+      returnPtr,
+    ) {
+      const fs = require("node:fs");
+      filesystemCall(returnPtr, 8, () => {
+        const written = fs.writeSync(
+          resources.get(handle).fd,
+          readBytes(ptr, length),
+          0,
+          length,
+          Number(offset),
+        );
+        memoryView().setBigUint64(returnPtr + 8, BigInt(written), true);
+      });
+    },
+    // only the size of the descriptor-stat record is filled in
+    "[method]descriptor.stat": function (handle, returnPtr) {
+      const fs = require("node:fs");
+      filesystemCall(returnPtr, 8, () => {
+        const stats = fs.fstatSync(resources.get(handle).fd);
+        memoryView().setBigUint64(returnPtr + 24, BigInt(stats.size), true);
+      });
+    },
+    "[method]descriptor.stat-at": function (
+      handle,
+      pathFlags,
+      ptr,
+      length,
+      returnPtr,
+    ) {
+      const fs = require("node:fs");
+      const path = require("node:path").join(
+        resources.get(handle).path,
+        readString(ptr, length),
+      );
+      filesystemCall(returnPtr, 8, () => {
+        const stats = fs.statSync(path);
+        memoryView().setBigUint64(returnPtr + 24, BigInt(stats.size), true);
+      });
+    },
+    "[resource-drop]descriptor": function (handle) {
+      const descriptor = resources.get(handle);
+      if (descriptor?.fd !== undefined) {
+        require("node:fs").closeSync(descriptor.fd);
+      }
+      resources.delete(handle);
+    },
   },
   "wasi:clocks/monotonic-clock@0.2.1": {
     "subscribe-duration": function (durationNanos) {
-      let index = pollableIndex;
-      let pollable = new Pollable(
-        sleep(durationNanos / BigInt(1000000)),
-        index,
+      return addPollable(sleep(durationNanos / BigInt(1000000)));
+    },
+    now: function () {
+      return BigInt(Math.round(performance.now() * 1000000));
+    },
+  },
+  "wasi:clocks/wall-clock@0.2.1": {
+    // the datetime record is returned through memory: u64 seconds followed
+    // by u32 nanoseconds
+    now: function (returnPtr) {
+      const milliseconds = Date.now();
+      const dataView = new DataView(instance.exports.memory.buffer);
+      dataView.setBigUint64(
+        returnPtr,
+        BigInt(Math.floor(milliseconds / 1000)),
+        true,
       );
-      pollables.push(pollable);
-      pollableIndex++;
-      return index;
+      dataView.setUint32(returnPtr + 8, (milliseconds % 1000) * 1000000, true);
     },
   },
   console: { log: (value) => console.log(`WebAssembly log: ${value}`) },
@@ -98,8 +713,8 @@ const importObject = {
         iovsPtr,
         iovsLength * 2,
       );
-      if (fd === 1) {
-        //stdout
+      if (fd === 1 || fd === 2) {
+        // stdout or stderr
         let text = "";
         let totalBytesWritten = 0;
         const decoder = new TextDecoder();
@@ -115,9 +730,11 @@ const importObject = {
         const dataView = new DataView(instance.exports.memory.buffer);
         dataView.setInt32(bytesWrittenPtr, totalBytesWritten, true);
         if (typeof process !== "undefined") {
-          process.stdout.write(text);
-        } else {
+          (fd === 1 ? process.stdout : process.stderr).write(text);
+        } else if (fd === 1) {
           console.log(text);
+        } else {
+          console.error(text);
         }
       }
       return 0;
@@ -141,7 +758,8 @@ const importObject = {
 
   let result = exports["wasi:cli/run@0.2.1#run"]();
 
-  if (typeof process !== undefined) {
-    process.exit(result);
+  // pending pollables keep Node running until the module is done with them
+  if (typeof process !== "undefined") {
+    process.exitCode = result;
   }
 })();
@@ -1,23 +1,22 @@
-use anyhow::{anyhow, Context, Result};
+use anyhow::anyhow;
 use boa_ast::{
     declaration::{Declaration, LexicalDeclaration, VarDeclaration, VariableList},
     expression::{
-        self,
         access::PropertyAccess,
         literal::{ArrayLiteral, Literal, ObjectLiteral},
         operator::{
-            binary::{ArithmeticOp, BinaryOp, LogicalOp},
+            binary::{ArithmeticOp, BinaryOp, BitwiseOp, LogicalOp},
             update::UpdateTarget,
             Assign, Binary, Unary, Update,
         },
-        Await, Call, Expression, Identifier, New, Parenthesized,
+        Await, Call, Expression, Identifier, New, Parenthesized, RegExpLiteral,
     },
     function::{
         ArrowFunction, AsyncFunction, FormalParameter, FormalParameterList, Function, FunctionBody,
     },
     statement::{
-        Block, Case, Catch, DoWhileLoop, ErrorHandler, Finally, ForInLoop, ForLoop, ForOfLoop, If,
-        Return, Statement, Switch, Throw, Try, WhileLoop, With,
+        iteration::ForLoopInitializer, Block, Case, Catch, DoWhileLoop, ErrorHandler, Finally,
+        ForInLoop, ForLoop, ForOfLoop, If, Return, Statement, Switch, Throw, Try, WhileLoop, With,
     },
     visitor::{VisitWith, Visitor},
     Script, StatementList, StatementListItem,
@@ -29,14 +28,24 @@ use std::{
     collections::HashMap,
     fs::File,
     io::{self, Read, Write},
-    ops::{ControlFlow, Deref},
+    ops::ControlFlow,
     path::Path,
 };
 
+mod regexp;
+mod scopes;
+mod type_inference;
 mod wat_ast;
 mod wat_template;
+use scopes::{scope_id, FunctionScopes, ScopeId};
+use type_inference::{function_id, FunctionId, TypeInfo, ValueType};
 use wat_ast::{WatFunction, WatInstruction as W, WatModule};
 
+// Strings are stored in the data section starting at this offset. Each of them is
+// prefixed with a 4 byte length, see `insert_data_string`
+pub const STATIC_STRINGS_START: i32 = 300;
+
+#[derive(Clone, Copy)]
 enum VarType {
     Const,
     Let,
@@ -45,7 +54,7 @@ enum VarType {
 }
 
 impl VarType {
-    fn to_i32(&self) -> i32 {
+    fn to_i32(self) -> i32 {
         match self {
             VarType::Const => 0,
             VarType::Let => 1,
@@ -55,6 +64,29 @@ impl VarType {
     }
 }
 
+// integers that fit in 31 bits are represented as unboxed i31ref, so they
+// don't need to go through $new_number
+fn number_literal(value: f64) -> Box<W> {
+    let fits_i31 = (-(1 << 30) as f64..(1 << 30) as f64).contains(&value);
+    if fits_i31 && value.fract() == 0.0 && !(value == 0.0 && value.is_sign_negative()) {
+        W::ref_i31(W::i32_const(value as i32))
+    } else {
+        W::call("$new_number", vec![W::f64_const(value)])
+    }
+}
+
+// a regular call, `this` is left for the runtime to fill in
+fn call_function(function: &str, arguments: &str) -> Box<W> {
+    W::call(
+        "$call_function",
+        vec![
+            W::local_get(function),
+            W::ref_null("any"),
+            W::local_get(arguments),
+        ],
+    )
+}
+
 fn gen_function_name(s: Option<String>) -> String {
     let r: String = rand::thread_rng()
         .sample_iter(&Alphanumeric)
@@ -69,34 +101,77 @@ fn gen_function_name(s: Option<String>) -> String {
     }
 }
 
+// unboxed variables of a function with their types and WASM local names
+type UnboxedLocals = HashMap<Sym, (ValueType, String)>;
+
+#[derive(Clone)]
+enum Storage {
+    // a variable that is not captured by any closure
+    Local(String),
+    // an index in the `$slots` array of the `$Scope` of one of the function's
+    // scopes, given by its position in `Frame::blocks`
+    Slot(usize, i32),
+    // looked up by name, in functions using `eval` or `with`
+    Dynamic,
+    // a property of the global object, for `var`s and functions declared at
+    // the top level of the script
+    Global,
+}
+
+#[derive(Clone)]
+struct Binding {
+    storage: Storage,
+    constant: bool,
+}
+
+// a function being translated along with the bindings of its active scopes
+struct Frame {
+    scopes: FunctionScopes,
+    // functions without captured variables don't create their own `$Scope`
+    has_environment: bool,
+    blocks: Vec<Scope>,
+}
+
+// the bindings of an active scope of a function
+struct Scope {
+    bindings: HashMap<Sym, Binding>,
+    // a local holding the `$Scope` with the captured bindings of a block, which
+    // is created each time the block is entered. The function body uses the
+    // function's `$scope` instead
+    environment: Option<String>,
+}
+
 struct WasmTranslator {
     module: WatModule,
     function_stack: Vec<WatFunction>,
     interner: Interner,
-    functions: HashMap<String, String>,
-    init_code: Vec<String>,
     data_entries: HashMap<i32, String>,
     string_offsets: HashMap<String, i32>,
     data_offset: i32,
     identifiers_map: HashMap<i32, i32>,
     current_block_number: u32,
+    types: TypeInfo,
+    // variables kept in unboxed locals, for each function in function_stack
+    unboxed_stack: Vec<(FunctionId, UnboxedLocals)>,
+    frames: Vec<Frame>,
 }
 
 impl WasmTranslator {
-    fn new(interner: Interner) -> Self {
+    fn new(interner: Interner, types: TypeInfo) -> Self {
         let module = WatModule::new();
         let function = WatFunction::new("init".to_string());
         Self {
             module,
             function_stack: vec![function],
             interner,
-            functions: HashMap::new(),
-            init_code: Vec::new(),
             data_entries: HashMap::new(),
             string_offsets: HashMap::new(),
-            data_offset: 300,
+            data_offset: STATIC_STRINGS_START,
             identifiers_map: HashMap::new(),
             current_block_number: 0,
+            types,
+            unboxed_stack: Vec::new(),
+            frames: Vec::new(),
         }
     }
 
@@ -137,6 +212,386 @@ impl WasmTranslator {
         self.module.add_function(function);
     }
 
+    // adds locals for the variables of the function that type inference
+    // found to be always numbers or booleans
+    fn enter_unboxed_scope(&mut self, body: &FunctionBody) {
+        let id = function_id(body);
+        let mut locals = HashMap::new();
+        for (sym, value_type) in self.types.unboxed_variables(id) {
+            let name = self.local_name(sym);
+            let local = match value_type {
+                ValueType::Number => self
+                    .current_function()
+                    .add_local(format!("$num-{name}"), "f64"),
+                _ => self
+                    .current_function()
+                    .add_local(format!("$bool-{name}"), "i32"),
+            };
+            locals.insert(sym, (value_type, local));
+        }
+        self.unboxed_stack.push((id, locals));
+    }
+
+    fn exit_unboxed_scope(&mut self) {
+        self.unboxed_stack.pop();
+    }
+
+    fn unboxed_local(&self, sym: Sym) -> Option<String> {
+        self.unboxed_stack
+            .last()
+            .and_then(|(_, locals)| locals.get(&sym))
+            .map(|(_, local)| local.clone())
+    }
+
+    fn expression_type(&self, expression: &Expression) -> ValueType {
+        match self.unboxed_stack.last() {
+            Some((id, _)) => self.types.expression_type(*id, expression),
+            None => ValueType::Any,
+        }
+    }
+
+    // starts resolving variables of a function, returns the instruction
+    // setting its `$scope` from the parent one
+    fn enter_frame(&mut self, scopes: FunctionScopes, parent: Box<W>) -> Box<W> {
+        let captured = scopes.captured_count(scopes.root());
+        let scope = if scopes.is_dynamic() {
+            W::call("$new_scope", vec![parent])
+        } else if captured > 0 {
+            W::call(
+                "$new_environment",
+                vec![parent, W::i32_const(captured as i32)],
+            )
+        } else {
+            parent
+        };
+        self.frames.push(Frame {
+            has_environment: scopes.is_dynamic() || captured > 0,
+            scopes,
+            blocks: Vec::new(),
+        });
+        W::list(vec![scope, W::local_set("$scope")])
+    }
+
+    fn exit_frame(&mut self) {
+        self.frames.pop();
+    }
+
+    // Adds the bindings declared in a scope of the current function. Returns
+    // the instruction creating the `$Scope` for captured bindings of a block,
+    // it has to run each time the block is entered
+    fn enter_scope(&mut self, id: ScopeId) -> Box<W> {
+        let frame = self.frames.last().unwrap();
+        let declarations = frame.scopes.declarations(id).to_vec();
+        let captured = frame.scopes.captured_count(id);
+        let index = frame.blocks.len();
+        let (environment, create_environment) =
+            if id != frame.scopes.root() && !frame.scopes.is_dynamic() && captured > 0 {
+                let parent = self.current_environment();
+                let local = self
+                    .current_function()
+                    .add_local("$environment", "(ref $Scope)");
+                let create = W::list(vec![
+                    W::call(
+                        "$new_environment",
+                        vec![parent, W::i32_const(captured as i32)],
+                    ),
+                    W::local_set(&local),
+                ]);
+                (Some(local), create)
+            } else {
+                (None, W::empty())
+            };
+        let mut next_slot = 0;
+        let mut bindings = HashMap::new();
+        for (sym, constant) in declarations {
+            // unboxed variables are resolved separately, see `unboxed_local`
+            if self.unboxed_local(sym).is_some() {
+                continue;
+            }
+            let frame = self.frames.last_mut().unwrap();
+            let storage = if frame.scopes.is_global(id, sym) {
+                Storage::Global
+            } else if frame.scopes.is_dynamic() {
+                Storage::Dynamic
+            } else if frame.scopes.is_captured(id, sym) {
+                next_slot += 1;
+                Storage::Slot(index, next_slot - 1)
+            } else {
+                let name = self.local_name(sym);
+                Storage::Local(
+                    self.current_function()
+                        .add_local(format!("$var-{name}"), "anyref"),
+                )
+            };
+            bindings.insert(sym, Binding { storage, constant });
+        }
+        self.frames.last_mut().unwrap().blocks.push(Scope {
+            bindings,
+            environment,
+        });
+        create_environment
+    }
+
+    fn exit_scope(&mut self) {
+        self.frames.last_mut().unwrap().blocks.pop();
+    }
+
+    // `var`s and functions declared at the top level of a script are created
+    // on the global object before any code runs
+    fn declare_globals(&mut self, id: ScopeId) -> Box<W> {
+        let frame = self.frames.last().unwrap();
+        let globals: Vec<Sym> = frame
+            .scopes
+            .declarations(id)
+            .iter()
+            .map(|(sym, _)| *sym)
+            .filter(|sym| frame.scopes.is_global(id, *sym))
+            .collect();
+        let declarations = globals
+            .into_iter()
+            .map(|sym| W::call("$declare_global", vec![W::i32_const(self.add_symbol(sym))]))
+            .collect();
+        W::list(declarations)
+    }
+
+    // a part of a WASM local name for a variable
+    fn local_name(&self, sym: Sym) -> String {
+        let name = self.interner.resolve_expect(sym).to_string();
+        if name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            name
+        } else {
+            sym.get().to_string()
+        }
+    }
+
+    // finds the innermost binding of a name, returns None for globals
+    fn resolve(&self, sym: Sym) -> Option<(usize, Binding)> {
+        self.frames
+            .iter()
+            .enumerate()
+            .rev()
+            .find_map(|(index, frame)| {
+                frame
+                    .blocks
+                    .iter()
+                    .rev()
+                    .find_map(|scope| scope.bindings.get(&sym))
+                    .map(|binding| (index, binding.clone()))
+            })
+    }
+
+    // the innermost `$Scope` of the current function, which closures created
+    // at this point use as their parent
+    fn current_environment(&self) -> Box<W> {
+        let local = self.frames.last().and_then(|frame| {
+            frame
+                .blocks
+                .iter()
+                .rev()
+                .find_map(|scope| scope.environment.clone())
+        });
+        W::local_get(local.as_deref().unwrap_or("$scope"))
+    }
+
+    // the `$slots` array of the `$Scope` of a scope of a function, found by
+    // walking the parents of the current `$scope`
+    fn slots(&self, frame: usize, block: usize) -> Box<W> {
+        let current = self.frames.len() - 1;
+        let environments = |frame: &Frame| -> usize {
+            frame
+                .blocks
+                .iter()
+                .filter(|scope| scope.environment.is_some())
+                .count()
+        };
+        let mut scope = match &self.frames[frame].blocks[block].environment {
+            Some(local) if frame == current => W::local_get(local),
+            _ => W::local_get("$scope"),
+        };
+        let depth = if frame == current {
+            0
+        } else {
+            usize::from(self.frames[current].has_environment)
+                + self.frames[frame + 1..current]
+                    .iter()
+                    .map(|frame| environments(frame) + usize::from(frame.has_environment))
+                    .sum::<usize>()
+                + self.frames[frame].blocks[block + 1..]
+                    .iter()
+                    .filter(|scope| scope.environment.is_some())
+                    .count()
+        };
+        for _ in 0..depth {
+            scope = W::instruction(
+                "struct.get",
+                vec![W::r#type("$Scope"), W::r#type("$parent"), scope],
+            );
+        }
+        W::instruction(
+            "struct.get",
+            vec![W::r#type("$Scope"), W::r#type("$slots"), scope],
+        )
+    }
+
+    fn get_variable(&mut self, identifier: &Identifier) -> Box<W> {
+        match self.resolve(identifier.sym()) {
+            Some((
+                _,
+                Binding {
+                    storage: Storage::Local(local),
+                    ..
+                },
+            )) => W::local_get(local),
+            Some((
+                frame,
+                Binding {
+                    storage: Storage::Slot(block, index),
+                    ..
+                },
+            )) => W::instruction(
+                "array.get",
+                vec![
+                    W::r#type("$AnyrefArray"),
+                    self.slots(frame, block),
+                    W::i32_const(index),
+                ],
+            ),
+            Some((
+                _,
+                Binding {
+                    storage: Storage::Dynamic | Storage::Global,
+                    ..
+                },
+            ))
+            | None => {
+                let offset = self.add_identifier(identifier);
+                W::call(
+                    "$get_variable",
+                    vec![W::local_get("$scope"), W::i32_const(offset)],
+                )
+            }
+        }
+    }
+
+    // Sets a variable to the value left on the stack by `value`. Declarations
+    // can initialize constants and in dynamic scopes they're passed on to
+    // $declare_variable, which checks for redeclarations
+    fn set_variable(
+        &mut self,
+        identifier: &Identifier,
+        value: Box<W>,
+        declaration: Option<VarType>,
+    ) -> Box<W> {
+        let binding = self.resolve(identifier.sym());
+        if let Some((
+            _,
+            Binding {
+                constant: true,
+                storage,
+            },
+        )) = &binding
+        {
+            if declaration.is_none() && !matches!(storage, Storage::Dynamic) {
+                let (offset, length) = self.insert_data_string("Assignment to constant variable.");
+                return W::list(vec![
+                    value,
+                    W::drop(),
+                    W::call(
+                        "$throw_type_error",
+                        vec![W::call(
+                            "$new_static_string",
+                            vec![W::i32_const(offset), W::i32_const(length)],
+                        )],
+                    ),
+                    // the value of an assignment is never left on the stack
+                    W::instruction("unreachable", vec![]),
+                ]);
+            }
+        }
+
+        match binding {
+            Some((
+                _,
+                Binding {
+                    storage: Storage::Local(local),
+                    ..
+                },
+            )) => W::list(vec![value, W::local_set(local)]),
+            // global declarations are hoisted, see `declare_globals`
+            Some((
+                _,
+                Binding {
+                    storage: Storage::Global,
+                    ..
+                },
+            )) => {
+                let offset = self.add_identifier(identifier);
+                let temp = self.current_function().add_local("$temp", "anyref");
+                W::list(vec![
+                    value,
+                    W::local_set(&temp),
+                    W::call(
+                        "$set_global",
+                        vec![W::i32_const(offset), W::local_get(&temp)],
+                    ),
+                ])
+            }
+            Some((
+                frame,
+                Binding {
+                    storage: Storage::Slot(block, index),
+                    ..
+                },
+            )) => {
+                let temp = self.current_function().add_local("$temp", "anyref");
+                W::list(vec![
+                    value,
+                    W::local_set(&temp),
+                    W::instruction(
+                        "array.set",
+                        vec![
+                            W::r#type("$AnyrefArray"),
+                            self.slots(frame, block),
+                            W::i32_const(index),
+                            W::local_get(&temp),
+                        ],
+                    ),
+                ])
+            }
+            Some((
+                _,
+                Binding {
+                    storage: Storage::Dynamic,
+                    ..
+                },
+            ))
+            | None => {
+                let offset = self.add_identifier(identifier);
+                let temp = self.current_function().add_local("$temp", "anyref");
+                let call = match declaration {
+                    Some(var_type) => W::call(
+                        "$declare_variable",
+                        vec![
+                            W::local_get("$scope"),
+                            W::i32_const(offset),
+                            W::local_get(&temp),
+                            W::i32_const(var_type.to_i32()),
+                        ],
+                    ),
+                    None => W::call(
+                        "$assign_variable",
+                        vec![
+                            W::local_get("$scope"),
+                            W::i32_const(offset),
+                            W::local_get(&temp),
+                        ],
+                    ),
+                };
+                W::list(vec![value, W::local_set(&temp), call])
+            }
+        }
+    }
+
     fn enter_block(&mut self) {
         self.current_block_number += 1;
     }
@@ -181,32 +636,40 @@ impl WasmTranslator {
 
         self.current_function()
             .add_local_exact("$scope", "(ref $Scope)");
-        self.current_function()
-            .add_instruction(W::call("$new_scope", vec![W::local_get("$parentScope")]));
-        self.current_function()
-            .add_instruction(W::local_set("$scope"));
-
-        // set parameters on the scope
+        let scopes = FunctionScopes::analyze(Some(params), body);
+        let set_scope = self.enter_frame(scopes, W::local_get("$parentScope"));
+        self.current_function().add_instruction(set_scope);
+        self.enter_unboxed_scope(body);
+        let environment = self.enter_scope(scope_id(body));
+        self.current_function().add_instruction(environment);
+
+        // set parameters
         for (i, param) in params.as_ref().iter().enumerate() {
             match param.variable().binding() {
                 boa_ast::declaration::Binding::Identifier(identifier) => {
-                    let offset = self.add_identifier(identifier);
-                    self.current_function().add_instruction(W::call(
-                        "$declare_variable",
+                    let argument = W::instruction(
+                        "array.get",
                         vec![
-                            W::local_get("$scope"),
-                            W::i32_const(offset),
-                            W::instruction(
-                                "array.get",
-                                vec![
-                                    W::r#type("$JSArgs"),
-                                    W::local_get("$arguments"),
-                                    W::i32_const(i as i32),
-                                ],
-                            ),
-                            W::i32_const(VarType::Param.to_i32()),
+                            W::r#type("$JSArgs"),
+                            W::local_get("$arguments"),
+                            W::i32_const(i as i32),
                         ],
-                    ));
+                    );
+                    if let Some(local) = self.unboxed_local(identifier.sym()) {
+                        // all of the callers are known to pass a value of this type
+                        let unbox = match self
+                            .types
+                            .variable_type(function_id(body), identifier.sym())
+                        {
+                            ValueType::Number => W::call("$number_value", vec![argument]),
+                            _ => W::call("$cast_ref_to_i32_bool", vec![argument]),
+                        };
+                        self.current_function()
+                            .add_instruction(W::list(vec![unbox, W::local_set(local)]));
+                        continue;
+                    }
+                    let set_param = self.set_variable(identifier, argument, Some(VarType::Param));
+                    self.current_function().add_instruction(set_param);
                 }
                 boa_ast::declaration::Binding::Pattern(_pattern) => todo!(),
             }
@@ -231,12 +694,15 @@ impl WasmTranslator {
         self.current_function()
             .add_instruction(W::list(vec![W::ref_null("any"), W::r#return()]));
 
+        self.exit_scope();
+        self.exit_unboxed_scope();
+        self.exit_frame();
         self.exit_function();
 
         W::call(
             "$new_function".to_string(),
             vec![
-                W::local_get("$scope"),
+                self.current_environment(),
                 W::ref_func(function_name),
                 W::ref_null("any"),
             ],
@@ -270,93 +736,61 @@ impl WasmTranslator {
         self.translate_let_vars(&decl.0, VarType::Var)
     }
 
-    fn translate_call(&mut self, call: &Call, get_this: Box<W>, will_use_return: bool) -> Box<W> {
+    // `emit_call` gets the names of the locals holding the called function and
+    // the arguments array, and returns the instruction that makes the call
+    fn translate_call(
+        &mut self,
+        call: &Call,
+        emit_call: impl FnOnce(&str, &str) -> Box<W>,
+        will_use_return: bool,
+    ) -> Box<W> {
         // println!(
         //     "translate_call {}",
         //     call.function().to_interned_string(&self.interner)
         // );
-        let function_name = call.function().to_interned_string(&self.interner);
         let mut instructions = Vec::new();
 
-        if function_name == "setTimeout" {
-            if let Some(callback) = call.args().get(0) {
-                let callback_var = self.current_function().add_local("$callback", "anyref");
-                let duration_var = self.current_function().add_local("$duration", "anyref");
-                instructions.push(self.translate_expression(callback, true));
-                instructions.push(W::local_set(&callback_var));
-
-                let time = if let Some(time) = call.args().get(1) {
-                    self.translate_expression(time, true)
-                } else {
-                    // pass undefined
-                    W::ref_null("any")
-                };
-                instructions.push(time);
-                instructions.push(W::local_set(&duration_var));
-
-                // the rest of arguments doesn't matter
-                instructions.push(W::call(
-                    "$set-timeout",
-                    vec![W::local_get(&callback_var), W::local_get(&duration_var)],
-                ));
-            } else {
-                // TODO: throw TypeError
-            }
-        } else {
-            // Add a local for arguments to the current function
-            let call_arguments = self
-                .current_function()
-                .add_local("$call_arguments", "(ref $JSArgs)");
-            let temp_arg = self.current_function().add_local("$temp_arg", "anyref");
-
-            // Create the arguments array
-            let args_count = call.args().len() as i32;
-            instructions.push(W::array_new(
-                "$JSArgs",
-                W::ref_null("any"),
-                W::i32_const(args_count),
-            ));
-            instructions.push(W::local_set(&call_arguments));
-
-            // Populate the arguments array
-            for (index, arg) in call.args().iter().enumerate() {
-                let arg_instruction = self.translate_expression(arg, true);
-                instructions.push(W::list(vec![
-                    arg_instruction,
-                    W::local_set(&temp_arg),
-                    W::instruction(
-                        "array.set",
-                        vec![
-                            W::r#type("$JSArgs"),
-                            W::local_get(&call_arguments),
-                            W::i32_const(index as i32),
-                            W::local_get(&temp_arg),
-                        ],
-                    ),
-                ]));
-            }
-
-            if function_name == "console.log" {
-                instructions.push(W::call("$log", vec![W::local_get(&call_arguments)]));
-                instructions.push(W::i32_const(1));
-            } else {
-                // Translate the function expression
-                let function_local = self.current_function().add_local("$function", "anyref");
-                instructions.push(self.translate_expression(call.function(), true));
-                instructions.push(W::local_set(&function_local));
-
-                // Call the function
-                instructions.push(W::call(
-                    "$call_function",
+        // Add a local for arguments to the current function
+        let call_arguments = self
+            .current_function()
+            .add_local("$call_arguments", "(ref $JSArgs)");
+        let temp_arg = self.current_function().add_local("$temp_arg", "anyref");
+
+        // Create the arguments array
+        let args_count = call.args().len() as i32;
+        instructions.push(W::array_new(
+            "$JSArgs",
+            W::ref_null("any"),
+            W::i32_const(args_count),
+        ));
+        instructions.push(W::local_set(&call_arguments));
+
+        // Populate the arguments array
+        for (index, arg) in call.args().iter().enumerate() {
+            let arg_instruction = self.translate_expression(arg, true);
+            instructions.push(W::list(vec![
+                arg_instruction,
+                W::local_set(&temp_arg),
+                W::instruction(
+                    "array.set",
                     vec![
-                        W::local_get(&function_local),
-                        get_this,
+                        W::r#type("$JSArgs"),
                         W::local_get(&call_arguments),
+                        W::i32_const(index as i32),
+                        W::local_get(&temp_arg),
                     ],
-                ));
-            }
+                ),
+            ]));
         }
 
+        // Translate the function expression
+        let function_local = self.current_function().add_local("$function", "anyref");
+        instructions.push(self.translate_expression(call.function(), true));
+        instructions.push(W::local_set(&function_local));
+
+        // Call the function
+        instructions.push(emit_call(&function_local, &call_arguments));
+
         if !will_use_return {
             instructions.push(W::drop());
         }
@@ -366,26 +800,29 @@ impl WasmTranslator {
     fn translate_let_vars(&mut self, variable_list: &VariableList, var_type: VarType) -> Box<W> {
         use boa_ast::declaration::Binding;
 
-        let var_name = self.current_function().add_local("$var", "anyref");
-
         let mut instructions = Vec::new();
         // TODO: handle hoisting
         for var in variable_list.as_ref() {
             match var.binding() {
                 Binding::Identifier(identifier) => {
-                    let offset = self.add_identifier(identifier);
-                    if let Some(expression) = var.init() {
-                        instructions.push(self.translate_expression(expression, true));
-                    } else {
-                        instructions.push(W::ref_null("any"));
+                    if let (Some(local), Some(expression)) =
+                        (self.unboxed_local(identifier.sym()), var.init())
+                    {
+                        instructions.push(self.translate_unboxed_value(expression));
+                        instructions.push(W::local_set(local));
+                        continue;
                     }
-                    instructions.push(W::local_set(&var_name));
-
-                    instructions.push(W::local_get("$scope"));
-                    instructions.push(W::i32_const(offset));
-                    instructions.push(W::local_get(&var_name));
-                    instructions.push(W::i32_const(var_type.to_i32()));
-                    instructions.push(W::call("$declare_variable", vec![]));
+                    let value = if let Some(expression) = var.init() {
+                        self.translate_expression(expression, true)
+                    } else if matches!(var_type, VarType::Var)
+                        && !self.frames.last().unwrap().scopes.is_dynamic()
+                    {
+                        // `var` without an initializer doesn't reset the variable
+                        continue;
+                    } else {
+                        W::ref_null("any")
+                    };
+                    instructions.push(self.set_variable(identifier, value, Some(var_type)));
                 }
                 Binding::Pattern(_pattern) => todo!(),
             }
@@ -414,19 +851,31 @@ impl WasmTranslator {
                 let rhs = self.translate_expression(binary.rhs(), true);
                 W::call(func.to_string(), vec![lhs, rhs])
             }
-            BinaryOp::Bitwise(_bitwise_op) => todo!(),
+            BinaryOp::Bitwise(bitwise_op) => {
+                let func = match bitwise_op {
+                    BitwiseOp::And => "$bitwise_and",
+                    BitwiseOp::Or => "$bitwise_or",
+                    BitwiseOp::Xor => "$bitwise_xor",
+                    BitwiseOp::Shl => "$shift_left",
+                    BitwiseOp::Shr => "$shift_right",
+                    BitwiseOp::UShr => "$unsigned_shift_right",
+                };
+                let lhs = self.translate_expression(binary.lhs(), true);
+                let rhs = self.translate_expression(binary.rhs(), true);
+                W::call(func.to_string(), vec![lhs, rhs])
+            }
             BinaryOp::Relational(relational_op) => {
                 let func_name = match relational_op {
                     RelationalOp::Equal => todo!(),
                     RelationalOp::NotEqual => todo!(),
                     RelationalOp::StrictEqual => "$strict_equal",
                     RelationalOp::StrictNotEqual => "$strict_not_equal",
-                    RelationalOp::GreaterThan => todo!(),
+                    RelationalOp::GreaterThan => "$greater_than",
                     RelationalOp::GreaterThanOrEqual => "$greater_than_or_equal",
                     RelationalOp::LessThan => "$less_than",
-                    RelationalOp::LessThanOrEqual => todo!(),
-                    RelationalOp::In => todo!(),
-                    RelationalOp::InstanceOf => todo!(),
+                    RelationalOp::LessThanOrEqual => "$less_than_or_equal",
+                    RelationalOp::In => "$in_operator",
+                    RelationalOp::InstanceOf => "$instance_of",
                 };
                 let rhs = self.current_function().add_local("$rhs", "anyref");
                 let lhs = self.current_function().add_local("$lhs", "anyref");
@@ -465,15 +914,13 @@ impl WasmTranslator {
     }
 
     fn translate_identifier(&mut self, identifier: &Identifier) -> Box<W> {
-        let offset = self.add_identifier(identifier);
-
+        if let Some(instruction) = self.translate_boxed(&Expression::Identifier(*identifier)) {
+            return instruction;
+        }
         if identifier.to_interned_string(&self.interner) == "undefined" {
             W::ref_null("any")
         } else {
-            W::call(
-                "$get_variable".to_string(),
-                vec![W::local_get("$scope"), W::i32_const(offset)],
-            )
+            self.get_variable(identifier)
         }
     }
 
@@ -513,18 +960,31 @@ impl WasmTranslator {
                         }
                     }
                     PropertyAccessField::Expr(expression) => {
-                        todo!()
-                        // let expr_result_var =
-                        //     self.current_function().add_local("$expr_result", "anyref");
-                        // let expr_result_instr = self.translate_expression(expression, true);
-                        //
-                        //  TODO:
-                        //
-                        //  we need to:
-                        //  1. create a function to convert various types to string
-                        //  2. create a way to put those strings into an array (on an object
-                        // itself, most probably)
-                        //  3. use the mappings to translate string access into i32 access
+                        let key = self.translate_expression(expression, true);
+
+                        if let Some(assign_instruction) = assign {
+                            let target_var = self.current_function().add_local("$target", "anyref");
+                            let key_var = self.current_function().add_local("$key", "anyref");
+                            let temp = self.current_function().add_local("$temp", "anyref");
+                            W::list(vec![
+                                target,
+                                W::local_set(&target_var),
+                                key,
+                                W::local_set(&key_var),
+                                assign_instruction,
+                                W::local_set(&temp),
+                                W::call(
+                                    "$set_computed_property",
+                                    vec![
+                                        W::local_get(&target_var),
+                                        W::local_get(&key_var),
+                                        W::local_get(&temp),
+                                    ],
+                                ),
+                            ])
+                        } else {
+                            W::list(vec![target, key, W::call("$get_computed_property", vec![])])
+                        }
                     }
                 }
             }
@@ -538,6 +998,14 @@ impl WasmTranslator {
         //     "translate expression ({will_use_return}) {} {expression:#?}",
         //     expression.to_interned_string(&self.interner)
         // );
+        if let Some(instruction) = self.translate_boxed(expression) {
+            return if will_use_return {
+                instruction
+            } else {
+                W::list(vec![instruction, W::drop()])
+            };
+        }
+
         match expression {
             Expression::This => W::local_get("$this"),
             Expression::Identifier(identifier) => {
@@ -549,7 +1017,7 @@ impl WasmTranslator {
                 }
             }
             Expression::Literal(literal) => self.translate_literal(literal),
-            Expression::RegExpLiteral(_reg_exp_literal) => todo!(),
+            Expression::RegExpLiteral(literal) => self.translate_regexp_literal(literal),
             Expression::ArrayLiteral(array_literal) => {
                 self.translate_array_literal(array_literal, will_use_return)
             }
@@ -572,11 +1040,16 @@ impl WasmTranslator {
             Expression::PropertyAccess(property_access) => {
                 self.translate_property_access(property_access, None)
             }
-            Expression::New(new) => self.translate_new(new),
-            // TODO: the default this value is a global object
-            Expression::Call(call) => {
-                self.translate_call(call, W::ref_null("any"), will_use_return)
+            Expression::New(new) => {
+                let instr = self.translate_new(new);
+                if !will_use_return {
+                    W::list(vec![instr, W::drop()])
+                } else {
+                    instr
+                }
             }
+            // TODO: the default this value is a global object
+            Expression::Call(call) => self.translate_call(call, call_function, will_use_return),
             Expression::SuperCall(_super_call) => todo!(),
             Expression::ImportCall(_import_call) => todo!(),
             Expression::Optional(_optional) => todo!(),
@@ -584,9 +1057,23 @@ impl WasmTranslator {
             Expression::NewTarget => todo!(),
             Expression::ImportMeta => todo!(),
             Expression::Assign(assign) => self.translate_assign(assign),
-            Expression::Unary(unary) => self.translate_unary(unary),
+            Expression::Unary(unary) => {
+                let instr = self.translate_unary(unary);
+                if !will_use_return {
+                    W::list(vec![instr, W::drop()])
+                } else {
+                    instr
+                }
+            }
             Expression::Update(update) => self.translate_update(update),
-            Expression::Binary(binary) => self.translate_binary(binary),
+            Expression::Binary(binary) => {
+                let instr = self.translate_binary(binary);
+                if !will_use_return {
+                    W::list(vec![instr, W::drop()])
+                } else {
+                    instr
+                }
+            }
             Expression::BinaryInPrivate(_binary_in_private) => todo!(),
             Expression::Conditional(_conditional) => todo!(),
             Expression::Await(await_expr) => self.translate_await_expression(await_expr),
@@ -596,10 +1083,9 @@ impl WasmTranslator {
         }
     }
 
-    fn translate_await_expression(&mut self, await_expression: &Await) -> Box<W> {
+    fn translate_await_expression(&mut self, _await_expression: &Await) -> Box<W> {
         // println!("AWAIT: {await_expression:#?}");
         todo!();
-        W::empty()
     }
 
     fn convert_return_to_resolve(&mut self, statement: &Statement) -> Statement {
@@ -910,33 +1396,53 @@ impl WasmTranslator {
                             W::call("$set_property", vec![]),
                         ])
                     }
-                    PropertyName::Computed(_) => todo!(),
+                    PropertyName::Computed(key) => {
+                        let key_var = self.current_function().add_local("$key", "anyref");
+                        W::list(vec![
+                            self.translate_expression(key, true),
+                            W::local_set(&key_var),
+                            self.translate_expression(expression, true),
+                            W::local_set(&temp),
+                            W::call(
+                                "$set_computed_property",
+                                vec![
+                                    W::local_get(&new_instance),
+                                    W::local_get(&key_var),
+                                    W::local_get(&temp),
+                                ],
+                            ),
+                        ])
+                    }
                 },
                 PropertyDefinition::MethodDefinition(property_name, method_definition) => {
-                    match property_name {
-                        PropertyName::Literal(sym) => {
-                            let offset = self.add_symbol(*sym);
-                            let func_instr = match method_definition {
-                                boa_ast::property::MethodDefinition::Get(_) => todo!(),
-                                boa_ast::property::MethodDefinition::Set(_) => todo!(),
-                                boa_ast::property::MethodDefinition::Ordinary(function) => {
-                                    self.translate_function(function)
-                                }
-                                boa_ast::property::MethodDefinition::Generator(_) => todo!(),
-                                boa_ast::property::MethodDefinition::AsyncGenerator(_) => todo!(),
-                                boa_ast::property::MethodDefinition::Async(_) => todo!(),
-                            };
-                            W::list(vec![
-                                func_instr,
-                                W::local_set(&temp),
-                                W::local_get(&new_instance),
-                                W::i32_const(offset),
-                                W::local_get(&temp),
-                                W::call("$set_property", vec![]),
-                            ])
+                    let key_instructions = match property_name {
+                        PropertyName::Literal(sym) => W::i32_const(self.add_symbol(*sym)),
+                        // the key is evaluated before the method is created
+                        PropertyName::Computed(key) => {
+                            let key_var = self.current_function().add_local("$key", "anyref");
+                            let key_instr = self.translate_expression(key, true);
+                            instructions.push(W::list(vec![key_instr, W::local_set(&key_var)]));
+                            W::call("$to_property_key", vec![W::local_get(&key_var)])
                         }
-                        PropertyName::Computed(_) => todo!(),
-                    }
+                    };
+                    let func_instr = match method_definition {
+                        boa_ast::property::MethodDefinition::Get(_) => todo!(),
+                        boa_ast::property::MethodDefinition::Set(_) => todo!(),
+                        boa_ast::property::MethodDefinition::Ordinary(function) => {
+                            self.translate_function(function)
+                        }
+                        boa_ast::property::MethodDefinition::Generator(_) => todo!(),
+                        boa_ast::property::MethodDefinition::AsyncGenerator(_) => todo!(),
+                        boa_ast::property::MethodDefinition::Async(_) => todo!(),
+                    };
+                    W::list(vec![
+                        func_instr,
+                        W::local_set(&temp),
+                        W::local_get(&new_instance),
+                        key_instructions,
+                        W::local_get(&temp),
+                        W::call("$set_property", vec![]),
+                    ])
                 }
                 PropertyDefinition::SpreadObject(_) => todo!(),
                 PropertyDefinition::CoverInitializedName(_, _) => todo!(),
@@ -951,16 +1457,16 @@ impl WasmTranslator {
     }
 
     fn translate_new(&mut self, new: &New) -> Box<W> {
-        let new_instance = self
-            .current_function()
-            .add_local("$new_instance", "(ref $Object)");
-        W::list(vec![
-            W::call("$new_object", vec![]),
-            W::local_set(&new_instance),
-            self.translate_call(new.call(), W::local_get(&new_instance), true),
-            W::local_get(&new_instance),
-            W::call("$return_object_or", vec![]),
-        ])
+        self.translate_call(
+            new.call(),
+            |constructor, arguments| {
+                W::call(
+                    "$construct",
+                    vec![W::local_get(constructor), W::local_get(arguments)],
+                )
+            },
+            true,
+        )
     }
 
     fn translate_arrow_function(&mut self, function: &ArrowFunction) -> Box<W> {
@@ -973,8 +1479,16 @@ impl WasmTranslator {
             UpdateTarget::Identifier(identifier) => identifier,
             UpdateTarget::PropertyAccess(_property_access) => todo!(),
         };
-        let var = self.current_function().add_local("$var", "anyref");
-
+        if let Some(local) = self.unboxed_local(identifier.sym()) {
+            let instruction = match update.op() {
+                UpdateOp::IncrementPost | UpdateOp::IncrementPre => "f64.add",
+                UpdateOp::DecrementPost | UpdateOp::DecrementPre => "f64.sub",
+            };
+            return W::list(vec![
+                W::instruction(instruction, vec![W::local_get(&local), W::f64_const(1.0)]),
+                W::local_set(local),
+            ]);
+        }
         // TODO: figure out pre vs post behaviour
         let instruction = match update.op() {
             UpdateOp::IncrementPost => W::call("$increment_number", vec![]),
@@ -982,44 +1496,37 @@ impl WasmTranslator {
             UpdateOp::DecrementPost => W::call("$decrement_number", vec![]),
             UpdateOp::DecrementPre => W::call("$decrement_number", vec![]),
         };
-        let target = self.translate_identifier(identifier);
-        let offset = self.add_identifier(identifier);
-        let set_variable = W::call(
-            "$set_variable".to_string(),
-            vec![
-                W::local_get("$scope".to_string()),
-                W::i32_const(offset),
-                W::local_get(&var),
-            ],
-        );
-
-        W::list(vec![target, instruction, W::local_set(&var), set_variable])
+        let value = W::list(vec![self.translate_identifier(identifier), instruction]);
+        self.set_variable(identifier, value, None)
     }
 
     fn translate_assign(&mut self, assign: &Assign) -> Box<W> {
         use boa_ast::expression::operator::assign::AssignOp;
         use boa_ast::expression::operator::assign::AssignTarget;
 
+        if let AssignTarget::Identifier(identifier) = assign.lhs() {
+            if let Some(local) = self.unboxed_local(identifier.sym()) {
+                let value = match assign.op() {
+                    AssignOp::Assign => self.translate_unboxed_value(assign.rhs()),
+                    // type inference only allows `+=` with numbers
+                    AssignOp::Add => W::instruction(
+                        "f64.add",
+                        vec![W::local_get(&local), self.translate_number(assign.rhs())],
+                    ),
+                    _ => unreachable!(
+                        "type inference doesn't unbox variables with other assignments"
+                    ),
+                };
+                return W::list(vec![value, W::local_set(local)]);
+            }
+        }
+
         match assign.op() {
             AssignOp::Assign => {
                 let rhs = self.translate_expression(assign.rhs(), true);
                 match assign.lhs() {
                     AssignTarget::Identifier(identifier) => {
-                        let offset = self.add_identifier(identifier);
-                        // identifier.sym().get(),
-                        let rhs_var = self.current_function().add_local("$rhs", "anyref");
-                        W::list(vec![
-                            rhs,
-                            W::local_set(&rhs_var),
-                            W::call(
-                                "$assign_variable".to_string(),
-                                vec![
-                                    W::local_get("$scope".to_string()),
-                                    W::i32_const(offset),
-                                    W::local_get(&rhs_var),
-                                ],
-                            ),
-                        ])
+                        self.set_variable(identifier, rhs, None)
                     }
                     AssignTarget::Access(property_access) => {
                         self.translate_property_access(property_access, Some(rhs))
@@ -1027,32 +1534,32 @@ impl WasmTranslator {
                     AssignTarget::Pattern(_pattern) => todo!(),
                 }
             }
-            AssignOp::Add => {
+            op => {
+                let func = match op {
+                    AssignOp::Add => "$add",
+                    AssignOp::Sub => "$sub",
+                    AssignOp::Mul => "$mul",
+                    AssignOp::Div => "$div",
+                    AssignOp::Mod => "$mod",
+                    AssignOp::Exp => "$exp",
+                    AssignOp::And => "$bitwise_and",
+                    AssignOp::Or => "$bitwise_or",
+                    AssignOp::Xor => "$bitwise_xor",
+                    AssignOp::Shl => "$shift_left",
+                    AssignOp::Shr => "$shift_right",
+                    AssignOp::Ushr => "$unsigned_shift_right",
+                    AssignOp::BoolAnd | AssignOp::BoolOr | AssignOp::Coalesce => todo!(),
+                    AssignOp::Assign => unreachable!(),
+                };
                 let rhs = self.translate_expression(assign.rhs(), true);
                 match assign.lhs() {
                     AssignTarget::Identifier(identifier) => {
-                        let offset = self.add_identifier(identifier);
-                        // identifier.sym().get(),
-                        let rhs_var = self.current_function().add_local("$rhs", "anyref");
-                        W::list(vec![
+                        let value = W::list(vec![
+                            self.translate_identifier(identifier),
                             rhs,
-                            W::local_set(&rhs_var),
-                            W::call(
-                                "$get_variable",
-                                vec![W::local_get("$scope"), W::i32_const(offset)],
-                            ),
-                            W::local_get(&rhs_var),
-                            W::call("$add", vec![]),
-                            W::local_set(&rhs_var),
-                            W::call(
-                                "$set_variable",
-                                vec![
-                                    W::local_get("$scope".to_string()),
-                                    W::i32_const(offset),
-                                    W::local_get(&rhs_var),
-                                ],
-                            ),
-                        ])
+                            W::call(func, vec![]),
+                        ]);
+                        self.set_variable(identifier, value, None)
                     }
                     AssignTarget::Access(property_access) => {
                         let rhs_var = self.current_function().add_local("$rhs", "anyref");
@@ -1061,7 +1568,7 @@ impl WasmTranslator {
                             W::local_set(&rhs_var),
                             self.translate_property_access(property_access, None),
                             W::local_get(&rhs_var),
-                            W::call("$add", vec![]),
+                            W::call(func, vec![]),
                             W::local_set(&rhs_var),
                             self.translate_property_access(
                                 property_access,
@@ -1072,42 +1579,175 @@ impl WasmTranslator {
                     AssignTarget::Pattern(_pattern) => todo!(),
                 }
             }
-            AssignOp::Sub => todo!(),
-            AssignOp::Mul => todo!(),
-            AssignOp::Div => todo!(),
-            AssignOp::Mod => todo!(),
-            AssignOp::Exp => todo!(),
-            AssignOp::And => todo!(),
-            AssignOp::Or => todo!(),
-            AssignOp::Xor => todo!(),
-            AssignOp::Shl => todo!(),
-            AssignOp::Shr => todo!(),
-            AssignOp::Ushr => todo!(),
-            AssignOp::BoolAnd => todo!(),
-            AssignOp::BoolOr => todo!(),
-            AssignOp::Coalesce => todo!(),
         }
     }
 
     fn translate_unary(&mut self, unary: &Unary) -> Box<W> {
         use boa_ast::expression::operator::unary::UnaryOp;
 
+        if unary.op() == UnaryOp::Delete {
+            return self.translate_delete(unary.target());
+        }
+        if unary.op() == UnaryOp::TypeOf {
+            // `typeof` doesn't throw for undeclared variables
+            if let Expression::Identifier(identifier) = unary.target() {
+                if self.unboxed_local(identifier.sym()).is_none()
+                    && matches!(
+                        self.resolve(identifier.sym()),
+                        Some((
+                            _,
+                            Binding {
+                                storage: Storage::Dynamic | Storage::Global,
+                                ..
+                            }
+                        )) | None
+                    )
+                {
+                    let offset = self.add_identifier(identifier);
+                    return W::call(
+                        "$type_of",
+                        vec![W::call(
+                            "$get_variable_or_undefined",
+                            vec![W::local_get("$scope"), W::i32_const(offset)],
+                        )],
+                    );
+                }
+            }
+        }
+
         let target = self.translate_expression(unary.target(), true);
         match unary.op() {
-            UnaryOp::Minus => todo!(),
-            UnaryOp::Plus => todo!(),
+            UnaryOp::Minus => W::list(vec![target, W::call("$negate", vec![])]),
+            UnaryOp::Plus => W::list(vec![target, W::call("$unary_plus", vec![])]),
             UnaryOp::Not => W::list(vec![target, W::call("$logical_not", vec![])]),
-            UnaryOp::Tilde => todo!(),
+            UnaryOp::Tilde => W::list(vec![target, W::call("$bitwise_not", vec![])]),
             UnaryOp::TypeOf => W::list(vec![target, W::call("$type_of", vec![])]),
-            UnaryOp::Delete => todo!(),
+            UnaryOp::Delete => unreachable!(),
             UnaryOp::Void => todo!(),
         }
     }
 
+    fn translate_delete(&mut self, target: &Expression) -> Box<W> {
+        use boa_ast::expression::access::PropertyAccessField;
+
+        let Expression::PropertyAccess(PropertyAccess::Simple(access)) = target else {
+            // deleting a variable doesn't do anything, anything else that is
+            // not a reference just evaluates to true
+            if let Expression::Identifier(_) = target {
+                return W::global_get("$false");
+            }
+            return W::list(vec![
+                self.translate_expression(target, false),
+                W::global_get("$true"),
+            ]);
+        };
+
+        let object = self.translate_expression(access.target(), true);
+        let key = match access.field() {
+            PropertyAccessField::Const(sym) => W::i32_const(self.add_symbol(*sym)),
+            PropertyAccessField::Expr(expression) => W::call(
+                "$to_property_key",
+                vec![self.translate_expression(expression, true)],
+            ),
+        };
+        W::call(
+            "$new_boolean",
+            vec![W::call("$delete_property", vec![object, key])],
+        )
+    }
+
+    fn translate_regexp_literal(&mut self, literal: &RegExpLiteral) -> Box<W> {
+        let pattern = self
+            .interner
+            .resolve(literal.pattern())
+            .unwrap()
+            .to_string();
+        let flags = self.interner.resolve(literal.flags()).unwrap().to_string();
+        // the parser already rejects invalid patterns, so this only fails for
+        // the syntax the matcher doesn't support or for programs that are too
+        // large. Those are left to the runtime compiler, which throws the
+        // SyntaxError when the literal is evaluated
+        let compiled = match regexp::compile(&pattern, &flags) {
+            Ok(compiled) => compiled,
+            Err(_) => {
+                let (pattern_offset, pattern_length) = self.insert_data_string(&pattern);
+                let (flags_offset, flags_length) = self.insert_data_string(&flags);
+                return W::call(
+                    "$regexp_compile",
+                    vec![
+                        W::call(
+                            "$new_static_string",
+                            vec![W::i32_const(pattern_offset), W::i32_const(pattern_length)],
+                        ),
+                        W::call(
+                            "$new_static_string",
+                            vec![W::i32_const(flags_offset), W::i32_const(flags_length)],
+                        ),
+                    ],
+                );
+            }
+        };
+
+        let program = format!("regexp-program-{}", self.module.globals.len());
+        let values = compiled
+            .program
+            .iter()
+            .map(|value| W::i32_const(*value))
+            .collect();
+        self.module.globals.push((
+            program.clone(),
+            "(ref $I32Array)".into(),
+            *W::instruction(
+                format!("array.new_fixed $I32Array {}", compiled.program.len()),
+                values,
+            ),
+        ));
+
+        let group_names = if compiled.group_names.iter().any(|name| name.is_some()) {
+            let names = compiled
+                .group_names
+                .iter()
+                .map(|name| match name {
+                    Some(name) => {
+                        let (offset, length) = self.insert_data_string(name);
+                        W::call(
+                            "$new_static_string",
+                            vec![W::i32_const(offset), W::i32_const(length)],
+                        )
+                    }
+                    None => W::ref_null("any"),
+                })
+                .collect();
+            W::instruction(
+                format!(
+                    "array.new_fixed $AnyrefArray {}",
+                    compiled.group_names.len()
+                ),
+                names,
+            )
+        } else {
+            W::ref_null("$AnyrefArray")
+        };
+
+        let (offset, length) = self.insert_data_string(&pattern);
+        W::call(
+            "$new_regexp",
+            vec![
+                W::call(
+                    "$new_static_string",
+                    vec![W::i32_const(offset), W::i32_const(length)],
+                ),
+                W::i32_const(compiled.flags),
+                W::global_get(format!("${program}")),
+                group_names,
+            ],
+        )
+    }
+
     fn translate_literal(&mut self, lit: &Literal) -> Box<W> {
         // println!("translate_literal: {lit:#?}");
         match lit {
-            Literal::Num(num) => W::call("$new_number", vec![W::f64_const(*num)]),
+            Literal::Num(num) => number_literal(*num),
             Literal::String(s) => {
                 let s = self.interner.resolve(*s).unwrap().to_string();
                 let (offset, length) = self.insert_data_string(&s);
@@ -1117,14 +1757,191 @@ impl WasmTranslator {
                     vec![W::i32_const(offset), W::i32_const(length)],
                 )
             }
-            Literal::Int(i) => W::call("$new_number", vec![W::f64_const(*i as f64)]),
-            Literal::BigInt(_big_int) => todo!(),
-            Literal::Bool(b) => W::call("$new_boolean", vec![W::i32_const(if *b { 1 } else { 0 })]),
-            Literal::Null => W::ref_i31(W::i32_const(2)),
+            Literal::Int(i) => number_literal(*i as f64),
+            Literal::BigInt(big_int) => {
+                let (_, digits) = big_int.to_u32_digits();
+                self.translate_bigint_literal(**big_int < Default::default(), &digits)
+            }
+            Literal::Bool(b) => W::global_get(if *b { "$true" } else { "$false" }),
+            Literal::Null => W::global_get("$null"),
             Literal::Undefined => W::ref_null("any"),
         }
     }
 
+    // BigInt literals are immutable, so each of them is created once, as a global
+    fn translate_bigint_literal(&mut self, negative: bool, digits: &[u32]) -> Box<W> {
+        let name = format!("bigint-{}", self.module.globals.len());
+        let values = digits
+            .iter()
+            .map(|digit| W::i32_const(*digit as i32))
+            .collect();
+        self.module.globals.push((
+            name.clone(),
+            "(ref $BigInt)".into(),
+            *W::instruction(
+                "struct.new $BigInt",
+                vec![
+                    W::i32_const(negative as i32),
+                    W::instruction(
+                        format!("array.new_fixed $BigDigits {}", digits.len()),
+                        values,
+                    ),
+                ],
+            ),
+        ));
+        W::global_get(format!("${name}"))
+    }
+
+    // Translates an expression that type inference proved to be a number or a
+    // boolean to an f64 or i32 value. Returns None if the expression can't be
+    // computed without the dynamic path, eg. a call
+    fn translate_unboxed(&mut self, expression: &Expression) -> Option<Box<W>> {
+        use boa_ast::expression::operator::{binary::RelationalOp, unary::UnaryOp};
+
+        match expression {
+            Expression::Literal(Literal::Num(num)) => Some(W::f64_const(*num)),
+            Expression::Literal(Literal::Int(i)) => Some(W::f64_const(*i as f64)),
+            Expression::Literal(Literal::Bool(b)) => Some(W::i32_const(*b as i32)),
+            Expression::Identifier(identifier) => {
+                self.unboxed_local(identifier.sym()).map(W::local_get)
+            }
+            Expression::Parenthesized(parenthesized) => {
+                self.translate_unboxed(parenthesized.expression())
+            }
+            Expression::Unary(unary) => {
+                let operand_type = self.expression_type(unary.target());
+                match (unary.op(), operand_type) {
+                    (UnaryOp::Minus, ValueType::Number) => Some(W::instruction(
+                        "f64.neg",
+                        vec![self.translate_number(unary.target())],
+                    )),
+                    (UnaryOp::Plus, ValueType::Number) => {
+                        Some(self.translate_number(unary.target()))
+                    }
+                    (UnaryOp::Not, ValueType::Boolean) => Some(W::instruction(
+                        "i32.eqz",
+                        vec![self.translate_boolean(unary.target())],
+                    )),
+                    _ => None,
+                }
+            }
+            Expression::Binary(binary) => {
+                let lhs_type = self.expression_type(binary.lhs());
+                let rhs_type = self.expression_type(binary.rhs());
+                let numbers = lhs_type == ValueType::Number && rhs_type == ValueType::Number;
+                let booleans = lhs_type == ValueType::Boolean && rhs_type == ValueType::Boolean;
+
+                let (instruction, is_call) = match binary.op() {
+                    BinaryOp::Arithmetic(op) if numbers => match op {
+                        ArithmeticOp::Add => ("f64.add", false),
+                        ArithmeticOp::Sub => ("f64.sub", false),
+                        ArithmeticOp::Mul => ("f64.mul", false),
+                        ArithmeticOp::Div => ("f64.div", false),
+                        ArithmeticOp::Mod => ("$f64_rem", true),
+                        ArithmeticOp::Exp => ("$math_pow", true),
+                    },
+                    BinaryOp::Relational(op) if numbers => match op {
+                        RelationalOp::LessThan => ("f64.lt", false),
+                        RelationalOp::GreaterThan => ("f64.gt", false),
+                        RelationalOp::LessThanOrEqual => ("f64.le", false),
+                        RelationalOp::GreaterThanOrEqual => ("f64.ge", false),
+                        RelationalOp::Equal | RelationalOp::StrictEqual => ("f64.eq", false),
+                        RelationalOp::NotEqual | RelationalOp::StrictNotEqual => ("f64.ne", false),
+                        _ => return None,
+                    },
+                    BinaryOp::Relational(op) if booleans => match op {
+                        RelationalOp::Equal | RelationalOp::StrictEqual => ("i32.eq", false),
+                        RelationalOp::NotEqual | RelationalOp::StrictNotEqual => ("i32.ne", false),
+                        _ => return None,
+                    },
+                    BinaryOp::Logical(op) if booleans => {
+                        // the right hand side is evaluated only if needed
+                        let lhs = self.translate_boolean(binary.lhs());
+                        let rhs = self.translate_boolean(binary.rhs());
+                        let (then, r#else) = match op {
+                            LogicalOp::And => (rhs, W::i32_const(0)),
+                            LogicalOp::Or => (W::i32_const(1), rhs),
+                            LogicalOp::Coalesce => return None,
+                        };
+                        return Some(W::instruction(
+                            "if (result i32)",
+                            vec![
+                                lhs,
+                                W::instruction("then", vec![then]),
+                                W::instruction("else", vec![r#else]),
+                            ],
+                        ));
+                    }
+                    _ => return None,
+                };
+
+                let (lhs, rhs) = if numbers {
+                    (
+                        self.translate_number(binary.lhs()),
+                        self.translate_number(binary.rhs()),
+                    )
+                } else {
+                    (
+                        self.translate_boolean(binary.lhs()),
+                        self.translate_boolean(binary.rhs()),
+                    )
+                };
+                Some(if is_call {
+                    W::call(instruction, vec![lhs, rhs])
+                } else {
+                    W::instruction(instruction, vec![lhs, rhs])
+                })
+            }
+            _ => None,
+        }
+    }
+
+    // an f64 value of an expression with the Number type
+    fn translate_number(&mut self, expression: &Expression) -> Box<W> {
+        if let Some(instruction) = self.translate_unboxed(expression) {
+            return instruction;
+        }
+        W::call(
+            "$number_value",
+            vec![self.translate_expression(expression, true)],
+        )
+    }
+
+    // an i32 truth value of any expression
+    fn translate_boolean(&mut self, expression: &Expression) -> Box<W> {
+        if self.expression_type(expression) == ValueType::Boolean {
+            if let Some(instruction) = self.translate_unboxed(expression) {
+                return instruction;
+            }
+        }
+        W::call(
+            "$cast_ref_to_i32_bool",
+            vec![self.translate_expression(expression, true)],
+        )
+    }
+
+    // a value for an unboxed local, the expression has the same type as the local
+    fn translate_unboxed_value(&mut self, expression: &Expression) -> Box<W> {
+        match self.expression_type(expression) {
+            ValueType::Number => self.translate_number(expression),
+            _ => self.translate_boolean(expression),
+        }
+    }
+
+    // the unboxed computation boxed into a value, literals are skipped as they
+    // have their own representation
+    fn translate_boxed(&mut self, expression: &Expression) -> Option<Box<W>> {
+        if matches!(expression, Expression::Literal(_)) {
+            return None;
+        }
+        let value_type = self.expression_type(expression);
+        let instruction = self.translate_unboxed(expression)?;
+        Some(match value_type {
+            ValueType::Number => W::call("$new_number", vec![instruction]),
+            _ => W::call("$new_boolean", vec![instruction]),
+        })
+    }
+
     fn translate_declaration(&mut self, declaration: &Declaration) -> Box<W> {
         // println!(
         //     "translate_declaration {}",
@@ -1136,16 +1953,7 @@ impl WasmTranslator {
                 // function declaration still needs to be added to the scope if function has a name
                 // TODO: declared functions need to be hoisted
                 if let Some(name) = decl.name() {
-                    let offset = self.add_identifier(&name);
-                    W::call(
-                        "$declare_variable".to_string(),
-                        vec![
-                            W::local_get("$scope"),
-                            W::i32_const(offset),
-                            declaration,
-                            W::i32_const(VarType::Var.to_i32()),
-                        ],
-                    )
+                    self.set_variable(&name, declaration, Some(VarType::Var))
                 } else {
                     // TODO: if it's empty and not called right away I guess we can just ignore it?
                     declaration
@@ -1156,16 +1964,7 @@ impl WasmTranslator {
             Declaration::AsyncFunction(decl) => {
                 let declaration = self.translate_async_function(decl);
                 if let Some(name) = decl.name() {
-                    let offset = self.add_identifier(&name);
-                    W::call(
-                        "$declare_variable".to_string(),
-                        vec![
-                            W::local_get("$scope"),
-                            W::i32_const(offset),
-                            declaration,
-                            W::i32_const(VarType::Var.to_i32()),
-                        ],
-                    )
+                    self.set_variable(&name, declaration, Some(VarType::Var))
                 } else {
                     // TODO: if it's empty and not called right away I guess we can just ignore it?
                     declaration
@@ -1180,21 +1979,25 @@ impl WasmTranslator {
         "".into()
     }
 
+    // Returns an offset of the string data and its length. The offset is also used
+    // as a key for properties and variables, so the same string will always end up
+    // at the same offset
     fn insert_data_string(&mut self, s: &str) -> (i32, i32) {
-        let value = s.replace("\"", "\\\"");
-        let len = value.len() as i32;
-        let offset = self.data_offset;
-        if let Some(offset) = self.string_offsets.get(&value) {
+        let len = s.len() as i32;
+        if let Some(offset) = self.string_offsets.get(s) {
             (*offset, len)
         } else {
-            self.data_entries.insert(offset, value.clone());
-            self.string_offsets.insert(value, offset);
-            self.data_offset += if len % 4 == 0 {
-                len
-            } else {
-                // some runtimes expect all data aligned to 4 bytes
-                len + (4 - len % 4)
-            };
+            // the first 4 bytes hold the length of the string
+            let offset = self.data_offset + 4;
+            self.data_entries.insert(offset, s.to_string());
+            self.string_offsets.insert(s.to_string(), offset);
+            self.data_offset = offset
+                + if len % 4 == 0 {
+                    len
+                } else {
+                    // some runtimes expect all data aligned to 4 bytes
+                    len + (4 - len % 4)
+                };
 
             (offset, len)
         }
@@ -1209,7 +2012,7 @@ impl WasmTranslator {
             Statement::If(if_statement) => self.translate_if_statement(if_statement),
             Statement::DoWhileLoop(_do_while_loop) => todo!(),
             Statement::WhileLoop(while_loop) => self.translate_while_loop(while_loop),
-            Statement::ForLoop(_for_loop) => todo!(),
+            Statement::ForLoop(for_loop) => self.translate_for_loop(for_loop),
             Statement::ForInLoop(_for_in_loop) => todo!(),
             Statement::ForOfLoop(_for_of_loop) => todo!(),
             Statement::Switch(_switch) => todo!(),
@@ -1226,30 +2029,25 @@ impl WasmTranslator {
     fn translate_catch(&mut self, catch: Option<&Catch>, finally: Option<&Finally>) -> Box<W> {
         use boa_ast::declaration::Binding;
         let catch_instr = if let Some(catch) = catch {
+            let environment = self.enter_scope(scope_id(catch));
             let binding_instr = if let Some(binding) = catch.parameter() {
                 match binding {
+                    // the exception is already on the stack
                     Binding::Identifier(identifier) => {
-                        let temp = self.current_function().add_local("$temp", "anyref");
-                        let offset = self.add_identifier(identifier);
-                        W::list(vec![
-                            W::local_set(&temp),
-                            W::call(
-                                "$declare_variable",
-                                vec![
-                                    W::local_get("$scope"),
-                                    W::i32_const(offset),
-                                    W::local_get(&temp),
-                                    W::i32_const(VarType::Param.to_i32()),
-                                ],
-                            ),
-                        ])
+                        self.set_variable(identifier, W::empty(), Some(VarType::Param))
                     }
                     Binding::Pattern(_) => todo!(),
                 }
             } else {
                 W::drop()
             };
-            W::list(vec![binding_instr, self.translate_block(catch.block())])
+            let instr = W::list(vec![
+                environment,
+                binding_instr,
+                self.translate_block(catch.block()),
+            ]);
+            self.exit_scope();
+            instr
         } else {
             W::empty()
         };
@@ -1280,8 +2078,7 @@ impl WasmTranslator {
 
     fn translate_if_statement(&mut self, if_statement: &If) -> Box<W> {
         W::list(vec![
-            self.translate_expression(if_statement.cond(), true),
-            W::call("$cast_ref_to_i32_bool", vec![]),
+            self.translate_boolean(if_statement.cond()),
             W::r#if(
                 None,
                 vec![self.translate_statement(if_statement.body())],
@@ -1293,14 +2090,13 @@ impl WasmTranslator {
     }
 
     fn translate_while_loop(&mut self, while_loop: &WhileLoop) -> Box<W> {
-        let condition = self.translate_expression(while_loop.condition(), true);
+        let condition = self.translate_boolean(while_loop.condition());
         W::r#loop(
             "$while_loop".to_string(),
             vec![W::block(
                 "$break",
                 vec![
                     condition,
-                    W::call("$cast_ref_to_i32_bool", vec![]),
                     W::i32_eqz(),
                     W::br_if("$break"),
                     self.translate_statement(while_loop.body()),
@@ -1310,17 +2106,77 @@ impl WasmTranslator {
         )
     }
 
+    // `let` and `const` declared in the initializer get a copy for each
+    // iteration, made before the final expression runs, so closures created in
+    // the body keep the values from their iteration
+    fn translate_for_loop(&mut self, for_loop: &ForLoop) -> Box<W> {
+        let environment = self.enter_scope(scope_id(for_loop));
+        let init = match for_loop.init() {
+            Some(ForLoopInitializer::Expression(expression)) => {
+                self.translate_expression(expression, false)
+            }
+            Some(ForLoopInitializer::Var(declaration)) => self.translate_var(declaration),
+            Some(ForLoopInitializer::Lexical(declaration)) => self.translate_lexical(declaration),
+            None => W::empty(),
+        };
+        let condition = match for_loop.condition() {
+            Some(condition) => W::list(vec![
+                self.translate_boolean(condition),
+                W::i32_eqz(),
+                W::br_if("$break"),
+            ]),
+            None => W::empty(),
+        };
+        let body = self.translate_statement(for_loop.body());
+        let final_expr = match for_loop.final_expr() {
+            Some(expression) => self.translate_expression(expression, false),
+            None => W::empty(),
+        };
+        let copy_environment = match &self
+            .frames
+            .last()
+            .unwrap()
+            .blocks
+            .last()
+            .unwrap()
+            .environment
+        {
+            Some(local) => W::list(vec![
+                W::call("$copy_environment", vec![W::local_get(local)]),
+                W::local_set(local),
+            ]),
+            None => W::empty(),
+        };
+        self.exit_scope();
+
+        W::list(vec![
+            environment,
+            init,
+            W::r#loop(
+                "$for_loop".to_string(),
+                vec![W::block(
+                    "$break",
+                    vec![
+                        condition,
+                        body,
+                        copy_environment,
+                        final_expr,
+                        W::br("$for_loop"),
+                    ],
+                )],
+            ),
+        ])
+    }
+
     fn translate_block(&mut self, block: &Block) -> Box<W> {
         self.enter_block();
-        let block_instr = W::block(
-            self.current_block_name(),
-            block
-                .statement_list()
-                .statements()
-                .iter()
-                .map(|s| self.translate_statement_list_item(s))
-                .collect(),
-        );
+        let environment = self.enter_scope(scope_id(block));
+        let mut instructions = vec![environment];
+        for statement in block.statement_list().statements() {
+            instructions.push(self.translate_statement_list_item(statement));
+        }
+        let block_instr = W::block(self.current_block_name(), instructions);
+        self.exit_scope();
         self.exit_block();
 
         block_instr
@@ -1378,13 +2234,15 @@ impl<'a> Visitor<'a> for WasmTranslator {
     }
 
     fn visit_call(&mut self, node: &'a Call) -> ControlFlow<Self::BreakTy> {
-        let instruction = self.translate_call(node, W::ref_null("any"), false);
+        let instruction = self.translate_call(node, call_function, false);
         self.current_function().add_instruction(instruction);
         ControlFlow::Continue(())
     }
 }
 
 fn main() -> anyhow::Result<()> {
+    let event_loop = wat_template::EventLoop::from_env()?;
+    let random_seed = wat_template::random_seed_from_env()?;
     let mut js_code = String::new();
     io::stdin().read_to_string(&mut js_code)?;
 
@@ -1395,21 +2253,59 @@ fn main() -> anyhow::Result<()> {
         .parse_script(&mut interner)
         .map_err(|e| anyhow!("JAWS parsing error: {e}"))?;
 
-    let mut translator = WasmTranslator::new(interner);
+    // WASI interfaces other than the basic ones are only imported by scripts
+    // that mention one of the globals using them
+    let mentions = |names: &[&str]| {
+        names
+            .iter()
+            .any(|name| interner.get(JStrRef::Utf8(name)).is_some())
+    };
+    let options = wat_template::TemplateOptions {
+        event_loop,
+        fetch: mentions(&["fetch", "Headers", "Response"]),
+        process: mentions(&["process"]),
+        filesystem: mentions(&["fs"]),
+        stdin: mentions(&["stdin"]),
+        date: mentions(&["Date", "performance"]),
+        crypto: mentions(&["crypto"]),
+        random: mentions(&["random"]),
+        random_seed,
+    };
+
+    let types = TypeInfo::analyze(&ast);
+    let mut translator = WasmTranslator::new(interner, types);
+    translator
+        .current_function()
+        .add_local_exact("$scope", "(ref $Scope)");
+    // `this` at the top level is the global object
+    translator
+        .current_function()
+        .add_local_exact("$this", "anyref");
+    translator.current_function().add_instruction(W::list(vec![
+        W::global_get("$global-object"),
+        W::local_set("$this"),
+    ]));
+    let global_scope = W::list(vec![
+        W::global_get("$scope"),
+        W::instruction("ref.cast (ref $Scope)", vec![]),
+    ]);
+    let set_scope = translator.enter_frame(FunctionScopes::analyze_script(&ast), global_scope);
+    translator.current_function().add_instruction(set_scope);
+    translator.enter_unboxed_scope(&ast);
+    let environment = translator.enter_scope(scope_id(&ast));
+    translator.current_function().add_instruction(environment);
+    let declare_globals = translator.declare_globals(scope_id(&ast));
+    translator
+        .current_function()
+        .add_instruction(declare_globals);
     // println!("{ast:#?}");
-    ast.visit_with(&mut translator);
+    let _ = ast.visit_with(&mut translator);
     // exit $init function
+    translator.exit_scope();
+    translator.exit_unboxed_scope();
+    translator.exit_frame();
     translator.exit_function();
 
-    let init = translator.module.get_function_mut("init").unwrap();
-    init.add_local_exact("$scope", "(ref $Scope)");
-
-    init.body.push_front(W::list(vec![
-        W::global_get("$scope"),
-        W::instruction("ref.cast (ref $Scope)", vec![]),
-        W::local_set("$scope"),
-    ]));
-
     // Generate the full WAT module
     let module = translator.module.to_string();
 
@@ -1418,6 +2314,7 @@ fn main() -> anyhow::Result<()> {
         translator.additional_functions(),
         module,
         &mut translator,
+        options,
     );
 
     let jaws_dir = std::env::var("JAWS_DIR").unwrap_or(".".into());
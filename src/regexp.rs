@@ -0,0 +1,925 @@
+//! Compiles regular expression literals ahead of time into the bytecode run by
+//! the backtracking matcher in `wat/regexp.wat`. Patterns only known at
+//! runtime (`new RegExp(source)`) go through the same compiler ported to WAT,
+//! so the two have to produce the same programs.
+//!
+//! A program starts with a header of two numbers: the count of capture groups
+//! (including the whole match as group 0) and the count of loop registers used
+//! to stop empty iterations. Every instruction is an opcode followed by its
+//! operands. Jump targets are relative to the opcode of the jumping
+//! instruction, which allows compiling parts of a pattern separately and
+//! concatenating or repeating them without any relocation.
+//!
+//! Strings are UTF-8 internally, so the matcher works on whole code points and
+//! match indexes are byte offsets, like the rest of the string builtins.
+pub const OP_MATCH: i32 = 0;
+// CHAR code_point
+pub const OP_CHAR: i32 = 1;
+// any code point but a line terminator
+pub const OP_ANY: i32 = 2;
+// any code point, with the `s` flag
+pub const OP_ANY_ALL: i32 = 3;
+// CLASS negated range_count [from to]*
+pub const OP_CLASS: i32 = 4;
+// SPLIT preferred alternative, tries the first target, then the second
+pub const OP_SPLIT: i32 = 5;
+// JMP target
+pub const OP_JMP: i32 = 6;
+// SAVE register, stores the current position in a capture register
+pub const OP_SAVE: i32 = 7;
+pub const OP_LINE_START: i32 = 8;
+pub const OP_LINE_START_MULTILINE: i32 = 9;
+pub const OP_LINE_END: i32 = 10;
+pub const OP_LINE_END_MULTILINE: i32 = 11;
+pub const OP_WORD_BOUNDARY: i32 = 12;
+pub const OP_NOT_WORD_BOUNDARY: i32 = 13;
+// BACKREF group
+pub const OP_BACKREF: i32 = 14;
+// LOOKAHEAD negated length, followed by the assertion's code ending with MATCH
+pub const OP_LOOKAHEAD: i32 = 15;
+// CHECK_PROGRESS loop_register, fails if a loop iteration matched nothing
+pub const OP_CHECK_PROGRESS: i32 = 16;
+// RESET from to, clears capture registers of groups inside a repeated atom
+pub const OP_RESET: i32 = 17;
+// the case insensitive versions, CHAR_I holds a canonicalized code point
+pub const OP_CHAR_I: i32 = 18;
+pub const OP_CLASS_I: i32 = 19;
+pub const OP_BACKREF_I: i32 = 20;
+// MARK loop_register, stores the current position in a loop register
+pub const OP_MARK: i32 = 21;
+// LOOKBEHIND negated length, like LOOKAHEAD
+pub const OP_LOOKBEHIND: i32 = 22;
+
+pub const FLAG_GLOBAL: i32 = 1;
+pub const FLAG_IGNORE_CASE: i32 = 2;
+pub const FLAG_MULTILINE: i32 = 4;
+pub const FLAG_DOT_ALL: i32 = 8;
+pub const FLAG_UNICODE: i32 = 16;
+pub const FLAG_STICKY: i32 = 32;
+
+// repeating bigger atoms more times than this would make huge programs
+const MAX_PROGRAM_SIZE: usize = 1 << 20;
+
+pub struct CompiledRegExp {
+    pub flags: i32,
+    pub program: Vec<i32>,
+    // names of the capture groups, indexed by the group number
+    pub group_names: Vec<Option<String>>,
+}
+
+pub fn parse_flags(flags: &str) -> Result<i32, String> {
+    let mut result = 0;
+    for c in flags.chars() {
+        let flag = match c {
+            'g' => FLAG_GLOBAL,
+            'i' => FLAG_IGNORE_CASE,
+            'm' => FLAG_MULTILINE,
+            's' => FLAG_DOT_ALL,
+            'u' => FLAG_UNICODE,
+            'y' => FLAG_STICKY,
+            _ => {
+                return Err(format!(
+                    "Invalid flags supplied to RegExp constructor '{flags}'"
+                ))
+            }
+        };
+        if result & flag != 0 {
+            return Err(format!(
+                "Invalid flags supplied to RegExp constructor '{flags}'"
+            ));
+        }
+        result |= flag;
+    }
+    Ok(result)
+}
+
+pub fn compile(pattern: &str, flags: &str) -> Result<CompiledRegExp, String> {
+    let flags = parse_flags(flags)?;
+    let chars: Vec<char> = pattern.chars().collect();
+    let group_names = scan_groups(&chars);
+    let mut compiler = Compiler {
+        chars,
+        position: 0,
+        flags,
+        group_count: 1,
+        mark_count: 0,
+        group_names,
+    };
+
+    let code = compiler.disjunction()?;
+    if compiler.position < compiler.chars.len() {
+        // the only thing that can stop a disjunction early
+        return Err("Unmatched ')'".into());
+    }
+
+    let mut program = vec![compiler.group_count as i32, compiler.mark_count];
+    program.extend([OP_SAVE, 0]);
+    program.extend(code);
+    program.extend([OP_SAVE, 1, OP_MATCH]);
+    if program.len() > MAX_PROGRAM_SIZE {
+        return Err("Regular expression too large".into());
+    }
+
+    Ok(CompiledRegExp {
+        flags,
+        program,
+        group_names: compiler.group_names,
+    })
+}
+
+// Finds the capture groups before compiling, as backreferences can refer to
+// groups that come later in the pattern
+fn scan_groups(chars: &[char]) -> Vec<Option<String>> {
+    let mut names = vec![None];
+    let mut in_class = false;
+    let mut i = 0;
+    while i < chars.len() {
+        match chars[i] {
+            '\\' => i += 1,
+            '[' => in_class = true,
+            ']' => in_class = false,
+            '(' if !in_class => {
+                if chars.get(i + 1) != Some(&'?') {
+                    names.push(None);
+                } else if chars.get(i + 2) == Some(&'<')
+                    && !matches!(chars.get(i + 3), Some('=') | Some('!'))
+                {
+                    let name: String = chars[i + 3..].iter().take_while(|c| **c != '>').collect();
+                    names.push(Some(name));
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    names
+}
+
+// The simple case mapping used for case insensitive matching, only covering
+// the scripts with simple one to one mappings. It has to stay in sync with
+// $regexp_canonicalize in regexp.wat
+pub fn canonicalize(c: u32) -> u32 {
+    match c {
+        0x61..=0x7a => c - 0x20,
+        0xe0..=0xfe if c != 0xf7 => c - 0x20,
+        0xff => 0x178,
+        0x3b1..=0x3c9 if c != 0x3c2 => c - 0x20,
+        0x3c2 => 0x3a3,
+        0x430..=0x44f => c - 0x20,
+        0x450..=0x45f => c - 0x50,
+        _ => c,
+    }
+}
+
+fn is_syntax_character(c: char) -> bool {
+    "^$\\.*+?()[]{}|/".contains(c)
+}
+
+const DIGIT_RANGES: &[(u32, u32)] = &[(0x30, 0x39)];
+const WORD_RANGES: &[(u32, u32)] = &[(0x30, 0x39), (0x41, 0x5a), (0x5f, 0x5f), (0x61, 0x7a)];
+const SPACE_RANGES: &[(u32, u32)] = &[
+    (0x09, 0x0d),
+    (0x20, 0x20),
+    (0xa0, 0xa0),
+    (0x1680, 0x1680),
+    (0x2000, 0x200a),
+    (0x2028, 0x2029),
+    (0x202f, 0x202f),
+    (0x205f, 0x205f),
+    (0x3000, 0x3000),
+    (0xfeff, 0xfeff),
+];
+
+fn complement(ranges: &[(u32, u32)]) -> Vec<(u32, u32)> {
+    let mut result = Vec::new();
+    let mut next = 0;
+    for &(from, to) in ranges {
+        if from > next {
+            result.push((next, from - 1));
+        }
+        next = to + 1;
+    }
+    if next <= 0x10ffff {
+        result.push((next, 0x10ffff));
+    }
+    result
+}
+
+// sorts the ranges and merges the overlapping and adjacent ones
+fn normalize(mut ranges: Vec<(u32, u32)>) -> Vec<(u32, u32)> {
+    ranges.sort();
+    let mut result: Vec<(u32, u32)> = Vec::new();
+    for (from, to) in ranges {
+        match result.last_mut() {
+            Some(last) if from <= last.1 + 1 => last.1 = last.1.max(to),
+            _ => result.push((from, to)),
+        }
+    }
+    result
+}
+
+// a single code point or a class escape like \d inside of a character class
+enum ClassAtom {
+    Char(u32),
+    Ranges(Vec<(u32, u32)>),
+}
+
+struct Compiler {
+    chars: Vec<char>,
+    position: usize,
+    flags: i32,
+    group_count: usize,
+    mark_count: i32,
+    group_names: Vec<Option<String>>,
+}
+
+impl Compiler {
+    fn unicode(&self) -> bool {
+        self.flags & FLAG_UNICODE != 0
+    }
+
+    fn ignore_case(&self) -> bool {
+        self.flags & FLAG_IGNORE_CASE != 0
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<char> {
+        self.chars.get(self.position + offset).copied()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.position += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn disjunction(&mut self) -> Result<Vec<i32>, String> {
+        let mut alternatives = vec![self.alternative()?];
+        while self.eat('|') {
+            alternatives.push(self.alternative()?);
+        }
+
+        // a|b|c is compiled as a|(b|c)
+        let mut code = alternatives.pop().unwrap();
+        while let Some(alternative) = alternatives.pop() {
+            let mut result = vec![OP_SPLIT, 3, 3 + alternative.len() as i32 + 2];
+            result.extend(alternative);
+            result.extend([OP_JMP, 2 + code.len() as i32]);
+            result.extend(code);
+            code = result;
+        }
+        Ok(code)
+    }
+
+    fn alternative(&mut self) -> Result<Vec<i32>, String> {
+        let mut code = Vec::new();
+        while !matches!(self.peek(), None | Some('|') | Some(')')) {
+            code.extend(self.term()?);
+            if code.len() > MAX_PROGRAM_SIZE {
+                return Err("Regular expression too large".into());
+            }
+        }
+        Ok(code)
+    }
+
+    fn term(&mut self) -> Result<Vec<i32>, String> {
+        let multiline = self.flags & FLAG_MULTILINE != 0;
+        match self.peek() {
+            Some('^') => {
+                self.position += 1;
+                return Ok(vec![if multiline {
+                    OP_LINE_START_MULTILINE
+                } else {
+                    OP_LINE_START
+                }]);
+            }
+            Some('$') => {
+                self.position += 1;
+                return Ok(vec![if multiline {
+                    OP_LINE_END_MULTILINE
+                } else {
+                    OP_LINE_END
+                }]);
+            }
+            Some('\\') if self.peek_at(1) == Some('b') => {
+                self.position += 2;
+                return Ok(vec![OP_WORD_BOUNDARY]);
+            }
+            Some('\\') if self.peek_at(1) == Some('B') => {
+                self.position += 2;
+                return Ok(vec![OP_NOT_WORD_BOUNDARY]);
+            }
+            Some('(') if self.peek_at(1) == Some('?') => {
+                let lookaround = match (self.peek_at(2), self.peek_at(3)) {
+                    (Some('='), _) => Some((OP_LOOKAHEAD, 0, 3)),
+                    (Some('!'), _) => Some((OP_LOOKAHEAD, 1, 3)),
+                    (Some('<'), Some('=')) => Some((OP_LOOKBEHIND, 0, 4)),
+                    (Some('<'), Some('!')) => Some((OP_LOOKBEHIND, 1, 4)),
+                    _ => None,
+                };
+                if let Some((op, negated, prefix)) = lookaround {
+                    self.position += prefix;
+                    let body = self.disjunction()?;
+                    if !self.eat(')') {
+                        return Err("Unterminated group".into());
+                    }
+                    let mut code = vec![op, negated, 3 + body.len() as i32 + 1];
+                    code.extend(body);
+                    code.push(OP_MATCH);
+                    return Ok(code);
+                }
+            }
+            _ => {}
+        }
+
+        let first_group = self.group_count;
+        let atom = self.atom()?;
+        let last_group = self.group_count;
+        self.quantifier(atom, first_group, last_group)
+    }
+
+    // reads {n}, {n,} or {n,m}, returns None and doesn't move if there's no
+    // valid quantifier
+    fn braced_quantifier(&mut self) -> Option<(u32, Option<u32>)> {
+        let start = self.position;
+        if !self.eat('{') {
+            return None;
+        }
+        let min = self.decimal();
+        let result = match min {
+            Some(min) if self.eat('}') => Some((min, Some(min))),
+            Some(min) if self.eat(',') => {
+                if self.eat('}') {
+                    Some((min, None))
+                } else {
+                    match self.decimal() {
+                        Some(max) if self.eat('}') => Some((min, Some(max))),
+                        _ => None,
+                    }
+                }
+            }
+            _ => None,
+        };
+        if result.is_none() {
+            self.position = start;
+        }
+        result
+    }
+
+    fn decimal(&mut self) -> Option<u32> {
+        let start = self.position;
+        let mut value: u32 = 0;
+        while let Some(digit) = self.peek().and_then(|c| c.to_digit(10)) {
+            value = value.saturating_mul(10).saturating_add(digit);
+            self.position += 1;
+        }
+        (self.position > start).then_some(value)
+    }
+
+    fn quantifier(
+        &mut self,
+        atom: Vec<i32>,
+        first_group: usize,
+        last_group: usize,
+    ) -> Result<Vec<i32>, String> {
+        let (min, max) = match self.peek() {
+            Some('*') => {
+                self.position += 1;
+                (0, None)
+            }
+            Some('+') => {
+                self.position += 1;
+                (1, None)
+            }
+            Some('?') => {
+                self.position += 1;
+                (0, Some(1))
+            }
+            Some('{') => match self.braced_quantifier() {
+                Some(quantifier) => quantifier,
+                None => return Ok(atom),
+            },
+            _ => return Ok(atom),
+        };
+        if let Some(max) = max {
+            if max < min {
+                return Err("numbers out of order in {} quantifier".into());
+            }
+        }
+        let greedy = !self.eat('?');
+
+        // groups inside of a repeated atom are cleared on each iteration
+        let mut body = Vec::new();
+        if last_group > first_group {
+            body.extend([OP_RESET, 2 * first_group as i32, 2 * last_group as i32]);
+        }
+        body.extend(atom);
+
+        let mut code = Vec::new();
+        for _ in 0..min {
+            code.extend_from_slice(&body);
+            if code.len() > MAX_PROGRAM_SIZE {
+                return Err("Regular expression too large".into());
+            }
+        }
+
+        match max {
+            None => {
+                let mark = self.mark_count;
+                self.mark_count += 1;
+                let mut iteration = vec![OP_MARK, mark];
+                iteration.extend(body);
+                iteration.extend([OP_CHECK_PROGRESS, mark]);
+                let length = iteration.len() as i32;
+                if greedy {
+                    code.extend([OP_SPLIT, 3, 3 + length + 2]);
+                } else {
+                    code.extend([OP_SPLIT, 3 + length + 2, 3]);
+                }
+                code.extend(iteration);
+                code.extend([OP_JMP, -(3 + length)]);
+            }
+            Some(max) => {
+                // each optional copy wraps the next one, so they're only tried
+                // if the previous one matched
+                let mut optional: Vec<i32> = Vec::new();
+                for _ in min..max {
+                    let mut inner = body.clone();
+                    inner.extend(optional);
+                    let length = inner.len() as i32;
+                    optional = if greedy {
+                        vec![OP_SPLIT, 3, 3 + length]
+                    } else {
+                        vec![OP_SPLIT, 3 + length, 3]
+                    };
+                    optional.extend(inner);
+                    if optional.len() > MAX_PROGRAM_SIZE {
+                        return Err("Regular expression too large".into());
+                    }
+                }
+                code.extend(optional);
+            }
+        }
+        Ok(code)
+    }
+
+    fn atom(&mut self) -> Result<Vec<i32>, String> {
+        let c = self.peek().unwrap();
+        match c {
+            '.' => {
+                self.position += 1;
+                Ok(vec![if self.flags & FLAG_DOT_ALL != 0 {
+                    OP_ANY_ALL
+                } else {
+                    OP_ANY
+                }])
+            }
+            '(' => {
+                self.position += 1;
+                let index = if self.eat('?') {
+                    if self.eat(':') {
+                        None
+                    } else if self.eat('<') {
+                        let mut name = String::new();
+                        while let Some(c) = self.peek() {
+                            self.position += 1;
+                            if c == '>' {
+                                break;
+                            }
+                            name.push(c);
+                        }
+                        if name.is_empty() {
+                            return Err("Invalid capture group name".into());
+                        }
+                        Some(self.next_group())
+                    } else {
+                        return Err("Invalid group".into());
+                    }
+                } else {
+                    Some(self.next_group())
+                };
+
+                let body = self.disjunction()?;
+                if !self.eat(')') {
+                    return Err("Unterminated group".into());
+                }
+                Ok(match index {
+                    Some(index) => {
+                        let mut code = vec![OP_SAVE, 2 * index as i32];
+                        code.extend(body);
+                        code.extend([OP_SAVE, 2 * index as i32 + 1]);
+                        code
+                    }
+                    None => body,
+                })
+            }
+            '[' => {
+                self.position += 1;
+                self.class()
+            }
+            '\\' => {
+                self.position += 1;
+                self.atom_escape()
+            }
+            '*' | '+' | '?' => Err("Nothing to repeat".into()),
+            '{' if self.unicode() || self.braced_quantifier().is_some() => {
+                Err("Nothing to repeat".into())
+            }
+            ']' | '}' if self.unicode() => Err("Lone quantifier brackets".into()),
+            _ => {
+                self.position += 1;
+                Ok(self.char_code(c as u32))
+            }
+        }
+    }
+
+    fn next_group(&mut self) -> usize {
+        let index = self.group_count;
+        self.group_count += 1;
+        index
+    }
+
+    fn char_code(&self, c: u32) -> Vec<i32> {
+        if self.ignore_case() {
+            vec![OP_CHAR_I, canonicalize(c) as i32]
+        } else {
+            vec![OP_CHAR, c as i32]
+        }
+    }
+
+    fn class_code(&self, negated: bool, ranges: &[(u32, u32)]) -> Vec<i32> {
+        let op = if self.ignore_case() {
+            OP_CLASS_I
+        } else {
+            OP_CLASS
+        };
+        let mut code = vec![op, negated as i32, ranges.len() as i32];
+        for &(from, to) in ranges {
+            code.extend([from as i32, to as i32]);
+        }
+        code
+    }
+
+    fn atom_escape(&mut self) -> Result<Vec<i32>, String> {
+        let Some(c) = self.peek() else {
+            return Err("\\ at end of pattern".into());
+        };
+
+        // backreferences
+        if c.is_ascii_digit() && c != '0' {
+            let start = self.position;
+            let group = self.decimal().unwrap() as usize;
+            if group < self.group_names.len() {
+                let op = if self.ignore_case() {
+                    OP_BACKREF_I
+                } else {
+                    OP_BACKREF
+                };
+                return Ok(vec![op, group as i32]);
+            }
+            if self.unicode() {
+                return Err("Invalid escape".into());
+            }
+            // without the `u` flag it's a legacy octal escape or an identity
+            // escape for 8 and 9
+            self.position = start;
+            if c >= '8' {
+                self.position += 1;
+                return Ok(self.char_code(c as u32));
+            }
+            let value = self.legacy_octal();
+            return Ok(self.char_code(value));
+        }
+
+        if c == 'k' {
+            let has_names = self.group_names.iter().any(|name| name.is_some());
+            if has_names || self.unicode() {
+                self.position += 1;
+                if !self.eat('<') {
+                    return Err("Invalid named reference".into());
+                }
+                let mut name = String::new();
+                loop {
+                    match self.peek() {
+                        Some('>') => {
+                            self.position += 1;
+                            break;
+                        }
+                        Some(c) => {
+                            self.position += 1;
+                            name.push(c);
+                        }
+                        None => return Err("Invalid named reference".into()),
+                    }
+                }
+                let Some(group) = self
+                    .group_names
+                    .iter()
+                    .position(|group_name| group_name.as_deref() == Some(name.as_str()))
+                else {
+                    return Err("Invalid named capture referenced".into());
+                };
+                let op = if self.ignore_case() {
+                    OP_BACKREF_I
+                } else {
+                    OP_BACKREF
+                };
+                return Ok(vec![op, group as i32]);
+            }
+        }
+
+        match self.class_escape()? {
+            ClassAtom::Char(c) => Ok(self.char_code(c)),
+            ClassAtom::Ranges(ranges) => Ok(self.class_code(false, &ranges)),
+        }
+    }
+
+    fn legacy_octal(&mut self) -> u32 {
+        let mut value = 0;
+        let mut digits = 0;
+        while let Some(digit) = self.peek().and_then(|c| c.to_digit(8)) {
+            if digits == 3 || value * 8 + digit > 0o377 {
+                break;
+            }
+            value = value * 8 + digit;
+            digits += 1;
+            self.position += 1;
+        }
+        value
+    }
+
+    fn hex_digits(&mut self, count: usize) -> Option<u32> {
+        let mut value = 0;
+        for offset in 0..count {
+            value = value * 16 + self.peek_at(offset)?.to_digit(16)?;
+        }
+        self.position += count;
+        Some(value)
+    }
+
+    // \uXXXX, joining surrogate pairs, or \u{X...} with the `u` flag
+    fn unicode_escape(&mut self) -> Option<u32> {
+        if self.unicode() && self.eat('{') {
+            let mut value: u32 = 0;
+            let start = self.position;
+            while let Some(digit) = self.peek().and_then(|c| c.to_digit(16)) {
+                value = value.checked_mul(16)?.checked_add(digit)?;
+                self.position += 1;
+            }
+            if self.position == start || value > 0x10ffff || !self.eat('}') {
+                return None;
+            }
+            return Some(value);
+        }
+
+        let value = self.hex_digits(4)?;
+        if (0xd800..0xdc00).contains(&value)
+            && self.peek() == Some('\\')
+            && self.peek_at(1) == Some('u')
+        {
+            let start = self.position;
+            self.position += 2;
+            match self.hex_digits(4) {
+                Some(low) if (0xdc00..0xe000).contains(&low) => {
+                    return Some(0x10000 + ((value - 0xd800) << 10) + (low - 0xdc00));
+                }
+                _ => self.position = start,
+            }
+        }
+        Some(value)
+    }
+
+    // the escapes allowed both in and outside of character classes, the
+    // backslash is already consumed
+    fn class_escape(&mut self) -> Result<ClassAtom, String> {
+        let Some(c) = self.peek() else {
+            return Err("\\ at end of pattern".into());
+        };
+        self.position += 1;
+        let value = match c {
+            'd' => return Ok(ClassAtom::Ranges(DIGIT_RANGES.to_vec())),
+            'D' => return Ok(ClassAtom::Ranges(complement(DIGIT_RANGES))),
+            'w' => return Ok(ClassAtom::Ranges(WORD_RANGES.to_vec())),
+            'W' => return Ok(ClassAtom::Ranges(complement(WORD_RANGES))),
+            's' => return Ok(ClassAtom::Ranges(SPACE_RANGES.to_vec())),
+            'S' => return Ok(ClassAtom::Ranges(complement(SPACE_RANGES))),
+            't' => 0x09,
+            'n' => 0x0a,
+            'v' => 0x0b,
+            'f' => 0x0c,
+            'r' => 0x0d,
+            '0' if !self.peek().is_some_and(|c| c.is_ascii_digit()) => 0,
+            '0'..='7' if !self.unicode() => {
+                self.position -= 1;
+                self.legacy_octal()
+            }
+            'c' => match self.peek() {
+                Some(letter) if letter.is_ascii_alphabetic() => {
+                    self.position += 1;
+                    letter as u32 % 32
+                }
+                _ if self.unicode() => return Err("Invalid unicode escape".into()),
+                _ => {
+                    // a lone \c is a backslash followed by c
+                    self.position -= 1;
+                    '\\' as u32
+                }
+            },
+            'x' => match self.hex_digits(2) {
+                Some(value) => value,
+                None if self.unicode() => return Err("Invalid escape".into()),
+                None => 'x' as u32,
+            },
+            'u' => match self.unicode_escape() {
+                Some(value) => value,
+                None if self.unicode() => return Err("Invalid Unicode escape".into()),
+                None => 'u' as u32,
+            },
+            'p' | 'P' if self.unicode() => {
+                return Err("Unicode property escapes are not supported".into())
+            }
+            c if self.unicode() && !is_syntax_character(c) && c != '-' => {
+                return Err("Invalid escape".into())
+            }
+            c => c as u32,
+        };
+        Ok(ClassAtom::Char(value))
+    }
+
+    fn class_atom(&mut self) -> Result<ClassAtom, String> {
+        match self.peek() {
+            None => Err("Unterminated character class".into()),
+            Some('\\') => {
+                self.position += 1;
+                match self.peek() {
+                    Some('b') => {
+                        self.position += 1;
+                        Ok(ClassAtom::Char(0x08))
+                    }
+                    Some('-') if self.unicode() => {
+                        self.position += 1;
+                        Ok(ClassAtom::Char('-' as u32))
+                    }
+                    _ => self.class_escape(),
+                }
+            }
+            Some(c) => {
+                self.position += 1;
+                Ok(ClassAtom::Char(c as u32))
+            }
+        }
+    }
+
+    fn class(&mut self) -> Result<Vec<i32>, String> {
+        let negated = self.eat('^');
+        let mut ranges = Vec::new();
+        loop {
+            if self.eat(']') {
+                break;
+            }
+            let from = self.class_atom()?;
+            if self.peek() == Some('-') && !matches!(self.peek_at(1), Some(']') | None) {
+                self.position += 1;
+                let to = self.class_atom()?;
+                match (from, to) {
+                    (ClassAtom::Char(from), ClassAtom::Char(to)) => {
+                        if from > to {
+                            return Err("Range out of order in character class".into());
+                        }
+                        ranges.push((from, to));
+                    }
+                    _ if self.unicode() => return Err("Invalid character class".into()),
+                    // without the `u` flag [\d-x] is a digit, - or x
+                    (from, to) => {
+                        for atom in [from, ClassAtom::Char('-' as u32), to] {
+                            match atom {
+                                ClassAtom::Char(c) => ranges.push((c, c)),
+                                ClassAtom::Ranges(more) => ranges.extend(more),
+                            }
+                        }
+                    }
+                }
+                continue;
+            }
+            match from {
+                ClassAtom::Char(c) => ranges.push((c, c)),
+                ClassAtom::Ranges(more) => ranges.extend(more),
+            }
+        }
+        Ok(self.class_code(negated, &normalize(ranges)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(pattern: &str, flags: &str) -> String {
+        compile(pattern, flags).err().unwrap()
+    }
+
+    #[test]
+    fn flags() {
+        assert_eq!(parse_flags(""), Ok(0));
+        assert_eq!(
+            parse_flags("gimsuy"),
+            Ok(FLAG_GLOBAL
+                | FLAG_IGNORE_CASE
+                | FLAG_MULTILINE
+                | FLAG_DOT_ALL
+                | FLAG_UNICODE
+                | FLAG_STICKY)
+        );
+        assert!(parse_flags("gg").is_err());
+        assert_eq!(
+            parse_flags("x"),
+            Err("Invalid flags supplied to RegExp constructor 'x'".to_string())
+        );
+    }
+
+    #[test]
+    fn programs() {
+        let program = compile("ab", "").unwrap().program;
+        assert_eq!(
+            program,
+            [1, 0, OP_SAVE, 0, OP_CHAR, 'a' as i32, OP_CHAR, 'b' as i32, OP_SAVE, 1, OP_MATCH]
+        );
+        // jumps are relative to the opcode
+        let program = compile("a|b", "").unwrap().program;
+        assert_eq!(
+            program,
+            [
+                1, 0, OP_SAVE, 0, OP_SPLIT, 3, 7, OP_CHAR, 'a' as i32, OP_JMP, 4, OP_CHAR,
+                'b' as i32, OP_SAVE, 1, OP_MATCH
+            ]
+        );
+        let program = compile("a", "i").unwrap().program;
+        assert_eq!(
+            program,
+            [1, 0, OP_SAVE, 0, OP_CHAR_I, 'A' as i32, OP_SAVE, 1, OP_MATCH]
+        );
+        // a loop that can match nothing needs a register to stop it
+        assert_eq!(compile("(?:)*", "").unwrap().program[1], 1);
+    }
+
+    #[test]
+    fn groups() {
+        let compiled = compile("(a)(?<year>b)(?:c)(?=d)", "").unwrap();
+        assert_eq!(compiled.program[0], 3);
+        assert_eq!(compiled.group_names, [None, None, Some("year".to_string())]);
+        // backreferences can come before the group
+        let program = compile("\\1(a)", "").unwrap().program;
+        assert_eq!(&program[4..6], [OP_BACKREF, 1]);
+        // without named groups \k is an identity escape
+        assert!(compile("\\k<m>", "").is_ok());
+        assert_eq!(
+            error("(?<n>a)\\k<m>", ""),
+            "Invalid named capture referenced"
+        );
+    }
+
+    #[test]
+    fn syntax_errors() {
+        assert_eq!(error("(a", ""), "Unterminated group");
+        assert_eq!(error("a)", ""), "Unmatched ')'");
+        assert_eq!(error("[b-a]", ""), "Range out of order in character class");
+        assert_eq!(error("a**", ""), "Nothing to repeat");
+        assert_eq!(error("a{2,1}", ""), "numbers out of order in {} quantifier");
+        // a lone brace is only a syntax character with the `u` flag
+        assert!(compile("{", "").is_ok());
+        assert_eq!(error("{", "u"), "Nothing to repeat");
+        assert_eq!(
+            error("\\p{L}", "u"),
+            "Unicode property escapes are not supported"
+        );
+    }
+
+    #[test]
+    fn case_folding() {
+        assert_eq!(canonicalize('a' as u32), 'A' as u32);
+        assert_eq!(canonicalize('A' as u32), 'A' as u32);
+        assert_eq!(canonicalize('1' as u32), '1' as u32);
+        assert_eq!(canonicalize('é' as u32), 'É' as u32);
+        assert_eq!(canonicalize('÷' as u32), '÷' as u32);
+        assert_eq!(canonicalize('ÿ' as u32), 'Ÿ' as u32);
+        assert_eq!(canonicalize('ς' as u32), 'Σ' as u32);
+        assert_eq!(canonicalize('ё' as u32), 'Ё' as u32);
+        assert_eq!(canonicalize('я' as u32), 'Я' as u32);
+    }
+
+    #[test]
+    fn ranges() {
+        assert_eq!(
+            normalize(vec![(5, 7), (1, 3), (4, 4), (10, 12)]),
+            [(1, 7), (10, 12)]
+        );
+        assert_eq!(complement(&[(0, 9), (20, 0x10ffff)]), [(10, 19)]);
+        assert_eq!(complement(&[]), [(0, 0x10ffff)]);
+    }
+}
@@ -0,0 +1,416 @@
+//! Scope analysis, which allows resolving variables at compile time instead of
+//! looking them up by name in the `$Scope` hash maps.
+//!
+//! For a single function it finds the names declared in the function itself
+//! and in each of its blocks, catch clauses and `for` loops, along with the
+//! bindings that are captured by nested functions. The translator keeps the
+//! bindings that are not captured in WASM locals and stores the captured ones
+//! in a fixed size array of slots on a `$Scope`, where closures can reach them
+//! by a (depth, slot) pair. Captured bindings of the function body live on the
+//! function's `$Scope`, while every other scope gets a new one each time it's
+//! entered, so that closures created in different iterations of a loop see
+//! different bindings.
+//!
+//! A function using `eval` or `with` can access its variables (and the
+//! variables of all of the functions enclosing it) by names known only at
+//! runtime, so such functions are marked as dynamic and keep using the hash
+//! maps.
+//!
+//! The top level of a script is special: `var` and function declarations
+//! there are properties of the global object, so they are always looked up
+//! by name. Only `let`, `const` and `class` declarations get slots or locals.
+use boa_ast::{
+    declaration::{Declaration, LexicalDeclaration},
+    expression::Identifier,
+    function::{
+        ArrowFunction, AsyncArrowFunction, AsyncFunction, AsyncGenerator, Class,
+        FormalParameterList, Function, FunctionBody, Generator,
+    },
+    operations::{bound_names, lexically_declared_names, var_declared_names},
+    statement::{iteration::ForLoopInitializer, Block, Catch, ForLoop, With},
+    visitor::{VisitWith, Visitor},
+    StatementList, StatementListItem,
+};
+use boa_interner::Sym;
+use std::{
+    collections::{HashMap, HashSet},
+    ops::ControlFlow,
+};
+
+// An address of the AST node creating a scope: a function body, a block, a
+// catch clause or a `for` loop. It's stable as long as the AST is not modified
+pub type ScopeId = usize;
+
+pub fn scope_id<T>(node: &T) -> ScopeId {
+    node as *const T as usize
+}
+
+#[derive(Debug, Default)]
+pub struct FunctionScopes {
+    // the scope of the function body
+    root: ScopeId,
+    // names declared in each scope of the function and whether they're constant
+    declarations: HashMap<ScopeId, Vec<(Sym, bool)>>,
+    captured: HashSet<(ScopeId, Sym)>,
+    // bindings living on the global object
+    globals: HashSet<(ScopeId, Sym)>,
+    dynamic: bool,
+}
+
+impl FunctionScopes {
+    pub fn analyze(params: Option<&FormalParameterList>, body: &FunctionBody) -> Self {
+        let mut scan = ScopeScan::default();
+        scan.result.root = scope_id(body);
+        scan.function(params, body);
+        scan.result
+    }
+
+    pub fn analyze_script(script: &FunctionBody) -> Self {
+        let mut result = Self::analyze(None, script);
+        let id = scope_id(script);
+        result.globals = var_declared_names(script)
+            .into_iter()
+            .map(|identifier| (id, identifier.sym()))
+            .collect();
+        let globals = &result.globals;
+        result.captured.retain(|binding| !globals.contains(binding));
+        result
+    }
+
+    pub fn declarations(&self, scope: ScopeId) -> &[(Sym, bool)] {
+        self.declarations
+            .get(&scope)
+            .map(|names| names.as_slice())
+            .unwrap_or_default()
+    }
+
+    pub fn is_captured(&self, scope: ScopeId, sym: Sym) -> bool {
+        self.captured.contains(&(scope, sym))
+    }
+
+    pub fn is_global(&self, scope: ScopeId, sym: Sym) -> bool {
+        self.globals.contains(&(scope, sym))
+    }
+
+    pub fn root(&self) -> ScopeId {
+        self.root
+    }
+
+    // the number of slots needed for the captured bindings of a scope
+    pub fn captured_count(&self, scope: ScopeId) -> usize {
+        self.captured.iter().filter(|(id, _)| *id == scope).count()
+    }
+
+    pub fn is_dynamic(&self) -> bool {
+        self.dynamic
+    }
+}
+
+fn constants(statements: &StatementList) -> HashSet<Sym> {
+    statements
+        .statements()
+        .iter()
+        .filter_map(|item| match item {
+            StatementListItem::Declaration(Declaration::Lexical(
+                declaration @ LexicalDeclaration::Const(_),
+            )) => Some(bound_names(declaration)),
+            _ => None,
+        })
+        .flatten()
+        .map(|identifier| identifier.sym())
+        .collect()
+}
+
+// removes duplicates, keeping the first occurrence, eg. a `var` with the same
+// name as a parameter is the same binding
+fn unique(
+    names: impl IntoIterator<Item = Identifier>,
+    constants: &HashSet<Sym>,
+) -> Vec<(Sym, bool)> {
+    let mut seen = HashSet::new();
+    names
+        .into_iter()
+        .map(|identifier| identifier.sym())
+        .filter(|sym| seen.insert(*sym))
+        .map(|sym| (sym, constants.contains(&sym)))
+        .collect()
+}
+
+#[derive(Default)]
+struct ScopeScan {
+    // scopes enclosing the visited node, with the nesting level of the
+    // function they belong to, the analyzed function being level 0
+    scopes: Vec<(usize, ScopeId, HashSet<Sym>)>,
+    level: usize,
+    result: FunctionScopes,
+}
+
+impl ScopeScan {
+    fn enter_scope(&mut self, id: ScopeId, names: Vec<(Sym, bool)>) {
+        let syms = names.iter().map(|(sym, _)| *sym).collect();
+        if self.level == 0 {
+            self.result.declarations.insert(id, names);
+        }
+        self.scopes.push((self.level, id, syms));
+    }
+
+    fn exit_scope(&mut self) {
+        self.scopes.pop();
+    }
+
+    fn function(&mut self, params: Option<&FormalParameterList>, body: &FunctionBody) {
+        let mut vars: Vec<_> = var_declared_names(body).into_iter().collect();
+        vars.sort_by_key(|identifier| identifier.sym().get());
+        let names = params
+            .map(bound_names)
+            .unwrap_or_default()
+            .into_iter()
+            .chain(vars)
+            .chain(lexically_declared_names(body));
+        let names = unique(names, &constants(body.statements()));
+
+        self.enter_scope(scope_id(body), names);
+        if let Some(params) = params {
+            let _ = params.visit_with(self);
+        }
+        let _ = body.visit_with(self);
+        self.exit_scope();
+    }
+
+    fn nested_function(&mut self, params: &FormalParameterList, body: &FunctionBody) {
+        self.level += 1;
+        self.function(Some(params), body);
+        self.level -= 1;
+    }
+}
+
+impl<'a> Visitor<'a> for ScopeScan {
+    type BreakTy = ();
+
+    fn visit_identifier(&mut self, node: &'a Identifier) -> ControlFlow<Self::BreakTy> {
+        let sym = node.sym();
+        if sym == Sym::EVAL {
+            self.result.dynamic = true;
+        }
+        if let Some((level, id, _)) = self
+            .scopes
+            .iter()
+            .rev()
+            .find(|(_, _, names)| names.contains(&sym))
+        {
+            if *level == 0 && self.level > 0 {
+                self.result.captured.insert((*id, sym));
+            }
+        }
+        ControlFlow::Continue(())
+    }
+
+    fn visit_block(&mut self, node: &'a Block) -> ControlFlow<Self::BreakTy> {
+        let names = unique(
+            lexically_declared_names(node),
+            &constants(node.statement_list()),
+        );
+        self.enter_scope(scope_id(node), names);
+        node.visit_with(self)?;
+        self.exit_scope();
+        ControlFlow::Continue(())
+    }
+
+    fn visit_catch(&mut self, node: &'a Catch) -> ControlFlow<Self::BreakTy> {
+        let names = node
+            .parameter()
+            .map(|parameter| unique(bound_names(parameter), &HashSet::new()))
+            .unwrap_or_default();
+        self.enter_scope(scope_id(node), names);
+        node.visit_with(self)?;
+        self.exit_scope();
+        ControlFlow::Continue(())
+    }
+
+    // `let` and `const` declared in the initializer are scoped to the loop
+    fn visit_for_loop(&mut self, node: &'a ForLoop) -> ControlFlow<Self::BreakTy> {
+        let names = match node.init() {
+            Some(ForLoopInitializer::Lexical(declaration)) => {
+                let constant = matches!(declaration, LexicalDeclaration::Const(_));
+                unique(bound_names(declaration), &HashSet::new())
+                    .into_iter()
+                    .map(|(sym, _)| (sym, constant))
+                    .collect()
+            }
+            _ => Vec::new(),
+        };
+        self.enter_scope(scope_id(node), names);
+        node.visit_with(self)?;
+        self.exit_scope();
+        ControlFlow::Continue(())
+    }
+
+    fn visit_with(&mut self, node: &'a With) -> ControlFlow<Self::BreakTy> {
+        self.result.dynamic = true;
+        node.visit_with(self)
+    }
+
+    fn visit_function(&mut self, node: &'a Function) -> ControlFlow<Self::BreakTy> {
+        self.nested_function(node.parameters(), node.body());
+        ControlFlow::Continue(())
+    }
+
+    fn visit_arrow_function(&mut self, node: &'a ArrowFunction) -> ControlFlow<Self::BreakTy> {
+        self.nested_function(node.parameters(), node.body());
+        ControlFlow::Continue(())
+    }
+
+    fn visit_async_function(&mut self, node: &'a AsyncFunction) -> ControlFlow<Self::BreakTy> {
+        self.nested_function(node.parameters(), node.body());
+        ControlFlow::Continue(())
+    }
+
+    fn visit_async_arrow_function(
+        &mut self,
+        node: &'a AsyncArrowFunction,
+    ) -> ControlFlow<Self::BreakTy> {
+        self.nested_function(node.parameters(), node.body());
+        ControlFlow::Continue(())
+    }
+
+    fn visit_generator(&mut self, node: &'a Generator) -> ControlFlow<Self::BreakTy> {
+        self.nested_function(node.parameters(), node.body());
+        ControlFlow::Continue(())
+    }
+
+    fn visit_async_generator(&mut self, node: &'a AsyncGenerator) -> ControlFlow<Self::BreakTy> {
+        self.nested_function(node.parameters(), node.body());
+        ControlFlow::Continue(())
+    }
+
+    // classes don't get their own scopes, so every name mentioned in them is
+    // treated as captured
+    fn visit_class(&mut self, node: &'a Class) -> ControlFlow<Self::BreakTy> {
+        self.level += 1;
+        node.visit_with(self)?;
+        self.level -= 1;
+        ControlFlow::Continue(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use boa_ast::{Script, Statement};
+    use boa_interner::{Interner, JStrRef};
+    use boa_parser::{Parser, Source};
+
+    fn parse(source: &str) -> (Script, Interner) {
+        let mut interner = Interner::default();
+        let script = Parser::new(Source::from_bytes(source))
+            .parse_script(&mut interner)
+            .unwrap();
+        (script, interner)
+    }
+
+    fn sym(interner: &Interner, name: &str) -> Sym {
+        interner.get(JStrRef::Utf8(name)).unwrap()
+    }
+
+    fn statement(body: &FunctionBody, index: usize) -> &Statement {
+        match &body.statements().statements()[index] {
+            StatementListItem::Statement(statement) => statement,
+            _ => panic!("not a statement"),
+        }
+    }
+
+    fn function(body: &FunctionBody, index: usize) -> &Function {
+        match &body.statements().statements()[index] {
+            StatementListItem::Declaration(Declaration::Function(function)) => function,
+            _ => panic!("not a function declaration"),
+        }
+    }
+
+    #[test]
+    fn top_level_vars_are_globals() {
+        let (script, interner) =
+            parse("let a = 1; let b = 2; var c = 3; function f() { return a + c; }");
+        let scopes = FunctionScopes::analyze_script(&script);
+        let root = scopes.root();
+        assert!(scopes.is_captured(root, sym(&interner, "a")));
+        assert!(!scopes.is_captured(root, sym(&interner, "b")));
+        assert!(!scopes.is_captured(root, sym(&interner, "c")));
+        assert!(scopes.is_global(root, sym(&interner, "c")));
+        assert!(scopes.is_global(root, sym(&interner, "f")));
+        assert!(!scopes.is_global(root, sym(&interner, "a")));
+        assert_eq!(scopes.captured_count(root), 1);
+    }
+
+    #[test]
+    fn constants_are_marked() {
+        let (script, interner) = parse("const a = 1; let b = 2;");
+        let scopes = FunctionScopes::analyze_script(&script);
+        let declarations = scopes.declarations(scopes.root());
+        assert!(declarations.contains(&(sym(&interner, "a"), true)));
+        assert!(declarations.contains(&(sym(&interner, "b"), false)));
+    }
+
+    #[test]
+    fn parameters_and_vars_with_the_same_name_are_one_binding() {
+        let (script, interner) = parse("function f(a) { var a; var b; return () => b; }");
+        let f = function(&script, 0);
+        let scopes = FunctionScopes::analyze(Some(f.parameters()), f.body());
+        let root = scopes.root();
+        assert_eq!(root, scope_id(f.body()));
+        assert_eq!(
+            scopes.declarations(root),
+            [(sym(&interner, "a"), false), (sym(&interner, "b"), false)]
+        );
+        assert!(scopes.is_captured(root, sym(&interner, "b")));
+        assert!(!scopes.is_captured(root, sym(&interner, "a")));
+    }
+
+    #[test]
+    fn loops_and_blocks_get_their_own_scopes() {
+        let (script, interner) = parse(
+            "let fs = []; for (let i = 0; i < 3; i++) { let x = i; let y = x; fs[i] = () => x + i; }",
+        );
+        let scopes = FunctionScopes::analyze_script(&script);
+        let Statement::ForLoop(for_loop) = statement(&script, 1) else {
+            panic!("not a for loop");
+        };
+        let Statement::Block(block) = for_loop.body() else {
+            panic!("not a block");
+        };
+        let (i, x, y) = (
+            sym(&interner, "i"),
+            sym(&interner, "x"),
+            sym(&interner, "y"),
+        );
+        assert_eq!(scopes.declarations(scope_id(for_loop)), [(i, false)]);
+        assert!(scopes.is_captured(scope_id(for_loop), i));
+        assert!(scopes.is_captured(scope_id(block), x));
+        assert!(!scopes.is_captured(scope_id(block), y));
+        assert_eq!(scopes.captured_count(scopes.root()), 0);
+        assert_eq!(scopes.captured_count(scope_id(for_loop)), 1);
+        assert_eq!(scopes.captured_count(scope_id(block)), 1);
+    }
+
+    #[test]
+    fn only_bindings_of_the_analyzed_function_are_recorded() {
+        let (script, interner) =
+            parse("function f() { let a = 1; function g() { let b = a; return () => b; } }");
+        let f = function(&script, 0);
+        let scopes = FunctionScopes::analyze(Some(f.parameters()), f.body());
+        let g = function(f.body(), 1);
+        assert!(scopes.is_captured(scopes.root(), sym(&interner, "a")));
+        assert!(scopes.declarations(scope_id(g.body())).is_empty());
+        assert_eq!(scopes.captured_count(scope_id(g.body())), 0);
+    }
+
+    #[test]
+    fn eval_and_with_make_functions_dynamic() {
+        let (script, _) =
+            parse("function f() { eval('1'); } function g(o) { with (o) { x; } } function h() {}");
+        for (index, dynamic) in [(0, true), (1, true), (2, false)] {
+            let function = function(&script, index);
+            let scopes = FunctionScopes::analyze(Some(function.parameters()), function.body());
+            assert_eq!(scopes.is_dynamic(), dynamic);
+        }
+    }
+}
@@ -0,0 +1,953 @@
+//! A static type inference pass over the AST. It finds variables that only
+//! ever hold numbers or booleans, so the translator can keep them in plain
+//! `f64`/`i32` locals instead of boxing them and storing them on the `$Scope`.
+//!
+//! A variable is a candidate for unboxing if:
+//!   * it's a parameter, it's declared with an initializer directly in the
+//!     function body or it's a `let`/`const` with an initializer in a nested
+//!     block or a `for` loop initializer, top-level `var`s of the script
+//!     don't count as they live on the global object
+//!   * it's declared only once in the function and it's not used before the
+//!     declaration or outside the block declaring it
+//!   * no nested function mentions its name, so it can't be captured
+//!   * the function doesn't use `eval`, `arguments` or `with`
+//!
+//! Parameters can only be typed for functions that are declared with a
+//! function declaration and are only ever called directly by name, as then
+//! all of the call sites are known. The same applies to return types. That
+//! rules out functions declared at the top level of a script, which can also
+//! be called through the global object.
+//!
+//! Types are found by iterating to a fixed point, starting with the optimistic
+//! assumption that nothing has been assigned yet, which allows recursive
+//! functions and loops to be typed.
+use boa_ast::{
+    declaration::{Binding, Declaration, LexicalDeclaration, Variable, VariableList},
+    expression::{
+        literal::Literal,
+        operator::{
+            assign::{AssignOp, AssignTarget},
+            binary::{BinaryOp, LogicalOp},
+            unary::UnaryOp,
+            update::UpdateTarget,
+            Assign, Update,
+        },
+        Call, Expression, Identifier, New,
+    },
+    function::{
+        ArrowFunction, AsyncArrowFunction, AsyncFunction, AsyncGenerator, Class,
+        FormalParameterList, Function, FunctionBody, Generator,
+    },
+    pattern::Pattern,
+    statement::{
+        iteration::{ForLoop, ForLoopInitializer, IterableLoopInitializer},
+        Return, Statement, With,
+    },
+    visitor::{VisitWith, Visitor},
+    Script, StatementList, StatementListItem,
+};
+use boa_interner::Sym;
+use std::{
+    collections::{HashMap, HashSet},
+    ops::ControlFlow,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValueType {
+    // nothing has been assigned yet, only used while searching for the fixed point
+    Unset,
+    Number,
+    Boolean,
+    Any,
+}
+
+impl ValueType {
+    fn join(self, other: ValueType) -> ValueType {
+        match (self, other) {
+            (ValueType::Unset, t) | (t, ValueType::Unset) => t,
+            (a, b) if a == b => a,
+            _ => ValueType::Any,
+        }
+    }
+
+    // the type of an operation that gives `result` if both operands are `expected`
+    fn combine(a: ValueType, b: ValueType, expected: ValueType, result: ValueType) -> ValueType {
+        if a == ValueType::Any || b == ValueType::Any {
+            ValueType::Any
+        } else if a == ValueType::Unset || b == ValueType::Unset {
+            ValueType::Unset
+        } else if a == expected && b == expected {
+            result
+        } else {
+            ValueType::Any
+        }
+    }
+}
+
+/// Functions are identified by the address of their body in the AST, which
+/// doesn't move between the analysis and the translation
+pub type FunctionId = usize;
+
+pub fn function_id(body: &FunctionBody) -> FunctionId {
+    body as *const FunctionBody as usize
+}
+
+#[derive(Debug, Default)]
+pub struct TypeInfo {
+    // types of variables that can be unboxed, for each of the functions
+    variables: HashMap<FunctionId, HashMap<Sym, ValueType>>,
+    // return types of functions that are only called directly by name
+    returns: HashMap<Sym, ValueType>,
+}
+
+impl TypeInfo {
+    pub fn analyze(script: &Script) -> TypeInfo {
+        let mut scans = Vec::new();
+        let mut pending = vec![(None, None, script)];
+        while let Some((name, params, body)) = pending.pop() {
+            let mut scan = FunctionScan::new(name, body);
+            scan.scan(params, body);
+            pending.append(&mut scan.nested);
+            scans.push(scan);
+        }
+
+        // a function is known if its name is bound only once in the whole
+        // program, by a function declaration, and it's never used as a value
+        let mut bindings: HashMap<Sym, usize> = HashMap::new();
+        let mut uses: HashSet<Sym> = HashSet::new();
+        for scan in &scans {
+            for (sym, count) in &scan.bindings {
+                *bindings.entry(*sym).or_default() += count;
+            }
+            uses.extend(&scan.uses);
+        }
+        // functions declared at the top level of the script are also
+        // properties of the global object, so they can be called through it
+        // with any arguments
+        let globals: HashSet<Sym> = script
+            .statements()
+            .statements()
+            .iter()
+            .filter_map(|statement| match statement {
+                StatementListItem::Declaration(Declaration::Function(function)) => {
+                    function.name().map(|name| name.sym())
+                }
+                _ => None,
+            })
+            .collect();
+        let known: HashMap<Sym, usize> = scans
+            .iter()
+            .enumerate()
+            .filter_map(|(i, scan)| scan.name.map(|name| (name, i)))
+            .filter(|(name, _)| {
+                bindings.get(name) == Some(&1) && !uses.contains(name) && !globals.contains(name)
+            })
+            .collect();
+
+        let mut info = TypeInfo::default();
+        for scan in &scans {
+            let candidates = scan.candidates();
+            info.variables.insert(
+                scan.id,
+                candidates
+                    .iter()
+                    .map(|sym| (*sym, ValueType::Unset))
+                    .collect(),
+            );
+        }
+        for name in known.keys() {
+            info.returns.insert(*name, ValueType::Unset);
+        }
+        // parameters of functions that can be called from unknown places can hold anything
+        for scan in &scans {
+            let is_known = scan.name.is_some_and(|name| known.contains_key(&name));
+            if !is_known {
+                for param in scan.params.iter().flatten() {
+                    info.join_variable(scan.id, *param, ValueType::Any);
+                }
+            }
+        }
+
+        loop {
+            let mut changed = false;
+
+            for scan in &scans {
+                for (sym, assignment) in &scan.assignments {
+                    let value_type = match assignment {
+                        Assignment::Value(expression) => info.expression_type(scan.id, expression),
+                        Assignment::Add(expression) => ValueType::combine(
+                            info.variable_type(scan.id, *sym),
+                            info.expression_type(scan.id, expression),
+                            ValueType::Number,
+                            ValueType::Number,
+                        ),
+                        Assignment::Update => ValueType::combine(
+                            info.variable_type(scan.id, *sym),
+                            ValueType::Number,
+                            ValueType::Number,
+                            ValueType::Number,
+                        ),
+                        Assignment::Unknown => ValueType::Any,
+                    };
+                    changed |= info.join_variable(scan.id, *sym, value_type);
+                }
+            }
+
+            for (name, index) in &known {
+                let function = &scans[*index];
+                for caller in &scans {
+                    for (callee, args) in &caller.calls {
+                        if callee != name {
+                            continue;
+                        }
+                        let has_spread = args.iter().any(|a| matches!(a, Expression::Spread(_)));
+                        for (i, param) in function.params.iter().enumerate() {
+                            let Some(param) = param else { continue };
+                            let value_type = match args.get(i) {
+                                Some(arg) if !has_spread => info.expression_type(caller.id, arg),
+                                _ => ValueType::Any,
+                            };
+                            changed |= info.join_variable(function.id, *param, value_type);
+                        }
+                    }
+                }
+
+                let mut return_type = if function.can_complete {
+                    ValueType::Any
+                } else {
+                    ValueType::Unset
+                };
+                for ret in &function.returns {
+                    return_type = return_type.join(match ret {
+                        Some(expression) => info.expression_type(function.id, expression),
+                        None => ValueType::Any,
+                    });
+                }
+                let previous = info.returns[name];
+                if previous.join(return_type) != previous {
+                    info.returns.insert(*name, previous.join(return_type));
+                    changed = true;
+                }
+            }
+
+            if !changed {
+                break;
+            }
+        }
+
+        let unboxed = |t: &ValueType| matches!(t, ValueType::Number | ValueType::Boolean);
+        for variables in info.variables.values_mut() {
+            variables.retain(|_, t| unboxed(t));
+        }
+        info.returns.retain(|_, t| unboxed(t));
+        info
+    }
+
+    // returns true if the type has changed
+    fn join_variable(&mut self, function: FunctionId, sym: Sym, value_type: ValueType) -> bool {
+        if let Some(current) = self
+            .variables
+            .get_mut(&function)
+            .and_then(|v| v.get_mut(&sym))
+        {
+            let joined = current.join(value_type);
+            if joined != *current {
+                *current = joined;
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Returns variables of the function that are kept unboxed
+    pub fn unboxed_variables(&self, function: FunctionId) -> Vec<(Sym, ValueType)> {
+        let mut variables: Vec<(Sym, ValueType)> = self
+            .variables
+            .get(&function)
+            .map(|v| v.iter().map(|(sym, t)| (*sym, *t)).collect())
+            .unwrap_or_default();
+        variables.sort_by_key(|(sym, _)| sym.get());
+        variables
+    }
+
+    pub fn variable_type(&self, function: FunctionId, sym: Sym) -> ValueType {
+        self.variables
+            .get(&function)
+            .and_then(|v| v.get(&sym))
+            .copied()
+            .unwrap_or(ValueType::Any)
+    }
+
+    pub fn expression_type(&self, function: FunctionId, expression: &Expression) -> ValueType {
+        match expression {
+            Expression::Literal(Literal::Num(_) | Literal::Int(_)) => ValueType::Number,
+            Expression::Literal(Literal::Bool(_)) => ValueType::Boolean,
+            Expression::Identifier(identifier) => self.variable_type(function, identifier.sym()),
+            Expression::Parenthesized(parenthesized) => {
+                self.expression_type(function, parenthesized.expression())
+            }
+            Expression::Unary(unary) => match unary.op() {
+                UnaryOp::Minus => ValueType::combine(
+                    self.expression_type(function, unary.target()),
+                    ValueType::Number,
+                    ValueType::Number,
+                    ValueType::Number,
+                ),
+                UnaryOp::Plus => ValueType::Number,
+                UnaryOp::Not => ValueType::Boolean,
+                _ => ValueType::Any,
+            },
+            Expression::Binary(binary) => match binary.op() {
+                BinaryOp::Arithmetic(_) => ValueType::combine(
+                    self.expression_type(function, binary.lhs()),
+                    self.expression_type(function, binary.rhs()),
+                    ValueType::Number,
+                    ValueType::Number,
+                ),
+                BinaryOp::Relational(_) => ValueType::Boolean,
+                BinaryOp::Logical(LogicalOp::And | LogicalOp::Or) => ValueType::combine(
+                    self.expression_type(function, binary.lhs()),
+                    self.expression_type(function, binary.rhs()),
+                    ValueType::Boolean,
+                    ValueType::Boolean,
+                ),
+                _ => ValueType::Any,
+            },
+            Expression::Call(call) => match call.function() {
+                Expression::Identifier(identifier) => self
+                    .returns
+                    .get(&identifier.sym())
+                    .copied()
+                    .unwrap_or(ValueType::Any),
+                _ => ValueType::Any,
+            },
+            _ => ValueType::Any,
+        }
+    }
+}
+
+enum Assignment<'a> {
+    Value(&'a Expression),
+    Add(&'a Expression),
+    Update,
+    Unknown,
+}
+
+type NestedFunction<'a> = (
+    Option<Sym>,
+    Option<&'a FormalParameterList>,
+    &'a FunctionBody,
+);
+
+// Collects everything the inference needs from a single function body,
+// without descending into nested functions
+struct FunctionScan<'a> {
+    id: FunctionId,
+    // set for function declarations, which may be callable only by name
+    name: Option<Sym>,
+    // simple parameters, `None` for destructuring, rest or default parameters
+    params: Vec<Option<Sym>>,
+    // how many times a name is bound in this function
+    bindings: HashMap<Sym, usize>,
+    // names used in any other way than a direct call
+    uses: HashSet<Sym>,
+    // names declared with an initializer, outside of nested `var`s
+    initialized: HashSet<Sym>,
+    // names that can be referenced at the current point of the scan
+    declared: HashSet<Sym>,
+    constants: HashSet<Sym>,
+    excluded: HashSet<Sym>,
+    assignments: Vec<(Sym, Assignment<'a>)>,
+    returns: Vec<Option<&'a Expression>>,
+    calls: Vec<(Sym, &'a [Expression])>,
+    can_complete: bool,
+    dynamic: bool,
+    nested: Vec<NestedFunction<'a>>,
+}
+
+impl<'a> FunctionScan<'a> {
+    fn new(name: Option<Sym>, body: &'a FunctionBody) -> Self {
+        Self {
+            id: function_id(body),
+            name,
+            params: Vec::new(),
+            bindings: HashMap::new(),
+            uses: HashSet::new(),
+            initialized: HashSet::new(),
+            declared: HashSet::new(),
+            constants: HashSet::new(),
+            excluded: HashSet::new(),
+            assignments: Vec::new(),
+            returns: Vec::new(),
+            calls: Vec::new(),
+            can_complete: true,
+            dynamic: false,
+            nested: Vec::new(),
+        }
+    }
+
+    fn scan(&mut self, params: Option<&'a FormalParameterList>, body: &'a FunctionBody) {
+        for param in params.map(|p| p.as_ref()).unwrap_or_default() {
+            match param.variable().binding() {
+                Binding::Identifier(identifier)
+                    if param.init().is_none() && !param.is_rest_param() =>
+                {
+                    self.bind(identifier.sym());
+                    self.declared.insert(identifier.sym());
+                    self.params.push(Some(identifier.sym()));
+                }
+                _ => {
+                    let _ = param.visit_with(self);
+                    self.params.push(None);
+                }
+            }
+        }
+
+        let statements = body.statements().statements();
+        for statement in statements {
+            match statement {
+                // `var`s at the top level of the script are properties of the
+                // global object, so they can't be unboxed
+                StatementListItem::Statement(Statement::Var(var)) if params.is_some() => {
+                    self.scan_declarations(&var.0, false);
+                }
+                StatementListItem::Declaration(Declaration::Lexical(lexical)) => {
+                    self.scan_lexical_declaration(lexical);
+                }
+                _ => {
+                    let _ = statement.visit_with(self);
+                }
+            }
+        }
+
+        self.can_complete = !matches!(
+            statements.last(),
+            Some(StatementListItem::Statement(Statement::Return(_)))
+        );
+    }
+
+    // returns the names that can be unboxed
+    fn scan_declarations(&mut self, list: &'a VariableList, constant: bool) -> Vec<Sym> {
+        let mut names = Vec::new();
+        for variable in list.as_ref() {
+            match (variable.binding(), variable.init()) {
+                (Binding::Identifier(identifier), Some(init)) => {
+                    let _ = init.visit_with(self);
+                    let sym = identifier.sym();
+                    self.bind(sym);
+                    self.declared.insert(sym);
+                    self.initialized.insert(sym);
+                    if constant {
+                        self.constants.insert(sym);
+                    }
+                    self.assignments.push((sym, Assignment::Value(init)));
+                    names.push(sym);
+                }
+                _ => {
+                    let _ = self.visit_variable(variable);
+                }
+            }
+        }
+        names
+    }
+
+    fn scan_lexical_declaration(&mut self, lexical: &'a LexicalDeclaration) -> Vec<Sym> {
+        self.scan_declarations(
+            lexical.variable_list(),
+            matches!(lexical, LexicalDeclaration::Const(_)),
+        )
+    }
+
+    // names declared in a block go out of scope at its end, so any later
+    // reference is to another binding and excludes them
+    fn exit_block(&mut self, names: Vec<Sym>) {
+        for sym in names {
+            self.declared.remove(&sym);
+        }
+    }
+
+    fn candidates(&self) -> HashSet<Sym> {
+        if self.dynamic {
+            return HashSet::new();
+        }
+        self.initialized
+            .iter()
+            .chain(self.params.iter().flatten())
+            .filter(|sym| self.bindings.get(sym) == Some(&1) && !self.excluded.contains(sym))
+            .copied()
+            .collect()
+    }
+
+    fn bind(&mut self, sym: Sym) {
+        *self.bindings.entry(sym).or_default() += 1;
+    }
+
+    fn reference(&mut self, sym: Sym) {
+        if sym == Sym::ARGUMENTS || sym == Sym::EVAL {
+            self.dynamic = true;
+        }
+        if !self.declared.contains(&sym) {
+            self.excluded.insert(sym);
+        }
+    }
+
+    fn assign(&mut self, sym: Sym, assignment: Assignment<'a>) {
+        self.reference(sym);
+        self.uses.insert(sym);
+        if self.constants.contains(&sym) {
+            self.excluded.insert(sym);
+        }
+        self.assignments.push((sym, assignment));
+    }
+
+    // nothing in a nested function can be unboxed in this one and names
+    // mentioned there can't be typed as known functions
+    fn exclude_all_names<N: VisitWith>(&mut self, node: &N) {
+        let mut collector = NameCollector::default();
+        let _ = node.visit_with(&mut collector);
+        self.excluded.extend(&collector.names);
+        self.uses.extend(&collector.names);
+    }
+
+    fn nested_function(
+        &mut self,
+        name: Option<Identifier>,
+        params: &'a FormalParameterList,
+        body: &'a FunctionBody,
+        declaration: bool,
+    ) {
+        let mut collector = NameCollector::default();
+        let _ = params.visit_with(&mut collector);
+        let _ = body.visit_with(&mut collector);
+        self.excluded.extend(&collector.names);
+        if let Some(name) = name {
+            self.bind(name.sym());
+            self.excluded.insert(name.sym());
+        }
+        self.nested.push((
+            name.filter(|_| declaration).map(|n| n.sym()),
+            Some(params),
+            body,
+        ));
+    }
+}
+
+impl<'a> Visitor<'a> for FunctionScan<'a> {
+    type BreakTy = ();
+
+    fn visit_identifier(&mut self, node: &'a Identifier) -> ControlFlow<Self::BreakTy> {
+        self.reference(node.sym());
+        self.uses.insert(node.sym());
+        ControlFlow::Continue(())
+    }
+
+    fn visit_binding(&mut self, node: &'a Binding) -> ControlFlow<Self::BreakTy> {
+        match node {
+            Binding::Identifier(identifier) => {
+                self.bind(identifier.sym());
+                self.excluded.insert(identifier.sym());
+            }
+            Binding::Pattern(pattern) => self.visit_pattern(pattern)?,
+        }
+        ControlFlow::Continue(())
+    }
+
+    fn visit_pattern(&mut self, node: &'a Pattern) -> ControlFlow<Self::BreakTy> {
+        let mut collector = NameCollector::default();
+        let _ = node.visit_with(&mut collector);
+        for sym in collector.names {
+            self.bind(sym);
+            self.excluded.insert(sym);
+        }
+        node.visit_with(self)
+    }
+
+    fn visit_variable(&mut self, node: &'a Variable) -> ControlFlow<Self::BreakTy> {
+        self.visit_binding(node.binding())?;
+        if let Some(init) = node.init() {
+            self.visit_expression(init)?;
+        }
+        ControlFlow::Continue(())
+    }
+
+    fn visit_statement_list(&mut self, node: &'a StatementList) -> ControlFlow<Self::BreakTy> {
+        let mut names = Vec::new();
+        for item in node.statements() {
+            match item {
+                StatementListItem::Declaration(Declaration::Lexical(lexical)) => {
+                    names.extend(self.scan_lexical_declaration(lexical));
+                }
+                _ => self.visit_statement_list_item(item)?,
+            }
+        }
+        self.exit_block(names);
+        ControlFlow::Continue(())
+    }
+
+    fn visit_for_loop(&mut self, node: &'a ForLoop) -> ControlFlow<Self::BreakTy> {
+        let names = match node.init() {
+            Some(ForLoopInitializer::Lexical(lexical)) => self.scan_lexical_declaration(lexical),
+            Some(init) => {
+                self.visit_for_loop_initializer(init)?;
+                Vec::new()
+            }
+            None => Vec::new(),
+        };
+        if let Some(condition) = node.condition() {
+            self.visit_expression(condition)?;
+        }
+        if let Some(final_expr) = node.final_expr() {
+            self.visit_expression(final_expr)?;
+        }
+        self.visit_statement(node.body())?;
+        self.exit_block(names);
+        ControlFlow::Continue(())
+    }
+
+    fn visit_declaration(&mut self, node: &'a Declaration) -> ControlFlow<Self::BreakTy> {
+        match node {
+            Declaration::Function(function) => {
+                self.nested_function(
+                    function.name(),
+                    function.parameters(),
+                    function.body(),
+                    true,
+                );
+                ControlFlow::Continue(())
+            }
+            _ => node.visit_with(self),
+        }
+    }
+
+    fn visit_function(&mut self, node: &'a Function) -> ControlFlow<Self::BreakTy> {
+        self.nested_function(node.name(), node.parameters(), node.body(), false);
+        ControlFlow::Continue(())
+    }
+
+    fn visit_arrow_function(&mut self, node: &'a ArrowFunction) -> ControlFlow<Self::BreakTy> {
+        self.nested_function(node.name(), node.parameters(), node.body(), false);
+        ControlFlow::Continue(())
+    }
+
+    fn visit_async_function(&mut self, node: &'a AsyncFunction) -> ControlFlow<Self::BreakTy> {
+        self.exclude_all_names(node);
+        ControlFlow::Continue(())
+    }
+
+    fn visit_async_arrow_function(
+        &mut self,
+        node: &'a AsyncArrowFunction,
+    ) -> ControlFlow<Self::BreakTy> {
+        self.exclude_all_names(node);
+        ControlFlow::Continue(())
+    }
+
+    fn visit_generator(&mut self, node: &'a Generator) -> ControlFlow<Self::BreakTy> {
+        self.exclude_all_names(node);
+        ControlFlow::Continue(())
+    }
+
+    fn visit_async_generator(&mut self, node: &'a AsyncGenerator) -> ControlFlow<Self::BreakTy> {
+        self.exclude_all_names(node);
+        ControlFlow::Continue(())
+    }
+
+    fn visit_class(&mut self, node: &'a Class) -> ControlFlow<Self::BreakTy> {
+        self.exclude_all_names(node);
+        ControlFlow::Continue(())
+    }
+
+    fn visit_with(&mut self, node: &'a With) -> ControlFlow<Self::BreakTy> {
+        self.dynamic = true;
+        node.visit_with(self)
+    }
+
+    fn visit_return(&mut self, node: &'a Return) -> ControlFlow<Self::BreakTy> {
+        self.returns.push(node.target());
+        node.visit_with(self)
+    }
+
+    fn visit_call(&mut self, node: &'a Call) -> ControlFlow<Self::BreakTy> {
+        match node.function() {
+            Expression::Identifier(identifier) => {
+                self.reference(identifier.sym());
+                self.calls.push((identifier.sym(), node.args()));
+            }
+            function => self.visit_expression(function)?,
+        }
+        for arg in node.args() {
+            self.visit_expression(arg)?;
+        }
+        ControlFlow::Continue(())
+    }
+
+    fn visit_new(&mut self, node: &'a New) -> ControlFlow<Self::BreakTy> {
+        // a function called with `new` returns an object, so it's not known
+        self.visit_expression(node.call().function())?;
+        for arg in node.arguments() {
+            self.visit_expression(arg)?;
+        }
+        ControlFlow::Continue(())
+    }
+
+    fn visit_assign(&mut self, node: &'a Assign) -> ControlFlow<Self::BreakTy> {
+        match node.lhs() {
+            AssignTarget::Identifier(identifier) => {
+                let assignment = match node.op() {
+                    AssignOp::Assign => Assignment::Value(node.rhs()),
+                    AssignOp::Add => Assignment::Add(node.rhs()),
+                    _ => Assignment::Unknown,
+                };
+                self.assign(identifier.sym(), assignment);
+            }
+            AssignTarget::Access(access) => self.visit_property_access(access)?,
+            AssignTarget::Pattern(pattern) => {
+                let mut collector = NameCollector::default();
+                let _ = pattern.visit_with(&mut collector);
+                for sym in collector.names {
+                    self.assign(sym, Assignment::Unknown);
+                }
+                pattern.visit_with(self)?;
+            }
+        }
+        self.visit_expression(node.rhs())
+    }
+
+    fn visit_update(&mut self, node: &'a Update) -> ControlFlow<Self::BreakTy> {
+        match node.target() {
+            UpdateTarget::Identifier(identifier) => {
+                self.assign(identifier.sym(), Assignment::Update);
+                ControlFlow::Continue(())
+            }
+            UpdateTarget::PropertyAccess(access) => self.visit_property_access(access),
+        }
+    }
+
+    fn visit_iterable_loop_initializer(
+        &mut self,
+        node: &'a IterableLoopInitializer,
+    ) -> ControlFlow<Self::BreakTy> {
+        if let IterableLoopInitializer::Identifier(identifier) = node {
+            self.assign(identifier.sym(), Assignment::Unknown);
+            return ControlFlow::Continue(());
+        }
+        node.visit_with(self)
+    }
+}
+
+#[derive(Default)]
+struct NameCollector {
+    names: HashSet<Sym>,
+}
+
+impl<'a> Visitor<'a> for NameCollector {
+    type BreakTy = ();
+
+    fn visit_identifier(&mut self, node: &'a Identifier) -> ControlFlow<Self::BreakTy> {
+        self.names.insert(node.sym());
+        ControlFlow::Continue(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use boa_interner::{Interner, JStrRef};
+    use boa_parser::{Parser, Source};
+
+    fn parse(source: &str) -> (Script, Interner) {
+        let mut interner = Interner::default();
+        let script = Parser::new(Source::from_bytes(source))
+            .parse_script(&mut interner)
+            .unwrap();
+        (script, interner)
+    }
+
+    fn sym(interner: &Interner, name: &str) -> Sym {
+        interner.get(JStrRef::Utf8(name)).unwrap()
+    }
+
+    // a function declared directly in the given body
+    fn declared_function(body: &FunctionBody, name: Sym) -> &Function {
+        body.statements()
+            .statements()
+            .iter()
+            .find_map(|statement| match statement {
+                StatementListItem::Declaration(Declaration::Function(function))
+                    if function.name().map(|n| n.sym()) == Some(name) =>
+                {
+                    Some(function)
+                }
+                _ => None,
+            })
+            .unwrap()
+    }
+
+    fn return_type(info: &TypeInfo, name: Sym) -> Option<ValueType> {
+        info.returns.get(&name).copied()
+    }
+
+    #[test]
+    fn numbers_and_booleans_are_unboxed() {
+        let (script, interner) = parse("let a = 1; let b = a * 2 + 3; let c = a < b; c = !c;");
+        let info = TypeInfo::analyze(&script);
+        let id = function_id(&script);
+        assert_eq!(
+            info.variable_type(id, sym(&interner, "a")),
+            ValueType::Number
+        );
+        assert_eq!(
+            info.variable_type(id, sym(&interner, "b")),
+            ValueType::Number
+        );
+        assert_eq!(
+            info.variable_type(id, sym(&interner, "c")),
+            ValueType::Boolean
+        );
+    }
+
+    #[test]
+    fn mixed_assignments_stay_boxed() {
+        let (script, interner) = parse("let a = 1; a = 'one'; let b = true; b = 2;");
+        let info = TypeInfo::analyze(&script);
+        assert!(info.unboxed_variables(function_id(&script)).is_empty());
+        assert_eq!(
+            info.variable_type(function_id(&script), sym(&interner, "a")),
+            ValueType::Any
+        );
+    }
+
+    #[test]
+    fn captured_and_top_level_var_bindings_stay_boxed() {
+        let (script, _) = parse("var a = 1; let b = 2; function f() { return b; }");
+        let info = TypeInfo::analyze(&script);
+        assert!(info.unboxed_variables(function_id(&script)).is_empty());
+    }
+
+    #[test]
+    fn loop_counters_and_block_bindings_are_unboxed() {
+        let (script, interner) = parse(
+            "let s = 0; for (let i = 0; i < 10; i++) { const d = i * 2; let odd = d % 4 > 0; \
+             if (odd) { s += d; } }",
+        );
+        let info = TypeInfo::analyze(&script);
+        let id = function_id(&script);
+        assert_eq!(
+            info.variable_type(id, sym(&interner, "i")),
+            ValueType::Number
+        );
+        assert_eq!(
+            info.variable_type(id, sym(&interner, "d")),
+            ValueType::Number
+        );
+        assert_eq!(
+            info.variable_type(id, sym(&interner, "odd")),
+            ValueType::Boolean
+        );
+    }
+
+    #[test]
+    fn captured_or_out_of_block_bindings_stay_boxed() {
+        let (script, interner) = parse(
+            "let fs = []; for (let i = 0; i < 3; i++) { fs.push(() => i); } \
+             { let j = 1; } j = 2; for (let k = 0; k < 3; k++) {} for (let k = 0; k < 3; k++) {}",
+        );
+        let info = TypeInfo::analyze(&script);
+        assert!(info.unboxed_variables(function_id(&script)).is_empty());
+        assert_eq!(
+            info.variable_type(function_id(&script), sym(&interner, "i")),
+            ValueType::Any
+        );
+    }
+
+    #[test]
+    fn eval_and_arguments_disable_unboxing() {
+        let (script, interner) = parse(
+            "function main() { function f(x) { let y = 1; return arguments.length; } \
+             let a = 1; eval('a'); f(1); }",
+        );
+        let info = TypeInfo::analyze(&script);
+        let main = declared_function(&script, sym(&interner, "main"));
+        assert!(info.unboxed_variables(function_id(main.body())).is_empty());
+        let f = declared_function(main.body(), sym(&interner, "f"));
+        assert!(info.unboxed_variables(function_id(f.body())).is_empty());
+    }
+
+    #[test]
+    fn known_functions_get_typed_parameters_and_returns() {
+        let (script, interner) =
+            parse("function main() { function sq(x) { return x * x; } let y = sq(3); return y; }");
+        let info = TypeInfo::analyze(&script);
+        let main = declared_function(&script, sym(&interner, "main"));
+        let sq = declared_function(main.body(), sym(&interner, "sq"));
+        assert_eq!(
+            return_type(&info, sym(&interner, "sq")),
+            Some(ValueType::Number)
+        );
+        assert_eq!(
+            info.variable_type(function_id(sq.body()), sym(&interner, "x")),
+            ValueType::Number
+        );
+        assert_eq!(
+            info.variable_type(function_id(main.body()), sym(&interner, "y")),
+            ValueType::Number
+        );
+    }
+
+    #[test]
+    fn top_level_functions_are_not_known() {
+        let (script, interner) = parse("function sq(x) { return x * x; } sq(3);");
+        let info = TypeInfo::analyze(&script);
+        let sq = declared_function(&script, sym(&interner, "sq"));
+        assert_eq!(return_type(&info, sym(&interner, "sq")), None);
+        assert_eq!(
+            info.variable_type(function_id(sq.body()), sym(&interner, "x")),
+            ValueType::Any
+        );
+    }
+
+    #[test]
+    fn functions_used_as_values_are_not_known() {
+        let (script, interner) = parse(
+            "function main() { function sq(x) { return x * x; } let f = sq; sq(2); f('a'); }",
+        );
+        let info = TypeInfo::analyze(&script);
+        let main = declared_function(&script, sym(&interner, "main"));
+        let sq = declared_function(main.body(), sym(&interner, "sq"));
+        assert_eq!(return_type(&info, sym(&interner, "sq")), None);
+        assert_eq!(
+            info.variable_type(function_id(sq.body()), sym(&interner, "x")),
+            ValueType::Any
+        );
+    }
+
+    #[test]
+    fn call_sites_with_other_types_box_parameters() {
+        let (script, interner) =
+            parse("function main() { function id(x) { return x; } id(1); id('one'); }");
+        let info = TypeInfo::analyze(&script);
+        assert_eq!(return_type(&info, sym(&interner, "id")), None);
+    }
+
+    #[test]
+    fn recursive_functions_reach_a_fixed_point() {
+        let (script, interner) = parse(
+            "function main() { \
+               function fib(n) { if (n < 2) { return n; } return fib(n - 1) + fib(n - 2); } \
+               return fib(10); }",
+        );
+        let info = TypeInfo::analyze(&script);
+        assert_eq!(
+            return_type(&info, sym(&interner, "fib")),
+            Some(ValueType::Number)
+        );
+    }
+
+    #[test]
+    fn functions_that_can_complete_return_undefined() {
+        let (script, interner) =
+            parse("function main() { function f(x) { if (x) { return 1; } } f(true); }");
+        let info = TypeInfo::analyze(&script);
+        assert_eq!(return_type(&info, sym(&interner, "f")), None);
+    }
+}
@@ -0,0 +1,903 @@
+  ;; `BigInt`
+  ;;
+  ;; A BigInt is a sign and a magnitude, the magnitude uses the unsigned
+  ;; numbers from bignum.wat. Zero is never negative, so two equal BigInts
+  ;; always have the same representation. Bitwise operators work on the two's
+  ;; complement of the magnitude, just like the spec describes them.
+
+  (type $BigInt (struct (field $negative i32) (field $digits (ref $BigDigits))))
+
+  (global $bigint-prototype (mut (ref null $Object)) (ref.null $Object))
+
+  (func $new_bigint (param $negative i32) (param $digits (ref $BigDigits)) (result (ref $BigInt))
+    (struct.new $BigInt
+      (i32.and (local.get $negative) (i32.eqz (call $big_is_zero (local.get $digits))))
+      (local.get $digits))
+  )
+
+  (func $is_bigint (param $arg anyref) (result i32)
+    (ref.test (ref $BigInt) (local.get $arg))
+  )
+
+  (func $bigint_from_i64 (param $value i64) (result (ref $BigInt))
+    (if (i64.lt_s (local.get $value) (i64.const 0))
+      (then
+        (return
+          (call $new_bigint
+            (i32.const 1)
+            (call $big_from_u64 (i64.sub (i64.const 0) (local.get $value)))))
+      )
+    )
+    (call $new_bigint (i32.const 0) (call $big_from_u64 (local.get $value)))
+  )
+
+  ;; the number has to be a finite integer
+  (func $bigint_from_f64 (param $value f64) (result (ref $BigInt))
+    (local $mantissa i64)
+    (local $exponent i32)
+    (local $digits (ref $BigDigits))
+
+    (call $f64_decompose (f64.abs (local.get $value)))
+    (local.set $exponent)
+    (local.set $mantissa)
+    (local.set $digits (call $big_from_u64 (local.get $mantissa)))
+    (if (i32.ge_s (local.get $exponent) (i32.const 0))
+      (then
+        (local.set $digits (call $big_shl (local.get $digits) (local.get $exponent)))
+      )
+      (else
+        (local.set $digits
+          (call $big_shr (local.get $digits) (i32.sub (i32.const 0) (local.get $exponent))))
+      )
+    )
+    (call $new_bigint (f64.lt (local.get $value) (f64.const 0)) (local.get $digits))
+  )
+
+  (func $bigint_to_f64 (param $value (ref $BigInt)) (result f64)
+    (local $result f64)
+    (local.set $result
+      (call $big_ratio_to_f64
+        (struct.get $BigInt $digits (local.get $value))
+        (call $big_from_u64 (i64.const 1))))
+    (if (struct.get $BigInt $negative (local.get $value))
+      (then (return (f64.neg (local.get $result))))
+    )
+    (local.get $result)
+  )
+
+  (func $bigint_to_string (param $value (ref $BigInt)) (param $radix i32) (result anyref)
+    (local $digits (ref $String))
+    (local.set $digits
+      (call $big_to_string (struct.get $BigInt $digits (local.get $value)) (local.get $radix)))
+    (if (struct.get $BigInt $negative (local.get $value))
+      (then
+        (return
+          (call $concat_strings
+            (call $new_static_string (i32.const {{ data(str="-") }}) (i32.const {{ data_length() }}))
+            (local.get $digits)))
+      )
+    )
+    (local.get $digits)
+  )
+
+  (func $bigint_is_zero (param $value (ref $BigInt)) (result i32)
+    (call $big_is_zero (struct.get $BigInt $digits (local.get $value)))
+  )
+
+  ;; returns -1, 0 or 1
+  (func $bigint_compare (param $a (ref $BigInt)) (param $b (ref $BigInt)) (result i32)
+    (local $negative i32)
+    (local.set $negative (struct.get $BigInt $negative (local.get $a)))
+    (if (i32.ne (local.get $negative) (struct.get $BigInt $negative (local.get $b)))
+      (then
+        (return (select (i32.const -1) (i32.const 1) (local.get $negative)))
+      )
+    )
+    (if (local.get $negative)
+      (then
+        (return
+          (call $big_compare
+            (struct.get $BigInt $digits (local.get $b))
+            (struct.get $BigInt $digits (local.get $a))))
+      )
+    )
+    (call $big_compare
+      (struct.get $BigInt $digits (local.get $a))
+      (struct.get $BigInt $digits (local.get $b)))
+  )
+
+  (func $bigint_equals (param $a (ref $BigInt)) (param $b (ref $BigInt)) (result i32)
+    (i32.eqz (call $bigint_compare (local.get $a) (local.get $b)))
+  )
+
+  ;; compares a BigInt with a number that isn't NaN, returns -1, 0 or 1
+  (func $bigint_compare_number (param $a (ref $BigInt)) (param $number f64) (result i32)
+    (local $floor f64)
+    (local $result i32)
+
+    (if (f64.eq (local.get $number) (f64.const inf))
+      (then (return (i32.const -1)))
+    )
+    (if (f64.eq (local.get $number) (f64.const -inf))
+      (then (return (i32.const 1)))
+    )
+
+    ;; a non integer number is between its floor and the next integer
+    (local.set $floor (f64.floor (local.get $number)))
+    (local.set $result
+      (call $bigint_compare (local.get $a) (call $bigint_from_f64 (local.get $floor))))
+    (if (i32.and
+          (i32.eqz (local.get $result))
+          (f64.ne (local.get $floor) (local.get $number)))
+      (then (return (i32.const -1)))
+    )
+    (local.get $result)
+  )
+
+  ;; IsLessThan for two primitives where at least one is a BigInt, returns -1 if
+  ;; the result is undefined
+  (func $bigint_is_less_than (param $arg1 anyref) (param $arg2 anyref) (result i32)
+    (local $number f64)
+
+    (if (call $is_string (local.get $arg1))
+      (then
+        (local.set $arg1 (call $string_to_bigint (local.get $arg1)))
+        (if (ref.is_null (local.get $arg1))
+          (then (return (i32.const -1)))
+        )
+      )
+    )
+    (if (call $is_string (local.get $arg2))
+      (then
+        (local.set $arg2 (call $string_to_bigint (local.get $arg2)))
+        (if (ref.is_null (local.get $arg2))
+          (then (return (i32.const -1)))
+        )
+      )
+    )
+
+    (if (i32.and (call $is_bigint (local.get $arg1)) (call $is_bigint (local.get $arg2)))
+      (then
+        (return
+          (i32.lt_s
+            (call $bigint_compare
+              (ref.cast (ref $BigInt) (local.get $arg1))
+              (ref.cast (ref $BigInt) (local.get $arg2)))
+            (i32.const 0)))
+      )
+    )
+
+    (if (call $is_bigint (local.get $arg1))
+      (then
+        (local.set $number (call $to_number (local.get $arg2)))
+        (if (f64.ne (local.get $number) (local.get $number))
+          (then (return (i32.const -1)))
+        )
+        (return
+          (i32.lt_s
+            (call $bigint_compare_number (ref.cast (ref $BigInt) (local.get $arg1)) (local.get $number))
+            (i32.const 0)))
+      )
+    )
+
+    (local.set $number (call $to_number (local.get $arg1)))
+    (if (f64.ne (local.get $number) (local.get $number))
+      (then (return (i32.const -1)))
+    )
+    (i32.gt_s
+      (call $bigint_compare_number (ref.cast (ref $BigInt) (local.get $arg2)) (local.get $number))
+      (i32.const 0))
+  )
+
+  (func $bigint_negate (param $value (ref $BigInt)) (result (ref $BigInt))
+    (call $new_bigint
+      (i32.eqz (struct.get $BigInt $negative (local.get $value)))
+      (struct.get $BigInt $digits (local.get $value)))
+  )
+
+  (func $bigint_add (param $a (ref $BigInt)) (param $b (ref $BigInt)) (result (ref $BigInt))
+    (local $digits_a (ref $BigDigits))
+    (local $digits_b (ref $BigDigits))
+
+    (local.set $digits_a (struct.get $BigInt $digits (local.get $a)))
+    (local.set $digits_b (struct.get $BigInt $digits (local.get $b)))
+
+    (if (i32.eq (struct.get $BigInt $negative (local.get $a)) (struct.get $BigInt $negative (local.get $b)))
+      (then
+        (return
+          (call $new_bigint
+            (struct.get $BigInt $negative (local.get $a))
+            (call $big_add (local.get $digits_a) (local.get $digits_b))))
+      )
+    )
+
+    ;; with different signs the smaller magnitude is subtracted from the larger
+    ;; one, which also decides the sign
+    (if (i32.ge_s (call $big_compare (local.get $digits_a) (local.get $digits_b)) (i32.const 0))
+      (then
+        (return
+          (call $new_bigint
+            (struct.get $BigInt $negative (local.get $a))
+            (call $big_sub (local.get $digits_a) (local.get $digits_b))))
+      )
+    )
+    (call $new_bigint
+      (struct.get $BigInt $negative (local.get $b))
+      (call $big_sub (local.get $digits_b) (local.get $digits_a)))
+  )
+
+  (func $bigint_sub (param $a (ref $BigInt)) (param $b (ref $BigInt)) (result (ref $BigInt))
+    (call $bigint_add (local.get $a) (call $bigint_negate (local.get $b)))
+  )
+
+  (func $bigint_mul (param $a (ref $BigInt)) (param $b (ref $BigInt)) (result (ref $BigInt))
+    (call $new_bigint
+      (i32.xor (struct.get $BigInt $negative (local.get $a)) (struct.get $BigInt $negative (local.get $b)))
+      (call $big_mul (struct.get $BigInt $digits (local.get $a)) (struct.get $BigInt $digits (local.get $b))))
+  )
+
+  (func $bigint_check_divisor (param $divisor (ref $BigInt))
+    (if (call $bigint_is_zero (local.get $divisor))
+      (then
+        (call $throw_range_error
+          (call $new_static_string (i32.const {{ data(str="Division by zero") }}) (i32.const {{ data_length() }})))
+      )
+    )
+  )
+
+  ;; the quotient is truncated towards zero
+  (func $bigint_div (param $a (ref $BigInt)) (param $b (ref $BigInt)) (result (ref $BigInt))
+    (local $quotient (ref $BigDigits))
+    (call $bigint_check_divisor (local.get $b))
+    (call $big_divmod (struct.get $BigInt $digits (local.get $a)) (struct.get $BigInt $digits (local.get $b)))
+    (drop)
+    (local.set $quotient)
+    (call $new_bigint
+      (i32.xor (struct.get $BigInt $negative (local.get $a)) (struct.get $BigInt $negative (local.get $b)))
+      (local.get $quotient))
+  )
+
+  ;; the remainder has the sign of the dividend
+  (func $bigint_rem (param $a (ref $BigInt)) (param $b (ref $BigInt)) (result (ref $BigInt))
+    (local $remainder (ref $BigDigits))
+    (call $bigint_check_divisor (local.get $b))
+    (call $big_divmod (struct.get $BigInt $digits (local.get $a)) (struct.get $BigInt $digits (local.get $b)))
+    (local.set $remainder)
+    (drop)
+    (call $new_bigint (struct.get $BigInt $negative (local.get $a)) (local.get $remainder))
+  )
+
+  (func $throw_bigint_too_big
+    (call $throw_range_error
+      (call $new_static_string (i32.const {{ data(str="Maximum BigInt size exceeded") }}) (i32.const {{ data_length() }})))
+  )
+
+  (func $bigint_exp (param $base (ref $BigInt)) (param $exponent (ref $BigInt)) (result (ref $BigInt))
+    (local $digits (ref $BigDigits))
+    (local $power i32)
+    (local $result (ref $BigDigits))
+    (local $odd i32)
+
+    (if (struct.get $BigInt $negative (local.get $exponent))
+      (then
+        (call $throw_range_error
+          (call $new_static_string (i32.const {{ data(str="Exponent must be non-negative") }}) (i32.const {{ data_length() }})))
+      )
+    )
+    (if (call $bigint_is_zero (local.get $exponent))
+      (then (return (call $bigint_from_i64 (i64.const 1))))
+    )
+
+    (local.set $digits (struct.get $BigInt $digits (local.get $base)))
+    (local.set $odd
+      (i32.and (array.get $BigDigits (struct.get $BigInt $digits (local.get $exponent)) (i32.const 0)) (i32.const 1)))
+    ;; 0, 1 and -1 stay small for any exponent
+    (if (i32.le_u (call $big_bit_length (local.get $digits)) (i32.const 1))
+      (then
+        (return
+          (call $new_bigint
+            (i32.and (struct.get $BigInt $negative (local.get $base)) (local.get $odd))
+            (local.get $digits)))
+      )
+    )
+
+    ;; the result has at least (bits - 1) * exponent bits, the same limit as V8
+    (if (i32.gt_u (array.len (struct.get $BigInt $digits (local.get $exponent))) (i32.const 1))
+      (then (call $throw_bigint_too_big))
+    )
+    (local.set $power (array.get $BigDigits (struct.get $BigInt $digits (local.get $exponent)) (i32.const 0)))
+    (if (i64.gt_u
+          (i64.mul
+            (i64.extend_i32_u (i32.sub (call $big_bit_length (local.get $digits)) (i32.const 1)))
+            (i64.extend_i32_u (local.get $power)))
+          (i64.const 0x40000000))
+      (then (call $throw_bigint_too_big))
+    )
+
+    ;; square and multiply
+    (local.set $result (call $big_from_u64 (i64.const 1)))
+    (block $break (loop $multiply
+      (if (i32.and (local.get $power) (i32.const 1))
+        (then
+          (local.set $result (call $big_mul (local.get $result) (local.get $digits)))
+        )
+      )
+      (local.set $power (i32.shr_u (local.get $power) (i32.const 1)))
+      (br_if $break (i32.eqz (local.get $power)))
+      (local.set $digits (call $big_mul (local.get $digits) (local.get $digits)))
+      (br $multiply)
+    ))
+
+    (call $new_bigint
+      (i32.and (struct.get $BigInt $negative (local.get $base)) (local.get $odd))
+      (local.get $result))
+  )
+
+  ;; the two's complement of a BigInt with a given number of digits, which has
+  ;; to be large enough for the sign bit
+  (func $bigint_to_twos (param $value (ref $BigInt)) (param $length i32) (result (ref $BigDigits))
+    (local $digits (ref $BigDigits))
+    (local $result (ref $BigDigits))
+    (local $i i32)
+
+    (local.set $digits (struct.get $BigInt $digits (local.get $value)))
+    (local.set $result (call $big_alloc (local.get $length)))
+
+    (if (i32.eqz (struct.get $BigInt $negative (local.get $value)))
+      (then
+        (array.copy $BigDigits $BigDigits
+          (local.get $result) (i32.const 0)
+          (local.get $digits) (i32.const 0)
+          (array.len (local.get $digits)))
+        (return (local.get $result))
+      )
+    )
+
+    ;; -x is the complement of x - 1
+    (local.set $digits (call $big_sub (local.get $digits) (call $big_from_u64 (i64.const 1))))
+    (block $break (loop $complement
+      (br_if $break (i32.ge_u (local.get $i) (local.get $length)))
+      (array.set $BigDigits (local.get $result) (local.get $i)
+        (i32.xor
+          (i32.wrap_i64 (call $big_digit (local.get $digits) (local.get $i)))
+          (i32.const -1)))
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $complement)
+    ))
+    (local.get $result)
+  )
+
+  ;; the lowest 64 bits of the two's complement, like BigInt.asUintN(64, value)
+  (func $bigint_to_u64 (param $value (ref $BigInt)) (result i64)
+    (local $digits (ref $BigDigits))
+    (local.set $digits
+      (call $bigint_to_twos
+        (local.get $value)
+        (i32.add (array.len (struct.get $BigInt $digits (local.get $value))) (i32.const 2))))
+    (i64.or
+      (i64.extend_i32_u (array.get $BigDigits (local.get $digits) (i32.const 0)))
+      (i64.shl
+        (i64.extend_i32_u (array.get $BigDigits (local.get $digits) (i32.const 1)))
+        (i64.const 32)))
+  )
+
+  ;; the array is modified
+  (func $bigint_from_twos (param $digits (ref $BigDigits)) (result (ref $BigInt))
+    (local $length i32)
+    (local $i i32)
+
+    (local.set $length (array.len (local.get $digits)))
+    (if (i32.or
+          (i32.eqz (local.get $length))
+          (i32.ge_s
+            (array.get $BigDigits (local.get $digits) (i32.sub (local.get $length) (i32.const 1)))
+            (i32.const 0)))
+      (then
+        (return (call $new_bigint (i32.const 0) (call $big_trim (local.get $digits))))
+      )
+    )
+
+    (block $break (loop $complement
+      (br_if $break (i32.ge_u (local.get $i) (local.get $length)))
+      (array.set $BigDigits (local.get $digits) (local.get $i)
+        (i32.xor (array.get $BigDigits (local.get $digits) (local.get $i)) (i32.const -1)))
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $complement)
+    ))
+    (call $new_bigint
+      (i32.const 1)
+      (call $big_add (call $big_trim (local.get $digits)) (call $big_from_u64 (i64.const 1))))
+  )
+
+  ;; $operation is 0 for &, 1 for | and 2 for ^
+  (func $bigint_bitwise (param $operation i32) (param $a (ref $BigInt)) (param $b (ref $BigInt)) (result (ref $BigInt))
+    (local $length i32)
+    (local $digits_a (ref $BigDigits))
+    (local $digits_b (ref $BigDigits))
+    (local $digit_a i32)
+    (local $digit_b i32)
+    (local $i i32)
+
+    (local.set $length (array.len (struct.get $BigInt $digits (local.get $a))))
+    (if (i32.gt_u (array.len (struct.get $BigInt $digits (local.get $b))) (local.get $length))
+      (then (local.set $length (array.len (struct.get $BigInt $digits (local.get $b)))))
+    )
+    (local.set $length (i32.add (local.get $length) (i32.const 1)))
+    (local.set $digits_a (call $bigint_to_twos (local.get $a) (local.get $length)))
+    (local.set $digits_b (call $bigint_to_twos (local.get $b) (local.get $length)))
+
+    (block $break (loop $combine
+      (br_if $break (i32.ge_u (local.get $i) (local.get $length)))
+      (local.set $digit_a (array.get $BigDigits (local.get $digits_a) (local.get $i)))
+      (local.set $digit_b (array.get $BigDigits (local.get $digits_b) (local.get $i)))
+      (array.set $BigDigits (local.get $digits_a) (local.get $i)
+        (if (result i32) (i32.eqz (local.get $operation))
+          (then (i32.and (local.get $digit_a) (local.get $digit_b)))
+          (else
+            (if (result i32) (i32.eq (local.get $operation) (i32.const 1))
+              (then (i32.or (local.get $digit_a) (local.get $digit_b)))
+              (else (i32.xor (local.get $digit_a) (local.get $digit_b)))))))
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $combine)
+    ))
+
+    (call $bigint_from_twos (local.get $digits_a))
+  )
+
+  ;; ~x is -x - 1
+  (func $bigint_not (param $value (ref $BigInt)) (result (ref $BigInt))
+    (call $bigint_sub (call $bigint_negate (local.get $value)) (call $bigint_from_i64 (i64.const 1)))
+  )
+
+  ;; shifts left for a positive $shift and right for a negative one, right
+  ;; shifts round towards negative infinity
+  (func $bigint_shift (param $value (ref $BigInt)) (param $shift (ref $BigInt)) (param $left i32) (result (ref $BigInt))
+    (local $digits (ref $BigDigits))
+    (local $shift_digits (ref $BigDigits))
+    (local $bits i32)
+    (local $negative i32)
+
+    (local.set $digits (struct.get $BigInt $digits (local.get $value)))
+    (local.set $shift_digits (struct.get $BigInt $digits (local.get $shift)))
+    (local.set $negative (struct.get $BigInt $negative (local.get $value)))
+    (if (i32.or (call $big_is_zero (local.get $digits)) (call $big_is_zero (local.get $shift_digits)))
+      (then (return (local.get $value)))
+    )
+    (if (struct.get $BigInt $negative (local.get $shift))
+      (then (local.set $left (i32.eqz (local.get $left))))
+    )
+
+    (local.set $bits (array.get $BigDigits (local.get $shift_digits) (i32.const 0)))
+    (if (local.get $left)
+      (then
+        (if (i32.or
+              (i32.gt_u (array.len (local.get $shift_digits)) (i32.const 1))
+              (i32.gt_u (local.get $bits) (i32.const 0x40000000)))
+          (then (call $throw_bigint_too_big))
+        )
+        (return
+          (call $new_bigint (local.get $negative) (call $big_shl (local.get $digits) (local.get $bits))))
+      )
+    )
+
+    ;; shifting all the bits out leaves 0 or -1
+    (if (i32.or
+          (i32.gt_u (array.len (local.get $shift_digits)) (i32.const 1))
+          (i32.ge_u (local.get $bits) (call $big_bit_length (local.get $digits))))
+      (then
+        (return (call $bigint_from_i64 (i64.extend_i32_s (i32.sub (i32.const 0) (local.get $negative)))))
+      )
+    )
+
+    (if (i32.eqz (local.get $negative))
+      (then
+        (return (call $new_bigint (i32.const 0) (call $big_shr (local.get $digits) (local.get $bits))))
+      )
+    )
+    ;; -x >> n is -(((x - 1) >> n) + 1)
+    (call $new_bigint
+      (i32.const 1)
+      (call $big_add
+        (call $big_shr
+          (call $big_sub (local.get $digits) (call $big_from_u64 (i64.const 1)))
+          (local.get $bits))
+        (call $big_from_u64 (i64.const 1))))
+  )
+
+  ;; ToNumeric from the spec, the result is either a number or a BigInt
+  (func $to_numeric (param $value anyref) (result anyref)
+    (if (i32.or (call $is_number (local.get $value)) (call $is_bigint (local.get $value)))
+      (then (return (local.get $value)))
+    )
+    (local.set $value (call $to_primitive (local.get $value) (i32.const 1)))
+    (if (call $is_bigint (local.get $value))
+      (then (return (local.get $value)))
+    )
+    (call $new_number (call $to_number (local.get $value)))
+  )
+
+  ;; operands of a binary operator that got at least one BigInt, both of them
+  ;; have to be BigInts
+  (func $bigint_operands (param $arg1 anyref) (param $arg2 anyref) (result (ref $BigInt) (ref $BigInt))
+    (if (i32.eqz (i32.and (call $is_bigint (local.get $arg1)) (call $is_bigint (local.get $arg2))))
+      (then
+        (call $throw_type_error
+          (call $new_static_string (i32.const {{ data(str="Cannot mix BigInt and other types, use explicit conversions") }}) (i32.const {{ data_length() }})))
+      )
+    )
+    (ref.cast (ref $BigInt) (local.get $arg1))
+    (ref.cast (ref $BigInt) (local.get $arg2))
+  )
+
+  ;; StringToBigInt from the spec, returns null if the string isn't a valid
+  ;; integer
+  (func $string_to_bigint (param $str anyref) (result (ref null $BigInt))
+    (local $start i32)
+    (local $end i32)
+    (local $radix i32)
+    (local $char i32)
+    (local $digit i32)
+    (local $negative i32)
+    (local $digits (ref $BigDigits))
+
+    (local.set $end (call $string_length (local.get $str)))
+    (local.set $start (call $skip_whitespace (local.get $str) (i32.const 0) (local.get $end)))
+    (block $break (loop $trim
+      (br_if $break (i32.le_s (local.get $end) (local.get $start)))
+      (br_if $break
+        (i32.eqz
+          (call $is_whitespace
+            (call $string_byte_at (local.get $str) (i32.sub (local.get $end) (i32.const 1))))))
+      (local.set $end (i32.sub (local.get $end) (i32.const 1)))
+      (br $trim)
+    ))
+
+    (local.set $digits (call $big_alloc (i32.const 0)))
+    (if (i32.eq (local.get $start) (local.get $end))
+      (then (return (call $new_bigint (i32.const 0) (local.get $digits))))
+    )
+
+    ;; 0x, 0o and 0b prefixes, only decimal numbers can have a sign
+    (local.set $radix (i32.const 10))
+    (if (i32.and
+          (i32.ge_s (i32.sub (local.get $end) (local.get $start)) (i32.const 3))
+          (i32.eq (call $string_byte_at (local.get $str) (local.get $start)) (i32.const 48)))
+      (then
+        (local.set $char
+          (i32.or (call $string_byte_at (local.get $str) (i32.add (local.get $start) (i32.const 1))) (i32.const 32)))
+        (if (i32.eq (local.get $char) (i32.const 120))
+          (then (local.set $radix (i32.const 16)))
+        )
+        (if (i32.eq (local.get $char) (i32.const 111))
+          (then (local.set $radix (i32.const 8)))
+        )
+        (if (i32.eq (local.get $char) (i32.const 98))
+          (then (local.set $radix (i32.const 2)))
+        )
+        (if (i32.ne (local.get $radix) (i32.const 10))
+          (then (local.set $start (i32.add (local.get $start) (i32.const 2))))
+        )
+      )
+      (else
+        (local.set $char (call $string_byte_at (local.get $str) (local.get $start)))
+        (if (i32.or (i32.eq (local.get $char) (i32.const 43)) (i32.eq (local.get $char) (i32.const 45)))
+          (then
+            (local.set $negative (i32.eq (local.get $char) (i32.const 45)))
+            (local.set $start (i32.add (local.get $start) (i32.const 1)))
+            (if (i32.eq (local.get $start) (local.get $end))
+              (then (return (ref.null $BigInt)))
+            )
+          )
+        )
+      )
+    )
+
+    (block $break (loop $parse
+      (br_if $break (i32.ge_s (local.get $start) (local.get $end)))
+      (local.set $digit
+        (call $digit_value (call $string_byte_at (local.get $str) (local.get $start)) (local.get $radix)))
+      (if (i32.lt_s (local.get $digit) (i32.const 0))
+        (then (return (ref.null $BigInt)))
+      )
+      (local.set $digits
+        (call $big_mul_add_small (local.get $digits) (local.get $radix) (local.get $digit)))
+      (local.set $start (i32.add (local.get $start) (i32.const 1)))
+      (br $parse)
+    ))
+
+    (call $new_bigint (local.get $negative) (local.get $digits))
+  )
+
+  (func $throw_bigint_conversion_error (param $value anyref)
+    (if (call $is_symbol (local.get $value))
+      (then
+        (local.set $value (call $symbol_descriptive_string (ref.cast (ref $Symbol) (local.get $value))))
+      )
+    )
+    (call $throw_type_error
+      (call $concat_strings
+        (call $concat_strings
+          (call $new_static_string (i32.const {{ data(str="Cannot convert ") }}) (i32.const {{ data_length() }}))
+          (call $to_string (local.get $value)))
+        (call $new_static_string (i32.const {{ data(str=" to a BigInt") }}) (i32.const {{ data_length() }}))))
+  )
+
+  ;; ToBigInt from the spec
+  (func $to_bigint (param $value anyref) (result (ref $BigInt))
+    (local $result (ref null $BigInt))
+
+    (local.set $value (call $to_primitive (local.get $value) (i32.const 1)))
+    (if (call $is_bigint (local.get $value))
+      (then (return (ref.cast (ref $BigInt) (local.get $value))))
+    )
+
+    (if (ref.test (ref $Boolean) (local.get $value))
+      (then
+        (return
+          (call $bigint_from_i64
+            (i64.extend_i32_u (struct.get $Boolean $value (ref.cast (ref $Boolean) (local.get $value))))))
+      )
+    )
+
+    (if (call $is_string (local.get $value))
+      (then
+        (local.set $result (call $string_to_bigint (local.get $value)))
+        (if (ref.is_null (local.get $result))
+          (then
+            (call $throw_syntax_error
+              (call $concat_strings
+                (call $concat_strings
+                  (call $new_static_string (i32.const {{ data(str="Cannot convert ") }}) (i32.const {{ data_length() }}))
+                  (local.get $value))
+                (call $new_static_string (i32.const {{ data(str=" to a BigInt") }}) (i32.const {{ data_length() }}))))
+          )
+        )
+        (return (ref.as_non_null (local.get $result)))
+      )
+    )
+
+    ;; numbers, undefined, null and symbols
+    (call $throw_bigint_conversion_error (local.get $value))
+    (unreachable)
+  )
+
+  ;; thisBigIntValue from the spec
+  (func $this_bigint_value (param $this anyref) (result (ref $BigInt))
+    (if (call $is_bigint (local.get $this))
+      (then (return (ref.cast (ref $BigInt) (local.get $this))))
+    )
+    (call $throw_type_error
+      (call $new_static_string (i32.const {{ data(str="BigInt.prototype method called on incompatible receiver") }}) (i32.const {{ data_length() }})))
+    (unreachable)
+  )
+
+  (func $BigInt-constructor
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (local $value anyref)
+    (local $number f64)
+
+    ;; `new` passes the newly created object as `this`
+    (if (ref.test (ref $Object) (local.get $this))
+      (then
+        (call $throw_type_error
+          (call $new_static_string (i32.const {{ data(str="BigInt is not a constructor") }}) (i32.const {{ data_length() }})))
+      )
+    )
+
+    (local.set $value
+      (call $to_primitive (call $get_argument (local.get $arguments) (i32.const 0)) (i32.const 1)))
+    (if (call $is_number (local.get $value))
+      (then
+        (local.set $number (call $number_value (local.get $value)))
+        (if (i32.eqz (call $f64_is_integer (local.get $number)))
+          (then
+            (call $throw_range_error
+              (call $concat_strings
+                (call $concat_strings
+                  (call $new_static_string (i32.const {{ data(str="The number ") }}) (i32.const {{ data_length() }}))
+                  (call $number_to_string (local.get $number)))
+                (call $new_static_string (i32.const {{ data(str=" cannot be converted to a BigInt because it is not an integer") }}) (i32.const {{ data_length() }}))))
+          )
+        )
+        (return (call $bigint_from_f64 (local.get $number)))
+      )
+    )
+    (call $to_bigint (local.get $value))
+  )
+
+  ;; ToIndex from the spec, for the number of bits in asIntN and asUintN
+  (func $bigint_bits_argument (param $arguments (ref $JSArgs)) (result i32)
+    (local $bits f64)
+    (local.set $bits
+      (call $to_integer_or_infinity (call $get_argument (local.get $arguments) (i32.const 0))))
+    (if (i32.or
+          (f64.lt (local.get $bits) (f64.const 0))
+          (f64.gt (local.get $bits) (f64.const 9007199254740991)))
+      (then
+        (call $throw_range_error
+          (call $new_static_string (i32.const {{ data(str="Invalid value: not (convertible to) a safe integer") }}) (i32.const {{ data_length() }})))
+      )
+    )
+    ;; anything above the size limit behaves the same
+    (if (f64.gt (local.get $bits) (f64.const 0x40000000))
+      (then (return (i32.const 0x40000001)))
+    )
+    (i32.trunc_f64_u (local.get $bits))
+  )
+
+  ;; the value modulo 2^bits
+  (func $bigint_as_uint (param $value (ref $BigInt)) (param $bits i32) (result (ref $BigInt))
+    (local $length i32)
+    (local $digits (ref $BigDigits))
+    (local $top_bits i32)
+
+    (if (i32.eqz (local.get $bits))
+      (then (return (call $bigint_from_i64 (i64.const 0))))
+    )
+    (if (i32.and
+          (i32.eqz (struct.get $BigInt $negative (local.get $value)))
+          (i32.le_u (call $big_bit_length (struct.get $BigInt $digits (local.get $value))) (local.get $bits)))
+      (then (return (local.get $value)))
+    )
+    ;; a negative value would need a huge number of ones
+    (if (i32.gt_u (local.get $bits) (i32.const 0x40000000))
+      (then (call $throw_bigint_too_big))
+    )
+
+    (local.set $length (i32.shr_u (i32.add (local.get $bits) (i32.const 31)) (i32.const 5)))
+    (local.set $digits
+      (call $bigint_to_twos
+        (local.get $value)
+        (i32.add
+          (i32.add (local.get $length) (array.len (struct.get $BigInt $digits (local.get $value))))
+          (i32.const 1))))
+    (local.set $digits (call $big_trim (call $big_truncate (local.get $digits) (local.get $length))))
+    (local.set $top_bits (i32.and (local.get $bits) (i32.const 31)))
+    (if (i32.and
+          (i32.ne (local.get $top_bits) (i32.const 0))
+          (i32.eq (array.len (local.get $digits)) (local.get $length)))
+      (then
+        (array.set $BigDigits (local.get $digits) (i32.sub (local.get $length) (i32.const 1))
+          (i32.and
+            (array.get $BigDigits (local.get $digits) (i32.sub (local.get $length) (i32.const 1)))
+            (i32.sub (i32.shl (i32.const 1) (local.get $top_bits)) (i32.const 1))))
+        (local.set $digits (call $big_trim (local.get $digits)))
+      )
+    )
+    (call $new_bigint (i32.const 0) (local.get $digits))
+  )
+
+  ;; the first $length digits of a number
+  (func $big_truncate (param $a (ref $BigDigits)) (param $length i32) (result (ref $BigDigits))
+    (local $result (ref $BigDigits))
+    (if (i32.le_u (array.len (local.get $a)) (local.get $length))
+      (then (return (local.get $a)))
+    )
+    (local.set $result (call $big_alloc (local.get $length)))
+    (array.copy $BigDigits $BigDigits
+      (local.get $result) (i32.const 0)
+      (local.get $a) (i32.const 0)
+      (local.get $length))
+    (local.get $result)
+  )
+
+  (func $BigInt-asUintN
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (local $bits i32)
+
+    (local.set $bits (call $bigint_bits_argument (local.get $arguments)))
+    (call $bigint_as_uint
+      (call $to_bigint (call $get_argument (local.get $arguments) (i32.const 1)))
+      (local.get $bits))
+  )
+
+  (func $BigInt-asIntN
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (local $bits i32)
+    (local $value (ref $BigInt))
+    (local $modulus (ref $BigDigits))
+
+    (local.set $bits (call $bigint_bits_argument (local.get $arguments)))
+    (local.set $value
+      (call $to_bigint (call $get_argument (local.get $arguments) (i32.const 1))))
+    (if (i32.eqz (local.get $bits))
+      (then (return (call $bigint_from_i64 (i64.const 0))))
+    )
+    ;; values that already fit don't change
+    (if (i32.lt_u
+          (call $big_bit_length (struct.get $BigInt $digits (local.get $value)))
+          (local.get $bits))
+      (then (return (local.get $value)))
+    )
+
+    ;; values with the sign bit set are negative
+    (local.set $value (call $bigint_as_uint (local.get $value) (local.get $bits)))
+    (if (i32.lt_u
+          (call $big_bit_length (struct.get $BigInt $digits (local.get $value)))
+          (local.get $bits))
+      (then (return (local.get $value)))
+    )
+    (local.set $modulus
+      (call $big_set_bit (call $big_alloc (i32.const 0)) (local.get $bits)))
+    (call $new_bigint
+      (i32.const 1)
+      (call $big_sub (local.get $modulus) (struct.get $BigInt $digits (local.get $value))))
+  )
+
+  (func $BigInt-prototype-toString
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (local $value (ref $BigInt))
+    (local $radix anyref)
+    (local $radix_value f64)
+
+    (local.set $value (call $this_bigint_value (local.get $this)))
+    (local.set $radix (call $get_argument (local.get $arguments) (i32.const 0)))
+    (if (ref.test nullref (local.get $radix))
+      (then (return (call $bigint_to_string (local.get $value) (i32.const 10))))
+    )
+
+    (local.set $radix_value (call $to_integer_or_infinity (local.get $radix)))
+    (if (i32.or
+          (f64.lt (local.get $radix_value) (f64.const 2))
+          (f64.gt (local.get $radix_value) (f64.const 36)))
+      (then
+        (call $throw_range_error
+          (call $new_static_string (i32.const {{ data(str="toString() radix must be between 2 and 36") }}) (i32.const {{ data_length() }})))
+      )
+    )
+    (call $bigint_to_string (local.get $value) (i32.trunc_f64_s (local.get $radix_value)))
+  )
+
+  (func $BigInt-prototype-valueOf
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+
+    (call $this_bigint_value (local.get $this))
+  )
+
+  (elem declare func $BigInt-constructor)
+  (elem declare func $BigInt-asUintN)
+  (elem declare func $BigInt-asIntN)
+  (elem declare func $BigInt-prototype-toString)
+  (elem declare func $BigInt-prototype-valueOf)
+
+  (func $install-bigint-globals (param $scope (ref $Scope))
+    (local $bigint (ref $Function))
+    (local $prototype (ref $Object))
+
+    (local.set $prototype (call $new_object))
+    (call $add_method (local.get $prototype) (i32.const {{ data(str="toString") }}) (ref.func $BigInt-prototype-toString))
+    (call $add_method (local.get $prototype) (i32.const {{ data(str="toLocaleString") }}) (ref.func $BigInt-prototype-toString))
+    (call $add_method (local.get $prototype) (i32.const {{ data(str="valueOf") }}) (ref.func $BigInt-prototype-valueOf))
+    (call $set_to_string_tag (local.get $prototype)
+      (call $new_static_string (i32.const {{ data(str="BigInt") }}) (i32.const {{ data_length() }})))
+    (global.set $bigint-prototype (local.get $prototype))
+
+    (local.set $bigint
+      (call $new_function (local.get $scope) (ref.func $BigInt-constructor) (ref.null any)))
+    (call $set_property (local.get $bigint) (i32.const {{ data(str="prototype") }}) (local.get $prototype))
+    (call $set_property (local.get $prototype) (i32.const {{ data(str="constructor") }}) (local.get $bigint))
+    (call $add_method (local.get $bigint) (i32.const {{ data(str="asUintN") }}) (ref.func $BigInt-asUintN))
+    (call $add_method (local.get $bigint) (i32.const {{ data(str="asIntN") }}) (ref.func $BigInt-asIntN))
+
+    (call $set_variable (local.get $scope) (i32.const {{ data(str="BigInt") }}) (local.get $bigint))
+  )
@@ -0,0 +1,487 @@
+  ;; Arbitrary precision unsigned integers. They're used wherever a conversion
+  ;; between binary and decimal numbers has to be exact, like formatting and
+  ;; parsing numbers.
+  ;;
+  ;; Digits are stored in base 2^32, the least significant first. A number never
+  ;; has leading zero digits, so zero is an empty array. None of the functions
+  ;; modify their arguments, a new number is always returned
+
+  (type $BigDigits (array (mut i32)))
+
+  ;; creates a number with a given amount of zeroed digits, it has to be trimmed
+  ;; with $big_trim after the digits are set
+  (func $big_alloc (param $length i32) (result (ref $BigDigits))
+    (array.new_default $BigDigits (local.get $length))
+  )
+
+  (func $big_trim (param $a (ref $BigDigits)) (result (ref $BigDigits))
+    (local $length i32)
+    (local $result (ref $BigDigits))
+
+    (local.set $length (array.len (local.get $a)))
+    (block $break (loop $find
+      (br_if $break (i32.eqz (local.get $length)))
+      (br_if $break
+        (array.get $BigDigits (local.get $a) (i32.sub (local.get $length) (i32.const 1))))
+      (local.set $length (i32.sub (local.get $length) (i32.const 1)))
+      (br $find)
+    ))
+
+    (if (i32.eq (local.get $length) (array.len (local.get $a)))
+      (then (return (local.get $a)))
+    )
+
+    (local.set $result (call $big_alloc (local.get $length)))
+    (array.copy $BigDigits $BigDigits
+      (local.get $result) (i32.const 0)
+      (local.get $a) (i32.const 0)
+      (local.get $length))
+    (local.get $result)
+  )
+
+  (func $big_from_u64 (param $value i64) (result (ref $BigDigits))
+    (local $result (ref $BigDigits))
+    (local.set $result (call $big_alloc (i32.const 2)))
+    (array.set $BigDigits (local.get $result) (i32.const 0) (i32.wrap_i64 (local.get $value)))
+    (array.set $BigDigits (local.get $result) (i32.const 1)
+      (i32.wrap_i64 (i64.shr_u (local.get $value) (i64.const 32))))
+    (call $big_trim (local.get $result))
+  )
+
+  ;; returns the lowest 64 bits of a number
+  (func $big_to_u64 (param $a (ref $BigDigits)) (result i64)
+    (local $result i64)
+    (if (array.len (local.get $a))
+      (then
+        (local.set $result
+          (i64.extend_i32_u (array.get $BigDigits (local.get $a) (i32.const 0))))
+      )
+    )
+    (if (i32.gt_u (array.len (local.get $a)) (i32.const 1))
+      (then
+        (local.set $result
+          (i64.or
+            (local.get $result)
+            (i64.shl
+              (i64.extend_i32_u (array.get $BigDigits (local.get $a) (i32.const 1)))
+              (i64.const 32))))
+      )
+    )
+    (local.get $result)
+  )
+
+  (func $big_is_zero (param $a (ref $BigDigits)) (result i32)
+    (i32.eqz (array.len (local.get $a)))
+  )
+
+  (func $big_digit (param $a (ref $BigDigits)) (param $index i32) (result i64)
+    (if (i32.ge_u (local.get $index) (array.len (local.get $a)))
+      (then (return (i64.const 0)))
+    )
+    (i64.extend_i32_u (array.get $BigDigits (local.get $a) (local.get $index)))
+  )
+
+  (func $big_bit_length (param $a (ref $BigDigits)) (result i32)
+    (local $length i32)
+    (local.set $length (array.len (local.get $a)))
+    (if (i32.eqz (local.get $length))
+      (then (return (i32.const 0)))
+    )
+    (i32.sub
+      (i32.mul (local.get $length) (i32.const 32))
+      (i32.clz (array.get $BigDigits (local.get $a) (i32.sub (local.get $length) (i32.const 1)))))
+  )
+
+  ;; returns -1, 0 or 1
+  (func $big_compare (param $a (ref $BigDigits)) (param $b (ref $BigDigits)) (result i32)
+    (local $i i32)
+    (local $digit_a i32)
+    (local $digit_b i32)
+
+    (if (i32.ne (array.len (local.get $a)) (array.len (local.get $b)))
+      (then
+        (return
+          (select (i32.const -1) (i32.const 1)
+            (i32.lt_u (array.len (local.get $a)) (array.len (local.get $b)))))
+      )
+    )
+
+    (local.set $i (array.len (local.get $a)))
+    (block $break (loop $compare
+      (br_if $break (i32.eqz (local.get $i)))
+      (local.set $i (i32.sub (local.get $i) (i32.const 1)))
+      (local.set $digit_a (array.get $BigDigits (local.get $a) (local.get $i)))
+      (local.set $digit_b (array.get $BigDigits (local.get $b) (local.get $i)))
+      (if (i32.ne (local.get $digit_a) (local.get $digit_b))
+        (then
+          (return
+            (select (i32.const -1) (i32.const 1)
+              (i32.lt_u (local.get $digit_a) (local.get $digit_b))))
+        )
+      )
+      (br $compare)
+    ))
+
+    (i32.const 0)
+  )
+
+  (func $big_add (param $a (ref $BigDigits)) (param $b (ref $BigDigits)) (result (ref $BigDigits))
+    (local $length i32)
+    (local $result (ref $BigDigits))
+    (local $i i32)
+    (local $sum i64)
+
+    (local.set $length (array.len (local.get $a)))
+    (if (i32.gt_u (array.len (local.get $b)) (local.get $length))
+      (then (local.set $length (array.len (local.get $b))))
+    )
+    (local.set $result (call $big_alloc (i32.add (local.get $length) (i32.const 1))))
+
+    (block $break (loop $add
+      (br_if $break (i32.ge_u (local.get $i) (local.get $length)))
+      (local.set $sum
+        (i64.add
+          (i64.shr_u (local.get $sum) (i64.const 32))
+          (i64.add
+            (call $big_digit (local.get $a) (local.get $i))
+            (call $big_digit (local.get $b) (local.get $i)))))
+      (array.set $BigDigits (local.get $result) (local.get $i) (i32.wrap_i64 (local.get $sum)))
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $add)
+    ))
+    (array.set $BigDigits (local.get $result) (local.get $length)
+      (i32.wrap_i64 (i64.shr_u (local.get $sum) (i64.const 32))))
+
+    (call $big_trim (local.get $result))
+  )
+
+  ;; subtracts b from a, a has to be greater or equal to b
+  (func $big_sub (param $a (ref $BigDigits)) (param $b (ref $BigDigits)) (result (ref $BigDigits))
+    (local $length i32)
+    (local $result (ref $BigDigits))
+    (local $i i32)
+    (local $difference i64)
+    (local $borrow i64)
+
+    (local.set $length (array.len (local.get $a)))
+    (local.set $result (call $big_alloc (local.get $length)))
+
+    (block $break (loop $sub
+      (br_if $break (i32.ge_u (local.get $i) (local.get $length)))
+      (local.set $difference
+        (i64.sub
+          (i64.sub
+            (call $big_digit (local.get $a) (local.get $i))
+            (call $big_digit (local.get $b) (local.get $i)))
+          (local.get $borrow)))
+      (local.set $borrow (i64.extend_i32_u (i64.lt_s (local.get $difference) (i64.const 0))))
+      (array.set $BigDigits (local.get $result) (local.get $i) (i32.wrap_i64 (local.get $difference)))
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $sub)
+    ))
+
+    (call $big_trim (local.get $result))
+  )
+
+  ;; computes a * multiplier + addend, both numbers are treated as unsigned
+  (func $big_mul_add_small (param $a (ref $BigDigits)) (param $multiplier i32) (param $addend i32) (result (ref $BigDigits))
+    (local $length i32)
+    (local $result (ref $BigDigits))
+    (local $i i32)
+    (local $carry i64)
+
+    (local.set $length (array.len (local.get $a)))
+    (local.set $result (call $big_alloc (i32.add (local.get $length) (i32.const 1))))
+    (local.set $carry (i64.extend_i32_u (local.get $addend)))
+
+    (block $break (loop $mul
+      (br_if $break (i32.ge_u (local.get $i) (local.get $length)))
+      (local.set $carry
+        (i64.add
+          (i64.mul
+            (i64.extend_i32_u (array.get $BigDigits (local.get $a) (local.get $i)))
+            (i64.extend_i32_u (local.get $multiplier)))
+          (local.get $carry)))
+      (array.set $BigDigits (local.get $result) (local.get $i) (i32.wrap_i64 (local.get $carry)))
+      (local.set $carry (i64.shr_u (local.get $carry) (i64.const 32)))
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $mul)
+    ))
+    (array.set $BigDigits (local.get $result) (local.get $length) (i32.wrap_i64 (local.get $carry)))
+
+    (call $big_trim (local.get $result))
+  )
+
+  (func $big_mul_small (param $a (ref $BigDigits)) (param $multiplier i32) (result (ref $BigDigits))
+    (call $big_mul_add_small (local.get $a) (local.get $multiplier) (i32.const 0))
+  )
+
+  ;; schoolbook multiplication, a digit times a digit plus two carries always
+  ;; fits in 64 bits
+  (func $big_mul (param $a (ref $BigDigits)) (param $b (ref $BigDigits)) (result (ref $BigDigits))
+    (local $result (ref $BigDigits))
+    (local $i i32)
+    (local $j i32)
+    (local $digit i64)
+    (local $carry i64)
+
+    (local.set $result
+      (call $big_alloc (i32.add (array.len (local.get $a)) (array.len (local.get $b)))))
+
+    (block $break (loop $outer
+      (br_if $break (i32.ge_u (local.get $i) (array.len (local.get $a))))
+      (local.set $digit (i64.extend_i32_u (array.get $BigDigits (local.get $a) (local.get $i))))
+      (local.set $carry (i64.const 0))
+      (local.set $j (i32.const 0))
+      (block $break_inner (loop $inner
+        (br_if $break_inner (i32.ge_u (local.get $j) (array.len (local.get $b))))
+        (local.set $carry
+          (i64.add
+            (i64.add
+              (i64.mul
+                (local.get $digit)
+                (i64.extend_i32_u (array.get $BigDigits (local.get $b) (local.get $j))))
+              (i64.extend_i32_u
+                (array.get $BigDigits (local.get $result) (i32.add (local.get $i) (local.get $j)))))
+            (local.get $carry)))
+        (array.set $BigDigits (local.get $result)
+          (i32.add (local.get $i) (local.get $j))
+          (i32.wrap_i64 (local.get $carry)))
+        (local.set $carry (i64.shr_u (local.get $carry) (i64.const 32)))
+        (local.set $j (i32.add (local.get $j) (i32.const 1)))
+        (br $inner)
+      ))
+      (array.set $BigDigits (local.get $result)
+        (i32.add (local.get $i) (array.len (local.get $b)))
+        (i32.wrap_i64 (local.get $carry)))
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $outer)
+    ))
+
+    (call $big_trim (local.get $result))
+  )
+
+  (func $big_mul_pow10 (param $a (ref $BigDigits)) (param $exponent i32) (result (ref $BigDigits))
+    (block $break (loop $mul
+      (br_if $break (i32.lt_s (local.get $exponent) (i32.const 9)))
+      (local.set $a (call $big_mul_small (local.get $a) (i32.const 1000000000)))
+      (local.set $exponent (i32.sub (local.get $exponent) (i32.const 9)))
+      (br $mul)
+    ))
+
+    (block $break (loop $mul
+      (br_if $break (i32.le_s (local.get $exponent) (i32.const 0)))
+      (local.set $a (call $big_mul_small (local.get $a) (i32.const 10)))
+      (local.set $exponent (i32.sub (local.get $exponent) (i32.const 1)))
+      (br $mul)
+    ))
+
+    (local.get $a)
+  )
+
+  (func $big_shl (param $a (ref $BigDigits)) (param $bits i32) (result (ref $BigDigits))
+    (local $digit_shift i32)
+    (local $bit_shift i64)
+    (local $length i32)
+    (local $result (ref $BigDigits))
+    (local $i i32)
+    (local $value i64)
+
+    (if (i32.eqz (array.len (local.get $a)))
+      (then (return (local.get $a)))
+    )
+
+    (local.set $digit_shift (i32.shr_u (local.get $bits) (i32.const 5)))
+    (local.set $bit_shift (i64.extend_i32_u (i32.and (local.get $bits) (i32.const 31))))
+    (local.set $length (array.len (local.get $a)))
+    (local.set $result
+      (call $big_alloc (i32.add (i32.add (local.get $length) (local.get $digit_shift)) (i32.const 1))))
+
+    (block $break (loop $shift
+      (br_if $break (i32.ge_u (local.get $i) (local.get $length)))
+      (local.set $value
+        (i64.shl
+          (i64.extend_i32_u (array.get $BigDigits (local.get $a) (local.get $i)))
+          (local.get $bit_shift)))
+      ;; the digit is split between two digits of the result
+      (array.set $BigDigits (local.get $result)
+        (i32.add (local.get $i) (local.get $digit_shift))
+        (i32.or
+          (array.get $BigDigits (local.get $result) (i32.add (local.get $i) (local.get $digit_shift)))
+          (i32.wrap_i64 (local.get $value))))
+      (array.set $BigDigits (local.get $result)
+        (i32.add (i32.add (local.get $i) (local.get $digit_shift)) (i32.const 1))
+        (i32.wrap_i64 (i64.shr_u (local.get $value) (i64.const 32))))
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $shift)
+    ))
+
+    (call $big_trim (local.get $result))
+  )
+
+  (func $big_shr (param $a (ref $BigDigits)) (param $bits i32) (result (ref $BigDigits))
+    (local $digit_shift i32)
+    (local $bit_shift i64)
+    (local $length i32)
+    (local $result (ref $BigDigits))
+    (local $i i32)
+
+    (local.set $digit_shift (i32.shr_u (local.get $bits) (i32.const 5)))
+    (local.set $bit_shift (i64.extend_i32_u (i32.and (local.get $bits) (i32.const 31))))
+    (if (i32.ge_u (local.get $digit_shift) (array.len (local.get $a)))
+      (then (return (call $big_alloc (i32.const 0))))
+    )
+
+    (local.set $length (i32.sub (array.len (local.get $a)) (local.get $digit_shift)))
+    (local.set $result (call $big_alloc (local.get $length)))
+
+    (block $break (loop $shift
+      (br_if $break (i32.ge_u (local.get $i) (local.get $length)))
+      (array.set $BigDigits (local.get $result) (local.get $i)
+        (i32.wrap_i64
+          (i64.shr_u
+            (i64.or
+              (call $big_digit (local.get $a) (i32.add (local.get $i) (local.get $digit_shift)))
+              (i64.shl
+                (call $big_digit (local.get $a) (i32.add (i32.add (local.get $i) (local.get $digit_shift)) (i32.const 1)))
+                (i64.const 32)))
+            (local.get $bit_shift))))
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $shift)
+    ))
+
+    (call $big_trim (local.get $result))
+  )
+
+  (func $big_divmod_small (param $a (ref $BigDigits)) (param $divisor i32) (result (ref $BigDigits) i32)
+    (local $result (ref $BigDigits))
+    (local $i i32)
+    (local $remainder i64)
+    (local $value i64)
+
+    (local.set $result (call $big_alloc (array.len (local.get $a))))
+    (local.set $i (array.len (local.get $a)))
+
+    (block $break (loop $divide
+      (br_if $break (i32.eqz (local.get $i)))
+      (local.set $i (i32.sub (local.get $i) (i32.const 1)))
+      (local.set $value
+        (i64.or
+          (i64.shl (local.get $remainder) (i64.const 32))
+          (i64.extend_i32_u (array.get $BigDigits (local.get $a) (local.get $i)))))
+      (array.set $BigDigits (local.get $result) (local.get $i)
+        (i32.wrap_i64 (i64.div_u (local.get $value) (i64.extend_i32_u (local.get $divisor)))))
+      (local.set $remainder (i64.rem_u (local.get $value) (i64.extend_i32_u (local.get $divisor))))
+      (br $divide)
+    ))
+
+    (call $big_trim (local.get $result))
+    (i32.wrap_i64 (local.get $remainder))
+  )
+
+  (func $big_set_bit (param $a (ref $BigDigits)) (param $bit i32) (result (ref $BigDigits))
+    (local $index i32)
+    (local $result (ref $BigDigits))
+
+    (local.set $index (i32.shr_u (local.get $bit) (i32.const 5)))
+    (local.set $result (local.get $a))
+    (if (i32.ge_u (local.get $index) (array.len (local.get $a)))
+      (then
+        (local.set $result (call $big_alloc (i32.add (local.get $index) (i32.const 1))))
+        (array.copy $BigDigits $BigDigits
+          (local.get $result) (i32.const 0)
+          (local.get $a) (i32.const 0)
+          (array.len (local.get $a)))
+      )
+    )
+
+    (array.set $BigDigits (local.get $result) (local.get $index)
+      (i32.or
+        (array.get $BigDigits (local.get $result) (local.get $index))
+        (i32.shl (i32.const 1) (i32.and (local.get $bit) (i32.const 31)))))
+    (local.get $result)
+  )
+
+  ;; long division, one bit at a time. It's slow, but quotients needed for
+  ;; number conversions are at most a few hundred bits long
+  (func $big_divmod (param $a (ref $BigDigits)) (param $b (ref $BigDigits)) (result (ref $BigDigits) (ref $BigDigits))
+    (local $shift i32)
+    (local $divisor (ref $BigDigits))
+    (local $quotient (ref $BigDigits))
+    (local $remainder (ref $BigDigits))
+
+    (local.set $quotient (call $big_alloc (i32.const 0)))
+    (local.set $remainder (local.get $a))
+    (if (i32.lt_s (call $big_compare (local.get $a) (local.get $b)) (i32.const 0))
+      (then
+        (return (local.get $quotient) (local.get $remainder))
+      )
+    )
+
+    (local.set $shift
+      (i32.sub (call $big_bit_length (local.get $a)) (call $big_bit_length (local.get $b))))
+    (local.set $divisor (call $big_shl (local.get $b) (local.get $shift)))
+
+    (block $break (loop $divide
+      (if (i32.ge_s (call $big_compare (local.get $remainder) (local.get $divisor)) (i32.const 0))
+        (then
+          (local.set $remainder (call $big_sub (local.get $remainder) (local.get $divisor)))
+          (local.set $quotient (call $big_set_bit (local.get $quotient) (local.get $shift)))
+        )
+      )
+      (br_if $break (i32.eqz (local.get $shift)))
+      (local.set $shift (i32.sub (local.get $shift) (i32.const 1)))
+      (local.set $divisor (call $big_shr (local.get $divisor) (i32.const 1)))
+      (br $divide)
+    ))
+
+    (local.get $quotient)
+    (local.get $remainder)
+  )
+
+  ;; formats a number in a given radix, digits are lowercase letters above 9
+  (func $big_to_string (param $a (ref $BigDigits)) (param $radix i32) (result (ref $String))
+    (local $digits (ref $CharArray))
+    (local $length i32)
+    (local $digit i32)
+    (local $result (ref $CharArray))
+    (local $i i32)
+
+    (if (call $big_is_zero (local.get $a))
+      (then
+        (return (struct.new $String (array.new $CharArray (i32.const 48) (i32.const 1)) (i32.const 1)))
+      )
+    )
+
+    ;; radix 2 needs the most digits, one for each bit
+    (local.set $digits (array.new_default $CharArray (call $big_bit_length (local.get $a))))
+    (block $break (loop $convert
+      (br_if $break (call $big_is_zero (local.get $a)))
+      (call $big_divmod_small (local.get $a) (local.get $radix))
+      (local.set $digit)
+      (local.set $a)
+      (array.set $CharArray (local.get $digits) (local.get $length) (call $digit_char (local.get $digit)))
+      (local.set $length (i32.add (local.get $length) (i32.const 1)))
+      (br $convert)
+    ))
+
+    ;; digits were generated from the least significant one
+    (local.set $result (array.new_default $CharArray (local.get $length)))
+    (block $break (loop $reverse
+      (br_if $break (i32.ge_u (local.get $i) (local.get $length)))
+      (array.set $CharArray (local.get $result) (local.get $i)
+        (array.get_u $CharArray (local.get $digits)
+          (i32.sub (i32.sub (local.get $length) (local.get $i)) (i32.const 1))))
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $reverse)
+    ))
+
+    (struct.new $String (local.get $result) (local.get $length))
+  )
+
+  (func $digit_char (param $digit i32) (result i32)
+    (if (i32.lt_u (local.get $digit) (i32.const 10))
+      (then (return (i32.add (local.get $digit) (i32.const 48))))
+    )
+    (i32.add (local.get $digit) (i32.const 87))
+  )
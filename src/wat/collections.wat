@@ -0,0 +1,1341 @@
+  ;; `Map`, `Set`, `WeakMap` and `WeakSet`
+  ;;
+  ;; All of them are backed by an $OrderedTable: entries are appended to the
+  ;; $keys and $values arrays, which keeps the insertion order, and a hash index
+  ;; ($buckets and $chains) points into them. A deleted entry is only marked with
+  ;; $no-value, so iterators walking the arrays by index see later additions and
+  ;; skip removed entries, just like the spec requires. Deleted entries are
+  ;; dropped when the table grows.
+  ;;
+  ;; Weak collections hold their keys strongly, WASM GC doesn't have weak
+  ;; references or ephemerons yet
+
+  (type $I32Array (array (mut i32)))
+
+  (type $OrderedTable (struct
+    (field $keys (mut (ref $AnyrefArray)))
+    (field $values (mut (ref $AnyrefArray)))
+    ;; for each entry, the index of the next entry in the same bucket or -1
+    (field $chains (mut (ref $I32Array)))
+    ;; for each bucket, the index of its first entry or -1
+    (field $buckets (mut (ref $I32Array)))
+    ;; number of used entries, including the deleted ones
+    (field $count (mut i32))
+    (field $size (mut i32))
+  ))
+
+  (type $Collection (sub final $Object (struct
+    (field $properties (mut (ref $HashMap)))
+    (field $prototype (mut anyref))
+    (field $hash (mut i32))
+    ;; one of the $collection-* constants
+    (field $kind i32)
+    (field $table (ref $OrderedTable))
+  )))
+
+  (type $CollectionIterator (sub final $Object (struct
+    (field $properties (mut (ref $HashMap)))
+    (field $prototype (mut anyref))
+    (field $hash (mut i32))
+    (field $table (ref $OrderedTable))
+    ;; 0 - keys, 1 - values, 2 - [key, value] pairs
+    (field $kind i32)
+    (field $index (mut i32))
+  )))
+
+  (global $collection-map i32 (i32.const 0))
+  (global $collection-set i32 (i32.const 1))
+  (global $collection-weak-map i32 (i32.const 2))
+  (global $collection-weak-set i32 (i32.const 3))
+
+  (global $map-prototype (mut (ref null $Object)) (ref.null $Object))
+  (global $set-prototype (mut (ref null $Object)) (ref.null $Object))
+  (global $weak-map-prototype (mut (ref null $Object)) (ref.null $Object))
+  (global $weak-set-prototype (mut (ref null $Object)) (ref.null $Object))
+  (global $collection-iterator-prototype (mut (ref null $Object)) (ref.null $Object))
+
+  (global $next-identity-hash (mut i32) (i32.const 0))
+
+  ;; objects don't have an address that could be hashed, so each of them gets a
+  ;; number the first time it's needed
+  (func $identity_hash (param $target anyref) (result i32)
+    (local $hash i32)
+
+    (block $assign
+      (if (ref.test (ref $Object) (local.get $target))
+        (then
+          (local.set $hash (struct.get $Object $hash (ref.cast (ref $Object) (local.get $target))))
+          (br_if $assign (i32.eqz (local.get $hash)))
+          (return (local.get $hash))
+        )
+      )
+      (if (ref.test (ref $Function) (local.get $target))
+        (then
+          (local.set $hash (struct.get $Function $hash (ref.cast (ref $Function) (local.get $target))))
+          (br_if $assign (i32.eqz (local.get $hash)))
+          (return (local.get $hash))
+        )
+      )
+      (if (ref.test (ref $Array) (local.get $target))
+        (then
+          (local.set $hash (struct.get $Array $hash (ref.cast (ref $Array) (local.get $target))))
+          (br_if $assign (i32.eqz (local.get $hash)))
+          (return (local.get $hash))
+        )
+      )
+      (if (ref.test (ref $Promise) (local.get $target))
+        (then
+          (local.set $hash (struct.get $Promise $hash (ref.cast (ref $Promise) (local.get $target))))
+          (br_if $assign (i32.eqz (local.get $hash)))
+          (return (local.get $hash))
+        )
+      )
+      (if (ref.test (ref $Symbol) (local.get $target))
+        (then
+          (local.set $hash (struct.get $Symbol $hash (ref.cast (ref $Symbol) (local.get $target))))
+          (br_if $assign (i32.eqz (local.get $hash)))
+          (return (local.get $hash))
+        )
+      )
+      ;; anything else ends up in a single bucket, which is slow, but correct
+      (return (i32.const 0))
+    )
+
+    (global.set $next-identity-hash (i32.add (global.get $next-identity-hash) (i32.const 1)))
+    (local.set $hash
+      (i32.or (i32.mul (global.get $next-identity-hash) (i32.const 0x9e3779b1)) (i32.const 1)))
+
+    (if (ref.test (ref $Object) (local.get $target))
+      (then (struct.set $Object $hash (ref.cast (ref $Object) (local.get $target)) (local.get $hash)))
+    )
+    (if (ref.test (ref $Function) (local.get $target))
+      (then (struct.set $Function $hash (ref.cast (ref $Function) (local.get $target)) (local.get $hash)))
+    )
+    (if (ref.test (ref $Array) (local.get $target))
+      (then (struct.set $Array $hash (ref.cast (ref $Array) (local.get $target)) (local.get $hash)))
+    )
+    (if (ref.test (ref $Promise) (local.get $target))
+      (then (struct.set $Promise $hash (ref.cast (ref $Promise) (local.get $target)) (local.get $hash)))
+    )
+    (if (ref.test (ref $Symbol) (local.get $target))
+      (then (struct.set $Symbol $hash (ref.cast (ref $Symbol) (local.get $target)) (local.get $hash)))
+    )
+    (local.get $hash)
+  )
+
+  ;; a hash consistent with SameValueZero: numbers are hashed by value (so an
+  ;; i31ref and a boxed $Number are the same key and 0 is the same as -0),
+  ;; strings and BigInts by content and everything else by identity
+  (func $collection_hash (param $key anyref) (result i32)
+    (local $value f64)
+    (local $bits i64)
+
+    (if (call $is_number (local.get $key))
+      (then
+        (local.set $value (call $number_value (local.get $key)))
+        (if (f64.eq (local.get $value) (f64.const 0))
+          (then (return (i32.const 0)))
+        )
+        (if (f64.ne (local.get $value) (local.get $value))
+          (then (return (i32.const 0x7ff80000)))
+        )
+        (local.set $bits (i64.reinterpret_f64 (local.get $value)))
+        (return
+          (i32.mul
+            (i32.wrap_i64 (i64.xor (local.get $bits) (i64.shr_u (local.get $bits) (i64.const 32))))
+            (i32.const 0x85ebca6b)))
+      )
+    )
+
+    (if (call $is_string (local.get $key))
+      (then (return (call $string_hash (local.get $key))))
+    )
+
+    (if (call $is_bigint (local.get $key))
+      (then
+        (return
+          (i32.mul
+            (i32.wrap_i64 (call $big_to_u64 (struct.get $BigInt $digits (ref.cast (ref $BigInt) (local.get $key)))))
+            (i32.const 0x85ebca6b)))
+      )
+    )
+
+    (if (ref.test (ref $Boolean) (local.get $key))
+      (then
+        (return
+          (i32.add (i32.const 0x100) (struct.get $Boolean $value (ref.cast (ref $Boolean) (local.get $key)))))
+      )
+    )
+
+    (if (ref.is_null (local.get $key))
+      (then (return (i32.const 0x200)))
+    )
+
+    (if (ref.test (ref $Null) (local.get $key))
+      (then (return (i32.const 0x300)))
+    )
+
+    (call $identity_hash (local.get $key))
+  )
+
+  (func $same_value_zero (param $arg1 anyref) (param $arg2 anyref) (result i32)
+    (local $num1 f64)
+    (local $num2 f64)
+
+    (if (i32.and
+          (call $is_number (local.get $arg1))
+          (call $is_number (local.get $arg2)))
+      (then
+        (local.set $num1 (call $number_value (local.get $arg1)))
+        (local.set $num2 (call $number_value (local.get $arg2)))
+        (return
+          (i32.or
+            (f64.eq (local.get $num1) (local.get $num2))
+            (i32.and
+              (f64.ne (local.get $num1) (local.get $num1))
+              (f64.ne (local.get $num2) (local.get $num2)))))
+      )
+    )
+
+    (call $same_value_non_number (local.get $arg1) (local.get $arg2))
+  )
+
+  ;; $capacity has to be a power of 2
+  (func $new_ordered_table (param $capacity i32) (result (ref $OrderedTable))
+    (struct.new $OrderedTable
+      (array.new $AnyrefArray (ref.null any) (local.get $capacity))
+      (array.new $AnyrefArray (ref.null any) (local.get $capacity))
+      (array.new $I32Array (i32.const -1) (local.get $capacity))
+      (array.new $I32Array (i32.const -1) (local.get $capacity))
+      (i32.const 0)
+      (i32.const 0))
+  )
+
+  (func $table_bucket (param $table (ref $OrderedTable)) (param $key anyref) (result i32)
+    (local $hash i32)
+    (local.set $hash (call $collection_hash (local.get $key)))
+    (i32.and
+      (i32.xor (local.get $hash) (i32.shr_u (local.get $hash) (i32.const 16)))
+      (i32.sub (array.len (struct.get $OrderedTable $buckets (local.get $table))) (i32.const 1)))
+  )
+
+  ;; returns the index of the entry with the given key or -1
+  (func $table_find (param $table (ref $OrderedTable)) (param $key anyref) (result i32)
+    (local $keys (ref $AnyrefArray))
+    (local $chains (ref $I32Array))
+    (local $index i32)
+
+    (local.set $keys (struct.get $OrderedTable $keys (local.get $table)))
+    (local.set $chains (struct.get $OrderedTable $chains (local.get $table)))
+    (local.set $index
+      (array.get $I32Array
+        (struct.get $OrderedTable $buckets (local.get $table))
+        (call $table_bucket (local.get $table) (local.get $key))))
+
+    ;; deleted entries stay in their chains, but $no-value is never equal to a key
+    (block $done (loop $chain
+      (br_if $done (i32.lt_s (local.get $index) (i32.const 0)))
+      (if (call $same_value_zero
+            (array.get $AnyrefArray (local.get $keys) (local.get $index))
+            (local.get $key))
+        (then (return (local.get $index)))
+      )
+      (local.set $index (array.get $I32Array (local.get $chains) (local.get $index)))
+      (br $chain)
+    ))
+
+    (i32.const -1)
+  )
+
+  ;; copies the live entries into arrays of a new capacity and rebuilds the index
+  (func $table_rehash (param $table (ref $OrderedTable)) (param $capacity i32)
+    (local $old_keys (ref $AnyrefArray))
+    (local $old_values (ref $AnyrefArray))
+    (local $count i32)
+    (local $key anyref)
+    (local $i i32)
+    (local $j i32)
+    (local $bucket i32)
+
+    (local.set $old_keys (struct.get $OrderedTable $keys (local.get $table)))
+    (local.set $old_values (struct.get $OrderedTable $values (local.get $table)))
+    (local.set $count (struct.get $OrderedTable $count (local.get $table)))
+
+    (struct.set $OrderedTable $keys (local.get $table)
+      (array.new $AnyrefArray (ref.null any) (local.get $capacity)))
+    (struct.set $OrderedTable $values (local.get $table)
+      (array.new $AnyrefArray (ref.null any) (local.get $capacity)))
+    (struct.set $OrderedTable $chains (local.get $table)
+      (array.new $I32Array (i32.const -1) (local.get $capacity)))
+    (struct.set $OrderedTable $buckets (local.get $table)
+      (array.new $I32Array (i32.const -1) (local.get $capacity)))
+
+    (block $break (loop $entries
+      (br_if $break (i32.ge_u (local.get $i) (local.get $count)))
+      (local.set $key (array.get $AnyrefArray (local.get $old_keys) (local.get $i)))
+
+      (if (i32.eqz (call $is_no_value_found (local.get $key)))
+        (then
+          (local.set $bucket (call $table_bucket (local.get $table) (local.get $key)))
+          (array.set $AnyrefArray (struct.get $OrderedTable $keys (local.get $table))
+            (local.get $j) (local.get $key))
+          (array.set $AnyrefArray (struct.get $OrderedTable $values (local.get $table))
+            (local.get $j) (array.get $AnyrefArray (local.get $old_values) (local.get $i)))
+          (array.set $I32Array (struct.get $OrderedTable $chains (local.get $table))
+            (local.get $j)
+            (array.get $I32Array (struct.get $OrderedTable $buckets (local.get $table)) (local.get $bucket)))
+          (array.set $I32Array (struct.get $OrderedTable $buckets (local.get $table))
+            (local.get $bucket) (local.get $j))
+          (local.set $j (i32.add (local.get $j) (i32.const 1)))
+        )
+      )
+
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $entries)
+    ))
+
+    (struct.set $OrderedTable $count (local.get $table) (local.get $j))
+  )
+
+  (func $table_set (param $table (ref $OrderedTable)) (param $key anyref) (param $value anyref)
+    (local $index i32)
+    (local $capacity i32)
+    (local $bucket i32)
+
+    (local.set $index (call $table_find (local.get $table) (local.get $key)))
+    (if (i32.ge_s (local.get $index) (i32.const 0))
+      (then
+        (array.set $AnyrefArray (struct.get $OrderedTable $values (local.get $table))
+          (local.get $index) (local.get $value))
+        (return)
+      )
+    )
+
+    ;; -0 is stored as 0
+    (if (call $is_number (local.get $key))
+      (then
+        (if (f64.eq (call $number_value (local.get $key)) (f64.const 0))
+          (then (local.set $key (ref.i31 (i32.const 0))))
+        )
+      )
+    )
+
+    (local.set $capacity (array.len (struct.get $OrderedTable $keys (local.get $table))))
+    (if (i32.eq (struct.get $OrderedTable $count (local.get $table)) (local.get $capacity))
+      (then
+        ;; only grow if the table is at least half full, otherwise dropping the
+        ;; deleted entries makes enough space
+        (if (i32.ge_u
+              (i32.shl (struct.get $OrderedTable $size (local.get $table)) (i32.const 1))
+              (local.get $capacity))
+          (then (local.set $capacity (i32.shl (local.get $capacity) (i32.const 1))))
+        )
+        (call $table_rehash (local.get $table) (local.get $capacity))
+      )
+    )
+
+    (local.set $index (struct.get $OrderedTable $count (local.get $table)))
+    (local.set $bucket (call $table_bucket (local.get $table) (local.get $key)))
+    (array.set $AnyrefArray (struct.get $OrderedTable $keys (local.get $table))
+      (local.get $index) (local.get $key))
+    (array.set $AnyrefArray (struct.get $OrderedTable $values (local.get $table))
+      (local.get $index) (local.get $value))
+    (array.set $I32Array (struct.get $OrderedTable $chains (local.get $table))
+      (local.get $index)
+      (array.get $I32Array (struct.get $OrderedTable $buckets (local.get $table)) (local.get $bucket)))
+    (array.set $I32Array (struct.get $OrderedTable $buckets (local.get $table))
+      (local.get $bucket) (local.get $index))
+
+    (struct.set $OrderedTable $count (local.get $table) (i32.add (local.get $index) (i32.const 1)))
+    (struct.set $OrderedTable $size (local.get $table)
+      (i32.add (struct.get $OrderedTable $size (local.get $table)) (i32.const 1)))
+  )
+
+  (func $table_delete (param $table (ref $OrderedTable)) (param $key anyref) (result i32)
+    (local $index i32)
+
+    (local.set $index (call $table_find (local.get $table) (local.get $key)))
+    (if (i32.lt_s (local.get $index) (i32.const 0))
+      (then (return (i32.const 0)))
+    )
+
+    (array.set $AnyrefArray (struct.get $OrderedTable $keys (local.get $table))
+      (local.get $index) (global.get $no-value))
+    (array.set $AnyrefArray (struct.get $OrderedTable $values (local.get $table))
+      (local.get $index) (ref.null any))
+    (struct.set $OrderedTable $size (local.get $table)
+      (i32.sub (struct.get $OrderedTable $size (local.get $table)) (i32.const 1)))
+    (i32.const 1)
+  )
+
+  (func $table_clear (param $table (ref $OrderedTable))
+    (local $i i32)
+
+    ;; existing iterators see all of the entries as deleted
+    (block $break (loop $entries
+      (br_if $break (i32.ge_u (local.get $i) (struct.get $OrderedTable $count (local.get $table))))
+      (array.set $AnyrefArray (struct.get $OrderedTable $keys (local.get $table))
+        (local.get $i) (global.get $no-value))
+      (array.set $AnyrefArray (struct.get $OrderedTable $values (local.get $table))
+        (local.get $i) (ref.null any))
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $entries)
+    ))
+    (struct.set $OrderedTable $size (local.get $table) (i32.const 0))
+  )
+
+  (func $new_collection (param $kind i32) (param $prototype anyref) (result (ref $Collection))
+    (struct.new $Collection
+      (call $new_hashmap)
+      (local.get $prototype)
+      (i32.const 0)
+      (local.get $kind)
+      (call $new_ordered_table (i32.const 8)))
+  )
+
+  ;; the collection passed as `this` to a prototype method, throws if it's
+  ;; something else
+  (func $this_collection (param $this anyref) (param $kind i32) (result (ref $Collection))
+    (if (ref.test (ref $Collection) (local.get $this))
+      (then
+        (if (i32.eq
+              (struct.get $Collection $kind (ref.cast (ref $Collection) (local.get $this)))
+              (local.get $kind))
+          (then (return (ref.cast (ref $Collection) (local.get $this))))
+        )
+      )
+    )
+
+    (if (i32.eq (local.get $kind) (global.get $collection-map))
+      (then
+        (call $throw_type_error
+          (call $new_static_string (i32.const {{ data(str="Map.prototype method called on incompatible receiver") }}) (i32.const {{ data_length() }})))
+      )
+    )
+    (if (i32.eq (local.get $kind) (global.get $collection-set))
+      (then
+        (call $throw_type_error
+          (call $new_static_string (i32.const {{ data(str="Set.prototype method called on incompatible receiver") }}) (i32.const {{ data_length() }})))
+      )
+    )
+    (if (i32.eq (local.get $kind) (global.get $collection-weak-map))
+      (then
+        (call $throw_type_error
+          (call $new_static_string (i32.const {{ data(str="WeakMap.prototype method called on incompatible receiver") }}) (i32.const {{ data_length() }})))
+      )
+    )
+    (call $throw_type_error
+      (call $new_static_string (i32.const {{ data(str="WeakSet.prototype method called on incompatible receiver") }}) (i32.const {{ data_length() }})))
+    (unreachable)
+  )
+
+  (func $collection_table (param $this anyref) (param $kind i32) (result (ref $OrderedTable))
+    (struct.get $Collection $table (call $this_collection (local.get $this) (local.get $kind)))
+  )
+
+  ;; the elements of an iterable as an array, collections are iterated the same
+  ;; way their default iterators would
+  (func $iterable_to_array (param $iterable anyref) (result (ref $Array))
+    (local $collection (ref $Collection))
+    (local $table (ref $OrderedTable))
+    (local $result (ref $Array))
+    (local $key anyref)
+    (local $method anyref)
+    (local $iterator anyref)
+    (local $next anyref)
+    (local $step anyref)
+    (local $count i32)
+    (local $length i32)
+    (local $i i32)
+
+    (if (ref.test (ref $Array) (local.get $iterable))
+      (then (return (ref.cast (ref $Array) (local.get $iterable))))
+    )
+
+    (if (ref.test (ref $TypedArray) (local.get $iterable))
+      (then (return (call $typed_array_to_array (ref.cast (ref $TypedArray) (local.get $iterable)))))
+    )
+
+    (if (ref.test (ref $Collection) (local.get $iterable))
+      (then
+        (local.set $collection (ref.cast (ref $Collection) (local.get $iterable)))
+        (local.set $table (struct.get $Collection $table (local.get $collection)))
+        (local.set $result (call $new_array (struct.get $OrderedTable $size (local.get $table))))
+        (block $break (loop $entries
+          (br_if $break (i32.ge_u (local.get $i) (struct.get $OrderedTable $count (local.get $table))))
+          (local.set $key (array.get $AnyrefArray (struct.get $OrderedTable $keys (local.get $table)) (local.get $i)))
+          (if (i32.eqz (call $is_no_value_found (local.get $key)))
+            (then
+              (call $array_set (local.get $result) (local.get $count)
+                (if (result anyref) (i32.eq (struct.get $Collection $kind (local.get $collection)) (global.get $collection-map))
+                  (then
+                    (call $collection_entry
+                      (local.get $key)
+                      (array.get $AnyrefArray (struct.get $OrderedTable $values (local.get $table)) (local.get $i))))
+                  (else (local.get $key))))
+              (local.set $count (i32.add (local.get $count) (i32.const 1)))
+            )
+          )
+          (local.set $i (i32.add (local.get $i) (i32.const 1)))
+          (br $entries)
+        ))
+        (return (local.get $result))
+      )
+    )
+
+    ;; strings are iterated by code points
+    (if (call $is_string (local.get $iterable))
+      (then
+        (local.set $length (call $string_length (local.get $iterable)))
+        (local.set $result (call $new_array (i32.const 0)))
+        (block $break (loop $chars
+          (br_if $break (i32.ge_u (local.get $i) (local.get $length)))
+          (call $array_set (local.get $result) (local.get $count)
+            (call $code_point_at (local.get $iterable) (local.get $i)))
+          (local.set $i
+            (i32.add (local.get $i)
+              (call $utf8_sequence_length (call $string_byte_at (local.get $iterable) (local.get $i)))))
+          (local.set $count (i32.add (local.get $count) (i32.const 1)))
+          (br $chars)
+        ))
+        (return (local.get $result))
+      )
+    )
+
+    ;; anything else has to implement the iterator protocol
+    (if (call $is_object (local.get $iterable))
+      (then
+        (local.set $method
+          (call $get_property
+            (local.get $iterable)
+            (call $well_known_symbol_key (global.get $symbol-iterator))))
+        (if (ref.test (ref $Function) (local.get $method))
+          (then
+            (local.set $iterator
+              (call $call_function (local.get $method) (local.get $iterable) (array.new_fixed $JSArgs 0)))
+            (if (i32.eqz (call $is_object (local.get $iterator)))
+              (then
+                (call $throw_type_error
+                  (call $new_static_string (i32.const {{ data(str="Result of the Symbol.iterator method is not an object") }}) (i32.const {{ data_length() }})))
+              )
+            )
+            (local.set $next (call $get_property (local.get $iterator) (i32.const {{ data(str="next") }})))
+            (local.set $result (call $new_array (i32.const 0)))
+            (block $break (loop $steps
+              (if (i32.eqz (ref.test (ref $Function) (local.get $next)))
+                (then
+                  (call $throw_type_error
+                    (call $new_static_string (i32.const {{ data(str="iterator.next is not a function") }}) (i32.const {{ data_length() }})))
+                )
+              )
+              (local.set $step
+                (call $call_function (local.get $next) (local.get $iterator) (array.new_fixed $JSArgs 0)))
+              (if (i32.eqz (call $is_object (local.get $step)))
+                (then
+                  (call $throw_type_error
+                    (call $new_static_string (i32.const {{ data(str="Iterator result is not an object") }}) (i32.const {{ data_length() }})))
+                )
+              )
+              (br_if $break
+                (call $cast_ref_to_i32_bool
+                  (call $get_property (local.get $step) (i32.const {{ data(str="done") }}))))
+              (call $array_set (local.get $result) (local.get $count)
+                (call $get_property (local.get $step) (i32.const {{ data(str="value") }})))
+              (local.set $count (i32.add (local.get $count) (i32.const 1)))
+              (br $steps)
+            ))
+            (return (local.get $result))
+          )
+        )
+      )
+    )
+
+    (call $throw_type_error
+      (call $new_static_string (i32.const {{ data(str="object is not iterable") }}) (i32.const {{ data_length() }})))
+    (unreachable)
+  )
+
+  ;; the number of bytes in a UTF-8 sequence starting with the given byte
+  (func $utf8_sequence_length (param $byte i32) (result i32)
+    (if (i32.lt_u (local.get $byte) (i32.const 0xc0))
+      (then (return (i32.const 1)))
+    )
+    (if (i32.lt_u (local.get $byte) (i32.const 0xe0))
+      (then (return (i32.const 2)))
+    )
+    (if (i32.lt_u (local.get $byte) (i32.const 0xf0))
+      (then (return (i32.const 3)))
+    )
+    (i32.const 4)
+  )
+
+  ;; a string with the single code point starting at the given byte
+  (func $code_point_at (param $str anyref) (param $index i32) (result (ref $String))
+    (local $length i32)
+    (local $data (ref $CharArray))
+    (local $i i32)
+
+    (local.set $length
+      (call $utf8_sequence_length (call $string_byte_at (local.get $str) (local.get $index))))
+    (if (i32.gt_u
+          (i32.add (local.get $index) (local.get $length))
+          (call $string_length (local.get $str)))
+      (then
+        (local.set $length (i32.sub (call $string_length (local.get $str)) (local.get $index)))
+      )
+    )
+
+    (local.set $data (array.new_default $CharArray (local.get $length)))
+    (block $break (loop $copy
+      (br_if $break (i32.ge_u (local.get $i) (local.get $length)))
+      (array.set $CharArray (local.get $data) (local.get $i)
+        (call $string_byte_at (local.get $str) (i32.add (local.get $index) (local.get $i))))
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $copy)
+    ))
+    (struct.new $String (local.get $data) (local.get $length))
+  )
+
+  (func $collection_entry (param $key anyref) (param $value anyref) (result (ref $Array))
+    (struct.new $Array
+      (array.new_fixed $AnyrefArray 2 (local.get $key) (local.get $value))
+      (ref.null $HashMap)
+      (i32.const 0))
+  )
+
+  ;; weak collections only accept objects as keys
+  ;; objects and symbols that are not in the Symbol.for registry
+  (func $can_be_held_weakly (param $value anyref) (result i32)
+    (if (call $is_symbol (local.get $value))
+      (then
+        (return (i32.eqz (struct.get $Symbol $registered (ref.cast (ref $Symbol) (local.get $value)))))
+      )
+    )
+    (call $is_object (local.get $value))
+  )
+
+  ;; the shared part of the constructors, $this is the object created by `new`
+  (func $construct_collection (param $this anyref) (param $kind i32) (param $prototype anyref) (param $iterable anyref) (result (ref $Collection))
+    (local $collection (ref $Collection))
+    (local $table (ref $OrderedTable))
+    (local $items (ref $Array))
+    (local $item anyref)
+    (local $key anyref)
+    (local $length i32)
+    (local $i i32)
+
+    (if (i32.eqz (ref.test (ref $Object) (local.get $this)))
+      (then
+        (call $throw_type_error
+          (call $new_static_string (i32.const {{ data(str="Constructor requires 'new'") }}) (i32.const {{ data_length() }})))
+      )
+    )
+
+    (local.set $collection (call $new_collection (local.get $kind) (local.get $prototype)))
+    (local.set $table (struct.get $Collection $table (local.get $collection)))
+
+    (if (i32.or
+          (ref.is_null (local.get $iterable))
+          (call $is_null (local.get $iterable)))
+      (then (return (local.get $collection)))
+    )
+
+    (local.set $items (call $iterable_to_array (local.get $iterable)))
+    (local.set $length (array.len (struct.get $Array $array (local.get $items))))
+    (block $break (loop $items
+      (br_if $break (i32.ge_u (local.get $i) (local.get $length)))
+      (local.set $item (call $array_get (local.get $items) (local.get $i)))
+
+      (if (i32.or
+            (i32.eq (local.get $kind) (global.get $collection-map))
+            (i32.eq (local.get $kind) (global.get $collection-weak-map)))
+        (then
+          (if (i32.eqz (call $is_object (local.get $item)))
+            (then
+              (call $throw_type_error
+                (call $new_static_string (i32.const {{ data(str="Iterator value is not an entry object") }}) (i32.const {{ data_length() }})))
+            )
+          )
+          (local.set $key (call $get_computed_property (local.get $item) (ref.i31 (i32.const 0))))
+          (if (i32.eq (local.get $kind) (global.get $collection-weak-map))
+            (then (call $check_weak_map_key (local.get $key)))
+          )
+          (call $table_set
+            (local.get $table)
+            (local.get $key)
+            (call $get_computed_property (local.get $item) (ref.i31 (i32.const 1))))
+        )
+        (else
+          (if (i32.eq (local.get $kind) (global.get $collection-weak-set))
+            (then (call $check_weak_set_value (local.get $item)))
+          )
+          (call $table_set (local.get $table) (local.get $item) (local.get $item))
+        )
+      )
+
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $items)
+    ))
+
+    (local.get $collection)
+  )
+
+  (func $check_weak_map_key (param $key anyref)
+    (if (i32.eqz (call $can_be_held_weakly (local.get $key)))
+      (then
+        (call $throw_type_error
+          (call $new_static_string (i32.const {{ data(str="Invalid value used as weak map key") }}) (i32.const {{ data_length() }})))
+      )
+    )
+  )
+
+  (func $check_weak_set_value (param $value anyref)
+    (if (i32.eqz (call $can_be_held_weakly (local.get $value)))
+      (then
+        (call $throw_type_error
+          (call $new_static_string (i32.const {{ data(str="Invalid value used in weak set") }}) (i32.const {{ data_length() }})))
+      )
+    )
+  )
+
+  (func $table_get (param $table (ref $OrderedTable)) (param $key anyref) (result anyref)
+    (local $index i32)
+    (local.set $index (call $table_find (local.get $table) (local.get $key)))
+    (if (i32.lt_s (local.get $index) (i32.const 0))
+      (then (return (ref.null any)))
+    )
+    (array.get $AnyrefArray (struct.get $OrderedTable $values (local.get $table)) (local.get $index))
+  )
+
+  ;; Map.prototype.forEach and Set.prototype.forEach, for sets the values are
+  ;; the same as keys
+  (func $collection_for_each (param $collection (ref $Collection)) (param $arguments (ref $JSArgs))
+    (local $table (ref $OrderedTable))
+    (local $callback anyref)
+    (local $this_arg anyref)
+    (local $key anyref)
+    (local $i i32)
+
+    (local.set $table (struct.get $Collection $table (local.get $collection)))
+    (local.set $callback (call $get_argument (local.get $arguments) (i32.const 0)))
+    (local.set $this_arg (call $get_argument (local.get $arguments) (i32.const 1)))
+    (if (i32.eqz (ref.test (ref $Function) (local.get $callback)))
+      (then
+        (call $throw_type_error
+          (call $new_static_string (i32.const {{ data(str="forEach callback is not a function") }}) (i32.const {{ data_length() }})))
+      )
+    )
+
+    ;; the count is checked on every iteration, so entries added by the
+    ;; callback are visited too
+    (block $break (loop $entries
+      (br_if $break (i32.ge_u (local.get $i) (struct.get $OrderedTable $count (local.get $table))))
+      (local.set $key (array.get $AnyrefArray (struct.get $OrderedTable $keys (local.get $table)) (local.get $i)))
+      (if (i32.eqz (call $is_no_value_found (local.get $key)))
+        (then
+          (drop
+            (call $call_function
+              (local.get $callback)
+              (local.get $this_arg)
+              (array.new_fixed $JSArgs 3
+                (array.get $AnyrefArray (struct.get $OrderedTable $values (local.get $table)) (local.get $i))
+                (local.get $key)
+                (local.get $collection))))
+        )
+      )
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $entries)
+    ))
+  )
+
+  (func $new_collection_iterator (param $collection (ref $Collection)) (param $kind i32) (result (ref $CollectionIterator))
+    (struct.new $CollectionIterator
+      (call $new_hashmap)
+      (global.get $collection-iterator-prototype)
+      (i32.const 0)
+      (struct.get $Collection $table (local.get $collection))
+      (local.get $kind)
+      (i32.const 0))
+  )
+
+  ;; { value, done } objects returned by iterators
+  (func $new_iterator_result (param $value anyref) (param $done i32) (result (ref $Object))
+    (local $result (ref $Object))
+    (local.set $result (call $new_object))
+    (call $set_property (local.get $result) (i32.const {{ data(str="value") }}) (local.get $value))
+    (call $set_property (local.get $result) (i32.const {{ data(str="done") }}) (call $new_boolean (local.get $done)))
+    (local.get $result)
+  )
+
+  ;; %IteratorPrototype%[Symbol.iterator], returns the iterator itself
+  (func $CollectionIterator-iterator
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+
+    (local.get $this)
+  )
+
+  (func $CollectionIterator-next
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (local $iterator (ref $CollectionIterator))
+    (local $table (ref $OrderedTable))
+    (local $key anyref)
+    (local $value anyref)
+    (local $index i32)
+
+    (if (i32.eqz (ref.test (ref $CollectionIterator) (local.get $this)))
+      (then
+        (call $throw_type_error
+          (call $new_static_string (i32.const {{ data(str="next method called on incompatible receiver") }}) (i32.const {{ data_length() }})))
+      )
+    )
+    (local.set $iterator (ref.cast (ref $CollectionIterator) (local.get $this)))
+    (local.set $table (struct.get $CollectionIterator $table (local.get $iterator)))
+    (local.set $index (struct.get $CollectionIterator $index (local.get $iterator)))
+
+    (block $done (loop $entries
+      (br_if $done (i32.ge_u (local.get $index) (struct.get $OrderedTable $count (local.get $table))))
+      (local.set $key (array.get $AnyrefArray (struct.get $OrderedTable $keys (local.get $table)) (local.get $index)))
+      (local.set $index (i32.add (local.get $index) (i32.const 1)))
+      (br_if $entries (call $is_no_value_found (local.get $key)))
+
+      (struct.set $CollectionIterator $index (local.get $iterator) (local.get $index))
+      (local.set $value
+        (array.get $AnyrefArray
+          (struct.get $OrderedTable $values (local.get $table))
+          (i32.sub (local.get $index) (i32.const 1))))
+      (if (i32.eqz (struct.get $CollectionIterator $kind (local.get $iterator)))
+        (then (return (call $new_iterator_result (local.get $key) (i32.const 0))))
+      )
+      (if (i32.eq (struct.get $CollectionIterator $kind (local.get $iterator)) (i32.const 1))
+        (then (return (call $new_iterator_result (local.get $value) (i32.const 0))))
+      )
+      (return
+        (call $new_iterator_result
+          (call $collection_entry (local.get $key) (local.get $value))
+          (i32.const 0)))
+    ))
+
+    ;; a finished iterator stays finished, even if new entries are added
+    (struct.set $CollectionIterator $index (local.get $iterator) (i32.const 0x7fffffff))
+    (call $new_iterator_result (ref.null any) (i32.const 1))
+  )
+
+  (func $Map-constructor
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+
+    (call $construct_collection
+      (local.get $this)
+      (global.get $collection-map)
+      (global.get $map-prototype)
+      (call $get_argument (local.get $arguments) (i32.const 0)))
+  )
+
+  (func $Map-prototype-get
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+
+    (call $table_get
+      (call $collection_table (local.get $this) (global.get $collection-map))
+      (call $get_argument (local.get $arguments) (i32.const 0)))
+  )
+
+  (func $Map-prototype-set
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+
+    (call $table_set
+      (call $collection_table (local.get $this) (global.get $collection-map))
+      (call $get_argument (local.get $arguments) (i32.const 0))
+      (call $get_argument (local.get $arguments) (i32.const 1)))
+    (local.get $this)
+  )
+
+  (func $Map-prototype-has
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+
+    (call $new_boolean
+      (i32.ge_s
+        (call $table_find
+          (call $collection_table (local.get $this) (global.get $collection-map))
+          (call $get_argument (local.get $arguments) (i32.const 0)))
+        (i32.const 0)))
+  )
+
+  (func $Map-prototype-delete
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+
+    (call $new_boolean
+      (call $table_delete
+        (call $collection_table (local.get $this) (global.get $collection-map))
+        (call $get_argument (local.get $arguments) (i32.const 0))))
+  )
+
+  (func $Map-prototype-clear
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+
+    (call $table_clear (call $collection_table (local.get $this) (global.get $collection-map)))
+    (ref.null any)
+  )
+
+  (func $Map-prototype-forEach
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+
+    (call $collection_for_each
+      (call $this_collection (local.get $this) (global.get $collection-map))
+      (local.get $arguments))
+    (ref.null any)
+  )
+
+  (func $Map-prototype-keys
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+
+    (call $new_collection_iterator
+      (call $this_collection (local.get $this) (global.get $collection-map))
+      (i32.const 0))
+  )
+
+  (func $Map-prototype-values
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+
+    (call $new_collection_iterator
+      (call $this_collection (local.get $this) (global.get $collection-map))
+      (i32.const 1))
+  )
+
+  (func $Map-prototype-entries
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+
+    (call $new_collection_iterator
+      (call $this_collection (local.get $this) (global.get $collection-map))
+      (i32.const 2))
+  )
+
+  (func $Set-constructor
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+
+    (call $construct_collection
+      (local.get $this)
+      (global.get $collection-set)
+      (global.get $set-prototype)
+      (call $get_argument (local.get $arguments) (i32.const 0)))
+  )
+
+  (func $Set-prototype-add
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (local $value anyref)
+
+    ;; sets keep the value as both the key and the value of an entry
+    (local.set $value (call $get_argument (local.get $arguments) (i32.const 0)))
+    (call $table_set
+      (call $collection_table (local.get $this) (global.get $collection-set))
+      (local.get $value)
+      (local.get $value))
+    (local.get $this)
+  )
+
+  (func $Set-prototype-has
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+
+    (call $new_boolean
+      (i32.ge_s
+        (call $table_find
+          (call $collection_table (local.get $this) (global.get $collection-set))
+          (call $get_argument (local.get $arguments) (i32.const 0)))
+        (i32.const 0)))
+  )
+
+  (func $Set-prototype-delete
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+
+    (call $new_boolean
+      (call $table_delete
+        (call $collection_table (local.get $this) (global.get $collection-set))
+        (call $get_argument (local.get $arguments) (i32.const 0))))
+  )
+
+  (func $Set-prototype-clear
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+
+    (call $table_clear (call $collection_table (local.get $this) (global.get $collection-set)))
+    (ref.null any)
+  )
+
+  (func $Set-prototype-forEach
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+
+    (call $collection_for_each
+      (call $this_collection (local.get $this) (global.get $collection-set))
+      (local.get $arguments))
+    (ref.null any)
+  )
+
+  (func $Set-prototype-values
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+
+    (call $new_collection_iterator
+      (call $this_collection (local.get $this) (global.get $collection-set))
+      (i32.const 1))
+  )
+
+  (func $Set-prototype-entries
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+
+    (call $new_collection_iterator
+      (call $this_collection (local.get $this) (global.get $collection-set))
+      (i32.const 2))
+  )
+
+  (func $WeakMap-constructor
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+
+    (call $construct_collection
+      (local.get $this)
+      (global.get $collection-weak-map)
+      (global.get $weak-map-prototype)
+      (call $get_argument (local.get $arguments) (i32.const 0)))
+  )
+
+  (func $WeakMap-prototype-get
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+
+    (call $table_get
+      (call $collection_table (local.get $this) (global.get $collection-weak-map))
+      (call $get_argument (local.get $arguments) (i32.const 0)))
+  )
+
+  (func $WeakMap-prototype-set
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (local $table (ref $OrderedTable))
+    (local $key anyref)
+
+    (local.set $table (call $collection_table (local.get $this) (global.get $collection-weak-map)))
+    (local.set $key (call $get_argument (local.get $arguments) (i32.const 0)))
+    (call $check_weak_map_key (local.get $key))
+    (call $table_set
+      (local.get $table)
+      (local.get $key)
+      (call $get_argument (local.get $arguments) (i32.const 1)))
+    (local.get $this)
+  )
+
+  (func $WeakMap-prototype-has
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+
+    (call $new_boolean
+      (i32.ge_s
+        (call $table_find
+          (call $collection_table (local.get $this) (global.get $collection-weak-map))
+          (call $get_argument (local.get $arguments) (i32.const 0)))
+        (i32.const 0)))
+  )
+
+  (func $WeakMap-prototype-delete
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+
+    (call $new_boolean
+      (call $table_delete
+        (call $collection_table (local.get $this) (global.get $collection-weak-map))
+        (call $get_argument (local.get $arguments) (i32.const 0))))
+  )
+
+  (func $WeakSet-constructor
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+
+    (call $construct_collection
+      (local.get $this)
+      (global.get $collection-weak-set)
+      (global.get $weak-set-prototype)
+      (call $get_argument (local.get $arguments) (i32.const 0)))
+  )
+
+  (func $WeakSet-prototype-add
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (local $table (ref $OrderedTable))
+    (local $value anyref)
+
+    (local.set $table (call $collection_table (local.get $this) (global.get $collection-weak-set)))
+    (local.set $value (call $get_argument (local.get $arguments) (i32.const 0)))
+    (call $check_weak_set_value (local.get $value))
+    (call $table_set (local.get $table) (local.get $value) (local.get $value))
+    (local.get $this)
+  )
+
+  (func $WeakSet-prototype-has
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+
+    (call $new_boolean
+      (i32.ge_s
+        (call $table_find
+          (call $collection_table (local.get $this) (global.get $collection-weak-set))
+          (call $get_argument (local.get $arguments) (i32.const 0)))
+        (i32.const 0)))
+  )
+
+  (func $WeakSet-prototype-delete
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+
+    (call $new_boolean
+      (call $table_delete
+        (call $collection_table (local.get $this) (global.get $collection-weak-set))
+        (call $get_argument (local.get $arguments) (i32.const 0))))
+  )
+
+  ;; `size` is an accessor in the spec, $get_property handles it like `length`
+  (func $collection_size (param $target anyref) (result anyref)
+    (local $collection (ref $Collection))
+
+    (if (ref.test (ref $Collection) (local.get $target))
+      (then
+        (local.set $collection (ref.cast (ref $Collection) (local.get $target)))
+        (if (i32.le_u (struct.get $Collection $kind (local.get $collection)) (global.get $collection-set))
+          (then
+            (return
+              (call $new_number_i32
+                (struct.get $OrderedTable $size (struct.get $Collection $table (local.get $collection)))))
+          )
+        )
+      )
+    )
+
+    (global.get $no-value)
+  )
+
+  (elem declare func $CollectionIterator-iterator)
+  (elem declare func $CollectionIterator-next)
+  (elem declare func $Map-constructor)
+  (elem declare func $Map-prototype-get)
+  (elem declare func $Map-prototype-set)
+  (elem declare func $Map-prototype-has)
+  (elem declare func $Map-prototype-delete)
+  (elem declare func $Map-prototype-clear)
+  (elem declare func $Map-prototype-forEach)
+  (elem declare func $Map-prototype-keys)
+  (elem declare func $Map-prototype-values)
+  (elem declare func $Map-prototype-entries)
+  (elem declare func $Set-constructor)
+  (elem declare func $Set-prototype-add)
+  (elem declare func $Set-prototype-has)
+  (elem declare func $Set-prototype-delete)
+  (elem declare func $Set-prototype-clear)
+  (elem declare func $Set-prototype-forEach)
+  (elem declare func $Set-prototype-values)
+  (elem declare func $Set-prototype-entries)
+  (elem declare func $WeakMap-constructor)
+  (elem declare func $WeakMap-prototype-get)
+  (elem declare func $WeakMap-prototype-set)
+  (elem declare func $WeakMap-prototype-has)
+  (elem declare func $WeakMap-prototype-delete)
+  (elem declare func $WeakSet-constructor)
+  (elem declare func $WeakSet-prototype-add)
+  (elem declare func $WeakSet-prototype-has)
+  (elem declare func $WeakSet-prototype-delete)
+
+  ;; creates a constructor and links it with its prototype
+  (func $install-collection-constructor
+    (param $scope (ref $Scope))
+    (param $name i32)
+    (param $constructor (ref $JSFunc))
+    (param $prototype (ref $Object))
+
+    (local $function (ref $Function))
+    (local.set $function (call $new_function (local.get $scope) (local.get $constructor) (ref.null any)))
+    (call $set_property (local.get $function) (i32.const {{ data(str="prototype") }}) (local.get $prototype))
+    (call $set_property (local.get $prototype) (i32.const {{ data(str="constructor") }}) (local.get $function))
+    (call $set_variable (local.get $scope) (local.get $name) (local.get $function))
+  )
+
+  (func $install-collection-globals (param $scope (ref $Scope))
+    (local $prototype (ref $Object))
+    (local $values (ref $Function))
+    (local $entries (ref $Function))
+
+    (local.set $prototype (call $new_object))
+    (call $add_method (local.get $prototype) (i32.const {{ data(str="next") }}) (ref.func $CollectionIterator-next))
+    ;; iterators are iterable themselves
+    (call $add_method
+      (local.get $prototype)
+      (call $well_known_symbol_key (global.get $symbol-iterator))
+      (ref.func $CollectionIterator-iterator))
+    (global.set $collection-iterator-prototype (local.get $prototype))
+
+    (local.set $prototype (call $new_object))
+    (call $add_method (local.get $prototype) (i32.const {{ data(str="get") }}) (ref.func $Map-prototype-get))
+    (call $add_method (local.get $prototype) (i32.const {{ data(str="set") }}) (ref.func $Map-prototype-set))
+    (call $add_method (local.get $prototype) (i32.const {{ data(str="has") }}) (ref.func $Map-prototype-has))
+    (call $add_method (local.get $prototype) (i32.const {{ data(str="delete") }}) (ref.func $Map-prototype-delete))
+    (call $add_method (local.get $prototype) (i32.const {{ data(str="clear") }}) (ref.func $Map-prototype-clear))
+    (call $add_method (local.get $prototype) (i32.const {{ data(str="forEach") }}) (ref.func $Map-prototype-forEach))
+    (call $add_method (local.get $prototype) (i32.const {{ data(str="keys") }}) (ref.func $Map-prototype-keys))
+    (call $add_method (local.get $prototype) (i32.const {{ data(str="values") }}) (ref.func $Map-prototype-values))
+    ;; Map.prototype[Symbol.iterator] is the same function as Map.prototype.entries
+    (local.set $entries
+      (call $new_function (local.get $scope) (ref.func $Map-prototype-entries) (ref.null any)))
+    (call $set_property (local.get $prototype) (i32.const {{ data(str="entries") }}) (local.get $entries))
+    (call $set_property
+      (local.get $prototype)
+      (call $well_known_symbol_key (global.get $symbol-iterator))
+      (local.get $entries))
+    (call $set_to_string_tag (local.get $prototype)
+      (call $new_static_string (i32.const {{ data(str="Map") }}) (i32.const {{ data_length() }})))
+    (global.set $map-prototype (local.get $prototype))
+    (call $install-collection-constructor
+      (local.get $scope) (i32.const {{ data(str="Map") }}) (ref.func $Map-constructor) (local.get $prototype))
+
+    (local.set $prototype (call $new_object))
+    (call $add_method (local.get $prototype) (i32.const {{ data(str="add") }}) (ref.func $Set-prototype-add))
+    (call $add_method (local.get $prototype) (i32.const {{ data(str="has") }}) (ref.func $Set-prototype-has))
+    (call $add_method (local.get $prototype) (i32.const {{ data(str="delete") }}) (ref.func $Set-prototype-delete))
+    (call $add_method (local.get $prototype) (i32.const {{ data(str="clear") }}) (ref.func $Set-prototype-clear))
+    (call $add_method (local.get $prototype) (i32.const {{ data(str="forEach") }}) (ref.func $Set-prototype-forEach))
+    (call $add_method (local.get $prototype) (i32.const {{ data(str="entries") }}) (ref.func $Set-prototype-entries))
+    ;; Set.prototype.keys and Set.prototype[Symbol.iterator] are the same
+    ;; function as Set.prototype.values
+    (local.set $values
+      (call $new_function (local.get $scope) (ref.func $Set-prototype-values) (ref.null any)))
+    (call $set_property (local.get $prototype) (i32.const {{ data(str="values") }}) (local.get $values))
+    (call $set_property (local.get $prototype) (i32.const {{ data(str="keys") }}) (local.get $values))
+    (call $set_property
+      (local.get $prototype)
+      (call $well_known_symbol_key (global.get $symbol-iterator))
+      (local.get $values))
+    (call $set_to_string_tag (local.get $prototype)
+      (call $new_static_string (i32.const {{ data(str="Set") }}) (i32.const {{ data_length() }})))
+    (global.set $set-prototype (local.get $prototype))
+    (call $install-collection-constructor
+      (local.get $scope) (i32.const {{ data(str="Set") }}) (ref.func $Set-constructor) (local.get $prototype))
+
+    (local.set $prototype (call $new_object))
+    (call $add_method (local.get $prototype) (i32.const {{ data(str="get") }}) (ref.func $WeakMap-prototype-get))
+    (call $add_method (local.get $prototype) (i32.const {{ data(str="set") }}) (ref.func $WeakMap-prototype-set))
+    (call $add_method (local.get $prototype) (i32.const {{ data(str="has") }}) (ref.func $WeakMap-prototype-has))
+    (call $add_method (local.get $prototype) (i32.const {{ data(str="delete") }}) (ref.func $WeakMap-prototype-delete))
+    (call $set_to_string_tag (local.get $prototype)
+      (call $new_static_string (i32.const {{ data(str="WeakMap") }}) (i32.const {{ data_length() }})))
+    (global.set $weak-map-prototype (local.get $prototype))
+    (call $install-collection-constructor
+      (local.get $scope) (i32.const {{ data(str="WeakMap") }}) (ref.func $WeakMap-constructor) (local.get $prototype))
+
+    (local.set $prototype (call $new_object))
+    (call $add_method (local.get $prototype) (i32.const {{ data(str="add") }}) (ref.func $WeakSet-prototype-add))
+    (call $add_method (local.get $prototype) (i32.const {{ data(str="has") }}) (ref.func $WeakSet-prototype-has))
+    (call $add_method (local.get $prototype) (i32.const {{ data(str="delete") }}) (ref.func $WeakSet-prototype-delete))
+    (call $set_to_string_tag (local.get $prototype)
+      (call $new_static_string (i32.const {{ data(str="WeakSet") }}) (i32.const {{ data_length() }})))
+    (global.set $weak-set-prototype (local.get $prototype))
+    (call $install-collection-constructor
+      (local.get $scope) (i32.const {{ data(str="WeakSet") }}) (ref.func $WeakSet-constructor) (local.get $prototype))
+  )
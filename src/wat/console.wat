@@ -0,0 +1,2154 @@
+  ;; `console` and the inspection of values it does, following Node's
+  ;; util.inspect: strings nested in objects are quoted, objects nested deeper
+  ;; than two levels are abbreviated, circular references are marked, and
+  ;; anything that doesn't fit in 80 columns is broken into multiple lines.
+  ;;
+  ;; log, info and debug write to stdout, warn and error to stderr. Every line
+  ;; is indented by the current console.group level.
+
+  (type $InspectContext (struct
+    ;; objects currently being formatted, to find circular references
+    (field $seen (ref $ValueList))
+    ;; objects referenced circularly, the index + 1 is the number in `*1`
+    (field $circular (ref $ValueList))
+    (field $indentation (mut i32))
+    ;; how deep the last formatted object was, it decides if an object can
+    ;; still be printed on one line
+    (field $current_depth (mut i32))
+    ;; objects nested deeper than this are printed as [Object]
+    (field $depth i32)
+    (field $break_length i32)
+    (field $max_array_length i32)
+  ))
+
+  (global $console-indentation (mut i32) (i32.const 0))
+  (global $console-counts (mut (ref null $HashMap)) (ref.null $HashMap))
+  (global $console-timers (mut (ref null $HashMap)) (ref.null $HashMap))
+
+  (func $new_inspect_context (param $depth i32) (param $break_length i32) (param $max_array_length i32) (result (ref $InspectContext))
+    (struct.new $InspectContext
+      (call $new_value_list)
+      (call $new_value_list)
+      (i32.const 0)
+      (i32.const 0)
+      (local.get $depth)
+      (local.get $break_length)
+      (local.get $max_array_length))
+  )
+
+  ;; util.inspect with the default options
+  (func $inspect (param $value anyref) (result anyref)
+    (call $inspect_value
+      (call $new_inspect_context (i32.const 2) (i32.const 80) (i32.const 100))
+      (local.get $value)
+      (i32.const 0))
+  )
+
+  (func $value_list_index_of (param $list (ref $ValueList)) (param $value anyref) (result i32)
+    (local $i i32)
+
+    (if (i32.eqz (ref.test eqref (local.get $value)))
+      (then (return (i32.const -1)))
+    )
+    (block $break (loop $search
+      (br_if $break (i32.ge_u (local.get $i) (struct.get $ValueList $size (local.get $list))))
+      (if (ref.eq
+            (ref.cast eqref (array.get $AnyrefArray (struct.get $ValueList $data (local.get $list)) (local.get $i)))
+            (ref.cast eqref (local.get $value)))
+        (then (return (local.get $i)))
+      )
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $search)
+    ))
+    (i32.const -1)
+  )
+
+  (func $value_list_get (param $list (ref $ValueList)) (param $index i32) (result anyref)
+    (array.get $AnyrefArray (struct.get $ValueList $data (local.get $list)) (local.get $index))
+  )
+
+  (func $builder_append_spaces (param $builder (ref $StringBuilder)) (param $count i32)
+    (block $break (loop $append
+      (br_if $break (i32.le_s (local.get $count) (i32.const 0)))
+      (call $builder_append_byte (local.get $builder) (i32.const 0x20))
+      (local.set $count (i32.sub (local.get $count) (i32.const 1)))
+      (br $append)
+    ))
+  )
+
+  (func $join_strings (param $list (ref $ValueList)) (param $separator anyref) (result (ref $String))
+    (local $builder (ref $StringBuilder))
+    (local $i i32)
+
+    (local.set $builder (call $new_string_builder))
+    (block $break (loop $join
+      (br_if $break (i32.ge_u (local.get $i) (struct.get $ValueList $size (local.get $list))))
+      (if (local.get $i)
+        (then (call $builder_append_string (local.get $builder) (local.get $separator)))
+      )
+      (call $builder_append_string (local.get $builder) (call $value_list_get (local.get $list) (local.get $i)))
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $join)
+    ))
+    (call $builder_to_string (local.get $builder))
+  )
+
+  (func $string_has_byte (param $str anyref) (param $byte i32) (result i32)
+    (local $i i32)
+    (local $length i32)
+
+    (local.set $length (call $string_length (local.get $str)))
+    (block $break (loop $search
+      (br_if $break (i32.ge_u (local.get $i) (local.get $length)))
+      (if (i32.eq (call $string_byte_at (local.get $str) (local.get $i)) (local.get $byte))
+        (then (return (i32.const 1)))
+      )
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $search)
+    ))
+    (i32.const 0)
+  )
+
+  ;; the number of characters, as opposed to bytes, in a string
+  (func $string_width (param $str anyref) (result i32)
+    (local $i i32)
+    (local $length i32)
+    (local $width i32)
+
+    (local.set $length (call $string_length (local.get $str)))
+    (block $break (loop $count
+      (br_if $break (i32.ge_u (local.get $i) (local.get $length)))
+      ;; continuation bytes of UTF-8 sequences are 10xxxxxx
+      (if (i32.ne
+            (i32.and (call $string_byte_at (local.get $str) (local.get $i)) (i32.const 0xc0))
+            (i32.const 0x80))
+        (then (local.set $width (i32.add (local.get $width) (i32.const 1))))
+      )
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $count)
+    ))
+    (local.get $width)
+  )
+
+  (func $builder_append_hex_byte (param $builder (ref $StringBuilder)) (param $byte i32) (param $letters i32)
+    (local $i i32)
+    (local $digit i32)
+
+    (block $break (loop $digits
+      (br_if $break (i32.ge_u (local.get $i) (i32.const 2)))
+      (local.set $digit
+        (i32.and
+          (i32.shr_u (local.get $byte) (i32.sub (i32.const 4) (i32.shl (local.get $i) (i32.const 2))))
+          (i32.const 0xf)))
+      (call $builder_append_byte (local.get $builder)
+        (if (result i32) (i32.lt_u (local.get $digit) (i32.const 10))
+          (then (i32.add (local.get $digit) (i32.const 0x30)))
+          (else (i32.add (local.get $digit) (i32.sub (local.get $letters) (i32.const 10))))))
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $digits)
+    ))
+  )
+
+  ;; a string literal for the string, single quotes are preferred, but if the
+  ;; string contains them double quotes or backticks are used instead
+  (func $inspect_quote (param $str anyref) (result (ref $String))
+    (local $builder (ref $StringBuilder))
+    (local $quote i32)
+    (local $byte i32)
+    (local $i i32)
+    (local $length i32)
+
+    (local.set $quote (i32.const 0x27))
+    (if (call $string_has_byte (local.get $str) (i32.const 0x27))
+      (then
+        (if (i32.eqz (call $string_has_byte (local.get $str) (i32.const 0x22)))
+          (then (local.set $quote (i32.const 0x22)))
+          (else
+            (if (i32.eqz (call $string_has_byte (local.get $str) (i32.const 0x60)))
+              (then
+                (if (i32.lt_s
+                      (call $string_index_of
+                        (local.get $str)
+                        (call $new_static_string (i32.const {{ data(str="${") }}) (i32.const {{ data_length() }}))
+                        (i32.const 0))
+                      (i32.const 0))
+                  (then (local.set $quote (i32.const 0x60)))
+                )
+              )
+            )
+          )
+        )
+      )
+    )
+
+    (local.set $builder (call $new_string_builder))
+    (call $builder_append_byte (local.get $builder) (local.get $quote))
+    (local.set $length (call $string_length (local.get $str)))
+    (block $break (loop $escape
+      (br_if $break (i32.ge_u (local.get $i) (local.get $length)))
+      (local.set $byte (call $string_byte_at (local.get $str) (local.get $i)))
+
+      (block $done
+        (if (i32.or
+              (i32.eq (local.get $byte) (local.get $quote))
+              (i32.eq (local.get $byte) (i32.const 0x5c)))
+          (then
+            (call $builder_append_byte (local.get $builder) (i32.const 0x5c))
+            (call $builder_append_byte (local.get $builder) (local.get $byte))
+            (br $done)
+          )
+        )
+        (if (i32.eq (local.get $byte) (i32.const 0x0a))
+          (then
+            (call $builder_append_string (local.get $builder)
+              (call $new_static_string (i32.const {{ data(str="\\n") }}) (i32.const {{ data_length() }})))
+            (br $done)
+          )
+        )
+        (if (i32.eq (local.get $byte) (i32.const 0x09))
+          (then
+            (call $builder_append_string (local.get $builder)
+              (call $new_static_string (i32.const {{ data(str="\\t") }}) (i32.const {{ data_length() }})))
+            (br $done)
+          )
+        )
+        (if (i32.eq (local.get $byte) (i32.const 0x0d))
+          (then
+            (call $builder_append_string (local.get $builder)
+              (call $new_static_string (i32.const {{ data(str="\\r") }}) (i32.const {{ data_length() }})))
+            (br $done)
+          )
+        )
+        (if (i32.eq (local.get $byte) (i32.const 0x08))
+          (then
+            (call $builder_append_string (local.get $builder)
+              (call $new_static_string (i32.const {{ data(str="\\b") }}) (i32.const {{ data_length() }})))
+            (br $done)
+          )
+        )
+        (if (i32.eq (local.get $byte) (i32.const 0x0c))
+          (then
+            (call $builder_append_string (local.get $builder)
+              (call $new_static_string (i32.const {{ data(str="\\f") }}) (i32.const {{ data_length() }})))
+            (br $done)
+          )
+        )
+        (if (i32.eq (local.get $byte) (i32.const 0x0b))
+          (then
+            (call $builder_append_string (local.get $builder)
+              (call $new_static_string (i32.const {{ data(str="\\v") }}) (i32.const {{ data_length() }})))
+            (br $done)
+          )
+        )
+        (if (i32.or
+              (i32.lt_u (local.get $byte) (i32.const 0x20))
+              (i32.eq (local.get $byte) (i32.const 0x7f)))
+          (then
+            (call $builder_append_string (local.get $builder)
+              (call $new_static_string (i32.const {{ data(str="\\x") }}) (i32.const {{ data_length() }})))
+            (call $builder_append_hex_byte (local.get $builder) (local.get $byte) (i32.const 0x41))
+            (br $done)
+          )
+        )
+        (call $builder_append_byte (local.get $builder) (local.get $byte))
+      )
+
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $escape)
+    ))
+    (call $builder_append_byte (local.get $builder) (local.get $quote))
+    (call $builder_to_string (local.get $builder))
+  )
+
+  ;; keys that are valid identifiers are printed as they are, others quoted
+  (func $inspect_key (param $key i32) (result anyref)
+    (local $name anyref)
+    (local $byte i32)
+    (local $i i32)
+    (local $length i32)
+
+    (local.set $name (call $key_to_string (local.get $key)))
+    (if (call $is_symbol (local.get $name))
+      (then
+        (return
+          (call $concat_strings
+            (call $concat_strings
+              (call $new_static_string (i32.const {{ data(str="[") }}) (i32.const {{ data_length() }}))
+              (call $symbol_descriptive_string (ref.cast (ref $Symbol) (local.get $name))))
+            (call $new_static_string (i32.const {{ data(str="]") }}) (i32.const {{ data_length() }}))))
+      )
+    )
+
+    (local.set $length (call $string_length (local.get $name)))
+    (if (i32.eqz (local.get $length))
+      (then (return (call $inspect_quote (local.get $name))))
+    )
+    (block $break (loop $check
+      (br_if $break (i32.ge_u (local.get $i) (local.get $length)))
+      (local.set $byte (call $string_byte_at (local.get $name) (local.get $i)))
+      (if (i32.eqz
+            (i32.or
+              (i32.or
+                (i32.lt_u (i32.sub (i32.or (local.get $byte) (i32.const 0x20)) (i32.const 0x61)) (i32.const 26))
+                (i32.eq (local.get $byte) (i32.const 0x5f)))
+              (i32.and
+                (i32.lt_u (i32.sub (local.get $byte) (i32.const 0x30)) (i32.const 10))
+                (i32.gt_u (local.get $i) (i32.const 0)))))
+        (then (return (call $inspect_quote (local.get $name))))
+      )
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $check)
+    ))
+    (local.get $name)
+  )
+
+  ;; numbers are printed like with toString, except for -0
+  (func $inspect_number (param $value f64) (result anyref)
+    (if (i32.and
+          (f64.eq (local.get $value) (f64.const 0))
+          (i64.lt_s (i64.reinterpret_f64 (local.get $value)) (i64.const 0)))
+      (then
+        (return (call $new_static_string (i32.const {{ data(str="-0") }}) (i32.const {{ data_length() }})))
+      )
+    )
+    (call $number_to_string (local.get $value))
+  )
+
+  (func $inspect_value (param $ctx (ref $InspectContext)) (param $value anyref) (param $recurse i32) (result anyref)
+    (local $index i32)
+
+    (if (ref.is_null (local.get $value))
+      (then
+        (return (call $new_static_string (i32.const {{ data(str="undefined") }}) (i32.const {{ data_length() }})))
+      )
+    )
+    (if (ref.test (ref $Null) (local.get $value))
+      (then
+        (return (call $new_static_string (i32.const {{ data(str="null") }}) (i32.const {{ data_length() }})))
+      )
+    )
+    (if (call $is_number (local.get $value))
+      (then (return (call $inspect_number (call $number_value (local.get $value)))))
+    )
+    (if (call $is_string (local.get $value))
+      (then (return (call $inspect_quote (local.get $value))))
+    )
+    (if (call $is_bigint (local.get $value))
+      (then
+        (return
+          (call $concat_strings
+            (call $to_string (local.get $value))
+            (call $new_static_string (i32.const {{ data(str="n") }}) (i32.const {{ data_length() }}))))
+      )
+    )
+    (if (call $is_symbol (local.get $value))
+      (then (return (call $symbol_descriptive_string (ref.cast (ref $Symbol) (local.get $value)))))
+    )
+    ;; proxies are shown as their targets
+    (if (ref.test (ref $Proxy) (local.get $value))
+      (then
+        (return
+          (call $inspect_value
+            (local.get $ctx)
+            (struct.get $Proxy $target (ref.cast (ref $Proxy) (local.get $value)))
+            (local.get $recurse)))
+      )
+    )
+    (if (ref.test (ref $Function) (local.get $value))
+      (then (return (call $inspect_function (local.get $value))))
+    )
+    ;; errors don't have a stack, Node shows those as [name: message]
+    (if (call $is_error (local.get $value))
+      (then
+        (return
+          (call $concat_strings
+            (call $concat_strings
+              (call $new_static_string (i32.const {{ data(str="[") }}) (i32.const {{ data_length() }}))
+              (call $error_to_string (local.get $value)))
+            (call $new_static_string (i32.const {{ data(str="]") }}) (i32.const {{ data_length() }}))))
+      )
+    )
+    (if (i32.eqz
+          (i32.or
+            (i32.or
+              (ref.test (ref $Object) (local.get $value))
+              (ref.test (ref $Array) (local.get $value)))
+            (ref.test (ref $Promise) (local.get $value))))
+      (then (return (call $to_string (local.get $value))))
+    )
+
+    (if (i32.ge_s (call $value_list_index_of (struct.get $InspectContext $seen (local.get $ctx)) (local.get $value)) (i32.const 0))
+      (then
+        (local.set $index (call $value_list_index_of (struct.get $InspectContext $circular (local.get $ctx)) (local.get $value)))
+        (if (i32.lt_s (local.get $index) (i32.const 0))
+          (then
+            (local.set $index (struct.get $ValueList $size (struct.get $InspectContext $circular (local.get $ctx))))
+            (call $value_list_push (struct.get $InspectContext $circular (local.get $ctx)) (local.get $value))
+          )
+        )
+        (return
+          (call $concat_strings
+            (call $concat_strings
+              (call $new_static_string (i32.const {{ data(str="[Circular *") }}) (i32.const {{ data_length() }}))
+              (call $number_to_string (f64.convert_i32_s (i32.add (local.get $index) (i32.const 1)))))
+            (call $new_static_string (i32.const {{ data(str="]") }}) (i32.const {{ data_length() }}))))
+      )
+    )
+
+    (call $inspect_object (local.get $ctx) (local.get $value) (local.get $recurse))
+  )
+
+  (func $inspect_function (param $value anyref) (result anyref)
+    (local $name anyref)
+
+    (local.set $name
+      (call $hashmap_get
+        (struct.get $Function $properties (ref.cast (ref $Function) (local.get $value)))
+        (i32.const {{ data(str="name") }})))
+    (if (call $is_string (local.get $name))
+      (then
+        (if (call $string_length (local.get $name))
+          (then
+            (return
+              (call $concat_strings
+                (call $concat_strings
+                  (call $new_static_string (i32.const {{ data(str="[Function: ") }}) (i32.const {{ data_length() }}))
+                  (local.get $name))
+                (call $new_static_string (i32.const {{ data(str="]") }}) (i32.const {{ data_length() }}))))
+          )
+        )
+      )
+    )
+    (call $new_static_string (i32.const {{ data(str="[Function (anonymous)]") }}) (i32.const {{ data_length() }}))
+  )
+
+  ;; the name used for objects nested too deep, like [Object] or [Map]
+  (func $inspect_constructor_name (param $value anyref) (result anyref)
+    (if (ref.test (ref $Array) (local.get $value))
+      (then (return (call $new_static_string (i32.const {{ data(str="Array") }}) (i32.const {{ data_length() }}))))
+    )
+    (if (ref.test (ref $Promise) (local.get $value))
+      (then (return (call $new_static_string (i32.const {{ data(str="Promise") }}) (i32.const {{ data_length() }}))))
+    )
+    (if (ref.test (ref $TypedArray) (local.get $value))
+      (then
+        (return (call $typed_array_name (struct.get $TypedArray $kind (ref.cast (ref $TypedArray) (local.get $value)))))
+      )
+    )
+    (if (ref.test (ref $Collection) (local.get $value))
+      (then (return (call $collection_name (ref.cast (ref $Collection) (local.get $value)))))
+    )
+    (if (ref.test (ref $ArrayBuffer) (local.get $value))
+      (then (return (call $new_static_string (i32.const {{ data(str="ArrayBuffer") }}) (i32.const {{ data_length() }}))))
+    )
+    (if (ref.test (ref $DataView) (local.get $value))
+      (then (return (call $new_static_string (i32.const {{ data(str="DataView") }}) (i32.const {{ data_length() }}))))
+    )
+    (if (ref.test (ref $Date) (local.get $value))
+      (then (return (call $new_static_string (i32.const {{ data(str="Date") }}) (i32.const {{ data_length() }}))))
+    )
+    (if (ref.test (ref $RegExp) (local.get $value))
+      (then (return (call $new_static_string (i32.const {{ data(str="RegExp") }}) (i32.const {{ data_length() }}))))
+    )
+    (if (call $is_null (struct.get $Object $prototype (ref.cast (ref $Object) (local.get $value))))
+      (then
+        (return (call $new_static_string (i32.const {{ data(str="Object: null prototype") }}) (i32.const {{ data_length() }})))
+      )
+    )
+    (call $new_static_string (i32.const {{ data(str="Object") }}) (i32.const {{ data_length() }}))
+  )
+
+  (func $collection_name (param $collection (ref $Collection)) (result anyref)
+    (local $kind i32)
+
+    (local.set $kind (struct.get $Collection $kind (local.get $collection)))
+    (if (i32.eq (local.get $kind) (global.get $collection-map))
+      (then (return (call $new_static_string (i32.const {{ data(str="Map") }}) (i32.const {{ data_length() }}))))
+    )
+    (if (i32.eq (local.get $kind) (global.get $collection-set))
+      (then (return (call $new_static_string (i32.const {{ data(str="Set") }}) (i32.const {{ data_length() }}))))
+    )
+    (if (i32.eq (local.get $kind) (global.get $collection-weak-map))
+      (then (return (call $new_static_string (i32.const {{ data(str="WeakMap") }}) (i32.const {{ data_length() }}))))
+    )
+    (call $new_static_string (i32.const {{ data(str="WeakSet") }}) (i32.const {{ data_length() }}))
+  )
+
+  ;; formats a nested value with the indentation of an entry
+  (func $inspect_entry (param $ctx (ref $InspectContext)) (param $value anyref) (param $recurse i32) (result anyref)
+    (local $result anyref)
+
+    (struct.set $InspectContext $indentation (local.get $ctx)
+      (i32.add (struct.get $InspectContext $indentation (local.get $ctx)) (i32.const 2)))
+    (local.set $result
+      (call $inspect_value (local.get $ctx) (local.get $value) (i32.add (local.get $recurse) (i32.const 1))))
+    (struct.set $InspectContext $indentation (local.get $ctx)
+      (i32.sub (struct.get $InspectContext $indentation (local.get $ctx)) (i32.const 2)))
+    (local.get $result)
+  )
+
+  ;; `key: value` entries for the own properties of an object, in the order
+  ;; of $own_key_order
+  (func $inspect_properties
+    (param $ctx (ref $InspectContext))
+    (param $properties (ref null $HashMap))
+    (param $recurse i32)
+    (param $output (ref $ValueList))
+    (local $entries (ref $EntriesArray))
+    (local $entry (ref null $HashMapEntry))
+    (local $order (ref $I32Array))
+    (local $size i32)
+    (local $i i32)
+
+    (if (ref.is_null (local.get $properties))
+      (then (return))
+    )
+    (local.set $entries (struct.get $HashMap $entries (ref.as_non_null (local.get $properties))))
+    (local.set $order (call $own_key_order (ref.as_non_null (local.get $properties)) (i32.const 1)))
+    (local.set $size (array.len (local.get $order)))
+
+    (block $break (loop $properties
+      (br_if $break (i32.ge_u (local.get $i) (local.get $size)))
+
+      (local.set $entry
+        (array.get $EntriesArray (local.get $entries) (array.get $I32Array (local.get $order) (local.get $i))))
+      (call $value_list_push (local.get $output)
+        (call $concat_strings
+          (call $concat_strings
+            (call $inspect_key (struct.get $HashMapEntry $key (local.get $entry)))
+            (call $new_static_string (i32.const {{ data(str=": ") }}) (i32.const {{ data_length() }})))
+          (call $inspect_entry
+            (local.get $ctx)
+            (struct.get $HashMapEntry $value (local.get $entry))
+            (local.get $recurse))))
+
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $properties)
+    ))
+  )
+
+  (func $inspect_more_items (param $remaining i32) (param $output (ref $ValueList))
+    (if (i32.le_s (local.get $remaining) (i32.const 0))
+      (then (return))
+    )
+    (call $value_list_push (local.get $output)
+      (call $concat_strings
+        (call $concat_strings
+          (call $new_static_string (i32.const {{ data(str="... ") }}) (i32.const {{ data_length() }}))
+          (call $number_to_string (f64.convert_i32_s (local.get $remaining))))
+        (if (result anyref) (i32.eq (local.get $remaining) (i32.const 1))
+          (then (call $new_static_string (i32.const {{ data(str=" more item") }}) (i32.const {{ data_length() }})))
+          (else (call $new_static_string (i32.const {{ data(str=" more items") }}) (i32.const {{ data_length() }}))))))
+  )
+
+  (func $inspect_array_buffer_contents (param $data (ref $CharArray)) (param $offset i32) (param $length i32) (result anyref)
+    (local $builder (ref $StringBuilder))
+    (local $count i32)
+    (local $i i32)
+
+    (local.set $builder (call $new_string_builder))
+    (call $builder_append_string (local.get $builder)
+      (call $new_static_string (i32.const {{ data(str="[Uint8Contents]: <") }}) (i32.const {{ data_length() }})))
+    (local.set $count (local.get $length))
+    (if (i32.gt_u (local.get $count) (i32.const 50))
+      (then (local.set $count (i32.const 50)))
+    )
+    (block $break (loop $bytes
+      (br_if $break (i32.ge_u (local.get $i) (local.get $count)))
+      (if (local.get $i)
+        (then (call $builder_append_byte (local.get $builder) (i32.const 0x20)))
+      )
+      (call $builder_append_hex_byte
+        (local.get $builder)
+        (array.get_u $CharArray (local.get $data) (i32.add (local.get $offset) (local.get $i)))
+        (i32.const 0x61))
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $bytes)
+    ))
+    (if (i32.gt_u (local.get $length) (local.get $count))
+      (then
+        (call $builder_append_string (local.get $builder)
+          (call $new_static_string (i32.const {{ data(str=" ... ") }}) (i32.const {{ data_length() }})))
+        (call $builder_append_string (local.get $builder)
+          (call $number_to_string (f64.convert_i32_u (i32.sub (local.get $length) (local.get $count)))))
+        (call $builder_append_string (local.get $builder)
+          (if (result anyref) (i32.eq (i32.sub (local.get $length) (local.get $count)) (i32.const 1))
+            (then (call $new_static_string (i32.const {{ data(str=" more byte") }}) (i32.const {{ data_length() }})))
+            (else (call $new_static_string (i32.const {{ data(str=" more bytes") }}) (i32.const {{ data_length() }})))))
+      )
+    )
+    (call $builder_append_byte (local.get $builder) (i32.const 0x3e))
+    (call $builder_to_string (local.get $builder))
+  )
+
+  (func $inspect_object (param $ctx (ref $InspectContext)) (param $value anyref) (param $recurse i32) (result anyref)
+    (local $output (ref $ValueList))
+    (local $base anyref)
+    (local $open anyref)
+    (local $close anyref)
+    (local $is_array i32)
+    (local $array (ref $AnyrefArray))
+    (local $typed_array (ref $TypedArray))
+    (local $buffer (ref $ArrayBuffer))
+    (local $table (ref $OrderedTable))
+    (local $promise (ref $Promise))
+    (local $object (ref $Object))
+    (local $tag anyref)
+    (local $kind i32)
+    (local $length i32)
+    (local $count i32)
+    (local $i i32)
+    (local $result anyref)
+
+    (if (i32.gt_s (local.get $recurse) (struct.get $InspectContext $depth (local.get $ctx)))
+      (then
+        (return
+          (call $concat_strings
+            (call $concat_strings
+              (call $new_static_string (i32.const {{ data(str="[") }}) (i32.const {{ data_length() }}))
+              (call $inspect_constructor_name (local.get $value)))
+            (call $new_static_string (i32.const {{ data(str="]") }}) (i32.const {{ data_length() }}))))
+      )
+    )
+
+    (call $value_list_push (struct.get $InspectContext $seen (local.get $ctx)) (local.get $value))
+    (struct.set $InspectContext $current_depth (local.get $ctx) (local.get $recurse))
+    (local.set $output (call $new_value_list))
+    (local.set $base (call $new_static_string (i32.const {{ data(str="") }}) (i32.const {{ data_length() }})))
+    (local.set $open (call $new_static_string (i32.const {{ data(str="{") }}) (i32.const {{ data_length() }})))
+    (local.set $close (call $new_static_string (i32.const {{ data(str="}") }}) (i32.const {{ data_length() }})))
+
+    (block $formatted
+      (if (ref.test (ref $Array) (local.get $value))
+        (then
+          (local.set $is_array (i32.const 1))
+          (local.set $open (call $new_static_string (i32.const {{ data(str="[") }}) (i32.const {{ data_length() }})))
+          (local.set $close (call $new_static_string (i32.const {{ data(str="]") }}) (i32.const {{ data_length() }})))
+          (local.set $array (struct.get $Array $array (ref.cast (ref $Array) (local.get $value))))
+          (local.set $length (array.len (local.get $array)))
+          (local.set $count (local.get $length))
+          (if (i32.gt_u (local.get $count) (struct.get $InspectContext $max_array_length (local.get $ctx)))
+            (then (local.set $count (struct.get $InspectContext $max_array_length (local.get $ctx))))
+          )
+          (block $break (loop $elements
+            (br_if $break (i32.ge_u (local.get $i) (local.get $count)))
+            (call $value_list_push (local.get $output)
+              (call $inspect_entry
+                (local.get $ctx)
+                (array.get $AnyrefArray (local.get $array) (local.get $i))
+                (local.get $recurse)))
+            (local.set $i (i32.add (local.get $i) (i32.const 1)))
+            (br $elements)
+          ))
+          (call $inspect_more_items (i32.sub (local.get $length) (local.get $count)) (local.get $output))
+          (call $inspect_properties
+            (local.get $ctx)
+            (struct.get $Array $properties (ref.cast (ref $Array) (local.get $value)))
+            (local.get $recurse)
+            (local.get $output))
+          (br $formatted)
+        )
+      )
+
+      (if (ref.test (ref $TypedArray) (local.get $value))
+        (then
+          (local.set $is_array (i32.const 1))
+          (local.set $typed_array (ref.cast (ref $TypedArray) (local.get $value)))
+          (local.set $length (struct.get $TypedArray $length (local.get $typed_array)))
+          (local.set $open
+            (call $concat_strings
+              (call $concat_strings
+                (call $concat_strings
+                  (call $typed_array_name (struct.get $TypedArray $kind (local.get $typed_array)))
+                  (call $new_static_string (i32.const {{ data(str="(") }}) (i32.const {{ data_length() }})))
+                (call $number_to_string (f64.convert_i32_u (local.get $length))))
+              (call $new_static_string (i32.const {{ data(str=") [") }}) (i32.const {{ data_length() }}))))
+          (local.set $close (call $new_static_string (i32.const {{ data(str="]") }}) (i32.const {{ data_length() }})))
+          (local.set $count (local.get $length))
+          (if (i32.gt_u (local.get $count) (struct.get $InspectContext $max_array_length (local.get $ctx)))
+            (then (local.set $count (struct.get $InspectContext $max_array_length (local.get $ctx))))
+          )
+          (block $break (loop $elements
+            (br_if $break (i32.ge_u (local.get $i) (local.get $count)))
+            (call $value_list_push (local.get $output)
+              (call $inspect_entry
+                (local.get $ctx)
+                (call $typed_array_get (local.get $typed_array) (local.get $i))
+                (local.get $recurse)))
+            (local.set $i (i32.add (local.get $i) (i32.const 1)))
+            (br $elements)
+          ))
+          (call $inspect_more_items (i32.sub (local.get $length) (local.get $count)) (local.get $output))
+          (br $formatted)
+        )
+      )
+
+      (if (ref.test (ref $Collection) (local.get $value))
+        (then
+          (local.set $kind (struct.get $Collection $kind (ref.cast (ref $Collection) (local.get $value))))
+          (local.set $table (struct.get $Collection $table (ref.cast (ref $Collection) (local.get $value))))
+          (if (i32.or
+                (i32.eq (local.get $kind) (global.get $collection-weak-map))
+                (i32.eq (local.get $kind) (global.get $collection-weak-set)))
+            (then
+              (local.set $open
+                (call $concat_strings
+                  (call $collection_name (ref.cast (ref $Collection) (local.get $value)))
+                  (call $new_static_string (i32.const {{ data(str=" {") }}) (i32.const {{ data_length() }}))))
+              (call $value_list_push (local.get $output)
+                (call $new_static_string (i32.const {{ data(str="<items unknown>") }}) (i32.const {{ data_length() }})))
+              (br $formatted)
+            )
+          )
+          (local.set $open
+            (call $concat_strings
+              (call $concat_strings
+                (call $concat_strings
+                  (call $collection_name (ref.cast (ref $Collection) (local.get $value)))
+                  (call $new_static_string (i32.const {{ data(str="(") }}) (i32.const {{ data_length() }})))
+                (call $number_to_string (f64.convert_i32_u (struct.get $OrderedTable $size (local.get $table)))))
+              (call $new_static_string (i32.const {{ data(str=") {") }}) (i32.const {{ data_length() }}))))
+          (block $break (loop $entries
+            (br_if $break (i32.ge_u (local.get $i) (struct.get $OrderedTable $count (local.get $table))))
+            (if (i32.eqz
+                  (call $is_no_value_found
+                    (array.get $AnyrefArray (struct.get $OrderedTable $keys (local.get $table)) (local.get $i))))
+              (then
+                (if (i32.eq (local.get $kind) (global.get $collection-map))
+                  (then
+                    (call $value_list_push (local.get $output)
+                      (call $concat_strings
+                        (call $concat_strings
+                          (call $inspect_entry
+                            (local.get $ctx)
+                            (array.get $AnyrefArray (struct.get $OrderedTable $keys (local.get $table)) (local.get $i))
+                            (local.get $recurse))
+                          (call $new_static_string (i32.const {{ data(str=" => ") }}) (i32.const {{ data_length() }})))
+                        (call $inspect_entry
+                          (local.get $ctx)
+                          (array.get $AnyrefArray (struct.get $OrderedTable $values (local.get $table)) (local.get $i))
+                          (local.get $recurse))))
+                  )
+                  (else
+                    (call $value_list_push (local.get $output)
+                      (call $inspect_entry
+                        (local.get $ctx)
+                        (array.get $AnyrefArray (struct.get $OrderedTable $keys (local.get $table)) (local.get $i))
+                        (local.get $recurse)))
+                  )
+                )
+              )
+            )
+            (local.set $i (i32.add (local.get $i) (i32.const 1)))
+            (br $entries)
+          ))
+          (br $formatted)
+        )
+      )
+
+      (if (ref.test (ref $ArrayBuffer) (local.get $value))
+        (then
+          (local.set $buffer (ref.cast (ref $ArrayBuffer) (local.get $value)))
+          (local.set $length (array.len (struct.get $ArrayBuffer $data (local.get $buffer))))
+          (local.set $open (call $new_static_string (i32.const {{ data(str="ArrayBuffer {") }}) (i32.const {{ data_length() }})))
+          (call $value_list_push (local.get $output)
+            (call $inspect_array_buffer_contents
+              (struct.get $ArrayBuffer $data (local.get $buffer)) (i32.const 0) (local.get $length)))
+          (call $value_list_push (local.get $output)
+            (call $concat_strings
+              (call $new_static_string (i32.const {{ data(str="byteLength: ") }}) (i32.const {{ data_length() }}))
+              (call $number_to_string (f64.convert_i32_u (local.get $length)))))
+          (br $formatted)
+        )
+      )
+
+      (if (ref.test (ref $DataView) (local.get $value))
+        (then
+          (local.set $open (call $new_static_string (i32.const {{ data(str="DataView {") }}) (i32.const {{ data_length() }})))
+          (call $value_list_push (local.get $output)
+            (call $concat_strings
+              (call $new_static_string (i32.const {{ data(str="byteLength: ") }}) (i32.const {{ data_length() }}))
+              (call $number_to_string
+                (f64.convert_i32_u (struct.get $DataView $length (ref.cast (ref $DataView) (local.get $value)))))))
+          (call $value_list_push (local.get $output)
+            (call $concat_strings
+              (call $new_static_string (i32.const {{ data(str="byteOffset: ") }}) (i32.const {{ data_length() }}))
+              (call $number_to_string
+                (f64.convert_i32_u (struct.get $DataView $offset (ref.cast (ref $DataView) (local.get $value)))))))
+          (call $value_list_push (local.get $output)
+            (call $concat_strings
+              (call $new_static_string (i32.const {{ data(str="buffer: ") }}) (i32.const {{ data_length() }}))
+              (call $inspect_entry
+                (local.get $ctx)
+                (struct.get $DataView $buffer (ref.cast (ref $DataView) (local.get $value)))
+                (local.get $recurse))))
+          (br $formatted)
+        )
+      )
+
+      (if (ref.test (ref $Promise) (local.get $value))
+        (then
+          (local.set $promise (ref.cast (ref $Promise) (local.get $value)))
+          (local.set $open (call $new_static_string (i32.const {{ data(str="Promise {") }}) (i32.const {{ data_length() }})))
+          (call $value_list_push (local.get $output)
+            (if (result anyref) (struct.get $Promise $errored (local.get $promise))
+              (then
+                (call $concat_strings
+                  (call $new_static_string (i32.const {{ data(str="<rejected> ") }}) (i32.const {{ data_length() }}))
+                  (call $inspect_entry
+                    (local.get $ctx) (struct.get $Promise $error-result (local.get $promise)) (local.get $recurse))))
+              (else
+                (if (result anyref) (struct.get $Promise $resolved (local.get $promise))
+                  (then
+                    (call $inspect_entry
+                      (local.get $ctx) (struct.get $Promise $success-result (local.get $promise)) (local.get $recurse)))
+                  (else
+                    (call $new_static_string (i32.const {{ data(str="<pending>") }}) (i32.const {{ data_length() }})))))))
+          (br $formatted)
+        )
+      )
+
+      (if (ref.test (ref $Date) (local.get $value))
+        (then
+          (local.set $base
+            (if (result anyref)
+              (f64.ne
+                (struct.get $Date $time (ref.cast (ref $Date) (local.get $value)))
+                (struct.get $Date $time (ref.cast (ref $Date) (local.get $value))))
+              (then (call $new_static_string (i32.const {{ data(str="Invalid Date") }}) (i32.const {{ data_length() }})))
+              (else
+                (call $Date-prototype-toISOString
+                  (ref.as_non_null (global.get $scope))
+                  (local.get $value)
+                  (array.new_fixed $JSArgs 0)))))
+          (br $formatted)
+        )
+      )
+
+      (if (ref.test (ref $RegExp) (local.get $value))
+        (then
+          (local.set $base
+            (call $RegExp-prototype-toString
+              (ref.as_non_null (global.get $scope))
+              (local.get $value)
+              (array.new_fixed $JSArgs 0)))
+          (br $formatted)
+        )
+      )
+
+      (local.set $object (ref.cast (ref $Object) (local.get $value)))
+      (if (call $is_null (struct.get $Object $prototype (local.get $object)))
+        (then
+          (local.set $open
+            (call $new_static_string (i32.const {{ data(str="[Object: null prototype] {") }}) (i32.const {{ data_length() }})))
+        )
+        (else
+          (local.set $tag
+            (call $get_property
+              (local.get $object)
+              (call $well_known_symbol_key (global.get $symbol-to-string-tag))))
+          (if (call $is_string (local.get $tag))
+            (then
+              (if (call $string_length (local.get $tag))
+                (then
+                  (local.set $open
+                    (call $concat_strings
+                      (call $concat_strings
+                        (call $new_static_string (i32.const {{ data(str="Object [") }}) (i32.const {{ data_length() }}))
+                        (local.get $tag))
+                      (call $new_static_string (i32.const {{ data(str="] {") }}) (i32.const {{ data_length() }}))))
+                )
+              )
+            )
+          )
+        )
+      )
+      (call $inspect_properties
+        (local.get $ctx)
+        (struct.get $Object $properties (local.get $object))
+        (local.get $recurse)
+        (local.get $output))
+    )
+
+    (struct.set $ValueList $size
+      (struct.get $InspectContext $seen (local.get $ctx))
+      (i32.sub (struct.get $ValueList $size (struct.get $InspectContext $seen (local.get $ctx))) (i32.const 1)))
+
+    (if (struct.get $ValueList $size (local.get $output))
+      (then
+        (local.set $result
+          (call $inspect_reduce
+            (local.get $ctx) (local.get $output) (local.get $base) (local.get $open) (local.get $close)
+            (local.get $is_array) (local.get $recurse) (local.get $value)))
+      )
+      (else
+        (local.set $result
+          (if (result anyref) (call $string_length (local.get $base))
+            (then (local.get $base))
+            (else (call $concat_strings (local.get $open) (local.get $close)))))
+      )
+    )
+
+    ;; objects referenced from inside themselves are marked with <ref *1>
+    (local.set $i (call $value_list_index_of (struct.get $InspectContext $circular (local.get $ctx)) (local.get $value)))
+    (if (i32.ge_s (local.get $i) (i32.const 0))
+      (then
+        (local.set $result
+          (call $concat_strings
+            (call $concat_strings
+              (call $concat_strings
+                (call $new_static_string (i32.const {{ data(str="<ref *") }}) (i32.const {{ data_length() }}))
+                (call $number_to_string (f64.convert_i32_s (i32.add (local.get $i) (i32.const 1)))))
+              (call $new_static_string (i32.const {{ data(str="> ") }}) (i32.const {{ data_length() }})))
+            (local.get $result)))
+      )
+    )
+    (local.get $result)
+  )
+
+  ;; puts the entries on one line if they fit, otherwise each entry (or each
+  ;; row of grouped array elements) goes on its own line
+  (func $inspect_reduce
+    (param $ctx (ref $InspectContext))
+    (param $output (ref $ValueList))
+    (param $base anyref)
+    (param $open anyref)
+    (param $close anyref)
+    (param $is_array i32)
+    (param $recurse i32)
+    (param $value anyref)
+    (result anyref)
+    (local $entries i32)
+    (local $start i32)
+    (local $joined anyref)
+    (local $builder (ref $StringBuilder))
+    (local $separator (ref $StringBuilder))
+
+    (local.set $entries (struct.get $ValueList $size (local.get $output)))
+    (if (i32.and (local.get $is_array) (i32.gt_u (local.get $entries) (i32.const 6)))
+      (then
+        (local.set $output (call $inspect_group_array (local.get $ctx) (local.get $output) (local.get $value)))
+      )
+    )
+
+    (local.set $builder (call $new_string_builder))
+    (if (call $string_length (local.get $base))
+      (then
+        (call $builder_append_string (local.get $builder) (local.get $base))
+        (call $builder_append_byte (local.get $builder) (i32.const 0x20))
+      )
+    )
+    (call $builder_append_string (local.get $builder) (local.get $open))
+
+    (if (i32.and
+          (i32.lt_s
+            (i32.sub (struct.get $InspectContext $current_depth (local.get $ctx)) (local.get $recurse))
+            (i32.const 3))
+          (i32.eq (local.get $entries) (struct.get $ValueList $size (local.get $output))))
+      (then
+        (local.set $start
+          (i32.add
+            (i32.add
+              (i32.add (struct.get $ValueList $size (local.get $output)) (struct.get $InspectContext $indentation (local.get $ctx)))
+              (i32.add (call $string_length (local.get $open)) (call $string_length (local.get $base))))
+            (i32.const 10)))
+        (if (call $is_below_break_length (local.get $ctx) (local.get $output) (local.get $start))
+          (then
+            (local.set $joined
+              (call $join_strings
+                (local.get $output)
+                (call $new_static_string (i32.const {{ data(str=", ") }}) (i32.const {{ data_length() }}))))
+            (if (i32.eqz (call $string_has_byte (local.get $joined) (i32.const 0x0a)))
+              (then
+                (call $builder_append_byte (local.get $builder) (i32.const 0x20))
+                (call $builder_append_string (local.get $builder) (local.get $joined))
+                (call $builder_append_byte (local.get $builder) (i32.const 0x20))
+                (call $builder_append_string (local.get $builder) (local.get $close))
+                (return (call $builder_to_string (local.get $builder)))
+              )
+            )
+          )
+        )
+      )
+    )
+
+    (local.set $separator (call $new_string_builder))
+    (call $builder_append_byte (local.get $separator) (i32.const 0x0a))
+    (call $builder_append_spaces (local.get $separator) (struct.get $InspectContext $indentation (local.get $ctx)))
+
+    (call $builder_append_string (local.get $builder) (call $builder_to_string (local.get $separator)))
+    (call $builder_append_spaces (local.get $builder) (i32.const 2))
+    (call $builder_append_string (local.get $builder)
+      (call $join_strings
+        (local.get $output)
+        (call $concat_strings
+          (call $concat_strings
+            (call $new_static_string (i32.const {{ data(str=",") }}) (i32.const {{ data_length() }}))
+            (call $builder_to_string (local.get $separator)))
+          (call $new_static_string (i32.const {{ data(str="  ") }}) (i32.const {{ data_length() }})))))
+    (call $builder_append_string (local.get $builder) (call $builder_to_string (local.get $separator)))
+    (call $builder_append_string (local.get $builder) (local.get $close))
+    (call $builder_to_string (local.get $builder))
+  )
+
+  (func $is_below_break_length (param $ctx (ref $InspectContext)) (param $output (ref $ValueList)) (param $start i32) (result i32)
+    (local $total i32)
+    (local $i i32)
+
+    (local.set $total (i32.add (struct.get $ValueList $size (local.get $output)) (local.get $start)))
+    (if (i32.gt_u
+          (i32.add (local.get $total) (struct.get $ValueList $size (local.get $output)))
+          (struct.get $InspectContext $break_length (local.get $ctx)))
+      (then (return (i32.const 0)))
+    )
+    (block $break (loop $lengths
+      (br_if $break (i32.ge_u (local.get $i) (struct.get $ValueList $size (local.get $output))))
+      (local.set $total
+        (i32.add (local.get $total) (call $string_length (call $value_list_get (local.get $output) (local.get $i)))))
+      (if (i32.gt_u (local.get $total) (struct.get $InspectContext $break_length (local.get $ctx)))
+        (then (return (i32.const 0)))
+      )
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $lengths)
+    ))
+    (i32.const 1)
+  )
+
+  ;; arrays with more than 6 short entries are printed in columns, numbers are
+  ;; aligned to the right and everything else to the left
+  (func $inspect_group_array (param $ctx (ref $InspectContext)) (param $output (ref $ValueList)) (param $value anyref) (result (ref $ValueList))
+    (local $output_length i32)
+    (local $total_length i32)
+    (local $max_length i32)
+    (local $actual_max i32)
+    (local $length i32)
+    (local $lengths (ref $I32Array))
+    (local $widths (ref $I32Array))
+    (local $average_bias f64)
+    (local $biased_max f64)
+    (local $columns i32)
+    (local $pad_start i32)
+    (local $element anyref)
+    (local $rows (ref $ValueList))
+    (local $row (ref $StringBuilder))
+    (local $row_end i32)
+    (local $i i32)
+    (local $j i32)
+
+    (local.set $output_length (struct.get $ValueList $size (local.get $output)))
+    (if (i32.lt_u (struct.get $InspectContext $max_array_length (local.get $ctx)) (local.get $output_length))
+      (then
+        ;; the "... more items" entry doesn't take part in grouping
+        (local.set $output_length (i32.sub (local.get $output_length) (i32.const 1)))
+      )
+    )
+    (local.set $lengths (array.new_default $I32Array (local.get $output_length)))
+    (block $break (loop $measure
+      (br_if $break (i32.ge_u (local.get $i) (local.get $output_length)))
+      (local.set $length (call $string_length (call $value_list_get (local.get $output) (local.get $i))))
+      (array.set $I32Array (local.get $lengths) (local.get $i) (local.get $length))
+      (local.set $total_length (i32.add (local.get $total_length) (i32.add (local.get $length) (i32.const 2))))
+      (if (i32.lt_s (local.get $max_length) (local.get $length))
+        (then (local.set $max_length (local.get $length)))
+      )
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $measure)
+    ))
+    (local.set $actual_max (i32.add (local.get $max_length) (i32.const 2)))
+
+    (if (i32.eqz
+          (i32.and
+            (i32.lt_s
+              (i32.add (i32.mul (local.get $actual_max) (i32.const 3)) (struct.get $InspectContext $indentation (local.get $ctx)))
+              (struct.get $InspectContext $break_length (local.get $ctx)))
+            (i32.or
+              (f64.gt
+                (f64.div (f64.convert_i32_s (local.get $total_length)) (f64.convert_i32_s (local.get $actual_max)))
+                (f64.const 5))
+              (i32.le_s (local.get $max_length) (i32.const 6)))))
+      (then (return (local.get $output)))
+    )
+
+    (local.set $average_bias
+      (f64.sqrt
+        (f64.sub
+          (f64.convert_i32_s (local.get $actual_max))
+          (f64.div
+            (f64.convert_i32_s (local.get $total_length))
+            (f64.convert_i32_s (struct.get $ValueList $size (local.get $output)))))))
+    (local.set $biased_max
+      (f64.max
+        (f64.sub
+          (f64.sub (f64.convert_i32_s (local.get $actual_max)) (f64.const 3))
+          (local.get $average_bias))
+        (f64.const 1)))
+    ;; roughly a square, with characters being 2.5 times as high as wide
+    (local.set $columns
+      (i32.trunc_f64_s
+        (f64.floor
+          (f64.add
+            (f64.div
+              (f64.sqrt
+                (f64.mul
+                  (f64.mul (f64.const 2.5) (local.get $biased_max))
+                  (f64.convert_i32_s (local.get $output_length))))
+              (local.get $biased_max))
+            (f64.const 0.5)))))
+    (local.set $length
+      (i32.div_s
+        (i32.sub (struct.get $InspectContext $break_length (local.get $ctx)) (struct.get $InspectContext $indentation (local.get $ctx)))
+        (local.get $actual_max)))
+    (if (i32.lt_s (local.get $length) (local.get $columns))
+      (then (local.set $columns (local.get $length)))
+    )
+    (if (i32.gt_s (local.get $columns) (i32.const 12))
+      (then (local.set $columns (i32.const 12)))
+    )
+    (if (i32.le_s (local.get $columns) (i32.const 1))
+      (then (return (local.get $output)))
+    )
+
+    (local.set $widths (array.new_default $I32Array (local.get $columns)))
+    (local.set $i (i32.const 0))
+    (block $break (loop $column
+      (br_if $break (i32.ge_u (local.get $i) (local.get $columns)))
+      (local.set $length (i32.const 0))
+      (local.set $j (local.get $i))
+      (block $rows_done (loop $rows
+        (br_if $rows_done (i32.ge_u (local.get $j) (local.get $output_length)))
+        (if (i32.gt_s (array.get $I32Array (local.get $lengths) (local.get $j)) (local.get $length))
+          (then (local.set $length (array.get $I32Array (local.get $lengths) (local.get $j))))
+        )
+        (local.set $j (i32.add (local.get $j) (local.get $columns)))
+        (br $rows)
+      ))
+      (array.set $I32Array (local.get $widths) (local.get $i) (i32.add (local.get $length) (i32.const 2)))
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $column)
+    ))
+
+    ;; numbers and bigints are padded at the start
+    (local.set $pad_start (i32.const 1))
+    (local.set $i (i32.const 0))
+    (block $break (loop $check
+      (br_if $break (i32.ge_u (local.get $i) (struct.get $ValueList $size (local.get $output))))
+      (local.set $element (ref.null any))
+      (if (ref.test (ref $Array) (local.get $value))
+        (then
+          (if (i32.lt_u
+                (local.get $i)
+                (array.len (struct.get $Array $array (ref.cast (ref $Array) (local.get $value)))))
+            (then
+              (local.set $element
+                (array.get $AnyrefArray
+                  (struct.get $Array $array (ref.cast (ref $Array) (local.get $value)))
+                  (local.get $i)))
+            )
+          )
+        )
+      )
+      (if (ref.test (ref $TypedArray) (local.get $value))
+        (then
+          (if (i32.lt_u
+                (local.get $i)
+                (struct.get $TypedArray $length (ref.cast (ref $TypedArray) (local.get $value))))
+            (then
+              (local.set $element
+                (call $typed_array_get (ref.cast (ref $TypedArray) (local.get $value)) (local.get $i)))
+            )
+          )
+        )
+      )
+      (if (i32.eqz
+            (i32.or
+              (i32.or
+                (ref.test (ref i31) (local.get $element))
+                (ref.test (ref $Number) (local.get $element)))
+              (call $is_bigint (local.get $element))))
+        (then
+          (local.set $pad_start (i32.const 0))
+          (br $break)
+        )
+      )
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $check)
+    ))
+
+    (local.set $rows (call $new_value_list))
+    (local.set $i (i32.const 0))
+    (block $break (loop $row_loop
+      (br_if $break (i32.ge_u (local.get $i) (local.get $output_length)))
+      (local.set $row (call $new_string_builder))
+      (local.set $row_end (i32.add (local.get $i) (local.get $columns)))
+      (if (i32.gt_u (local.get $row_end) (local.get $output_length))
+        (then (local.set $row_end (local.get $output_length)))
+      )
+      (local.set $j (local.get $i))
+      (block $cells_done (loop $cells
+        (local.set $length (array.get $I32Array (local.get $lengths) (local.get $j)))
+        (local.set $max_length (array.get $I32Array (local.get $widths) (i32.sub (local.get $j) (local.get $i))))
+        (if (i32.eq (local.get $j) (i32.sub (local.get $row_end) (i32.const 1)))
+          (then
+            ;; the last cell doesn't have a separator and only pads at the start
+            (if (local.get $pad_start)
+              (then
+                (call $builder_append_spaces (local.get $row)
+                  (i32.sub (i32.sub (local.get $max_length) (i32.const 2)) (local.get $length)))
+              )
+            )
+            (call $builder_append_string (local.get $row) (call $value_list_get (local.get $output) (local.get $j)))
+            (br $cells_done)
+          )
+        )
+        (if (local.get $pad_start)
+          (then
+            (call $builder_append_spaces (local.get $row)
+              (i32.sub (i32.sub (local.get $max_length) (i32.const 2)) (local.get $length)))
+          )
+        )
+        (call $builder_append_string (local.get $row) (call $value_list_get (local.get $output) (local.get $j)))
+        (call $builder_append_string (local.get $row)
+          (call $new_static_string (i32.const {{ data(str=", ") }}) (i32.const {{ data_length() }})))
+        (if (i32.eqz (local.get $pad_start))
+          (then
+            (call $builder_append_spaces (local.get $row)
+              (i32.sub (i32.sub (local.get $max_length) (i32.const 2)) (local.get $length)))
+          )
+        )
+        (local.set $j (i32.add (local.get $j) (i32.const 1)))
+        (br $cells)
+      ))
+      (call $value_list_push (local.get $rows) (call $builder_to_string (local.get $row)))
+      (local.set $i (i32.add (local.get $i) (local.get $columns)))
+      (br $row_loop)
+    ))
+    (if (i32.lt_u (local.get $output_length) (struct.get $ValueList $size (local.get $output)))
+      (then
+        (call $value_list_push (local.get $rows)
+          (call $value_list_get (local.get $output) (local.get $output_length)))
+      )
+    )
+    (local.get $rows)
+  )
+
+  ;; -- formatting of console arguments --
+
+  ;; a single argument of a format specifier like %s or %d
+  (func $format_specifier (param $specifier i32) (param $value anyref) (result anyref)
+    (local $args (ref $JSArgs))
+
+    ;; %s
+    (if (i32.eq (local.get $specifier) (i32.const 0x73))
+      (then
+        (if (call $is_string (local.get $value))
+          (then (return (local.get $value)))
+        )
+        (if (call $is_symbol (local.get $value))
+          (then (return (call $symbol_descriptive_string (ref.cast (ref $Symbol) (local.get $value)))))
+        )
+        ;; objects are only shown one level deep
+        (return
+          (call $inspect_value
+            (call $new_inspect_context (i32.const 0) (i32.const 80) (i32.const 100))
+            (local.get $value)
+            (i32.const 0)))
+      )
+    )
+    ;; %d, %i and %f
+    (if (i32.or
+          (i32.eq (local.get $specifier) (i32.const 0x64))
+          (i32.or
+            (i32.eq (local.get $specifier) (i32.const 0x69))
+            (i32.eq (local.get $specifier) (i32.const 0x66))))
+      (then
+        (if (call $is_symbol (local.get $value))
+          (then (return (call $new_static_string (i32.const {{ data(str="NaN") }}) (i32.const {{ data_length() }}))))
+        )
+        (if (i32.and
+              (call $is_bigint (local.get $value))
+              (i32.ne (local.get $specifier) (i32.const 0x66)))
+          (then (return (call $inspect (local.get $value))))
+        )
+        (if (i32.eq (local.get $specifier) (i32.const 0x64))
+          (then (return (call $inspect_number (call $to_number (local.get $value)))))
+        )
+        (local.set $args (array.new_fixed $JSArgs 1 (local.get $value)))
+        (if (i32.eq (local.get $specifier) (i32.const 0x69))
+          (then
+            (return
+              (call $inspect_number
+                (call $number_value
+                  (call $parseInt (ref.as_non_null (global.get $scope)) (ref.null any) (local.get $args))))))
+        )
+        (return
+          (call $inspect_number
+            (call $number_value
+              (call $parseFloat (ref.as_non_null (global.get $scope)) (ref.null any) (local.get $args)))))
+      )
+    )
+    ;; %j
+    (if (i32.eq (local.get $specifier) (i32.const 0x6a))
+      (then
+        (local.set $value
+          (call $JSON-stringify
+            (ref.as_non_null (global.get $scope))
+            (ref.null any)
+            (array.new_fixed $JSArgs 1 (local.get $value))))
+        (if (ref.is_null (local.get $value))
+          (then (return (call $new_static_string (i32.const {{ data(str="undefined") }}) (i32.const {{ data_length() }}))))
+        )
+        (return (local.get $value))
+      )
+    )
+    ;; %c takes CSS, which doesn't mean anything in a terminal
+    (if (i32.eq (local.get $specifier) (i32.const 0x63))
+      (then (return (call $new_static_string (i32.const {{ data(str="") }}) (i32.const {{ data_length() }}))))
+    )
+    ;; %o and %O
+    (call $inspect (local.get $value))
+  )
+
+  ;; util.format: if the first argument is a string, it can have format
+  ;; specifiers, the remaining arguments are appended separated by spaces
+  (func $format_arguments (param $arguments (ref $JSArgs)) (param $from i32) (result (ref $String))
+    (local $builder (ref $StringBuilder))
+    (local $first anyref)
+    (local $length i32)
+    (local $next i32)
+    (local $i i32)
+    (local $last i32)
+    (local $char i32)
+    (local $value anyref)
+
+    (local.set $builder (call $new_string_builder))
+    (local.set $next (local.get $from))
+    (if (i32.ge_u (local.get $from) (array.len (local.get $arguments)))
+      (then (return (call $builder_to_string (local.get $builder))))
+    )
+
+    (local.set $first (array.get $JSArgs (local.get $arguments) (local.get $from)))
+    (if (i32.and
+          (call $is_string (local.get $first))
+          (i32.gt_u (array.len (local.get $arguments)) (i32.add (local.get $from) (i32.const 1))))
+      (then
+        (local.set $length (call $string_length (local.get $first)))
+        (block $break (loop $scan
+          (br_if $break (i32.ge_u (i32.add (local.get $i) (i32.const 1)) (local.get $length)))
+          (if (i32.eq (call $string_byte_at (local.get $first) (local.get $i)) (i32.const 0x25))
+            (then
+              (local.set $i (i32.add (local.get $i) (i32.const 1)))
+              (local.set $char (call $string_byte_at (local.get $first) (local.get $i)))
+              (block $specifier
+                (if (i32.eq (local.get $char) (i32.const 0x25))
+                  (then
+                    (call $builder_append_string (local.get $builder)
+                      (call $substring (local.get $first) (local.get $last) (local.get $i)))
+                    (local.set $last (i32.add (local.get $i) (i32.const 1)))
+                    (br $specifier)
+                  )
+                )
+                (br_if $specifier
+                  (i32.ge_u (i32.add (local.get $next) (i32.const 1)) (array.len (local.get $arguments))))
+                (br_if $specifier
+                  (i32.lt_s
+                    (call $string_index_of
+                      (call $new_static_string (i32.const {{ data(str="sdifjoOc") }}) (i32.const {{ data_length() }}))
+                      (call $substring (local.get $first) (local.get $i) (i32.add (local.get $i) (i32.const 1)))
+                      (i32.const 0))
+                    (i32.const 0)))
+                (local.set $next (i32.add (local.get $next) (i32.const 1)))
+                (call $builder_append_string (local.get $builder)
+                  (call $substring (local.get $first) (local.get $last) (i32.sub (local.get $i) (i32.const 1))))
+                (call $builder_append_string (local.get $builder)
+                  (call $format_specifier
+                    (local.get $char)
+                    (array.get $JSArgs (local.get $arguments) (local.get $next))))
+                (local.set $last (i32.add (local.get $i) (i32.const 1)))
+              )
+            )
+          )
+          (local.set $i (i32.add (local.get $i) (i32.const 1)))
+          (br $scan)
+        ))
+        (if (local.get $last)
+          (then
+            (local.set $next (i32.add (local.get $next) (i32.const 1)))
+            (call $builder_append_string (local.get $builder)
+              (call $substring (local.get $first) (local.get $last) (local.get $length)))
+            (if (i32.lt_u (local.get $next) (array.len (local.get $arguments)))
+              (then (call $builder_append_byte (local.get $builder) (i32.const 0x20)))
+            )
+          )
+        )
+      )
+    )
+
+    (block $break (loop $rest
+      (br_if $break (i32.ge_u (local.get $next) (array.len (local.get $arguments))))
+      (local.set $value (array.get $JSArgs (local.get $arguments) (local.get $next)))
+      (call $builder_append_string (local.get $builder)
+        (if (result anyref) (call $is_string (local.get $value))
+          (then (local.get $value))
+          (else (call $inspect (local.get $value)))))
+      (local.set $next (i32.add (local.get $next) (i32.const 1)))
+      (if (i32.lt_u (local.get $next) (array.len (local.get $arguments)))
+        (then (call $builder_append_byte (local.get $builder) (i32.const 0x20)))
+      )
+      (br $rest)
+    ))
+    (call $builder_to_string (local.get $builder))
+  )
+
+  ;; writes a string to a file descriptor
+  (func $write_string (param $fd i32) (param $str (ref $String))
+    (local $length i32)
+    (local $iovec i32)
+
+    (local.set $length (struct.get $String $length (local.get $str)))
+    ;; the iovec, the number of bytes written and the string itself
+    (local.set $iovec (call $malloc (i32.add (i32.const 12) (local.get $length))))
+    (call $store_string_data (local.get $str) (i32.add (local.get $iovec) (i32.const 12)))
+    (i32.store (local.get $iovec) (i32.add (local.get $iovec) (i32.const 12)))
+    (i32.store (i32.add (local.get $iovec) (i32.const 4)) (local.get $length))
+    (drop
+      (call $write
+        (local.get $fd)
+        (local.get $iovec)
+        (i32.const 1)
+        (i32.add (local.get $iovec) (i32.const 8))))
+    (call $free (local.get $iovec))
+  )
+
+  ;; writes a line indented by the current group, including the lines inside
+  ;; multiline text
+  (func $console_write (param $fd i32) (param $text anyref)
+    (local $builder (ref $StringBuilder))
+    (local $length i32)
+    (local $byte i32)
+    (local $i i32)
+
+    (local.set $builder (call $new_string_builder))
+    (call $builder_append_spaces (local.get $builder) (global.get $console-indentation))
+    (local.set $length (call $string_length (local.get $text)))
+    (block $break (loop $copy
+      (br_if $break (i32.ge_u (local.get $i) (local.get $length)))
+      (local.set $byte (call $string_byte_at (local.get $text) (local.get $i)))
+      (call $builder_append_byte (local.get $builder) (local.get $byte))
+      (if (i32.eq (local.get $byte) (i32.const 0x0a))
+        (then (call $builder_append_spaces (local.get $builder) (global.get $console-indentation)))
+      )
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $copy)
+    ))
+    (call $builder_append_byte (local.get $builder) (i32.const 0x0a))
+    (call $write_string (local.get $fd) (call $builder_to_string (local.get $builder)))
+  )
+
+  (func $console_print (param $fd i32) (param $arguments (ref $JSArgs))
+    (call $console_write (local.get $fd) (call $format_arguments (local.get $arguments) (i32.const 0)))
+  )
+
+  (func $console-log
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (call $console_print (i32.const 1) (local.get $arguments))
+    (ref.null any)
+  )
+
+  (func $console-error
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (call $console_print (i32.const 2) (local.get $arguments))
+    (ref.null any)
+  )
+
+  (func $console-dir
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (call $console_write (i32.const 1) (call $inspect (call $get_argument (local.get $arguments) (i32.const 0))))
+    (ref.null any)
+  )
+
+  (func $console-assert
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (local $message (ref $StringBuilder))
+    (local $first anyref)
+
+    (if (call $cast_ref_to_i32_bool (call $get_argument (local.get $arguments) (i32.const 0)))
+      (then (return (ref.null any)))
+    )
+    (local.set $message (call $new_string_builder))
+    (call $builder_append_string (local.get $message)
+      (call $new_static_string (i32.const {{ data(str="Assertion failed") }}) (i32.const {{ data_length() }})))
+    (if (i32.gt_u (array.len (local.get $arguments)) (i32.const 1))
+      (then
+        (local.set $first (array.get $JSArgs (local.get $arguments) (i32.const 1)))
+        (call $builder_append_string (local.get $message)
+          (if (result anyref) (call $is_string (local.get $first))
+            (then (call $new_static_string (i32.const {{ data(str=": ") }}) (i32.const {{ data_length() }})))
+            (else (call $new_static_string (i32.const {{ data(str=" ") }}) (i32.const {{ data_length() }})))))
+        (call $builder_append_string (local.get $message)
+          (call $format_arguments (local.get $arguments) (i32.const 1)))
+      )
+    )
+    (call $console_write (i32.const 2) (call $builder_to_string (local.get $message)))
+    (ref.null any)
+  )
+
+  (func $console-group
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (if (array.len (local.get $arguments))
+      (then (call $console_print (i32.const 1) (local.get $arguments)))
+    )
+    (global.set $console-indentation (i32.add (global.get $console-indentation) (i32.const 2)))
+    (ref.null any)
+  )
+
+  (func $console-groupEnd
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (if (global.get $console-indentation)
+      (then
+        (global.set $console-indentation (i32.sub (global.get $console-indentation) (i32.const 2)))
+      )
+    )
+    (ref.null any)
+  )
+
+  ;; the label of count and time, "default" if it's not given
+  (func $console_label (param $arguments (ref $JSArgs)) (result anyref)
+    (local $label anyref)
+
+    (local.set $label (call $get_argument (local.get $arguments) (i32.const 0)))
+    (if (ref.is_null (local.get $label))
+      (then
+        (return (call $new_static_string (i32.const {{ data(str="default") }}) (i32.const {{ data_length() }})))
+      )
+    )
+    (call $to_string (local.get $label))
+  )
+
+  (func $console_warning (param $before anyref) (param $label anyref) (param $after anyref)
+    (call $console_write (i32.const 2)
+      (call $concat_strings
+        (call $concat_strings
+          (call $concat_strings
+            (call $new_static_string (i32.const {{ data(str="Warning: ") }}) (i32.const {{ data_length() }}))
+            (local.get $before))
+          (local.get $label))
+        (local.get $after)))
+  )
+
+  (func $console-count
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (local $label anyref)
+    (local $key i32)
+    (local $count anyref)
+
+    (if (ref.is_null (global.get $console-counts))
+      (then (global.set $console-counts (call $new_hashmap)))
+    )
+    (local.set $label (call $console_label (local.get $arguments)))
+    (local.set $key (call $intern_string (local.get $label)))
+    (local.set $count (call $hashmap_get (ref.as_non_null (global.get $console-counts)) (local.get $key)))
+    (local.set $count
+      (call $new_number
+        (f64.add
+          (if (result f64) (call $is_no_value_found (local.get $count))
+            (then (f64.const 0))
+            (else (call $number_value (local.get $count))))
+          (f64.const 1))))
+    (call $hashmap_set (ref.as_non_null (global.get $console-counts)) (local.get $key) (local.get $count))
+    (call $console_write (i32.const 1)
+      (call $concat_strings
+        (call $concat_strings
+          (local.get $label)
+          (call $new_static_string (i32.const {{ data(str=": ") }}) (i32.const {{ data_length() }})))
+        (call $to_string (local.get $count))))
+    (ref.null any)
+  )
+
+  (func $console-countReset
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (local $label anyref)
+    (local $key i32)
+
+    (if (ref.is_null (global.get $console-counts))
+      (then (global.set $console-counts (call $new_hashmap)))
+    )
+    (local.set $label (call $console_label (local.get $arguments)))
+    (local.set $key (call $intern_string (local.get $label)))
+    (if (i32.eqz (call $hashmap_has (ref.as_non_null (global.get $console-counts)) (local.get $key)))
+      (then
+        (call $console_warning
+          (call $new_static_string (i32.const {{ data(str="Count for '") }}) (i32.const {{ data_length() }}))
+          (local.get $label)
+          (call $new_static_string (i32.const {{ data(str="' does not exist") }}) (i32.const {{ data_length() }})))
+        (return (ref.null any))
+      )
+    )
+    (call $hashmap_set
+      (ref.as_non_null (global.get $console-counts))
+      (local.get $key)
+      (call $new_number (f64.const 0)))
+    (ref.null any)
+  )
+
+  ;; elapsed time formatted like Node does, eg. 1.234ms, 1.500s or 1:05.000 (m:ss.mmm)
+  (func $format_duration (param $ms f64) (result anyref)
+    (local $minutes f64)
+    (local $seconds anyref)
+
+    (if (f64.ge (local.get $ms) (f64.const 60000))
+      (then
+        (local.set $minutes (f64.floor (f64.div (local.get $ms) (f64.const 60000))))
+        (local.set $seconds
+          (call $number_to_fixed
+            (f64.div
+              (f64.sub (local.get $ms) (f64.mul (local.get $minutes) (f64.const 60000)))
+              (f64.const 1000))
+            (i32.const 3)))
+        (if (i32.lt_u (call $string_length (local.get $seconds)) (i32.const 6))
+          (then
+            (local.set $seconds
+              (call $concat_strings
+                (call $new_static_string (i32.const {{ data(str="0") }}) (i32.const {{ data_length() }}))
+                (local.get $seconds)))
+          )
+        )
+        (return
+          (call $concat_strings
+            (call $concat_strings
+              (call $concat_strings
+                (call $number_to_string (local.get $minutes))
+                (call $new_static_string (i32.const {{ data(str=":") }}) (i32.const {{ data_length() }})))
+              (local.get $seconds))
+            (call $new_static_string (i32.const {{ data(str=" (m:ss.mmm)") }}) (i32.const {{ data_length() }}))))
+      )
+    )
+    (if (f64.ge (local.get $ms) (f64.const 1000))
+      (then
+        (return
+          (call $concat_strings
+            (call $number_to_fixed (f64.div (local.get $ms) (f64.const 1000)) (i32.const 3))
+            (call $new_static_string (i32.const {{ data(str="s") }}) (i32.const {{ data_length() }}))))
+      )
+    )
+    (call $concat_strings
+      (call $number_to_string
+        (f64.div (f64.nearest (f64.mul (local.get $ms) (f64.const 1000))) (f64.const 1000)))
+      (call $new_static_string (i32.const {{ data(str="ms") }}) (i32.const {{ data_length() }})))
+  )
+
+  (func $console_now (result f64)
+    (f64.div (f64.convert_i64_u (call $monotonic-now)) (f64.const 1000000))
+  )
+
+  (func $console-time
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (local $label anyref)
+    (local $key i32)
+
+    (if (ref.is_null (global.get $console-timers))
+      (then (global.set $console-timers (call $new_hashmap)))
+    )
+    (local.set $label (call $console_label (local.get $arguments)))
+    (local.set $key (call $intern_string (local.get $label)))
+    (if (call $hashmap_has (ref.as_non_null (global.get $console-timers)) (local.get $key))
+      (then
+        (call $console_warning
+          (call $new_static_string (i32.const {{ data(str="Label '") }}) (i32.const {{ data_length() }}))
+          (local.get $label)
+          (call $new_static_string (i32.const {{ data(str="' already exists for console.time()") }}) (i32.const {{ data_length() }})))
+        (return (ref.null any))
+      )
+    )
+    (call $hashmap_set
+      (ref.as_non_null (global.get $console-timers))
+      (local.get $key)
+      (call $new_number (call $console_now)))
+    (ref.null any)
+  )
+
+  ;; prints the time elapsed for a label, $end says if the timer is removed
+  (func $console_time_log (param $arguments (ref $JSArgs)) (param $end i32) (param $method anyref)
+    (local $label anyref)
+    (local $key i32)
+    (local $start anyref)
+    (local $message anyref)
+
+    (if (ref.is_null (global.get $console-timers))
+      (then (global.set $console-timers (call $new_hashmap)))
+    )
+    (local.set $label (call $console_label (local.get $arguments)))
+    (local.set $key (call $intern_string (local.get $label)))
+    (local.set $start (call $hashmap_get (ref.as_non_null (global.get $console-timers)) (local.get $key)))
+    (if (call $is_no_value_found (local.get $start))
+      (then
+        (call $console_warning
+          (call $new_static_string (i32.const {{ data(str="No such label '") }}) (i32.const {{ data_length() }}))
+          (local.get $label)
+          (call $concat_strings
+            (call $concat_strings
+              (call $new_static_string (i32.const {{ data(str="' for console.") }}) (i32.const {{ data_length() }}))
+              (local.get $method))
+            (call $new_static_string (i32.const {{ data(str="()") }}) (i32.const {{ data_length() }}))))
+        (return)
+      )
+    )
+
+    (local.set $message
+      (call $concat_strings
+        (call $concat_strings
+          (local.get $label)
+          (call $new_static_string (i32.const {{ data(str=": ") }}) (i32.const {{ data_length() }})))
+        (call $format_duration (f64.sub (call $console_now) (call $number_value (local.get $start))))))
+    (if (local.get $end)
+      (then
+        (drop (call $hashmap_delete (ref.as_non_null (global.get $console-timers)) (local.get $key)))
+      )
+      (else
+        ;; timeLog prints the rest of its arguments after the time
+        (if (i32.gt_u (array.len (local.get $arguments)) (i32.const 1))
+          (then
+            (local.set $message
+              (call $concat_strings
+                (call $concat_strings
+                  (local.get $message)
+                  (call $new_static_string (i32.const {{ data(str=" ") }}) (i32.const {{ data_length() }})))
+                (call $format_arguments (local.get $arguments) (i32.const 1))))
+          )
+        )
+      )
+    )
+    (call $console_write (i32.const 1) (local.get $message))
+  )
+
+  (func $console-timeEnd
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (call $console_time_log
+      (local.get $arguments)
+      (i32.const 1)
+      (call $new_static_string (i32.const {{ data(str="timeEnd") }}) (i32.const {{ data_length() }})))
+    (ref.null any)
+  )
+
+  (func $console-timeLog
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (call $console_time_log
+      (local.get $arguments)
+      (i32.const 0)
+      (call $new_static_string (i32.const {{ data(str="timeLog") }}) (i32.const {{ data_length() }})))
+    (ref.null any)
+  )
+
+  ;; -- console.table --
+
+  ;; a cell of a table is inspected on a single line with nested objects
+  ;; abbreviated
+  (func $table_cell (param $value anyref) (result anyref)
+    (local $depth i32)
+
+    (if (i32.and
+          (ref.test (ref $Object) (local.get $value))
+          (i32.gt_u
+            (call $own_key_count (local.get $value))
+            (i32.const 2)))
+      (then (local.set $depth (i32.const -1)))
+    )
+    (call $inspect_value
+      (call $new_inspect_context (local.get $depth) (i32.const 0x7fffffff) (i32.const 3))
+      (local.get $value)
+      (i32.const 0))
+  )
+
+  (func $own_key_count (param $value anyref) (result i32)
+    (array.len (struct.get $Array $array (call $own_property_entries (local.get $value) (i32.const 0))))
+  )
+
+  ;; the rows of a table as [key, value] pairs
+  (func $table_rows (param $data anyref) (result (ref $AnyrefArray))
+    (struct.get $Array $array (call $own_property_entries (local.get $data) (i32.const 2)))
+  )
+
+  (func $table_line
+    (param $widths (ref $ValueList))
+    (param $left anyref)
+    (param $middle anyref)
+    (param $right anyref)
+    (result (ref $String))
+    (local $builder (ref $StringBuilder))
+    (local $i i32)
+    (local $j i32)
+    (local $width i32)
+
+    (local.set $builder (call $new_string_builder))
+    (call $builder_append_string (local.get $builder) (local.get $left))
+    (block $break (loop $columns
+      (br_if $break (i32.ge_u (local.get $i) (struct.get $ValueList $size (local.get $widths))))
+      (if (local.get $i)
+        (then (call $builder_append_string (local.get $builder) (local.get $middle)))
+      )
+      (local.set $width (i31.get_s (ref.cast (ref i31) (call $value_list_get (local.get $widths) (local.get $i)))))
+      (local.set $j (i32.const 0))
+      (block $done (loop $dashes
+        (br_if $done (i32.ge_s (local.get $j) (local.get $width)))
+        (call $builder_append_string (local.get $builder)
+          (call $new_static_string (i32.const {{ data(str="─") }}) (i32.const {{ data_length() }})))
+        (local.set $j (i32.add (local.get $j) (i32.const 1)))
+        (br $dashes)
+      ))
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $columns)
+    ))
+    (call $builder_append_string (local.get $builder) (local.get $right))
+    (call $builder_to_string (local.get $builder))
+  )
+
+  (func $table_row (param $widths (ref $ValueList)) (param $cells (ref $ValueList)) (result (ref $String))
+    (local $builder (ref $StringBuilder))
+    (local $cell anyref)
+    (local $i i32)
+
+    (local.set $builder (call $new_string_builder))
+    (call $builder_append_string (local.get $builder)
+      (call $new_static_string (i32.const {{ data(str="│") }}) (i32.const {{ data_length() }})))
+    (block $break (loop $columns
+      (br_if $break (i32.ge_u (local.get $i) (struct.get $ValueList $size (local.get $widths))))
+      (local.set $cell (call $value_list_get (local.get $cells) (local.get $i)))
+      (call $builder_append_byte (local.get $builder) (i32.const 0x20))
+      (call $builder_append_string (local.get $builder) (local.get $cell))
+      (call $builder_append_spaces (local.get $builder)
+        (i32.sub
+          (i32.sub
+            (i31.get_s (ref.cast (ref i31) (call $value_list_get (local.get $widths) (local.get $i))))
+            (call $string_width (local.get $cell)))
+          (i32.const 1)))
+      (call $builder_append_string (local.get $builder)
+        (call $new_static_string (i32.const {{ data(str="│") }}) (i32.const {{ data_length() }})))
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $columns)
+    ))
+    (call $builder_to_string (local.get $builder))
+  )
+
+  (func $console-table
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (local $data anyref)
+    (local $filter anyref)
+    (local $rows (ref $AnyrefArray))
+    (local $row (ref $AnyrefArray))
+    (local $value anyref)
+    (local $columns (ref $ValueList))
+    (local $keys (ref $AnyrefArray))
+    (local $key anyref)
+    (local $has_values i32)
+    (local $table (ref $ValueList))
+    (local $cells (ref $ValueList))
+    (local $widths (ref $ValueList))
+    (local $width i32)
+    (local $length i32)
+    (local $lines (ref $ValueList))
+    (local $i i32)
+    (local $j i32)
+
+    (local.set $data (call $get_argument (local.get $arguments) (i32.const 0)))
+    (if (i32.eqz (call $is_object (local.get $data)))
+      (then
+        (call $console_print (i32.const 1) (local.get $arguments))
+        (return (ref.null any))
+      )
+    )
+    (local.set $filter (call $get_argument (local.get $arguments) (i32.const 1)))
+
+    ;; columns are the keys of the rows in the order they first appear
+    (local.set $rows (call $table_rows (local.get $data)))
+    (local.set $columns (call $new_value_list))
+    (if (ref.test (ref $Array) (local.get $filter))
+      (then
+        (local.set $keys (struct.get $Array $array (ref.cast (ref $Array) (local.get $filter))))
+        (block $break (loop $filtered
+          (br_if $break (i32.ge_u (local.get $i) (array.len (local.get $keys))))
+          (call $value_list_push (local.get $columns)
+            (call $to_string (array.get $AnyrefArray (local.get $keys) (local.get $i))))
+          (local.set $i (i32.add (local.get $i) (i32.const 1)))
+          (br $filtered)
+        ))
+      )
+    )
+    (local.set $i (i32.const 0))
+    (block $break (loop $scan
+      (br_if $break (i32.ge_u (local.get $i) (array.len (local.get $rows))))
+      (local.set $value
+        (array.get $AnyrefArray
+          (struct.get $Array $array (ref.cast (ref $Array) (array.get $AnyrefArray (local.get $rows) (local.get $i))))
+          (i32.const 1)))
+      (if (i32.and
+            (call $is_object (local.get $value))
+            (i32.eqz (ref.test (ref $Function) (local.get $value))))
+        (then
+          (if (i32.eqz (ref.test (ref $Array) (local.get $filter)))
+            (then
+              (local.set $keys
+                (struct.get $Array $array (call $own_property_entries (local.get $value) (i32.const 0))))
+              (local.set $j (i32.const 0))
+              (block $keys_done (loop $keys
+                (br_if $keys_done (i32.ge_u (local.get $j) (array.len (local.get $keys))))
+                (local.set $key (array.get $AnyrefArray (local.get $keys) (local.get $j)))
+                (if (i32.lt_s (call $table_column_index (local.get $columns) (local.get $key)) (i32.const 0))
+                  (then (call $value_list_push (local.get $columns) (local.get $key)))
+                )
+                (local.set $j (i32.add (local.get $j) (i32.const 1)))
+                (br $keys)
+              ))
+            )
+          )
+        )
+        (else (local.set $has_values (i32.const 1)))
+      )
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $scan)
+    ))
+
+    ;; the header and all of the rows as lists of cells
+    (local.set $table (call $new_value_list))
+    (local.set $cells (call $new_value_list))
+    (call $value_list_push (local.get $cells)
+      (call $new_static_string (i32.const {{ data(str="(index)") }}) (i32.const {{ data_length() }})))
+    (local.set $j (i32.const 0))
+    (block $break (loop $header
+      (br_if $break (i32.ge_u (local.get $j) (struct.get $ValueList $size (local.get $columns))))
+      (call $value_list_push (local.get $cells) (call $value_list_get (local.get $columns) (local.get $j)))
+      (local.set $j (i32.add (local.get $j) (i32.const 1)))
+      (br $header)
+    ))
+    (if (local.get $has_values)
+      (then
+        (call $value_list_push (local.get $cells)
+          (call $new_static_string (i32.const {{ data(str="Values") }}) (i32.const {{ data_length() }})))
+      )
+    )
+    (call $value_list_push (local.get $table) (local.get $cells))
+
+    (local.set $i (i32.const 0))
+    (block $break (loop $body
+      (br_if $break (i32.ge_u (local.get $i) (array.len (local.get $rows))))
+      (local.set $row
+        (struct.get $Array $array (ref.cast (ref $Array) (array.get $AnyrefArray (local.get $rows) (local.get $i)))))
+      (local.set $value (array.get $AnyrefArray (local.get $row) (i32.const 1)))
+      (local.set $cells (call $new_value_list))
+      (call $value_list_push (local.get $cells) (array.get $AnyrefArray (local.get $row) (i32.const 0)))
+      (local.set $j (i32.const 0))
+      (block $cells_done (loop $cells_loop
+        (br_if $cells_done (i32.ge_u (local.get $j) (struct.get $ValueList $size (local.get $columns))))
+        (local.set $key
+          (if (result anyref)
+            (i32.and
+              (call $is_object (local.get $value))
+              (i32.eqz (ref.test (ref $Function) (local.get $value))))
+            (then (call $table_value (local.get $value) (call $value_list_get (local.get $columns) (local.get $j))))
+            (else (global.get $no-value))))
+        (call $value_list_push (local.get $cells)
+          (if (result anyref) (call $is_no_value_found (local.get $key))
+            (then (call $new_static_string (i32.const {{ data(str="") }}) (i32.const {{ data_length() }})))
+            (else (call $table_cell (local.get $key)))))
+        (local.set $j (i32.add (local.get $j) (i32.const 1)))
+        (br $cells_loop)
+      ))
+      (if (local.get $has_values)
+        (then
+          (call $value_list_push (local.get $cells)
+            (if (result anyref)
+              (i32.and
+                (call $is_object (local.get $value))
+                (i32.eqz (ref.test (ref $Function) (local.get $value))))
+              (then (call $new_static_string (i32.const {{ data(str="") }}) (i32.const {{ data_length() }})))
+              (else (call $table_cell (local.get $value)))))
+        )
+      )
+      (call $value_list_push (local.get $table) (local.get $cells))
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $body)
+    ))
+
+    ;; each column is as wide as its widest cell plus a space on each side
+    (local.set $widths (call $new_value_list))
+    (local.set $cells (ref.cast (ref $ValueList) (call $value_list_get (local.get $table) (i32.const 0))))
+    (local.set $j (i32.const 0))
+    (block $break (loop $columns_loop
+      (br_if $break (i32.ge_u (local.get $j) (struct.get $ValueList $size (local.get $cells))))
+      (local.set $width (i32.const 0))
+      (local.set $i (i32.const 0))
+      (block $rows_done (loop $rows_loop
+        (br_if $rows_done (i32.ge_u (local.get $i) (struct.get $ValueList $size (local.get $table))))
+        (local.set $length
+          (call $string_width
+            (call $value_list_get
+              (ref.cast (ref $ValueList) (call $value_list_get (local.get $table) (local.get $i)))
+              (local.get $j))))
+        (if (i32.gt_s (local.get $length) (local.get $width))
+          (then (local.set $width (local.get $length)))
+        )
+        (local.set $i (i32.add (local.get $i) (i32.const 1)))
+        (br $rows_loop)
+      ))
+      (call $value_list_push (local.get $widths) (ref.i31 (i32.add (local.get $width) (i32.const 2))))
+      (local.set $j (i32.add (local.get $j) (i32.const 1)))
+      (br $columns_loop)
+    ))
+
+    (local.set $lines (call $new_value_list))
+    (call $value_list_push (local.get $lines)
+      (call $table_line
+        (local.get $widths)
+        (call $new_static_string (i32.const {{ data(str="┌") }}) (i32.const {{ data_length() }}))
+        (call $new_static_string (i32.const {{ data(str="┬") }}) (i32.const {{ data_length() }}))
+        (call $new_static_string (i32.const {{ data(str="┐") }}) (i32.const {{ data_length() }}))))
+    (local.set $i (i32.const 0))
+    (block $break (loop $lines_loop
+      (br_if $break (i32.ge_u (local.get $i) (struct.get $ValueList $size (local.get $table))))
+      (call $value_list_push (local.get $lines)
+        (call $table_row
+          (local.get $widths)
+          (ref.cast (ref $ValueList) (call $value_list_get (local.get $table) (local.get $i)))))
+      (if (i32.eqz (local.get $i))
+        (then
+          (call $value_list_push (local.get $lines)
+            (call $table_line
+              (local.get $widths)
+              (call $new_static_string (i32.const {{ data(str="├") }}) (i32.const {{ data_length() }}))
+              (call $new_static_string (i32.const {{ data(str="┼") }}) (i32.const {{ data_length() }}))
+              (call $new_static_string (i32.const {{ data(str="┤") }}) (i32.const {{ data_length() }}))))
+        )
+      )
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $lines_loop)
+    ))
+    (call $value_list_push (local.get $lines)
+      (call $table_line
+        (local.get $widths)
+        (call $new_static_string (i32.const {{ data(str="└") }}) (i32.const {{ data_length() }}))
+        (call $new_static_string (i32.const {{ data(str="┴") }}) (i32.const {{ data_length() }}))
+        (call $new_static_string (i32.const {{ data(str="┘") }}) (i32.const {{ data_length() }}))))
+
+    (call $console_write (i32.const 1)
+      (call $join_strings
+        (local.get $lines)
+        (call $new_static_string (i32.const {{ data(str="\n") }}) (i32.const {{ data_length() }}))))
+    (ref.null any)
+  )
+
+  ;; the own property of a row shown in a column, or $no-value if it doesn't
+  ;; have one, arrays are included by going through the entries
+  (func $table_value (param $row anyref) (param $column anyref) (result anyref)
+    (local $entries (ref $AnyrefArray))
+    (local $pair (ref $AnyrefArray))
+    (local $i i32)
+
+    (local.set $entries (struct.get $Array $array (call $own_property_entries (local.get $row) (i32.const 2))))
+    (block $break (loop $search
+      (br_if $break (i32.ge_u (local.get $i) (array.len (local.get $entries))))
+      (local.set $pair
+        (struct.get $Array $array (ref.cast (ref $Array) (array.get $AnyrefArray (local.get $entries) (local.get $i)))))
+      (if (call $string_equals (array.get $AnyrefArray (local.get $pair) (i32.const 0)) (local.get $column))
+        (then (return (array.get $AnyrefArray (local.get $pair) (i32.const 1))))
+      )
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $search)
+    ))
+    (global.get $no-value)
+  )
+
+  (func $table_column_index (param $columns (ref $ValueList)) (param $key anyref) (result i32)
+    (local $i i32)
+
+    (block $break (loop $search
+      (br_if $break (i32.ge_u (local.get $i) (struct.get $ValueList $size (local.get $columns))))
+      (if (call $string_equals (call $value_list_get (local.get $columns) (local.get $i)) (local.get $key))
+        (then (return (local.get $i)))
+      )
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $search)
+    ))
+    (i32.const -1)
+  )
+
+  (elem declare func $console-log)
+  (elem declare func $console-error)
+  (elem declare func $console-dir)
+  (elem declare func $console-assert)
+  (elem declare func $console-group)
+  (elem declare func $console-groupEnd)
+  (elem declare func $console-count)
+  (elem declare func $console-countReset)
+  (elem declare func $console-time)
+  (elem declare func $console-timeEnd)
+  (elem declare func $console-timeLog)
+  (elem declare func $console-table)
+
+  (func $install-console-globals (param $scope (ref $Scope))
+    (local $console (ref $Object))
+
+    (local.set $console (call $new_object))
+    (call $add_method (local.get $console) (i32.const {{ data(str="log") }}) (ref.func $console-log))
+    (call $add_method (local.get $console) (i32.const {{ data(str="info") }}) (ref.func $console-log))
+    (call $add_method (local.get $console) (i32.const {{ data(str="debug") }}) (ref.func $console-log))
+    (call $add_method (local.get $console) (i32.const {{ data(str="warn") }}) (ref.func $console-error))
+    (call $add_method (local.get $console) (i32.const {{ data(str="error") }}) (ref.func $console-error))
+    (call $add_method (local.get $console) (i32.const {{ data(str="dir") }}) (ref.func $console-dir))
+    (call $add_method (local.get $console) (i32.const {{ data(str="assert") }}) (ref.func $console-assert))
+    (call $add_method (local.get $console) (i32.const {{ data(str="group") }}) (ref.func $console-group))
+    (call $add_method (local.get $console) (i32.const {{ data(str="groupCollapsed") }}) (ref.func $console-group))
+    (call $add_method (local.get $console) (i32.const {{ data(str="groupEnd") }}) (ref.func $console-groupEnd))
+    (call $add_method (local.get $console) (i32.const {{ data(str="count") }}) (ref.func $console-count))
+    (call $add_method (local.get $console) (i32.const {{ data(str="countReset") }}) (ref.func $console-countReset))
+    (call $add_method (local.get $console) (i32.const {{ data(str="time") }}) (ref.func $console-time))
+    (call $add_method (local.get $console) (i32.const {{ data(str="timeEnd") }}) (ref.func $console-timeEnd))
+    (call $add_method (local.get $console) (i32.const {{ data(str="timeLog") }}) (ref.func $console-timeLog))
+    (call $add_method (local.get $console) (i32.const {{ data(str="table") }}) (ref.func $console-table))
+    (call $set_variable (local.get $scope) (i32.const {{ data(str="console") }}) (local.get $console))
+  )
@@ -0,0 +1,145 @@
+  ;; The `crypto` global from the Web Crypto API, without `subtle`. The bytes
+  ;; come from wasi:random/random, which is meant to be cryptographically
+  ;; secure, so unlike Math.random they don't depend on JAWS_RANDOM_SEED. It's
+  ;; only included when the script mentions `crypto`.
+
+  ;; the most getRandomValues fills at once, like in browsers
+  (global $crypto-max-bytes i32 (i32.const 65536))
+
+  ;; list<u8>, allocated by the host
+  (func $random_bytes (param $length i32) (result (ref $CharArray))
+    (local $result i32)
+    (local $list i32)
+    (local $data (ref $CharArray))
+    (local $i i32)
+
+    (local.set $result (call $malloc (i32.const 8)))
+    (call $get-random-bytes (i64.extend_i32_u (local.get $length)) (local.get $result))
+    (local.set $list (i32.load (local.get $result)))
+    (call $free (local.get $result))
+
+    (local.set $data (array.new_default $CharArray (local.get $length)))
+    (block $break (loop $bytes
+      (br_if $break (i32.ge_u (local.get $i) (local.get $length)))
+      (array.set $CharArray (local.get $data) (local.get $i)
+        (i32.load8_u (i32.add (local.get $list) (local.get $i))))
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $bytes)
+    ))
+    (call $free (local.get $list))
+    (local.get $data)
+  )
+
+  ;; getRandomValues(array) fills an integer typed array in place and returns it
+  (func $crypto-getRandomValues
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (local $value anyref)
+    (local $array (ref $TypedArray))
+    (local $length i32)
+
+    (local.set $value (call $get_argument (local.get $arguments) (i32.const 0)))
+    (if (i32.eqz (ref.test (ref $TypedArray) (local.get $value)))
+      (then
+        (call $throw_type_error
+          (call $new_static_string
+            (i32.const {{ data(str="The data argument must be an integer-type TypedArray") }})
+            (i32.const {{ data_length() }})))
+      )
+    )
+    (local.set $array (ref.cast (ref $TypedArray) (local.get $value)))
+    (if (call $is_float_kind (struct.get $TypedArray $kind (local.get $array)))
+      (then
+        (call $throw_type_error
+          (call $new_static_string
+            (i32.const {{ data(str="The data argument must be an integer-type TypedArray") }})
+            (i32.const {{ data_length() }})))
+      )
+    )
+
+    (local.set $length
+      (i32.mul
+        (struct.get $TypedArray $length (local.get $array))
+        (call $element_size (struct.get $TypedArray $kind (local.get $array)))))
+    (if (i32.gt_u (local.get $length) (global.get $crypto-max-bytes))
+      (then
+        (call $throw_range_error
+          (call $concat_strings
+            (call $concat_strings
+              (call $new_static_string
+                (i32.const {{ data(str="The ArrayBufferView's byte length (") }})
+                (i32.const {{ data_length() }}))
+              (call $to_string (call $new_number (f64.convert_i32_u (local.get $length)))))
+            (call $new_static_string
+              (i32.const {{ data(str=") exceeds the number of bytes of entropy available via this API (65536)") }})
+              (i32.const {{ data_length() }}))))
+      )
+    )
+
+    (array.copy $CharArray $CharArray
+      (struct.get $ArrayBuffer $data (struct.get $TypedArray $buffer (local.get $array)))
+      (struct.get $TypedArray $offset (local.get $array))
+      (call $random_bytes (local.get $length))
+      (i32.const 0)
+      (local.get $length))
+    (local.get $array)
+  )
+
+  ;; randomUUID() returns a version 4 UUID, 122 random bits formatted as
+  ;; xxxxxxxx-xxxx-4xxx-yxxx-xxxxxxxxxxxx
+  (func $crypto-randomUUID
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (local $bytes (ref $CharArray))
+    (local $builder (ref $StringBuilder))
+    (local $i i32)
+
+    (local.set $bytes (call $random_bytes (i32.const 16)))
+    ;; the version in the high nibble of byte 6, the variant in the top two
+    ;; bits of byte 8
+    (array.set $CharArray (local.get $bytes) (i32.const 6)
+      (i32.or
+        (i32.and (array.get_u $CharArray (local.get $bytes) (i32.const 6)) (i32.const 0x0f))
+        (i32.const 0x40)))
+    (array.set $CharArray (local.get $bytes) (i32.const 8)
+      (i32.or
+        (i32.and (array.get_u $CharArray (local.get $bytes) (i32.const 8)) (i32.const 0x3f))
+        (i32.const 0x80)))
+
+    (local.set $builder (call $new_string_builder))
+    (block $break (loop $bytes
+      (br_if $break (i32.ge_u (local.get $i) (i32.const 16)))
+      (if (i32.or
+            (i32.or (i32.eq (local.get $i) (i32.const 4)) (i32.eq (local.get $i) (i32.const 6)))
+            (i32.or (i32.eq (local.get $i) (i32.const 8)) (i32.eq (local.get $i) (i32.const 10))))
+        (then (call $builder_append_byte (local.get $builder) (i32.const 0x2d)))
+      )
+      (call $builder_append_hex_byte
+        (local.get $builder)
+        (array.get_u $CharArray (local.get $bytes) (local.get $i))
+        (i32.const 0x61))
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $bytes)
+    ))
+    (call $builder_to_string (local.get $builder))
+  )
+
+  (elem declare func $crypto-getRandomValues)
+  (elem declare func $crypto-randomUUID)
+
+  (func $install-crypto-globals (param $scope (ref $Scope))
+    (local $crypto (ref $Object))
+
+    (local.set $crypto (call $new_object))
+    (call $add_method (local.get $crypto) (i32.const {{ data(str="getRandomValues") }}) (ref.func $crypto-getRandomValues))
+    (call $add_method (local.get $crypto) (i32.const {{ data(str="randomUUID") }}) (ref.func $crypto-randomUUID))
+    (call $set_to_string_tag (local.get $crypto)
+      (call $new_static_string (i32.const {{ data(str="Crypto") }}) (i32.const {{ data_length() }})))
+    (call $set_variable (local.get $scope) (i32.const {{ data(str="crypto") }}) (local.get $crypto))
+  )
@@ -0,0 +1,1203 @@
+  ;; `Date` and `performance`
+  ;;
+  ;; A date is a time value in milliseconds since the epoch, NaN for invalid
+  ;; dates, and all the calendar math works on it the way the spec describes.
+  ;; There's no time zone support, so local time is the same as UTC and the
+  ;; local getters and setters are the UTC ones.
+
+  {% set ms_per_day = 86400000 %}
+  {% set field_year = 0 %}
+  {% set field_month = 1 %}
+  {% set field_date = 2 %}
+  {% set field_hours = 3 %}
+  {% set field_minutes = 4 %}
+  {% set field_seconds = 5 %}
+  {% set field_milliseconds = 6 %}
+  {% set field_day = 7 %}
+
+  (type $Date
+    (sub final $Object
+      (struct
+        (field $properties (mut (ref $HashMap)))
+        (field $prototype (mut anyref))
+        (field $hash (mut i32))
+        (field $time (mut f64)))))
+
+  (type $F64Array (array (mut f64)))
+
+  (global $date-prototype (mut (ref null $Object)) (ref.null $Object))
+  ;; the monotonic clock reading performance.now() is relative to
+  (global $time-origin (mut i64) (i64.const 0))
+
+  ;; wall-clock.now returns a datetime record of u64 seconds and u32
+  ;; nanoseconds, written to linear memory
+  (func $current_time (result f64)
+    (local $datetime i32)
+    (local $time f64)
+
+{% if date %}
+    (local.set $datetime (call $malloc (i32.const 12)))
+    (call $wall-clock-now (local.get $datetime))
+    (local.set $time
+      (f64.add
+        (f64.mul (f64.convert_i64_u (i64.load (local.get $datetime))) (f64.const 1000))
+        (f64.convert_i32_u
+          (i32.div_u
+            (i32.load (i32.add (local.get $datetime) (i32.const 8)))
+            (i32.const 1000000)))))
+    (call $free (local.get $datetime))
+    (local.get $time)
+{% else %}
+    ;; Date was reached by a name built at runtime, eg. globalThis["Da" + "te"],
+    ;; so the module was compiled without the wall clock
+    (call $throw_type_error
+      (call $new_static_string
+        (i32.const {{ data(str="The wall clock is not available, wasi:clocks/wall-clock is only imported by scripts mentioning Date or performance") }})
+        (i32.const {{ data_length() }})))
+    (unreachable)
+{% endif %}
+  )
+
+  (func $time_clip (param $time f64) (result f64)
+    (if (i32.eqz (call $is_finite (local.get $time)))
+      (then (return (f64.const nan)))
+    )
+    (if (f64.gt (f64.abs (local.get $time)) (f64.const 8.64e15))
+      (then (return (f64.const nan)))
+    )
+    ;; adding zero turns -0 into +0
+    (f64.add (f64.trunc (local.get $time)) (f64.const 0))
+  )
+
+  (func $is_finite (param $value f64) (result i32)
+    (f64.eq (f64.sub (local.get $value) (local.get $value)) (f64.const 0))
+  )
+
+  ;; the number of days between the epoch and the given date in the
+  ;; proleptic Gregorian calendar, with months from 1 to 12
+  (func $days_from_civil (param $year i64) (param $month i64) (param $day i64) (result i64)
+    (local $era i64)
+    (local $year_of_era i64)
+    (local $day_of_year i64)
+
+    (if (i64.le_s (local.get $month) (i64.const 2))
+      (then (local.set $year (i64.sub (local.get $year) (i64.const 1))))
+    )
+    (local.set $era
+      (i64.div_s
+        (if (result i64) (i64.ge_s (local.get $year) (i64.const 0))
+          (then (local.get $year))
+          (else (i64.sub (local.get $year) (i64.const 399)))
+        )
+        (i64.const 400)))
+    (local.set $year_of_era (i64.sub (local.get $year) (i64.mul (local.get $era) (i64.const 400))))
+    (local.set $day_of_year
+      (i64.add
+        (i64.div_s
+          (i64.add
+            (i64.mul
+              (i64.const 153)
+              (if (result i64) (i64.gt_s (local.get $month) (i64.const 2))
+                (then (i64.sub (local.get $month) (i64.const 3)))
+                (else (i64.add (local.get $month) (i64.const 9)))
+              ))
+            (i64.const 2))
+          (i64.const 5))
+        (i64.sub (local.get $day) (i64.const 1))))
+    (i64.sub
+      (i64.add
+        (i64.mul (local.get $era) (i64.const 146097))
+        (i64.add
+          (i64.sub
+            (i64.add
+              (i64.mul (local.get $year_of_era) (i64.const 365))
+              (i64.div_s (local.get $year_of_era) (i64.const 4)))
+            (i64.div_s (local.get $year_of_era) (i64.const 100)))
+          (local.get $day_of_year)))
+      (i64.const 719468))
+  )
+
+  ;; the inverse of $days_from_civil, returns the year, the month (from 1 to
+  ;; 12) or the day of the month
+  (func $civil_from_days (param $days i64) (param $field i32) (result i64)
+    (local $era i64)
+    (local $day_of_era i64)
+    (local $year_of_era i64)
+    (local $day_of_year i64)
+    (local $month_index i64)
+    (local $month i64)
+
+    (local.set $days (i64.add (local.get $days) (i64.const 719468)))
+    (local.set $era
+      (i64.div_s
+        (if (result i64) (i64.ge_s (local.get $days) (i64.const 0))
+          (then (local.get $days))
+          (else (i64.sub (local.get $days) (i64.const 146096)))
+        )
+        (i64.const 146097)))
+    (local.set $day_of_era (i64.sub (local.get $days) (i64.mul (local.get $era) (i64.const 146097))))
+    (local.set $year_of_era
+      (i64.div_s
+        (i64.sub
+          (i64.add
+            (i64.sub
+              (local.get $day_of_era)
+              (i64.div_s (local.get $day_of_era) (i64.const 1460)))
+            (i64.div_s (local.get $day_of_era) (i64.const 36524)))
+          (i64.div_s (local.get $day_of_era) (i64.const 146096)))
+        (i64.const 365)))
+    (local.set $day_of_year
+      (i64.sub
+        (local.get $day_of_era)
+        (i64.sub
+          (i64.add
+            (i64.mul (local.get $year_of_era) (i64.const 365))
+            (i64.div_s (local.get $year_of_era) (i64.const 4)))
+          (i64.div_s (local.get $year_of_era) (i64.const 100)))))
+    (local.set $month_index
+      (i64.div_s
+        (i64.add (i64.mul (local.get $day_of_year) (i64.const 5)) (i64.const 2))
+        (i64.const 153)))
+
+    (if (i32.eq (local.get $field) (i32.const {{ field_date }}))
+      (then
+        (return
+          (i64.add
+            (i64.sub
+              (local.get $day_of_year)
+              (i64.div_s
+                (i64.add (i64.mul (local.get $month_index) (i64.const 153)) (i64.const 2))
+                (i64.const 5)))
+            (i64.const 1)))
+      )
+    )
+
+    (local.set $month
+      (if (result i64) (i64.lt_s (local.get $month_index) (i64.const 10))
+        (then (i64.add (local.get $month_index) (i64.const 3)))
+        (else (i64.sub (local.get $month_index) (i64.const 9)))
+      ))
+    (if (i32.eq (local.get $field) (i32.const {{ field_month }}))
+      (then (return (local.get $month)))
+    )
+
+    (i64.add
+      (i64.add (local.get $year_of_era) (i64.mul (local.get $era) (i64.const 400)))
+      (i64.extend_i32_u (i64.le_s (local.get $month) (i64.const 2))))
+  )
+
+  ;; MakeDay from the spec, months outside of 0-11 move to other years
+  (func $make_day (param $year f64) (param $month f64) (param $date f64) (result f64)
+    (local $full_year f64)
+
+    (if (i32.eqz
+          (i32.and
+            (call $is_finite (local.get $year))
+            (i32.and (call $is_finite (local.get $month)) (call $is_finite (local.get $date)))))
+      (then (return (f64.const nan)))
+    )
+    (local.set $year (f64.trunc (local.get $year)))
+    (local.set $month (f64.trunc (local.get $month)))
+    (local.set $date (f64.trunc (local.get $date)))
+
+    (local.set $full_year
+      (f64.add (local.get $year) (f64.floor (f64.div (local.get $month) (f64.const 12)))))
+    ;; anything further away would be outside of the time value range anyway
+    (if (f64.gt (f64.abs (local.get $full_year)) (f64.const 400000))
+      (then (return (f64.const nan)))
+    )
+    (local.set $month
+      (f64.sub
+        (local.get $month)
+        (f64.mul (f64.floor (f64.div (local.get $month) (f64.const 12))) (f64.const 12))))
+
+    (f64.add
+      (f64.convert_i64_s
+        (call $days_from_civil
+          (i64.trunc_f64_s (local.get $full_year))
+          (i64.add (i64.trunc_f64_s (local.get $month)) (i64.const 1))
+          (i64.const 1)))
+      (f64.sub (local.get $date) (f64.const 1)))
+  )
+
+  (func $make_time (param $hours f64) (param $minutes f64) (param $seconds f64) (param $milliseconds f64) (result f64)
+    (if (i32.eqz
+          (i32.and
+            (i32.and (call $is_finite (local.get $hours)) (call $is_finite (local.get $minutes)))
+            (i32.and (call $is_finite (local.get $seconds)) (call $is_finite (local.get $milliseconds)))))
+      (then (return (f64.const nan)))
+    )
+    (f64.add
+      (f64.add
+        (f64.mul (f64.trunc (local.get $hours)) (f64.const 3600000))
+        (f64.mul (f64.trunc (local.get $minutes)) (f64.const 60000)))
+      (f64.add
+        (f64.mul (f64.trunc (local.get $seconds)) (f64.const 1000))
+        (f64.trunc (local.get $milliseconds))))
+  )
+
+  (func $make_date (param $day f64) (param $time f64) (result f64)
+    (if (i32.eqz (i32.and (call $is_finite (local.get $day)) (call $is_finite (local.get $time))))
+      (then (return (f64.const nan)))
+    )
+    (f64.add (f64.mul (local.get $day) (f64.const {{ ms_per_day }})) (local.get $time))
+  )
+
+  ;; one of the calendar fields of a valid time value, the day field is the
+  ;; day of the week
+  (func $date_field (param $time f64) (param $field i32) (result f64)
+    (local $day f64)
+    (local $time_in_day f64)
+
+    (local.set $day (f64.floor (f64.div (local.get $time) (f64.const {{ ms_per_day }}))))
+    (local.set $time_in_day
+      (f64.sub (local.get $time) (f64.mul (local.get $day) (f64.const {{ ms_per_day }}))))
+
+    (if (i32.lt_u (local.get $field) (i32.const {{ field_hours }}))
+      (then
+        (return
+          (f64.convert_i64_s
+            (i64.sub
+              (call $civil_from_days (i64.trunc_f64_s (local.get $day)) (local.get $field))
+              ;; months are counted from 0
+              (i64.extend_i32_u (i32.eq (local.get $field) (i32.const {{ field_month }}))))))
+      )
+    )
+    (if (i32.eq (local.get $field) (i32.const {{ field_hours }}))
+      (then (return (f64.floor (f64.div (local.get $time_in_day) (f64.const 3600000)))))
+    )
+    (if (i32.eq (local.get $field) (i32.const {{ field_minutes }}))
+      (then
+        (return
+          (call $positive_remainder
+            (f64.floor (f64.div (local.get $time_in_day) (f64.const 60000)))
+            (f64.const 60)))
+      )
+    )
+    (if (i32.eq (local.get $field) (i32.const {{ field_seconds }}))
+      (then
+        (return
+          (call $positive_remainder
+            (f64.floor (f64.div (local.get $time_in_day) (f64.const 1000)))
+            (f64.const 60)))
+      )
+    )
+    (if (i32.eq (local.get $field) (i32.const {{ field_milliseconds }}))
+      (then (return (call $positive_remainder (local.get $time_in_day) (f64.const 1000))))
+    )
+    ;; the epoch was a Thursday
+    (call $positive_remainder (f64.add (local.get $day) (f64.const 4)) (f64.const 7))
+  )
+
+  ;; modulo with a non-negative result
+  (func $positive_remainder (param $value f64) (param $divisor f64) (result f64)
+    (f64.sub
+      (local.get $value)
+      (f64.mul
+        (f64.floor (f64.div (local.get $value) (local.get $divisor)))
+        (local.get $divisor)))
+  )
+
+  (func $new_date (param $time f64) (result (ref $Date))
+    (struct.new $Date
+      (call $new_hashmap)
+      (global.get $date-prototype)
+      (i32.const 0)
+      (local.get $time))
+  )
+
+  (func $this_date (param $this anyref) (result (ref $Date))
+    (if (ref.test (ref $Date) (local.get $this))
+      (then (return (ref.cast (ref $Date) (local.get $this))))
+    )
+    (call $throw_type_error
+      (call $new_static_string (i32.const {{ data(str="this is not a Date object.") }}) (i32.const {{ data_length() }})))
+    (unreachable)
+  )
+
+  (func $date_number_argument (param $arguments (ref $JSArgs)) (param $index i32) (param $default f64) (result f64)
+    (if (i32.ge_u (local.get $index) (array.len (local.get $arguments)))
+      (then (return (local.get $default)))
+    )
+    (call $to_number (array.get $JSArgs (local.get $arguments) (local.get $index)))
+  )
+
+  ;; the time value for the arguments of Date.UTC and the Date constructor,
+  ;; years from 0 to 99 mean 1900 to 1999
+  (func $date_from_components (param $arguments (ref $JSArgs)) (result f64)
+    (local $year f64)
+    (local $integer_year f64)
+
+    (local.set $year (call $date_number_argument (local.get $arguments) (i32.const 0) (f64.const nan)))
+    (if (f64.eq (local.get $year) (local.get $year))
+      (then
+        (local.set $integer_year (f64.trunc (local.get $year)))
+        (if (i32.and
+              (f64.ge (local.get $integer_year) (f64.const 0))
+              (f64.le (local.get $integer_year) (f64.const 99)))
+          (then (local.set $year (f64.add (local.get $integer_year) (f64.const 1900))))
+        )
+      )
+    )
+
+    (call $time_clip
+      (call $make_date
+        (call $make_day
+          (local.get $year)
+          (call $date_number_argument (local.get $arguments) (i32.const 1) (f64.const 0))
+          (call $date_number_argument (local.get $arguments) (i32.const 2) (f64.const 1)))
+        (call $make_time
+          (call $date_number_argument (local.get $arguments) (i32.const 3) (f64.const 0))
+          (call $date_number_argument (local.get $arguments) (i32.const 4) (f64.const 0))
+          (call $date_number_argument (local.get $arguments) (i32.const 5) (f64.const 0))
+          (call $date_number_argument (local.get $arguments) (i32.const 6) (f64.const 0)))))
+  )
+
+  ;; reads exactly $count digits, returns -1 if there aren't enough of them
+  (func $date_parse_digits (param $string anyref) (param $position i32) (param $count i32) (result i32)
+    (local $value i32)
+    (local $digit i32)
+    (local $i i32)
+
+    (if (i32.gt_u
+          (i32.add (local.get $position) (local.get $count))
+          (call $string_length (local.get $string)))
+      (then (return (i32.const -1)))
+    )
+    (block $done (loop $digits
+      (br_if $done (i32.ge_u (local.get $i) (local.get $count)))
+      (local.set $digit
+        (i32.sub
+          (call $string_byte_at (local.get $string) (i32.add (local.get $position) (local.get $i)))
+          (i32.const 0x30)))
+      (if (i32.ge_u (local.get $digit) (i32.const 10))
+        (then (return (i32.const -1)))
+      )
+      (local.set $value (i32.add (i32.mul (local.get $value) (i32.const 10)) (local.get $digit)))
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $digits)
+    ))
+    (local.get $value)
+  )
+
+  (func $date_byte_is (param $string anyref) (param $position i32) (param $byte i32) (result i32)
+    (if (i32.ge_u (local.get $position) (call $string_length (local.get $string)))
+      (then (return (i32.const 0)))
+    )
+    (i32.eq (call $string_byte_at (local.get $string) (local.get $position)) (local.get $byte))
+  )
+
+  ;; parses the date time string format from the spec, like
+  ;; 2024-05-01T12:30:00.000Z, returns NaN for anything else
+  (func $date_parse (param $string anyref) (result f64)
+    (local $length i32)
+    (local $position i32)
+    (local $sign i32)
+    (local $year i32)
+    (local $month i32)
+    (local $day i32)
+    (local $hours i32)
+    (local $minutes i32)
+    (local $seconds i32)
+    (local $milliseconds i32)
+    (local $scale i32)
+    (local $digit i32)
+    (local $offset i32)
+    (local $offset_sign i32)
+    (local $offset_hours i32)
+    (local $offset_minutes i32)
+
+    (local.set $length (call $string_length (local.get $string)))
+    (local.set $month (i32.const 1))
+    (local.set $day (i32.const 1))
+
+    ;; the year has 4 digits, or 6 with a sign
+    (local.set $sign (i32.const 1))
+    (if (i32.or
+          (call $date_byte_is (local.get $string) (i32.const 0) (i32.const 0x2b)) ;; +
+          (call $date_byte_is (local.get $string) (i32.const 0) (i32.const 0x2d))) ;; -
+      (then
+        (if (call $date_byte_is (local.get $string) (i32.const 0) (i32.const 0x2d))
+          (then (local.set $sign (i32.const -1)))
+        )
+        (local.set $year (call $date_parse_digits (local.get $string) (i32.const 1) (i32.const 6)))
+        ;; -000000 is not a valid year
+        (if (i32.and (i32.eqz (local.get $year)) (i32.eq (local.get $sign) (i32.const -1)))
+          (then (return (f64.const nan)))
+        )
+        (local.set $position (i32.const 7))
+      )
+      (else
+        (local.set $year (call $date_parse_digits (local.get $string) (i32.const 0) (i32.const 4)))
+        (local.set $position (i32.const 4))
+      )
+    )
+    (if (i32.lt_s (local.get $year) (i32.const 0))
+      (then (return (f64.const nan)))
+    )
+
+    (if (call $date_byte_is (local.get $string) (local.get $position) (i32.const 0x2d))
+      (then
+        (local.set $month
+          (call $date_parse_digits (local.get $string) (i32.add (local.get $position) (i32.const 1)) (i32.const 2)))
+        (local.set $position (i32.add (local.get $position) (i32.const 3)))
+        (if (call $date_byte_is (local.get $string) (local.get $position) (i32.const 0x2d))
+          (then
+            (local.set $day
+              (call $date_parse_digits (local.get $string) (i32.add (local.get $position) (i32.const 1)) (i32.const 2)))
+            (local.set $position (i32.add (local.get $position) (i32.const 3)))
+          )
+        )
+      )
+    )
+    (if (i32.or
+          (i32.or (i32.lt_s (local.get $month) (i32.const 1)) (i32.gt_s (local.get $month) (i32.const 12)))
+          (i32.lt_s (local.get $day) (i32.const 1)))
+      (then (return (f64.const nan)))
+    )
+    ;; like other engines, days that don't exist in the month roll over into
+    ;; the next one
+    (if (i32.gt_s (local.get $day) (i32.const 31))
+      (then (return (f64.const nan)))
+    )
+
+    ;; date-only forms are UTC, date-time forms without an offset are local
+    ;; time, which is UTC as well
+    (if (call $date_byte_is (local.get $string) (local.get $position) (i32.const 0x54)) ;; T
+      (then
+        (local.set $hours
+          (call $date_parse_digits (local.get $string) (i32.add (local.get $position) (i32.const 1)) (i32.const 2)))
+        (if (i32.eqz (call $date_byte_is (local.get $string) (i32.add (local.get $position) (i32.const 3)) (i32.const 0x3a))) ;; :
+          (then (return (f64.const nan)))
+        )
+        (local.set $minutes
+          (call $date_parse_digits (local.get $string) (i32.add (local.get $position) (i32.const 4)) (i32.const 2)))
+        (local.set $position (i32.add (local.get $position) (i32.const 6)))
+
+        (if (call $date_byte_is (local.get $string) (local.get $position) (i32.const 0x3a))
+          (then
+            (local.set $seconds
+              (call $date_parse_digits (local.get $string) (i32.add (local.get $position) (i32.const 1)) (i32.const 2)))
+            (local.set $position (i32.add (local.get $position) (i32.const 3)))
+
+            ;; only milliseconds are kept from the fraction
+            (if (call $date_byte_is (local.get $string) (local.get $position) (i32.const 0x2e)) ;; .
+              (then
+                (local.set $position (i32.add (local.get $position) (i32.const 1)))
+                (local.set $scale (i32.const 100))
+                (local.set $milliseconds (i32.const -1))
+                (block $done (loop $fraction
+                  (br_if $done (i32.ge_u (local.get $position) (local.get $length)))
+                  (local.set $digit
+                    (i32.sub (call $string_byte_at (local.get $string) (local.get $position)) (i32.const 0x30)))
+                  (br_if $done (i32.ge_u (local.get $digit) (i32.const 10)))
+                  (if (i32.lt_s (local.get $milliseconds) (i32.const 0))
+                    (then (local.set $milliseconds (i32.const 0)))
+                  )
+                  (local.set $milliseconds
+                    (i32.add (local.get $milliseconds) (i32.mul (local.get $digit) (local.get $scale))))
+                  (local.set $scale (i32.div_u (local.get $scale) (i32.const 10)))
+                  (local.set $position (i32.add (local.get $position) (i32.const 1)))
+                  (br $fraction)
+                ))
+              )
+            )
+          )
+        )
+
+        (if (i32.or
+              (i32.or
+                (i32.or (i32.lt_s (local.get $hours) (i32.const 0)) (i32.gt_s (local.get $hours) (i32.const 24)))
+                (i32.or (i32.lt_s (local.get $minutes) (i32.const 0)) (i32.gt_s (local.get $minutes) (i32.const 59))))
+              (i32.or
+                (i32.or (i32.lt_s (local.get $seconds) (i32.const 0)) (i32.gt_s (local.get $seconds) (i32.const 59)))
+                (i32.lt_s (local.get $milliseconds) (i32.const 0))))
+          (then (return (f64.const nan)))
+        )
+        ;; 24:00 is the end of the day, but nothing after it
+        (if (i32.and
+              (i32.eq (local.get $hours) (i32.const 24))
+              (i32.ne
+                (i32.or (local.get $minutes) (i32.or (local.get $seconds) (local.get $milliseconds)))
+                (i32.const 0)))
+          (then (return (f64.const nan)))
+        )
+
+        (if (call $date_byte_is (local.get $string) (local.get $position) (i32.const 0x5a)) ;; Z
+          (then (local.set $position (i32.add (local.get $position) (i32.const 1))))
+          (else
+            (if (i32.or
+                  (call $date_byte_is (local.get $string) (local.get $position) (i32.const 0x2b))
+                  (call $date_byte_is (local.get $string) (local.get $position) (i32.const 0x2d)))
+              (then
+                (local.set $offset_sign
+                  (if (result i32) (call $date_byte_is (local.get $string) (local.get $position) (i32.const 0x2d))
+                    (then (i32.const -1))
+                    (else (i32.const 1))
+                  ))
+                (local.set $offset_hours
+                  (call $date_parse_digits (local.get $string) (i32.add (local.get $position) (i32.const 1)) (i32.const 2)))
+                (if (i32.eqz
+                      (call $date_byte_is (local.get $string) (i32.add (local.get $position) (i32.const 3)) (i32.const 0x3a)))
+                  (then (return (f64.const nan)))
+                )
+                (local.set $offset_minutes
+                  (call $date_parse_digits (local.get $string) (i32.add (local.get $position) (i32.const 4)) (i32.const 2)))
+                (if (i32.or
+                      (i32.or (i32.lt_s (local.get $offset_hours) (i32.const 0)) (i32.gt_s (local.get $offset_hours) (i32.const 23)))
+                      (i32.or (i32.lt_s (local.get $offset_minutes) (i32.const 0)) (i32.gt_s (local.get $offset_minutes) (i32.const 59))))
+                  (then (return (f64.const nan)))
+                )
+                (local.set $offset
+                  (i32.mul
+                    (local.get $offset_sign)
+                    (i32.add
+                      (i32.mul (local.get $offset_hours) (i32.const 3600000))
+                      (i32.mul (local.get $offset_minutes) (i32.const 60000)))))
+                (local.set $position (i32.add (local.get $position) (i32.const 6)))
+              )
+            )
+          )
+        )
+      )
+    )
+
+    (if (i32.ne (local.get $position) (local.get $length))
+      (then (return (f64.const nan)))
+    )
+
+    (call $time_clip
+      (f64.sub
+        (call $make_date
+          (call $make_day
+            (f64.convert_i32_s (i32.mul (local.get $year) (local.get $sign)))
+            (f64.convert_i32_s (i32.sub (local.get $month) (i32.const 1)))
+            (f64.convert_i32_s (local.get $day)))
+          (call $make_time
+            (f64.convert_i32_s (local.get $hours))
+            (f64.convert_i32_s (local.get $minutes))
+            (f64.convert_i32_s (local.get $seconds))
+            (f64.convert_i32_s (local.get $milliseconds))))
+        (f64.convert_i32_s (local.get $offset))))
+  )
+
+  ;; appends a non-negative number padded with zeros to $width digits
+  (func $builder_append_padded (param $builder (ref $StringBuilder)) (param $value i32) (param $width i32)
+    (local $digits i32)
+    (local $divisor i32)
+
+    (local.set $digits (i32.const 1))
+    (local.set $divisor (i32.const 1))
+    (block $done (loop $count
+      (br_if $done
+        (i32.and
+          (i32.ge_u (local.get $digits) (local.get $width))
+          (i32.lt_u (i32.div_u (local.get $value) (local.get $divisor)) (i32.const 10))))
+      (local.set $digits (i32.add (local.get $digits) (i32.const 1)))
+      (local.set $divisor (i32.mul (local.get $divisor) (i32.const 10)))
+      (br $count)
+    ))
+    (block $done (loop $digits
+      (br_if $done (i32.eqz (local.get $divisor)))
+      (call $builder_append_byte
+        (local.get $builder)
+        (i32.add
+          (i32.const 0x30)
+          (i32.rem_u (i32.div_u (local.get $value) (local.get $divisor)) (i32.const 10))))
+      (local.set $divisor (i32.div_u (local.get $divisor) (i32.const 10)))
+      (br $digits)
+    ))
+  )
+
+  (func $date_append_field (param $builder (ref $StringBuilder)) (param $time f64) (param $field i32) (param $width i32)
+    (call $builder_append_padded
+      (local.get $builder)
+      (i32.trunc_f64_s (call $date_field (local.get $time) (local.get $field)))
+      (local.get $width))
+  )
+
+  ;; years before 0 get a minus sign, like -000001 or -0001
+  (func $date_append_year (param $builder (ref $StringBuilder)) (param $time f64) (param $width i32)
+    (local $year i32)
+    (local.set $year (i32.trunc_f64_s (call $date_field (local.get $time) (i32.const {{ field_year }}))))
+    (if (i32.lt_s (local.get $year) (i32.const 0))
+      (then
+        (call $builder_append_byte (local.get $builder) (i32.const 0x2d))
+        (local.set $year (i32.sub (i32.const 0) (local.get $year)))
+      )
+    )
+    (call $builder_append_padded (local.get $builder) (local.get $year) (local.get $width))
+  )
+
+  (func $date_append_name (param $builder (ref $StringBuilder)) (param $names anyref) (param $index i32)
+    (call $builder_append_string
+      (local.get $builder)
+      (call $substring
+        (local.get $names)
+        (i32.mul (local.get $index) (i32.const 3))
+        (i32.add (i32.mul (local.get $index) (i32.const 3)) (i32.const 3))))
+  )
+
+  (func $date_append_week_day (param $builder (ref $StringBuilder)) (param $time f64)
+    (call $date_append_name
+      (local.get $builder)
+      (call $new_static_string (i32.const {{ data(str="SunMonTueWedThuFriSat") }}) (i32.const {{ data_length() }}))
+      (i32.trunc_f64_s (call $date_field (local.get $time) (i32.const {{ field_day }}))))
+  )
+
+  (func $date_append_month (param $builder (ref $StringBuilder)) (param $time f64)
+    (call $date_append_name
+      (local.get $builder)
+      (call $new_static_string (i32.const {{ data(str="JanFebMarAprMayJunJulAugSepOctNovDec") }}) (i32.const {{ data_length() }}))
+      (i32.trunc_f64_s (call $date_field (local.get $time) (i32.const {{ field_month }}))))
+  )
+
+  ;; HH:mm:ss
+  (func $date_append_time (param $builder (ref $StringBuilder)) (param $time f64)
+    (call $date_append_field (local.get $builder) (local.get $time) (i32.const {{ field_hours }}) (i32.const 2))
+    (call $builder_append_byte (local.get $builder) (i32.const 0x3a))
+    (call $date_append_field (local.get $builder) (local.get $time) (i32.const {{ field_minutes }}) (i32.const 2))
+    (call $builder_append_byte (local.get $builder) (i32.const 0x3a))
+    (call $date_append_field (local.get $builder) (local.get $time) (i32.const {{ field_seconds }}) (i32.const 2))
+  )
+
+  ;; Tue Mar 05 2024
+  (func $date_append_date (param $builder (ref $StringBuilder)) (param $time f64)
+    (call $date_append_week_day (local.get $builder) (local.get $time))
+    (call $builder_append_byte (local.get $builder) (i32.const 0x20))
+    (call $date_append_month (local.get $builder) (local.get $time))
+    (call $builder_append_byte (local.get $builder) (i32.const 0x20))
+    (call $date_append_field (local.get $builder) (local.get $time) (i32.const {{ field_date }}) (i32.const 2))
+    (call $builder_append_byte (local.get $builder) (i32.const 0x20))
+    (call $date_append_year (local.get $builder) (local.get $time) (i32.const 4))
+  )
+
+  (func $date_append_time_zone (param $builder (ref $StringBuilder))
+    (call $builder_append_string
+      (local.get $builder)
+      (call $new_static_string (i32.const {{ data(str=" GMT+0000 (Coordinated Universal Time)") }}) (i32.const {{ data_length() }})))
+  )
+
+  (func $invalid_date_string (result anyref)
+    (call $new_static_string (i32.const {{ data(str="Invalid Date") }}) (i32.const {{ data_length() }}))
+  )
+
+  ;; the format of Date.prototype.toString, also returned by Date() called
+  ;; as a function
+  (func $date_to_string (param $time f64) (result anyref)
+    (local $builder (ref $StringBuilder))
+    (if (f64.ne (local.get $time) (local.get $time))
+      (then (return (call $invalid_date_string)))
+    )
+    (local.set $builder (call $new_string_builder))
+    (call $date_append_date (local.get $builder) (local.get $time))
+    (call $builder_append_byte (local.get $builder) (i32.const 0x20))
+    (call $date_append_time (local.get $builder) (local.get $time))
+    (call $date_append_time_zone (local.get $builder))
+    (call $builder_to_string (local.get $builder))
+  )
+
+  (func $Date-constructor
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (local $value anyref)
+
+    ;; called without `new` it ignores the arguments and returns a string
+    (if (i32.eqz (ref.test (ref $Object) (local.get $this)))
+      (then (return (call $date_to_string (call $current_time))))
+    )
+
+    (if (i32.eqz (array.len (local.get $arguments)))
+      (then (return (call $new_date (call $current_time))))
+    )
+
+    (if (i32.gt_u (array.len (local.get $arguments)) (i32.const 1))
+      (then (return (call $new_date (call $date_from_components (local.get $arguments)))))
+    )
+
+    (local.set $value (array.get $JSArgs (local.get $arguments) (i32.const 0)))
+    (if (ref.test (ref $Date) (local.get $value))
+      (then
+        (return (call $new_date (struct.get $Date $time (ref.cast (ref $Date) (local.get $value)))))
+      )
+    )
+    (local.set $value (call $to_primitive (local.get $value) (i32.const 0)))
+    (if (call $is_string (local.get $value))
+      (then (return (call $new_date (call $date_parse (local.get $value)))))
+    )
+    (call $new_date (call $time_clip (call $to_number (local.get $value))))
+  )
+
+  (func $Date-now
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+
+    (call $new_number (call $current_time))
+  )
+
+  (func $Date-parse
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+
+    (call $new_number
+      (call $date_parse (call $to_string (call $get_argument (local.get $arguments) (i32.const 0)))))
+  )
+
+  (func $Date-UTC
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+
+    (call $new_number (call $date_from_components (local.get $arguments)))
+  )
+
+  (func $Date-prototype-getTime
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+
+    (call $new_number (struct.get $Date $time (call $this_date (local.get $this))))
+  )
+
+  (func $Date-prototype-getTimezoneOffset
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (local $time f64)
+
+    (local.set $time (struct.get $Date $time (call $this_date (local.get $this))))
+    (if (f64.ne (local.get $time) (local.get $time))
+      (then (return (call $new_number (f64.const nan))))
+    )
+    (ref.i31 (i32.const 0))
+  )
+
+  (func $Date-prototype-setTime
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (local $date (ref $Date))
+    (local $time f64)
+
+    (local.set $date (call $this_date (local.get $this)))
+    (local.set $time (call $time_clip (call $number_argument (local.get $arguments) (i32.const 0))))
+    (struct.set $Date $time (local.get $date) (local.get $time))
+    (call $new_number (local.get $time))
+  )
+
+  {% set date_fields = ["FullYear", "Month", "Date", "Hours", "Minutes", "Seconds", "Milliseconds", "Day"] %}
+  ;; how many arguments each setter takes, eg. setUTCHours(hours, minutes,
+  ;; seconds, milliseconds)
+  {% set setter_arguments = [3, 2, 1, 4, 3, 2, 1] %}
+
+  {% for name in date_fields %}
+  (func $Date-prototype-getUTC{{ name }}
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (local $time f64)
+
+    (local.set $time (struct.get $Date $time (call $this_date (local.get $this))))
+    (if (f64.ne (local.get $time) (local.get $time))
+      (then (return (call $new_number (f64.const nan))))
+    )
+    (call $new_number (call $date_field (local.get $time) (i32.const {{ loop.index0 }})))
+  )
+  (elem declare func $Date-prototype-getUTC{{ name }})
+  {% endfor %}
+
+  ;; the setters replace some of the fields and put the date back together,
+  ;; arguments that aren't passed keep their current values
+  (func $date_set_fields (param $this anyref) (param $arguments (ref $JSArgs)) (param $first i32) (param $count i32) (result anyref)
+    (local $date (ref $Date))
+    (local $time f64)
+    (local $fields (ref $F64Array))
+    (local $i i32)
+
+    (local.set $date (call $this_date (local.get $this)))
+    (local.set $time (struct.get $Date $time (local.get $date)))
+    ;; setting the year of an invalid date starts from the epoch
+    (if (i32.and
+          (f64.ne (local.get $time) (local.get $time))
+          (i32.eq (local.get $first) (i32.const {{ field_year }})))
+      (then (local.set $time (f64.const 0)))
+    )
+
+    (local.set $fields (array.new $F64Array (f64.const nan) (i32.const 7)))
+    (if (f64.eq (local.get $time) (local.get $time))
+      (then
+        (block $done (loop $read
+          (br_if $done (i32.ge_u (local.get $i) (i32.const 7)))
+          (array.set $F64Array (local.get $fields) (local.get $i)
+            (call $date_field (local.get $time) (local.get $i)))
+          (local.set $i (i32.add (local.get $i) (i32.const 1)))
+          (br $read)
+        ))
+      )
+    )
+
+    ;; the first argument is always converted, even if it's missing
+    (local.set $i (i32.const 0))
+    (block $done (loop $write
+      (br_if $done (i32.ge_u (local.get $i) (local.get $count)))
+      (br_if $done
+        (i32.and
+          (i32.ne (local.get $i) (i32.const 0))
+          (i32.ge_u (local.get $i) (array.len (local.get $arguments)))))
+      (array.set $F64Array
+        (local.get $fields)
+        (i32.add (local.get $first) (local.get $i))
+        (call $number_argument (local.get $arguments) (local.get $i)))
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $write)
+    ))
+
+    (if (f64.ne (local.get $time) (local.get $time))
+      (then (return (call $new_number (f64.const nan))))
+    )
+
+    (local.set $time
+      (call $time_clip
+        (call $make_date
+          (call $make_day
+            (array.get $F64Array (local.get $fields) (i32.const {{ field_year }}))
+            (array.get $F64Array (local.get $fields) (i32.const {{ field_month }}))
+            (array.get $F64Array (local.get $fields) (i32.const {{ field_date }})))
+          (call $make_time
+            (array.get $F64Array (local.get $fields) (i32.const {{ field_hours }}))
+            (array.get $F64Array (local.get $fields) (i32.const {{ field_minutes }}))
+            (array.get $F64Array (local.get $fields) (i32.const {{ field_seconds }}))
+            (array.get $F64Array (local.get $fields) (i32.const {{ field_milliseconds }}))))))
+    (struct.set $Date $time (local.get $date) (local.get $time))
+    (call $new_number (local.get $time))
+  )
+
+  {% for count in setter_arguments %}
+  (func $Date-prototype-setUTC{{ date_fields[loop.index0] }}
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+
+    (call $date_set_fields
+      (local.get $this)
+      (local.get $arguments)
+      (i32.const {{ loop.index0 }})
+      (i32.const {{ count }}))
+  )
+  (elem declare func $Date-prototype-setUTC{{ date_fields[loop.index0] }})
+  {% endfor %}
+
+  (func $Date-prototype-toISOString
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (local $time f64)
+    (local $year f64)
+    (local $builder (ref $StringBuilder))
+
+    (local.set $time (struct.get $Date $time (call $this_date (local.get $this))))
+    (if (f64.ne (local.get $time) (local.get $time))
+      (then
+        (call $throw_range_error
+          (call $new_static_string (i32.const {{ data(str="Invalid time value") }}) (i32.const {{ data_length() }})))
+      )
+    )
+
+    (local.set $builder (call $new_string_builder))
+    ;; years outside of 0-9999 use the expanded format with a sign
+    (local.set $year (call $date_field (local.get $time) (i32.const {{ field_year }})))
+    (if (i32.or
+          (f64.lt (local.get $year) (f64.const 0))
+          (f64.gt (local.get $year) (f64.const 9999)))
+      (then
+        (if (f64.gt (local.get $year) (f64.const 0))
+          (then (call $builder_append_byte (local.get $builder) (i32.const 0x2b)))
+        )
+        (call $date_append_year (local.get $builder) (local.get $time) (i32.const 6))
+      )
+      (else
+        (call $date_append_year (local.get $builder) (local.get $time) (i32.const 4))
+      )
+    )
+    (call $builder_append_byte (local.get $builder) (i32.const 0x2d))
+    (call $builder_append_padded
+      (local.get $builder)
+      (i32.add (i32.trunc_f64_s (call $date_field (local.get $time) (i32.const {{ field_month }}))) (i32.const 1))
+      (i32.const 2))
+    (call $builder_append_byte (local.get $builder) (i32.const 0x2d))
+    (call $date_append_field (local.get $builder) (local.get $time) (i32.const {{ field_date }}) (i32.const 2))
+    (call $builder_append_byte (local.get $builder) (i32.const 0x54)) ;; T
+    (call $date_append_time (local.get $builder) (local.get $time))
+    (call $builder_append_byte (local.get $builder) (i32.const 0x2e))
+    (call $date_append_field (local.get $builder) (local.get $time) (i32.const {{ field_milliseconds }}) (i32.const 3))
+    (call $builder_append_byte (local.get $builder) (i32.const 0x5a)) ;; Z
+    (call $builder_to_string (local.get $builder))
+  )
+
+  (func $Date-prototype-toJSON
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (local $value anyref)
+
+    (local.set $value (call $to_primitive (local.get $this) (i32.const 1)))
+    (if (call $is_number (local.get $value))
+      (then
+        (if (i32.eqz (call $is_finite (call $number_value (local.get $value))))
+          (then (return (global.get $null)))
+        )
+      )
+    )
+    (call $call_function
+      (call $get_property (local.get $this) (i32.const {{ data(str="toISOString") }}))
+      (local.get $this)
+      (array.new_fixed $JSArgs 0))
+  )
+
+  (func $Date-prototype-toString
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+
+    (call $date_to_string (struct.get $Date $time (call $this_date (local.get $this))))
+  )
+
+  (func $Date-prototype-toDateString
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (local $time f64)
+    (local $builder (ref $StringBuilder))
+
+    (local.set $time (struct.get $Date $time (call $this_date (local.get $this))))
+    (if (f64.ne (local.get $time) (local.get $time))
+      (then (return (call $invalid_date_string)))
+    )
+    (local.set $builder (call $new_string_builder))
+    (call $date_append_date (local.get $builder) (local.get $time))
+    (call $builder_to_string (local.get $builder))
+  )
+
+  (func $Date-prototype-toTimeString
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (local $time f64)
+    (local $builder (ref $StringBuilder))
+
+    (local.set $time (struct.get $Date $time (call $this_date (local.get $this))))
+    (if (f64.ne (local.get $time) (local.get $time))
+      (then (return (call $invalid_date_string)))
+    )
+    (local.set $builder (call $new_string_builder))
+    (call $date_append_time (local.get $builder) (local.get $time))
+    (call $date_append_time_zone (local.get $builder))
+    (call $builder_to_string (local.get $builder))
+  )
+
+  ;; Tue, 05 Mar 2024 14:03:01 GMT
+  (func $Date-prototype-toUTCString
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (local $time f64)
+    (local $builder (ref $StringBuilder))
+
+    (local.set $time (struct.get $Date $time (call $this_date (local.get $this))))
+    (if (f64.ne (local.get $time) (local.get $time))
+      (then (return (call $invalid_date_string)))
+    )
+    (local.set $builder (call $new_string_builder))
+    (call $date_append_week_day (local.get $builder) (local.get $time))
+    (call $builder_append_byte (local.get $builder) (i32.const 0x2c))
+    (call $builder_append_byte (local.get $builder) (i32.const 0x20))
+    (call $date_append_field (local.get $builder) (local.get $time) (i32.const {{ field_date }}) (i32.const 2))
+    (call $builder_append_byte (local.get $builder) (i32.const 0x20))
+    (call $date_append_month (local.get $builder) (local.get $time))
+    (call $builder_append_byte (local.get $builder) (i32.const 0x20))
+    (call $date_append_year (local.get $builder) (local.get $time) (i32.const 4))
+    (call $builder_append_byte (local.get $builder) (i32.const 0x20))
+    (call $date_append_time (local.get $builder) (local.get $time))
+    (call $builder_append_string
+      (local.get $builder)
+      (call $new_static_string (i32.const {{ data(str=" GMT") }}) (i32.const {{ data_length() }})))
+    (call $builder_to_string (local.get $builder))
+  )
+
+  ;; dates convert to strings unless a number is asked for, so `date + 1`
+  ;; concatenates while `date - 1` subtracts
+  (func $Date-prototype-toPrimitive
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (local $hint anyref)
+    (local $first i32)
+    (local $second i32)
+    (local $result anyref)
+
+    (if (i32.eqz (call $is_object (local.get $this)))
+      (then
+        (call $throw_type_error
+          (call $new_static_string (i32.const {{ data(str="Date.prototype[Symbol.toPrimitive] called on non-object") }}) (i32.const {{ data_length() }})))
+      )
+    )
+
+    (local.set $hint (call $get_argument (local.get $arguments) (i32.const 0)))
+    (local.set $first (i32.const {{ data(str="toString") }}))
+    (local.set $second (i32.const {{ data(str="valueOf") }}))
+    (block $valid
+      (br_if $valid
+        (call $string_equals
+          (local.get $hint)
+          (call $new_static_string (i32.const {{ data(str="string") }}) (i32.const {{ data_length() }}))))
+      (br_if $valid
+        (call $string_equals
+          (local.get $hint)
+          (call $new_static_string (i32.const {{ data(str="default") }}) (i32.const {{ data_length() }}))))
+      (if (call $string_equals
+            (local.get $hint)
+            (call $new_static_string (i32.const {{ data(str="number") }}) (i32.const {{ data_length() }})))
+        (then
+          (local.set $first (i32.const {{ data(str="valueOf") }}))
+          (local.set $second (i32.const {{ data(str="toString") }}))
+          (br $valid)
+        )
+      )
+      (call $throw_type_error
+        (call $new_static_string (i32.const {{ data(str="Invalid hint") }}) (i32.const {{ data_length() }})))
+    )
+
+    (local.set $result (call $call_primitive_method (local.get $this) (local.get $first)))
+    (if (i32.eqz (call $is_no_value_found (local.get $result)))
+      (then (return (local.get $result)))
+    )
+    (local.set $result (call $call_primitive_method (local.get $this) (local.get $second)))
+    (if (i32.eqz (call $is_no_value_found (local.get $result)))
+      (then (return (local.get $result)))
+    )
+    (call $throw_type_error
+      (call $new_static_string (i32.const {{ data(str="Cannot convert object to primitive value") }}) (i32.const {{ data_length() }})))
+    (unreachable)
+  )
+
+  ;; milliseconds since the program started, from the monotonic clock
+  (func $performance-now
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+
+    (call $new_number
+      (f64.div
+        (f64.convert_i64_u (i64.sub (call $monotonic-now) (global.get $time-origin)))
+        (f64.const 1000000)))
+  )
+
+  (elem declare func $Date-constructor)
+  (elem declare func $Date-now)
+  (elem declare func $Date-parse)
+  (elem declare func $Date-UTC)
+  (elem declare func $Date-prototype-getTime)
+  (elem declare func $Date-prototype-getTimezoneOffset)
+  (elem declare func $Date-prototype-setTime)
+  (elem declare func $Date-prototype-toISOString)
+  (elem declare func $Date-prototype-toJSON)
+  (elem declare func $Date-prototype-toString)
+  (elem declare func $Date-prototype-toDateString)
+  (elem declare func $Date-prototype-toTimeString)
+  (elem declare func $Date-prototype-toUTCString)
+  (elem declare func $Date-prototype-toPrimitive)
+  (elem declare func $performance-now)
+
+  (func $install-date-globals (param $scope (ref $Scope))
+    (local $prototype (ref $Object))
+    (local $constructor anyref)
+    (local $performance (ref $Object))
+    (local $getter (ref $Function))
+
+    (global.set $time-origin (call $monotonic-now))
+
+    (local.set $prototype (call $new_object))
+    (call $add_method (local.get $prototype) (i32.const {{ data(str="getTime") }}) (ref.func $Date-prototype-getTime))
+    (call $add_method (local.get $prototype) (i32.const {{ data(str="valueOf") }}) (ref.func $Date-prototype-getTime))
+    (call $add_method (local.get $prototype) (i32.const {{ data(str="getTimezoneOffset") }}) (ref.func $Date-prototype-getTimezoneOffset))
+    (call $add_method (local.get $prototype) (i32.const {{ data(str="setTime") }}) (ref.func $Date-prototype-setTime))
+    ;; local time is UTC, so the local getters and setters are the same functions
+    {% for name in date_fields %}
+    (local.set $getter
+      (call $new_function (local.get $scope) (ref.func $Date-prototype-getUTC{{ name }}) (ref.null any)))
+    (call $set_property (local.get $prototype) (i32.const {{ data(str="getUTC" ~ name) }}) (local.get $getter))
+    (call $set_property (local.get $prototype) (i32.const {{ data(str="get" ~ name) }}) (local.get $getter))
+    {% endfor %}
+    {% for count in setter_arguments %}
+    (local.set $getter
+      (call $new_function
+        (local.get $scope) (ref.func $Date-prototype-setUTC{{ date_fields[loop.index0] }}) (ref.null any)))
+    (call $set_property (local.get $prototype) (i32.const {{ data(str="setUTC" ~ date_fields[loop.index0]) }}) (local.get $getter))
+    (call $set_property (local.get $prototype) (i32.const {{ data(str="set" ~ date_fields[loop.index0]) }}) (local.get $getter))
+    {% endfor %}
+    (call $add_method (local.get $prototype) (i32.const {{ data(str="toISOString") }}) (ref.func $Date-prototype-toISOString))
+    (call $add_method (local.get $prototype) (i32.const {{ data(str="toJSON") }}) (ref.func $Date-prototype-toJSON))
+    (call $add_method (local.get $prototype) (i32.const {{ data(str="toString") }}) (ref.func $Date-prototype-toString))
+    (call $add_method (local.get $prototype) (i32.const {{ data(str="toDateString") }}) (ref.func $Date-prototype-toDateString))
+    (call $add_method (local.get $prototype) (i32.const {{ data(str="toTimeString") }}) (ref.func $Date-prototype-toTimeString))
+    (call $add_method (local.get $prototype) (i32.const {{ data(str="toUTCString") }}) (ref.func $Date-prototype-toUTCString))
+    (call $add_method
+      (local.get $prototype)
+      (call $well_known_symbol_key (global.get $symbol-to-primitive))
+      (ref.func $Date-prototype-toPrimitive))
+    (global.set $date-prototype (local.get $prototype))
+    (call $install-collection-constructor
+      (local.get $scope) (i32.const {{ data(str="Date") }}) (ref.func $Date-constructor) (local.get $prototype))
+
+    (local.set $constructor (call $get_variable (local.get $scope) (i32.const {{ data(str="Date") }})))
+    (call $add_method (local.get $constructor) (i32.const {{ data(str="now") }}) (ref.func $Date-now))
+    (call $add_method (local.get $constructor) (i32.const {{ data(str="parse") }}) (ref.func $Date-parse))
+    (call $add_method (local.get $constructor) (i32.const {{ data(str="UTC") }}) (ref.func $Date-UTC))
+
+    (local.set $performance (call $new_object))
+    (call $add_method (local.get $performance) (i32.const {{ data(str="now") }}) (ref.func $performance-now))
+{% if date %}
+    (call $set_property
+      (local.get $performance)
+      (i32.const {{ data(str="timeOrigin") }})
+      (call $new_number (call $current_time)))
+{% endif %}
+    (call $set_variable (local.get $scope) (i32.const {{ data(str="performance") }}) (local.get $performance))
+  )
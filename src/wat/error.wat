@@ -0,0 +1,224 @@
+  ;; Error and its native subclasses. Instances are plain objects with an own
+  ;; `message`, the name comes from the prototype. There's no `stack` and, as
+  ;; property attributes are not supported yet, `message` is enumerable.
+
+  (global $error-prototype (mut (ref null $Object)) (ref.null $Object))
+  (global $type-error-prototype (mut (ref null $Object)) (ref.null $Object))
+  (global $range-error-prototype (mut (ref null $Object)) (ref.null $Object))
+  (global $syntax-error-prototype (mut (ref null $Object)) (ref.null $Object))
+  (global $reference-error-prototype (mut (ref null $Object)) (ref.null $Object))
+
+  (func $new_error (param $prototype (ref null $Object)) (param $message anyref) (result (ref $Object))
+    (local $error (ref $Object))
+
+    (local.set $error (call $new_object))
+    (struct.set $Object $prototype (local.get $error) (local.get $prototype))
+    (if (i32.eqz (ref.is_null (local.get $message)))
+      (then
+        (call $set_property (local.get $error) (i32.const {{ data(str="message") }}) (call $to_string (local.get $message)))
+      )
+    )
+    (local.get $error)
+  )
+
+  (func $throw_error (param $message anyref)
+    (throw $JSException (call $new_error (global.get $error-prototype) (local.get $message)))
+  )
+
+  (func $throw_type_error (param $message anyref)
+    (throw $JSException (call $new_error (global.get $type-error-prototype) (local.get $message)))
+  )
+
+  (func $throw_range_error (param $message anyref)
+    (throw $JSException (call $new_error (global.get $range-error-prototype) (local.get $message)))
+  )
+
+  (func $throw_syntax_error (param $message anyref)
+    (throw $JSException (call $new_error (global.get $syntax-error-prototype) (local.get $message)))
+  )
+
+  (func $throw_reference_error (param $message anyref)
+    (throw $JSException (call $new_error (global.get $reference-error-prototype) (local.get $message)))
+  )
+
+  ;; whether Error.prototype is on the prototype chain of a value
+  (func $is_error (param $value anyref) (result i32)
+    (local $current anyref)
+
+    (if (i32.eqz (call $is_object (local.get $value)))
+      (then (return (i32.const 0)))
+    )
+    (local.set $current (call $get_prototype (local.get $value)))
+    (block $done (loop $walk
+      (br_if $done (ref.is_null (local.get $current)))
+      (if (ref.eq (ref.cast eqref (local.get $current)) (global.get $error-prototype))
+        (then (return (i32.const 1)))
+      )
+      (local.set $current (call $get_prototype (local.get $current)))
+      (br $walk)
+    ))
+    (i32.const 0)
+  )
+
+  ;; new Error(message, options), `options.cause` is copied to the error
+  (func $construct_error (param $prototype (ref null $Object)) (param $arguments (ref $JSArgs)) (result (ref $Object))
+    (local $error (ref $Object))
+    (local $options anyref)
+
+    (local.set $error
+      (call $new_error (local.get $prototype) (call $get_argument (local.get $arguments) (i32.const 0))))
+    (local.set $options (call $get_argument (local.get $arguments) (i32.const 1)))
+    (if (call $is_object (local.get $options))
+      (then
+        (if (call $has_property (local.get $options) (i32.const {{ data(str="cause") }}))
+          (then
+            (call $set_property (local.get $error) (i32.const {{ data(str="cause") }})
+              (call $get_property (local.get $options) (i32.const {{ data(str="cause") }})))
+          )
+        )
+      )
+    )
+    (local.get $error)
+  )
+
+  (func $Error-constructor
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (call $construct_error (global.get $error-prototype) (local.get $arguments))
+  )
+
+  (func $TypeError-constructor
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (call $construct_error (global.get $type-error-prototype) (local.get $arguments))
+  )
+
+  (func $RangeError-constructor
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (call $construct_error (global.get $range-error-prototype) (local.get $arguments))
+  )
+
+  (func $SyntaxError-constructor
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (call $construct_error (global.get $syntax-error-prototype) (local.get $arguments))
+  )
+
+  (func $ReferenceError-constructor
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (call $construct_error (global.get $reference-error-prototype) (local.get $arguments))
+  )
+
+  ;; "name: message", or just one of them if the other one is empty
+  (func $error_to_string (param $error anyref) (result anyref)
+    (local $name anyref)
+    (local $message anyref)
+
+    (local.set $name (call $get_property (local.get $error) (i32.const {{ data(str="name") }})))
+    (local.set $name
+      (if (result anyref) (ref.is_null (local.get $name))
+        (then (call $new_static_string (i32.const {{ data(str="Error") }}) (i32.const {{ data_length() }})))
+        (else (call $to_string (local.get $name)))))
+    (local.set $message (call $get_property (local.get $error) (i32.const {{ data(str="message") }})))
+    (local.set $message
+      (if (result anyref) (ref.is_null (local.get $message))
+        (then (call $new_static_string (i32.const {{ data(str="") }}) (i32.const {{ data_length() }})))
+        (else (call $to_string (local.get $message)))))
+    (if (i32.eqz (call $string_length (local.get $name)))
+      (then (return (local.get $message)))
+    )
+    (if (i32.eqz (call $string_length (local.get $message)))
+      (then (return (local.get $name)))
+    )
+    (call $concat_strings
+      (call $concat_strings
+        (local.get $name)
+        (call $new_static_string (i32.const {{ data(str=": ") }}) (i32.const {{ data_length() }})))
+      (local.get $message))
+  )
+
+  (func $Error-prototype-toString
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (if (i32.eqz (call $is_object (local.get $this)))
+      (then
+        (call $throw_type_error
+          (call $new_static_string
+            (i32.const {{ data(str="Error.prototype.toString called on non-object") }})
+            (i32.const {{ data_length() }})))
+      )
+    )
+    (call $error_to_string (local.get $this))
+  )
+
+  (elem declare func $Error-constructor)
+  (elem declare func $TypeError-constructor)
+  (elem declare func $RangeError-constructor)
+  (elem declare func $SyntaxError-constructor)
+  (elem declare func $ReferenceError-constructor)
+  (elem declare func $Error-prototype-toString)
+
+  ;; creates the constructor of an error type as a global and returns its
+  ;; prototype, which inherits from $parent
+  (func $install_error_type
+    (param $scope (ref $Scope))
+    (param $name i32)
+    (param $parent anyref)
+    (param $constructor-func (ref $JSFunc))
+    (result (ref $Object))
+    (local $prototype (ref $Object))
+    (local $constructor (ref $Function))
+
+    (local.set $prototype (call $new_object))
+    (struct.set $Object $prototype (local.get $prototype) (local.get $parent))
+    (call $set_property (local.get $prototype) (i32.const {{ data(str="name") }})
+      (call $key_to_string (local.get $name)))
+    (call $set_property (local.get $prototype) (i32.const {{ data(str="message") }})
+      (call $new_static_string (i32.const {{ data(str="") }}) (i32.const {{ data_length() }})))
+    (local.set $constructor
+      (call $new_function (local.get $scope) (local.get $constructor-func) (ref.null any)))
+    (call $set_property (local.get $constructor) (i32.const {{ data(str="prototype") }}) (local.get $prototype))
+    (call $set_property (local.get $prototype) (i32.const {{ data(str="constructor") }}) (local.get $constructor))
+    (call $set_variable (local.get $scope) (local.get $name) (local.get $constructor))
+    (local.get $prototype)
+  )
+
+  (func $install-error-globals (param $scope (ref $Scope))
+    (global.set $error-prototype
+      (call $install_error_type (local.get $scope) (i32.const {{ data(str="Error") }})
+        (global.get $object-prototype) (ref.func $Error-constructor)))
+    (call $add_method (global.get $error-prototype) (i32.const {{ data(str="toString") }})
+      (ref.func $Error-prototype-toString))
+    (global.set $type-error-prototype
+      (call $install_error_type (local.get $scope) (i32.const {{ data(str="TypeError") }})
+        (global.get $error-prototype) (ref.func $TypeError-constructor)))
+    (global.set $range-error-prototype
+      (call $install_error_type (local.get $scope) (i32.const {{ data(str="RangeError") }})
+        (global.get $error-prototype) (ref.func $RangeError-constructor)))
+    (global.set $syntax-error-prototype
+      (call $install_error_type (local.get $scope) (i32.const {{ data(str="SyntaxError") }})
+        (global.get $error-prototype) (ref.func $SyntaxError-constructor)))
+    (global.set $reference-error-prototype
+      (call $install_error_type (local.get $scope) (i32.const {{ data(str="ReferenceError") }})
+        (global.get $error-prototype) (ref.func $ReferenceError-constructor)))
+  )
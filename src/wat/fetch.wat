@@ -0,0 +1,1112 @@
+  ;; fetch() on top of wasi:http/outgoing-handler, along with Headers and
+  ;; Response. The request is sent right away and the returned promise settles
+  ;; once the pollable of the future-incoming-response is ready. The body is
+  ;; only read when one of the body methods is called, waiting on the pollable
+  ;; of the input-stream whenever there's no data available yet. Redirects
+  ;; aren't followed, wasi:http doesn't do it on its own.
+  ;;
+  ;; Values returned through pointers follow the canonical ABI, the offsets
+  ;; below come from the layout of the result types. It's only included when
+  ;; the script mentions `fetch`, `Headers` or `Response`, so other modules
+  ;; don't import wasi:http.
+
+  (type $Headers (sub final $Object (struct
+    (field $properties (mut (ref $HashMap)))
+    (field $prototype (mut anyref))
+    (field $hash (mut i32))
+    ;; lowercased names and their values, in the order they were added
+    (field $names (ref $ValueList))
+    (field $values (ref $ValueList))
+  )))
+
+  (type $Response (sub final $Object (struct
+    (field $properties (mut (ref $HashMap)))
+    (field $prototype (mut anyref))
+    (field $hash (mut i32))
+    ;; resource handles, -1 once they're dropped
+    (field $incoming-response (mut i32))
+    (field $incoming-body (mut i32))
+    (field $stream (mut i32))
+    (field $body-used (mut i32))
+  )))
+
+  ;; a request waiting for the response
+  (type $PendingFetch (struct
+    (field $future i32)
+    (field $promise (ref $Promise))
+    (field $url anyref)
+  ))
+
+  ;; a body being read by text(), json() or arrayBuffer()
+  (type $BodyReader (struct
+    (field $response (ref $Response))
+    (field $promise (ref $Promise))
+    (field $kind i32)
+    (field $bytes (ref $StringBuilder))
+  ))
+
+  (global $body-text i32 (i32.const 0))
+  (global $body-json i32 (i32.const 1))
+  (global $body-array-buffer i32 (i32.const 2))
+
+  ;; outgoing-body.write only takes this much at a time
+  (global $http-write-chunk i32 (i32.const 4096))
+  (global $http-read-chunk i64 (i64.const 65536))
+
+  (global $headers-prototype (mut (ref null $Object)) (ref.null $Object))
+  (global $response-prototype (mut (ref null $Object)) (ref.null $Object))
+
+  ;; ASCII only, which is all header names and methods need
+  (func $ascii_change_case (param $str anyref) (param $upper i32) (result (ref $String))
+    (local $builder (ref $StringBuilder))
+    (local $length i32)
+    (local $byte i32)
+    (local $i i32)
+
+    (local.set $builder (call $new_string_builder))
+    (local.set $length (call $string_length (local.get $str)))
+    (block $break (loop $bytes
+      (br_if $break (i32.ge_u (local.get $i) (local.get $length)))
+      (local.set $byte (call $string_byte_at (local.get $str) (local.get $i)))
+      (if (local.get $upper)
+        (then
+          (if (i32.and
+                (i32.ge_u (local.get $byte) (i32.const 0x61))
+                (i32.le_u (local.get $byte) (i32.const 0x7a)))
+            (then (local.set $byte (i32.sub (local.get $byte) (i32.const 0x20))))
+          )
+        )
+        (else
+          (if (i32.and
+                (i32.ge_u (local.get $byte) (i32.const 0x41))
+                (i32.le_u (local.get $byte) (i32.const 0x5a)))
+            (then (local.set $byte (i32.add (local.get $byte) (i32.const 0x20))))
+          )
+        )
+      )
+      (call $builder_append_byte (local.get $builder) (local.get $byte))
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $bytes)
+    ))
+    (call $builder_to_string (local.get $builder))
+  )
+
+  ;; -- Headers --
+
+  (func $new_headers (result (ref $Headers))
+    (struct.new $Headers
+      (call $new_hashmap)
+      (global.get $headers-prototype)
+      (i32.const 0)
+      (call $new_value_list)
+      (call $new_value_list))
+  )
+
+  (func $headers_find (param $headers (ref $Headers)) (param $name anyref) (result i32)
+    (local $names (ref $ValueList))
+    (local $i i32)
+
+    (local.set $names (struct.get $Headers $names (local.get $headers)))
+    (block $break (loop $find
+      (br_if $break (i32.ge_u (local.get $i) (struct.get $ValueList $size (local.get $names))))
+      (if (call $string_equals (call $value_list_get (local.get $names) (local.get $i)) (local.get $name))
+        (then (return (local.get $i)))
+      )
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $find)
+    ))
+    (i32.const -1)
+  )
+
+  ;; values of a header that's already there are combined, like in Node
+  (func $headers_append (param $headers (ref $Headers)) (param $name anyref) (param $value anyref)
+    (local $index i32)
+
+    (local.set $name (call $ascii_change_case (call $to_string (local.get $name)) (i32.const 0)))
+    (local.set $value (call $to_string (local.get $value)))
+    (local.set $index (call $headers_find (local.get $headers) (local.get $name)))
+    (if (i32.eq (local.get $index) (i32.const -1))
+      (then
+        (call $value_list_push (struct.get $Headers $names (local.get $headers)) (local.get $name))
+        (call $value_list_push (struct.get $Headers $values (local.get $headers)) (local.get $value))
+        (return)
+      )
+    )
+    (array.set $AnyrefArray
+      (struct.get $ValueList $data (struct.get $Headers $values (local.get $headers)))
+      (local.get $index)
+      (call $concat_strings
+        (call $concat_strings
+          (call $value_list_get (struct.get $Headers $values (local.get $headers)) (local.get $index))
+          (call $new_static_string (i32.const {{ data(str=", ") }}) (i32.const {{ data_length() }})))
+        (local.get $value)))
+  )
+
+  (func $headers_delete (param $headers (ref $Headers)) (param $name anyref)
+    (local $index i32)
+    (local $names (ref $ValueList))
+    (local $values (ref $ValueList))
+    (local $size i32)
+
+    (local.set $index
+      (call $headers_find
+        (local.get $headers)
+        (call $ascii_change_case (call $to_string (local.get $name)) (i32.const 0))))
+    (if (i32.eq (local.get $index) (i32.const -1))
+      (then (return))
+    )
+    (local.set $names (struct.get $Headers $names (local.get $headers)))
+    (local.set $values (struct.get $Headers $values (local.get $headers)))
+    (local.set $size (i32.sub (struct.get $ValueList $size (local.get $names)) (i32.const 1)))
+    (array.copy $AnyrefArray $AnyrefArray
+      (struct.get $ValueList $data (local.get $names))
+      (local.get $index)
+      (struct.get $ValueList $data (local.get $names))
+      (i32.add (local.get $index) (i32.const 1))
+      (i32.sub (local.get $size) (local.get $index)))
+    (array.copy $AnyrefArray $AnyrefArray
+      (struct.get $ValueList $data (local.get $values))
+      (local.get $index)
+      (struct.get $ValueList $data (local.get $values))
+      (i32.add (local.get $index) (i32.const 1))
+      (i32.sub (local.get $size) (local.get $index)))
+    (struct.set $ValueList $size (local.get $names) (local.get $size))
+    (struct.set $ValueList $size (local.get $values) (local.get $size))
+  )
+
+  ;; headers can be given as another Headers object, an array of [name, value]
+  ;; pairs or an object
+  (func $headers_fill (param $headers (ref $Headers)) (param $init anyref)
+    (local $source (ref $Headers))
+    (local $pairs (ref $Array))
+    (local $pair anyref)
+    (local $i i32)
+
+    (if (ref.is_null (local.get $init))
+      (then (return))
+    )
+    (if (ref.test (ref $Headers) (local.get $init))
+      (then
+        (local.set $source (ref.cast (ref $Headers) (local.get $init)))
+        (block $break (loop $copy
+          (br_if $break
+            (i32.ge_u (local.get $i) (struct.get $ValueList $size (struct.get $Headers $names (local.get $source)))))
+          (call $headers_append
+            (local.get $headers)
+            (call $value_list_get (struct.get $Headers $names (local.get $source)) (local.get $i))
+            (call $value_list_get (struct.get $Headers $values (local.get $source)) (local.get $i)))
+          (local.set $i (i32.add (local.get $i) (i32.const 1)))
+          (br $copy)
+        ))
+        (return)
+      )
+    )
+
+    (local.set $pairs
+      (if (result (ref $Array)) (ref.test (ref $Array) (local.get $init))
+        (then (ref.cast (ref $Array) (local.get $init)))
+        (else (call $own_property_entries (local.get $init) (i32.const 2)))))
+    (block $break (loop $pairs
+      (br_if $break (i32.ge_u (local.get $i) (array.len (struct.get $Array $array (local.get $pairs)))))
+      (local.set $pair (array.get $AnyrefArray (struct.get $Array $array (local.get $pairs)) (local.get $i)))
+      (if (i32.eqz (ref.test (ref $Array) (local.get $pair)))
+        (then
+          (call $throw_type_error
+            (call $new_static_string (i32.const {{ data(str="Headers init must be an object or an array of [name, value] pairs") }}) (i32.const {{ data_length() }})))
+        )
+      )
+      (call $headers_append
+        (local.get $headers)
+        (array.get $AnyrefArray (struct.get $Array $array (ref.cast (ref $Array) (local.get $pair))) (i32.const 0))
+        (array.get $AnyrefArray (struct.get $Array $array (ref.cast (ref $Array) (local.get $pair))) (i32.const 1)))
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $pairs)
+    ))
+  )
+
+  (func $this_headers (param $this anyref) (result (ref $Headers))
+    (if (i32.eqz (ref.test (ref $Headers) (local.get $this)))
+      (then
+        (call $throw_type_error
+          (call $new_static_string (i32.const {{ data(str="Illegal invocation") }}) (i32.const {{ data_length() }})))
+      )
+    )
+    (ref.cast (ref $Headers) (local.get $this))
+  )
+
+  (func $Headers-constructor
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (local $headers (ref $Headers))
+
+    (local.set $headers (call $new_headers))
+    (call $headers_fill (local.get $headers) (call $get_argument (local.get $arguments) (i32.const 0)))
+    (local.get $headers)
+  )
+
+  (func $Headers-get
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (local $headers (ref $Headers))
+    (local $index i32)
+
+    (local.set $headers (call $this_headers (local.get $this)))
+    (local.set $index
+      (call $headers_find
+        (local.get $headers)
+        (call $ascii_change_case
+          (call $to_string (call $get_argument (local.get $arguments) (i32.const 0)))
+          (i32.const 0))))
+    (if (i32.eq (local.get $index) (i32.const -1))
+      (then (return (global.get $null)))
+    )
+    (call $value_list_get (struct.get $Headers $values (local.get $headers)) (local.get $index))
+  )
+
+  (func $Headers-has
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+
+    (if (result anyref)
+      (i32.eq
+        (call $headers_find
+          (call $this_headers (local.get $this))
+          (call $ascii_change_case
+            (call $to_string (call $get_argument (local.get $arguments) (i32.const 0)))
+            (i32.const 0)))
+        (i32.const -1))
+      (then (global.get $false))
+      (else (global.get $true)))
+  )
+
+  (func $Headers-append
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+
+    (call $headers_append
+      (call $this_headers (local.get $this))
+      (call $get_argument (local.get $arguments) (i32.const 0))
+      (call $get_argument (local.get $arguments) (i32.const 1)))
+    (ref.null any)
+  )
+
+  (func $Headers-set
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (local $headers (ref $Headers))
+
+    (local.set $headers (call $this_headers (local.get $this)))
+    (call $headers_delete (local.get $headers) (call $get_argument (local.get $arguments) (i32.const 0)))
+    (call $headers_append
+      (local.get $headers)
+      (call $get_argument (local.get $arguments) (i32.const 0))
+      (call $get_argument (local.get $arguments) (i32.const 1)))
+    (ref.null any)
+  )
+
+  (func $Headers-delete
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+
+    (call $headers_delete
+      (call $this_headers (local.get $this))
+      (call $get_argument (local.get $arguments) (i32.const 0)))
+    (ref.null any)
+  )
+
+  ;; forEach(callback) calls callback(value, name, headers)
+  (func $Headers-forEach
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (local $headers (ref $Headers))
+    (local $callback anyref)
+    (local $i i32)
+
+    (local.set $headers (call $this_headers (local.get $this)))
+    (local.set $callback (call $get_argument (local.get $arguments) (i32.const 0)))
+    (if (i32.eqz (ref.test (ref $Function) (local.get $callback)))
+      (then
+        (call $throw_type_error
+          (call $new_static_string (i32.const {{ data(str='The "callback" argument must be of type function') }}) (i32.const {{ data_length() }})))
+      )
+    )
+    (block $break (loop $each
+      (br_if $break
+        (i32.ge_u (local.get $i) (struct.get $ValueList $size (struct.get $Headers $names (local.get $headers)))))
+      (drop
+        (call $call_function
+          (local.get $callback)
+          (ref.null any)
+          (array.new_fixed $JSArgs 3
+            (call $value_list_get (struct.get $Headers $values (local.get $headers)) (local.get $i))
+            (call $value_list_get (struct.get $Headers $names (local.get $headers)) (local.get $i))
+            (local.get $headers))))
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $each)
+    ))
+    (ref.null any)
+  )
+
+  ;; -- sending the request --
+
+  ;; the variant case of a wasi:http method, 9 is `other`
+  (func $http_method (param $method anyref) (result i32)
+    {% for method in ["GET", "HEAD", "POST", "PUT", "DELETE", "CONNECT", "OPTIONS", "TRACE", "PATCH"] %}
+    (if (call $string_equals (local.get $method) (call $new_static_string (i32.const {{ data(str=method) }}) (i32.const {{ data_length() }})))
+      (then (return (i32.const {{ loop.index0 }})))
+    )
+    {% endfor %}
+    (i32.const 9)
+  )
+
+  (func $throw_fetch_failed
+    (call $throw_type_error
+      (call $new_static_string (i32.const {{ data(str="fetch failed") }}) (i32.const {{ data_length() }})))
+  )
+
+  ;; frees the memory for results and drops the request before throwing,
+  ;; the request owns its fields
+  (func $abort_request (param $result i32) (param $request i32)
+    (call $free (local.get $result))
+    (call $drop-http-outgoing-request (local.get $request))
+    (call $throw_fetch_failed)
+  )
+
+  ;; sets the authority of the request, or the path with the query when
+  ;; `path` is set, both are option<string>. Returns whether it failed
+  (func $set_request_string (param $request i32) (param $value anyref) (param $path i32) (result i32)
+    (local $ptr i32)
+    (local $length i32)
+    (local $failed i32)
+
+    (local.set $ptr (call $string_to_memory (local.get $value)))
+    (local.set $length (call $string_length (local.get $value)))
+    (local.set $failed
+      (if (result i32) (local.get $path)
+        (then
+          (call $http-request-set-path-with-query
+            (local.get $request) (i32.const 1) (local.get $ptr) (local.get $length)))
+        (else
+          (call $http-request-set-authority
+            (local.get $request) (i32.const 1) (local.get $ptr) (local.get $length)))))
+    (call $free (local.get $ptr))
+    (local.get $failed)
+  )
+
+  (func $write_request_body (param $body i32) (param $bytes (ref $CharArray))
+    (local $result i32)
+    (local $stream i32)
+    (local $chunk i32)
+    (local $length i32)
+    (local $offset i32)
+    (local $i i32)
+
+    (local.set $result (call $malloc (i32.const 64)))
+    (call $http-outgoing-body-write (local.get $body) (local.get $result))
+    (if (i32.load8_u (local.get $result))
+      (then (call $throw_fetch_failed))
+    )
+    (local.set $stream (i32.load offset=4 (local.get $result)))
+
+    (local.set $chunk (call $malloc (global.get $http-write-chunk)))
+    (block $done (loop $write
+      (local.set $length (i32.sub (array.len (local.get $bytes)) (local.get $offset)))
+      (br_if $done (i32.eqz (local.get $length)))
+      (if (i32.gt_u (local.get $length) (global.get $http-write-chunk))
+        (then (local.set $length (global.get $http-write-chunk)))
+      )
+      (local.set $i (i32.const 0))
+      (block $break (loop $copy
+        (br_if $break (i32.ge_u (local.get $i) (local.get $length)))
+        (i32.store8
+          (i32.add (local.get $chunk) (local.get $i))
+          (array.get_u $CharArray (local.get $bytes) (i32.add (local.get $offset) (local.get $i))))
+        (local.set $i (i32.add (local.get $i) (i32.const 1)))
+        (br $copy)
+      ))
+      (call $output-stream-blocking-write-and-flush
+        (local.get $stream) (local.get $chunk) (local.get $length) (local.get $result))
+      (if (i32.load8_u (local.get $result))
+        (then (call $throw_fetch_failed))
+      )
+      (local.set $offset (i32.add (local.get $offset) (local.get $length)))
+      (br $write)
+    ))
+    (call $free (local.get $chunk))
+    (call $drop-output-stream (local.get $stream))
+
+    (call $http-outgoing-body-finish (local.get $body) (i32.const 0) (i32.const 0) (local.get $result))
+    (if (i32.load8_u (local.get $result))
+      (then (call $throw_fetch_failed))
+    )
+    (call $free (local.get $result))
+  )
+
+  ;; sends the request and returns the handle of the future-incoming-response
+  (func $send_request (param $url anyref) (param $init anyref) (result i32)
+    (local $length i32)
+    (local $colon i32)
+    (local $start i32)
+    (local $end i32)
+    (local $i i32)
+    (local $byte i32)
+    (local $scheme anyref)
+    (local $authority anyref)
+    (local $path anyref)
+    (local $method anyref)
+    (local $method_case i32)
+    (local $headers (ref $Headers))
+    (local $fields i32)
+    (local $request i32)
+    (local $body anyref)
+    (local $body_handle i32)
+    (local $result i32)
+    (local $ptr i32)
+    (local $future i32)
+
+    ;; scheme://authority/path?query#fragment
+    (local.set $length (call $string_length (local.get $url)))
+    (local.set $colon (i32.const -1))
+    (block $break (loop $find
+      (br_if $break (i32.ge_u (local.get $i) (local.get $length)))
+      (if (i32.eq (call $string_byte_at (local.get $url) (local.get $i)) (i32.const 0x3a))
+        (then
+          (local.set $colon (local.get $i))
+          (br $break)
+        )
+      )
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $find)
+    ))
+    (if (i32.or
+          (i32.lt_s (local.get $colon) (i32.const 1))
+          (i32.or
+            (i32.gt_u (i32.add (local.get $colon) (i32.const 3)) (local.get $length))
+            (i32.eqz
+              (call $string_equals
+                (call $substring (local.get $url) (local.get $colon) (i32.add (local.get $colon) (i32.const 3)))
+                (call $new_static_string (i32.const {{ data(str="://") }}) (i32.const {{ data_length() }}))))))
+      (then
+        (call $throw_type_error
+          (call $concat_strings
+            (call $new_static_string (i32.const {{ data(str="Failed to parse URL from ") }}) (i32.const {{ data_length() }}))
+            (local.get $url)))
+      )
+    )
+    (local.set $scheme
+      (call $ascii_change_case (call $substring (local.get $url) (i32.const 0) (local.get $colon)) (i32.const 0)))
+
+    (local.set $start (i32.add (local.get $colon) (i32.const 3)))
+    (local.set $i (local.get $start))
+    (local.set $end (local.get $length))
+    (block $break (loop $find
+      (br_if $break (i32.ge_u (local.get $i) (local.get $length)))
+      (local.set $byte (call $string_byte_at (local.get $url) (local.get $i)))
+      (if (i32.or
+            (i32.eq (local.get $byte) (i32.const 0x2f))
+            (i32.or
+              (i32.eq (local.get $byte) (i32.const 0x3f))
+              (i32.eq (local.get $byte) (i32.const 0x23))))
+        (then
+          (local.set $end (local.get $i))
+          (br $break)
+        )
+      )
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $find)
+    ))
+    (if (i32.eq (local.get $start) (local.get $end))
+      (then
+        (call $throw_type_error
+          (call $concat_strings
+            (call $new_static_string (i32.const {{ data(str="Failed to parse URL from ") }}) (i32.const {{ data_length() }}))
+            (local.get $url)))
+      )
+    )
+    (local.set $authority (call $substring (local.get $url) (local.get $start) (local.get $end)))
+
+    ;; the fragment isn't sent
+    (local.set $start (local.get $end))
+    (local.set $i (local.get $start))
+    (block $break (loop $find
+      (br_if $break (i32.ge_u (local.get $i) (local.get $length)))
+      (if (i32.eq (call $string_byte_at (local.get $url) (local.get $i)) (i32.const 0x23))
+        (then
+          (local.set $length (local.get $i))
+          (br $break)
+        )
+      )
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $find)
+    ))
+    (local.set $path (call $substring (local.get $url) (local.get $start) (local.get $length)))
+    (if (i32.or
+          (i32.eqz (call $string_length (local.get $path)))
+          (i32.ne (call $string_byte_at (local.get $path) (i32.const 0)) (i32.const 0x2f)))
+      (then
+        (local.set $path
+          (call $concat_strings
+            (call $new_static_string (i32.const {{ data(str="/") }}) (i32.const {{ data_length() }}))
+            (local.get $path)))
+      )
+    )
+
+    ;; init
+    (local.set $headers (call $new_headers))
+    (if (call $is_object (local.get $init))
+      (then
+        (local.set $method (call $get_property (local.get $init) (i32.const {{ data(str="method") }})))
+        (call $headers_fill (local.get $headers) (call $get_property (local.get $init) (i32.const {{ data(str="headers") }})))
+        (local.set $body (call $get_property (local.get $init) (i32.const {{ data(str="body") }})))
+      )
+    )
+    (local.set $method
+      (if (result anyref) (ref.is_null (local.get $method))
+        (then (call $new_static_string (i32.const {{ data(str="GET") }}) (i32.const {{ data_length() }})))
+        (else (call $to_string (local.get $method)))))
+    (local.set $method_case (call $http_method (call $ascii_change_case (local.get $method) (i32.const 1))))
+    (if (i32.eqz (call $is_null (local.get $body)))
+      (then
+        (if (i32.eqz (ref.is_null (local.get $body)))
+          (then
+            (if (i32.le_u (local.get $method_case) (i32.const 1))
+              (then
+                (call $throw_type_error
+                  (call $new_static_string (i32.const {{ data(str="Request with GET/HEAD method cannot have body.") }}) (i32.const {{ data_length() }})))
+              )
+            )
+          )
+        )
+      )
+      (else (local.set $body (ref.null any)))
+    )
+
+    ;; the fields are owned by the request once it's created
+    (local.set $fields (call $http-new-fields))
+    (local.set $result (call $malloc (i32.const 64)))
+    (local.set $i (i32.const 0))
+    (block $break (loop $fields
+      (br_if $break
+        (i32.ge_u (local.get $i) (struct.get $ValueList $size (struct.get $Headers $names (local.get $headers)))))
+      (local.set $ptr
+        (call $string_to_memory (call $value_list_get (struct.get $Headers $names (local.get $headers)) (local.get $i))))
+      (local.set $start
+        (call $string_to_memory (call $value_list_get (struct.get $Headers $values (local.get $headers)) (local.get $i))))
+      (call $http-fields-append
+        (local.get $fields)
+        (local.get $ptr)
+        (call $string_length (call $value_list_get (struct.get $Headers $names (local.get $headers)) (local.get $i)))
+        (local.get $start)
+        (call $string_length (call $value_list_get (struct.get $Headers $values (local.get $headers)) (local.get $i)))
+        (local.get $result))
+      (call $free (local.get $ptr))
+      (call $free (local.get $start))
+      (if (i32.load8_u (local.get $result))
+        (then
+          (call $free (local.get $result))
+          (call $drop-http-fields (local.get $fields))
+          (call $throw_type_error
+            (call $concat_strings
+              (call $new_static_string (i32.const {{ data(str="Invalid header: ") }}) (i32.const {{ data_length() }}))
+              (call $value_list_get (struct.get $Headers $names (local.get $headers)) (local.get $i))))
+        )
+      )
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $fields)
+    ))
+    (local.set $request (call $http-new-outgoing-request (local.get $fields)))
+
+    (if (i32.eq (local.get $method_case) (i32.const 9))
+      (then
+        (local.set $ptr (call $string_to_memory (local.get $method)))
+        (local.set $i
+          (call $http-request-set-method
+            (local.get $request) (i32.const 9) (local.get $ptr) (call $string_length (local.get $method))))
+        (call $free (local.get $ptr))
+      )
+      (else
+        (local.set $i
+          (call $http-request-set-method (local.get $request) (local.get $method_case) (i32.const 0) (i32.const 0)))
+      )
+    )
+    (if (local.get $i)
+      (then (call $abort_request (local.get $result) (local.get $request)))
+    )
+
+    (if (call $string_equals (local.get $scheme) (call $new_static_string (i32.const {{ data(str="http") }}) (i32.const {{ data_length() }})))
+      (then (local.set $i (i32.const 0)))
+      (else
+        (if (call $string_equals (local.get $scheme) (call $new_static_string (i32.const {{ data(str="https") }}) (i32.const {{ data_length() }})))
+          (then (local.set $i (i32.const 1)))
+          (else (call $abort_request (local.get $result) (local.get $request)))
+        )
+      )
+    )
+    (if (i32.or
+          (call $http-request-set-scheme (local.get $request) (i32.const 1) (local.get $i) (i32.const 0) (i32.const 0))
+          (i32.or
+            (call $set_request_string (local.get $request) (local.get $authority) (i32.const 0))
+            (call $set_request_string (local.get $request) (local.get $path) (i32.const 1))))
+      (then (call $abort_request (local.get $result) (local.get $request)))
+    )
+
+    ;; the body has to be taken before the request is handed over, but it's
+    ;; written afterwards, a host could wait for the request to be sent
+    ;; before accepting the data
+    (if (i32.eqz (ref.is_null (local.get $body)))
+      (then
+        (call $http-outgoing-request-body (local.get $request) (local.get $result))
+        (if (i32.load8_u (local.get $result))
+          (then (call $abort_request (local.get $result) (local.get $request)))
+        )
+        (local.set $body_handle (i32.load offset=4 (local.get $result)))
+      )
+    )
+
+    ;; the request is handed over even if it fails, but the body is still ours
+    (call $http-handle (local.get $request) (i32.const 0) (i32.const 0) (local.get $result))
+    (if (i32.load8_u (local.get $result))
+      (then
+        (call $free (local.get $result))
+        (if (i32.eqz (ref.is_null (local.get $body)))
+          (then (call $drop-http-outgoing-body (local.get $body_handle)))
+        )
+        (call $throw_fetch_failed)
+      )
+    )
+    (local.set $future (i32.load offset=8 (local.get $result)))
+    (call $free (local.get $result))
+
+    (if (i32.eqz (ref.is_null (local.get $body)))
+      (then (call $write_request_body (local.get $body_handle) (call $value_bytes (local.get $body))))
+    )
+    (local.get $future)
+  )
+
+  (func $wait_for_response (param $pending (ref $PendingFetch))
+    (local $environment (ref $Scope))
+
+    (local.set $environment (call $new_environment (ref.cast (ref $Scope) (global.get $scope)) (i32.const 1)))
+    (array.set $AnyrefArray (struct.get $Scope $slots (local.get $environment)) (i32.const 0) (local.get $pending))
+    (drop
+      (call $add-pollable
+        (call $http-future-response-subscribe (struct.get $PendingFetch $future (local.get $pending)))
+        (call $new_function (local.get $environment) (ref.func $fetch-response-ready) (ref.null any))))
+  )
+
+  (func $fetch
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (local $promise (ref $Promise))
+    (local $url anyref)
+    (local $future i32)
+    (local $error anyref)
+
+    (local.set $promise (call $new-promise))
+    try
+      (local.set $url (call $to_string (call $get_argument (local.get $arguments) (i32.const 0))))
+      (local.set $future
+        (call $send_request (local.get $url) (call $get_argument (local.get $arguments) (i32.const 1))))
+      (call $wait_for_response (struct.new $PendingFetch (local.get $future) (local.get $promise) (local.get $url)))
+    catch $JSException
+      (local.set $error)
+      (call $reject-promise (local.get $promise) (local.get $error))
+    end
+    (local.get $promise)
+  )
+
+  ;; -- the response --
+
+  (func $new_response (param $handle i32) (param $url anyref) (result (ref $Response))
+    (local $status i32)
+    (local $fields i32)
+    (local $headers (ref $Headers))
+    (local $result i32)
+    (local $list i32)
+    (local $count i32)
+    (local $entry i32)
+    (local $i i32)
+    (local $body i32)
+    (local $stream i32)
+    (local $response (ref $Response))
+
+    (local.set $status (call $http-incoming-response-status (local.get $handle)))
+    (local.set $result (call $malloc (i32.const 16)))
+
+    ;; entries are a list<tuple<string, list<u8>>>, 16 bytes each
+    (local.set $headers (call $new_headers))
+    (local.set $fields (call $http-incoming-response-headers (local.get $handle)))
+    (call $http-fields-entries (local.get $fields) (local.get $result))
+    (local.set $list (i32.load (local.get $result)))
+    (local.set $count (i32.load offset=4 (local.get $result)))
+    (block $break (loop $entries
+      (br_if $break (i32.ge_u (local.get $i) (local.get $count)))
+      (local.set $entry (i32.add (local.get $list) (i32.shl (local.get $i) (i32.const 4))))
+      (call $headers_append
+        (local.get $headers)
+        (call $take_host_string (i32.load (local.get $entry)) (i32.load offset=4 (local.get $entry)))
+        (call $take_host_string (i32.load offset=8 (local.get $entry)) (i32.load offset=12 (local.get $entry))))
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $entries)
+    ))
+    (call $free (local.get $list))
+    (call $drop-http-fields (local.get $fields))
+
+    (local.set $body (i32.const -1))
+    (local.set $stream (i32.const -1))
+    (call $http-incoming-response-consume (local.get $handle) (local.get $result))
+    (if (i32.eqz (i32.load8_u (local.get $result)))
+      (then
+        (local.set $body (i32.load offset=4 (local.get $result)))
+        (call $http-incoming-body-stream (local.get $body) (local.get $result))
+        (if (i32.eqz (i32.load8_u (local.get $result)))
+          (then (local.set $stream (i32.load offset=4 (local.get $result))))
+        )
+      )
+    )
+    (call $free (local.get $result))
+
+    (local.set $response
+      (struct.new $Response
+        (call $new_hashmap)
+        (global.get $response-prototype)
+        (i32.const 0)
+        (local.get $handle)
+        (local.get $body)
+        (local.get $stream)
+        (i32.const 0)))
+    (call $set_property (local.get $response) (i32.const {{ data(str="status") }})
+      (call $new_number_i32 (local.get $status)))
+    (call $set_property (local.get $response) (i32.const {{ data(str="statusText") }})
+      (call $new_static_string (i32.const {{ data(str="") }}) (i32.const {{ data_length() }})))
+    (call $set_property (local.get $response) (i32.const {{ data(str="ok") }})
+      (if (result anyref)
+        (i32.and
+          (i32.ge_u (local.get $status) (i32.const 200))
+          (i32.le_u (local.get $status) (i32.const 299)))
+        (then (global.get $true))
+        (else (global.get $false))))
+    (call $set_property (local.get $response) (i32.const {{ data(str="headers") }}) (local.get $headers))
+    (call $set_property (local.get $response) (i32.const {{ data(str="url") }}) (local.get $url))
+    (call $set_property (local.get $response) (i32.const {{ data(str="redirected") }}) (global.get $false))
+    (call $set_property (local.get $response) (i32.const {{ data(str="bodyUsed") }}) (global.get $false))
+    (local.get $response)
+  )
+
+  ;; called when the pollable of a future-incoming-response is ready
+  (func $fetch-response-ready
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (local $pending (ref $PendingFetch))
+    (local $future i32)
+    (local $result i32)
+    (local $handle i32)
+
+    (local.set $pending
+      (ref.cast (ref $PendingFetch) (array.get $AnyrefArray (struct.get $Scope $slots (local.get $scope)) (i32.const 0))))
+    (local.set $future (struct.get $PendingFetch $future (local.get $pending)))
+
+    ;; option<result<result<incoming-response, error-code>>>
+    (local.set $result (call $malloc (i32.const 64)))
+    (call $http-future-response-get (local.get $future) (local.get $result))
+    (if (i32.eqz (i32.load8_u (local.get $result)))
+      (then
+        ;; not ready after all
+        (call $free (local.get $result))
+        (call $wait_for_response (local.get $pending))
+        (return (ref.null any))
+      )
+    )
+    (call $drop-http-future-response (local.get $future))
+
+    (if (i32.or
+          (i32.load8_u offset=8 (local.get $result))
+          (i32.load8_u offset=16 (local.get $result)))
+      (then
+        (call $free (local.get $result))
+        (call $reject-promise
+          (struct.get $PendingFetch $promise (local.get $pending))
+          (call $new_error
+            (global.get $type-error-prototype)
+            (call $new_static_string (i32.const {{ data(str="fetch failed") }}) (i32.const {{ data_length() }}))))
+        (return (ref.null any))
+      )
+    )
+    (local.set $handle (i32.load offset=24 (local.get $result)))
+    (call $free (local.get $result))
+
+    (call $resolve-promise
+      (struct.get $PendingFetch $promise (local.get $pending))
+      (call $new_response (local.get $handle) (struct.get $PendingFetch $url (local.get $pending))))
+    (ref.null any)
+  )
+
+  ;; the stream has to be dropped before the body and the body before the
+  ;; response
+  (func $close_response_body (param $response (ref $Response))
+    (if (i32.ne (struct.get $Response $stream (local.get $response)) (i32.const -1))
+      (then
+        (call $drop-input-stream (struct.get $Response $stream (local.get $response)))
+        (struct.set $Response $stream (local.get $response) (i32.const -1))
+      )
+    )
+    (if (i32.ne (struct.get $Response $incoming-body (local.get $response)) (i32.const -1))
+      (then
+        (call $drop-http-incoming-body (struct.get $Response $incoming-body (local.get $response)))
+        (struct.set $Response $incoming-body (local.get $response) (i32.const -1))
+      )
+    )
+    (if (i32.ne (struct.get $Response $incoming-response (local.get $response)) (i32.const -1))
+      (then
+        (call $drop-http-incoming-response (struct.get $Response $incoming-response (local.get $response)))
+        (struct.set $Response $incoming-response (local.get $response) (i32.const -1))
+      )
+    )
+  )
+
+  (func $finish_body (param $reader (ref $BodyReader))
+    (local $text (ref $String))
+    (local $kind i32)
+    (local $value anyref)
+    (local $error anyref)
+
+    (local.set $text (call $builder_to_string (struct.get $BodyReader $bytes (local.get $reader))))
+    (local.set $kind (struct.get $BodyReader $kind (local.get $reader)))
+    (if (i32.eq (local.get $kind) (global.get $body-array-buffer))
+      (then
+        (call $resolve-promise
+          (struct.get $BodyReader $promise (local.get $reader))
+          (call $new_array_buffer (struct.get $String $data (local.get $text))))
+        (return)
+      )
+    )
+    (if (i32.eq (local.get $kind) (global.get $body-json))
+      (then
+        try
+          (local.set $value
+            (call $JSON-parse
+              (ref.cast (ref $Scope) (global.get $scope))
+              (ref.null any)
+              (call $create-arguments-1 (local.get $text))))
+        catch $JSException
+          (local.set $error)
+          (call $reject-promise (struct.get $BodyReader $promise (local.get $reader)) (local.get $error))
+          (return)
+        end
+        (call $resolve-promise (struct.get $BodyReader $promise (local.get $reader)) (local.get $value))
+        (return)
+      )
+    )
+    (call $resolve-promise (struct.get $BodyReader $promise (local.get $reader)) (local.get $text))
+  )
+
+  ;; reads whatever is available, then either waits for more or finishes
+  (func $read_body (param $reader (ref $BodyReader))
+    (local $response (ref $Response))
+    (local $stream i32)
+    (local $result i32)
+    (local $length i32)
+    (local $failed i32)
+    (local $environment (ref $Scope))
+
+    (local.set $response (struct.get $BodyReader $response (local.get $reader)))
+    (local.set $stream (struct.get $Response $stream (local.get $response)))
+    (if (i32.eq (local.get $stream) (i32.const -1))
+      (then
+        (call $close_response_body (local.get $response))
+        (call $finish_body (local.get $reader))
+        (return)
+      )
+    )
+
+    ;; result<list<u8>, stream-error>
+    (local.set $result (call $malloc (i32.const 16)))
+    (block $closed (loop $read
+      (call $input-stream-read (local.get $stream) (global.get $http-read-chunk) (local.get $result))
+      (br_if $closed (i32.load8_u (local.get $result)))
+
+      (local.set $length (i32.load offset=8 (local.get $result)))
+      (call $builder_append_memory
+        (struct.get $BodyReader $bytes (local.get $reader))
+        (i32.load offset=4 (local.get $result))
+        (local.get $length))
+      (call $free (i32.load offset=4 (local.get $result)))
+      (br_if $read (local.get $length))
+
+      ;; nothing to read yet
+      (call $free (local.get $result))
+      (local.set $environment (call $new_environment (ref.cast (ref $Scope) (global.get $scope)) (i32.const 1)))
+      (array.set $AnyrefArray (struct.get $Scope $slots (local.get $environment)) (i32.const 0) (local.get $reader))
+      (drop
+        (call $add-pollable
+          (call $input-stream-subscribe (local.get $stream))
+          (call $new_function (local.get $environment) (ref.func $fetch-body-ready) (ref.null any))))
+      (return)
+    ))
+
+    ;; last-operation-failed carries an error resource, closed is the end of
+    ;; the body
+    (if (i32.eqz (i32.load8_u offset=4 (local.get $result)))
+      (then
+        (call $drop-io-error (i32.load offset=8 (local.get $result)))
+        (local.set $failed (i32.const 1))
+      )
+    )
+    (call $free (local.get $result))
+    (call $close_response_body (local.get $response))
+    (if (local.get $failed)
+      (then
+        (call $reject-promise
+          (struct.get $BodyReader $promise (local.get $reader))
+          (call $new_error
+            (global.get $type-error-prototype)
+            (call $new_static_string (i32.const {{ data(str="terminated") }}) (i32.const {{ data_length() }}))))
+        (return)
+      )
+    )
+    (call $finish_body (local.get $reader))
+  )
+
+  (func $fetch-body-ready
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+
+    (call $read_body
+      (ref.cast (ref $BodyReader) (array.get $AnyrefArray (struct.get $Scope $slots (local.get $scope)) (i32.const 0))))
+    (ref.null any)
+  )
+
+  (func $response_body (param $this anyref) (param $kind i32) (result anyref)
+    (local $response (ref $Response))
+    (local $promise (ref $Promise))
+
+    (if (i32.eqz (ref.test (ref $Response) (local.get $this)))
+      (then
+        (call $throw_type_error
+          (call $new_static_string (i32.const {{ data(str="Illegal invocation") }}) (i32.const {{ data_length() }})))
+      )
+    )
+    (local.set $response (ref.cast (ref $Response) (local.get $this)))
+    (local.set $promise (call $new-promise))
+    (if (struct.get $Response $body-used (local.get $response))
+      (then
+        (call $reject-promise
+          (local.get $promise)
+          (call $new_error
+            (global.get $type-error-prototype)
+            (call $new_static_string (i32.const {{ data(str="Body is unusable: Body has already been read") }}) (i32.const {{ data_length() }}))))
+        (return (local.get $promise))
+      )
+    )
+    (struct.set $Response $body-used (local.get $response) (i32.const 1))
+    (call $set_property (local.get $response) (i32.const {{ data(str="bodyUsed") }}) (global.get $true))
+
+    (call $read_body
+      (struct.new $BodyReader
+        (local.get $response)
+        (local.get $promise)
+        (local.get $kind)
+        (call $new_string_builder)))
+    (local.get $promise)
+  )
+
+  (func $Response-text
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (call $response_body (local.get $this) (global.get $body-text))
+  )
+
+  (func $Response-json
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (call $response_body (local.get $this) (global.get $body-json))
+  )
+
+  (func $Response-arrayBuffer
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (call $response_body (local.get $this) (global.get $body-array-buffer))
+  )
+
+  (elem declare func $fetch)
+  (elem declare func $fetch-response-ready)
+  (elem declare func $fetch-body-ready)
+  (elem declare func $Headers-constructor)
+  (elem declare func $Headers-get)
+  (elem declare func $Headers-has)
+  (elem declare func $Headers-append)
+  (elem declare func $Headers-set)
+  (elem declare func $Headers-delete)
+  (elem declare func $Headers-forEach)
+  (elem declare func $Response-text)
+  (elem declare func $Response-json)
+  (elem declare func $Response-arrayBuffer)
+
+  (func $install-fetch-globals (param $scope (ref $Scope))
+    (local $headers (ref $Function))
+
+    (global.set $headers-prototype (call $new_object))
+    (call $add_method (global.get $headers-prototype) (i32.const {{ data(str="get") }}) (ref.func $Headers-get))
+    (call $add_method (global.get $headers-prototype) (i32.const {{ data(str="has") }}) (ref.func $Headers-has))
+    (call $add_method (global.get $headers-prototype) (i32.const {{ data(str="append") }}) (ref.func $Headers-append))
+    (call $add_method (global.get $headers-prototype) (i32.const {{ data(str="set") }}) (ref.func $Headers-set))
+    (call $add_method (global.get $headers-prototype) (i32.const {{ data(str="delete") }}) (ref.func $Headers-delete))
+    (call $add_method (global.get $headers-prototype) (i32.const {{ data(str="forEach") }}) (ref.func $Headers-forEach))
+    (call $set_to_string_tag (global.get $headers-prototype)
+      (call $new_static_string (i32.const {{ data(str="Headers") }}) (i32.const {{ data_length() }})))
+    (local.set $headers (call $new_function (local.get $scope) (ref.func $Headers-constructor) (ref.null any)))
+    (call $set_property (local.get $headers) (i32.const {{ data(str="prototype") }}) (global.get $headers-prototype))
+    (call $set_property (global.get $headers-prototype) (i32.const {{ data(str="constructor") }}) (local.get $headers))
+    (call $set_variable (local.get $scope) (i32.const {{ data(str="Headers") }}) (local.get $headers))
+
+    (global.set $response-prototype (call $new_object))
+    (call $add_method (global.get $response-prototype) (i32.const {{ data(str="text") }}) (ref.func $Response-text))
+    (call $add_method (global.get $response-prototype) (i32.const {{ data(str="json") }}) (ref.func $Response-json))
+    (call $add_method (global.get $response-prototype) (i32.const {{ data(str="arrayBuffer") }}) (ref.func $Response-arrayBuffer))
+    (call $set_to_string_tag (global.get $response-prototype)
+      (call $new_static_string (i32.const {{ data(str="Response") }}) (i32.const {{ data_length() }})))
+
+    (call $set_variable
+      (local.get $scope)
+      (i32.const {{ data(str="fetch") }})
+      (call $new_function (local.get $scope) (ref.func $fetch) (ref.null any)))
+  )
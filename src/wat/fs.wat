@@ -0,0 +1,518 @@
+  ;; The `fs` global, a synchronous subset of Node's `fs` module on top of
+  ;; wasi:filesystem: readFileSync, writeFileSync, appendFileSync and
+  ;; existsSync. There's no working directory in WASI, so absolute paths are
+  ;; resolved against the preopened directory with the longest matching name
+  ;; and relative ones against the one called ".", which is what runtimes
+  ;; usually preopen for the current directory. Errors are thrown with the
+  ;; same messages as in Node. It's only included when the script mentions
+  ;; `fs`.
+
+  {% set filesystem_errors = [
+    "EACCES: permission denied",
+    "EAGAIN: resource temporarily unavailable",
+    "EALREADY: connection already in progress",
+    "EBADF: bad file descriptor",
+    "EBUSY: resource busy or locked",
+    "EDEADLK: resource deadlock avoided",
+    "EDQUOT: disk quota exceeded",
+    "EEXIST: file already exists",
+    "EFBIG: file too large",
+    "EILSEQ: illegal byte sequence",
+    "EINPROGRESS: operation in progress",
+    "EINTR: interrupted system call",
+    "EINVAL: invalid argument",
+    "EIO: i/o error",
+    "EISDIR: illegal operation on a directory",
+    "ELOOP: too many symbolic links encountered",
+    "EMLINK: too many links",
+    "EMSGSIZE: message too long",
+    "ENAMETOOLONG: name too long",
+    "ENODEV: no such device",
+    "ENOENT: no such file or directory",
+    "ENOLCK: no locks available",
+    "ENOMEM: not enough memory",
+    "ENOSPC: no space left on device",
+    "ENOTDIR: not a directory",
+    "ENOTEMPTY: directory not empty",
+    "ENOTRECOVERABLE: state not recoverable",
+    "ENOTSUP: operation not supported",
+    "ENOTTY: inappropriate ioctl for device",
+    "ENXIO: no such device or address",
+    "EOVERFLOW: value too large for defined data type",
+    "EPERM: operation not permitted",
+    "EPIPE: broken pipe",
+    "EROFS: read-only file system",
+    "ESPIPE: invalid seek",
+    "ETXTBSY: text file is busy",
+    "EXDEV: cross-device link not permitted",
+  ] %}
+  {% set error_no_entry = 20 %}
+
+  ;; open-flags and descriptor-flags
+  {% set open_create = 1 %}
+  {% set open_truncate = 8 %}
+  {% set descriptor_read = 1 %}
+  {% set descriptor_write = 2 %}
+  {% set path_symlink_follow = 1 %}
+
+  (global $fs-chunk-size i32 (i32.const 65536))
+
+  ;; the preopened directories and their names, loaded on first use
+  (global $preopen-names (mut (ref null $ValueList)) (ref.null $ValueList))
+  (global $preopen-handles (mut (ref null $I32Array)) (ref.null $I32Array))
+
+  ;; a file found in one of the preopened directories, set by $resolve_path
+  (global $resolved-directory (mut i32) (i32.const -1))
+  (global $resolved-path (mut anyref) (ref.null any))
+
+  ;; throws something like "ENOENT: no such file or directory, open 'a.txt'"
+  (func $throw_filesystem_error (param $code i32) (param $syscall anyref) (param $path anyref)
+    (local $message anyref)
+
+    (local.set $message
+      (call $new_static_string (i32.const {{ data(str="EIO: i/o error") }}) (i32.const {{ data_length() }})))
+    {% for error in filesystem_errors %}
+    (if (i32.eq (local.get $code) (i32.const {{ loop.index0 }}))
+      (then
+        (local.set $message
+          (call $new_static_string (i32.const {{ data(str=error) }}) (i32.const {{ data_length() }})))
+      )
+    )
+    {% endfor %}
+    (local.set $message
+      (call $concat_strings
+        (call $concat_strings
+          (call $concat_strings
+            (call $concat_strings (local.get $message)
+              (call $new_static_string (i32.const {{ data(str=", ") }}) (i32.const {{ data_length() }})))
+            (local.get $syscall))
+          (call $new_static_string (i32.const {{ data(str=" '") }}) (i32.const {{ data_length() }})))
+        (call $concat_strings
+          (local.get $path)
+          (call $new_static_string (i32.const {{ data(str="'") }}) (i32.const {{ data_length() }})))))
+    (call $throw_error (local.get $message))
+  )
+
+  ;; list<tuple<descriptor, string>>, 12 bytes for every directory
+  (func $load_preopens
+    (local $result i32)
+    (local $list i32)
+    (local $count i32)
+    (local $entry i32)
+    (local $i i32)
+
+    (local.set $result (call $malloc (i32.const 8)))
+    (call $get-directories (local.get $result))
+    (local.set $list (i32.load (local.get $result)))
+    (local.set $count (i32.load offset=4 (local.get $result)))
+    (call $free (local.get $result))
+
+    (global.set $preopen-names (call $new_value_list))
+    (global.set $preopen-handles (array.new_default $I32Array (local.get $count)))
+    (block $break (loop $directories
+      (br_if $break (i32.ge_u (local.get $i) (local.get $count)))
+      (local.set $entry (i32.add (local.get $list) (i32.mul (local.get $i) (i32.const 12))))
+      (array.set $I32Array (ref.as_non_null (global.get $preopen-handles)) (local.get $i)
+        (i32.load (local.get $entry)))
+      (call $value_list_push
+        (ref.as_non_null (global.get $preopen-names))
+        (call $take_host_string (i32.load offset=4 (local.get $entry)) (i32.load offset=8 (local.get $entry))))
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $directories)
+    ))
+    (call $free (local.get $list))
+  )
+
+  ;; whether `path` is `prefix` or a path inside of it
+  (func $path_in_directory (param $path anyref) (param $prefix anyref) (result i32)
+    (local $length i32)
+
+    (local.set $length (call $string_length (local.get $prefix)))
+    (if (i32.gt_u (local.get $length) (call $string_length (local.get $path)))
+      (then (return (i32.const 0)))
+    )
+    (if (i32.eqz
+          (call $string_equals
+            (call $substring (local.get $path) (i32.const 0) (local.get $length))
+            (local.get $prefix)))
+      (then (return (i32.const 0)))
+    )
+    (if (i32.eq (local.get $length) (call $string_length (local.get $path)))
+      (then (return (i32.const 1)))
+    )
+    ;; "/" and the like
+    (if (i32.and
+          (i32.gt_u (local.get $length) (i32.const 0))
+          (i32.eq (call $string_byte_at (local.get $prefix) (i32.sub (local.get $length) (i32.const 1))) (i32.const 0x2f)))
+      (then (return (i32.const 1)))
+    )
+    (i32.eq (call $string_byte_at (local.get $path) (local.get $length)) (i32.const 0x2f))
+  )
+
+  ;; sets $resolved-directory and $resolved-path, or throws ENOENT if no
+  ;; preopened directory fits
+  (func $resolve_path (param $path anyref) (param $syscall anyref)
+    (local $names (ref $ValueList))
+    (local $name anyref)
+    (local $best i32)
+    (local $best_length i32)
+    (local $start i32)
+    (local $length i32)
+    (local $i i32)
+
+    (if (ref.is_null (global.get $preopen-names))
+      (then (call $load_preopens))
+    )
+    (local.set $names (ref.as_non_null (global.get $preopen-names)))
+    (local.set $length (call $string_length (local.get $path)))
+    (local.set $best (i32.const -1))
+
+    (if (i32.and
+          (i32.gt_u (local.get $length) (i32.const 0))
+          (i32.eq (call $string_byte_at (local.get $path) (i32.const 0)) (i32.const 0x2f)))
+      (then
+        (block $break (loop $directories
+          (br_if $break (i32.ge_u (local.get $i) (struct.get $ValueList $size (local.get $names))))
+          (local.set $name (call $value_list_get (local.get $names) (local.get $i)))
+          (if (i32.and
+                (call $path_in_directory (local.get $path) (local.get $name))
+                (i32.or
+                  (i32.eq (local.get $best) (i32.const -1))
+                  (i32.gt_u (call $string_length (local.get $name)) (local.get $best_length))))
+            (then
+              (local.set $best (local.get $i))
+              (local.set $best_length (call $string_length (local.get $name)))
+            )
+          )
+          (local.set $i (i32.add (local.get $i) (i32.const 1)))
+          (br $directories)
+        ))
+        (local.set $start (local.get $best_length))
+      )
+      (else
+        (block $break (loop $directories
+          (br_if $break (i32.ge_u (local.get $i) (struct.get $ValueList $size (local.get $names))))
+          (if (call $string_equals
+                (call $value_list_get (local.get $names) (local.get $i))
+                (call $new_static_string (i32.const {{ data(str=".") }}) (i32.const {{ data_length() }})))
+            (then
+              (local.set $best (local.get $i))
+              (br $break)
+            )
+          )
+          (local.set $i (i32.add (local.get $i) (i32.const 1)))
+          (br $directories)
+        ))
+      )
+    )
+    (if (i32.eq (local.get $best) (i32.const -1))
+      (then (call $throw_filesystem_error (i32.const {{ error_no_entry }}) (local.get $syscall) (local.get $path)))
+    )
+
+    ;; the rest of the path is relative to the directory
+    (block $break (loop $slashes
+      (br_if $break (i32.ge_u (local.get $start) (local.get $length)))
+      (br_if $break (i32.ne (call $string_byte_at (local.get $path) (local.get $start)) (i32.const 0x2f)))
+      (local.set $start (i32.add (local.get $start) (i32.const 1)))
+      (br $slashes)
+    ))
+    (global.set $resolved-directory
+      (array.get $I32Array (ref.as_non_null (global.get $preopen-handles)) (local.get $best)))
+    (global.set $resolved-path
+      (if (result anyref) (i32.eq (local.get $start) (local.get $length))
+        (then (call $new_static_string (i32.const {{ data(str=".") }}) (i32.const {{ data_length() }})))
+        (else (call $substring (local.get $path) (local.get $start) (local.get $length)))))
+  )
+
+  ;; opens a file and returns its descriptor
+  (func $open_file (param $path anyref) (param $open_flags i32) (param $flags i32) (result i32)
+    (local $syscall anyref)
+    (local $relative anyref)
+    (local $ptr i32)
+    (local $result i32)
+    (local $code i32)
+    (local $descriptor i32)
+
+    (local.set $syscall (call $new_static_string (i32.const {{ data(str="open") }}) (i32.const {{ data_length() }})))
+    (call $resolve_path (local.get $path) (local.get $syscall))
+    (local.set $relative (global.get $resolved-path))
+
+    ;; result<descriptor, error-code>
+    (local.set $result (call $malloc (i32.const 8)))
+    (local.set $ptr (call $string_to_memory (local.get $relative)))
+    (call $descriptor-open-at
+      (global.get $resolved-directory)
+      (i32.const {{ path_symlink_follow }})
+      (local.get $ptr)
+      (call $string_length (local.get $relative))
+      (local.get $open_flags)
+      (local.get $flags)
+      (local.get $result))
+    (call $free (local.get $ptr))
+    (local.set $code (i32.load8_u offset=4 (local.get $result)))
+    (local.set $descriptor (i32.load offset=4 (local.get $result)))
+    (if (i32.load8_u (local.get $result))
+      (then
+        (call $free (local.get $result))
+        (call $throw_filesystem_error (local.get $code) (local.get $syscall) (local.get $path))
+      )
+    )
+    (call $free (local.get $result))
+    (local.get $descriptor)
+  )
+
+  (func $read_file (param $descriptor i32) (param $path anyref) (result (ref $String))
+    (local $result i32)
+    (local $list i32)
+    (local $length i32)
+    (local $offset i64)
+    (local $end i32)
+    (local $code i32)
+    (local $bytes (ref $StringBuilder))
+
+    (local.set $bytes (call $new_string_builder))
+    ;; result<tuple<list<u8>, bool>, error-code>, the bool is set at the end
+    ;; of the file
+    (local.set $result (call $malloc (i32.const 16)))
+    (block $done (loop $read
+      (call $descriptor-read
+        (local.get $descriptor)
+        (i64.extend_i32_u (global.get $fs-chunk-size))
+        (local.get $offset)
+        (local.get $result))
+      (if (i32.load8_u (local.get $result))
+        (then
+          (local.set $code (i32.load8_u offset=4 (local.get $result)))
+          (call $free (local.get $result))
+          (call $drop-descriptor (local.get $descriptor))
+          (call $throw_filesystem_error
+            (local.get $code)
+            (call $new_static_string (i32.const {{ data(str="read") }}) (i32.const {{ data_length() }}))
+            (local.get $path))
+        )
+      )
+      (local.set $list (i32.load offset=4 (local.get $result)))
+      (local.set $length (i32.load offset=8 (local.get $result)))
+      (local.set $end (i32.load8_u offset=12 (local.get $result)))
+      (call $builder_append_memory (local.get $bytes) (local.get $list) (local.get $length))
+      (call $free (local.get $list))
+      (local.set $offset (i64.add (local.get $offset) (i64.extend_i32_u (local.get $length))))
+      (br_if $done (i32.or (local.get $end) (i32.eqz (local.get $length))))
+      (br $read)
+    ))
+    (call $free (local.get $result))
+    (call $builder_to_string (local.get $bytes))
+  )
+
+  ;; writes all of the bytes, starting at the given offset in the file
+  (func $write_file (param $descriptor i32) (param $data (ref $CharArray)) (param $offset i64) (param $path anyref)
+    (local $result i32)
+    (local $chunk i32)
+    (local $position i32)
+    (local $length i32)
+    (local $written i32)
+    (local $code i32)
+    (local $i i32)
+
+    ;; result<filesize, error-code>
+    (local.set $result (call $malloc (i32.const 16)))
+    (local.set $chunk (call $malloc (global.get $fs-chunk-size)))
+    (block $done (loop $write
+      (local.set $length (i32.sub (array.len (local.get $data)) (local.get $position)))
+      (br_if $done (i32.eqz (local.get $length)))
+      (if (i32.gt_u (local.get $length) (global.get $fs-chunk-size))
+        (then (local.set $length (global.get $fs-chunk-size)))
+      )
+      (local.set $i (i32.const 0))
+      (block $break (loop $copy
+        (br_if $break (i32.ge_u (local.get $i) (local.get $length)))
+        (i32.store8
+          (i32.add (local.get $chunk) (local.get $i))
+          (array.get_u $CharArray (local.get $data) (i32.add (local.get $position) (local.get $i))))
+        (local.set $i (i32.add (local.get $i) (i32.const 1)))
+        (br $copy)
+      ))
+      (call $descriptor-write
+        (local.get $descriptor)
+        (local.get $chunk)
+        (local.get $length)
+        (i64.add (local.get $offset) (i64.extend_i32_u (local.get $position)))
+        (local.get $result))
+      (if (i32.load8_u (local.get $result))
+        (then
+          (local.set $code (i32.load8_u offset=8 (local.get $result)))
+          (call $free (local.get $chunk))
+          (call $free (local.get $result))
+          (call $drop-descriptor (local.get $descriptor))
+          (call $throw_filesystem_error
+            (local.get $code)
+            (call $new_static_string (i32.const {{ data(str="write") }}) (i32.const {{ data_length() }}))
+            (local.get $path))
+        )
+      )
+      ;; a write can be shorter than the chunk
+      (local.set $written (i32.wrap_i64 (i64.load offset=8 (local.get $result))))
+      (local.set $position (i32.add (local.get $position) (local.get $written)))
+      (br $write)
+    ))
+    (call $free (local.get $chunk))
+    (call $free (local.get $result))
+  )
+
+  ;; the encoding can be passed as a string or as the `encoding` property of
+  ;; an options object, anything else than undefined or null means a string
+  ;; should be returned
+  (func $encoding_option (param $options anyref) (result i32)
+    (if (call $is_object (local.get $options))
+      (then (local.set $options (call $get_property (local.get $options) (i32.const {{ data(str="encoding") }}))))
+    )
+    (i32.eqz
+      (i32.or (ref.is_null (local.get $options)) (call $is_null (local.get $options))))
+  )
+
+  (func $fs-readFileSync
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (local $path anyref)
+    (local $descriptor i32)
+    (local $contents (ref $String))
+
+    (local.set $path (call $to_string (call $get_argument (local.get $arguments) (i32.const 0))))
+    (local.set $descriptor
+      (call $open_file (local.get $path) (i32.const 0) (i32.const {{ descriptor_read }})))
+    (local.set $contents (call $read_file (local.get $descriptor) (local.get $path)))
+    (call $drop-descriptor (local.get $descriptor))
+
+    (if (call $encoding_option (call $get_argument (local.get $arguments) (i32.const 1)))
+      (then (return (local.get $contents)))
+    )
+    ;; Node returns a Buffer, which is a Uint8Array
+    (call $new_typed_array
+      (i32.const 1)
+      (call $new_array_buffer (struct.get $String $data (local.get $contents)))
+      (i32.const 0)
+      (struct.get $String $length (local.get $contents)))
+  )
+
+  (func $fs-writeFileSync
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (local $path anyref)
+    (local $descriptor i32)
+
+    (local.set $path (call $to_string (call $get_argument (local.get $arguments) (i32.const 0))))
+    (local.set $descriptor
+      (call $open_file
+        (local.get $path)
+        (i32.const {{ open_create + open_truncate }})
+        (i32.const {{ descriptor_write }})))
+    (call $write_file
+      (local.get $descriptor)
+      (call $value_bytes (call $get_argument (local.get $arguments) (i32.const 1)))
+      (i64.const 0)
+      (local.get $path))
+    (call $drop-descriptor (local.get $descriptor))
+    (ref.null any)
+  )
+
+  (func $fs-appendFileSync
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (local $path anyref)
+    (local $descriptor i32)
+    (local $result i32)
+    (local $code i32)
+    (local $size i64)
+
+    (local.set $path (call $to_string (call $get_argument (local.get $arguments) (i32.const 0))))
+    (local.set $descriptor
+      (call $open_file (local.get $path) (i32.const {{ open_create }}) (i32.const {{ descriptor_write }})))
+
+    ;; result<descriptor-stat, error-code>, the size is the third field of
+    ;; the record
+    (local.set $result (call $malloc (i32.const 112)))
+    (call $descriptor-stat (local.get $descriptor) (local.get $result))
+    (local.set $code (i32.load8_u offset=8 (local.get $result)))
+    (local.set $size (i64.load offset=24 (local.get $result)))
+    (if (i32.load8_u (local.get $result))
+      (then
+        (call $free (local.get $result))
+        (call $drop-descriptor (local.get $descriptor))
+        (call $throw_filesystem_error
+          (local.get $code)
+          (call $new_static_string (i32.const {{ data(str="fstat") }}) (i32.const {{ data_length() }}))
+          (local.get $path))
+      )
+    )
+    (call $free (local.get $result))
+
+    (call $write_file
+      (local.get $descriptor)
+      (call $value_bytes (call $get_argument (local.get $arguments) (i32.const 1)))
+      (local.get $size)
+      (local.get $path))
+    (call $drop-descriptor (local.get $descriptor))
+    (ref.null any)
+  )
+
+  (func $fs-existsSync
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (local $path anyref)
+    (local $relative anyref)
+    (local $ptr i32)
+    (local $result i32)
+    (local $exists i32)
+
+    (local.set $path (call $to_string (call $get_argument (local.get $arguments) (i32.const 0))))
+    try
+      (call $resolve_path
+        (local.get $path)
+        (call $new_static_string (i32.const {{ data(str="stat") }}) (i32.const {{ data_length() }})))
+    catch $JSException
+      (drop)
+      (return (global.get $false))
+    end
+    (local.set $relative (global.get $resolved-path))
+
+    (local.set $result (call $malloc (i32.const 112)))
+    (local.set $ptr (call $string_to_memory (local.get $relative)))
+    (call $descriptor-stat-at
+      (global.get $resolved-directory)
+      (i32.const {{ path_symlink_follow }})
+      (local.get $ptr)
+      (call $string_length (local.get $relative))
+      (local.get $result))
+    (call $free (local.get $ptr))
+    (local.set $exists (i32.eqz (i32.load8_u (local.get $result))))
+    (call $free (local.get $result))
+    (call $new_boolean (local.get $exists))
+  )
+
+  (elem declare func $fs-readFileSync)
+  (elem declare func $fs-writeFileSync)
+  (elem declare func $fs-appendFileSync)
+  (elem declare func $fs-existsSync)
+
+  (func $install-fs-globals (param $scope (ref $Scope))
+    (local $fs (ref $Object))
+
+    (local.set $fs (call $new_object))
+    (call $add_method (local.get $fs) (i32.const {{ data(str="readFileSync") }}) (ref.func $fs-readFileSync))
+    (call $add_method (local.get $fs) (i32.const {{ data(str="writeFileSync") }}) (ref.func $fs-writeFileSync))
+    (call $add_method (local.get $fs) (i32.const {{ data(str="appendFileSync") }}) (ref.func $fs-appendFileSync))
+    (call $add_method (local.get $fs) (i32.const {{ data(str="existsSync") }}) (ref.func $fs-existsSync))
+    (call $set_variable (local.get $scope) (i32.const {{ data(str="fs") }}) (local.get $fs))
+  )
@@ -0,0 +1,1123 @@
+  ;; The `JSON` builtin
+  ;;
+  ;; JSON.parse is a recursive descent parser working directly on the UTF-8 bytes
+  ;; of the text, JSON.stringify follows SerializeJSONProperty from the spec and
+  ;; writes everything into a single $StringBuilder
+
+  (type $JSONParser (struct
+    (field $text anyref)
+    (field $position (mut i32))
+    (field $length i32)
+  ))
+
+  (type $JSONStringifier (struct
+    (field $builder (ref $StringBuilder))
+    ;; a replacer function or undefined
+    (field $replacer anyref)
+    ;; an $Array of keys to serialize or undefined
+    (field $property_list anyref)
+    (field $gap anyref)
+    (field $indent (mut i32))
+    ;; objects currently being serialized, used to detect cycles
+    (field $stack (mut (ref $AnyrefArray)))
+    (field $stack_size (mut i32))
+  ))
+
+  (func $json_syntax_error (param $parser (ref $JSONParser))
+    (local $position i32)
+    (local.set $position (struct.get $JSONParser $position (local.get $parser)))
+
+    (if (i32.ge_u (local.get $position) (struct.get $JSONParser $length (local.get $parser)))
+      (then
+        (call $throw_syntax_error
+          (call $new_static_string (i32.const {{ data(str="Unexpected end of JSON input") }}) (i32.const {{ data_length() }})))
+      )
+    )
+    (call $throw_syntax_error
+      (call $concat_strings
+        (call $new_static_string (i32.const {{ data(str="Unexpected token in JSON at position ") }}) (i32.const {{ data_length() }}))
+        (call $number_to_string (f64.convert_i32_u (local.get $position)))))
+  )
+
+  ;; the current byte or -1 at the end of the text
+  (func $json_peek (param $parser (ref $JSONParser)) (result i32)
+    (local $position i32)
+    (local.set $position (struct.get $JSONParser $position (local.get $parser)))
+
+    (if (i32.ge_u (local.get $position) (struct.get $JSONParser $length (local.get $parser)))
+      (then (return (i32.const -1)))
+    )
+    (call $string_byte_at (struct.get $JSONParser $text (local.get $parser)) (local.get $position))
+  )
+
+  (func $json_advance (param $parser (ref $JSONParser))
+    (struct.set $JSONParser $position (local.get $parser)
+      (i32.add (struct.get $JSONParser $position (local.get $parser)) (i32.const 1)))
+  )
+
+  ;; JSON only allows these four characters as whitespace
+  (func $json_skip_whitespace (param $parser (ref $JSONParser))
+    (local $char i32)
+
+    (block $break (loop $skip
+      (local.set $char (call $json_peek (local.get $parser)))
+      (br_if $break
+        (i32.eqz
+          (i32.or
+            (i32.or
+              (i32.eq (local.get $char) (i32.const 0x20))
+              (i32.eq (local.get $char) (i32.const 0x09)))
+            (i32.or
+              (i32.eq (local.get $char) (i32.const 0x0a))
+              (i32.eq (local.get $char) (i32.const 0x0d))))))
+      (call $json_advance (local.get $parser))
+      (br $skip)
+    ))
+  )
+
+  (func $json_expect (param $parser (ref $JSONParser)) (param $char i32)
+    (if (i32.ne (call $json_peek (local.get $parser)) (local.get $char))
+      (then (call $json_syntax_error (local.get $parser)))
+    )
+    (call $json_advance (local.get $parser))
+  )
+
+  ;; consumes one of the true, false or null literals
+  (func $json_expect_word (param $parser (ref $JSONParser)) (param $word anyref)
+    (local $i i32)
+    (local $length i32)
+    (local.set $length (call $string_length (local.get $word)))
+
+    (block $break (loop $compare
+      (br_if $break (i32.ge_u (local.get $i) (local.get $length)))
+      (call $json_expect (local.get $parser) (call $string_byte_at (local.get $word) (local.get $i)))
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $compare)
+    ))
+  )
+
+  ;; consumes a sequence of decimal digits and returns how many there were
+  (func $json_digits (param $parser (ref $JSONParser)) (result i32)
+    (local $char i32)
+    (local $count i32)
+
+    (block $break (loop $digits
+      (local.set $char (call $json_peek (local.get $parser)))
+      (br_if $break
+        (i32.or
+          (i32.lt_s (local.get $char) (i32.const 0x30))
+          (i32.gt_s (local.get $char) (i32.const 0x39))))
+      (call $json_advance (local.get $parser))
+      (local.set $count (i32.add (local.get $count) (i32.const 1)))
+      (br $digits)
+    ))
+
+    (local.get $count)
+  )
+
+  (func $json_parse_number (param $parser (ref $JSONParser)) (result anyref)
+    (local $start i32)
+    (local $char i32)
+
+    (local.set $start (struct.get $JSONParser $position (local.get $parser)))
+
+    (if (i32.eq (call $json_peek (local.get $parser)) (i32.const 0x2d)) ;; -
+      (then (call $json_advance (local.get $parser)))
+    )
+
+    ;; the integer part can't have leading zeros
+    (if (i32.eq (call $json_peek (local.get $parser)) (i32.const 0x30))
+      (then (call $json_advance (local.get $parser)))
+      (else
+        (if (i32.eqz (call $json_digits (local.get $parser)))
+          (then (call $json_syntax_error (local.get $parser)))
+        )
+      )
+    )
+
+    (if (i32.eq (call $json_peek (local.get $parser)) (i32.const 0x2e)) ;; .
+      (then
+        (call $json_advance (local.get $parser))
+        (if (i32.eqz (call $json_digits (local.get $parser)))
+          (then (call $json_syntax_error (local.get $parser)))
+        )
+      )
+    )
+
+    (local.set $char (call $json_peek (local.get $parser)))
+    (if (i32.or
+          (i32.eq (local.get $char) (i32.const 0x65))  ;; e
+          (i32.eq (local.get $char) (i32.const 0x45))) ;; E
+      (then
+        (call $json_advance (local.get $parser))
+        (local.set $char (call $json_peek (local.get $parser)))
+        (if (i32.or
+              (i32.eq (local.get $char) (i32.const 0x2b))  ;; +
+              (i32.eq (local.get $char) (i32.const 0x2d))) ;; -
+          (then (call $json_advance (local.get $parser)))
+        )
+        (if (i32.eqz (call $json_digits (local.get $parser)))
+          (then (call $json_syntax_error (local.get $parser)))
+        )
+      )
+    )
+
+    ;; the literal is already validated, so it's also a valid decimal literal
+    (call $parse_decimal
+      (struct.get $JSONParser $text (local.get $parser))
+      (local.get $start)
+      (struct.get $JSONParser $position (local.get $parser)))
+    (drop)
+    (call $new_number)
+  )
+
+  (func $json_parse_hex4 (param $parser (ref $JSONParser)) (result i32)
+    (local $value i32)
+    (local $char i32)
+    (local $i i32)
+
+    (block $break (loop $digits
+      (br_if $break (i32.ge_u (local.get $i) (i32.const 4)))
+
+      (local.set $char (call $json_peek (local.get $parser)))
+      (if (i32.and
+            (i32.ge_s (local.get $char) (i32.const 0x30))
+            (i32.le_s (local.get $char) (i32.const 0x39)))
+        (then (local.set $char (i32.sub (local.get $char) (i32.const 0x30))))
+        (else
+          ;; lowercase the letter, anything that's not a-f will fail the check
+          (local.set $char (i32.or (local.get $char) (i32.const 0x20)))
+          (if (i32.and
+                (i32.ge_s (local.get $char) (i32.const 0x61))
+                (i32.le_s (local.get $char) (i32.const 0x66)))
+            (then (local.set $char (i32.sub (local.get $char) (i32.const 0x57))))
+            (else (call $json_syntax_error (local.get $parser)))
+          )
+        )
+      )
+
+      (local.set $value (i32.or (i32.shl (local.get $value) (i32.const 4)) (local.get $char)))
+      (call $json_advance (local.get $parser))
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $digits)
+    ))
+
+    (local.get $value)
+  )
+
+  ;; parses a \uXXXX escape (after the backslash), joining surrogate pairs
+  (func $json_parse_unicode_escape (param $parser (ref $JSONParser)) (result i32)
+    (local $high i32)
+    (local $low i32)
+    (local $position i32)
+
+    (call $json_expect (local.get $parser) (i32.const 0x75)) ;; u
+    (local.set $high (call $json_parse_hex4 (local.get $parser)))
+    (if (i32.ne (i32.and (local.get $high) (i32.const 0xfc00)) (i32.const 0xd800))
+      (then (return (local.get $high)))
+    )
+
+    ;; a high surrogate only forms a pair if a low surrogate escape follows,
+    ;; otherwise it stays a lone surrogate
+    (local.set $position (struct.get $JSONParser $position (local.get $parser)))
+    (if (i32.ne (call $json_peek (local.get $parser)) (i32.const 0x5c)) ;; \
+      (then (return (local.get $high)))
+    )
+    (call $json_advance (local.get $parser))
+    (if (i32.ne (call $json_peek (local.get $parser)) (i32.const 0x75)) ;; u
+      (then
+        (struct.set $JSONParser $position (local.get $parser) (local.get $position))
+        (return (local.get $high))
+      )
+    )
+    (call $json_advance (local.get $parser))
+    (local.set $low (call $json_parse_hex4 (local.get $parser)))
+    (if (i32.ne (i32.and (local.get $low) (i32.const 0xfc00)) (i32.const 0xdc00))
+      (then
+        (struct.set $JSONParser $position (local.get $parser) (local.get $position))
+        (return (local.get $high))
+      )
+    )
+
+    (i32.add
+      (i32.const 0x10000)
+      (i32.or
+        (i32.shl (i32.and (local.get $high) (i32.const 0x3ff)) (i32.const 10))
+        (i32.and (local.get $low) (i32.const 0x3ff))))
+  )
+
+  (func $json_parse_string (param $parser (ref $JSONParser)) (result (ref $String))
+    (local $builder (ref $StringBuilder))
+    (local $char i32)
+
+    (call $json_expect (local.get $parser) (i32.const 0x22)) ;; "
+    (local.set $builder (call $new_string_builder))
+
+    (block $break (loop $chars
+      (local.set $char (call $json_peek (local.get $parser)))
+
+      (if (i32.eq (local.get $char) (i32.const 0x22)) ;; "
+        (then
+          (call $json_advance (local.get $parser))
+          (br $break)
+        )
+      )
+
+      ;; control characters (and the end of the text) have to be escaped
+      (if (i32.lt_s (local.get $char) (i32.const 0x20))
+        (then (call $json_syntax_error (local.get $parser)))
+      )
+
+      (if (i32.ne (local.get $char) (i32.const 0x5c)) ;; \
+        (then
+          (call $builder_append_byte (local.get $builder) (local.get $char))
+          (call $json_advance (local.get $parser))
+          (br $chars)
+        )
+      )
+
+      (call $json_advance (local.get $parser))
+      (local.set $char (call $json_peek (local.get $parser)))
+      (block $escaped
+        (if (i32.or
+              (i32.or
+                (i32.eq (local.get $char) (i32.const 0x22))  ;; "
+                (i32.eq (local.get $char) (i32.const 0x5c))) ;; \
+              (i32.eq (local.get $char) (i32.const 0x2f)))   ;; /
+          (then (br $escaped))
+        )
+        (if (i32.eq (local.get $char) (i32.const 0x62)) ;; b
+          (then (local.set $char (i32.const 0x08)) (br $escaped))
+        )
+        (if (i32.eq (local.get $char) (i32.const 0x66)) ;; f
+          (then (local.set $char (i32.const 0x0c)) (br $escaped))
+        )
+        (if (i32.eq (local.get $char) (i32.const 0x6e)) ;; n
+          (then (local.set $char (i32.const 0x0a)) (br $escaped))
+        )
+        (if (i32.eq (local.get $char) (i32.const 0x72)) ;; r
+          (then (local.set $char (i32.const 0x0d)) (br $escaped))
+        )
+        (if (i32.eq (local.get $char) (i32.const 0x74)) ;; t
+          (then (local.set $char (i32.const 0x09)) (br $escaped))
+        )
+        (if (i32.eq (local.get $char) (i32.const 0x75)) ;; u
+          (then
+            (call $builder_append_code_point
+              (local.get $builder)
+              (call $json_parse_unicode_escape (local.get $parser)))
+            (br $chars)
+          )
+        )
+        (call $json_syntax_error (local.get $parser))
+      )
+
+      (call $builder_append_byte (local.get $builder) (local.get $char))
+      (call $json_advance (local.get $parser))
+      (br $chars)
+    ))
+
+    (call $builder_to_string (local.get $builder))
+  )
+
+  (func $json_parse_array (param $parser (ref $JSONParser)) (result anyref)
+    (local $elements (ref $AnyrefArray))
+    (local $new_elements (ref $AnyrefArray))
+    (local $count i32)
+    (local $char i32)
+
+    (call $json_expect (local.get $parser) (i32.const 0x5b)) ;; [
+    (local.set $elements (array.new $AnyrefArray (ref.null any) (i32.const 8)))
+
+    (call $json_skip_whitespace (local.get $parser))
+    (if (i32.eq (call $json_peek (local.get $parser)) (i32.const 0x5d)) ;; ]
+      (then
+        (call $json_advance (local.get $parser))
+        (return (call $new_array (i32.const 0)))
+      )
+    )
+
+    (block $break (loop $elements
+      (if (i32.eq (local.get $count) (array.len (local.get $elements)))
+        (then
+          (local.set $new_elements
+            (array.new $AnyrefArray (ref.null any) (i32.shl (local.get $count) (i32.const 1))))
+          (array.copy $AnyrefArray $AnyrefArray
+            (local.get $new_elements)
+            (i32.const 0)
+            (local.get $elements)
+            (i32.const 0)
+            (local.get $count))
+          (local.set $elements (local.get $new_elements))
+        )
+      )
+
+      (array.set $AnyrefArray
+        (local.get $elements)
+        (local.get $count)
+        (call $json_parse_value (local.get $parser)))
+      (local.set $count (i32.add (local.get $count) (i32.const 1)))
+
+      (call $json_skip_whitespace (local.get $parser))
+      (local.set $char (call $json_peek (local.get $parser)))
+      (call $json_advance (local.get $parser))
+      (br_if $elements (i32.eq (local.get $char) (i32.const 0x2c))) ;; ,
+      (br_if $break (i32.eq (local.get $char) (i32.const 0x5d))) ;; ]
+
+      (struct.set $JSONParser $position (local.get $parser)
+        (i32.sub (struct.get $JSONParser $position (local.get $parser)) (i32.const 1)))
+      (call $json_syntax_error (local.get $parser))
+    ))
+
+    (local.set $new_elements (array.new $AnyrefArray (ref.null any) (local.get $count)))
+    (array.copy $AnyrefArray $AnyrefArray
+      (local.get $new_elements)
+      (i32.const 0)
+      (local.get $elements)
+      (i32.const 0)
+      (local.get $count))
+    (struct.new $Array (local.get $new_elements) (ref.null $HashMap) (i32.const 0))
+  )
+
+  (func $json_parse_object (param $parser (ref $JSONParser)) (result anyref)
+    (local $object (ref $Object))
+    (local $key i32)
+    (local $char i32)
+
+    (call $json_expect (local.get $parser) (i32.const 0x7b)) ;; {
+    (local.set $object (call $new_object))
+
+    (call $json_skip_whitespace (local.get $parser))
+    (if (i32.eq (call $json_peek (local.get $parser)) (i32.const 0x7d)) ;; }
+      (then
+        (call $json_advance (local.get $parser))
+        (return (local.get $object))
+      )
+    )
+
+    (block $break (loop $members
+      (call $json_skip_whitespace (local.get $parser))
+      (local.set $key (call $intern_string (call $json_parse_string (local.get $parser))))
+      (call $json_skip_whitespace (local.get $parser))
+      (call $json_expect (local.get $parser) (i32.const 0x3a)) ;; :
+
+      ;; a duplicate key overwrites the value, but keeps its original position
+      (call $set_property
+        (local.get $object)
+        (local.get $key)
+        (call $json_parse_value (local.get $parser)))
+
+      (call $json_skip_whitespace (local.get $parser))
+      (local.set $char (call $json_peek (local.get $parser)))
+      (call $json_advance (local.get $parser))
+      (br_if $members (i32.eq (local.get $char) (i32.const 0x2c))) ;; ,
+      (br_if $break (i32.eq (local.get $char) (i32.const 0x7d))) ;; }
+
+      (struct.set $JSONParser $position (local.get $parser)
+        (i32.sub (struct.get $JSONParser $position (local.get $parser)) (i32.const 1)))
+      (call $json_syntax_error (local.get $parser))
+    ))
+
+    (local.get $object)
+  )
+
+  (func $json_parse_value (param $parser (ref $JSONParser)) (result anyref)
+    (local $char i32)
+
+    (call $json_skip_whitespace (local.get $parser))
+    (local.set $char (call $json_peek (local.get $parser)))
+
+    (if (i32.eq (local.get $char) (i32.const 0x7b)) ;; {
+      (then (return (call $json_parse_object (local.get $parser))))
+    )
+    (if (i32.eq (local.get $char) (i32.const 0x5b)) ;; [
+      (then (return (call $json_parse_array (local.get $parser))))
+    )
+    (if (i32.eq (local.get $char) (i32.const 0x22)) ;; "
+      (then (return (call $json_parse_string (local.get $parser))))
+    )
+    (if (i32.or
+          (i32.eq (local.get $char) (i32.const 0x2d)) ;; -
+          (i32.and
+            (i32.ge_s (local.get $char) (i32.const 0x30))
+            (i32.le_s (local.get $char) (i32.const 0x39))))
+      (then (return (call $json_parse_number (local.get $parser))))
+    )
+    (if (i32.eq (local.get $char) (i32.const 0x74)) ;; t
+      (then
+        (call $json_expect_word (local.get $parser)
+          (call $new_static_string (i32.const {{ data(str="true") }}) (i32.const {{ data_length() }})))
+        (return (global.get $true))
+      )
+    )
+    (if (i32.eq (local.get $char) (i32.const 0x66)) ;; f
+      (then
+        (call $json_expect_word (local.get $parser)
+          (call $new_static_string (i32.const {{ data(str="false") }}) (i32.const {{ data_length() }})))
+        (return (global.get $false))
+      )
+    )
+    (if (i32.eq (local.get $char) (i32.const 0x6e)) ;; n
+      (then
+        (call $json_expect_word (local.get $parser)
+          (call $new_static_string (i32.const {{ data(str="null") }}) (i32.const {{ data_length() }})))
+        (return (global.get $null))
+      )
+    )
+
+    (call $json_syntax_error (local.get $parser))
+    (unreachable)
+  )
+
+  ;; InternalizeJSONProperty from the spec, passes every parsed value through
+  ;; the reviver, starting from the most nested ones
+  (func $json_internalize (param $holder anyref) (param $name anyref) (param $reviver anyref) (result anyref)
+    (local $value anyref)
+    (local $array (ref $Array))
+    (local $keys (ref $Array))
+    (local $key anyref)
+    (local $element anyref)
+    (local $properties (ref null $HashMap))
+    (local $length i32)
+    (local $i i32)
+
+    (local.set $value (call $get_computed_property (local.get $holder) (local.get $name)))
+
+    (if (ref.test (ref $Array) (local.get $value))
+      (then
+        (local.set $array (ref.cast (ref $Array) (local.get $value)))
+        (local.set $length (array.len (struct.get $Array $array (local.get $array))))
+        (block $break (loop $elements
+          (br_if $break (i32.ge_u (local.get $i) (local.get $length)))
+          (call $array_set
+            (local.get $array)
+            (local.get $i)
+            (call $json_internalize
+              (local.get $array)
+              (call $number_to_string (f64.convert_i32_u (local.get $i)))
+              (local.get $reviver)))
+          (local.set $i (i32.add (local.get $i) (i32.const 1)))
+          (br $elements)
+        ))
+      )
+      (else
+        (if (call $is_object (local.get $value))
+          (then
+            (local.set $keys (call $own_property_entries (local.get $value) (i32.const 0)))
+            (local.set $length (array.len (struct.get $Array $array (local.get $keys))))
+            (block $break (loop $members
+              (br_if $break (i32.ge_u (local.get $i) (local.get $length)))
+              (local.set $key (call $array_get (local.get $keys) (local.get $i)))
+              (local.set $element
+                (call $json_internalize (local.get $value) (local.get $key) (local.get $reviver)))
+
+              ;; returning undefined from the reviver removes the property
+              (if (ref.is_null (local.get $element))
+                (then
+                  (local.set $properties (call $get_own_properties (local.get $value)))
+                  (if (i32.eqz (ref.is_null (local.get $properties)))
+                    (then
+                      (drop
+                        (call $hashmap_delete
+                          (ref.as_non_null (local.get $properties))
+                          (call $intern_string (local.get $key))))
+                    )
+                  )
+                )
+                (else
+                  (call $set_computed_property (local.get $value) (local.get $key) (local.get $element))
+                )
+              )
+
+              (local.set $i (i32.add (local.get $i) (i32.const 1)))
+              (br $members)
+            ))
+          )
+        )
+      )
+    )
+
+    (call $call_function
+      (local.get $reviver)
+      (local.get $holder)
+      (array.new_fixed $JSArgs 2 (local.get $name) (local.get $value)))
+  )
+
+  (func $JSON-parse
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (local $text anyref)
+    (local $parser (ref $JSONParser))
+    (local $result anyref)
+    (local $reviver anyref)
+    (local $holder (ref $Object))
+
+    (local.set $text (call $to_string (call $get_argument (local.get $arguments) (i32.const 0))))
+    (local.set $parser
+      (struct.new $JSONParser
+        (local.get $text)
+        (i32.const 0)
+        (call $string_length (local.get $text))))
+
+    (local.set $result (call $json_parse_value (local.get $parser)))
+    (call $json_skip_whitespace (local.get $parser))
+    (if (i32.ne (call $json_peek (local.get $parser)) (i32.const -1))
+      (then (call $json_syntax_error (local.get $parser)))
+    )
+
+    (local.set $reviver (call $get_argument (local.get $arguments) (i32.const 1)))
+    (if (i32.eqz (ref.test (ref $Function) (local.get $reviver)))
+      (then (return (local.get $result)))
+    )
+
+    (local.set $holder (call $new_object))
+    (call $set_property (local.get $holder) (i32.const {{ data(str="") }}) (local.get $result))
+    (call $json_internalize
+      (local.get $holder)
+      (call $new_static_string (i32.const {{ data(str="") }}) (i32.const {{ data_length() }}))
+      (local.get $reviver))
+  )
+
+  (func $json_append_hex4 (param $builder (ref $StringBuilder)) (param $value i32)
+    (local $shift i32)
+    (local $digit i32)
+
+    (local.set $shift (i32.const 12))
+    (block $break (loop $digits
+      (br_if $break (i32.lt_s (local.get $shift) (i32.const 0)))
+      (local.set $digit
+        (i32.and (i32.shr_u (local.get $value) (local.get $shift)) (i32.const 0xf)))
+      (call $builder_append_byte (local.get $builder)
+        (i32.add
+          (local.get $digit)
+          (select
+            (i32.const 0x30)
+            (i32.const 0x57)
+            (i32.lt_u (local.get $digit) (i32.const 10)))))
+      (local.set $shift (i32.sub (local.get $shift) (i32.const 4)))
+      (br $digits)
+    ))
+  )
+
+  ;; QuoteJSONString from the spec
+  (func $json_quote (param $builder (ref $StringBuilder)) (param $str anyref)
+    (local $length i32)
+    (local $char i32)
+    (local $next i32)
+    (local $i i32)
+
+    (local.set $length (call $string_length (local.get $str)))
+    (call $builder_append_byte (local.get $builder) (i32.const 0x22)) ;; "
+
+    (block $break (loop $chars
+      (br_if $break (i32.ge_u (local.get $i) (local.get $length)))
+      (local.set $char (call $string_byte_at (local.get $str) (local.get $i)))
+
+      (block $next_char
+        (block $escape
+          (if (i32.eq (local.get $char) (i32.const 0x08))
+            (then (local.set $char (i32.const 0x62)) (br $escape))) ;; \b
+          (if (i32.eq (local.get $char) (i32.const 0x09))
+            (then (local.set $char (i32.const 0x74)) (br $escape))) ;; \t
+          (if (i32.eq (local.get $char) (i32.const 0x0a))
+            (then (local.set $char (i32.const 0x6e)) (br $escape))) ;; \n
+          (if (i32.eq (local.get $char) (i32.const 0x0c))
+            (then (local.set $char (i32.const 0x66)) (br $escape))) ;; \f
+          (if (i32.eq (local.get $char) (i32.const 0x0d))
+            (then (local.set $char (i32.const 0x72)) (br $escape))) ;; \r
+          (br_if $escape
+            (i32.or
+              (i32.eq (local.get $char) (i32.const 0x22))   ;; "
+              (i32.eq (local.get $char) (i32.const 0x5c)))) ;; \
+
+          ;; the remaining control characters
+          (if (i32.lt_u (local.get $char) (i32.const 0x20))
+            (then
+              (call $builder_append_byte (local.get $builder) (i32.const 0x5c))
+              (call $builder_append_byte (local.get $builder) (i32.const 0x75))
+              (call $json_append_hex4 (local.get $builder) (local.get $char))
+              (br $next_char)
+            )
+          )
+
+          ;; lone surrogates (0xed 0xa0-0xbf 0x80-0xbf) are written as escapes,
+          ;; so the output is always valid UTF-8
+          (if (i32.and
+                (i32.eq (local.get $char) (i32.const 0xed))
+                (i32.lt_u (i32.add (local.get $i) (i32.const 2)) (local.get $length)))
+            (then
+              (local.set $next (call $string_byte_at (local.get $str) (i32.add (local.get $i) (i32.const 1))))
+              (if (i32.ge_u (local.get $next) (i32.const 0xa0))
+                (then
+                  (call $builder_append_byte (local.get $builder) (i32.const 0x5c))
+                  (call $builder_append_byte (local.get $builder) (i32.const 0x75))
+                  (call $json_append_hex4 (local.get $builder)
+                    (i32.or
+                      (i32.or
+                        (i32.const 0xd000)
+                        (i32.shl (i32.and (local.get $next) (i32.const 0x3f)) (i32.const 6)))
+                      (i32.and
+                        (call $string_byte_at (local.get $str) (i32.add (local.get $i) (i32.const 2)))
+                        (i32.const 0x3f))))
+                  (local.set $i (i32.add (local.get $i) (i32.const 2)))
+                  (br $next_char)
+                )
+              )
+            )
+          )
+
+          (call $builder_append_byte (local.get $builder) (local.get $char))
+          (br $next_char)
+        )
+        (call $builder_append_byte (local.get $builder) (i32.const 0x5c))
+        (call $builder_append_byte (local.get $builder) (local.get $char))
+      )
+
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $chars)
+    ))
+
+    (call $builder_append_byte (local.get $builder) (i32.const 0x22)) ;; "
+  )
+
+  ;; the part of SerializeJSONProperty that gives objects (through toJSON) and
+  ;; the replacer function a chance to change the value
+  (func $json_transform
+    (param $state (ref $JSONStringifier))
+    (param $holder anyref)
+    (param $key anyref)
+    (param $value anyref)
+    (result anyref)
+    (local $to_json anyref)
+    (local $replacer anyref)
+
+    (if (i32.or (call $is_object (local.get $value)) (call $is_bigint (local.get $value)))
+      (then
+        (local.set $to_json (call $get_property (local.get $value) (i32.const {{ data(str="toJSON") }})))
+        (if (ref.test (ref $Function) (local.get $to_json))
+          (then
+            (local.set $value
+              (call $call_function
+                (local.get $to_json)
+                (local.get $value)
+                (array.new_fixed $JSArgs 1 (call $to_string (local.get $key)))))
+          )
+        )
+      )
+    )
+
+    (local.set $replacer (struct.get $JSONStringifier $replacer (local.get $state)))
+    (if (ref.test (ref $Function) (local.get $replacer))
+      (then
+        (local.set $value
+          (call $call_function
+            (local.get $replacer)
+            (local.get $holder)
+            (array.new_fixed $JSArgs 2 (call $to_string (local.get $key)) (local.get $value))))
+      )
+    )
+
+    (local.get $value)
+  )
+
+  ;; undefined, functions and symbols are skipped in objects and written as
+  ;; null in arrays
+  (func $json_is_serializable (param $value anyref) (result i32)
+    (i32.eqz
+      (i32.or
+        (i32.or
+          (ref.is_null (local.get $value))
+          (ref.test (ref $Function) (local.get $value)))
+        (call $is_symbol (local.get $value))))
+  )
+
+  (func $json_newline (param $state (ref $JSONStringifier))
+    (local $builder (ref $StringBuilder))
+    (local $gap anyref)
+    (local $i i32)
+
+    (local.set $gap (struct.get $JSONStringifier $gap (local.get $state)))
+    (if (i32.eqz (call $string_length (local.get $gap)))
+      (then (return))
+    )
+
+    (local.set $builder (struct.get $JSONStringifier $builder (local.get $state)))
+    (call $builder_append_byte (local.get $builder) (i32.const 0x0a))
+    (block $break (loop $indent
+      (br_if $break (i32.ge_u (local.get $i) (struct.get $JSONStringifier $indent (local.get $state))))
+      (call $builder_append_string (local.get $builder) (local.get $gap))
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $indent)
+    ))
+  )
+
+  ;; pushes an object on the stack of objects being serialized, throwing if it's
+  ;; already there
+  (func $json_enter (param $state (ref $JSONStringifier)) (param $value anyref)
+    (local $stack (ref $AnyrefArray))
+    (local $new_stack (ref $AnyrefArray))
+    (local $size i32)
+    (local $i i32)
+
+    (local.set $stack (struct.get $JSONStringifier $stack (local.get $state)))
+    (local.set $size (struct.get $JSONStringifier $stack_size (local.get $state)))
+
+    (block $break (loop $search
+      (br_if $break (i32.ge_u (local.get $i) (local.get $size)))
+      (if (ref.eq
+            (ref.cast eqref (array.get $AnyrefArray (local.get $stack) (local.get $i)))
+            (ref.cast eqref (local.get $value)))
+        (then
+          (call $throw_type_error
+            (call $new_static_string (i32.const {{ data(str="Converting circular structure to JSON") }}) (i32.const {{ data_length() }})))
+        )
+      )
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $search)
+    ))
+
+    (if (i32.eq (local.get $size) (array.len (local.get $stack)))
+      (then
+        (local.set $new_stack
+          (array.new $AnyrefArray (ref.null any) (i32.shl (local.get $size) (i32.const 1))))
+        (array.copy $AnyrefArray $AnyrefArray
+          (local.get $new_stack)
+          (i32.const 0)
+          (local.get $stack)
+          (i32.const 0)
+          (local.get $size))
+        (struct.set $JSONStringifier $stack (local.get $state) (local.get $new_stack))
+        (local.set $stack (local.get $new_stack))
+      )
+    )
+
+    (array.set $AnyrefArray (local.get $stack) (local.get $size) (local.get $value))
+    (struct.set $JSONStringifier $stack_size (local.get $state) (i32.add (local.get $size) (i32.const 1)))
+    (struct.set $JSONStringifier $indent (local.get $state)
+      (i32.add (struct.get $JSONStringifier $indent (local.get $state)) (i32.const 1)))
+  )
+
+  (func $json_exit (param $state (ref $JSONStringifier))
+    (struct.set $JSONStringifier $stack_size (local.get $state)
+      (i32.sub (struct.get $JSONStringifier $stack_size (local.get $state)) (i32.const 1)))
+    (struct.set $JSONStringifier $indent (local.get $state)
+      (i32.sub (struct.get $JSONStringifier $indent (local.get $state)) (i32.const 1)))
+  )
+
+  ;; SerializeJSONObject from the spec
+  (func $json_write_object (param $state (ref $JSONStringifier)) (param $value anyref)
+    (local $builder (ref $StringBuilder))
+    (local $keys (ref $Array))
+    (local $key anyref)
+    (local $element anyref)
+    (local $length i32)
+    (local $count i32)
+    (local $i i32)
+
+    (local.set $builder (struct.get $JSONStringifier $builder (local.get $state)))
+    (call $json_enter (local.get $state) (local.get $value))
+
+    (local.set $keys
+      (if (result (ref $Array))
+          (ref.test (ref $Array) (struct.get $JSONStringifier $property_list (local.get $state)))
+        (then
+          (ref.cast (ref $Array) (struct.get $JSONStringifier $property_list (local.get $state))))
+        (else
+          (call $own_property_entries (local.get $value) (i32.const 0)))))
+    (local.set $length (array.len (struct.get $Array $array (local.get $keys))))
+
+    (call $builder_append_byte (local.get $builder) (i32.const 0x7b)) ;; {
+    (block $break (loop $members
+      (br_if $break (i32.ge_u (local.get $i) (local.get $length)))
+      (local.set $key (call $array_get (local.get $keys) (local.get $i)))
+      (local.set $element
+        (call $json_transform
+          (local.get $state)
+          (local.get $value)
+          (local.get $key)
+          (call $get_computed_property (local.get $value) (local.get $key))))
+
+      (if (call $json_is_serializable (local.get $element))
+        (then
+          (if (local.get $count)
+            (then (call $builder_append_byte (local.get $builder) (i32.const 0x2c))) ;; ,
+          )
+          (call $json_newline (local.get $state))
+          (call $json_quote (local.get $builder) (local.get $key))
+          (call $builder_append_byte (local.get $builder) (i32.const 0x3a)) ;; :
+          (if (call $string_length (struct.get $JSONStringifier $gap (local.get $state)))
+            (then (call $builder_append_byte (local.get $builder) (i32.const 0x20)))
+          )
+          (call $json_write_value (local.get $state) (local.get $element))
+          (local.set $count (i32.add (local.get $count) (i32.const 1)))
+        )
+      )
+
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $members)
+    ))
+
+    (call $json_exit (local.get $state))
+    (if (local.get $count)
+      (then (call $json_newline (local.get $state)))
+    )
+    (call $builder_append_byte (local.get $builder) (i32.const 0x7d)) ;; }
+  )
+
+  ;; SerializeJSONArray from the spec
+  (func $json_write_array (param $state (ref $JSONStringifier)) (param $array (ref $Array))
+    (local $builder (ref $StringBuilder))
+    (local $element anyref)
+    (local $length i32)
+    (local $i i32)
+
+    (local.set $builder (struct.get $JSONStringifier $builder (local.get $state)))
+    (call $json_enter (local.get $state) (local.get $array))
+    (local.set $length (array.len (struct.get $Array $array (local.get $array))))
+
+    (call $builder_append_byte (local.get $builder) (i32.const 0x5b)) ;; [
+    (block $break (loop $elements
+      (br_if $break (i32.ge_u (local.get $i) (local.get $length)))
+
+      ;; the index is only turned into a string if toJSON or the replacer needs it
+      (local.set $element
+        (call $json_transform
+          (local.get $state)
+          (local.get $array)
+          (ref.i31 (local.get $i))
+          (call $array_get (local.get $array) (local.get $i))))
+
+      (if (local.get $i)
+        (then (call $builder_append_byte (local.get $builder) (i32.const 0x2c))) ;; ,
+      )
+      (call $json_newline (local.get $state))
+      (if (call $json_is_serializable (local.get $element))
+        (then (call $json_write_value (local.get $state) (local.get $element)))
+        (else
+          (call $builder_append_string (local.get $builder)
+            (call $new_static_string (i32.const {{ data(str="null") }}) (i32.const {{ data_length() }})))
+        )
+      )
+
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $elements)
+    ))
+
+    (call $json_exit (local.get $state))
+    (if (local.get $length)
+      (then (call $json_newline (local.get $state)))
+    )
+    (call $builder_append_byte (local.get $builder) (i32.const 0x5d)) ;; ]
+  )
+
+  ;; writes a value that already went through $json_transform and is serializable
+  (func $json_write_value (param $state (ref $JSONStringifier)) (param $value anyref)
+    (local $builder (ref $StringBuilder))
+    (local $number f64)
+
+    (local.set $builder (struct.get $JSONStringifier $builder (local.get $state)))
+
+    (if (ref.test (ref $Null) (local.get $value))
+      (then
+        (call $builder_append_string (local.get $builder)
+          (call $new_static_string (i32.const {{ data(str="null") }}) (i32.const {{ data_length() }})))
+        (return)
+      )
+    )
+
+    (if (ref.test (ref $Boolean) (local.get $value))
+      (then
+        (call $builder_append_string (local.get $builder) (call $to_string (local.get $value)))
+        (return)
+      )
+    )
+
+    (if (call $is_string (local.get $value))
+      (then
+        (call $json_quote (local.get $builder) (local.get $value))
+        (return)
+      )
+    )
+
+    (if (call $is_number (local.get $value))
+      (then
+        (local.set $number (call $number_value (local.get $value)))
+        ;; NaN and infinities can't be represented in JSON
+        (if (f64.eq (f64.sub (local.get $number) (local.get $number)) (f64.const 0))
+          (then
+            (call $builder_append_string (local.get $builder) (call $number_to_string (local.get $number)))
+          )
+          (else
+            (call $builder_append_string (local.get $builder)
+              (call $new_static_string (i32.const {{ data(str="null") }}) (i32.const {{ data_length() }})))
+          )
+        )
+        (return)
+      )
+    )
+
+    (if (call $is_bigint (local.get $value))
+      (then
+        (call $throw_type_error
+          (call $new_static_string (i32.const {{ data(str="Do not know how to serialize a BigInt") }}) (i32.const {{ data_length() }})))
+      )
+    )
+
+    (if (ref.test (ref $Array) (local.get $value))
+      (then
+        (call $json_write_array (local.get $state) (ref.cast (ref $Array) (local.get $value)))
+        (return)
+      )
+    )
+
+    (call $json_write_object (local.get $state) (local.get $value))
+  )
+
+  ;; turns an array replacer into a list of unique keys, only strings and
+  ;; numbers are used
+  (func $json_property_list (param $replacer (ref $Array)) (result (ref $Array))
+    (local $list (ref $Array))
+    (local $item anyref)
+    (local $length i32)
+    (local $count i32)
+    (local $i i32)
+    (local $j i32)
+
+    (local.set $length (array.len (struct.get $Array $array (local.get $replacer))))
+    (local.set $list (call $new_array (i32.const 0)))
+
+    (block $break (loop $items
+      (br_if $break (i32.ge_u (local.get $i) (local.get $length)))
+      (local.set $item (call $array_get (local.get $replacer) (local.get $i)))
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+
+      (br_if $items
+        (i32.eqz
+          (i32.or
+            (call $is_string (local.get $item))
+            (call $is_number (local.get $item)))))
+      (local.set $item (call $to_string (local.get $item)))
+
+      (local.set $j (i32.const 0))
+      (block $unique (loop $search
+        (br_if $unique (i32.ge_u (local.get $j) (local.get $count)))
+        (br_if $items
+          (call $string_equals (call $array_get (local.get $list) (local.get $j)) (local.get $item)))
+        (local.set $j (i32.add (local.get $j) (i32.const 1)))
+        (br $search)
+      ))
+
+      (call $array_set (local.get $list) (local.get $count) (local.get $item))
+      (local.set $count (i32.add (local.get $count) (i32.const 1)))
+      (br $items)
+    ))
+
+    (local.get $list)
+  )
+
+  ;; the indentation string, either a number of spaces or the first 10
+  ;; characters of a string
+  (func $json_gap (param $space anyref) (result anyref)
+    (local $builder (ref $StringBuilder))
+    (local $count i32)
+    (local $i i32)
+
+    (local.set $builder (call $new_string_builder))
+
+    (if (call $is_number (local.get $space))
+      (then
+        (local.set $count
+          (i32.trunc_sat_f64_s
+            (f64.min (f64.const 10) (call $to_integer_or_infinity (local.get $space)))))
+        (block $break (loop $spaces
+          (br_if $break (i32.ge_s (local.get $i) (local.get $count)))
+          (call $builder_append_byte (local.get $builder) (i32.const 0x20))
+          (local.set $i (i32.add (local.get $i) (i32.const 1)))
+          (br $spaces)
+        ))
+      )
+    )
+
+    (if (call $is_string (local.get $space))
+      (then
+        (if (i32.le_u (call $string_length (local.get $space)) (i32.const 10))
+          (then (return (local.get $space)))
+        )
+        (block $break (loop $chars
+          (br_if $break (i32.ge_u (local.get $i) (i32.const 10)))
+          (call $builder_append_byte (local.get $builder)
+            (call $string_byte_at (local.get $space) (local.get $i)))
+          (local.set $i (i32.add (local.get $i) (i32.const 1)))
+          (br $chars)
+        ))
+      )
+    )
+
+    (call $builder_to_string (local.get $builder))
+  )
+
+  (func $JSON-stringify
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (local $value anyref)
+    (local $replacer anyref)
+    (local $property_list anyref)
+    (local $state (ref $JSONStringifier))
+    (local $wrapper (ref $Object))
+    (local $empty anyref)
+
+    (local.set $value (call $get_argument (local.get $arguments) (i32.const 0)))
+    (local.set $replacer (call $get_argument (local.get $arguments) (i32.const 1)))
+
+    (if (ref.test (ref $Array) (local.get $replacer))
+      (then
+        (local.set $property_list
+          (call $json_property_list (ref.cast (ref $Array) (local.get $replacer))))
+      )
+    )
+
+    (local.set $state
+      (struct.new $JSONStringifier
+        (call $new_string_builder)
+        (local.get $replacer)
+        (local.get $property_list)
+        (call $json_gap (call $get_argument (local.get $arguments) (i32.const 2)))
+        (i32.const 0)
+        (array.new $AnyrefArray (ref.null any) (i32.const 8))
+        (i32.const 0)))
+
+    (local.set $empty
+      (call $new_static_string (i32.const {{ data(str="") }}) (i32.const {{ data_length() }})))
+    (local.set $wrapper (call $new_object))
+    (call $set_property (local.get $wrapper) (i32.const {{ data(str="") }}) (local.get $value))
+
+    (local.set $value
+      (call $json_transform (local.get $state) (local.get $wrapper) (local.get $empty) (local.get $value)))
+    (if (i32.eqz (call $json_is_serializable (local.get $value)))
+      (then (return (ref.null any)))
+    )
+
+    (call $json_write_value (local.get $state) (local.get $value))
+    (call $builder_to_string (struct.get $JSONStringifier $builder (local.get $state)))
+  )
+
+  (elem declare func $JSON-parse)
+  (elem declare func $JSON-stringify)
+
+  (func $create-json-object (result (ref $Object))
+    (local $json (ref $Object))
+    (local.set $json (call $new_object))
+
+    (call $add_method (local.get $json) (i32.const {{ data(str="parse") }}) (ref.func $JSON-parse))
+    (call $add_method (local.get $json) (i32.const {{ data(str="stringify") }}) (ref.func $JSON-stringify))
+    (call $set_to_string_tag (local.get $json)
+      (call $new_static_string (i32.const {{ data(str="JSON") }}) (i32.const {{ data_length() }})))
+
+    (local.get $json)
+  )
@@ -0,0 +1,2301 @@
+  ;; The `Math` builtin
+  ;;
+  ;; Most of the functions are ports of fdlibm (the same library V8 uses), so the
+  ;; results should match other engines in most cases
+
+  ;; the state of Math.random, set on first use
+  (global $random-seeded (mut i32) (i32.const 0))
+  (global $random-state0 (mut i64) (i64.const 0))
+  (global $random-state1 (mut i64) (i64.const 0))
+
+  (func $f64_high (param $x f64) (result i32)
+    (i32.wrap_i64 (i64.shr_u (i64.reinterpret_f64 (local.get $x)) (i64.const 32)))
+  )
+
+  (func $f64_low (param $x f64) (result i32)
+    (i32.wrap_i64 (i64.reinterpret_f64 (local.get $x)))
+  )
+
+  (func $f64_from_words (param $high i32) (param $low i32) (result f64)
+    (f64.reinterpret_i64
+      (i64.or
+        (i64.shl (i64.extend_i32_u (local.get $high)) (i64.const 32))
+        (i64.extend_i32_u (local.get $low))))
+  )
+
+  (func $f64_clear_low (param $x f64) (result f64)
+    (call $f64_from_words (call $f64_high (local.get $x)) (i32.const 0))
+  )
+
+  (func $math_abs (param $x f64) (result f64)
+    (f64.abs (local.get $x))
+  )
+
+  (func $math_ceil (param $x f64) (result f64)
+    (f64.ceil (local.get $x))
+  )
+
+  (func $math_floor (param $x f64) (result f64)
+    (f64.floor (local.get $x))
+  )
+
+  (func $math_sqrt (param $x f64) (result f64)
+    (f64.sqrt (local.get $x))
+  )
+
+  (func $math_trunc (param $x f64) (result f64)
+    (f64.trunc (local.get $x))
+  )
+
+  (func $math_fround (param $x f64) (result f64)
+    (f64.promote_f32 (f32.demote_f64 (local.get $x)))
+  )
+
+  (func $math_clz32 (param $x f64) (result f64)
+    (f64.convert_i32_u (i32.clz (call $f64_to_int32 (local.get $x))))
+  )
+
+  (func $math_imul (param $x f64) (param $y f64) (result f64)
+    (f64.convert_i32_s
+      (i32.mul (call $f64_to_int32 (local.get $x)) (call $f64_to_int32 (local.get $y))))
+  )
+
+  (func $math_sign (param $x f64) (result f64)
+    ;; NaN, 0 and -0 are returned as they are
+    (if (i32.or
+          (f64.ne (local.get $x) (local.get $x))
+          (f64.eq (local.get $x) (f64.const 0)))
+      (then (return (local.get $x)))
+    )
+    (f64.copysign (f64.const 1) (local.get $x))
+  )
+
+  (func $math_round (param $x f64) (result f64)
+    (local $floor f64)
+
+    (if (i32.or
+          (f64.ne (f64.sub (local.get $x) (local.get $x)) (f64.const 0))
+          (f64.eq (local.get $x) (f64.const 0)))
+      (then (return (local.get $x)))
+    )
+    ;; the sign of zero has to be preserved
+    (if (i32.and (f64.lt (local.get $x) (f64.const 0)) (f64.ge (local.get $x) (f64.const -0.5)))
+      (then (return (f64.const -0)))
+    )
+
+    (local.set $floor (f64.floor (local.get $x)))
+    (if (f64.ge (f64.sub (local.get $x) (local.get $floor)) (f64.const 0.5))
+      (then (return (f64.add (local.get $floor) (f64.const 1))))
+    )
+    (local.get $floor)
+  )
+
+  ;; e_exp.c from fdlibm
+  (func $math_exp (param $x f64) (result f64)
+    (local $hx i32)
+    (local $k i32)
+    (local $hi f64)
+    (local $lo f64)
+    (local $t f64)
+    (local $c f64)
+    (local $y f64)
+
+    (if (f64.ne (local.get $x) (local.get $x))
+      (then (return (local.get $x)))
+    )
+    (if (f64.gt (local.get $x) (f64.const 7.09782712893383973096e+02))
+      (then (return (f64.const inf)))
+    )
+    (if (f64.lt (local.get $x) (f64.const -7.45133219101941108420e+02))
+      (then (return (f64.const 0)))
+    )
+
+    (local.set $hx (i32.and (call $f64_high (local.get $x)) (i32.const 0x7fffffff)))
+
+    (if (i32.gt_u (local.get $hx) (i32.const 0x3fd62e42))
+      (then
+        ;; the computation below gets the last bit of e wrong, V8 has the same
+        ;; special case
+        (if (f64.eq (local.get $x) (f64.const 1))
+          (then (return (f64.const 2.718281828459045)))
+        )
+
+        ;; |x| > 0.5 ln2, reduce x to hi - lo, where |hi - lo| <= 0.5 ln2
+        (if (i32.lt_u (local.get $hx) (i32.const 0x3ff0a2b2))
+          (then
+            (local.set $k (select (i32.const -1) (i32.const 1) (f64.lt (local.get $x) (f64.const 0))))
+          )
+          (else
+            (local.set $k
+              (i32.trunc_f64_s
+                (f64.add
+                  (f64.mul (f64.const 1.44269504088896338700e+00) (local.get $x))
+                  (f64.copysign (f64.const 0.5) (local.get $x)))))
+          )
+        )
+        (local.set $hi
+          (f64.sub
+            (local.get $x)
+            (f64.mul (f64.convert_i32_s (local.get $k)) (f64.const 6.93147180369123816490e-01))))
+        (local.set $lo (f64.mul (f64.convert_i32_s (local.get $k)) (f64.const 1.90821492927058770002e-10)))
+        (local.set $x (f64.sub (local.get $hi) (local.get $lo)))
+      )
+      (else
+        (if (i32.lt_u (local.get $hx) (i32.const 0x3e300000))
+          (then (return (f64.add (f64.const 1) (local.get $x))))
+        )
+      )
+    )
+
+    (local.set $t (f64.mul (local.get $x) (local.get $x)))
+    (local.set $c
+      (f64.sub
+        (local.get $x)
+        (f64.mul
+          (local.get $t)
+          (f64.add (f64.const 1.66666666666666019037e-01)
+            (f64.mul (local.get $t)
+              (f64.add (f64.const -2.77777777770155933842e-03)
+                (f64.mul (local.get $t)
+                  (f64.add (f64.const 6.61375632143793436117e-05)
+                    (f64.mul (local.get $t)
+                      (f64.add (f64.const -1.65339022054652515390e-06)
+                        (f64.mul (local.get $t) (f64.const 4.13813679705723846039e-08))))))))))))
+
+    (if (i32.eqz (local.get $k))
+      (then
+        (return
+          (f64.sub
+            (f64.const 1)
+            (f64.sub
+              (f64.div
+                (f64.mul (local.get $x) (local.get $c))
+                (f64.sub (local.get $c) (f64.const 2)))
+              (local.get $x))))
+      )
+    )
+
+    (local.set $y
+      (f64.sub
+        (f64.const 1)
+        (f64.sub
+          (f64.sub
+            (local.get $lo)
+            (f64.div
+              (f64.mul (local.get $x) (local.get $c))
+              (f64.sub (f64.const 2) (local.get $c))))
+          (local.get $hi))))
+    (call $f64_scale (local.get $y) (local.get $k))
+  )
+
+  ;; e_log.c from fdlibm
+  (func $math_log (param $x f64) (result f64)
+    (local $hx i32)
+    (local $k i32)
+    (local $i i32)
+    (local $j i32)
+    (local $f f64)
+    (local $s f64)
+    (local $z f64)
+    (local $w f64)
+    (local $r f64)
+    (local $t1 f64)
+    (local $t2 f64)
+    (local $dk f64)
+    (local $hfsq f64)
+
+    (local.set $hx (call $f64_high (local.get $x)))
+
+    (if (i32.lt_s (local.get $hx) (i32.const 0x00100000))
+      (then
+        (if (f64.eq (local.get $x) (f64.const 0))
+          (then (return (f64.const -inf)))
+        )
+        (if (i32.lt_s (local.get $hx) (i32.const 0))
+          (then (return (f64.const nan)))
+        )
+        ;; subnormal number, scale it up
+        (local.set $k (i32.const -54))
+        (local.set $x (f64.mul (local.get $x) (f64.const 1.80143985094819840000e+16)))
+        (local.set $hx (call $f64_high (local.get $x)))
+      )
+    )
+    (if (i32.ge_s (local.get $hx) (i32.const 0x7ff00000))
+      (then (return (f64.add (local.get $x) (local.get $x))))
+    )
+
+    (local.set $k
+      (i32.add (local.get $k) (i32.sub (i32.shr_s (local.get $hx) (i32.const 20)) (i32.const 1023))))
+    (local.set $hx (i32.and (local.get $hx) (i32.const 0x000fffff)))
+    (local.set $i (i32.and (i32.add (local.get $hx) (i32.const 0x95f64)) (i32.const 0x100000)))
+    ;; normalize x or x/2
+    (local.set $x
+      (call $f64_from_words
+        (i32.or (local.get $hx) (i32.xor (local.get $i) (i32.const 0x3ff00000)))
+        (call $f64_low (local.get $x))))
+    (local.set $k (i32.add (local.get $k) (i32.shr_u (local.get $i) (i32.const 20))))
+    (local.set $f (f64.sub (local.get $x) (f64.const 1)))
+    (local.set $dk (f64.convert_i32_s (local.get $k)))
+
+    ;; |f| < 2^-20
+    (if (i32.lt_u (i32.and (i32.add (local.get $hx) (i32.const 2)) (i32.const 0x000fffff)) (i32.const 3))
+      (then
+        (if (f64.eq (local.get $f) (f64.const 0))
+          (then
+            (if (i32.eqz (local.get $k))
+              (then (return (f64.const 0)))
+            )
+            (return
+              (f64.add
+                (f64.mul (local.get $dk) (f64.const 6.93147180369123816490e-01))
+                (f64.mul (local.get $dk) (f64.const 1.90821492927058770002e-10))))
+          )
+        )
+        (local.set $r
+          (f64.mul
+            (f64.mul (local.get $f) (local.get $f))
+            (f64.sub (f64.const 0.5) (f64.mul (f64.const 0.33333333333333333) (local.get $f)))))
+        (if (i32.eqz (local.get $k))
+          (then (return (f64.sub (local.get $f) (local.get $r))))
+        )
+        (return
+          (f64.sub
+            (f64.mul (local.get $dk) (f64.const 6.93147180369123816490e-01))
+            (f64.sub
+              (f64.sub (local.get $r) (f64.mul (local.get $dk) (f64.const 1.90821492927058770002e-10)))
+              (local.get $f))))
+      )
+    )
+
+    (local.set $s (f64.div (local.get $f) (f64.add (f64.const 2) (local.get $f))))
+    (local.set $z (f64.mul (local.get $s) (local.get $s)))
+    (local.set $i (i32.sub (local.get $hx) (i32.const 0x6147a)))
+    (local.set $w (f64.mul (local.get $z) (local.get $z)))
+    (local.set $j (i32.sub (i32.const 0x6b851) (local.get $hx)))
+    (local.set $t1
+      (f64.mul (local.get $w)
+        (f64.add (f64.const 3.999999999940941908e-01)
+          (f64.mul (local.get $w)
+            (f64.add (f64.const 2.222219843214978396e-01)
+              (f64.mul (local.get $w) (f64.const 1.531383769920937332e-01)))))))
+    (local.set $t2
+      (f64.mul (local.get $z)
+        (f64.add (f64.const 6.666666666666735130e-01)
+          (f64.mul (local.get $w)
+            (f64.add (f64.const 2.857142874366239149e-01)
+              (f64.mul (local.get $w)
+                (f64.add (f64.const 1.818357216161805012e-01)
+                  (f64.mul (local.get $w) (f64.const 1.479819860511658591e-01)))))))))
+    (local.set $i (i32.or (local.get $i) (local.get $j)))
+    (local.set $r (f64.add (local.get $t2) (local.get $t1)))
+
+    (if (i32.gt_s (local.get $i) (i32.const 0))
+      (then
+        (local.set $hfsq (f64.mul (f64.const 0.5) (f64.mul (local.get $f) (local.get $f))))
+        (if (i32.eqz (local.get $k))
+          (then
+            (return
+              (f64.sub
+                (local.get $f)
+                (f64.sub
+                  (local.get $hfsq)
+                  (f64.mul (local.get $s) (f64.add (local.get $hfsq) (local.get $r))))))
+          )
+        )
+        (return
+          (f64.sub
+            (f64.mul (local.get $dk) (f64.const 6.93147180369123816490e-01))
+            (f64.sub
+              (f64.sub
+                (local.get $hfsq)
+                (f64.add
+                  (f64.mul (local.get $s) (f64.add (local.get $hfsq) (local.get $r)))
+                  (f64.mul (local.get $dk) (f64.const 1.90821492927058770002e-10))))
+              (local.get $f))))
+      )
+    )
+
+    (if (i32.eqz (local.get $k))
+      (then
+        (return
+          (f64.sub
+            (local.get $f)
+            (f64.mul (local.get $s) (f64.sub (local.get $f) (local.get $r)))))
+      )
+    )
+    (f64.sub
+      (f64.mul (local.get $dk) (f64.const 6.93147180369123816490e-01))
+      (f64.sub
+        (f64.sub
+          (f64.mul (local.get $s) (f64.sub (local.get $f) (local.get $r)))
+          (f64.mul (local.get $dk) (f64.const 1.90821492927058770002e-10)))
+        (local.get $f)))
+  )
+
+  ;; splits a positive, finite number into m * 2^k, where 1 <= m < 2
+  (func $f64_frexp (param $x f64) (result f64 i32)
+    (local $k i32)
+    (local $hx i32)
+
+    (local.set $hx (call $f64_high (local.get $x)))
+    (if (i32.lt_s (local.get $hx) (i32.const 0x00100000))
+      (then
+        (local.set $k (i32.const -54))
+        (local.set $x (f64.mul (local.get $x) (f64.const 1.80143985094819840000e+16)))
+        (local.set $hx (call $f64_high (local.get $x)))
+      )
+    )
+    (local.set $k
+      (i32.add (local.get $k) (i32.sub (i32.shr_s (local.get $hx) (i32.const 20)) (i32.const 1023))))
+
+    (call $f64_from_words
+      (i32.or (i32.and (local.get $hx) (i32.const 0x000fffff)) (i32.const 0x3ff00000))
+      (call $f64_low (local.get $x)))
+    (local.get $k)
+  )
+
+  ;; e_log10.c from fdlibm
+  (func $math_log10 (param $x f64) (result f64)
+    (local $m f64)
+    (local $k i32)
+    (local $y f64)
+
+    (if (i32.or
+          (i32.or (f64.ne (local.get $x) (local.get $x)) (f64.lt (local.get $x) (f64.const 0)))
+          (f64.eq (local.get $x) (f64.const inf)))
+      (then (return (call $math_log (local.get $x))))
+    )
+    (if (f64.eq (local.get $x) (f64.const 0))
+      (then (return (f64.const -inf)))
+    )
+
+    (call $f64_frexp (local.get $x))
+    (local.set $k)
+    (local.set $m)
+    ;; for negative exponents the mantissa is taken from [0.5, 1)
+    (if (i32.lt_s (local.get $k) (i32.const 0))
+      (then
+        (local.set $k (i32.add (local.get $k) (i32.const 1)))
+        (local.set $m (f64.mul (local.get $m) (f64.const 0.5)))
+      )
+    )
+    (local.set $y (f64.convert_i32_s (local.get $k)))
+
+    (f64.add
+      (f64.add
+        (f64.mul (local.get $y) (f64.const 3.69423907715893078616e-13))
+        (f64.mul (f64.const 4.34294481903251816668e-01) (call $math_log (local.get $m))))
+      (f64.mul (local.get $y) (f64.const 3.01029995663611771306e-01)))
+  )
+
+  (func $math_log2 (param $x f64) (result f64)
+    (local $m f64)
+    (local $k i32)
+
+    (if (i32.or
+          (i32.or (f64.ne (local.get $x) (local.get $x)) (f64.lt (local.get $x) (f64.const 0)))
+          (f64.eq (local.get $x) (f64.const inf)))
+      (then (return (call $math_log (local.get $x))))
+    )
+    (if (f64.eq (local.get $x) (f64.const 0))
+      (then (return (f64.const -inf)))
+    )
+
+    ;; powers of two give exact results
+    (call $f64_frexp (local.get $x))
+    (local.set $k)
+    (local.set $m)
+    (f64.add
+      (f64.convert_i32_s (local.get $k))
+      (f64.mul (call $math_log (local.get $m)) (f64.const 1.44269504088896338700e+00)))
+  )
+
+  ;; s_expm1.c from fdlibm
+  (func $math_expm1 (param $x f64) (result f64)
+    (local $hx i32)
+    (local $negative i32)
+    (local $k i32)
+    (local $hi f64)
+    (local $lo f64)
+    (local $c f64)
+    (local $t f64)
+    (local $e f64)
+    (local $y f64)
+    (local $hfx f64)
+    (local $hxs f64)
+    (local $r1 f64)
+
+    (local.set $hx (i32.and (call $f64_high (local.get $x)) (i32.const 0x7fffffff)))
+    (local.set $negative (f64.lt (local.get $x) (f64.const 0)))
+
+    ;; |x| >= 56 ln2
+    (if (i32.ge_u (local.get $hx) (i32.const 0x4043687a))
+      (then
+        (if (f64.ne (local.get $x) (local.get $x))
+          (then (return (local.get $x)))
+        )
+        (if (local.get $negative)
+          (then (return (f64.const -1)))
+        )
+        (if (f64.gt (local.get $x) (f64.const 7.09782712893383973096e+02))
+          (then (return (f64.const inf)))
+        )
+      )
+    )
+
+    (if (i32.gt_u (local.get $hx) (i32.const 0x3fd62e42))
+      (then
+        ;; |x| > 0.5 ln2, reduce x to hi - lo, where |hi - lo| <= 0.5 ln2
+        (if (i32.lt_u (local.get $hx) (i32.const 0x3ff0a2b2))
+          (then
+            (if (local.get $negative)
+              (then
+                (local.set $hi (f64.add (local.get $x) (f64.const 6.93147180369123816490e-01)))
+                (local.set $lo (f64.const -1.90821492927058770002e-10))
+                (local.set $k (i32.const -1))
+              )
+              (else
+                (local.set $hi (f64.sub (local.get $x) (f64.const 6.93147180369123816490e-01)))
+                (local.set $lo (f64.const 1.90821492927058770002e-10))
+                (local.set $k (i32.const 1))
+              )
+            )
+          )
+          (else
+            (local.set $k
+              (i32.trunc_f64_s
+                (f64.add
+                  (f64.mul (f64.const 1.44269504088896338700e+00) (local.get $x))
+                  (f64.copysign (f64.const 0.5) (local.get $x)))))
+            (local.set $t (f64.convert_i32_s (local.get $k)))
+            (local.set $hi
+              (f64.sub (local.get $x) (f64.mul (local.get $t) (f64.const 6.93147180369123816490e-01))))
+            (local.set $lo (f64.mul (local.get $t) (f64.const 1.90821492927058770002e-10)))
+          )
+        )
+        (local.set $x (f64.sub (local.get $hi) (local.get $lo)))
+        (local.set $c (f64.sub (f64.sub (local.get $hi) (local.get $x)) (local.get $lo)))
+      )
+      (else
+        (if (i32.lt_u (local.get $hx) (i32.const 0x3c900000))
+          (then (return (local.get $x)))
+        )
+      )
+    )
+
+    (local.set $hfx (f64.mul (f64.const 0.5) (local.get $x)))
+    (local.set $hxs (f64.mul (local.get $x) (local.get $hfx)))
+    (local.set $r1
+      (f64.add (f64.const 1)
+        (f64.mul (local.get $hxs)
+          (f64.add (f64.const -3.33333333333331316428e-02)
+            (f64.mul (local.get $hxs)
+              (f64.add (f64.const 1.58730158725481460165e-03)
+                (f64.mul (local.get $hxs)
+                  (f64.add (f64.const -7.93650757867487942473e-05)
+                    (f64.mul (local.get $hxs)
+                      (f64.add (f64.const 4.00821782732936239552e-06)
+                        (f64.mul (local.get $hxs) (f64.const -2.01099218183624371326e-07))))))))))))
+    (local.set $t (f64.sub (f64.const 3) (f64.mul (local.get $r1) (local.get $hfx))))
+    (local.set $e
+      (f64.mul
+        (local.get $hxs)
+        (f64.div
+          (f64.sub (local.get $r1) (local.get $t))
+          (f64.sub (f64.const 6) (f64.mul (local.get $x) (local.get $t))))))
+
+    (if (i32.eqz (local.get $k))
+      (then
+        (return
+          (f64.sub
+            (local.get $x)
+            (f64.sub (f64.mul (local.get $x) (local.get $e)) (local.get $hxs))))
+      )
+    )
+
+    (local.set $e
+      (f64.sub
+        (f64.sub
+          (f64.mul (local.get $x) (f64.sub (local.get $e) (local.get $c)))
+          (local.get $c))
+        (local.get $hxs)))
+
+    (if (i32.eq (local.get $k) (i32.const -1))
+      (then
+        (return
+          (f64.sub
+            (f64.mul (f64.const 0.5) (f64.sub (local.get $x) (local.get $e)))
+            (f64.const 0.5)))
+      )
+    )
+    (if (i32.eq (local.get $k) (i32.const 1))
+      (then
+        (if (f64.lt (local.get $x) (f64.const -0.25))
+          (then
+            (return
+              (f64.mul
+                (f64.const -2)
+                (f64.sub (local.get $e) (f64.add (local.get $x) (f64.const 0.5)))))
+          )
+        )
+        (return
+          (f64.add
+            (f64.const 1)
+            (f64.mul (f64.const 2) (f64.sub (local.get $x) (local.get $e)))))
+      )
+    )
+
+    (if (i32.or (i32.le_s (local.get $k) (i32.const -2)) (i32.gt_s (local.get $k) (i32.const 56)))
+      (then
+        (local.set $y (f64.sub (f64.const 1) (f64.sub (local.get $e) (local.get $x))))
+        (return (f64.sub (call $f64_scale (local.get $y) (local.get $k)) (f64.const 1)))
+      )
+    )
+
+    (if (i32.lt_s (local.get $k) (i32.const 20))
+      (then
+        ;; t = 1 - 2^-k
+        (local.set $t
+          (call $f64_from_words
+            (i32.sub (i32.const 0x3ff00000) (i32.shr_s (i32.const 0x200000) (local.get $k)))
+            (i32.const 0)))
+        (local.set $y (f64.sub (local.get $t) (f64.sub (local.get $e) (local.get $x))))
+      )
+      (else
+        ;; t = 2^-k
+        (local.set $t
+          (call $f64_from_words
+            (i32.shl (i32.sub (i32.const 0x3ff) (local.get $k)) (i32.const 20))
+            (i32.const 0)))
+        (local.set $y
+          (f64.add
+            (f64.sub (local.get $x) (f64.add (local.get $e) (local.get $t)))
+            (f64.const 1)))
+      )
+    )
+    (call $f64_scale (local.get $y) (local.get $k))
+  )
+
+  ;; log(1 + x) uses the trick from "What Every Computer Scientist Should Know
+  ;; About Floating-Point Arithmetic" to stay accurate near 0
+  (func $math_log1p (param $x f64) (result f64)
+    (local $u f64)
+
+    (local.set $u (f64.add (f64.const 1) (local.get $x)))
+    (if (f64.eq (local.get $u) (f64.const 1))
+      (then (return (local.get $x)))
+    )
+    (if (f64.eq (local.get $u) (f64.const inf))
+      (then (return (call $math_log (local.get $u))))
+    )
+    (f64.div
+      (f64.mul (call $math_log (local.get $u)) (local.get $x))
+      (f64.sub (local.get $u) (f64.const 1)))
+  )
+
+  ;; k_sin.c from fdlibm, for |x| < pi/4, y is the tail of x
+  (func $kernel_sin (param $x f64) (param $y f64) (param $has_tail i32) (result f64)
+    (local $z f64)
+    (local $v f64)
+    (local $r f64)
+
+    (if (i32.lt_u (i32.and (call $f64_high (local.get $x)) (i32.const 0x7fffffff)) (i32.const 0x3e400000))
+      (then (return (local.get $x)))
+    )
+
+    (local.set $z (f64.mul (local.get $x) (local.get $x)))
+    (local.set $v (f64.mul (local.get $z) (local.get $x)))
+    (local.set $r
+      (f64.add (f64.const 8.33333333332248946124e-03)
+        (f64.mul (local.get $z)
+          (f64.add (f64.const -1.98412698298579493134e-04)
+            (f64.mul (local.get $z)
+              (f64.add (f64.const 2.75573137070700676789e-06)
+                (f64.mul (local.get $z)
+                  (f64.add (f64.const -2.50507602534068634195e-08)
+                    (f64.mul (local.get $z) (f64.const 1.58969099521155010221e-10))))))))))
+
+    (if (i32.eqz (local.get $has_tail))
+      (then
+        (return
+          (f64.add
+            (local.get $x)
+            (f64.mul
+              (local.get $v)
+              (f64.add (f64.const -1.66666666666666324348e-01) (f64.mul (local.get $z) (local.get $r))))))
+      )
+    )
+    (f64.sub
+      (local.get $x)
+      (f64.sub
+        (f64.sub
+          (f64.mul
+            (local.get $z)
+            (f64.sub
+              (f64.mul (f64.const 0.5) (local.get $y))
+              (f64.mul (local.get $v) (local.get $r))))
+          (local.get $y))
+        (f64.mul (local.get $v) (f64.const -1.66666666666666324348e-01))))
+  )
+
+  ;; k_cos.c from fdlibm, for |x| < pi/4, y is the tail of x
+  (func $kernel_cos (param $x f64) (param $y f64) (result f64)
+    (local $ix i32)
+    (local $z f64)
+    (local $r f64)
+    (local $qx f64)
+
+    (local.set $ix (i32.and (call $f64_high (local.get $x)) (i32.const 0x7fffffff)))
+    (if (i32.lt_u (local.get $ix) (i32.const 0x3e400000))
+      (then (return (f64.const 1)))
+    )
+
+    (local.set $z (f64.mul (local.get $x) (local.get $x)))
+    (local.set $r
+      (f64.mul (local.get $z)
+        (f64.add (f64.const 4.16666666666666019037e-02)
+          (f64.mul (local.get $z)
+            (f64.add (f64.const -1.38888888888741095749e-03)
+              (f64.mul (local.get $z)
+                (f64.add (f64.const 2.48015872894767294178e-05)
+                  (f64.mul (local.get $z)
+                    (f64.add (f64.const -2.75573143513906633035e-07)
+                      (f64.mul (local.get $z)
+                        (f64.add (f64.const 2.08757232129817482790e-09)
+                          (f64.mul (local.get $z) (f64.const -1.13596475577881948265e-11)))))))))))))
+
+    (if (i32.lt_u (local.get $ix) (i32.const 0x3fd33333))
+      (then
+        (return
+          (f64.sub
+            (f64.const 1)
+            (f64.sub
+              (f64.mul (f64.const 0.5) (local.get $z))
+              (f64.sub
+                (f64.mul (local.get $z) (local.get $r))
+                (f64.mul (local.get $x) (local.get $y))))))
+      )
+    )
+
+    (if (i32.gt_u (local.get $ix) (i32.const 0x3fe90000))
+      (then (local.set $qx (f64.const 0.28125)))
+      (else (local.set $qx (call $f64_from_words (i32.sub (local.get $ix) (i32.const 0x00200000)) (i32.const 0))))
+    )
+    (f64.sub
+      (f64.sub (f64.const 1) (local.get $qx))
+      (f64.sub
+        (f64.sub (f64.mul (f64.const 0.5) (local.get $z)) (local.get $qx))
+        (f64.sub
+          (f64.mul (local.get $z) (local.get $r))
+          (f64.mul (local.get $x) (local.get $y)))))
+  )
+
+  ;; a * b as an exact sum of two numbers, using Dekker's algorithm
+  (func $two_product (param $a f64) (param $b f64) (result f64 f64)
+    (local $product f64)
+    (local $a_high f64)
+    (local $a_low f64)
+    (local $b_high f64)
+    (local $b_low f64)
+
+    (local.set $product (f64.mul (local.get $a) (local.get $b)))
+    (local.set $a_high
+      (f64.sub
+        (f64.mul (local.get $a) (f64.const 134217729))
+        (f64.sub (f64.mul (local.get $a) (f64.const 134217729)) (local.get $a))))
+    (local.set $a_low (f64.sub (local.get $a) (local.get $a_high)))
+    (local.set $b_high
+      (f64.sub
+        (f64.mul (local.get $b) (f64.const 134217729))
+        (f64.sub (f64.mul (local.get $b) (f64.const 134217729)) (local.get $b))))
+    (local.set $b_low (f64.sub (local.get $b) (local.get $b_high)))
+
+    (local.get $product)
+    (f64.add
+      (f64.add
+        (f64.add
+          (f64.sub (f64.mul (local.get $a_high) (local.get $b_high)) (local.get $product))
+          (f64.mul (local.get $a_high) (local.get $b_low)))
+        (f64.mul (local.get $a_low) (local.get $b_high)))
+      (f64.mul (local.get $a_low) (local.get $b_low)))
+  )
+
+  ;; Argument reduction for big numbers. Instead of the Payne-Hanek algorithm from
+  ;; fdlibm, the number is multiplied by 2/pi with bignums, which is slower, but
+  ;; just as exact
+  (func $rem_pio2_large (param $x f64) (result i32 f64 f64)
+    (local $mantissa i64)
+    (local $exponent i32)
+    (local $product (ref $BigDigits))
+    (local $shift i32)
+    (local $n i32)
+    (local $fraction (ref $BigDigits))
+    (local $bits i64)
+    (local $high f64)
+    (local $low f64)
+    (local $p f64)
+    (local $error f64)
+    (local $y0 f64)
+    (local $y1 f64)
+    (local $rounded_up i32)
+
+    (call $f64_decompose (f64.abs (local.get $x)))
+    (local.set $exponent)
+    (local.set $mantissa)
+
+    ;; floor(2/pi * 2^1216)
+    (local.set $product
+      (array.new_fixed $BigDigits 38
+        (i32.const 0xfc7b6bab) (i32.const 0x56033046) (i32.const 0x1f8d5d08) (i32.const 0x6bfb5fb1)
+        (i32.const 0x8a5292ea) (i32.const 0x3d0739f7) (i32.const 0xebe5f17b) (i32.const 0x7527bac7)
+        (i32.const 0x9e5fea2d) (i32.const 0x4f463f66) (i32.const 0x27cb09b7) (i32.const 0x6d367ecf)
+        (i32.const 0x5a0a6d1f) (i32.const 0xef2f118b) (i32.const 0xde05980f) (i32.const 0x1ff897ff)
+        (i32.const 0xbdf9283b) (i32.const 0x9c845f8b) (i32.const 0x835339f4) (i32.const 0x3991d639)
+        (i32.const 0xb45f7e41) (i32.const 0xe99c7026) (i32.const 0x2ebb4484) (i32.const 0xe88235f5)
+        (i32.const 0xb129a73e) (i32.const 0xfe1deb1c) (i32.const 0x09d1921c) (i32.const 0x06492eea)
+        (i32.const 0x424dd2e0) (i32.const 0xb7246e3a) (i32.const 0xdebbc561) (i32.const 0xfe5163ab)
+        (i32.const 0x3c439041) (i32.const 0xdb629599) (i32.const 0xf534ddc0) (i32.const 0xfc2757d1)
+        (i32.const 0x4e441529) (i32.const 0xa2f9836e)))
+    ;; the mantissa has 53 bits, so it's multiplied in two parts
+    (local.set $product
+      (call $big_add
+        (call $big_mul_small (local.get $product) (i32.wrap_i64 (local.get $mantissa)))
+        (call $big_shl
+          (call $big_mul_small
+            (local.get $product)
+            (i32.wrap_i64 (i64.shr_u (local.get $mantissa) (i64.const 32))))
+          (i32.const 32))))
+
+    ;; x * 2/pi = product * 2^(exponent - 1216), only the last two bits of the
+    ;; integer part are needed
+    (local.set $shift (i32.sub (i32.const 1216) (local.get $exponent)))
+    (local.set $n
+      (i32.and (i32.wrap_i64 (call $big_to_u64 (call $big_shr (local.get $product) (local.get $shift)))) (i32.const 3)))
+    (local.set $fraction
+      (call $big_sub
+        (local.get $product)
+        (call $big_shl (call $big_shr (local.get $product) (local.get $shift)) (local.get $shift))))
+
+    ;; fractions above 0.5 are rounded up, so the result is in [-pi/4, pi/4]
+    (if (i32.ge_s
+          (call $big_compare
+            (local.get $fraction)
+            (call $big_shl (call $big_from_u64 (i64.const 1)) (i32.sub (local.get $shift) (i32.const 1))))
+          (i32.const 0))
+      (then
+        (local.set $n (i32.add (local.get $n) (i32.const 1)))
+        (local.set $fraction
+          (call $big_sub
+            (call $big_shl (call $big_from_u64 (i64.const 1)) (local.get $shift))
+            (local.get $fraction)))
+        (local.set $rounded_up (i32.const 1))
+      )
+    )
+
+    ;; the fraction as high + low, 53 bits each
+    (local.set $fraction (call $big_shr (local.get $fraction) (i32.sub (local.get $shift) (i32.const 106))))
+    (local.set $high
+      (f64.mul
+        (f64.convert_i64_u (call $big_to_u64 (call $big_shr (local.get $fraction) (i32.const 53))))
+        (f64.const 0x1p-53)))
+    (local.set $low
+      (f64.mul
+        (f64.convert_i64_u
+          (i64.and (call $big_to_u64 (local.get $fraction)) (i64.const 0x1fffffffffffff)))
+        (f64.const 0x1p-106)))
+
+    ;; multiplied by pi/2 in double-double arithmetic
+    (call $two_product (local.get $high) (f64.const 1.5707963267948966))
+    (local.set $error)
+    (local.set $p)
+    (local.set $error
+      (f64.add
+        (local.get $error)
+        (f64.add
+          (f64.mul (local.get $high) (f64.const 6.123233995736766e-17))
+          (f64.mul (local.get $low) (f64.const 1.5707963267948966)))))
+    (local.set $y0 (f64.add (local.get $p) (local.get $error)))
+    (local.set $y1 (f64.sub (local.get $error) (f64.sub (local.get $y0) (local.get $p))))
+    (if (local.get $rounded_up)
+      (then
+        (local.set $y0 (f64.neg (local.get $y0)))
+        (local.set $y1 (f64.neg (local.get $y1)))
+      )
+    )
+
+    (if (f64.lt (local.get $x) (f64.const 0))
+      (then
+        (return
+          (i32.sub (i32.const 0) (local.get $n))
+          (f64.neg (local.get $y0))
+          (f64.neg (local.get $y1)))
+      )
+    )
+    (local.get $n)
+    (local.get $y0)
+    (local.get $y1)
+  )
+
+  ;; e_rem_pio2.c from fdlibm, returns n and x - n * pi/2 as a sum of two numbers
+  (func $rem_pio2 (param $x f64) (result i32 f64 f64)
+    (local $t f64)
+    (local $fn f64)
+    (local $n i32)
+    (local $r f64)
+    (local $w f64)
+    (local $y0 f64)
+    (local $j i32)
+    (local $i i32)
+
+    (local.set $t (f64.abs (local.get $x)))
+    (if (f64.gt (local.get $t) (f64.const 0x1.921fbp+20))
+      (then (return (call $rem_pio2_large (local.get $x))))
+    )
+
+    (local.set $fn (f64.nearest (f64.mul (local.get $t) (f64.const 6.36619772367581382433e-01))))
+    (local.set $n (i32.trunc_f64_s (local.get $fn)))
+    (local.set $r (f64.sub (local.get $t) (f64.mul (local.get $fn) (f64.const 1.57079632673412561417e+00))))
+    (local.set $w (f64.mul (local.get $fn) (f64.const 6.07710050650619224932e-11)))
+
+    (local.set $j (i32.shr_u (call $f64_high (local.get $t)) (i32.const 20)))
+    (local.set $y0 (f64.sub (local.get $r) (local.get $w)))
+    (local.set $i
+      (i32.sub
+        (local.get $j)
+        (i32.and (i32.shr_u (call $f64_high (local.get $y0)) (i32.const 20)) (i32.const 0x7ff))))
+
+    ;; if there was a big cancellation, use more bits of pi/2
+    (if (i32.gt_s (local.get $i) (i32.const 16))
+      (then
+        (local.set $t (local.get $r))
+        (local.set $w (f64.mul (local.get $fn) (f64.const 6.07710050630396597660e-11)))
+        (local.set $r (f64.sub (local.get $t) (local.get $w)))
+        (local.set $w
+          (f64.sub
+            (f64.mul (local.get $fn) (f64.const 2.02226624879595063154e-21))
+            (f64.sub (f64.sub (local.get $t) (local.get $r)) (local.get $w))))
+        (local.set $y0 (f64.sub (local.get $r) (local.get $w)))
+        (local.set $i
+          (i32.sub
+            (local.get $j)
+            (i32.and (i32.shr_u (call $f64_high (local.get $y0)) (i32.const 20)) (i32.const 0x7ff))))
+        (if (i32.gt_s (local.get $i) (i32.const 49))
+          (then
+            (local.set $t (local.get $r))
+            (local.set $w (f64.mul (local.get $fn) (f64.const 2.02226624871116645580e-21)))
+            (local.set $r (f64.sub (local.get $t) (local.get $w)))
+            (local.set $w
+              (f64.sub
+                (f64.mul (local.get $fn) (f64.const 8.47842766036889956997e-32))
+                (f64.sub (f64.sub (local.get $t) (local.get $r)) (local.get $w))))
+            (local.set $y0 (f64.sub (local.get $r) (local.get $w)))
+          )
+        )
+      )
+    )
+
+    (if (f64.lt (local.get $x) (f64.const 0))
+      (then
+        (return
+          (i32.sub (i32.const 0) (local.get $n))
+          (f64.neg (local.get $y0))
+          (f64.neg (f64.sub (f64.sub (local.get $r) (local.get $y0)) (local.get $w))))
+      )
+    )
+    (local.get $n)
+    (local.get $y0)
+    (f64.sub (f64.sub (local.get $r) (local.get $y0)) (local.get $w))
+  )
+
+  (func $math_sin (param $x f64) (result f64)
+    (local $n i32)
+    (local $y0 f64)
+    (local $y1 f64)
+
+    (if (f64.le (f64.abs (local.get $x)) (f64.const 0.7853981633974483))
+      (then (return (call $kernel_sin (local.get $x) (f64.const 0) (i32.const 0))))
+    )
+    (if (f64.ne (f64.sub (local.get $x) (local.get $x)) (f64.const 0))
+      (then (return (f64.const nan)))
+    )
+
+    (call $rem_pio2 (local.get $x))
+    (local.set $y1)
+    (local.set $y0)
+    (local.set $n)
+    (local.set $n (i32.and (local.get $n) (i32.const 3)))
+
+    (if (i32.eqz (local.get $n))
+      (then (return (call $kernel_sin (local.get $y0) (local.get $y1) (i32.const 1))))
+    )
+    (if (i32.eq (local.get $n) (i32.const 1))
+      (then (return (call $kernel_cos (local.get $y0) (local.get $y1))))
+    )
+    (if (i32.eq (local.get $n) (i32.const 2))
+      (then (return (f64.neg (call $kernel_sin (local.get $y0) (local.get $y1) (i32.const 1)))))
+    )
+    (f64.neg (call $kernel_cos (local.get $y0) (local.get $y1)))
+  )
+
+  (func $math_cos (param $x f64) (result f64)
+    (local $n i32)
+    (local $y0 f64)
+    (local $y1 f64)
+
+    (if (f64.le (f64.abs (local.get $x)) (f64.const 0.7853981633974483))
+      (then (return (call $kernel_cos (local.get $x) (f64.const 0))))
+    )
+    (if (f64.ne (f64.sub (local.get $x) (local.get $x)) (f64.const 0))
+      (then (return (f64.const nan)))
+    )
+
+    (call $rem_pio2 (local.get $x))
+    (local.set $y1)
+    (local.set $y0)
+    (local.set $n)
+    (local.set $n (i32.and (local.get $n) (i32.const 3)))
+
+    (if (i32.eqz (local.get $n))
+      (then (return (call $kernel_cos (local.get $y0) (local.get $y1))))
+    )
+    (if (i32.eq (local.get $n) (i32.const 1))
+      (then (return (f64.neg (call $kernel_sin (local.get $y0) (local.get $y1) (i32.const 1)))))
+    )
+    (if (i32.eq (local.get $n) (i32.const 2))
+      (then (return (f64.neg (call $kernel_cos (local.get $y0) (local.get $y1)))))
+    )
+    (call $kernel_sin (local.get $y0) (local.get $y1) (i32.const 1))
+  )
+
+  ;; -1 / (x + y) computed in extra precision, for k_tan.c
+  (func $negative_reciprocal (param $x f64) (param $y f64) (param $r f64) (result f64)
+    (local $w f64)
+    (local $z f64)
+    (local $v f64)
+    (local $a f64)
+    (local $t f64)
+
+    (local.set $w (f64.add (local.get $x) (local.get $r)))
+    (local.set $z (call $f64_clear_low (local.get $w)))
+    (local.set $v (f64.sub (local.get $r) (f64.sub (local.get $z) (local.get $x))))
+    (local.set $a (f64.div (f64.const -1) (local.get $w)))
+    (local.set $t (call $f64_clear_low (local.get $a)))
+    (f64.add
+      (local.get $t)
+      (f64.mul
+        (local.get $a)
+        (f64.add
+          (f64.add (f64.const 1) (f64.mul (local.get $t) (local.get $z)))
+          (f64.mul (local.get $t) (local.get $v)))))
+  )
+
+  ;; k_tan.c from fdlibm, for |x| < pi/4, y is the tail of x. Returns tan(x + y)
+  ;; if odd is 0, otherwise -1 / tan(x + y)
+  (func $kernel_tan (param $x f64) (param $y f64) (param $odd i32) (result f64)
+    (local $hx i32)
+    (local $ix i32)
+    (local $big i32)
+    (local $z f64)
+    (local $w f64)
+    (local $r f64)
+    (local $v f64)
+    (local $s f64)
+
+    (local.set $hx (call $f64_high (local.get $x)))
+    (local.set $ix (i32.and (local.get $hx) (i32.const 0x7fffffff)))
+
+    (if (i32.lt_u (local.get $ix) (i32.const 0x3e300000))
+      (then
+        (if (i32.eqz (local.get $odd))
+          (then (return (local.get $x)))
+        )
+        (return (call $negative_reciprocal (local.get $x) (local.get $y) (local.get $y)))
+      )
+    )
+
+    ;; |x| >= 0.6744, use tan(x) = tan(pi/4 - x)
+    (local.set $big (i32.ge_u (local.get $ix) (i32.const 0x3fe59428)))
+    (if (local.get $big)
+      (then
+        (if (i32.lt_s (local.get $hx) (i32.const 0))
+          (then
+            (local.set $x (f64.neg (local.get $x)))
+            (local.set $y (f64.neg (local.get $y)))
+          )
+        )
+        (local.set $x
+          (f64.add
+            (f64.sub (f64.const 7.85398163397448278999e-01) (local.get $x))
+            (f64.sub (f64.const 3.06161699786838301793e-17) (local.get $y))))
+        (local.set $y (f64.const 0))
+      )
+    )
+
+    (local.set $z (f64.mul (local.get $x) (local.get $x)))
+    (local.set $w (f64.mul (local.get $z) (local.get $z)))
+    (local.set $r
+      (f64.add (f64.const 1.33333333333201242699e-01)
+        (f64.mul (local.get $w)
+          (f64.add (f64.const 2.18694882948595424599e-02)
+            (f64.mul (local.get $w)
+              (f64.add (f64.const 3.59207910759131235356e-03)
+                (f64.mul (local.get $w)
+                  (f64.add (f64.const 5.88041240820264096874e-04)
+                    (f64.mul (local.get $w)
+                      (f64.add (f64.const 7.81794442939557092300e-05)
+                        (f64.mul (local.get $w) (f64.const -1.85586374855275456654e-05))))))))))))
+    (local.set $v
+      (f64.mul (local.get $z)
+        (f64.add (f64.const 5.39682539762260521377e-02)
+          (f64.mul (local.get $w)
+            (f64.add (f64.const 8.86323982359930005737e-03)
+              (f64.mul (local.get $w)
+                (f64.add (f64.const 1.45620945432529025516e-03)
+                  (f64.mul (local.get $w)
+                    (f64.add (f64.const 2.46463134818469906812e-04)
+                      (f64.mul (local.get $w)
+                        (f64.add (f64.const 7.14072491382608190305e-05)
+                          (f64.mul (local.get $w) (f64.const 2.59073051863633712884e-05)))))))))))))
+    (local.set $s (f64.mul (local.get $z) (local.get $x)))
+    (local.set $r
+      (f64.add
+        (local.get $y)
+        (f64.mul
+          (local.get $z)
+          (f64.add
+            (f64.mul (local.get $s) (f64.add (local.get $r) (local.get $v)))
+            (local.get $y)))))
+    (local.set $r (f64.add (local.get $r) (f64.mul (f64.const 3.33333333333334091986e-01) (local.get $s))))
+    (local.set $w (f64.add (local.get $x) (local.get $r)))
+
+    (if (local.get $big)
+      (then
+        (local.set $v (select (f64.const -1) (f64.const 1) (local.get $odd)))
+        (return
+          (f64.mul
+            (select (f64.const -1) (f64.const 1) (i32.lt_s (local.get $hx) (i32.const 0)))
+            (f64.sub
+              (local.get $v)
+              (f64.mul
+                (f64.const 2)
+                (f64.sub
+                  (local.get $x)
+                  (f64.sub
+                    (f64.div
+                      (f64.mul (local.get $w) (local.get $w))
+                      (f64.add (local.get $w) (local.get $v)))
+                    (local.get $r)))))))
+      )
+    )
+
+    (if (i32.eqz (local.get $odd))
+      (then (return (local.get $w)))
+    )
+    (call $negative_reciprocal (local.get $x) (local.get $r) (local.get $r))
+  )
+
+  (func $math_tan (param $x f64) (result f64)
+    (local $n i32)
+    (local $y0 f64)
+    (local $y1 f64)
+
+    (if (f64.le (f64.abs (local.get $x)) (f64.const 0.7853981633974483))
+      (then (return (call $kernel_tan (local.get $x) (f64.const 0) (i32.const 0))))
+    )
+    (if (f64.ne (f64.sub (local.get $x) (local.get $x)) (f64.const 0))
+      (then (return (f64.const nan)))
+    )
+
+    (call $rem_pio2 (local.get $x))
+    (local.set $y1)
+    (local.set $y0)
+    (local.set $n)
+    (call $kernel_tan (local.get $y0) (local.get $y1) (i32.and (local.get $n) (i32.const 1)))
+  )
+
+  ;; s_atan.c from fdlibm
+  (func $math_atan (param $x f64) (result f64)
+    (local $hx i32)
+    (local $ix i32)
+    (local $id i32)
+    (local $z f64)
+    (local $w f64)
+    (local $s1 f64)
+    (local $s2 f64)
+    (local $hi f64)
+    (local $lo f64)
+
+    (local.set $hx (call $f64_high (local.get $x)))
+    (local.set $ix (i32.and (local.get $hx) (i32.const 0x7fffffff)))
+
+    ;; |x| >= 2^66
+    (if (i32.ge_u (local.get $ix) (i32.const 0x44100000))
+      (then
+        (if (f64.ne (local.get $x) (local.get $x))
+          (then (return (local.get $x)))
+        )
+        (return (f64.copysign (f64.const 1.57079632679489661923) (local.get $x)))
+      )
+    )
+
+    (local.set $id (i32.const -1))
+    (if (i32.lt_u (local.get $ix) (i32.const 0x3fdc0000))
+      (then
+        (if (i32.lt_u (local.get $ix) (i32.const 0x3e200000))
+          (then (return (local.get $x)))
+        )
+      )
+      (else
+        (local.set $x (f64.abs (local.get $x)))
+        (if (i32.lt_u (local.get $ix) (i32.const 0x3ff30000))
+          (then
+            (if (i32.lt_u (local.get $ix) (i32.const 0x3fe60000))
+              (then
+                (local.set $id (i32.const 0))
+                (local.set $hi (f64.const 4.63647609000806093515e-01))
+                (local.set $lo (f64.const 2.26987774529616870924e-17))
+                (local.set $x
+                  (f64.div
+                    (f64.sub (f64.mul (f64.const 2) (local.get $x)) (f64.const 1))
+                    (f64.add (f64.const 2) (local.get $x))))
+              )
+              (else
+                (local.set $id (i32.const 1))
+                (local.set $hi (f64.const 7.85398163397448278999e-01))
+                (local.set $lo (f64.const 3.06161699786838301793e-17))
+                (local.set $x
+                  (f64.div
+                    (f64.sub (local.get $x) (f64.const 1))
+                    (f64.add (local.get $x) (f64.const 1))))
+              )
+            )
+          )
+          (else
+            (if (i32.lt_u (local.get $ix) (i32.const 0x40038000))
+              (then
+                (local.set $id (i32.const 2))
+                (local.set $hi (f64.const 9.82793723247329054082e-01))
+                (local.set $lo (f64.const 1.39033110312309984516e-17))
+                (local.set $x
+                  (f64.div
+                    (f64.sub (local.get $x) (f64.const 1.5))
+                    (f64.add (f64.const 1) (f64.mul (f64.const 1.5) (local.get $x)))))
+              )
+              (else
+                (local.set $id (i32.const 3))
+                (local.set $hi (f64.const 1.57079632679489655800e+00))
+                (local.set $lo (f64.const 6.12323399573676603587e-17))
+                (local.set $x (f64.div (f64.const -1) (local.get $x)))
+              )
+            )
+          )
+        )
+      )
+    )
+
+    (local.set $z (f64.mul (local.get $x) (local.get $x)))
+    (local.set $w (f64.mul (local.get $z) (local.get $z)))
+    (local.set $s1
+      (f64.mul (local.get $z)
+        (f64.add (f64.const 3.33333333333329318027e-01)
+          (f64.mul (local.get $w)
+            (f64.add (f64.const 1.42857142725034663711e-01)
+              (f64.mul (local.get $w)
+                (f64.add (f64.const 9.09088713343650656196e-02)
+                  (f64.mul (local.get $w)
+                    (f64.add (f64.const 6.66107313738753120669e-02)
+                      (f64.mul (local.get $w)
+                        (f64.add (f64.const 4.97687799461593236017e-02)
+                          (f64.mul (local.get $w) (f64.const 1.62858201153657823623e-02)))))))))))))
+    (local.set $s2
+      (f64.mul (local.get $w)
+        (f64.add (f64.const -1.99999999998764832476e-01)
+          (f64.mul (local.get $w)
+            (f64.add (f64.const -1.11111104054623557880e-01)
+              (f64.mul (local.get $w)
+                (f64.add (f64.const -7.69187620504482999495e-02)
+                  (f64.mul (local.get $w)
+                    (f64.add (f64.const -5.83357013379057348645e-02)
+                      (f64.mul (local.get $w) (f64.const -3.65315727442169155270e-02)))))))))))
+
+    (if (i32.lt_s (local.get $id) (i32.const 0))
+      (then
+        (return
+          (f64.sub
+            (local.get $x)
+            (f64.mul (local.get $x) (f64.add (local.get $s1) (local.get $s2)))))
+      )
+    )
+
+    (local.set $z
+      (f64.sub
+        (local.get $hi)
+        (f64.sub
+          (f64.sub
+            (f64.mul (local.get $x) (f64.add (local.get $s1) (local.get $s2)))
+            (local.get $lo))
+          (local.get $x))))
+    (if (i32.lt_s (local.get $hx) (i32.const 0))
+      (then (return (f64.neg (local.get $z))))
+    )
+    (local.get $z)
+  )
+
+  ;; e_atan2.c from fdlibm
+  (func $math_atan2 (param $y f64) (param $x f64) (result f64)
+    (local $m i32)
+    (local $k i32)
+    (local $z f64)
+
+    (if (i32.or (f64.ne (local.get $x) (local.get $x)) (f64.ne (local.get $y) (local.get $y)))
+      (then (return (f64.const nan)))
+    )
+    (if (f64.eq (local.get $x) (f64.const 1))
+      (then (return (call $math_atan (local.get $y))))
+    )
+
+    ;; 2 * sign(x) + sign(y)
+    (local.set $m
+      (i32.or
+        (i32.shr_u (call $f64_high (local.get $y)) (i32.const 31))
+        (i32.and (i32.shr_u (call $f64_high (local.get $x)) (i32.const 30)) (i32.const 2))))
+
+    (if (f64.eq (local.get $y) (f64.const 0))
+      (then
+        (if (i32.lt_u (local.get $m) (i32.const 2))
+          (then (return (local.get $y)))
+        )
+        (return (f64.copysign (f64.const 3.1415926535897931160E+00) (local.get $y)))
+      )
+    )
+
+    (if (f64.eq (local.get $x) (f64.const 0))
+      (then (return (f64.copysign (f64.const 1.5707963267948965580E+00) (local.get $y))))
+    )
+
+    (if (f64.eq (f64.abs (local.get $x)) (f64.const inf))
+      (then
+        (if (f64.eq (f64.abs (local.get $y)) (f64.const inf))
+          (then
+            (if (i32.lt_u (local.get $m) (i32.const 2))
+              (then (return (f64.copysign (f64.const 7.8539816339744827900E-01) (local.get $y))))
+            )
+            (return (f64.copysign (f64.const 2.356194490192344836998) (local.get $y)))
+          )
+        )
+        (if (i32.lt_u (local.get $m) (i32.const 2))
+          (then (return (f64.copysign (f64.const 0) (local.get $y))))
+        )
+        (return (f64.copysign (f64.const 3.1415926535897931160E+00) (local.get $y)))
+      )
+    )
+
+    (if (f64.eq (f64.abs (local.get $y)) (f64.const inf))
+      (then (return (f64.copysign (f64.const 1.5707963267948965580E+00) (local.get $y))))
+    )
+
+    (local.set $k
+      (i32.shr_s
+        (i32.sub
+          (i32.and (call $f64_high (local.get $y)) (i32.const 0x7fffffff))
+          (i32.and (call $f64_high (local.get $x)) (i32.const 0x7fffffff)))
+        (i32.const 20)))
+
+    (if (i32.gt_s (local.get $k) (i32.const 60))
+      (then
+        ;; |y / x| > 2^60
+        (local.set $z (f64.add (f64.const 1.5707963267948965580E+00) (f64.mul (f64.const 0.5) (f64.const 1.2246467991473531772E-16))))
+        (local.set $m (i32.and (local.get $m) (i32.const 1)))
+      )
+      (else
+        (if (i32.and (f64.lt (local.get $x) (f64.const 0)) (i32.lt_s (local.get $k) (i32.const -60)))
+          (then (local.set $z (f64.const 0)))
+          (else (local.set $z (call $math_atan (f64.abs (f64.div (local.get $y) (local.get $x))))))
+        )
+      )
+    )
+
+    (if (i32.eqz (local.get $m))
+      (then (return (local.get $z)))
+    )
+    (if (i32.eq (local.get $m) (i32.const 1))
+      (then (return (f64.neg (local.get $z))))
+    )
+    (if (i32.eq (local.get $m) (i32.const 2))
+      (then
+        (return
+          (f64.sub
+            (f64.const 3.1415926535897931160E+00)
+            (f64.sub (local.get $z) (f64.const 1.2246467991473531772E-16))))
+      )
+    )
+    (f64.sub
+      (f64.sub (local.get $z) (f64.const 1.2246467991473531772E-16))
+      (f64.const 3.1415926535897931160E+00))
+  )
+
+  (func $math_asin (param $x f64) (result f64)
+    (if (f64.gt (f64.abs (local.get $x)) (f64.const 1))
+      (then (return (f64.const nan)))
+    )
+    (call $math_atan2
+      (local.get $x)
+      (f64.sqrt
+        (f64.mul
+          (f64.sub (f64.const 1) (local.get $x))
+          (f64.add (f64.const 1) (local.get $x)))))
+  )
+
+  (func $math_acos (param $x f64) (result f64)
+    (if (f64.gt (f64.abs (local.get $x)) (f64.const 1))
+      (then (return (f64.const nan)))
+    )
+    (call $math_atan2
+      (f64.sqrt
+        (f64.mul
+          (f64.sub (f64.const 1) (local.get $x))
+          (f64.add (f64.const 1) (local.get $x))))
+      (local.get $x))
+  )
+
+  ;; s_sinh.c, e_cosh.c and s_tanh.c from fdlibm
+  (func $math_sinh (param $x f64) (result f64)
+    (local $a f64)
+    (local $h f64)
+    (local $t f64)
+    (local $w f64)
+
+    (local.set $a (f64.abs (local.get $x)))
+    (if (i32.or
+          (f64.ne (local.get $x) (local.get $x))
+          (f64.lt (local.get $a) (f64.const 0x1p-28)))
+      (then (return (local.get $x)))
+    )
+    (local.set $h (f64.copysign (f64.const 0.5) (local.get $x)))
+
+    (if (f64.lt (local.get $a) (f64.const 22))
+      (then
+        (local.set $t (call $math_expm1 (local.get $a)))
+        (if (f64.lt (local.get $a) (f64.const 1))
+          (then
+            (return
+              (f64.mul
+                (local.get $h)
+                (f64.sub
+                  (f64.mul (f64.const 2) (local.get $t))
+                  (f64.div
+                    (f64.mul (local.get $t) (local.get $t))
+                    (f64.add (local.get $t) (f64.const 1))))))
+          )
+        )
+        (return
+          (f64.mul
+            (local.get $h)
+            (f64.add
+              (local.get $t)
+              (f64.div (local.get $t) (f64.add (local.get $t) (f64.const 1))))))
+      )
+    )
+
+    (if (f64.lt (local.get $a) (f64.const 7.09782712893383973096e+02))
+      (then (return (f64.mul (local.get $h) (call $math_exp (local.get $a)))))
+    )
+
+    ;; exp(|x|) overflows, but the result might not yet
+    (local.set $w (call $math_exp (f64.mul (f64.const 0.5) (local.get $a))))
+    (f64.mul (f64.mul (local.get $h) (local.get $w)) (local.get $w))
+  )
+
+  (func $math_cosh (param $x f64) (result f64)
+    (local $a f64)
+    (local $t f64)
+    (local $w f64)
+
+    (local.set $a (f64.abs (local.get $x)))
+    (if (f64.ne (local.get $x) (local.get $x))
+      (then (return (local.get $x)))
+    )
+
+    (if (f64.lt (local.get $a) (f64.const 0.34657359027997264))
+      (then
+        (local.set $t (call $math_expm1 (local.get $a)))
+        (local.set $w (f64.add (f64.const 1) (local.get $t)))
+        (return
+          (f64.add
+            (f64.const 1)
+            (f64.div
+              (f64.mul (local.get $t) (local.get $t))
+              (f64.add (local.get $w) (local.get $w)))))
+      )
+    )
+
+    (if (f64.lt (local.get $a) (f64.const 22))
+      (then
+        (local.set $t (call $math_exp (local.get $a)))
+        (return
+          (f64.add
+            (f64.mul (f64.const 0.5) (local.get $t))
+            (f64.div (f64.const 0.5) (local.get $t))))
+      )
+    )
+
+    (if (f64.lt (local.get $a) (f64.const 7.09782712893383973096e+02))
+      (then (return (f64.mul (f64.const 0.5) (call $math_exp (local.get $a)))))
+    )
+
+    (local.set $w (call $math_exp (f64.mul (f64.const 0.5) (local.get $a))))
+    (f64.mul (f64.mul (f64.const 0.5) (local.get $w)) (local.get $w))
+  )
+
+  (func $math_tanh (param $x f64) (result f64)
+    (local $a f64)
+    (local $t f64)
+    (local $z f64)
+
+    (local.set $a (f64.abs (local.get $x)))
+    (if (i32.or
+          (f64.ne (local.get $x) (local.get $x))
+          (f64.lt (local.get $a) (f64.const 0x1p-55)))
+      (then (return (local.get $x)))
+    )
+
+    (if (f64.ge (local.get $a) (f64.const 22))
+      (then (return (f64.copysign (f64.const 1) (local.get $x))))
+    )
+
+    (if (f64.ge (local.get $a) (f64.const 1))
+      (then
+        (local.set $t (call $math_expm1 (f64.mul (f64.const 2) (local.get $a))))
+        (local.set $z
+          (f64.sub (f64.const 1) (f64.div (f64.const 2) (f64.add (local.get $t) (f64.const 2)))))
+      )
+      (else
+        (local.set $t (call $math_expm1 (f64.mul (f64.const -2) (local.get $a))))
+        (local.set $z
+          (f64.div (f64.neg (local.get $t)) (f64.add (local.get $t) (f64.const 2))))
+      )
+    )
+    (f64.copysign (local.get $z) (local.get $x))
+  )
+
+  ;; s_asinh.c, e_acosh.c and e_atanh.c from fdlibm
+  (func $math_asinh (param $x f64) (result f64)
+    (local $a f64)
+    (local $t f64)
+    (local $w f64)
+
+    (local.set $a (f64.abs (local.get $x)))
+    (if (i32.or
+          (i32.or
+            (f64.ne (local.get $x) (local.get $x))
+            (f64.eq (local.get $a) (f64.const inf)))
+          (f64.lt (local.get $a) (f64.const 0x1p-28)))
+      (then (return (local.get $x)))
+    )
+
+    (if (f64.gt (local.get $a) (f64.const 0x1p28))
+      (then
+        (local.set $w
+          (f64.add (call $math_log (local.get $a)) (f64.const 6.93147180559945286227e-01)))
+      )
+      (else
+        (if (f64.gt (local.get $a) (f64.const 2))
+          (then
+            (local.set $w
+              (call $math_log
+                (f64.add
+                  (f64.mul (f64.const 2) (local.get $a))
+                  (f64.div
+                    (f64.const 1)
+                    (f64.add
+                      (f64.sqrt (f64.add (f64.mul (local.get $x) (local.get $x)) (f64.const 1)))
+                      (local.get $a))))))
+          )
+          (else
+            (local.set $t (f64.mul (local.get $x) (local.get $x)))
+            (local.set $w
+              (call $math_log1p
+                (f64.add
+                  (local.get $a)
+                  (f64.div
+                    (local.get $t)
+                    (f64.add (f64.const 1) (f64.sqrt (f64.add (f64.const 1) (local.get $t))))))))
+          )
+        )
+      )
+    )
+    (f64.copysign (local.get $w) (local.get $x))
+  )
+
+  (func $math_acosh (param $x f64) (result f64)
+    (local $t f64)
+
+    (if (f64.lt (local.get $x) (f64.const 1))
+      (then (return (f64.const nan)))
+    )
+    (if (i32.or
+          (f64.ne (local.get $x) (local.get $x))
+          (f64.eq (local.get $x) (f64.const inf)))
+      (then (return (local.get $x)))
+    )
+
+    (if (f64.ge (local.get $x) (f64.const 0x1p28))
+      (then
+        (return (f64.add (call $math_log (local.get $x)) (f64.const 6.93147180559945286227e-01)))
+      )
+    )
+    (if (f64.eq (local.get $x) (f64.const 1))
+      (then (return (f64.const 0)))
+    )
+    (if (f64.gt (local.get $x) (f64.const 2))
+      (then
+        (return
+          (call $math_log
+            (f64.sub
+              (f64.mul (f64.const 2) (local.get $x))
+              (f64.div
+                (f64.const 1)
+                (f64.add
+                  (local.get $x)
+                  (f64.sqrt (f64.sub (f64.mul (local.get $x) (local.get $x)) (f64.const 1))))))))
+      )
+    )
+
+    (local.set $t (f64.sub (local.get $x) (f64.const 1)))
+    (call $math_log1p
+      (f64.add
+        (local.get $t)
+        (f64.sqrt
+          (f64.add
+            (f64.mul (f64.const 2) (local.get $t))
+            (f64.mul (local.get $t) (local.get $t))))))
+  )
+
+  (func $math_atanh (param $x f64) (result f64)
+    (local $a f64)
+    (local $t f64)
+
+    (local.set $a (f64.abs (local.get $x)))
+    (if (f64.gt (local.get $a) (f64.const 1))
+      (then (return (f64.const nan)))
+    )
+    (if (f64.eq (local.get $a) (f64.const 1))
+      (then (return (f64.copysign (f64.const inf) (local.get $x))))
+    )
+    (if (i32.or
+          (f64.ne (local.get $x) (local.get $x))
+          (f64.lt (local.get $a) (f64.const 0x1p-28)))
+      (then (return (local.get $x)))
+    )
+
+    (if (f64.lt (local.get $a) (f64.const 0.5))
+      (then
+        (local.set $t (f64.add (local.get $a) (local.get $a)))
+        (local.set $t
+          (f64.mul
+            (f64.const 0.5)
+            (call $math_log1p
+              (f64.add
+                (local.get $t)
+                (f64.div
+                  (f64.mul (local.get $t) (local.get $a))
+                  (f64.sub (f64.const 1) (local.get $a)))))))
+      )
+      (else
+        (local.set $t
+          (f64.mul
+            (f64.const 0.5)
+            (call $math_log1p
+              (f64.div
+                (f64.add (local.get $a) (local.get $a))
+                (f64.sub (f64.const 1) (local.get $a))))))
+      )
+    )
+    (f64.copysign (local.get $t) (local.get $x))
+  )
+
+  (func $math_cbrt (param $x f64) (result f64)
+    (local $a f64)
+    (local $y f64)
+    (local $rounded f64)
+
+    (if (i32.or
+          (f64.eq (local.get $x) (f64.const 0))
+          (f64.ne (f64.sub (local.get $x) (local.get $x)) (f64.const 0)))
+      (then (return (local.get $x)))
+    )
+
+    (local.set $a (f64.abs (local.get $x)))
+    (local.set $y
+      (call $math_exp (f64.div (call $math_log (local.get $a)) (f64.const 3))))
+    ;; one step of Newton's method
+    (local.set $y
+      (f64.sub
+        (local.get $y)
+        (f64.div
+          (f64.sub (f64.mul (f64.mul (local.get $y) (local.get $y)) (local.get $y)) (local.get $a))
+          (f64.mul (f64.const 3) (f64.mul (local.get $y) (local.get $y))))))
+
+    ;; perfect cubes should give exact results
+    (local.set $rounded (f64.nearest (local.get $y)))
+    (if (f64.eq
+          (f64.mul (f64.mul (local.get $rounded) (local.get $rounded)) (local.get $rounded))
+          (local.get $a))
+      (then (local.set $y (local.get $rounded)))
+    )
+    (f64.copysign (local.get $y) (local.get $x))
+  )
+
+  ;; e_pow.c from fdlibm with special cases adjusted to the spec
+  (func $math_pow (param $x f64) (param $y f64) (result f64)
+    (local $hx i32)
+    (local $hy i32)
+    (local $ix i32)
+    (local $iy i32)
+    (local $yisint i32)
+    (local $n i32)
+    (local $k i32)
+    (local $j i32)
+    (local $i i32)
+    (local $sn f64)
+    (local $ax f64)
+    (local $z f64)
+    (local $t f64)
+    (local $w f64)
+    (local $u f64)
+    (local $v f64)
+    (local $t1 f64)
+    (local $t2 f64)
+    (local $ss f64)
+    (local $s2 f64)
+    (local $s_h f64)
+    (local $s_l f64)
+    (local $t_h f64)
+    (local $t_l f64)
+    (local $r f64)
+    (local $p_h f64)
+    (local $p_l f64)
+    (local $z_h f64)
+    (local $z_l f64)
+    (local $y1 f64)
+    (local $bp f64)
+    (local $dp_h f64)
+    (local $dp_l f64)
+
+    (if (f64.ne (local.get $y) (local.get $y))
+      (then (return (f64.const nan)))
+    )
+    (if (f64.eq (local.get $y) (f64.const 0))
+      (then (return (f64.const 1)))
+    )
+    (if (f64.ne (local.get $x) (local.get $x))
+      (then (return (f64.const nan)))
+    )
+
+    (local.set $hx (call $f64_high (local.get $x)))
+    (local.set $hy (call $f64_high (local.get $y)))
+    (local.set $ix (i32.and (local.get $hx) (i32.const 0x7fffffff)))
+    (local.set $iy (i32.and (local.get $hy) (i32.const 0x7fffffff)))
+    (local.set $ax (f64.abs (local.get $x)))
+
+    ;; 0 if y is not an integer, 1 if it's odd and 2 if it's even
+    (if (i32.lt_s (local.get $hx) (i32.const 0))
+      (then
+        (if (call $f64_is_integer (local.get $y))
+          (then
+            (local.set $yisint
+              (select
+                (i32.const 2)
+                (i32.const 1)
+                (i32.or
+                  (f64.ge (f64.abs (local.get $y)) (f64.const 9007199254740992))
+                  (call $f64_is_integer (f64.mul (local.get $y) (f64.const 0.5))))))
+          )
+        )
+      )
+    )
+
+    (if (f64.eq (f64.abs (local.get $y)) (f64.const inf))
+      (then
+        (if (f64.eq (local.get $ax) (f64.const 1))
+          (then (return (f64.const nan)))
+        )
+        (if (f64.gt (local.get $ax) (f64.const 1))
+          (then
+            (return (select (local.get $y) (f64.const 0) (f64.gt (local.get $y) (f64.const 0))))
+          )
+        )
+        (return (select (f64.neg (local.get $y)) (f64.const 0) (f64.lt (local.get $y) (f64.const 0))))
+      )
+    )
+    (if (f64.eq (local.get $y) (f64.const 1))
+      (then (return (local.get $x)))
+    )
+    (if (f64.eq (local.get $y) (f64.const -1))
+      (then (return (f64.div (f64.const 1) (local.get $x))))
+    )
+    (if (f64.eq (local.get $y) (f64.const 2))
+      (then (return (f64.mul (local.get $x) (local.get $x))))
+    )
+    (if (i32.and
+          (f64.eq (local.get $y) (f64.const 0.5))
+          (i32.ge_s (local.get $hx) (i32.const 0)))
+      (then (return (f64.sqrt (local.get $x))))
+    )
+
+    ;; x is +-0, +-inf or +-1
+    (if (i32.or
+          (i32.or (f64.eq (local.get $ax) (f64.const 0)) (f64.eq (local.get $ax) (f64.const inf)))
+          (f64.eq (local.get $ax) (f64.const 1)))
+      (then
+        (local.set $z (local.get $ax))
+        (if (i32.lt_s (local.get $hy) (i32.const 0))
+          (then (local.set $z (f64.div (f64.const 1) (local.get $z))))
+        )
+        (if (i32.lt_s (local.get $hx) (i32.const 0))
+          (then
+            (if (i32.eqz (i32.or (i32.sub (local.get $ix) (i32.const 0x3ff00000)) (local.get $yisint)))
+              (then
+                (local.set $z (f64.const nan))
+              )
+              (else
+                (if (i32.eq (local.get $yisint) (i32.const 1))
+                  (then (local.set $z (f64.neg (local.get $z))))
+                )
+              )
+            )
+          )
+        )
+        (return (local.get $z))
+      )
+    )
+
+    ;; negative number to a non integer power
+    (if (i32.and (i32.lt_s (local.get $hx) (i32.const 0)) (i32.eqz (local.get $yisint)))
+      (then (return (f64.const nan)))
+    )
+    (local.set $sn (f64.const 1))
+    (if (i32.and (i32.lt_s (local.get $hx) (i32.const 0)) (i32.eq (local.get $yisint) (i32.const 1)))
+      (then (local.set $sn (f64.const -1)))
+    )
+
+    (if (i32.gt_u (local.get $iy) (i32.const 0x41e00000))
+      (then
+        ;; |y| > 2^31
+        (if (i32.gt_u (local.get $iy) (i32.const 0x43f00000))
+          (then
+            (if (i32.le_u (local.get $ix) (i32.const 0x3fefffff))
+              (then
+                (return (select (f64.const inf) (f64.const 0) (i32.lt_s (local.get $hy) (i32.const 0))))
+              )
+            )
+            (return (select (f64.const inf) (f64.const 0) (i32.gt_s (local.get $hy) (i32.const 0))))
+          )
+        )
+        ;; over or underflow if x is not close to one
+        (if (i32.lt_u (local.get $ix) (i32.const 0x3fefffff))
+          (then
+            (return
+              (f64.mul
+                (local.get $sn)
+                (select (f64.const inf) (f64.const 0) (i32.lt_s (local.get $hy) (i32.const 0)))))
+          )
+        )
+        (if (i32.gt_u (local.get $ix) (i32.const 0x3ff00000))
+          (then
+            (return
+              (f64.mul
+                (local.get $sn)
+                (select (f64.const inf) (f64.const 0) (i32.gt_s (local.get $hy) (i32.const 0)))))
+          )
+        )
+        ;; log(x) computed with a series, as |1 - x| <= 2^-20
+        (local.set $t (f64.sub (local.get $ax) (f64.const 1)))
+        (local.set $w
+          (f64.mul
+            (f64.mul (local.get $t) (local.get $t))
+            (f64.sub
+              (f64.const 0.5)
+              (f64.mul
+                (local.get $t)
+                (f64.sub (f64.const 0.3333333333333333333333) (f64.mul (local.get $t) (f64.const 0.25)))))))
+        (local.set $u (f64.mul (f64.const 1.44269502162933349609e+00) (local.get $t)))
+        (local.set $v
+          (f64.sub
+            (f64.mul (local.get $t) (f64.const 1.92596299112661746887e-08))
+            (f64.mul (local.get $w) (f64.const 1.44269504088896338700e+00))))
+        (local.set $t1 (call $f64_clear_low (f64.add (local.get $u) (local.get $v))))
+        (local.set $t2 (f64.sub (local.get $v) (f64.sub (local.get $t1) (local.get $u))))
+      )
+      (else
+        ;; log2(ax) computed in extra precision as t1 + t2
+        (local.set $n (i32.const 0))
+        (if (i32.lt_u (local.get $ix) (i32.const 0x00100000))
+          (then
+            (local.set $ax (f64.mul (local.get $ax) (f64.const 9007199254740992)))
+            (local.set $n (i32.const -53))
+            (local.set $ix (call $f64_high (local.get $ax)))
+          )
+        )
+        (local.set $n
+          (i32.add (local.get $n) (i32.sub (i32.shr_s (local.get $ix) (i32.const 20)) (i32.const 0x3ff))))
+        (local.set $j (i32.and (local.get $ix) (i32.const 0x000fffff)))
+        (local.set $ix (i32.or (local.get $j) (i32.const 0x3ff00000)))
+        (if (i32.le_s (local.get $j) (i32.const 0x3988e))
+          (then (local.set $k (i32.const 0)))
+          (else
+            (if (i32.lt_s (local.get $j) (i32.const 0xbb67a))
+              (then (local.set $k (i32.const 1)))
+              (else
+                (local.set $k (i32.const 0))
+                (local.set $n (i32.add (local.get $n) (i32.const 1)))
+                (local.set $ix (i32.sub (local.get $ix) (i32.const 0x00100000)))
+              )
+            )
+          )
+        )
+        (local.set $ax (call $f64_from_words (local.get $ix) (call $f64_low (local.get $ax))))
+
+        (if (local.get $k)
+          (then
+            (local.set $bp (f64.const 1.5))
+            (local.set $dp_h (f64.const 5.84962487220764160156e-01))
+            (local.set $dp_l (f64.const 1.35003920212974897128e-08))
+          )
+          (else
+            (local.set $bp (f64.const 1))
+            (local.set $dp_h (f64.const 0))
+            (local.set $dp_l (f64.const 0))
+          )
+        )
+
+        (local.set $u (f64.sub (local.get $ax) (local.get $bp)))
+        (local.set $v (f64.div (f64.const 1) (f64.add (local.get $ax) (local.get $bp))))
+        (local.set $ss (f64.mul (local.get $u) (local.get $v)))
+        (local.set $s_h (call $f64_clear_low (local.get $ss)))
+        (local.set $t_h
+          (call $f64_from_words
+            (i32.add
+              (i32.add
+                (i32.or (i32.shr_s (local.get $ix) (i32.const 1)) (i32.const 0x20000000))
+                (i32.const 0x00080000))
+              (i32.shl (local.get $k) (i32.const 18)))
+            (i32.const 0)))
+        (local.set $t_l (f64.sub (local.get $ax) (f64.sub (local.get $t_h) (local.get $bp))))
+        (local.set $s_l
+          (f64.mul
+            (local.get $v)
+            (f64.sub
+              (f64.sub (local.get $u) (f64.mul (local.get $s_h) (local.get $t_h)))
+              (f64.mul (local.get $s_h) (local.get $t_l)))))
+
+        (local.set $s2 (f64.mul (local.get $ss) (local.get $ss)))
+        (local.set $r
+          (f64.mul
+            (f64.mul (local.get $s2) (local.get $s2))
+            (f64.add (f64.const 5.99999999999994648725e-01)
+              (f64.mul (local.get $s2)
+                (f64.add (f64.const 4.28571428578550184252e-01)
+                  (f64.mul (local.get $s2)
+                    (f64.add (f64.const 3.33333329818377432918e-01)
+                      (f64.mul (local.get $s2)
+                        (f64.add (f64.const 2.72728123808534006489e-01)
+                          (f64.mul (local.get $s2)
+                            (f64.add (f64.const 2.30660745775561754067e-01)
+                              (f64.mul (local.get $s2) (f64.const 2.06975017800338417784e-01)))))))))))))
+        (local.set $r
+          (f64.add
+            (local.get $r)
+            (f64.mul (local.get $s_l) (f64.add (local.get $s_h) (local.get $ss)))))
+        (local.set $s2 (f64.mul (local.get $s_h) (local.get $s_h)))
+        (local.set $t_h
+          (call $f64_clear_low
+            (f64.add (f64.add (f64.const 3) (local.get $s2)) (local.get $r))))
+        (local.set $t_l
+          (f64.sub
+            (local.get $r)
+            (f64.sub (f64.sub (local.get $t_h) (f64.const 3)) (local.get $s2))))
+
+        (local.set $u (f64.mul (local.get $s_h) (local.get $t_h)))
+        (local.set $v
+          (f64.add
+            (f64.mul (local.get $s_l) (local.get $t_h))
+            (f64.mul (local.get $t_l) (local.get $ss))))
+        (local.set $p_h (call $f64_clear_low (f64.add (local.get $u) (local.get $v))))
+        (local.set $p_l (f64.sub (local.get $v) (f64.sub (local.get $p_h) (local.get $u))))
+        (local.set $z_h (f64.mul (f64.const 9.61796700954437255859e-01) (local.get $p_h)))
+        (local.set $z_l
+          (f64.add
+            (f64.add
+              (f64.mul (f64.const -7.02846165095275826516e-09) (local.get $p_h))
+              (f64.mul (local.get $p_l) (f64.const 9.61796693925975554329e-01)))
+            (local.get $dp_l)))
+
+        (local.set $t (f64.convert_i32_s (local.get $n)))
+        (local.set $t1
+          (call $f64_clear_low
+            (f64.add
+              (f64.add (f64.add (local.get $z_h) (local.get $z_l)) (local.get $dp_h))
+              (local.get $t))))
+        (local.set $t2
+          (f64.sub
+            (local.get $z_l)
+            (f64.sub
+              (f64.sub (f64.sub (local.get $t1) (local.get $t)) (local.get $dp_h))
+              (local.get $z_h))))
+      )
+    )
+
+    ;; y * log2(x) split into p_h + p_l
+    (local.set $y1 (call $f64_clear_low (local.get $y)))
+    (local.set $p_l
+      (f64.add
+        (f64.mul (f64.sub (local.get $y) (local.get $y1)) (local.get $t1))
+        (f64.mul (local.get $y) (local.get $t2))))
+    (local.set $p_h (f64.mul (local.get $y1) (local.get $t1)))
+    (local.set $z (f64.add (local.get $p_l) (local.get $p_h)))
+    (local.set $j (call $f64_high (local.get $z)))
+    (local.set $i (call $f64_low (local.get $z)))
+
+    (if (i32.ge_s (local.get $j) (i32.const 0x40900000))
+      (then
+        ;; z >= 1024
+        (if (i32.or (i32.sub (local.get $j) (i32.const 0x40900000)) (local.get $i))
+          (then (return (f64.mul (local.get $sn) (f64.const inf))))
+        )
+        (if (f64.gt
+              (f64.add (local.get $p_l) (f64.const 8.0085662595372944372e-17))
+              (f64.sub (local.get $z) (local.get $p_h)))
+          (then (return (f64.mul (local.get $sn) (f64.const inf))))
+        )
+      )
+      (else
+        (if (i32.ge_u (i32.and (local.get $j) (i32.const 0x7fffffff)) (i32.const 0x4090cc00))
+          (then
+            ;; z <= -1075
+            (if (i32.or (i32.sub (local.get $j) (i32.const 0xc090cc00)) (local.get $i))
+              (then (return (f64.mul (local.get $sn) (f64.const 0))))
+            )
+            (if (f64.le (local.get $p_l) (f64.sub (local.get $z) (local.get $p_h)))
+              (then (return (f64.mul (local.get $sn) (f64.const 0))))
+            )
+          )
+        )
+      )
+    )
+
+    ;; 2^(p_h + p_l)
+    (local.set $i (i32.and (local.get $j) (i32.const 0x7fffffff)))
+    (local.set $k (i32.sub (i32.shr_u (local.get $i) (i32.const 20)) (i32.const 0x3ff)))
+    (local.set $n (i32.const 0))
+    (if (i32.gt_u (local.get $i) (i32.const 0x3fe00000))
+      (then
+        (local.set $n
+          (i32.add
+            (local.get $j)
+            (i32.shr_u (i32.const 0x00100000) (i32.add (local.get $k) (i32.const 1)))))
+        (local.set $k
+          (i32.sub
+            (i32.shr_u (i32.and (local.get $n) (i32.const 0x7fffffff)) (i32.const 20))
+            (i32.const 0x3ff)))
+        (local.set $t
+          (call $f64_from_words
+            (i32.and
+              (local.get $n)
+              (i32.xor (i32.shr_u (i32.const 0x000fffff) (local.get $k)) (i32.const -1)))
+            (i32.const 0)))
+        (local.set $n
+          (i32.shr_u
+            (i32.or (i32.and (local.get $n) (i32.const 0x000fffff)) (i32.const 0x00100000))
+            (i32.sub (i32.const 20) (local.get $k))))
+        (if (i32.lt_s (local.get $j) (i32.const 0))
+          (then (local.set $n (i32.sub (i32.const 0) (local.get $n))))
+        )
+        (local.set $p_h (f64.sub (local.get $p_h) (local.get $t)))
+      )
+    )
+
+    (local.set $t (call $f64_clear_low (f64.add (local.get $p_l) (local.get $p_h))))
+    (local.set $u (f64.mul (local.get $t) (f64.const 6.93147182464599609375e-01)))
+    (local.set $v
+      (f64.add
+        (f64.mul
+          (f64.sub (local.get $p_l) (f64.sub (local.get $t) (local.get $p_h)))
+          (f64.const 6.93147180559945286227e-01))
+        (f64.mul (local.get $t) (f64.const -1.90465429995776804525e-09))))
+    (local.set $z (f64.add (local.get $u) (local.get $v)))
+    (local.set $w (f64.sub (local.get $v) (f64.sub (local.get $z) (local.get $u))))
+    (local.set $t (f64.mul (local.get $z) (local.get $z)))
+    (local.set $t1
+      (f64.sub
+        (local.get $z)
+        (f64.mul (local.get $t)
+          (f64.add (f64.const 1.66666666666666019037e-01)
+            (f64.mul (local.get $t)
+              (f64.add (f64.const -2.77777777770155933842e-03)
+                (f64.mul (local.get $t)
+                  (f64.add (f64.const 6.61375632143793436117e-05)
+                    (f64.mul (local.get $t)
+                      (f64.add (f64.const -1.65339022054652515390e-06)
+                        (f64.mul (local.get $t) (f64.const 4.13813679705723846039e-08))))))))))))
+    (local.set $r
+      (f64.sub
+        (f64.div
+          (f64.mul (local.get $z) (local.get $t1))
+          (f64.sub (local.get $t1) (f64.const 2)))
+        (f64.add (local.get $w) (f64.mul (local.get $z) (local.get $w)))))
+    (local.set $z (f64.sub (f64.const 1) (f64.sub (local.get $r) (local.get $z))))
+
+    (local.set $j
+      (i32.add (call $f64_high (local.get $z)) (i32.shl (local.get $n) (i32.const 20))))
+    (if (i32.le_s (i32.shr_s (local.get $j) (i32.const 20)) (i32.const 0))
+      (then (local.set $z (call $f64_scale (local.get $z) (local.get $n))))
+      (else (local.set $z (call $f64_from_words (local.get $j) (call $f64_low (local.get $z)))))
+    )
+    (f64.mul (local.get $sn) (local.get $z))
+  )
+
+  ;; splitmix64, turns the seed into the two words of the state, which can't
+  ;; both be zero
+  (func $splitmix64 (param $x i64) (result i64)
+    (local.set $x
+      (i64.mul
+        (i64.xor (local.get $x) (i64.shr_u (local.get $x) (i64.const 30)))
+        (i64.const 0xbf58476d1ce4e5b9)))
+    (local.set $x
+      (i64.mul
+        (i64.xor (local.get $x) (i64.shr_u (local.get $x) (i64.const 27)))
+        (i64.const 0x94d049bb133111eb)))
+    (i64.xor (local.get $x) (i64.shr_u (local.get $x) (i64.const 31)))
+  )
+
+  ;; the seed comes from wasi:random/random, unless the module was compiled
+  ;; with JAWS_RANDOM_SEED to get the same numbers on every run
+  (func $seed_math_random
+    (local $seed i64)
+
+{% if random_seed is defined %}
+    (local.set $seed (i64.const {{ random_seed }}))
+{% elif random %}
+    (local.set $seed (call $get-random-u64))
+{% else %}
+    ;; the script never names `random`, so get-random-u64 wasn't imported and
+    ;; a fixed seed would give numbers nobody asked to be reproducible
+    (call $throw_type_error
+      (call $new_static_string
+        (i32.const {{ data(str="Math.random is not available, wasi:random is only imported by scripts mentioning random") }})
+        (i32.const {{ data_length() }})))
+{% endif %}
+    (local.set $seed (i64.add (local.get $seed) (i64.const 0x9e3779b97f4a7c15)))
+    (global.set $random-state0 (call $splitmix64 (local.get $seed)))
+    (local.set $seed (i64.add (local.get $seed) (i64.const 0x9e3779b97f4a7c15)))
+    (global.set $random-state1 (call $splitmix64 (local.get $seed)))
+    (global.set $random-seeded (i32.const 1))
+  )
+
+  ;; xorshift128+, like V8
+  (func $math_random (result f64)
+    (local $s0 i64)
+    (local $s1 i64)
+
+    (if (i32.eqz (global.get $random-seeded))
+      (then (call $seed_math_random))
+    )
+    (local.set $s1 (global.get $random-state0))
+    (local.set $s0 (global.get $random-state1))
+    (global.set $random-state0 (local.get $s0))
+    (local.set $s1 (i64.xor (local.get $s1) (i64.shl (local.get $s1) (i64.const 23))))
+    (local.set $s1
+      (i64.xor
+        (i64.xor (local.get $s1) (local.get $s0))
+        (i64.xor
+          (i64.shr_u (local.get $s1) (i64.const 17))
+          (i64.shr_u (local.get $s0) (i64.const 26)))))
+    (global.set $random-state1 (local.get $s1))
+
+    ;; the top 53 bits make a number in [0, 1)
+    (f64.mul
+      (f64.convert_i64_u (i64.shr_u (i64.add (local.get $s0) (local.get $s1)) (i64.const 11)))
+      (f64.const 0x1p-53))
+  )
+
+  {% set unary = ["abs", "acos", "acosh", "asin", "asinh", "atan", "atanh", "cbrt", "ceil", "clz32", "cos", "cosh", "exp", "expm1", "floor", "fround", "log", "log1p", "log10", "log2", "round", "sign", "sin", "sinh", "sqrt", "tan", "tanh", "trunc"] %}
+  {% set binary = ["atan2", "imul", "pow"] %}
+
+  {% for name in unary %}
+  (func $Math-{{ name }}
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+
+    (call $new_number
+      (call $math_{{ name }} (call $number_argument (local.get $arguments) (i32.const 0))))
+  )
+  (elem declare func $Math-{{ name }})
+  {% endfor %}
+
+  {% for name in binary %}
+  (func $Math-{{ name }}
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+
+    (call $new_number
+      (call $math_{{ name }}
+        (call $number_argument (local.get $arguments) (i32.const 0))
+        (call $number_argument (local.get $arguments) (i32.const 1))))
+  )
+  (elem declare func $Math-{{ name }})
+  {% endfor %}
+
+  (func $Math-max
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (local $result f64)
+    (local $i i32)
+
+    ;; f64.max propagates NaN and orders -0 before 0, just like the spec requires
+    (local.set $result (f64.const -inf))
+    (block $break (loop $arguments
+      (br_if $break (i32.ge_u (local.get $i) (array.len (local.get $arguments))))
+      (local.set $result
+        (f64.max (local.get $result) (call $number_argument (local.get $arguments) (local.get $i))))
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $arguments)
+    ))
+    (call $new_number (local.get $result))
+  )
+
+  (func $Math-min
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (local $result f64)
+    (local $i i32)
+
+    (local.set $result (f64.const inf))
+    (block $break (loop $arguments
+      (br_if $break (i32.ge_u (local.get $i) (array.len (local.get $arguments))))
+      (local.set $result
+        (f64.min (local.get $result) (call $number_argument (local.get $arguments) (local.get $i))))
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $arguments)
+    ))
+    (call $new_number (local.get $result))
+  )
+
+  (func $Math-hypot
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (local $values (ref $JSArgs))
+    (local $value f64)
+    (local $max f64)
+    (local $any_nan i32)
+    (local $sum f64)
+    (local $compensation f64)
+    (local $term f64)
+    (local $next f64)
+    (local $i i32)
+    (local $len i32)
+
+    (local.set $len (array.len (local.get $arguments)))
+    (local.set $values (array.new $JSArgs (ref.null any) (local.get $len)))
+    (block $break (loop $convert
+      (br_if $break (i32.ge_u (local.get $i) (local.get $len)))
+      (local.set $value (f64.abs (call $number_argument (local.get $arguments) (local.get $i))))
+      (array.set $JSArgs (local.get $values) (local.get $i) (call $new_number (local.get $value)))
+      ;; infinity wins over NaN
+      (if (f64.eq (local.get $value) (f64.const inf))
+        (then (return (call $new_number (f64.const inf))))
+      )
+      (if (f64.ne (local.get $value) (local.get $value))
+        (then (local.set $any_nan (i32.const 1)))
+        (else (local.set $max (f64.max (local.get $max) (local.get $value))))
+      )
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $convert)
+    ))
+
+    (if (local.get $any_nan)
+      (then (return (call $new_number (f64.const nan))))
+    )
+    (if (f64.eq (local.get $max) (f64.const 0))
+      (then (return (call $new_number (f64.const 0))))
+    )
+
+    ;; scale by the biggest value to avoid overflow, sum with Kahan summation
+    (local.set $i (i32.const 0))
+    (block $break (loop $sum
+      (br_if $break (i32.ge_u (local.get $i) (local.get $len)))
+      (local.set $value
+        (f64.div
+          (call $number_value (array.get $JSArgs (local.get $values) (local.get $i)))
+          (local.get $max)))
+      (local.set $term
+        (f64.sub (f64.mul (local.get $value) (local.get $value)) (local.get $compensation)))
+      (local.set $next (f64.add (local.get $sum) (local.get $term)))
+      (local.set $compensation
+        (f64.sub (f64.sub (local.get $next) (local.get $sum)) (local.get $term)))
+      (local.set $sum (local.get $next))
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $sum)
+    ))
+
+    (call $new_number (f64.mul (f64.sqrt (local.get $sum)) (local.get $max)))
+  )
+
+  (func $Math-random
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+
+    (call $new_number (call $math_random))
+  )
+
+  (elem declare func $Math-max)
+  (elem declare func $Math-min)
+  (elem declare func $Math-hypot)
+  (elem declare func $Math-random)
+
+  (func $create-math-object (result (ref $Object))
+    (local $math (ref $Object))
+    (local.set $math (call $new_object))
+
+    {% for name in unary %}
+    (call $add_method (local.get $math) (i32.const {{ data(str=name) }}) (ref.func $Math-{{ name }}))
+    {%- endfor %}
+    {% for name in binary %}
+    (call $add_method (local.get $math) (i32.const {{ data(str=name) }}) (ref.func $Math-{{ name }}))
+    {%- endfor %}
+    (call $add_method (local.get $math) (i32.const {{ data(str="max") }}) (ref.func $Math-max))
+    (call $add_method (local.get $math) (i32.const {{ data(str="min") }}) (ref.func $Math-min))
+    (call $add_method (local.get $math) (i32.const {{ data(str="hypot") }}) (ref.func $Math-hypot))
+    (call $add_method (local.get $math) (i32.const {{ data(str="random") }}) (ref.func $Math-random))
+
+    (call $set_property (local.get $math) (i32.const {{ data(str="E") }}) (call $new_number (f64.const 2.718281828459045)))
+    (call $set_property (local.get $math) (i32.const {{ data(str="LN10") }}) (call $new_number (f64.const 2.302585092994046)))
+    (call $set_property (local.get $math) (i32.const {{ data(str="LN2") }}) (call $new_number (f64.const 0.6931471805599453)))
+    (call $set_property (local.get $math) (i32.const {{ data(str="LOG10E") }}) (call $new_number (f64.const 0.4342944819032518)))
+    (call $set_property (local.get $math) (i32.const {{ data(str="LOG2E") }}) (call $new_number (f64.const 1.4426950408889634)))
+    (call $set_property (local.get $math) (i32.const {{ data(str="PI") }}) (call $new_number (f64.const 3.141592653589793)))
+    (call $set_property (local.get $math) (i32.const {{ data(str="SQRT1_2") }}) (call $new_number (f64.const 0.7071067811865476)))
+    (call $set_property (local.get $math) (i32.const {{ data(str="SQRT2") }}) (call $new_number (f64.const 1.4142135623730951)))
+    (call $set_to_string_tag (local.get $math)
+      (call $new_static_string (i32.const {{ data(str="Math") }}) (i32.const {{ data_length() }})))
+
+    (local.get $math)
+  )
@@ -0,0 +1,138 @@
+  ;; The linear memory allocator, used for everything exchanged with the host:
+  ;; iovecs for fd_write, records returned through pointers, the list passed to
+  ;; `poll` and whatever the host allocates with cabi_realloc.
+  ;;
+  ;; Blocks come in power of two size classes starting at 16 bytes, each with
+  ;; an 8 byte header holding its class, so pointers handed out stay 8 byte
+  ;; aligned, which is the biggest alignment the canonical ABI needs. Freed
+  ;; blocks go to the free list of their class and new ones are bumped from
+  ;; $free_memory_offset, right after the static data, growing the memory if
+  ;; needed.
+
+  (global $memory-min-class i32 (i32.const 4))
+  (global $memory-header-size i32 (i32.const 8))
+  ;; the first free block of every size class, 0 if there's none, the next
+  ;; one is stored at the start of the block's data
+  (global $memory-free-lists (mut (ref null $I32Array)) (ref.null $I32Array))
+
+  (func $memory_size_class (param $size i32) (result i32)
+    (local $class i32)
+
+    (local.set $class
+      (i32.sub
+        (i32.const 32)
+        (i32.clz (i32.sub (i32.add (local.get $size) (global.get $memory-header-size)) (i32.const 1)))))
+    (if (result i32) (i32.lt_u (local.get $class) (global.get $memory-min-class))
+      (then (global.get $memory-min-class))
+      (else (local.get $class)))
+  )
+
+  (func $ensure_memory (param $end i32)
+    (local $available i32)
+
+    (local.set $available (i32.mul (memory.size) (i32.const 65536)))
+    (if (i32.gt_u (local.get $end) (local.get $available))
+      (then
+        (if (i32.eq
+              (memory.grow
+                (i32.shr_u
+                  (i32.add (i32.sub (local.get $end) (local.get $available)) (i32.const 65535))
+                  (i32.const 16)))
+              (i32.const -1))
+          (then (unreachable))
+        )
+      )
+    )
+  )
+
+  ;; returns a pointer to at least `size` bytes
+  (func $malloc (param $size i32) (result i32)
+    (local $class i32)
+    (local $block i32)
+    (local $end i32)
+
+    (if (ref.is_null (global.get $memory-free-lists))
+      (then (global.set $memory-free-lists (array.new $I32Array (i32.const 0) (i32.const 32))))
+    )
+    (local.set $class (call $memory_size_class (local.get $size)))
+    ;; the biggest block would have to span the whole address space
+    (if (i32.gt_u (local.get $class) (i32.const 31))
+      (then (unreachable))
+    )
+
+    (local.set $block (array.get $I32Array (ref.as_non_null (global.get $memory-free-lists)) (local.get $class)))
+    (if (local.get $block)
+      (then
+        (array.set $I32Array
+          (ref.as_non_null (global.get $memory-free-lists))
+          (local.get $class)
+          (i32.load (i32.add (local.get $block) (global.get $memory-header-size))))
+      )
+      (else
+        (local.set $block
+          (i32.and (i32.add (global.get $free_memory_offset) (i32.const 7)) (i32.const -8)))
+        (local.set $end (i32.add (local.get $block) (i32.shl (i32.const 1) (local.get $class))))
+        (call $ensure_memory (local.get $end))
+        (global.set $free_memory_offset (local.get $end))
+        (i32.store (local.get $block) (local.get $class))
+      )
+    )
+
+    (i32.add (local.get $block) (global.get $memory-header-size))
+  )
+
+  (func $free (param $ptr i32)
+    (local $block i32)
+    (local $class i32)
+
+    (if (i32.eqz (local.get $ptr))
+      (then (return))
+    )
+    (local.set $block (i32.sub (local.get $ptr) (global.get $memory-header-size)))
+    (local.set $class (i32.load (local.get $block)))
+    (i32.store
+      (local.get $ptr)
+      (array.get $I32Array (ref.as_non_null (global.get $memory-free-lists)) (local.get $class)))
+    (array.set $I32Array (ref.as_non_null (global.get $memory-free-lists)) (local.get $class) (local.get $block))
+  )
+
+  ;; moves the data to a bigger block if it doesn't fit in the current one
+  (func $realloc (param $ptr i32) (param $old_size i32) (param $new_size i32) (result i32)
+    (local $capacity i32)
+    (local $new_ptr i32)
+
+    (if (i32.eqz (local.get $ptr))
+      (then (return (call $malloc (local.get $new_size))))
+    )
+    (local.set $capacity
+      (i32.sub
+        (i32.shl (i32.const 1) (i32.load (i32.sub (local.get $ptr) (global.get $memory-header-size))))
+        (global.get $memory-header-size)))
+    (if (i32.le_u (local.get $new_size) (local.get $capacity))
+      (then (return (local.get $ptr)))
+    )
+
+    (local.set $new_ptr (call $malloc (local.get $new_size)))
+    (memory.copy
+      (local.get $new_ptr)
+      (local.get $ptr)
+      (if (result i32) (i32.lt_u (local.get $old_size) (local.get $new_size))
+        (then (local.get $old_size))
+        (else (local.get $new_size))))
+    (call $free (local.get $ptr))
+    (local.get $new_ptr)
+  )
+
+  ;; Memory management functions required by the Component Model, hosts use
+  ;; them for lists and strings returned from imports, which have to be freed
+  ;; once they're read
+  (func $cabi_realloc (export "cabi_realloc")
+    (param $old_ptr i32) (param $old_size i32) (param $align i32) (param $new_size i32)
+    (result i32)
+    (call $realloc (local.get $old_ptr) (local.get $old_size) (local.get $new_size))
+  )
+
+  (func $canonical_abi_free (export "canonical_abi_free")
+    (param $ptr i32) (param $size i32)
+    (call $free (local.get $ptr))
+  )
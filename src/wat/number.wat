@@ -0,0 +1,1764 @@
+  ;; Number conversions and the `Number` builtin
+  ;;
+  ;; Formatting and parsing is exact: decimal digits are generated with bignums,
+  ;; using the free-format algorithm from "Printing Floating-Point Numbers Quickly
+  ;; and Accurately" by Burger and Dybvig, and parsed numbers are correctly rounded
+
+  (global $number-prototype (mut (ref null $Object)) (ref.null $Object))
+
+  (func $f64_pow2 (param $exponent i32) (result f64)
+    (f64.reinterpret_i64
+      (i64.shl
+        (i64.extend_i32_u (i32.add (local.get $exponent) (i32.const 1023)))
+        (i64.const 52)))
+  )
+
+  ;; multiplies a number by 2^exponent. If the result is a subnormal number it's
+  ;; rounded only once
+  (func $f64_scale (param $value f64) (param $exponent i32) (result f64)
+    (if (i32.gt_s (local.get $exponent) (i32.const 1023))
+      (then
+        (local.set $value (f64.mul (local.get $value) (call $f64_pow2 (i32.const 1023))))
+        (local.set $exponent (i32.sub (local.get $exponent) (i32.const 1023)))
+        (if (i32.gt_s (local.get $exponent) (i32.const 1023))
+          (then (local.set $exponent (i32.const 1023)))
+        )
+      )
+    )
+
+    (if (i32.lt_s (local.get $exponent) (i32.const -1022))
+      (then
+        (if (i32.lt_s (local.get $exponent) (i32.const -2000))
+          (then (local.set $exponent (i32.const -2000)))
+        )
+        (local.set $value
+          (f64.mul (local.get $value) (call $f64_pow2 (i32.add (local.get $exponent) (i32.const 1000)))))
+        (local.set $exponent (i32.const -1000))
+      )
+    )
+
+    (f64.mul (local.get $value) (call $f64_pow2 (local.get $exponent)))
+  )
+
+  ;; splits a finite, non negative number into an integer mantissa and an
+  ;; exponent, so that value = mantissa * 2^exponent
+  (func $f64_decompose (param $value f64) (result i64 i32)
+    (local $bits i64)
+    (local $biased i32)
+    (local $mantissa i64)
+
+    (local.set $bits (i64.reinterpret_f64 (local.get $value)))
+    (local.set $biased
+      (i32.and (i32.wrap_i64 (i64.shr_u (local.get $bits) (i64.const 52))) (i32.const 0x7ff)))
+    (local.set $mantissa (i64.and (local.get $bits) (i64.const 0xfffffffffffff)))
+
+    ;; subnormal numbers don't have the implicit leading bit
+    (if (i32.eqz (local.get $biased))
+      (then
+        (return (local.get $mantissa) (i32.const -1074))
+      )
+    )
+
+    (i64.or (local.get $mantissa) (i64.const 0x10000000000000))
+    (i32.sub (local.get $biased) (i32.const 1075))
+  )
+
+  (func $f64_is_integer (param $value f64) (result i32)
+    (i32.and
+      (f64.eq (local.get $value) (f64.trunc (local.get $value)))
+      (f64.ne (f64.abs (local.get $value)) (f64.const inf)))
+  )
+
+  ;; writes decimal digits of a non negative integer, returns the position after
+  ;; the last digit
+  (func $write_integer (param $buffer (ref $CharArray)) (param $position i32) (param $value i64) (result i32)
+    (local $length i32)
+    (local $temp i64)
+    (local $i i32)
+
+    (local.set $temp (local.get $value))
+    (loop $count
+      (local.set $length (i32.add (local.get $length) (i32.const 1)))
+      (local.set $temp (i64.div_u (local.get $temp) (i64.const 10)))
+      (br_if $count (i64.ne (local.get $temp) (i64.const 0)))
+    )
+
+    (local.set $i (local.get $length))
+    (loop $write
+      (local.set $i (i32.sub (local.get $i) (i32.const 1)))
+      (array.set $CharArray (local.get $buffer)
+        (i32.add (local.get $position) (local.get $i))
+        (i32.add (i32.wrap_i64 (i64.rem_u (local.get $value) (i64.const 10))) (i32.const 48)))
+      (local.set $value (i64.div_u (local.get $value) (i64.const 10)))
+      (br_if $write (local.get $i))
+    )
+
+    (i32.add (local.get $position) (local.get $length))
+  )
+
+  (func $write_char (param $buffer (ref $CharArray)) (param $position i32) (param $char i32) (result i32)
+    (array.set $CharArray (local.get $buffer) (local.get $position) (local.get $char))
+    (i32.add (local.get $position) (i32.const 1))
+  )
+
+  (func $write_chars (param $buffer (ref $CharArray)) (param $position i32) (param $char i32) (param $count i32) (result i32)
+    (block $break (loop $write
+      (br_if $break (i32.le_s (local.get $count) (i32.const 0)))
+      (array.set $CharArray (local.get $buffer) (local.get $position) (local.get $char))
+      (local.set $position (i32.add (local.get $position) (i32.const 1)))
+      (local.set $count (i32.sub (local.get $count) (i32.const 1)))
+      (br $write)
+    ))
+    (local.get $position)
+  )
+
+  (func $write_digits (param $buffer (ref $CharArray)) (param $position i32) (param $digits (ref $CharArray)) (param $from i32) (param $to i32) (result i32)
+    (if (i32.gt_s (local.get $to) (local.get $from))
+      (then
+        (array.copy $CharArray $CharArray
+          (local.get $buffer) (local.get $position)
+          (local.get $digits) (local.get $from)
+          (i32.sub (local.get $to) (local.get $from)))
+        (return (i32.add (local.get $position) (i32.sub (local.get $to) (local.get $from))))
+      )
+    )
+    (local.get $position)
+  )
+
+  ;; writes the exponent part, like "e+21" or "e-7"
+  (func $write_exponent (param $buffer (ref $CharArray)) (param $position i32) (param $exponent i32) (result i32)
+    (local.set $position (call $write_char (local.get $buffer) (local.get $position) (i32.const 101)))
+    (local.set $position
+      (call $write_char (local.get $buffer) (local.get $position)
+        (select (i32.const 45) (i32.const 43) (i32.lt_s (local.get $exponent) (i32.const 0)))))
+    (if (i32.lt_s (local.get $exponent) (i32.const 0))
+      (then (local.set $exponent (i32.sub (i32.const 0) (local.get $exponent))))
+    )
+    (call $write_integer (local.get $buffer) (local.get $position) (i64.extend_i32_u (local.get $exponent)))
+  )
+
+  ;; Generates the shortest digits that uniquely identify a positive, finite
+  ;; number. Returns the digits (as ASCII characters), their count and an exponent n,
+  ;; such that value = 0.d1d2d3... * 10^n
+  (func $number_to_digits (param $value f64) (result (ref $CharArray) i32 i32)
+    (local $mantissa i64)
+    (local $exponent i32)
+    (local $even i32)
+    (local $r (ref $BigDigits))
+    (local $s (ref $BigDigits))
+    (local $m_plus (ref $BigDigits))
+    (local $m_minus (ref $BigDigits))
+    (local $k i32)
+    (local $digits (ref $CharArray))
+    (local $count i32)
+    (local $digit i32)
+    (local $low i32)
+    (local $high i32)
+
+    (call $f64_decompose (local.get $value))
+    (local.set $exponent)
+    (local.set $mantissa)
+    (local.set $even (i64.eqz (i64.and (local.get $mantissa) (i64.const 1))))
+
+    ;; value = r / s, the boundaries between this number and its neighbours
+    ;; are (r - m_minus) / s and (r + m_plus) / s
+    (if (result (ref $BigDigits) (ref $BigDigits) (ref $BigDigits) (ref $BigDigits))
+      (i32.ge_s (local.get $exponent) (i32.const 0))
+      (then
+        (if (result (ref $BigDigits) (ref $BigDigits) (ref $BigDigits) (ref $BigDigits))
+          (i64.ne (local.get $mantissa) (i64.const 0x10000000000000))
+          (then
+            (call $big_shl (call $big_from_u64 (local.get $mantissa)) (i32.add (local.get $exponent) (i32.const 1)))
+            (call $big_from_u64 (i64.const 2))
+            (call $big_shl (call $big_from_u64 (i64.const 1)) (local.get $exponent))
+            (call $big_shl (call $big_from_u64 (i64.const 1)) (local.get $exponent))
+          )
+          (else
+            ;; the gap to the lower neighbour is smaller for powers of two
+            (call $big_shl (call $big_from_u64 (local.get $mantissa)) (i32.add (local.get $exponent) (i32.const 2)))
+            (call $big_from_u64 (i64.const 4))
+            (call $big_shl (call $big_from_u64 (i64.const 1)) (i32.add (local.get $exponent) (i32.const 1)))
+            (call $big_shl (call $big_from_u64 (i64.const 1)) (local.get $exponent))
+          )
+        )
+      )
+      (else
+        (if (result (ref $BigDigits) (ref $BigDigits) (ref $BigDigits) (ref $BigDigits))
+          (i32.or
+            (i32.eq (local.get $exponent) (i32.const -1074))
+            (i64.ne (local.get $mantissa) (i64.const 0x10000000000000)))
+          (then
+            (call $big_from_u64 (i64.shl (local.get $mantissa) (i64.const 1)))
+            (call $big_shl (call $big_from_u64 (i64.const 1)) (i32.sub (i32.const 1) (local.get $exponent)))
+            (call $big_from_u64 (i64.const 1))
+            (call $big_from_u64 (i64.const 1))
+          )
+          (else
+            (call $big_from_u64 (i64.shl (local.get $mantissa) (i64.const 2)))
+            (call $big_shl (call $big_from_u64 (i64.const 1)) (i32.sub (i32.const 2) (local.get $exponent)))
+            (call $big_from_u64 (i64.const 2))
+            (call $big_from_u64 (i64.const 1))
+          )
+        )
+      )
+    )
+    (local.set $m_minus)
+    (local.set $m_plus)
+    (local.set $s)
+    (local.set $r)
+
+    ;; estimate k = ceil(log10(value)) based on the bit length, it can be too small
+    ;; by one, which is fixed below
+    (local.set $k
+      (i32.trunc_f64_s
+        (f64.ceil
+          (f64.sub
+            (f64.mul
+              (f64.convert_i32_s
+                (i32.sub
+                  (i32.add (local.get $exponent) (i32.sub (i32.const 64) (i32.wrap_i64 (i64.clz (local.get $mantissa)))))
+                  (i32.const 1)))
+              (f64.const 0.30102999566398114))
+            (f64.const 1e-10)))))
+
+    (if (i32.ge_s (local.get $k) (i32.const 0))
+      (then
+        (local.set $s (call $big_mul_pow10 (local.get $s) (local.get $k)))
+      )
+      (else
+        (local.set $r (call $big_mul_pow10 (local.get $r) (i32.sub (i32.const 0) (local.get $k))))
+        (local.set $m_plus (call $big_mul_pow10 (local.get $m_plus) (i32.sub (i32.const 0) (local.get $k))))
+        (local.set $m_minus (call $big_mul_pow10 (local.get $m_minus) (i32.sub (i32.const 0) (local.get $k))))
+      )
+    )
+
+    (block $break (loop $fixup
+      (local.set $high (call $big_compare (call $big_add (local.get $r) (local.get $m_plus)) (local.get $s)))
+      (br_if $break
+        (i32.eqz
+          (select
+            (i32.ge_s (local.get $high) (i32.const 0))
+            (i32.gt_s (local.get $high) (i32.const 0))
+            (local.get $even))))
+      (local.set $s (call $big_mul_small (local.get $s) (i32.const 10)))
+      (local.set $k (i32.add (local.get $k) (i32.const 1)))
+      (br $fixup)
+    ))
+
+    (local.set $digits (array.new_default $CharArray (i32.const 20)))
+    (block $done (loop $generate
+      (local.set $r (call $big_mul_small (local.get $r) (i32.const 10)))
+      (local.set $m_plus (call $big_mul_small (local.get $m_plus) (i32.const 10)))
+      (local.set $m_minus (call $big_mul_small (local.get $m_minus) (i32.const 10)))
+
+      (local.set $digit (i32.const 0))
+      (block $break (loop $divide
+        (br_if $break (i32.lt_s (call $big_compare (local.get $r) (local.get $s)) (i32.const 0)))
+        (local.set $r (call $big_sub (local.get $r) (local.get $s)))
+        (local.set $digit (i32.add (local.get $digit) (i32.const 1)))
+        (br $divide)
+      ))
+
+      (local.set $low (call $big_compare (local.get $r) (local.get $m_minus)))
+      (local.set $low
+        (select
+          (i32.le_s (local.get $low) (i32.const 0))
+          (i32.lt_s (local.get $low) (i32.const 0))
+          (local.get $even)))
+      (local.set $high (call $big_compare (call $big_add (local.get $r) (local.get $m_plus)) (local.get $s)))
+      (local.set $high
+        (select
+          (i32.ge_s (local.get $high) (i32.const 0))
+          (i32.gt_s (local.get $high) (i32.const 0))
+          (local.get $even)))
+
+      (if (i32.and (local.get $low) (local.get $high))
+        (then
+          ;; both digits are within the boundaries, pick the closer one
+          (if (i32.ge_s
+                (call $big_compare (call $big_shl (local.get $r) (i32.const 1)) (local.get $s))
+                (i32.const 0))
+            (then (local.set $digit (i32.add (local.get $digit) (i32.const 1))))
+          )
+        )
+        (else
+          (if (local.get $high)
+            (then (local.set $digit (i32.add (local.get $digit) (i32.const 1))))
+          )
+        )
+      )
+
+      (array.set $CharArray (local.get $digits) (local.get $count) (i32.add (local.get $digit) (i32.const 48)))
+      (local.set $count (i32.add (local.get $count) (i32.const 1)))
+      (br_if $generate (i32.eqz (i32.or (local.get $low) (local.get $high))))
+    ))
+
+    (local.get $digits)
+    (local.get $count)
+    (local.get $k)
+  )
+
+  ;; Number::toString(x) from the spec
+  (func $number_to_string (param $value f64) (result (ref $String))
+    (local $buffer (ref $CharArray))
+    (local $position i32)
+    (local $digits (ref $CharArray))
+    (local $count i32)
+    (local $n i32)
+
+    (if (f64.ne (local.get $value) (local.get $value))
+      (then (return (call $static_to_string (i32.const {{ data(str="NaN") }}) (i32.const {{ data_length() }}))))
+    )
+    (if (f64.eq (local.get $value) (f64.const 0))
+      (then (return (call $static_to_string (i32.const {{ data(str="0") }}) (i32.const {{ data_length() }}))))
+    )
+    (if (f64.eq (local.get $value) (f64.const inf))
+      (then (return (call $static_to_string (i32.const {{ data(str="Infinity") }}) (i32.const {{ data_length() }}))))
+    )
+    (if (f64.eq (local.get $value) (f64.const -inf))
+      (then (return (call $static_to_string (i32.const {{ data(str="-Infinity") }}) (i32.const {{ data_length() }}))))
+    )
+
+    (local.set $buffer (array.new_default $CharArray (i32.const 32)))
+    (if (f64.lt (local.get $value) (f64.const 0))
+      (then
+        (local.set $position (call $write_char (local.get $buffer) (local.get $position) (i32.const 45)))
+        (local.set $value (f64.neg (local.get $value)))
+      )
+    )
+
+    ;; integers that can be represented exactly are their own shortest representation
+    (if (i32.and
+          (f64.eq (local.get $value) (f64.trunc (local.get $value)))
+          (f64.lt (local.get $value) (f64.const 9007199254740992)))
+      (then
+        (local.set $position
+          (call $write_integer (local.get $buffer) (local.get $position) (i64.trunc_f64_u (local.get $value))))
+        (return (struct.new $String (local.get $buffer) (local.get $position)))
+      )
+    )
+
+    (call $number_to_digits (local.get $value))
+    (local.set $n)
+    (local.set $count)
+    (local.set $digits)
+
+    (if (i32.and
+          (i32.le_s (local.get $count) (local.get $n))
+          (i32.le_s (local.get $n) (i32.const 21)))
+      (then
+        ;; an integer, possibly with some trailing zeros
+        (local.set $position
+          (call $write_digits (local.get $buffer) (local.get $position) (local.get $digits) (i32.const 0) (local.get $count)))
+        (local.set $position
+          (call $write_chars (local.get $buffer) (local.get $position) (i32.const 48) (i32.sub (local.get $n) (local.get $count))))
+        (return (struct.new $String (local.get $buffer) (local.get $position)))
+      )
+    )
+
+    (if (i32.and
+          (i32.gt_s (local.get $n) (i32.const 0))
+          (i32.le_s (local.get $n) (i32.const 21)))
+      (then
+        (local.set $position
+          (call $write_digits (local.get $buffer) (local.get $position) (local.get $digits) (i32.const 0) (local.get $n)))
+        (local.set $position (call $write_char (local.get $buffer) (local.get $position) (i32.const 46)))
+        (local.set $position
+          (call $write_digits (local.get $buffer) (local.get $position) (local.get $digits) (local.get $n) (local.get $count)))
+        (return (struct.new $String (local.get $buffer) (local.get $position)))
+      )
+    )
+
+    (if (i32.and
+          (i32.gt_s (local.get $n) (i32.const -6))
+          (i32.le_s (local.get $n) (i32.const 0)))
+      (then
+        (local.set $position (call $write_char (local.get $buffer) (local.get $position) (i32.const 48)))
+        (local.set $position (call $write_char (local.get $buffer) (local.get $position) (i32.const 46)))
+        (local.set $position
+          (call $write_chars (local.get $buffer) (local.get $position) (i32.const 48) (i32.sub (i32.const 0) (local.get $n))))
+        (local.set $position
+          (call $write_digits (local.get $buffer) (local.get $position) (local.get $digits) (i32.const 0) (local.get $count)))
+        (return (struct.new $String (local.get $buffer) (local.get $position)))
+      )
+    )
+
+    ;; exponential notation
+    (local.set $position
+      (call $write_digits (local.get $buffer) (local.get $position) (local.get $digits) (i32.const 0) (i32.const 1)))
+    (if (i32.gt_s (local.get $count) (i32.const 1))
+      (then
+        (local.set $position (call $write_char (local.get $buffer) (local.get $position) (i32.const 46)))
+        (local.set $position
+          (call $write_digits (local.get $buffer) (local.get $position) (local.get $digits) (i32.const 1) (local.get $count)))
+      )
+    )
+    (local.set $position
+      (call $write_exponent (local.get $buffer) (local.get $position) (i32.sub (local.get $n) (i32.const 1))))
+    (struct.new $String (local.get $buffer) (local.get $position))
+  )
+
+  (func $static_to_string (param $offset i32) (param $length i32) (result (ref $String))
+    (local $data (ref $CharArray))
+    (local.set $data (array.new_default $CharArray (local.get $length)))
+    (call $copy_string_data
+      (call $new_static_string (local.get $offset) (local.get $length))
+      (local.get $data)
+      (i32.const 0))
+    (struct.new $String (local.get $data) (local.get $length))
+  )
+
+  ;; round(value * 10^exponent), with ties rounded up
+  (func $scale_and_round (param $value f64) (param $exponent i32) (result (ref $BigDigits))
+    (local $mantissa i64)
+    (local $binary_exponent i32)
+    (local $numerator (ref $BigDigits))
+    (local $denominator (ref $BigDigits))
+
+    (call $f64_decompose (local.get $value))
+    (local.set $binary_exponent)
+    (local.set $mantissa)
+
+    (local.set $numerator (call $big_from_u64 (local.get $mantissa)))
+    (local.set $denominator (call $big_from_u64 (i64.const 1)))
+    (if (i32.ge_s (local.get $binary_exponent) (i32.const 0))
+      (then
+        (local.set $numerator (call $big_shl (local.get $numerator) (local.get $binary_exponent)))
+      )
+      (else
+        (local.set $denominator
+          (call $big_shl (local.get $denominator) (i32.sub (i32.const 0) (local.get $binary_exponent))))
+      )
+    )
+    (if (i32.ge_s (local.get $exponent) (i32.const 0))
+      (then
+        (local.set $numerator (call $big_mul_pow10 (local.get $numerator) (local.get $exponent)))
+      )
+      (else
+        (local.set $denominator
+          (call $big_mul_pow10 (local.get $denominator) (i32.sub (i32.const 0) (local.get $exponent))))
+      )
+    )
+
+    ;; floor((2 * numerator + denominator) / (2 * denominator))
+    (call $big_divmod
+      (call $big_add (call $big_shl (local.get $numerator) (i32.const 1)) (local.get $denominator))
+      (call $big_shl (local.get $denominator) (i32.const 1)))
+    (drop)
+  )
+
+  ;; Number.prototype.toFixed for a finite number smaller than 10^21
+  (func $number_to_fixed (param $value f64) (param $fraction_digits i32) (result (ref $String))
+    (local $digits (ref $String))
+    (local $count i32)
+    (local $buffer (ref $CharArray))
+    (local $position i32)
+    (local $integer_digits i32)
+
+    (local.set $buffer (array.new_default $CharArray (i32.add (local.get $fraction_digits) (i32.const 32))))
+    (if (f64.lt (local.get $value) (f64.const 0))
+      (then
+        (local.set $position (call $write_char (local.get $buffer) (local.get $position) (i32.const 45)))
+        (local.set $value (f64.neg (local.get $value)))
+      )
+    )
+
+    (local.set $digits
+      (call $big_to_string
+        (call $scale_and_round (local.get $value) (local.get $fraction_digits))
+        (i32.const 10)))
+    (local.set $count (struct.get $String $length (local.get $digits)))
+
+    (if (i32.eqz (local.get $fraction_digits))
+      (then
+        (local.set $position
+          (call $write_digits (local.get $buffer) (local.get $position)
+            (struct.get $String $data (local.get $digits)) (i32.const 0) (local.get $count)))
+        (return (struct.new $String (local.get $buffer) (local.get $position)))
+      )
+    )
+
+    (local.set $integer_digits (i32.sub (local.get $count) (local.get $fraction_digits)))
+    (if (i32.le_s (local.get $integer_digits) (i32.const 0))
+      (then
+        (local.set $position (call $write_char (local.get $buffer) (local.get $position) (i32.const 48)))
+        (local.set $position (call $write_char (local.get $buffer) (local.get $position) (i32.const 46)))
+        (local.set $position
+          (call $write_chars (local.get $buffer) (local.get $position)
+            (i32.const 48) (i32.sub (i32.const 0) (local.get $integer_digits))))
+        (local.set $position
+          (call $write_digits (local.get $buffer) (local.get $position)
+            (struct.get $String $data (local.get $digits)) (i32.const 0) (local.get $count)))
+        (return (struct.new $String (local.get $buffer) (local.get $position)))
+      )
+    )
+
+    (local.set $position
+      (call $write_digits (local.get $buffer) (local.get $position)
+        (struct.get $String $data (local.get $digits)) (i32.const 0) (local.get $integer_digits)))
+    (local.set $position (call $write_char (local.get $buffer) (local.get $position) (i32.const 46)))
+    (local.set $position
+      (call $write_digits (local.get $buffer) (local.get $position)
+        (struct.get $String $data (local.get $digits)) (local.get $integer_digits) (local.get $count)))
+    (struct.new $String (local.get $buffer) (local.get $position))
+  )
+
+  ;; Number.prototype.toPrecision for a finite number
+  (func $number_to_precision (param $value f64) (param $precision i32) (result (ref $String))
+    (local $buffer (ref $CharArray))
+    (local $position i32)
+    (local $mantissa i64)
+    (local $binary_exponent i32)
+    (local $e i32)
+    (local $n (ref $BigDigits))
+    (local $digits (ref $CharArray))
+
+    (local.set $buffer (array.new_default $CharArray (i32.add (local.get $precision) (i32.const 32))))
+    (if (f64.lt (local.get $value) (f64.const 0))
+      (then
+        (local.set $position (call $write_char (local.get $buffer) (local.get $position) (i32.const 45)))
+        (local.set $value (f64.neg (local.get $value)))
+      )
+    )
+
+    (local.set $digits
+      (if (result (ref $CharArray)) (f64.eq (local.get $value) (f64.const 0))
+        (then
+          (array.new $CharArray (i32.const 48) (local.get $precision))
+        )
+        (else
+          (call $f64_decompose (local.get $value))
+          (local.set $binary_exponent)
+          (local.set $mantissa)
+
+          ;; e = floor(log10(value)), the estimate might be off by one
+          (local.set $e
+            (i32.trunc_f64_s
+              (f64.floor
+                (f64.mul
+                  (f64.convert_i32_s
+                    (i32.sub
+                      (i32.add
+                        (local.get $binary_exponent)
+                        (i32.sub (i32.const 64) (i32.wrap_i64 (i64.clz (local.get $mantissa)))))
+                      (i32.const 1)))
+                  (f64.const 0.30102999566398114)))))
+
+          ;; find n, such that 10^(p-1) <= n < 10^p
+          (loop $find (result (ref $CharArray))
+            (local.set $n
+              (call $scale_and_round
+                (local.get $value)
+                (i32.sub (i32.sub (local.get $precision) (i32.const 1)) (local.get $e))))
+            (if (i32.ge_s
+                  (call $big_compare
+                    (local.get $n)
+                    (call $big_mul_pow10 (call $big_from_u64 (i64.const 1)) (local.get $precision)))
+                  (i32.const 0))
+              (then
+                (local.set $e (i32.add (local.get $e) (i32.const 1)))
+                (br $find)
+              )
+            )
+            (if (i32.lt_s
+                  (call $big_compare
+                    (local.get $n)
+                    (call $big_mul_pow10
+                      (call $big_from_u64 (i64.const 1))
+                      (i32.sub (local.get $precision) (i32.const 1))))
+                  (i32.const 0))
+              (then
+                (local.set $e (i32.sub (local.get $e) (i32.const 1)))
+                (br $find)
+              )
+            )
+
+            (struct.get $String $data (call $big_to_string (local.get $n) (i32.const 10)))
+          )
+        )
+      )
+    )
+
+    (if (i32.or
+          (i32.lt_s (local.get $e) (i32.const -6))
+          (i32.ge_s (local.get $e) (local.get $precision)))
+      (then
+        (local.set $position
+          (call $write_digits (local.get $buffer) (local.get $position) (local.get $digits) (i32.const 0) (i32.const 1)))
+        (if (i32.ne (local.get $precision) (i32.const 1))
+          (then
+            (local.set $position (call $write_char (local.get $buffer) (local.get $position) (i32.const 46)))
+            (local.set $position
+              (call $write_digits (local.get $buffer) (local.get $position)
+                (local.get $digits) (i32.const 1) (local.get $precision)))
+          )
+        )
+        (local.set $position (call $write_exponent (local.get $buffer) (local.get $position) (local.get $e)))
+        (return (struct.new $String (local.get $buffer) (local.get $position)))
+      )
+    )
+
+    (if (i32.ge_s (local.get $e) (i32.const 0))
+      (then
+        (local.set $position
+          (call $write_digits (local.get $buffer) (local.get $position)
+            (local.get $digits) (i32.const 0) (i32.add (local.get $e) (i32.const 1))))
+        (if (i32.lt_s (i32.add (local.get $e) (i32.const 1)) (local.get $precision))
+          (then
+            (local.set $position (call $write_char (local.get $buffer) (local.get $position) (i32.const 46)))
+            (local.set $position
+              (call $write_digits (local.get $buffer) (local.get $position)
+                (local.get $digits) (i32.add (local.get $e) (i32.const 1)) (local.get $precision)))
+          )
+        )
+        (return (struct.new $String (local.get $buffer) (local.get $position)))
+      )
+    )
+
+    (local.set $position (call $write_char (local.get $buffer) (local.get $position) (i32.const 48)))
+    (local.set $position (call $write_char (local.get $buffer) (local.get $position) (i32.const 46)))
+    (local.set $position
+      (call $write_chars (local.get $buffer) (local.get $position)
+        (i32.const 48) (i32.sub (i32.const -1) (local.get $e))))
+    (local.set $position
+      (call $write_digits (local.get $buffer) (local.get $position) (local.get $digits) (i32.const 0) (local.get $precision)))
+    (struct.new $String (local.get $buffer) (local.get $position))
+  )
+
+  ;; Number.prototype.toString with a radix other than 10. The integer part is
+  ;; exact, the fraction gets as many digits as needed to distinguish the number
+  ;; from its neighbours (the same approach V8 takes)
+  (func $number_to_radix_string (param $value f64) (param $radix i32) (result (ref $String))
+    (local $negative i32)
+    (local $integer f64)
+    (local $fraction f64)
+    (local $delta f64)
+    (local $fraction_digits (ref $CharArray))
+    (local $fraction_length i32)
+    (local $digit i32)
+    (local $carry i32)
+    (local $mantissa i64)
+    (local $exponent i32)
+    (local $integer_digits (ref $BigDigits))
+    (local $integer_string (ref $String))
+    (local $buffer (ref $CharArray))
+    (local $position i32)
+
+    (if (i32.or
+          (f64.ne (local.get $value) (local.get $value))
+          (i32.or
+            (f64.eq (local.get $value) (f64.const 0))
+            (f64.eq (f64.abs (local.get $value)) (f64.const inf))))
+      (then (return (call $number_to_string (local.get $value))))
+    )
+
+    (local.set $negative (f64.lt (local.get $value) (f64.const 0)))
+    (local.set $value (f64.abs (local.get $value)))
+    (local.set $integer (f64.floor (local.get $value)))
+    (local.set $fraction (f64.sub (local.get $value) (local.get $integer)))
+
+    ;; half of the distance to the next number
+    (local.set $delta
+      (f64.mul
+        (f64.const 0.5)
+        (f64.sub
+          (f64.reinterpret_i64 (i64.add (i64.reinterpret_f64 (local.get $value)) (i64.const 1)))
+          (local.get $value))))
+    (local.set $delta (f64.max (local.get $delta) (f64.const 0x1p-1074)))
+
+    (local.set $fraction_digits (array.new_default $CharArray (i32.const 1100)))
+    (if (f64.ge (local.get $fraction) (local.get $delta))
+      (then
+        (block $done (loop $generate
+          (local.set $fraction (f64.mul (local.get $fraction) (f64.convert_i32_s (local.get $radix))))
+          (local.set $delta (f64.mul (local.get $delta) (f64.convert_i32_s (local.get $radix))))
+          (local.set $digit (i32.trunc_f64_u (local.get $fraction)))
+          (array.set $CharArray (local.get $fraction_digits) (local.get $fraction_length) (local.get $digit))
+          (local.set $fraction_length (i32.add (local.get $fraction_length) (i32.const 1)))
+          (local.set $fraction (f64.sub (local.get $fraction) (f64.convert_i32_u (local.get $digit))))
+
+          (if (i32.or
+                (f64.gt (local.get $fraction) (f64.const 0.5))
+                (i32.and
+                  (f64.eq (local.get $fraction) (f64.const 0.5))
+                  (i32.and (local.get $digit) (i32.const 1))))
+            (then
+              (if (f64.gt (f64.add (local.get $fraction) (local.get $delta)) (f64.const 1))
+                (then
+                  ;; round up, propagating the carry
+                  (block $rounded (loop $round
+                    (if (i32.eqz (local.get $fraction_length))
+                      (then
+                        (local.set $carry (i32.const 1))
+                        (br $rounded)
+                      )
+                    )
+                    (local.set $fraction_length (i32.sub (local.get $fraction_length) (i32.const 1)))
+                    (local.set $digit
+                      (array.get_u $CharArray (local.get $fraction_digits) (local.get $fraction_length)))
+                    (if (i32.lt_u (i32.add (local.get $digit) (i32.const 1)) (local.get $radix))
+                      (then
+                        (array.set $CharArray (local.get $fraction_digits) (local.get $fraction_length)
+                          (i32.add (local.get $digit) (i32.const 1)))
+                        (local.set $fraction_length (i32.add (local.get $fraction_length) (i32.const 1)))
+                        (br $rounded)
+                      )
+                    )
+                    (br $round)
+                  ))
+                  (br $done)
+                )
+              )
+            )
+          )
+
+          (br_if $generate (f64.ge (local.get $fraction) (local.get $delta)))
+        ))
+      )
+    )
+
+    (call $f64_decompose (local.get $integer))
+    (local.set $exponent)
+    (local.set $mantissa)
+    (local.set $integer_digits (call $big_from_u64 (local.get $mantissa)))
+    (if (i32.ge_s (local.get $exponent) (i32.const 0))
+      (then
+        (local.set $integer_digits (call $big_shl (local.get $integer_digits) (local.get $exponent)))
+      )
+      (else
+        (local.set $integer_digits
+          (call $big_shr (local.get $integer_digits) (i32.sub (i32.const 0) (local.get $exponent))))
+      )
+    )
+    (if (local.get $carry)
+      (then
+        (local.set $integer_digits
+          (call $big_add (local.get $integer_digits) (call $big_from_u64 (i64.const 1))))
+      )
+    )
+    (local.set $integer_string (call $big_to_string (local.get $integer_digits) (local.get $radix)))
+
+    (local.set $buffer
+      (array.new_default $CharArray
+        (i32.add
+          (i32.add (struct.get $String $length (local.get $integer_string)) (local.get $fraction_length))
+          (i32.const 2))))
+    (if (local.get $negative)
+      (then
+        (local.set $position (call $write_char (local.get $buffer) (local.get $position) (i32.const 45)))
+      )
+    )
+    (local.set $position
+      (call $write_digits (local.get $buffer) (local.get $position)
+        (struct.get $String $data (local.get $integer_string))
+        (i32.const 0)
+        (struct.get $String $length (local.get $integer_string))))
+
+    (if (local.get $fraction_length)
+      (then
+        (local.set $position (call $write_char (local.get $buffer) (local.get $position) (i32.const 46)))
+        (local.set $digit (i32.const 0))
+        (block $break (loop $write
+          (br_if $break (i32.ge_u (local.get $digit) (local.get $fraction_length)))
+          (local.set $position
+            (call $write_char (local.get $buffer) (local.get $position)
+              (call $digit_char (array.get_u $CharArray (local.get $fraction_digits) (local.get $digit)))))
+          (local.set $digit (i32.add (local.get $digit) (i32.const 1)))
+          (br $write)
+        ))
+      )
+    )
+
+    (struct.new $String (local.get $buffer) (local.get $position))
+  )
+
+  ;; Converts numerator / denominator to the nearest double, ties to even
+  (func $big_ratio_to_f64 (param $numerator (ref $BigDigits)) (param $denominator (ref $BigDigits)) (result f64)
+    (local $shift i32)
+    (local $quotient (ref $BigDigits))
+    (local $sticky i32)
+    (local $mantissa i64)
+    (local $bits i32)
+    (local $leading_exponent i32)
+    (local $precision i32)
+    (local $dropped i32)
+    (local $kept i64)
+    (local $rest i64)
+    (local $half i64)
+
+    (if (call $big_is_zero (local.get $numerator))
+      (then (return (f64.const 0)))
+    )
+
+    ;; scale the numerator, so that the quotient has 55 or 56 bits, which is
+    ;; enough for a 53 bit mantissa and rounding
+    (local.set $shift
+      (i32.sub
+        (i32.const 55)
+        (i32.sub
+          (call $big_bit_length (local.get $numerator))
+          (call $big_bit_length (local.get $denominator)))))
+    (if (i32.ge_s (local.get $shift) (i32.const 0))
+      (then
+        (local.set $numerator (call $big_shl (local.get $numerator) (local.get $shift)))
+      )
+      (else
+        (local.set $denominator
+          (call $big_shl (local.get $denominator) (i32.sub (i32.const 0) (local.get $shift))))
+      )
+    )
+
+    (call $big_divmod (local.get $numerator) (local.get $denominator))
+    (local.set $sticky (i32.eqz (call $big_is_zero)))
+    (local.set $quotient)
+
+    (local.set $mantissa (call $big_to_u64 (local.get $quotient)))
+    (local.set $bits (i32.sub (i32.const 64) (i32.wrap_i64 (i64.clz (local.get $mantissa)))))
+    (local.set $leading_exponent (i32.sub (i32.sub (local.get $bits) (i32.const 1)) (local.get $shift)))
+
+    (if (i32.gt_s (local.get $leading_exponent) (i32.const 1023))
+      (then (return (f64.const inf)))
+    )
+
+    ;; subnormal numbers have less bits of precision
+    (local.set $precision (i32.const 53))
+    (if (i32.lt_s (local.get $leading_exponent) (i32.const -1022))
+      (then
+        (local.set $precision (i32.add (local.get $leading_exponent) (i32.const 1075)))
+      )
+    )
+    (if (i32.lt_s (local.get $precision) (i32.const 0))
+      (then (return (f64.const 0)))
+    )
+
+    (local.set $dropped (i32.sub (local.get $bits) (local.get $precision)))
+    (local.set $kept (i64.shr_u (local.get $mantissa) (i64.extend_i32_u (local.get $dropped))))
+    (local.set $rest
+      (i64.and
+        (local.get $mantissa)
+        (i64.sub (i64.shl (i64.const 1) (i64.extend_i32_u (local.get $dropped))) (i64.const 1))))
+    (local.set $half (i64.shl (i64.const 1) (i64.extend_i32_u (i32.sub (local.get $dropped) (i32.const 1)))))
+
+    (if (i32.or
+          (i64.gt_u (local.get $rest) (local.get $half))
+          (i32.and
+            (i64.eq (local.get $rest) (local.get $half))
+            (i32.or
+              (local.get $sticky)
+              (i32.wrap_i64 (i64.and (local.get $kept) (i64.const 1))))))
+      (then
+        (local.set $kept (i64.add (local.get $kept) (i64.const 1)))
+      )
+    )
+
+    (call $f64_scale
+      (f64.convert_i64_u (local.get $kept))
+      (i32.sub (local.get $dropped) (local.get $shift)))
+  )
+
+  (func $f64_pow10 (param $exponent i32) (result f64)
+    (local $result f64)
+    (local.set $result (f64.const 1))
+    (block $break (loop $multiply
+      (br_if $break (i32.le_s (local.get $exponent) (i32.const 0)))
+      (local.set $result (f64.mul (local.get $result) (f64.const 10)))
+      (local.set $exponent (i32.sub (local.get $exponent) (i32.const 1)))
+      (br $multiply)
+    ))
+    (local.get $result)
+  )
+
+  ;; value = digits * 10^exponent, where digits has a given number of
+  ;; significant digits. If there are at most 15 of them, they're also passed as
+  ;; an i64, which allows to skip bignums for most of the numbers
+  (func $decimal_to_f64 (param $digits (ref $BigDigits)) (param $small i64) (param $significant i32) (param $exponent i32) (result f64)
+    (if (i32.eqz (local.get $significant))
+      (then (return (f64.const 0)))
+    )
+
+    ;; both the digits and the power of 10 are exact, so the result is rounded
+    ;; only once
+    (if (i32.and
+          (i32.le_s (local.get $significant) (i32.const 15))
+          (i32.le_u
+            (i32.add (local.get $exponent) (i32.const 22))
+            (i32.const 44)))
+      (then
+        (if (i32.ge_s (local.get $exponent) (i32.const 0))
+          (then
+            (return
+              (f64.mul
+                (f64.convert_i64_u (local.get $small))
+                (call $f64_pow10 (local.get $exponent))))
+          )
+        )
+        (return
+          (f64.div
+            (f64.convert_i64_u (local.get $small))
+            (call $f64_pow10 (i32.sub (i32.const 0) (local.get $exponent)))))
+      )
+    )
+
+    (if (i32.gt_s (i32.add (local.get $significant) (local.get $exponent)) (i32.const 310))
+      (then (return (f64.const inf)))
+    )
+    (if (i32.lt_s (i32.add (local.get $significant) (local.get $exponent)) (i32.const -330))
+      (then (return (f64.const 0)))
+    )
+
+    (if (i32.ge_s (local.get $exponent) (i32.const 0))
+      (then
+        (return
+          (call $big_ratio_to_f64
+            (call $big_mul_pow10 (local.get $digits) (local.get $exponent))
+            (call $big_from_u64 (i64.const 1))))
+      )
+    )
+    (call $big_ratio_to_f64
+      (local.get $digits)
+      (call $big_mul_pow10 (call $big_from_u64 (i64.const 1)) (i32.sub (i32.const 0) (local.get $exponent))))
+  )
+
+  (func $is_whitespace (param $char i32) (result i32)
+    (i32.or
+      (i32.eq (local.get $char) (i32.const 32))
+      (i32.and
+        (i32.ge_u (local.get $char) (i32.const 9))
+        (i32.le_u (local.get $char) (i32.const 13))))
+  )
+
+  (func $skip_whitespace (param $str anyref) (param $position i32) (param $end i32) (result i32)
+    (block $break (loop $skip
+      (br_if $break (i32.ge_s (local.get $position) (local.get $end)))
+      (br_if $break
+        (i32.eqz (call $is_whitespace (call $string_byte_at (local.get $str) (local.get $position)))))
+      (local.set $position (i32.add (local.get $position) (i32.const 1)))
+      (br $skip)
+    ))
+    (local.get $position)
+  )
+
+  ;; returns the value of a digit in a given radix or -1 if it's not a valid digit
+  (func $digit_value (param $char i32) (param $radix i32) (result i32)
+    (local $value i32)
+    (local.set $value (i32.const -1))
+    (if (i32.and (i32.ge_u (local.get $char) (i32.const 48)) (i32.le_u (local.get $char) (i32.const 57)))
+      (then (local.set $value (i32.sub (local.get $char) (i32.const 48))))
+    )
+    (if (i32.and (i32.ge_u (local.get $char) (i32.const 97)) (i32.le_u (local.get $char) (i32.const 122)))
+      (then (local.set $value (i32.sub (local.get $char) (i32.const 87))))
+    )
+    (if (i32.and (i32.ge_u (local.get $char) (i32.const 65)) (i32.le_u (local.get $char) (i32.const 90)))
+      (then (local.set $value (i32.sub (local.get $char) (i32.const 55))))
+    )
+    (if (i32.ge_s (local.get $value) (local.get $radix))
+      (then (return (i32.const -1)))
+    )
+    (local.get $value)
+  )
+
+  (func $string_matches_at (param $str anyref) (param $position i32) (param $end i32) (param $offset i32) (param $length i32) (result i32)
+    (local $i i32)
+    (if (i32.gt_s (i32.add (local.get $position) (local.get $length)) (local.get $end))
+      (then (return (i32.const 0)))
+    )
+    (block $break (loop $compare
+      (br_if $break (i32.ge_u (local.get $i) (local.get $length)))
+      (if (i32.ne
+            (call $string_byte_at (local.get $str) (i32.add (local.get $position) (local.get $i)))
+            (i32.load8_u (i32.add (local.get $offset) (local.get $i))))
+        (then (return (i32.const 0)))
+      )
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $compare)
+    ))
+    (i32.const 1)
+  )
+
+  ;; Parses the longest prefix of str[position..end] that is a decimal literal
+  ;; (with an optional sign, or Infinity). Returns the value and the position after
+  ;; the literal, which is the same as the starting position if there's no number
+  (func $parse_decimal (param $str anyref) (param $position i32) (param $end i32) (result f64 i32)
+    (local $start i32)
+    (local $negative i32)
+    (local $char i32)
+    (local $digits (ref $BigDigits))
+    (local $small i64)
+    (local $significant i32)
+    (local $exponent i32)
+    (local $any_digits i32)
+    (local $exponent_position i32)
+    (local $exponent_negative i32)
+    (local $exponent_value i32)
+    (local $value f64)
+
+    (local.set $start (local.get $position))
+    (local.set $digits (call $big_alloc (i32.const 0)))
+
+    (if (i32.lt_s (local.get $position) (local.get $end))
+      (then
+        (local.set $char (call $string_byte_at (local.get $str) (local.get $position)))
+        (if (i32.or (i32.eq (local.get $char) (i32.const 43)) (i32.eq (local.get $char) (i32.const 45)))
+          (then
+            (local.set $negative (i32.eq (local.get $char) (i32.const 45)))
+            (local.set $position (i32.add (local.get $position) (i32.const 1)))
+          )
+        )
+      )
+    )
+
+    (if (call $string_matches_at
+          (local.get $str) (local.get $position) (local.get $end)
+          (i32.const {{ data(str="Infinity") }}) (i32.const {{ data_length() }}))
+      (then
+        (return
+          (select (f64.const -inf) (f64.const inf) (local.get $negative))
+          (i32.add (local.get $position) (i32.const 8)))
+      )
+    )
+
+    (block $break (loop $integer
+      (br_if $break (i32.ge_s (local.get $position) (local.get $end)))
+      (local.set $char (call $string_byte_at (local.get $str) (local.get $position)))
+      (br_if $break (i32.or (i32.lt_u (local.get $char) (i32.const 48)) (i32.gt_u (local.get $char) (i32.const 57))))
+      (local.set $any_digits (i32.const 1))
+      ;; leading zeros are not significant
+      (if (i32.or (local.get $significant) (i32.ne (local.get $char) (i32.const 48)))
+        (then
+          (local.set $digits
+            (call $big_mul_add_small (local.get $digits) (i32.const 10) (i32.sub (local.get $char) (i32.const 48))))
+          (local.set $small
+            (i64.add
+              (i64.mul (local.get $small) (i64.const 10))
+              (i64.extend_i32_u (i32.sub (local.get $char) (i32.const 48)))))
+          (local.set $significant (i32.add (local.get $significant) (i32.const 1)))
+        )
+      )
+      (local.set $position (i32.add (local.get $position) (i32.const 1)))
+      (br $integer)
+    ))
+
+    (if (i32.lt_s (local.get $position) (local.get $end))
+      (then
+        (if (i32.eq (call $string_byte_at (local.get $str) (local.get $position)) (i32.const 46))
+          (then
+            (local.set $position (i32.add (local.get $position) (i32.const 1)))
+            (block $break (loop $fraction
+              (br_if $break (i32.ge_s (local.get $position) (local.get $end)))
+              (local.set $char (call $string_byte_at (local.get $str) (local.get $position)))
+              (br_if $break (i32.or (i32.lt_u (local.get $char) (i32.const 48)) (i32.gt_u (local.get $char) (i32.const 57))))
+              (local.set $any_digits (i32.const 1))
+              (if (i32.or (local.get $significant) (i32.ne (local.get $char) (i32.const 48)))
+                (then
+                  (local.set $digits
+                    (call $big_mul_add_small (local.get $digits) (i32.const 10) (i32.sub (local.get $char) (i32.const 48))))
+                  (local.set $small
+                    (i64.add
+                      (i64.mul (local.get $small) (i64.const 10))
+                      (i64.extend_i32_u (i32.sub (local.get $char) (i32.const 48)))))
+                  (local.set $significant (i32.add (local.get $significant) (i32.const 1)))
+                )
+              )
+              (local.set $exponent (i32.sub (local.get $exponent) (i32.const 1)))
+              (local.set $position (i32.add (local.get $position) (i32.const 1)))
+              (br $fraction)
+            ))
+          )
+        )
+      )
+    )
+
+    (if (i32.eqz (local.get $any_digits))
+      (then (return (f64.const nan) (local.get $start)))
+    )
+
+    ;; the exponent is only a part of the number if there are digits after the `e`
+    (if (i32.lt_s (local.get $position) (local.get $end))
+      (then
+        (local.set $char (call $string_byte_at (local.get $str) (local.get $position)))
+        (if (i32.or (i32.eq (local.get $char) (i32.const 101)) (i32.eq (local.get $char) (i32.const 69)))
+          (then
+            (local.set $exponent_position (i32.add (local.get $position) (i32.const 1)))
+            (if (i32.lt_s (local.get $exponent_position) (local.get $end))
+              (then
+                (local.set $char (call $string_byte_at (local.get $str) (local.get $exponent_position)))
+                (if (i32.or (i32.eq (local.get $char) (i32.const 43)) (i32.eq (local.get $char) (i32.const 45)))
+                  (then
+                    (local.set $exponent_negative (i32.eq (local.get $char) (i32.const 45)))
+                    (local.set $exponent_position (i32.add (local.get $exponent_position) (i32.const 1)))
+                  )
+                )
+              )
+            )
+            (block $break (loop $exponent_digits
+              (br_if $break (i32.ge_s (local.get $exponent_position) (local.get $end)))
+              (local.set $char (call $string_byte_at (local.get $str) (local.get $exponent_position)))
+              (br_if $break (i32.or (i32.lt_u (local.get $char) (i32.const 48)) (i32.gt_u (local.get $char) (i32.const 57))))
+              ;; anything bigger than that overflows or underflows anyway
+              (if (i32.lt_s (local.get $exponent_value) (i32.const 100000))
+                (then
+                  (local.set $exponent_value
+                    (i32.add
+                      (i32.mul (local.get $exponent_value) (i32.const 10))
+                      (i32.sub (local.get $char) (i32.const 48))))
+                )
+              )
+              (local.set $exponent_position (i32.add (local.get $exponent_position) (i32.const 1)))
+              ;; only now we know the exponent is valid
+              (local.set $position (local.get $exponent_position))
+              (br $exponent_digits)
+            ))
+            (if (local.get $exponent_negative)
+              (then (local.set $exponent_value (i32.sub (i32.const 0) (local.get $exponent_value))))
+            )
+            (if (i32.eq (local.get $position) (local.get $exponent_position))
+              (then
+                (local.set $exponent (i32.add (local.get $exponent) (local.get $exponent_value)))
+              )
+            )
+          )
+        )
+      )
+    )
+
+    (local.set $value
+      (call $decimal_to_f64 (local.get $digits) (local.get $small) (local.get $significant) (local.get $exponent)))
+    (if (local.get $negative)
+      (then (local.set $value (f64.neg (local.get $value))))
+    )
+    (local.get $value)
+    (local.get $position)
+  )
+
+  ;; parses digits in a given radix, returns the value and the position
+  ;; after the last digit
+  (func $parse_integer_digits (param $str anyref) (param $position i32) (param $end i32) (param $radix i32) (result f64 i32)
+    (local $digits (ref $BigDigits))
+    (local $digit i32)
+
+    (local.set $digits (call $big_alloc (i32.const 0)))
+    (block $break (loop $parse
+      (br_if $break (i32.ge_s (local.get $position) (local.get $end)))
+      (local.set $digit
+        (call $digit_value (call $string_byte_at (local.get $str) (local.get $position)) (local.get $radix)))
+      (br_if $break (i32.lt_s (local.get $digit) (i32.const 0)))
+      (local.set $digits (call $big_mul_add_small (local.get $digits) (local.get $radix) (local.get $digit)))
+      (local.set $position (i32.add (local.get $position) (i32.const 1)))
+      (br $parse)
+    ))
+
+    (call $big_ratio_to_f64 (local.get $digits) (call $big_from_u64 (i64.const 1)))
+    (local.get $position)
+  )
+
+  ;; StringToNumber from the spec
+  (func $string_to_number (param $str anyref) (result f64)
+    (local $start i32)
+    (local $end i32)
+    (local $radix i32)
+    (local $char i32)
+    (local $value f64)
+    (local $position i32)
+
+    (local.set $end (call $string_length (local.get $str)))
+    (local.set $start (call $skip_whitespace (local.get $str) (i32.const 0) (local.get $end)))
+    (block $break (loop $trim
+      (br_if $break (i32.le_s (local.get $end) (local.get $start)))
+      (br_if $break
+        (i32.eqz
+          (call $is_whitespace
+            (call $string_byte_at (local.get $str) (i32.sub (local.get $end) (i32.const 1))))))
+      (local.set $end (i32.sub (local.get $end) (i32.const 1)))
+      (br $trim)
+    ))
+
+    (if (i32.eq (local.get $start) (local.get $end))
+      (then (return (f64.const 0)))
+    )
+
+    ;; 0x, 0o and 0b prefixes
+    (if (i32.and
+          (i32.ge_s (i32.sub (local.get $end) (local.get $start)) (i32.const 3))
+          (i32.eq (call $string_byte_at (local.get $str) (local.get $start)) (i32.const 48)))
+      (then
+        (local.set $char
+          (i32.or (call $string_byte_at (local.get $str) (i32.add (local.get $start) (i32.const 1))) (i32.const 32)))
+        (if (i32.eq (local.get $char) (i32.const 120))
+          (then (local.set $radix (i32.const 16)))
+        )
+        (if (i32.eq (local.get $char) (i32.const 111))
+          (then (local.set $radix (i32.const 8)))
+        )
+        (if (i32.eq (local.get $char) (i32.const 98))
+          (then (local.set $radix (i32.const 2)))
+        )
+        (if (local.get $radix)
+          (then
+            (call $parse_integer_digits
+              (local.get $str)
+              (i32.add (local.get $start) (i32.const 2))
+              (local.get $end)
+              (local.get $radix))
+            (local.set $position)
+            (local.set $value)
+            (if (i32.ne (local.get $position) (local.get $end))
+              (then (return (f64.const nan)))
+            )
+            (return (local.get $value))
+          )
+        )
+      )
+    )
+
+    (call $parse_decimal (local.get $str) (local.get $start) (local.get $end))
+    (local.set $position)
+    (local.set $value)
+    (if (i32.ne (local.get $position) (local.get $end))
+      (then (return (f64.const nan)))
+    )
+    (local.get $value)
+  )
+
+  ;; ToNumber from the spec
+  (func $to_number (param $arg anyref) (result f64)
+    (if (call $is_number (local.get $arg))
+      (then
+        (return (call $number_value (local.get $arg)))
+      )
+    )
+
+    (if (ref.test nullref (local.get $arg))
+      (then (return (f64.const nan)))
+    )
+
+    (if (ref.test (ref $Boolean) (local.get $arg))
+      (then
+        (return (f64.convert_i32_s (struct.get $Boolean $value (ref.cast (ref $Boolean) (local.get $arg)))))
+      )
+    )
+
+    ;; null is 0, just like false
+    (if (ref.test (ref $Null) (local.get $arg))
+      (then (return (f64.const 0)))
+    )
+
+    (if (call $is_string (local.get $arg))
+      (then (return (call $string_to_number (local.get $arg))))
+    )
+
+    (if (call $is_symbol (local.get $arg))
+      (then
+        (call $throw_type_error
+          (call $new_static_string (i32.const {{ data(str="Cannot convert a Symbol value to a number") }}) (i32.const {{ data_length() }})))
+      )
+    )
+
+    (if (call $is_bigint (local.get $arg))
+      (then
+        (call $throw_type_error
+          (call $new_static_string (i32.const {{ data(str="Cannot convert a BigInt value to a number") }}) (i32.const {{ data_length() }})))
+      )
+    )
+
+    ;; objects are converted to a primitive first, preferring valueOf
+    (call $to_number (call $to_primitive (local.get $arg) (i32.const 1)))
+  )
+
+  (func $to_integer_or_infinity (param $arg anyref) (result f64)
+    (local $value f64)
+    (local.set $value (call $to_number (local.get $arg)))
+    (if (f64.ne (local.get $value) (local.get $value))
+      (then (return (f64.const 0)))
+    )
+    ;; adding 0 turns -0 into +0
+    (f64.add (f64.trunc (local.get $value)) (f64.const 0))
+  )
+
+  ;; the integer modulo 2^32, as in ToInt32 and ToUint32
+  (func $f64_to_int32 (param $value f64) (result i32)
+    (if (i32.eqz (f64.eq (f64.sub (local.get $value) (local.get $value)) (f64.const 0)))
+      (then (return (i32.const 0)))
+    )
+    (local.set $value (f64.trunc (local.get $value)))
+    (local.set $value
+      (f64.sub
+        (local.get $value)
+        (f64.mul
+          (f64.const 4294967296)
+          (f64.floor (f64.div (local.get $value) (f64.const 4294967296))))))
+    (i32.wrap_i64 (i64.trunc_f64_u (local.get $value)))
+  )
+
+  (func $to_int32 (param $arg anyref) (result i32)
+    (call $f64_to_int32 (call $to_number (local.get $arg)))
+  )
+
+  (func $number_argument (param $arguments (ref $JSArgs)) (param $index i32) (result f64)
+    (call $to_number (call $get_argument (local.get $arguments) (local.get $index)))
+  )
+
+  ;; thisNumberValue from the spec
+  (func $this_number_value (param $this anyref) (result f64)
+    (if (call $is_number (local.get $this))
+      (then
+        (return (call $number_value (local.get $this)))
+      )
+    )
+    (call $throw_type_error
+      (call $new_static_string (i32.const {{ data(str="Number.prototype method called on incompatible receiver") }}) (i32.const {{ data_length() }})))
+    (unreachable)
+  )
+
+  ;; the remainder of a division with the sign of the dividend, like fmod in C
+  (func $f64_rem (param $dividend f64) (param $divisor f64) (result f64)
+    (local $dividend_mantissa i64)
+    (local $dividend_exponent i32)
+    (local $divisor_mantissa i64)
+    (local $divisor_exponent i32)
+    (local $remainder i64)
+
+    (if (i32.or
+          (i32.or
+            (f64.ne (local.get $dividend) (local.get $dividend))
+            (f64.ne (local.get $divisor) (local.get $divisor)))
+          (i32.or
+            (f64.eq (f64.abs (local.get $dividend)) (f64.const inf))
+            (f64.eq (local.get $divisor) (f64.const 0))))
+      (then (return (f64.const nan)))
+    )
+
+    (if (f64.lt (f64.abs (local.get $dividend)) (f64.abs (local.get $divisor)))
+      (then (return (local.get $dividend)))
+    )
+
+    ;; the most common case, both numbers are integers
+    (if (i32.and
+          (f64.lt (f64.abs (local.get $dividend)) (f64.const 9007199254740992))
+          (i32.and
+            (f64.eq (local.get $dividend) (f64.trunc (local.get $dividend)))
+            (f64.eq (local.get $divisor) (f64.trunc (local.get $divisor)))))
+      (then
+        (return
+          (f64.copysign
+            (f64.convert_i64_s
+              (i64.rem_s
+                (i64.trunc_f64_s (local.get $dividend))
+                (i64.trunc_f64_s (local.get $divisor))))
+            (local.get $dividend)))
+      )
+    )
+
+    (call $f64_decompose (f64.abs (local.get $dividend)))
+    (local.set $dividend_exponent)
+    (local.set $dividend_mantissa)
+    (call $f64_decompose (f64.abs (local.get $divisor)))
+    (local.set $divisor_exponent)
+    (local.set $divisor_mantissa)
+
+    ;; dividend_mantissa * 2^(dividend_exponent - divisor_exponent) mod divisor_mantissa,
+    ;; one bit at a time
+    (local.set $remainder (i64.rem_u (local.get $dividend_mantissa) (local.get $divisor_mantissa)))
+    (block $break (loop $reduce
+      (br_if $break (i32.le_s (local.get $dividend_exponent) (local.get $divisor_exponent)))
+      (local.set $remainder
+        (i64.rem_u (i64.shl (local.get $remainder) (i64.const 1)) (local.get $divisor_mantissa)))
+      (local.set $dividend_exponent (i32.sub (local.get $dividend_exponent) (i32.const 1)))
+      (br $reduce)
+    ))
+
+    (f64.copysign
+      (call $f64_scale (f64.convert_i64_u (local.get $remainder)) (local.get $divisor_exponent))
+      (local.get $dividend))
+  )
+
+  (func $Number-constructor
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (local $value anyref)
+
+    (if (i32.eqz (array.len (local.get $arguments)))
+      (then (return (call $new_number (f64.const 0))))
+    )
+    ;; unlike the other conversions, Number() accepts BigInts
+    (local.set $value (call $to_numeric (call $get_argument (local.get $arguments) (i32.const 0))))
+    (if (call $is_bigint (local.get $value))
+      (then
+        (return (call $new_number (call $bigint_to_f64 (ref.cast (ref $BigInt) (local.get $value)))))
+      )
+    )
+    (local.get $value)
+  )
+
+  (func $Number-isFinite
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (local $arg anyref)
+    (local $value f64)
+
+    (local.set $arg (call $get_argument (local.get $arguments) (i32.const 0)))
+    (if (i32.eqz (call $is_number (local.get $arg)))
+      (then (return (global.get $false)))
+    )
+    (local.set $value (call $number_value (local.get $arg)))
+    (call $new_boolean (f64.eq (f64.sub (local.get $value) (local.get $value)) (f64.const 0)))
+  )
+
+  (func $Number-isInteger
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (local $arg anyref)
+
+    (local.set $arg (call $get_argument (local.get $arguments) (i32.const 0)))
+    (if (i32.eqz (call $is_number (local.get $arg)))
+      (then (return (global.get $false)))
+    )
+    (call $new_boolean (call $f64_is_integer (call $number_value (local.get $arg))))
+  )
+
+  (func $Number-isSafeInteger
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (local $arg anyref)
+    (local $value f64)
+
+    (local.set $arg (call $get_argument (local.get $arguments) (i32.const 0)))
+    (if (i32.eqz (call $is_number (local.get $arg)))
+      (then (return (global.get $false)))
+    )
+    (local.set $value (call $number_value (local.get $arg)))
+    (call $new_boolean
+      (i32.and
+        (call $f64_is_integer (local.get $value))
+        (f64.le (f64.abs (local.get $value)) (f64.const 9007199254740991))))
+  )
+
+  (func $Number-isNaN
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (local $arg anyref)
+    (local $value f64)
+
+    (local.set $arg (call $get_argument (local.get $arguments) (i32.const 0)))
+    (if (i32.eqz (call $is_number (local.get $arg)))
+      (then (return (global.get $false)))
+    )
+    (local.set $value (call $number_value (local.get $arg)))
+    (call $new_boolean (f64.ne (local.get $value) (local.get $value)))
+  )
+
+  (func $isNaN
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (local $value f64)
+
+    (local.set $value (call $number_argument (local.get $arguments) (i32.const 0)))
+    (call $new_boolean (f64.ne (local.get $value) (local.get $value)))
+  )
+
+  (func $isFinite
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (local $value f64)
+
+    (local.set $value (call $number_argument (local.get $arguments) (i32.const 0)))
+    (call $new_boolean (f64.eq (f64.sub (local.get $value) (local.get $value)) (f64.const 0)))
+  )
+
+  (func $parseFloat
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (local $str anyref)
+    (local $start i32)
+    (local $end i32)
+    (local $value f64)
+    (local $position i32)
+
+    (local.set $str (call $to_string (call $get_argument (local.get $arguments) (i32.const 0))))
+    (local.set $end (call $string_length (local.get $str)))
+    (local.set $start (call $skip_whitespace (local.get $str) (i32.const 0) (local.get $end)))
+
+    (call $parse_decimal (local.get $str) (local.get $start) (local.get $end))
+    (local.set $position)
+    (local.set $value)
+    (if (i32.eq (local.get $position) (local.get $start))
+      (then (return (call $new_number (f64.const nan))))
+    )
+    (call $new_number (local.get $value))
+  )
+
+  (func $parseInt
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (local $str anyref)
+    (local $position i32)
+    (local $end i32)
+    (local $char i32)
+    (local $negative i32)
+    (local $radix i32)
+    (local $strip_prefix i32)
+    (local $value f64)
+    (local $digits_end i32)
+
+    (local.set $str (call $to_string (call $get_argument (local.get $arguments) (i32.const 0))))
+    (local.set $end (call $string_length (local.get $str)))
+    (local.set $position (call $skip_whitespace (local.get $str) (i32.const 0) (local.get $end)))
+
+    (if (i32.lt_s (local.get $position) (local.get $end))
+      (then
+        (local.set $char (call $string_byte_at (local.get $str) (local.get $position)))
+        (if (i32.or (i32.eq (local.get $char) (i32.const 43)) (i32.eq (local.get $char) (i32.const 45)))
+          (then
+            (local.set $negative (i32.eq (local.get $char) (i32.const 45)))
+            (local.set $position (i32.add (local.get $position) (i32.const 1)))
+          )
+        )
+      )
+    )
+
+    (local.set $radix (call $to_int32 (call $get_argument (local.get $arguments) (i32.const 1))))
+    (local.set $strip_prefix (i32.const 1))
+    (if (local.get $radix)
+      (then
+        (if (i32.or (i32.lt_s (local.get $radix) (i32.const 2)) (i32.gt_s (local.get $radix) (i32.const 36)))
+          (then (return (call $new_number (f64.const nan))))
+        )
+        (if (i32.ne (local.get $radix) (i32.const 16))
+          (then (local.set $strip_prefix (i32.const 0)))
+        )
+      )
+      (else
+        (local.set $radix (i32.const 10))
+      )
+    )
+
+    (if (i32.and
+          (local.get $strip_prefix)
+          (i32.le_s (i32.add (local.get $position) (i32.const 2)) (local.get $end)))
+      (then
+        (if (i32.and
+              (i32.eq (call $string_byte_at (local.get $str) (local.get $position)) (i32.const 48))
+              (i32.eq
+                (i32.or
+                  (call $string_byte_at (local.get $str) (i32.add (local.get $position) (i32.const 1)))
+                  (i32.const 32))
+                (i32.const 120)))
+          (then
+            (local.set $position (i32.add (local.get $position) (i32.const 2)))
+            (local.set $radix (i32.const 16))
+          )
+        )
+      )
+    )
+
+    (call $parse_integer_digits (local.get $str) (local.get $position) (local.get $end) (local.get $radix))
+    (local.set $digits_end)
+    (local.set $value)
+    (if (i32.eq (local.get $digits_end) (local.get $position))
+      (then (return (call $new_number (f64.const nan))))
+    )
+    (if (local.get $negative)
+      (then (local.set $value (f64.neg (local.get $value))))
+    )
+    (call $new_number (local.get $value))
+  )
+
+  (func $Number-prototype-toString
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (local $value f64)
+    (local $radix anyref)
+    (local $radix_value f64)
+
+    (local.set $value (call $this_number_value (local.get $this)))
+    (local.set $radix (call $get_argument (local.get $arguments) (i32.const 0)))
+    (if (ref.test nullref (local.get $radix))
+      (then (return (call $number_to_string (local.get $value))))
+    )
+
+    (local.set $radix_value (call $to_integer_or_infinity (local.get $radix)))
+    (if (i32.or
+          (f64.lt (local.get $radix_value) (f64.const 2))
+          (f64.gt (local.get $radix_value) (f64.const 36)))
+      (then
+        (call $throw_range_error
+          (call $new_static_string (i32.const {{ data(str="toString() radix must be between 2 and 36") }}) (i32.const {{ data_length() }})))
+      )
+    )
+
+    (if (f64.eq (local.get $radix_value) (f64.const 10))
+      (then (return (call $number_to_string (local.get $value))))
+    )
+    (call $number_to_radix_string (local.get $value) (i32.trunc_f64_s (local.get $radix_value)))
+  )
+
+  (func $Number-prototype-toFixed
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (local $value f64)
+    (local $digits f64)
+
+    (local.set $value (call $this_number_value (local.get $this)))
+    (local.set $digits
+      (call $to_integer_or_infinity (call $get_argument (local.get $arguments) (i32.const 0))))
+    (if (i32.or
+          (f64.lt (local.get $digits) (f64.const 0))
+          (f64.gt (local.get $digits) (f64.const 100)))
+      (then
+        (call $throw_range_error
+          (call $new_static_string (i32.const {{ data(str="toFixed() digits argument must be between 0 and 100") }}) (i32.const {{ data_length() }})))
+      )
+    )
+
+    (if (i32.or
+          (f64.ne (local.get $value) (local.get $value))
+          (f64.ge (f64.abs (local.get $value)) (f64.const 1e21)))
+      (then (return (call $number_to_string (local.get $value))))
+    )
+    (call $number_to_fixed (local.get $value) (i32.trunc_f64_s (local.get $digits)))
+  )
+
+  (func $Number-prototype-toPrecision
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (local $value f64)
+    (local $precision anyref)
+    (local $precision_value f64)
+
+    (local.set $value (call $this_number_value (local.get $this)))
+    (local.set $precision (call $get_argument (local.get $arguments) (i32.const 0)))
+    (if (ref.test nullref (local.get $precision))
+      (then (return (call $number_to_string (local.get $value))))
+    )
+
+    (local.set $precision_value (call $to_integer_or_infinity (local.get $precision)))
+    (if (i32.eqz (f64.eq (f64.sub (local.get $value) (local.get $value)) (f64.const 0)))
+      (then (return (call $number_to_string (local.get $value))))
+    )
+    (if (i32.or
+          (f64.lt (local.get $precision_value) (f64.const 1))
+          (f64.gt (local.get $precision_value) (f64.const 100)))
+      (then
+        (call $throw_range_error
+          (call $new_static_string (i32.const {{ data(str="toPrecision() argument must be between 1 and 100") }}) (i32.const {{ data_length() }})))
+      )
+    )
+    (call $number_to_precision (local.get $value) (i32.trunc_f64_s (local.get $precision_value)))
+  )
+
+  (func $Number-prototype-valueOf
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+
+    (call $new_number (call $this_number_value (local.get $this)))
+  )
+
+  (elem declare func $Number-constructor)
+  (elem declare func $Number-isFinite)
+  (elem declare func $Number-isInteger)
+  (elem declare func $Number-isSafeInteger)
+  (elem declare func $Number-isNaN)
+  (elem declare func $isNaN)
+  (elem declare func $isFinite)
+  (elem declare func $parseFloat)
+  (elem declare func $parseInt)
+  (elem declare func $Number-prototype-toString)
+  (elem declare func $Number-prototype-toFixed)
+  (elem declare func $Number-prototype-toPrecision)
+  (elem declare func $Number-prototype-valueOf)
+
+  (func $create-number-prototype (result (ref $Object))
+    (local $object (ref $Object))
+    (local.set $object (call $new_object))
+
+    (call $add_method (local.get $object) (i32.const {{ data(str="toString") }}) (ref.func $Number-prototype-toString))
+    (call $add_method (local.get $object) (i32.const {{ data(str="toLocaleString") }}) (ref.func $Number-prototype-toString))
+    (call $add_method (local.get $object) (i32.const {{ data(str="toFixed") }}) (ref.func $Number-prototype-toFixed))
+    (call $add_method (local.get $object) (i32.const {{ data(str="toPrecision") }}) (ref.func $Number-prototype-toPrecision))
+    (call $add_method (local.get $object) (i32.const {{ data(str="valueOf") }}) (ref.func $Number-prototype-valueOf))
+
+    (local.get $object)
+  )
+
+  ;; installs Number and the global functions related to numbers
+  (func $install-number-globals (param $scope (ref $Scope))
+    (local $number (ref $Function))
+    (local $parse-float (ref $Function))
+    (local $parse-int (ref $Function))
+
+    (global.set $number-prototype (call $create-number-prototype))
+    (local.set $number
+      (call $new_function (local.get $scope) (ref.func $Number-constructor) (ref.null any)))
+    (call $set_property (local.get $number) (i32.const {{ data(str="prototype") }}) (global.get $number-prototype))
+    (call $set_property (global.get $number-prototype) (i32.const {{ data(str="constructor") }}) (local.get $number))
+
+    (call $add_method (local.get $number) (i32.const {{ data(str="isFinite") }}) (ref.func $Number-isFinite))
+    (call $add_method (local.get $number) (i32.const {{ data(str="isInteger") }}) (ref.func $Number-isInteger))
+    (call $add_method (local.get $number) (i32.const {{ data(str="isSafeInteger") }}) (ref.func $Number-isSafeInteger))
+    (call $add_method (local.get $number) (i32.const {{ data(str="isNaN") }}) (ref.func $Number-isNaN))
+
+    (call $set_property (local.get $number) (i32.const {{ data(str="EPSILON") }}) (call $new_number (f64.const 0x1p-52)))
+    (call $set_property (local.get $number) (i32.const {{ data(str="MAX_SAFE_INTEGER") }}) (call $new_number (f64.const 9007199254740991)))
+    (call $set_property (local.get $number) (i32.const {{ data(str="MIN_SAFE_INTEGER") }}) (call $new_number (f64.const -9007199254740991)))
+    (call $set_property (local.get $number) (i32.const {{ data(str="MAX_VALUE") }}) (call $new_number (f64.const 0x1.fffffffffffffp+1023)))
+    (call $set_property (local.get $number) (i32.const {{ data(str="MIN_VALUE") }}) (call $new_number (f64.const 0x1p-1074)))
+    (call $set_property (local.get $number) (i32.const {{ data(str="NaN") }}) (call $new_number (f64.const nan)))
+    (call $set_property (local.get $number) (i32.const {{ data(str="POSITIVE_INFINITY") }}) (call $new_number (f64.const inf)))
+    (call $set_property (local.get $number) (i32.const {{ data(str="NEGATIVE_INFINITY") }}) (call $new_number (f64.const -inf)))
+
+    ;; Number.parseFloat and Number.parseInt are the same functions as the global ones
+    (local.set $parse-float
+      (call $new_function (local.get $scope) (ref.func $parseFloat) (ref.null any)))
+    (local.set $parse-int
+      (call $new_function (local.get $scope) (ref.func $parseInt) (ref.null any)))
+    (call $set_property (local.get $number) (i32.const {{ data(str="parseFloat") }}) (local.get $parse-float))
+    (call $set_property (local.get $number) (i32.const {{ data(str="parseInt") }}) (local.get $parse-int))
+
+    (call $set_variable (local.get $scope) (i32.const {{ data(str="Number") }}) (local.get $number))
+    (call $set_variable (local.get $scope) (i32.const {{ data(str="parseFloat") }}) (local.get $parse-float))
+    (call $set_variable (local.get $scope) (i32.const {{ data(str="parseInt") }}) (local.get $parse-int))
+    (call $set_variable (local.get $scope) (i32.const {{ data(str="isNaN") }})
+      (call $new_function (local.get $scope) (ref.func $isNaN) (ref.null any)))
+    (call $set_variable (local.get $scope) (i32.const {{ data(str="isFinite") }})
+      (call $new_function (local.get $scope) (ref.func $isFinite) (ref.null any)))
+    (call $set_variable (local.get $scope) (i32.const {{ data(str="NaN") }}) (call $new_number (f64.const nan)))
+    (call $set_variable (local.get $scope) (i32.const {{ data(str="Infinity") }}) (call $new_number (f64.const inf)))
+  )
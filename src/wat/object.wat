@@ -0,0 +1,1455 @@
+  ;; Object model helpers and the `Object` builtin
+
+  (global $object-prototype (mut (ref null $Object)) (ref.null $Object))
+
+  (func $get_argument (param $arguments (ref $JSArgs)) (param $index i32) (result anyref)
+    (if (i32.lt_u (local.get $index) (array.len (local.get $arguments)))
+      (then
+        (return (array.get $JSArgs (local.get $arguments) (local.get $index)))
+      )
+    )
+
+    ;; missing arguments are undefined
+    (ref.null any)
+  )
+
+  (func $is_object (param $arg anyref) (result i32)
+    (i32.or
+      (i32.or
+        (ref.test (ref $Object) (local.get $arg))
+        (ref.test (ref $Function) (local.get $arg)))
+      (i32.or
+        (ref.test (ref $Promise) (local.get $arg))
+        (ref.test (ref $Array) (local.get $arg))))
+  )
+
+  ;; JS null, as opposed to a null reference, which is undefined
+  (func $is_null (param $arg anyref) (result i32)
+    (ref.test (ref $Null) (local.get $arg))
+  )
+
+  ;; returns the map with own properties of an object or null if the value
+  ;; doesn't hold properties in a map
+  (func $get_own_properties (param $target anyref) (result (ref null $HashMap))
+    (if (ref.test (ref $Object) (local.get $target))
+      (then
+        (return (struct.get $Object $properties (ref.cast (ref $Object) (local.get $target))))
+      )
+    )
+
+    (if (ref.test (ref $Function) (local.get $target))
+      (then
+        (return (struct.get $Function $properties (ref.cast (ref $Function) (local.get $target))))
+      )
+    )
+
+    (if (ref.test (ref $Promise) (local.get $target))
+      (then
+        (return (struct.get $Promise $properties (ref.cast (ref $Promise) (local.get $target))))
+      )
+    )
+
+    (if (ref.test (ref $Array) (local.get $target))
+      (then
+        (return (struct.get $Array $properties (ref.cast (ref $Array) (local.get $target))))
+      )
+    )
+
+    (ref.null $HashMap)
+  )
+
+  ;; the named properties of an array, allocated when the first one is set
+  (func $array_properties (param $array (ref $Array)) (result (ref $HashMap))
+    (local $properties (ref null $HashMap))
+    (local.set $properties (struct.get $Array $properties (local.get $array)))
+    (if (ref.is_null (local.get $properties))
+      (then
+        (local.set $properties (call $new_hashmap))
+        (struct.set $Array $properties (local.get $array) (local.get $properties))
+      )
+    )
+    (ref.as_non_null (local.get $properties))
+  )
+
+  ;; returns the prototype of a value or null if there is none
+  (func $get_prototype (param $target anyref) (result anyref)
+    (local $prototype anyref)
+
+    (if (ref.test (ref $Proxy) (local.get $target))
+      (then (return (call $proxy_get_prototype (ref.cast (ref $Proxy) (local.get $target)))))
+    )
+
+    (if (ref.test (ref $Object) (local.get $target))
+      (then
+        (local.set $prototype (struct.get $Object $prototype (ref.cast (ref $Object) (local.get $target))))
+        ;; JS null is also a valid prototype that ends the chain
+        (if (call $is_null (local.get $prototype))
+          (then (return (ref.null any)))
+        )
+        (return (local.get $prototype))
+      )
+    )
+
+    (if (ref.test (ref $Promise) (local.get $target))
+      (then
+        (return (struct.get $Promise $prototype (ref.cast (ref $Promise) (local.get $target))))
+      )
+    )
+
+    ;; primitive numbers get their methods from Number.prototype
+    (if (call $is_number (local.get $target))
+      (then
+        (return (global.get $number-prototype))
+      )
+    )
+
+    (if (call $is_symbol (local.get $target))
+      (then
+        (return (global.get $symbol-prototype))
+      )
+    )
+
+    (if (call $is_string (local.get $target))
+      (then
+        (return (global.get $string-prototype))
+      )
+    )
+
+    (if (call $is_bigint (local.get $target))
+      (then
+        (return (global.get $bigint-prototype))
+      )
+    )
+
+    (ref.null any)
+  )
+
+  (func $has_own_property (param $target anyref) (param $name i32) (result i32)
+    (local $properties (ref null $HashMap))
+
+    (if (ref.test (ref $Proxy) (local.get $target))
+      (then
+        (return
+          (i32.eqz
+            (ref.is_null
+              (call $proxy_get_own_property (ref.cast (ref $Proxy) (local.get $target)) (local.get $name)))))
+      )
+    )
+
+    (if (ref.test (ref $Array) (local.get $target))
+      (then
+        (if (i32.eq (local.get $name) (i32.const {{ data(str="length") }}))
+          (then (return (i32.const 1)))
+        )
+        ;; invalid indexes are -1, which is out of range when unsigned
+        (if (i32.lt_u
+              (call $to_array_index (call $key_to_string (local.get $name)))
+              (array.len (struct.get $Array $array (ref.cast (ref $Array) (local.get $target)))))
+          (then (return (i32.const 1)))
+        )
+      )
+    )
+
+    (local.set $properties (call $get_own_properties (local.get $target)))
+    (if (ref.is_null (local.get $properties))
+      (then (return (i32.const 0)))
+    )
+
+    (call $hashmap_has (ref.as_non_null (local.get $properties)) (local.get $name))
+  )
+
+  (func $array_get (param $array (ref $Array)) (param $index i32) (result anyref)
+    (local $data (ref $AnyrefArray))
+    (local.set $data (struct.get $Array $array (local.get $array)))
+
+    (if (i32.lt_u (local.get $index) (array.len (local.get $data)))
+      (then
+        (return (array.get $AnyrefArray (local.get $data) (local.get $index)))
+      )
+    )
+
+    (ref.null any)
+  )
+
+  (func $array_set (param $array (ref $Array)) (param $index i32) (param $value anyref)
+    (local $data (ref $AnyrefArray))
+    (local $new_data (ref $AnyrefArray))
+    (local.set $data (struct.get $Array $array (local.get $array)))
+
+    ;; assigning past the end extends the array, any holes are undefined
+    (if (i32.ge_u (local.get $index) (array.len (local.get $data)))
+      (then
+        (local.set $new_data
+          (array.new $AnyrefArray (ref.null any) (i32.add (local.get $index) (i32.const 1))))
+        (array.copy $AnyrefArray $AnyrefArray
+          (local.get $new_data)
+          (i32.const 0)
+          (local.get $data)
+          (i32.const 0)
+          (array.len (local.get $data)))
+        (struct.set $Array $array (local.get $array) (local.get $new_data))
+        (local.set $data (local.get $new_data))
+      )
+    )
+
+    (array.set $AnyrefArray (local.get $data) (local.get $index) (local.get $value))
+  )
+
+  ;; obj[key]
+  (func $get_computed_property (param $target anyref) (param $key anyref) (result anyref)
+    (local $index i32)
+
+    (if (ref.test (ref $Array) (local.get $target))
+      (then
+        (local.set $index (call $to_array_index (local.get $key)))
+        (if (i32.ge_s (local.get $index) (i32.const 0))
+          (then
+            (return (call $array_get (ref.cast (ref $Array) (local.get $target)) (local.get $index)))
+          )
+        )
+      )
+    )
+
+    (if (ref.test (ref $TypedArray) (local.get $target))
+      (then
+        (local.set $index (call $to_array_index (local.get $key)))
+        (if (i32.ge_s (local.get $index) (i32.const 0))
+          (then
+            (return (call $typed_array_get (ref.cast (ref $TypedArray) (local.get $target)) (local.get $index)))
+          )
+        )
+      )
+    )
+
+    (call $get_property (local.get $target) (call $to_property_key (local.get $key)))
+  )
+
+  ;; obj[key] = value
+  (func $set_computed_property (param $target anyref) (param $key anyref) (param $value anyref)
+    (local $index i32)
+
+    (if (ref.test (ref $Array) (local.get $target))
+      (then
+        (local.set $index (call $to_array_index (local.get $key)))
+        (if (i32.ge_s (local.get $index) (i32.const 0))
+          (then
+            (call $array_set (ref.cast (ref $Array) (local.get $target)) (local.get $index) (local.get $value))
+            (return)
+          )
+        )
+      )
+    )
+
+    ;; writes past the end of a typed array are ignored
+    (if (ref.test (ref $TypedArray) (local.get $target))
+      (then
+        (local.set $index (call $to_array_index (local.get $key)))
+        (if (i32.ge_s (local.get $index) (i32.const 0))
+          (then
+            (call $typed_array_set (ref.cast (ref $TypedArray) (local.get $target)) (local.get $index) (local.get $value))
+            (return)
+          )
+        )
+      )
+    )
+
+    (call $set_property (local.get $target) (call $to_property_key (local.get $key)) (local.get $value))
+  )
+
+  ;; SameValue from the spec, used by Object.is
+  (func $same_value (param $arg1 anyref) (param $arg2 anyref) (result i32)
+    (local $num1 f64)
+    (local $num2 f64)
+
+    (if (i32.and
+          (call $is_number (local.get $arg1))
+          (call $is_number (local.get $arg2)))
+      (then
+        (local.set $num1 (call $number_value (local.get $arg1)))
+        (local.set $num2 (call $number_value (local.get $arg2)))
+        ;; NaN is the same value as NaN
+        (if (i32.and
+              (f64.ne (local.get $num1) (local.get $num1))
+              (f64.ne (local.get $num2) (local.get $num2)))
+          (then (return (i32.const 1)))
+        )
+        ;; comparing bits makes +0 and -0 different
+        (return
+          (i64.eq
+            (i64.reinterpret_f64 (local.get $num1))
+            (i64.reinterpret_f64 (local.get $num2))))
+      )
+    )
+
+    (call $same_value_non_number (local.get $arg1) (local.get $arg2))
+  )
+
+  (func $same_value_non_number (param $arg1 anyref) (param $arg2 anyref) (result i32)
+    (if (i32.and
+          (ref.test nullref (local.get $arg1))
+          (ref.test nullref (local.get $arg2)))
+      (then (return (i32.const 1)))
+    )
+
+    (if (i32.or
+          (ref.test nullref (local.get $arg1))
+          (ref.test nullref (local.get $arg2)))
+      (then (return (i32.const 0)))
+    )
+
+    (if (i32.and
+          (call $is_string (local.get $arg1))
+          (call $is_string (local.get $arg2)))
+      (then
+        (return (call $string_equals (local.get $arg1) (local.get $arg2)))
+      )
+    )
+
+    ;; BigInts are compared by value
+    (if (i32.and
+          (call $is_bigint (local.get $arg1))
+          (call $is_bigint (local.get $arg2)))
+      (then
+        (return
+          (call $bigint_equals
+            (ref.cast (ref $BigInt) (local.get $arg1))
+            (ref.cast (ref $BigInt) (local.get $arg2))))
+      )
+    )
+
+    (if (i32.and
+          (ref.test eqref (local.get $arg1))
+          (ref.test eqref (local.get $arg2)))
+      (then
+        (return
+          (ref.eq
+            (ref.cast eqref (local.get $arg1))
+            (ref.cast eqref (local.get $arg2))))
+      )
+    )
+
+    (i32.const 0)
+  )
+
+  ;; Returns own enumerable properties of an object as an array. $kind decides
+  ;; what is put in the array:
+  ;;   0 - keys
+  ;;   1 - values
+  ;;   2 - [key, value] pairs
+  (func $own_property_entries (param $target anyref) (param $kind i32) (result (ref $Array))
+    (local $properties (ref null $HashMap))
+    (local $entries (ref $EntriesArray))
+    (local $entry (ref null $HashMapEntry))
+    (local $source (ref $AnyrefArray))
+    (local $result (ref $Array))
+    (local $data (ref $AnyrefArray))
+    (local $order (ref $I32Array))
+    (local $size i32)
+    (local $i i32)
+
+    (if (ref.test (ref $Proxy) (local.get $target))
+      (then
+        (return (call $proxy_property_entries (ref.cast (ref $Proxy) (local.get $target)) (local.get $kind)))
+      )
+    )
+
+    ;; arrays don't keep their elements in a map, but they are still enumerable
+    (if (ref.test (ref $Array) (local.get $target))
+      (then
+        (local.set $source (struct.get $Array $array (ref.cast (ref $Array) (local.get $target))))
+        (local.set $size (array.len (local.get $source)))
+        (local.set $result (call $new_array (local.get $size)))
+        (local.set $data (struct.get $Array $array (local.get $result)))
+
+        (block $break (loop $copy
+          (br_if $break (i32.ge_u (local.get $i) (local.get $size)))
+
+          (array.set $AnyrefArray (local.get $data) (local.get $i)
+            (call $property_entry
+              (local.get $kind)
+              (call $number_to_string (f64.convert_i32_u (local.get $i)))
+              (array.get $AnyrefArray (local.get $source) (local.get $i))))
+
+          (local.set $i (i32.add (local.get $i) (i32.const 1)))
+          (br $copy)
+        ))
+
+        (return (local.get $result))
+      )
+    )
+
+    (local.set $properties (call $get_own_properties (local.get $target)))
+    (if (ref.is_null (local.get $properties))
+      (then (return (call $new_array (i32.const 0))))
+    )
+
+    (local.set $entries (struct.get $HashMap $entries (ref.as_non_null (local.get $properties))))
+    ;; symbol keys are not enumerated
+    (local.set $order (call $own_key_order (ref.as_non_null (local.get $properties)) (i32.const 0)))
+    (local.set $size (array.len (local.get $order)))
+    (local.set $result (call $new_array (local.get $size)))
+    (local.set $data (struct.get $Array $array (local.get $result)))
+
+    (local.set $i (i32.const 0))
+    (block $break (loop $copy
+      (br_if $break (i32.ge_u (local.get $i) (local.get $size)))
+
+      (local.set $entry
+        (array.get $EntriesArray (local.get $entries) (array.get $I32Array (local.get $order) (local.get $i))))
+      (array.set $AnyrefArray (local.get $data) (local.get $i)
+        (call $property_entry
+          (local.get $kind)
+          (call $key_to_string (struct.get $HashMapEntry $key (local.get $entry)))
+          (struct.get $HashMapEntry $value (local.get $entry))))
+
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $copy)
+    ))
+
+    (local.get $result)
+  )
+
+  ;; The array index a property key stands for, a canonical number between 0
+  ;; and 2^32 - 2 (read as unsigned), or -1 if it's not one
+  (func $key_array_index (param $key anyref) (result i32)
+    (local $length i32)
+    (local $digit i32)
+    (local $value i64)
+    (local $i i32)
+
+    (local.set $length (call $string_length (local.get $key)))
+    ;; an empty string or a number with a leading zero is not an index
+    (if (i32.or
+          (i32.or (i32.eqz (local.get $length)) (i32.gt_u (local.get $length) (i32.const 10)))
+          (i32.and
+            (i32.gt_u (local.get $length) (i32.const 1))
+            (i32.eq (call $string_byte_at (local.get $key) (i32.const 0)) (i32.const 48))))
+      (then (return (i32.const -1)))
+    )
+    (block $break (loop $digits
+      (br_if $break (i32.ge_u (local.get $i) (local.get $length)))
+      (local.set $digit (i32.sub (call $string_byte_at (local.get $key) (local.get $i)) (i32.const 48)))
+      (if (i32.gt_u (local.get $digit) (i32.const 9))
+        (then (return (i32.const -1)))
+      )
+      (local.set $value
+        (i64.add
+          (i64.mul (local.get $value) (i64.const 10))
+          (i64.extend_i32_u (local.get $digit))))
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $digits)
+    ))
+    (if (i64.ge_u (local.get $value) (i64.const 4294967295))
+      (then (return (i32.const -1)))
+    )
+    (i32.wrap_i64 (local.get $value))
+  )
+
+  ;; The positions of the live entries of a property map in the order of
+  ;; OrdinaryOwnPropertyKeys: keys that are array indexes in ascending order,
+  ;; then other strings and then symbols (if $symbols is set), both in
+  ;; insertion order
+  (func $own_key_order (param $properties (ref $HashMap)) (param $symbols i32) (result (ref $I32Array))
+    (local $entries (ref $EntriesArray))
+    (local $entry (ref null $HashMapEntry))
+    (local $size i32)
+    (local $kinds (ref $I32Array))
+    (local $indexes (ref $I32Array))
+    (local $order (ref $I32Array))
+    (local $result (ref $I32Array))
+    (local $count i32)
+    (local $kind i32)
+    (local $index i32)
+    (local $i i32)
+    (local $j i32)
+
+    (local.set $entries (struct.get $HashMap $entries (local.get $properties)))
+    (local.set $size (struct.get $HashMap $size (local.get $properties)))
+    ;; 0 for deleted entries, 1 for indexes, 2 for other strings, 3 for symbols
+    (local.set $kinds (array.new_default $I32Array (local.get $size)))
+    (local.set $indexes (array.new_default $I32Array (local.get $size)))
+    (local.set $order (array.new_default $I32Array (local.get $size)))
+
+    ;; indexes go first, insertion sorted by their value
+    (block $break (loop $integers
+      (br_if $break (i32.ge_u (local.get $i) (local.get $size)))
+
+      (local.set $entry (array.get $EntriesArray (local.get $entries) (local.get $i)))
+      (if (i32.eqz (ref.is_null (local.get $entry)))
+        (then
+          (local.set $kind (i32.const 3))
+          (if (i32.eqz (call $is_symbol_key (struct.get $HashMapEntry $key (local.get $entry))))
+            (then
+              (local.set $kind (i32.const 2))
+              (local.set $index
+                (call $key_array_index (call $key_to_string (struct.get $HashMapEntry $key (local.get $entry)))))
+              (if (i32.ne (local.get $index) (i32.const -1))
+                (then
+                  (local.set $kind (i32.const 1))
+                  (local.set $j (local.get $count))
+                  (block $placed (loop $shift
+                    (br_if $placed (i32.eqz (local.get $j)))
+                    (br_if $placed
+                      (i32.le_u
+                        (array.get $I32Array (local.get $indexes) (i32.sub (local.get $j) (i32.const 1)))
+                        (local.get $index)))
+                    (array.set $I32Array (local.get $indexes) (local.get $j)
+                      (array.get $I32Array (local.get $indexes) (i32.sub (local.get $j) (i32.const 1))))
+                    (array.set $I32Array (local.get $order) (local.get $j)
+                      (array.get $I32Array (local.get $order) (i32.sub (local.get $j) (i32.const 1))))
+                    (local.set $j (i32.sub (local.get $j) (i32.const 1)))
+                    (br $shift)
+                  ))
+                  (array.set $I32Array (local.get $indexes) (local.get $j) (local.get $index))
+                  (array.set $I32Array (local.get $order) (local.get $j) (local.get $i))
+                  (local.set $count (i32.add (local.get $count) (i32.const 1)))
+                )
+              )
+            )
+          )
+          (array.set $I32Array (local.get $kinds) (local.get $i) (local.get $kind))
+        )
+      )
+
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $integers)
+    ))
+
+    ;; then the rest, a pass for strings and one for symbols
+    (local.set $kind (i32.const 2))
+    (block $done (loop $pass
+      (local.set $i (i32.const 0))
+      (block $break (loop $others
+        (br_if $break (i32.ge_u (local.get $i) (local.get $size)))
+        (if (i32.eq (array.get $I32Array (local.get $kinds) (local.get $i)) (local.get $kind))
+          (then
+            (array.set $I32Array (local.get $order) (local.get $count) (local.get $i))
+            (local.set $count (i32.add (local.get $count) (i32.const 1)))
+          )
+        )
+        (local.set $i (i32.add (local.get $i) (i32.const 1)))
+        (br $others)
+      ))
+
+      (br_if $done (i32.or (i32.eq (local.get $kind) (i32.const 3)) (i32.eqz (local.get $symbols))))
+      (local.set $kind (i32.const 3))
+      (br $pass)
+    ))
+
+    (local.set $result (array.new_default $I32Array (local.get $count)))
+    (array.copy $I32Array $I32Array
+      (local.get $result) (i32.const 0) (local.get $order) (i32.const 0) (local.get $count))
+    (local.get $result)
+  )
+
+  (func $property_entry (param $kind i32) (param $key anyref) (param $value anyref) (result anyref)
+    (local $pair (ref $Array))
+
+    (if (i32.eqz (local.get $kind))
+      (then (return (local.get $key)))
+    )
+    (if (i32.eq (local.get $kind) (i32.const 1))
+      (then (return (local.get $value)))
+    )
+
+    (local.set $pair (call $new_array (i32.const 2)))
+    (array.set $AnyrefArray (struct.get $Array $array (local.get $pair)) (i32.const 0) (local.get $key))
+    (array.set $AnyrefArray (struct.get $Array $array (local.get $pair)) (i32.const 1) (local.get $value))
+    (local.get $pair)
+  )
+
+  ;; copies own enumerable properties from $source to $target
+  (func $copy_own_properties (param $target anyref) (param $source anyref)
+    (local $entries (ref $Array))
+    (local $data (ref $AnyrefArray))
+    (local $pair (ref $AnyrefArray))
+    (local $i i32)
+
+    (local.set $entries (call $own_property_entries (local.get $source) (i32.const 2)))
+    (local.set $data (struct.get $Array $array (local.get $entries)))
+
+    (block $break (loop $copy
+      (br_if $break (i32.ge_u (local.get $i) (array.len (local.get $data))))
+
+      (local.set $pair
+        (struct.get $Array $array
+          (ref.cast (ref $Array) (array.get $AnyrefArray (local.get $data) (local.get $i)))))
+      (call $set_computed_property
+        (local.get $target)
+        (array.get $AnyrefArray (local.get $pair) (i32.const 0))
+        (array.get $AnyrefArray (local.get $pair) (i32.const 1)))
+
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $copy)
+    ))
+  )
+
+  ;; the object created by `new`, it inherits from the constructor's prototype
+  ;; property if that's an object
+  (func $new_instance (param $constructor anyref) (result (ref $Object))
+    (local $instance (ref $Object))
+    (local $prototype anyref)
+
+    (local.set $instance (call $new_object))
+    (if (ref.test (ref $Function) (local.get $constructor))
+      (then
+        (local.set $prototype
+          (call $get_property (local.get $constructor) (i32.const {{ data(str="prototype") }})))
+        (if (call $is_object (local.get $prototype))
+          (then (struct.set $Object $prototype (local.get $instance) (local.get $prototype)))
+        )
+      )
+    )
+
+    (local.get $instance)
+  )
+
+  ;; [[SetPrototypeOf]], returns 0 if the prototype couldn't be changed
+  (func $set_prototype (param $target anyref) (param $prototype anyref) (result i32)
+    (if (ref.test (ref $Proxy) (local.get $target))
+      (then
+        (return (call $proxy_set_prototype (ref.cast (ref $Proxy) (local.get $target)) (local.get $prototype)))
+      )
+    )
+
+    ;; TODO: other objects can't have their prototype changed yet
+    (if (ref.test (ref $Object) (local.get $target))
+      (then
+        (struct.set $Object $prototype
+          (ref.cast (ref $Object) (local.get $target))
+          (local.get $prototype))
+      )
+    )
+
+    (i32.const 1)
+  )
+
+  ;; [[Construct]], used by `new` and Reflect.construct
+  (func $construct (param $constructor anyref) (param $arguments (ref $JSArgs)) (result anyref)
+    (local $instance (ref $Object))
+
+    (if (ref.test (ref $Proxy) (local.get $constructor))
+      (then
+        (return (call $proxy_construct (ref.cast (ref $Proxy) (local.get $constructor)) (local.get $arguments)))
+      )
+    )
+
+    (local.set $instance (call $new_instance (local.get $constructor)))
+    (call $return_object_or
+      (call $call_function (local.get $constructor) (local.get $instance) (local.get $arguments))
+      (local.get $instance))
+  )
+
+  ;; [[HasProperty]], looks for the key on the object and its prototypes
+  (func $has_property (param $target anyref) (param $name i32) (result i32)
+    (local $current anyref)
+
+    (local.set $current (local.get $target))
+    (block $done (loop $lookup
+      (br_if $done (ref.is_null (local.get $current)))
+
+      (if (ref.test (ref $Proxy) (local.get $current))
+        (then (return (call $proxy_has (ref.cast (ref $Proxy) (local.get $current)) (local.get $name))))
+      )
+      (if (call $has_own_property (local.get $current) (local.get $name))
+        (then (return (i32.const 1)))
+      )
+
+      (local.set $current (call $get_prototype (local.get $current)))
+      (br $lookup)
+    ))
+
+    (i32.const 0)
+  )
+
+  ;; key in target
+  (func $in_operator (param $key anyref) (param $target anyref) (result (ref $Boolean))
+    (if (i32.eqz (call $is_object (local.get $target)))
+      (then
+        (call $throw_type_error
+          (call $concat_strings
+            (call $concat_strings
+              (call $concat_strings
+                (call $new_static_string (i32.const {{ data(str="Cannot use 'in' operator to search for '") }}) (i32.const {{ data_length() }}))
+                (call $to_string (local.get $key)))
+              (call $new_static_string (i32.const {{ data(str="' in ") }}) (i32.const {{ data_length() }})))
+            (call $to_string (local.get $target))))
+      )
+    )
+
+    (call $new_boolean (call $has_property (local.get $target) (call $to_property_key (local.get $key))))
+  )
+
+  ;; [[Delete]], returns 0 for properties that can't be deleted
+  (func $delete_property (param $target anyref) (param $name i32) (result i32)
+    (local $properties (ref null $HashMap))
+    (local $array (ref $Array))
+    (local $index i32)
+
+    (if (ref.test (ref $Proxy) (local.get $target))
+      (then
+        (return (call $proxy_delete (ref.cast (ref $Proxy) (local.get $target)) (local.get $name)))
+      )
+    )
+
+    (if (i32.or
+          (ref.test nullref (local.get $target))
+          (call $is_null (local.get $target)))
+      (then
+        (call $throw_type_error
+          (call $new_static_string (i32.const {{ data(str="Cannot convert undefined or null to object") }}) (i32.const {{ data_length() }})))
+      )
+    )
+
+    ;; deleting an element leaves a hole, which reads as undefined
+    (if (ref.test (ref $Array) (local.get $target))
+      (then
+        (local.set $array (ref.cast (ref $Array) (local.get $target)))
+        (if (i32.eq (local.get $name) (i32.const {{ data(str="length") }}))
+          (then (return (i32.const 0)))
+        )
+        (local.set $index (call $to_array_index (call $key_to_string (local.get $name))))
+        (if (i32.lt_u (local.get $index) (array.len (struct.get $Array $array (local.get $array))))
+          (then
+            (array.set $AnyrefArray (struct.get $Array $array (local.get $array)) (local.get $index) (ref.null any))
+            (return (i32.const 1))
+          )
+        )
+      )
+    )
+
+    (local.set $properties (call $get_own_properties (local.get $target)))
+    (if (i32.eqz (ref.is_null (local.get $properties)))
+      (then (drop (call $hashmap_delete (ref.as_non_null (local.get $properties)) (local.get $name))))
+    )
+
+    (i32.const 1)
+  )
+
+  ;; [[OwnPropertyKeys]], all own keys including symbols and non-enumerable
+  ;; ones, see $own_key_order
+  (func $own_property_keys (param $target anyref) (result (ref $Array))
+    (local $properties (ref null $HashMap))
+    (local $entries (ref $EntriesArray))
+    (local $entry (ref null $HashMapEntry))
+    (local $order (ref $I32Array))
+    (local $keys (ref $ValueList))
+    (local $length i32)
+    (local $i i32)
+
+    (if (ref.test (ref $Proxy) (local.get $target))
+      (then (return (call $proxy_own_keys (ref.cast (ref $Proxy) (local.get $target)))))
+    )
+
+    (local.set $keys (call $new_value_list))
+    (if (ref.test (ref $Array) (local.get $target))
+      (then
+        (local.set $length (array.len (struct.get $Array $array (ref.cast (ref $Array) (local.get $target)))))
+        (block $break (loop $indexes
+          (br_if $break (i32.ge_u (local.get $i) (local.get $length)))
+          (call $value_list_push (local.get $keys) (call $number_to_string (f64.convert_i32_u (local.get $i))))
+          (local.set $i (i32.add (local.get $i) (i32.const 1)))
+          (br $indexes)
+        ))
+        (call $value_list_push (local.get $keys)
+          (call $new_static_string (i32.const {{ data(str="length") }}) (i32.const {{ data_length() }})))
+      )
+    )
+
+    (local.set $properties (call $get_own_properties (local.get $target)))
+    (if (ref.is_null (local.get $properties))
+      (then (return (call $value_list_to_array (local.get $keys))))
+    )
+    (local.set $entries (struct.get $HashMap $entries (ref.as_non_null (local.get $properties))))
+    (local.set $order (call $own_key_order (ref.as_non_null (local.get $properties)) (i32.const 1)))
+    (local.set $length (array.len (local.get $order)))
+
+    (local.set $i (i32.const 0))
+    (block $break (loop $copy
+      (br_if $break (i32.ge_u (local.get $i) (local.get $length)))
+      (local.set $entry
+        (array.get $EntriesArray (local.get $entries) (array.get $I32Array (local.get $order) (local.get $i))))
+      (call $value_list_push (local.get $keys)
+        (call $key_to_string (struct.get $HashMapEntry $key (local.get $entry))))
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $copy)
+    ))
+
+    (call $value_list_to_array (local.get $keys))
+  )
+
+  ;; [[GetOwnProperty]], returns a descriptor object or undefined
+  (func $own_property_descriptor (param $target anyref) (param $name i32) (result anyref)
+    (local $descriptor (ref $Object))
+    (local $is_length i32)
+
+    (if (ref.test (ref $Proxy) (local.get $target))
+      (then
+        (return (call $proxy_get_own_property (ref.cast (ref $Proxy) (local.get $target)) (local.get $name)))
+      )
+    )
+
+    (if (i32.eqz (call $has_own_property (local.get $target) (local.get $name)))
+      (then (return (ref.null any)))
+    )
+
+    ;; TODO: property attributes are not supported yet, so apart from the
+    ;; length of arrays every property is a plain data property
+    (local.set $is_length
+      (i32.and
+        (ref.test (ref $Array) (local.get $target))
+        (i32.eq (local.get $name) (i32.const {{ data(str="length") }}))))
+
+    (local.set $descriptor (call $new_object))
+    (call $set_property (local.get $descriptor) (i32.const {{ data(str="value") }})
+      (call $get_computed_property (local.get $target) (call $key_to_string (local.get $name))))
+    (call $set_property (local.get $descriptor) (i32.const {{ data(str="writable") }}) (global.get $true))
+    (call $set_property (local.get $descriptor) (i32.const {{ data(str="enumerable") }})
+      (call $new_boolean (i32.eqz (local.get $is_length))))
+    (call $set_property (local.get $descriptor) (i32.const {{ data(str="configurable") }})
+      (call $new_boolean (i32.eqz (local.get $is_length))))
+    (local.get $descriptor)
+  )
+
+  ;; [[DefineOwnProperty]], only the value of the descriptor is used
+  (func $define_property (param $target anyref) (param $name i32) (param $descriptor anyref) (result i32)
+    (if (ref.test (ref $Proxy) (local.get $target))
+      (then
+        (return
+          (call $proxy_define_property
+            (ref.cast (ref $Proxy) (local.get $target))
+            (local.get $name)
+            (local.get $descriptor)))
+      )
+    )
+
+    (if (call $has_property (local.get $descriptor) (i32.const {{ data(str="value") }}))
+      (then
+        (call $set_computed_property
+          (local.get $target)
+          (call $key_to_string (local.get $name))
+          (call $get_property (local.get $descriptor) (i32.const {{ data(str="value") }})))
+      )
+    )
+
+    (i32.const 1)
+  )
+
+  ;; functions get their `prototype` object the first time it's accessed, so
+  ;; functions that are never used as constructors don't need one
+  (func $function_prototype (param $target anyref) (result anyref)
+    (local $function (ref $Function))
+    (local $prototype (ref $Object))
+
+    (if (i32.eqz (ref.test (ref $Function) (local.get $target)))
+      (then (return (global.get $no-value)))
+    )
+    (local.set $function (ref.cast (ref $Function) (local.get $target)))
+    (if (call $hashmap_has
+          (struct.get $Function $properties (local.get $function))
+          (i32.const {{ data(str="prototype") }}))
+      (then (return (global.get $no-value)))
+    )
+
+    (local.set $prototype (call $new_object))
+    (call $set_property (local.get $prototype) (i32.const {{ data(str="constructor") }}) (local.get $function))
+    (call $set_property (local.get $function) (i32.const {{ data(str="prototype") }}) (local.get $prototype))
+    (local.get $prototype)
+  )
+
+  ;; ToPrimitive from the spec, $hint is 0 for "default", 1 for "number" and
+  ;; 2 for "string"
+  (func $to_primitive (param $value anyref) (param $hint i32) (result anyref)
+    (local $method anyref)
+    (local $result anyref)
+    (local $hint_string anyref)
+
+    (if (i32.eqz (call $is_object (local.get $value)))
+      (then (return (local.get $value)))
+    )
+
+    (local.set $method
+      (call $get_property
+        (local.get $value)
+        (call $well_known_symbol_key (global.get $symbol-to-primitive))))
+    (if (i32.eqz (i32.or (ref.is_null (local.get $method)) (call $is_null (local.get $method))))
+      (then
+        (if (i32.eqz (ref.test (ref $Function) (local.get $method)))
+          (then
+            (call $throw_type_error
+              (call $new_static_string (i32.const {{ data(str="Symbol.toPrimitive is not a function") }}) (i32.const {{ data_length() }})))
+          )
+        )
+
+        (local.set $hint_string
+          (call $new_static_string (i32.const {{ data(str="default") }}) (i32.const {{ data_length() }})))
+        (if (i32.eq (local.get $hint) (i32.const 1))
+          (then
+            (local.set $hint_string
+              (call $new_static_string (i32.const {{ data(str="number") }}) (i32.const {{ data_length() }})))
+          )
+        )
+        (if (i32.eq (local.get $hint) (i32.const 2))
+          (then
+            (local.set $hint_string
+              (call $new_static_string (i32.const {{ data(str="string") }}) (i32.const {{ data_length() }})))
+          )
+        )
+
+        (local.set $result
+          (call $call_function
+            (local.get $method)
+            (local.get $value)
+            (array.new_fixed $JSArgs 1 (local.get $hint_string))))
+        (if (call $is_object (local.get $result))
+          (then
+            (call $throw_type_error
+              (call $new_static_string (i32.const {{ data(str="Cannot convert object to primitive value") }}) (i32.const {{ data_length() }})))
+          )
+        )
+        (return (local.get $result))
+      )
+    )
+
+    ;; OrdinaryToPrimitive, the string hint tries toString first
+    (if (i32.eq (local.get $hint) (i32.const 2))
+      (then
+        (local.set $result (call $call_primitive_method (local.get $value) (i32.const {{ data(str="toString") }})))
+        (if (i32.eqz (call $is_no_value_found (local.get $result)))
+          (then (return (local.get $result)))
+        )
+        (local.set $result (call $call_primitive_method (local.get $value) (i32.const {{ data(str="valueOf") }})))
+      )
+      (else
+        (local.set $result (call $call_primitive_method (local.get $value) (i32.const {{ data(str="valueOf") }})))
+        (if (i32.eqz (call $is_no_value_found (local.get $result)))
+          (then (return (local.get $result)))
+        )
+        (local.set $result (call $call_primitive_method (local.get $value) (i32.const {{ data(str="toString") }})))
+      )
+    )
+    (if (i32.eqz (call $is_no_value_found (local.get $result)))
+      (then (return (local.get $result)))
+    )
+
+    ;; arrays and functions don't have a prototype with these methods yet, so
+    ;; they use the conversions built into ToString
+    (if (i32.or
+          (ref.test (ref $Array) (local.get $value))
+          (ref.test (ref $Function) (local.get $value)))
+      (then (return (call $to_string (local.get $value))))
+    )
+    ;; and neither do proxies of them
+    (if (ref.test (ref $Proxy) (local.get $value))
+      (then
+        (return
+          (call $to_primitive
+            (struct.get $Proxy $target (ref.cast (ref $Proxy) (local.get $value)))
+            (local.get $hint)))
+      )
+    )
+
+    (call $throw_type_error
+      (call $new_static_string (i32.const {{ data(str="Cannot convert object to primitive value") }}) (i32.const {{ data_length() }})))
+    (unreachable)
+  )
+
+  ;; calls a method used by OrdinaryToPrimitive, returns $no-value if there's
+  ;; no such method or if it returns an object
+  (func $call_primitive_method (param $value anyref) (param $name i32) (result anyref)
+    (local $method anyref)
+    (local $result anyref)
+
+    (local.set $method (call $get_property (local.get $value) (local.get $name)))
+    (if (i32.eqz (ref.test (ref $Function) (local.get $method)))
+      (then (return (global.get $no-value)))
+    )
+
+    (local.set $result
+      (call $call_function (local.get $method) (local.get $value) (array.new_fixed $JSArgs 0)))
+    (if (call $is_object (local.get $result))
+      (then (return (global.get $no-value)))
+    )
+    (local.get $result)
+  )
+
+  ;; InstanceofOperator from the spec
+  (func $instance_of (param $value anyref) (param $target anyref) (result (ref $Boolean))
+    (local $method anyref)
+    (local $prototype anyref)
+    (local $current anyref)
+
+    (if (i32.eqz (call $is_object (local.get $target)))
+      (then
+        (call $throw_type_error
+          (call $new_static_string (i32.const {{ data(str="Right-hand side of 'instanceof' is not callable") }}) (i32.const {{ data_length() }})))
+      )
+    )
+
+    (local.set $method
+      (call $get_property
+        (local.get $target)
+        (call $well_known_symbol_key (global.get $symbol-has-instance))))
+    (if (ref.test (ref $Function) (local.get $method))
+      (then
+        (return
+          (call $new_boolean
+            (call $cast_ref_to_i32_bool
+              (call $call_function
+                (local.get $method)
+                (local.get $target)
+                (array.new_fixed $JSArgs 1 (local.get $value))))))
+      )
+    )
+
+    (if (i32.eqz (ref.test (ref $Function) (local.get $target)))
+      (then
+        (call $throw_type_error
+          (call $new_static_string (i32.const {{ data(str="Right-hand side of 'instanceof' is not callable") }}) (i32.const {{ data_length() }})))
+      )
+    )
+
+    ;; OrdinaryHasInstance, primitives are never instances
+    (if (i32.eqz (call $is_object (local.get $value)))
+      (then (return (global.get $false)))
+    )
+
+    (local.set $prototype (call $get_property (local.get $target) (i32.const {{ data(str="prototype") }})))
+    (if (i32.eqz (call $is_object (local.get $prototype)))
+      (then
+        (call $throw_type_error
+          (call $new_static_string (i32.const {{ data(str="Function has non-object prototype in instanceof check") }}) (i32.const {{ data_length() }})))
+      )
+    )
+
+    (local.set $current (call $get_prototype (local.get $value)))
+    (block $done (loop $walk
+      (br_if $done (ref.is_null (local.get $current)))
+      (if (ref.eq (ref.cast eqref (local.get $current)) (ref.cast eqref (local.get $prototype)))
+        (then (return (global.get $true)))
+      )
+      (local.set $current (call $get_prototype (local.get $current)))
+      (br $walk)
+    ))
+
+    (global.get $false)
+  )
+
+  (func $Object-constructor
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+
+    (local $value anyref)
+    (local.set $value (call $get_argument (local.get $arguments) (i32.const 0)))
+
+    (if (call $is_object (local.get $value))
+      (then (return (local.get $value)))
+    )
+
+    ;; TODO: primitives should be wrapped in their wrapper objects
+    (call $new_object)
+  )
+
+  (func $Object-keys
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+
+    (call $own_property_entries (call $object_argument (local.get $arguments)) (i32.const 0))
+  )
+
+  (func $Object-values
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+
+    (call $own_property_entries (call $object_argument (local.get $arguments)) (i32.const 1))
+  )
+
+  (func $Object-entries
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+
+    (call $own_property_entries (call $object_argument (local.get $arguments)) (i32.const 2))
+  )
+
+  ;; returns the first argument, throwing a TypeError if it can't be converted
+  ;; to an object
+  (func $object_argument (param $arguments (ref $JSArgs)) (result anyref)
+    (local $value anyref)
+    (local.set $value (call $get_argument (local.get $arguments) (i32.const 0)))
+
+    (if (i32.or
+          (ref.test nullref (local.get $value))
+          (call $is_null (local.get $value)))
+      (then
+        (call $throw_type_error
+          (call $new_static_string
+            (i32.const {{ data(str="Cannot convert undefined or null to object") }})
+            (i32.const {{ data_length() }})))
+      )
+    )
+
+    (local.get $value)
+  )
+
+  (func $Object-fromEntries
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+
+    (local $iterable anyref)
+    (local $data (ref $AnyrefArray))
+    (local $entry anyref)
+    (local $object (ref $Object))
+    (local $i i32)
+
+    (local.set $object (call $new_object))
+    (local.set $iterable (call $get_argument (local.get $arguments) (i32.const 0)))
+
+    ;; TODO: this should use the iterator protocol
+    (if (i32.eqz (ref.test (ref $Array) (local.get $iterable)))
+      (then
+        (call $throw_type_error
+          (call $new_static_string
+            (i32.const {{ data(str="Object.fromEntries requires an iterable") }})
+            (i32.const {{ data_length() }})))
+      )
+    )
+
+    (local.set $data (struct.get $Array $array (ref.cast (ref $Array) (local.get $iterable))))
+    (block $break (loop $copy
+      (br_if $break (i32.ge_u (local.get $i) (array.len (local.get $data))))
+
+      (local.set $entry (array.get $AnyrefArray (local.get $data) (local.get $i)))
+      (if (i32.eqz (call $is_object (local.get $entry)))
+        (then
+          (call $throw_type_error
+            (call $new_static_string
+              (i32.const {{ data(str="Iterator value is not an entry object") }})
+              (i32.const {{ data_length() }})))
+        )
+      )
+
+      (call $set_computed_property
+        (local.get $object)
+        (call $get_computed_property (local.get $entry) (call $new_number (f64.const 0)))
+        (call $get_computed_property (local.get $entry) (call $new_number (f64.const 1))))
+
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $copy)
+    ))
+
+    (local.get $object)
+  )
+
+  (func $Object-assign
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+
+    (local $target anyref)
+    (local $i i32)
+
+    (local.set $target (call $object_argument (local.get $arguments)))
+
+    (local.set $i (i32.const 1))
+    (block $break (loop $sources
+      (br_if $break (i32.ge_u (local.get $i) (array.len (local.get $arguments))))
+
+      (call $copy_own_properties
+        (local.get $target)
+        (array.get $JSArgs (local.get $arguments) (local.get $i)))
+
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $sources)
+    ))
+
+    (local.get $target)
+  )
+
+  (func $Object-create
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+
+    (local $prototype anyref)
+    (local $properties anyref)
+    (local $object (ref $Object))
+    (local $descriptors (ref $AnyrefArray))
+    (local $pair (ref $AnyrefArray))
+    (local $i i32)
+
+    (local.set $prototype (call $get_argument (local.get $arguments) (i32.const 0)))
+    (call $check_prototype_argument (local.get $prototype))
+
+    (local.set $object (call $new_object))
+    (struct.set $Object $prototype (local.get $object) (local.get $prototype))
+
+    ;; TODO: property attributes are not supported yet, so only `value` is taken
+    ;; from descriptors
+    (local.set $properties (call $get_argument (local.get $arguments) (i32.const 1)))
+    (if (call $is_object (local.get $properties))
+      (then
+        (local.set $descriptors
+          (struct.get $Array $array (call $own_property_entries (local.get $properties) (i32.const 2))))
+        (block $break (loop $define
+          (br_if $break (i32.ge_u (local.get $i) (array.len (local.get $descriptors))))
+
+          (local.set $pair
+            (struct.get $Array $array
+              (ref.cast (ref $Array) (array.get $AnyrefArray (local.get $descriptors) (local.get $i)))))
+          (call $set_computed_property
+            (local.get $object)
+            (array.get $AnyrefArray (local.get $pair) (i32.const 0))
+            (call $get_property
+              (array.get $AnyrefArray (local.get $pair) (i32.const 1))
+              (i32.const {{ data(str="value") }})))
+
+          (local.set $i (i32.add (local.get $i) (i32.const 1)))
+          (br $define)
+        ))
+      )
+    )
+
+    (local.get $object)
+  )
+
+  ;; a prototype has to be either an object or null
+  (func $check_prototype_argument (param $prototype anyref)
+    (if (i32.eqz
+          (i32.or
+            (call $is_object (local.get $prototype))
+            (call $is_null (local.get $prototype))))
+      (then
+        (call $throw_type_error
+          (call $new_static_string
+            (i32.const {{ data(str="Object prototype may only be an Object or null") }})
+            (i32.const {{ data_length() }})))
+      )
+    )
+  )
+
+  (func $Object-getPrototypeOf
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+
+    (local $prototype anyref)
+    (local.set $prototype (call $get_prototype (call $object_argument (local.get $arguments))))
+
+    (if (ref.is_null (local.get $prototype))
+      (then
+        (return (global.get $null))
+      )
+    )
+
+    (local.get $prototype)
+  )
+
+  (func $Object-setPrototypeOf
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+
+    (local $target anyref)
+    (local $prototype anyref)
+
+    (local.set $target (call $object_argument (local.get $arguments)))
+    (local.set $prototype (call $get_argument (local.get $arguments) (i32.const 1)))
+    (call $check_prototype_argument (local.get $prototype))
+
+    (if (i32.eqz (call $set_prototype (local.get $target) (local.get $prototype)))
+      (then
+        (call $throw_type_error
+          (call $new_static_string
+            (i32.const {{ data(str="'setPrototypeOf' on proxy: trap returned falsish for property 'undefined'") }})
+            (i32.const {{ data_length() }})))
+      )
+    )
+
+    (local.get $target)
+  )
+
+  (func $Object-hasOwn
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+
+    (call $new_boolean
+      (call $has_own_property
+        (call $object_argument (local.get $arguments))
+        (call $to_property_key (call $get_argument (local.get $arguments) (i32.const 1)))))
+  )
+
+  (func $Object-is
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+
+    (call $new_boolean
+      (call $same_value
+        (call $get_argument (local.get $arguments) (i32.const 0))
+        (call $get_argument (local.get $arguments) (i32.const 1))))
+  )
+
+  (func $Object-prototype-hasOwnProperty
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+
+    (call $new_boolean
+      (call $has_own_property
+        (local.get $this)
+        (call $to_property_key (call $get_argument (local.get $arguments) (i32.const 0)))))
+  )
+
+  (func $Object-prototype-toString
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (local $tag anyref)
+
+    (if (ref.test nullref (local.get $this))
+      (then
+        (return (call $new_static_string (i32.const {{ data(str="[object Undefined]") }}) (i32.const {{ data_length() }})))
+      )
+    )
+
+    (if (ref.test (ref $Null) (local.get $this))
+      (then
+        (return (call $new_static_string (i32.const {{ data(str="[object Null]") }}) (i32.const {{ data_length() }})))
+      )
+    )
+
+    (if (ref.test (ref $Boolean) (local.get $this))
+      (then
+        (return (call $new_static_string (i32.const {{ data(str="[object Boolean]") }}) (i32.const {{ data_length() }})))
+      )
+    )
+
+    ;; Symbol.toStringTag overrides the tag of other values
+    (local.set $tag
+      (call $get_property
+        (local.get $this)
+        (call $well_known_symbol_key (global.get $symbol-to-string-tag))))
+    (if (call $is_string (local.get $tag))
+      (then
+        (return
+          (call $concat_strings
+            (call $concat_strings
+              (call $new_static_string (i32.const {{ data(str="[object ") }}) (i32.const {{ data_length() }}))
+              (local.get $tag))
+            (call $new_static_string (i32.const {{ data(str="]") }}) (i32.const {{ data_length() }}))))
+      )
+    )
+
+    (if (ref.test (ref $Array) (local.get $this))
+      (then
+        (return (call $new_static_string (i32.const {{ data(str="[object Array]") }}) (i32.const {{ data_length() }})))
+      )
+    )
+
+    (if (ref.test (ref $Function) (local.get $this))
+      (then
+        (return (call $new_static_string (i32.const {{ data(str="[object Function]") }}) (i32.const {{ data_length() }})))
+      )
+    )
+
+    (if (call $is_number (local.get $this))
+      (then
+        (return (call $new_static_string (i32.const {{ data(str="[object Number]") }}) (i32.const {{ data_length() }})))
+      )
+    )
+
+    (if (call $is_string (local.get $this))
+      (then
+        (return (call $new_static_string (i32.const {{ data(str="[object String]") }}) (i32.const {{ data_length() }})))
+      )
+    )
+
+    (call $new_static_string (i32.const {{ data(str="[object Object]") }}) (i32.const {{ data_length() }}))
+  )
+
+  (elem declare func $Object-constructor)
+  (elem declare func $Object-keys)
+  (elem declare func $Object-values)
+  (elem declare func $Object-entries)
+  (elem declare func $Object-fromEntries)
+  (elem declare func $Object-assign)
+  (elem declare func $Object-create)
+  (elem declare func $Object-getPrototypeOf)
+  (elem declare func $Object-setPrototypeOf)
+  (elem declare func $Object-hasOwn)
+  (elem declare func $Object-is)
+  (elem declare func $Object-prototype-hasOwnProperty)
+  (elem declare func $Object-prototype-toString)
+
+  (func $add_method (param $target anyref) (param $name i32) (param $func (ref $JSFunc))
+    (call $set_property
+      (local.get $target)
+      (local.get $name)
+      (call $new_function (ref.cast (ref $Scope) (global.get $scope)) (local.get $func) (ref.null any)))
+  )
+
+  ;; has to be called before any other object is created, as objects use it
+  ;; as their prototype
+  (func $create-object-prototype (result (ref $Object))
+    (local $object (ref $Object))
+    (local.set $object (call $new_object))
+
+    (call $add_method (local.get $object) (i32.const {{ data(str="hasOwnProperty") }}) (ref.func $Object-prototype-hasOwnProperty))
+    (call $add_method (local.get $object) (i32.const {{ data(str="toString") }}) (ref.func $Object-prototype-toString))
+
+    (local.get $object)
+  )
+
+  (func $create-object-constructor (result (ref $Function))
+    (local $object (ref $Function))
+    (local.set $object
+      (call $new_function (ref.cast (ref $Scope) (global.get $scope)) (ref.func $Object-constructor) (ref.null any)))
+
+    (call $set_property (local.get $object) (i32.const {{ data(str="prototype") }}) (global.get $object-prototype))
+    (call $set_property (global.get $object-prototype) (i32.const {{ data(str="constructor") }}) (local.get $object))
+
+    (call $add_method (local.get $object) (i32.const {{ data(str="keys") }}) (ref.func $Object-keys))
+    (call $add_method (local.get $object) (i32.const {{ data(str="values") }}) (ref.func $Object-values))
+    (call $add_method (local.get $object) (i32.const {{ data(str="entries") }}) (ref.func $Object-entries))
+    (call $add_method (local.get $object) (i32.const {{ data(str="fromEntries") }}) (ref.func $Object-fromEntries))
+    (call $add_method (local.get $object) (i32.const {{ data(str="assign") }}) (ref.func $Object-assign))
+    (call $add_method (local.get $object) (i32.const {{ data(str="create") }}) (ref.func $Object-create))
+    (call $add_method (local.get $object) (i32.const {{ data(str="getPrototypeOf") }}) (ref.func $Object-getPrototypeOf))
+    (call $add_method (local.get $object) (i32.const {{ data(str="setPrototypeOf") }}) (ref.func $Object-setPrototypeOf))
+    (call $add_method (local.get $object) (i32.const {{ data(str="hasOwn") }}) (ref.func $Object-hasOwn))
+    (call $add_method (local.get $object) (i32.const {{ data(str="is") }}) (ref.func $Object-is))
+
+    (local.get $object)
+  )
@@ -0,0 +1,251 @@
+  ;; The pollable registry. Every pollable handle the runtime owns (a
+  ;; `wasi:io/poll` `pollable` resource) is kept in a slot together with the
+  ;; function to call once it's ready. Slots are reused through a free list, so
+  ;; adding and removing a pollable doesn't depend on how many there are, and
+  ;; the slot index is the key the rest of the runtime uses to refer to it.
+  ;; Removing a pollable drops the resource, which the host can then reuse.
+  ;;
+  ;; Waiting is done by writing the handles to a dedicated scratch area and
+  ;; passing it to `poll`, which returns the indexes of the ready ones in that
+  ;; list.
+
+  (type $Pollable (struct
+    (field $handle i32)
+    (field $func anyref)
+    (field $key i32)
+  ))
+
+  (type $PollablesArray (array (mut (ref null $Pollable))))
+
+  (global $pollables (mut (ref null $PollablesArray)) (ref.null $PollablesArray))
+  ;; for every free slot the index of the next free one, -1 ends the list
+  (global $pollable-next-free (mut (ref null $I32Array)) (ref.null $I32Array))
+  (global $pollable-free-head (mut i32) (i32.const -1))
+  ;; slots past this one were never used, so they aren't on the free list
+  (global $pollable-slots-used (mut i32) (i32.const 0))
+  (global $pollable-count (mut i32) (i32.const 0))
+
+  ;; the pollables passed to the last `poll`, by their index in the list
+  (global $polled (mut (ref null $PollablesArray)) (ref.null $PollablesArray))
+  (global $polled-count (mut i32) (i32.const 0))
+  ;; the list of handles, followed by the pointer and length of the result
+  (global $poll-scratch (mut i32) (i32.const 0))
+  (global $poll-scratch-size (mut i32) (i32.const 0))
+
+  ;; registers a pollable handle, `func` is called without arguments once it's
+  ;; ready and the pollable is removed right before that
+  (func $add-pollable (param $handle i32) (param $func anyref) (result i32)
+    (local $key i32)
+    (local $size i32)
+    (local $slots (ref $PollablesArray))
+    (local $next-free (ref $I32Array))
+
+    (if (ref.is_null (global.get $pollables))
+      (then
+        (global.set $pollables (array.new $PollablesArray (ref.null $Pollable) (i32.const 8)))
+        (global.set $pollable-next-free (array.new $I32Array (i32.const -1) (i32.const 8)))
+      )
+    )
+
+    (if (i32.ne (global.get $pollable-free-head) (i32.const -1))
+      (then
+        (local.set $key (global.get $pollable-free-head))
+        (global.set $pollable-free-head
+          (array.get $I32Array (ref.as_non_null (global.get $pollable-next-free)) (local.get $key)))
+      )
+      (else
+        (local.set $key (global.get $pollable-slots-used))
+        (local.set $size (array.len (ref.as_non_null (global.get $pollables))))
+        (if (i32.eq (local.get $key) (local.get $size))
+          (then
+            (local.set $slots
+              (array.new $PollablesArray (ref.null $Pollable) (i32.shl (local.get $size) (i32.const 1))))
+            (array.copy $PollablesArray $PollablesArray
+              (local.get $slots)
+              (i32.const 0)
+              (ref.as_non_null (global.get $pollables))
+              (i32.const 0)
+              (local.get $size))
+            (global.set $pollables (local.get $slots))
+            (local.set $next-free
+              (array.new $I32Array (i32.const -1) (i32.shl (local.get $size) (i32.const 1))))
+            (array.copy $I32Array $I32Array
+              (local.get $next-free)
+              (i32.const 0)
+              (ref.as_non_null (global.get $pollable-next-free))
+              (i32.const 0)
+              (local.get $size))
+            (global.set $pollable-next-free (local.get $next-free))
+          )
+        )
+        (global.set $pollable-slots-used (i32.add (local.get $key) (i32.const 1)))
+      )
+    )
+
+    (array.set $PollablesArray
+      (ref.as_non_null (global.get $pollables))
+      (local.get $key)
+      (struct.new $Pollable (local.get $handle) (local.get $func) (local.get $key)))
+    (global.set $pollable-count (i32.add (global.get $pollable-count) (i32.const 1)))
+    (local.get $key)
+  )
+
+  ;; removes a pollable and drops its handle, removing a key that's not in use
+  ;; does nothing
+  (func $remove-pollable (param $key i32)
+    (local $pollable (ref null $Pollable))
+
+    (if (i32.or
+          (ref.is_null (global.get $pollables))
+          (i32.ge_u (local.get $key) (global.get $pollable-slots-used)))
+      (then (return))
+    )
+    (local.set $pollable (array.get $PollablesArray (ref.as_non_null (global.get $pollables)) (local.get $key)))
+    (if (ref.is_null (local.get $pollable))
+      (then (return))
+    )
+
+    (array.set $PollablesArray (ref.as_non_null (global.get $pollables)) (local.get $key) (ref.null $Pollable))
+    (array.set $I32Array
+      (ref.as_non_null (global.get $pollable-next-free))
+      (local.get $key)
+      (global.get $pollable-free-head))
+    (global.set $pollable-free-head (local.get $key))
+    (global.set $pollable-count (i32.sub (global.get $pollable-count) (i32.const 1)))
+    (call $drop-pollable (struct.get $Pollable $handle (local.get $pollable)))
+  )
+
+  ;; makes sure the scratch area fits the list of handles and the result
+  (func $reserve-poll-scratch (param $count i32)
+    (local $size i32)
+
+    (local.set $size (i32.add (i32.shl (local.get $count) (i32.const 2)) (i32.const 8)))
+    (if (i32.le_u (local.get $size) (global.get $poll-scratch-size))
+      (then (return))
+    )
+    (local.set $size (i32.shl (local.get $size) (i32.const 1)))
+    ;; the old contents don't matter, the list is written from scratch
+    (call $free (global.get $poll-scratch))
+    (global.set $poll-scratch (call $malloc (local.get $size)))
+    (global.set $poll-scratch-size (local.get $size))
+  )
+
+  ;; calls `poll` with all the registered pollables, returns 0 without calling
+  ;; it if there aren't any
+  (func $poll-pollables (result i32)
+    (local $slots (ref $PollablesArray))
+    (local $polled (ref $PollablesArray))
+    (local $pollable (ref null $Pollable))
+    (local $count i32)
+    (local $i i32)
+
+    (if (i32.eqz (global.get $pollable-count))
+      (then (return (i32.const 0)))
+    )
+    (local.set $slots (ref.as_non_null (global.get $pollables)))
+
+    (call $reserve-poll-scratch (global.get $pollable-count))
+    (if (i32.lt_u
+          (if (result i32) (ref.is_null (global.get $polled))
+            (then (i32.const 0))
+            (else (array.len (ref.as_non_null (global.get $polled)))))
+          (global.get $pollable-count))
+      (then
+        (global.set $polled (array.new $PollablesArray (ref.null $Pollable) (array.len (local.get $slots))))
+      )
+    )
+    (local.set $polled (ref.as_non_null (global.get $polled)))
+
+    (local.set $count (i32.const 0))
+    (local.set $i (i32.const 0))
+    (block $break (loop $store
+      (br_if $break (i32.ge_u (local.get $i) (global.get $pollable-slots-used)))
+      (local.set $pollable (array.get $PollablesArray (local.get $slots) (local.get $i)))
+      (if (i32.eqz (ref.is_null (local.get $pollable)))
+        (then
+          (i32.store
+            (i32.add (global.get $poll-scratch) (i32.shl (local.get $count) (i32.const 2)))
+            (struct.get $Pollable $handle (local.get $pollable)))
+          (array.set $PollablesArray (local.get $polled) (local.get $count) (local.get $pollable))
+          (local.set $count (i32.add (local.get $count) (i32.const 1)))
+        )
+      )
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $store)
+    ))
+    (global.set $polled-count (local.get $count))
+
+    (call $poll-many
+      (global.get $poll-scratch)
+      (local.get $count)
+      (i32.add (global.get $poll-scratch) (i32.shl (local.get $count) (i32.const 2))))
+    (i32.const 1)
+  )
+
+  ;; the pollables `poll` reported as ready, taken out of the result list before
+  ;; any callback gets a chance to overwrite it
+  (func $ready-pollables (result (ref $PollablesArray))
+    (local $result i32)
+    (local $ready i32)
+    (local $count i32)
+    (local $pollables (ref $PollablesArray))
+    (local $i i32)
+
+    (local.set $result
+      (i32.add (global.get $poll-scratch) (i32.shl (global.get $polled-count) (i32.const 2))))
+    (local.set $ready (i32.load (local.get $result)))
+    (local.set $count (i32.load offset=4 (local.get $result)))
+
+    (local.set $pollables (array.new $PollablesArray (ref.null $Pollable) (local.get $count)))
+    (local.set $i (i32.const 0))
+    (block $break (loop $copy
+      (br_if $break (i32.ge_u (local.get $i) (local.get $count)))
+      (array.set $PollablesArray (local.get $pollables) (local.get $i)
+        (array.get $PollablesArray
+          (ref.as_non_null (global.get $polled))
+          (i32.load (i32.add (local.get $ready) (i32.shl (local.get $i) (i32.const 2))))))
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $copy)
+    ))
+
+    ;; the host allocated the result list with cabi_realloc
+    (call $free (local.get $ready))
+    (local.get $pollables)
+  )
+
+  ;; runs the callback of a ready pollable, followed by the microtasks. It's
+  ;; skipped if an earlier callback already removed the pollable, even if its
+  ;; slot was taken by a new one since
+  (func $run-pollable (param $pollable (ref $Pollable))
+    (local $key i32)
+
+    (local.set $key (struct.get $Pollable $key (local.get $pollable)))
+    (if (i32.eqz
+          (ref.eq
+            (array.get $PollablesArray (ref.as_non_null (global.get $pollables)) (local.get $key))
+            (local.get $pollable)))
+      (then (return))
+    )
+    (call $remove-pollable (local.get $key))
+
+    (drop
+      (call $call_function
+        (struct.get $Pollable $func (local.get $pollable))
+        (ref.null any)
+        (array.new $JSArgs (ref.null any) (i32.const 0))))
+    (call $run-microtasks)
+  )
+
+  (func $run-ready-pollables
+    (local $ready (ref $PollablesArray))
+    (local $i i32)
+
+    (local.set $ready (call $ready-pollables))
+    (local.set $i (i32.const 0))
+    (block $break (loop $run
+      (br_if $break (i32.ge_u (local.get $i) (array.len (local.get $ready))))
+      (call $run-pollable (ref.as_non_null (array.get $PollablesArray (local.get $ready) (local.get $i))))
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $run)
+    ))
+  )
@@ -0,0 +1,106 @@
+  ;; The `process` global, with the parts of Node's API scripts need to find
+  ;; out how they were started: `argv`, `env` and `exit`. The arguments and
+  ;; the environment come from wasi:cli/environment, so `argv` has whatever the
+  ;; host passes, usually starting with the name of the program. It's only
+  ;; included when the script mentions `process`; `process.stdin` comes from
+  ;; stdin.wat.
+
+  ;; list<string>, 8 bytes for every string
+  (func $load_arguments (result (ref $Array))
+    (local $result i32)
+    (local $list i32)
+    (local $count i32)
+    (local $entry i32)
+    (local $arguments (ref $ValueList))
+    (local $i i32)
+
+    (local.set $result (call $malloc (i32.const 8)))
+    (call $get-arguments (local.get $result))
+    (local.set $list (i32.load (local.get $result)))
+    (local.set $count (i32.load offset=4 (local.get $result)))
+    (call $free (local.get $result))
+
+    (local.set $arguments (call $new_value_list))
+    (block $break (loop $arguments
+      (br_if $break (i32.ge_u (local.get $i) (local.get $count)))
+      (local.set $entry (i32.add (local.get $list) (i32.shl (local.get $i) (i32.const 3))))
+      (call $value_list_push
+        (local.get $arguments)
+        (call $take_host_string (i32.load (local.get $entry)) (i32.load offset=4 (local.get $entry))))
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $arguments)
+    ))
+    (call $free (local.get $list))
+    (call $value_list_to_array (local.get $arguments))
+  )
+
+  ;; list<tuple<string, string>>, 16 bytes for every variable
+  (func $load_environment (result (ref $Object))
+    (local $result i32)
+    (local $list i32)
+    (local $count i32)
+    (local $entry i32)
+    (local $environment (ref $Object))
+    (local $i i32)
+
+    (local.set $result (call $malloc (i32.const 8)))
+    (call $get-environment (local.get $result))
+    (local.set $list (i32.load (local.get $result)))
+    (local.set $count (i32.load offset=4 (local.get $result)))
+    (call $free (local.get $result))
+
+    (local.set $environment (call $new_object))
+    (block $break (loop $variables
+      (br_if $break (i32.ge_u (local.get $i) (local.get $count)))
+      (local.set $entry (i32.add (local.get $list) (i32.shl (local.get $i) (i32.const 4))))
+      (call $set_property
+        (local.get $environment)
+        (call $to_property_key
+          (call $take_host_string (i32.load (local.get $entry)) (i32.load offset=4 (local.get $entry))))
+        (call $take_host_string (i32.load offset=8 (local.get $entry)) (i32.load offset=12 (local.get $entry))))
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $variables)
+    ))
+    (call $free (local.get $list))
+    (local.get $environment)
+  )
+
+  ;; exit(code) ends the program right away, without running pending timers
+  ;; or callbacks. Without a code it uses `process.exitCode`, like Node
+  (func $process-exit
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (local $code anyref)
+
+    (local.set $code (call $get_argument (local.get $arguments) (i32.const 0)))
+    (if (i32.and (ref.is_null (local.get $code)) (call $is_object (local.get $this)))
+      (then (local.set $code (call $get_property (local.get $this) (i32.const {{ data(str="exitCode") }}))))
+    )
+    (call $proc_exit
+      (if (result i32) (ref.is_null (local.get $code))
+        (then (i32.const 0))
+        (else (call $to_int32 (local.get $code)))))
+    (unreachable)
+  )
+
+  (elem declare func $process-exit)
+
+  (func $install-process-globals (param $scope (ref $Scope))
+    (local $process (ref $Object))
+
+    (local.set $process (call $new_object))
+    (call $set_property (local.get $process) (i32.const {{ data(str="argv") }}) (call $load_arguments))
+    (call $set_property (local.get $process) (i32.const {{ data(str="env") }}) (call $load_environment))
+    (call $set_property (local.get $process) (i32.const {{ data(str="platform") }})
+      (call $new_static_string (i32.const {{ data(str="wasi") }}) (i32.const {{ data_length() }})))
+    (call $add_method (local.get $process) (i32.const {{ data(str="exit") }}) (ref.func $process-exit))
+{% if stdin %}
+    (call $set_property (local.get $process) (i32.const {{ data(str="stdin") }}) (call $create-stdin-object))
+{% endif %}
+    (call $set_to_string_tag (local.get $process)
+      (call $new_static_string (i32.const {{ data(str="process") }}) (i32.const {{ data_length() }})))
+    (call $set_variable (local.get $scope) (i32.const {{ data(str="process") }}) (local.get $process))
+  )
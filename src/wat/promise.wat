@@ -0,0 +1,415 @@
+  ;; Promise static methods: resolve, reject, withResolvers and the combinators
+  ;; all, allSettled, race and any. The promises always use the intrinsic
+  ;; Promise, subclassing isn't supported.
+
+  (global $combinator-all i32 (i32.const 0))
+  (global $combinator-all-settled i32 (i32.const 1))
+  (global $combinator-race i32 (i32.const 2))
+  (global $combinator-any i32 (i32.const 3))
+
+  (global $aggregate-error-prototype (mut (ref null $Object)) (ref.null $Object))
+
+  ;; what the element functions of a combinator share
+  (type $PromiseCombinator (struct
+    ;; results (or errors for `any`) in the order of the iterable
+    (field $values (ref $Array))
+    ;; elements still waiting, plus one until all of them were subscribed to
+    (field $remaining (mut i32))
+    ;; resolve and reject of the returned promise
+    (field $resolvers (ref $JSArgs))
+  ))
+
+  ;; a single element, its functions only count the first time either of them
+  ;; is called
+  (type $PromiseElement (struct
+    (field $combinator (ref $PromiseCombinator))
+    (field $index i32)
+    (field $already-called (mut i32))
+  ))
+
+  ;; PromiseResolve(%Promise%, value), promises are returned as they are
+  (func $promise_resolve_value (param $value anyref) (result (ref $Promise))
+    (local $promise (ref $Promise))
+
+    (if (ref.test (ref $Promise) (local.get $value))
+      (then (return (ref.cast (ref $Promise) (local.get $value))))
+    )
+    (local.set $promise (call $new-promise))
+    (call $resolve-promise (local.get $promise) (local.get $value))
+    (local.get $promise)
+  )
+
+  (func $Promise-resolve
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (call $promise_resolve_value (call $get_argument (local.get $arguments) (i32.const 0)))
+  )
+
+  (func $Promise-reject
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (local $promise (ref $Promise))
+
+    (local.set $promise (call $new-promise))
+    (call $reject-promise (local.get $promise) (call $get_argument (local.get $arguments) (i32.const 0)))
+    (local.get $promise)
+  )
+
+  ;; { promise, resolve, reject }
+  (func $Promise-withResolvers
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (local $promise (ref $Promise))
+    (local $resolvers (ref $JSArgs))
+    (local $result (ref $Object))
+
+    (local.set $promise (call $new-promise))
+    (local.set $resolvers (call $create-resolving-functions (local.get $promise)))
+    (local.set $result (call $new_object))
+    (call $set_property (local.get $result) (i32.const {{ data(str="promise") }}) (local.get $promise))
+    (call $set_property (local.get $result) (i32.const {{ data(str="resolve") }})
+      (array.get $JSArgs (local.get $resolvers) (i32.const 0)))
+    (call $set_property (local.get $result) (i32.const {{ data(str="reject") }})
+      (array.get $JSArgs (local.get $resolvers) (i32.const 1)))
+    (local.get $result)
+  )
+
+  ;; an element function gets its $PromiseElement from its scope, returns null
+  ;; if the element was already settled
+  (func $take-promise-element (param $scope (ref $Scope)) (result (ref null $PromiseElement))
+    (local $element (ref $PromiseElement))
+
+    (local.set $element
+      (ref.cast (ref $PromiseElement)
+        (array.get $AnyrefArray (struct.get $Scope $slots (local.get $scope)) (i32.const 0))))
+    (if (struct.get $PromiseElement $already-called (local.get $element))
+      (then (return (ref.null $PromiseElement)))
+    )
+    (struct.set $PromiseElement $already-called (local.get $element) (i32.const 1))
+    (local.get $element)
+  )
+
+  ;; stores the value of an element and, once it's the last one, settles the
+  ;; returned promise with all of the values
+  (func $settle-promise-element (param $element (ref $PromiseElement)) (param $value anyref) (param $reject i32)
+    (local $combinator (ref $PromiseCombinator))
+
+    (local.set $combinator (struct.get $PromiseElement $combinator (local.get $element)))
+    (call $array_set
+      (struct.get $PromiseCombinator $values (local.get $combinator))
+      (struct.get $PromiseElement $index (local.get $element))
+      (local.get $value))
+    (call $finish-promise-element (local.get $combinator) (local.get $reject))
+  )
+
+  (func $finish-promise-element (param $combinator (ref $PromiseCombinator)) (param $reject i32)
+    (local $values (ref $Array))
+
+    (struct.set $PromiseCombinator $remaining (local.get $combinator)
+      (i32.sub (struct.get $PromiseCombinator $remaining (local.get $combinator)) (i32.const 1)))
+    (if (struct.get $PromiseCombinator $remaining (local.get $combinator))
+      (then (return))
+    )
+
+    (local.set $values (struct.get $PromiseCombinator $values (local.get $combinator)))
+    (drop
+      (call $call_function
+        (array.get $JSArgs
+          (struct.get $PromiseCombinator $resolvers (local.get $combinator))
+          (local.get $reject))
+        (ref.null any)
+        (call $create-arguments-1
+          (if (result anyref) (local.get $reject)
+            (then
+              (call $new_aggregate_error
+                (local.get $values)
+                (call $new_static_string (i32.const {{ data(str="All promises were rejected") }}) (i32.const {{ data_length() }}))))
+            (else (local.get $values))))))
+  )
+
+  (func $Promise-all-resolve-element
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (local $element (ref null $PromiseElement))
+
+    (local.set $element (call $take-promise-element (local.get $scope)))
+    (if (i32.eqz (ref.is_null (local.get $element)))
+      (then
+        (call $settle-promise-element
+          (ref.as_non_null (local.get $element))
+          (call $get_argument (local.get $arguments) (i32.const 0))
+          (i32.const 0))
+      )
+    )
+    (ref.null any)
+  )
+
+  (func $Promise-any-reject-element
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (local $element (ref null $PromiseElement))
+
+    (local.set $element (call $take-promise-element (local.get $scope)))
+    (if (i32.eqz (ref.is_null (local.get $element)))
+      (then
+        (call $settle-promise-element
+          (ref.as_non_null (local.get $element))
+          (call $get_argument (local.get $arguments) (i32.const 0))
+          (i32.const 1))
+      )
+    )
+    (ref.null any)
+  )
+
+  ;; { status: "fulfilled", value } or { status: "rejected", reason }
+  (func $settled-result (param $fulfilled i32) (param $value anyref) (result (ref $Object))
+    (local $result (ref $Object))
+
+    (local.set $result (call $new_object))
+    (if (local.get $fulfilled)
+      (then
+        (call $set_property (local.get $result) (i32.const {{ data(str="status") }})
+          (call $new_static_string (i32.const {{ data(str="fulfilled") }}) (i32.const {{ data_length() }})))
+        (call $set_property (local.get $result) (i32.const {{ data(str="value") }}) (local.get $value))
+      )
+      (else
+        (call $set_property (local.get $result) (i32.const {{ data(str="status") }})
+          (call $new_static_string (i32.const {{ data(str="rejected") }}) (i32.const {{ data_length() }})))
+        (call $set_property (local.get $result) (i32.const {{ data(str="reason") }}) (local.get $value))
+      )
+    )
+    (local.get $result)
+  )
+
+  (func $Promise-allSettled-resolve-element
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (local $element (ref null $PromiseElement))
+
+    (local.set $element (call $take-promise-element (local.get $scope)))
+    (if (i32.eqz (ref.is_null (local.get $element)))
+      (then
+        (call $settle-promise-element
+          (ref.as_non_null (local.get $element))
+          (call $settled-result (i32.const 1) (call $get_argument (local.get $arguments) (i32.const 0)))
+          (i32.const 0))
+      )
+    )
+    (ref.null any)
+  )
+
+  (func $Promise-allSettled-reject-element
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (local $element (ref null $PromiseElement))
+
+    (local.set $element (call $take-promise-element (local.get $scope)))
+    (if (i32.eqz (ref.is_null (local.get $element)))
+      (then
+        (call $settle-promise-element
+          (ref.as_non_null (local.get $element))
+          (call $settled-result (i32.const 0) (call $get_argument (local.get $arguments) (i32.const 0)))
+          (i32.const 0))
+      )
+    )
+    (ref.null any)
+  )
+
+  ;; the shared part of all, allSettled, race and any: subscribes to each of the
+  ;; values of the iterable with callbacks depending on the kind
+  (func $promise_combinator (param $iterable anyref) (param $kind i32) (result (ref $Promise))
+    (local $promise (ref $Promise))
+    (local $resolvers (ref $JSArgs))
+    (local $items (ref null $AnyrefArray))
+    (local $combinator (ref $PromiseCombinator))
+    (local $environment (ref $Scope))
+    (local $on-fulfilled anyref)
+    (local $on-rejected anyref)
+    (local $error anyref)
+    (local $i i32)
+
+    (local.set $promise (call $new-promise))
+    (local.set $resolvers (call $create-resolving-functions (local.get $promise)))
+
+    ;; errors of the iteration reject the returned promise instead of being
+    ;; thrown
+    try
+      (local.set $items (struct.get $Array $array (call $iterable_to_array (local.get $iterable))))
+    catch $JSException
+      (local.set $error)
+      (call $reject-promise (local.get $promise) (local.get $error))
+      (return (local.get $promise))
+    end
+
+    (local.set $combinator
+      (struct.new $PromiseCombinator
+        (call $new_array (array.len (local.get $items)))
+        (i32.const 1)
+        (local.get $resolvers)))
+    (local.set $on-fulfilled (array.get $JSArgs (local.get $resolvers) (i32.const 0)))
+    (local.set $on-rejected (array.get $JSArgs (local.get $resolvers) (i32.const 1)))
+
+    (block $break (loop $subscribe
+      (br_if $break (i32.ge_u (local.get $i) (array.len (local.get $items))))
+
+      (local.set $environment (call $new_environment (ref.cast (ref $Scope) (global.get $scope)) (i32.const 1)))
+      (array.set $AnyrefArray (struct.get $Scope $slots (local.get $environment)) (i32.const 0)
+        (struct.new $PromiseElement (local.get $combinator) (local.get $i) (i32.const 0)))
+      (if (i32.ne (local.get $kind) (global.get $combinator-race))
+        (then
+          (struct.set $PromiseCombinator $remaining (local.get $combinator)
+            (i32.add (struct.get $PromiseCombinator $remaining (local.get $combinator)) (i32.const 1)))
+        )
+      )
+      (if (i32.eq (local.get $kind) (global.get $combinator-all))
+        (then
+          (local.set $on-fulfilled
+            (call $new_function (local.get $environment) (ref.func $Promise-all-resolve-element) (ref.null any)))
+        )
+      )
+      (if (i32.eq (local.get $kind) (global.get $combinator-all-settled))
+        (then
+          (local.set $on-fulfilled
+            (call $new_function (local.get $environment) (ref.func $Promise-allSettled-resolve-element) (ref.null any)))
+          (local.set $on-rejected
+            (call $new_function (local.get $environment) (ref.func $Promise-allSettled-reject-element) (ref.null any)))
+        )
+      )
+      (if (i32.eq (local.get $kind) (global.get $combinator-any))
+        (then
+          (local.set $on-rejected
+            (call $new_function (local.get $environment) (ref.func $Promise-any-reject-element) (ref.null any)))
+        )
+      )
+
+      (drop
+        (call $Promise-then
+          (ref.cast (ref $Scope) (global.get $scope))
+          (call $promise_resolve_value (array.get $AnyrefArray (local.get $items) (local.get $i)))
+          (call $create-arguments-2 (local.get $on-fulfilled) (local.get $on-rejected))))
+
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $subscribe)
+    ))
+
+    ;; race never settles for an empty iterable, the others settle once every
+    ;; element did, right away if there are none
+    (if (i32.ne (local.get $kind) (global.get $combinator-race))
+      (then
+        (call $finish-promise-element
+          (local.get $combinator)
+          (i32.eq (local.get $kind) (global.get $combinator-any)))
+      )
+    )
+    (local.get $promise)
+  )
+
+  (func $Promise-all
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (call $promise_combinator (call $get_argument (local.get $arguments) (i32.const 0)) (global.get $combinator-all))
+  )
+
+  (func $Promise-allSettled
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (call $promise_combinator (call $get_argument (local.get $arguments) (i32.const 0)) (global.get $combinator-all-settled))
+  )
+
+  (func $Promise-race
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (call $promise_combinator (call $get_argument (local.get $arguments) (i32.const 0)) (global.get $combinator-race))
+  )
+
+  (func $Promise-any
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (call $promise_combinator (call $get_argument (local.get $arguments) (i32.const 0)) (global.get $combinator-any))
+  )
+
+  ;; -- AggregateError --
+  ;; an Error with the list of `errors` that rejected Promise.any
+
+  (func $new_aggregate_error (param $errors anyref) (param $message anyref) (result (ref $Object))
+    (local $error (ref $Object))
+
+    (local.set $error (call $new_error (global.get $aggregate-error-prototype) (local.get $message)))
+    (call $set_property (local.get $error) (i32.const {{ data(str="errors") }}) (call $iterable_to_array (local.get $errors)))
+    (local.get $error)
+  )
+
+  ;; new AggregateError(errors, message)
+  (func $AggregateError-constructor
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (call $new_aggregate_error
+      (call $get_argument (local.get $arguments) (i32.const 0))
+      (call $get_argument (local.get $arguments) (i32.const 1)))
+  )
+
+  (elem declare func $Promise-resolve)
+  (elem declare func $Promise-reject)
+  (elem declare func $Promise-withResolvers)
+  (elem declare func $Promise-all)
+  (elem declare func $Promise-allSettled)
+  (elem declare func $Promise-race)
+  (elem declare func $Promise-any)
+  (elem declare func $Promise-all-resolve-element)
+  (elem declare func $Promise-allSettled-resolve-element)
+  (elem declare func $Promise-allSettled-reject-element)
+  (elem declare func $Promise-any-reject-element)
+  (elem declare func $AggregateError-constructor)
+
+  (func $install-promise-globals (param $scope (ref $Scope)) (param $promise (ref $Function))
+    (call $set_property (local.get $promise) (i32.const {{ data(str="prototype") }}) (global.get $promise-prototype))
+    (call $set_property (global.get $promise-prototype) (i32.const {{ data(str="constructor") }}) (local.get $promise))
+
+    (call $add_method (local.get $promise) (i32.const {{ data(str="resolve") }}) (ref.func $Promise-resolve))
+    (call $add_method (local.get $promise) (i32.const {{ data(str="reject") }}) (ref.func $Promise-reject))
+    (call $add_method (local.get $promise) (i32.const {{ data(str="withResolvers") }}) (ref.func $Promise-withResolvers))
+    (call $add_method (local.get $promise) (i32.const {{ data(str="all") }}) (ref.func $Promise-all))
+    (call $add_method (local.get $promise) (i32.const {{ data(str="allSettled") }}) (ref.func $Promise-allSettled))
+    (call $add_method (local.get $promise) (i32.const {{ data(str="race") }}) (ref.func $Promise-race))
+    (call $add_method (local.get $promise) (i32.const {{ data(str="any") }}) (ref.func $Promise-any))
+
+    (global.set $aggregate-error-prototype
+      (call $install_error_type (local.get $scope) (i32.const {{ data(str="AggregateError") }})
+        (global.get $error-prototype) (ref.func $AggregateError-constructor)))
+  )
@@ -0,0 +1,869 @@
+  ;; `Proxy` and `Reflect`
+  ;;
+  ;; A proxy is an $Object subtype holding a target and a handler. The object
+  ;; model operations ($get_property, $set_property, $has_property,
+  ;; $delete_property, $own_property_keys, $get_prototype, $call_function,
+  ;; $construct, ...) check for proxies before anything else and hand them to
+  ;; the $proxy_* functions below. Each of them calls the handler's trap if
+  ;; there is one, or forwards the operation to the target. Reflect exposes the
+  ;; same operations as plain functions.
+  ;;
+  ;; Properties don't have attributes yet, so the invariants the spec checks
+  ;; on trap results (like reporting a non-configurable property as missing)
+  ;; are not enforced.
+
+  (type $Proxy (sub final $Object (struct
+    (field $properties (mut (ref $HashMap)))
+    (field $prototype (mut anyref))
+    (field $hash (mut i32))
+    (field $target anyref)
+    ;; null once the proxy is revoked
+    (field $handler (mut anyref))
+    ;; proxies of functions can be called and constructed
+    (field $callable i32)
+    ;; like in $Function, set when the proxy is read as a method
+    (field $this (mut anyref))
+  )))
+
+  (func $is_callable (param $value anyref) (result i32)
+    (if (ref.test (ref $Proxy) (local.get $value))
+      (then (return (struct.get $Proxy $callable (ref.cast (ref $Proxy) (local.get $value)))))
+    )
+    (ref.test (ref $Function) (local.get $value))
+  )
+
+  (func $new_proxy (param $target anyref) (param $handler anyref) (result (ref $Proxy))
+    (if (i32.eqz
+          (i32.and
+            (call $is_object (local.get $target))
+            (call $is_object (local.get $handler))))
+      (then
+        (call $throw_type_error
+          (call $new_static_string (i32.const {{ data(str="Cannot create proxy with a non-object as target or handler") }}) (i32.const {{ data_length() }})))
+      )
+    )
+
+    (struct.new $Proxy
+      (call $new_hashmap)
+      (ref.null any)
+      (i32.const 0)
+      (local.get $target)
+      (local.get $handler)
+      (call $is_callable (local.get $target))
+      (ref.null any))
+  )
+
+  ;; the arguments of a call as a JS array, which is how traps get them
+  (func $arguments_to_array (param $arguments (ref $JSArgs)) (result (ref $Array))
+    (local $result (ref $Array))
+    (local.set $result (call $new_array (array.len (local.get $arguments))))
+    (array.copy $AnyrefArray $JSArgs
+      (struct.get $Array $array (local.get $result))
+      (i32.const 0)
+      (local.get $arguments)
+      (i32.const 0)
+      (array.len (local.get $arguments)))
+    (local.get $result)
+  )
+
+  ;; returns the trap called $name or undefined if the handler doesn't have
+  ;; one, throws if the proxy has been revoked
+  (func $proxy_trap (param $proxy (ref $Proxy)) (param $name i32) (result anyref)
+    (local $handler anyref)
+    (local $trap anyref)
+
+    (local.set $handler (struct.get $Proxy $handler (local.get $proxy)))
+    (if (ref.is_null (local.get $handler))
+      (then
+        (call $throw_type_error
+          (call $concat_strings
+            (call $concat_strings
+              (call $new_static_string (i32.const {{ data(str="Cannot perform '") }}) (i32.const {{ data_length() }}))
+              (call $key_to_string (local.get $name)))
+            (call $new_static_string (i32.const {{ data(str="' on a proxy that has been revoked") }}) (i32.const {{ data_length() }}))))
+      )
+    )
+
+    (local.set $trap (call $get_property (local.get $handler) (local.get $name)))
+    (if (i32.or (ref.is_null (local.get $trap)) (call $is_null (local.get $trap)))
+      (then (return (ref.null any)))
+    )
+    (if (i32.eqz (call $is_callable (local.get $trap)))
+      (then
+        (call $throw_type_error
+          (call $concat_strings
+            (call $concat_strings
+              (call $concat_strings
+                (call $concat_strings
+                  (call $new_static_string (i32.const {{ data(str="'") }}) (i32.const {{ data_length() }}))
+                  (call $to_string (local.get $trap)))
+                (call $new_static_string (i32.const {{ data(str="' returned for property '") }}) (i32.const {{ data_length() }})))
+              (call $key_to_string (local.get $name)))
+            (call $new_static_string (i32.const {{ data(str="' of object '#<Object>' is not a function") }}) (i32.const {{ data_length() }}))))
+      )
+    )
+
+    (local.get $trap)
+  )
+
+  (func $call_trap (param $proxy (ref $Proxy)) (param $trap anyref) (param $arguments (ref $JSArgs)) (result anyref)
+    (call $call_function
+      (local.get $trap)
+      (struct.get $Proxy $handler (local.get $proxy))
+      (local.get $arguments))
+  )
+
+  (func $throw_proxy_trap_error (param $name i32) (param $message anyref)
+    (call $throw_type_error
+      (call $concat_strings
+        (call $concat_strings
+          (call $concat_strings
+            (call $new_static_string (i32.const {{ data(str="'") }}) (i32.const {{ data_length() }}))
+            (call $key_to_string (local.get $name)))
+          (call $new_static_string (i32.const {{ data(str="' on proxy: ") }}) (i32.const {{ data_length() }})))
+        (local.get $message)))
+  )
+
+  (func $proxy_get (param $proxy (ref $Proxy)) (param $name i32) (param $receiver anyref) (result anyref)
+    (local $trap anyref)
+    (local.set $trap (call $proxy_trap (local.get $proxy) (i32.const {{ data(str="get") }})))
+
+    (if (ref.is_null (local.get $trap))
+      (then
+        (return
+          (call $get_computed_property
+            (struct.get $Proxy $target (local.get $proxy))
+            (call $key_to_string (local.get $name))))
+      )
+    )
+
+    (call $call_trap (local.get $proxy) (local.get $trap)
+      (array.new_fixed $JSArgs 3
+        (struct.get $Proxy $target (local.get $proxy))
+        (call $key_to_string (local.get $name))
+        (local.get $receiver)))
+  )
+
+  ;; a falsy result of the trap is ignored, like assignments in sloppy mode
+  (func $proxy_set (param $proxy (ref $Proxy)) (param $name i32) (param $value anyref) (param $receiver anyref) (result i32)
+    (local $trap anyref)
+    (local.set $trap (call $proxy_trap (local.get $proxy) (i32.const {{ data(str="set") }})))
+
+    (if (ref.is_null (local.get $trap))
+      (then
+        (call $set_computed_property
+          (struct.get $Proxy $target (local.get $proxy))
+          (call $key_to_string (local.get $name))
+          (local.get $value))
+        (return (i32.const 1))
+      )
+    )
+
+    (call $cast_ref_to_i32_bool
+      (call $call_trap (local.get $proxy) (local.get $trap)
+        (array.new_fixed $JSArgs 4
+          (struct.get $Proxy $target (local.get $proxy))
+          (call $key_to_string (local.get $name))
+          (local.get $value)
+          (local.get $receiver))))
+  )
+
+  (func $proxy_has (param $proxy (ref $Proxy)) (param $name i32) (result i32)
+    (local $trap anyref)
+    (local.set $trap (call $proxy_trap (local.get $proxy) (i32.const {{ data(str="has") }})))
+
+    (if (ref.is_null (local.get $trap))
+      (then
+        (return (call $has_property (struct.get $Proxy $target (local.get $proxy)) (local.get $name)))
+      )
+    )
+
+    (call $cast_ref_to_i32_bool
+      (call $call_trap (local.get $proxy) (local.get $trap)
+        (array.new_fixed $JSArgs 2
+          (struct.get $Proxy $target (local.get $proxy))
+          (call $key_to_string (local.get $name)))))
+  )
+
+  (func $proxy_delete (param $proxy (ref $Proxy)) (param $name i32) (result i32)
+    (local $trap anyref)
+    (local.set $trap (call $proxy_trap (local.get $proxy) (i32.const {{ data(str="deleteProperty") }})))
+
+    (if (ref.is_null (local.get $trap))
+      (then
+        (return (call $delete_property (struct.get $Proxy $target (local.get $proxy)) (local.get $name)))
+      )
+    )
+
+    (call $cast_ref_to_i32_bool
+      (call $call_trap (local.get $proxy) (local.get $trap)
+        (array.new_fixed $JSArgs 2
+          (struct.get $Proxy $target (local.get $proxy))
+          (call $key_to_string (local.get $name)))))
+  )
+
+  (func $proxy_own_keys (param $proxy (ref $Proxy)) (result (ref $Array))
+    (local $trap anyref)
+    (local $keys anyref)
+    (local.set $trap (call $proxy_trap (local.get $proxy) (i32.const {{ data(str="ownKeys") }})))
+
+    (if (ref.is_null (local.get $trap))
+      (then
+        (return (call $own_property_keys (struct.get $Proxy $target (local.get $proxy))))
+      )
+    )
+
+    (local.set $keys
+      (call $call_trap (local.get $proxy) (local.get $trap)
+        (array.new_fixed $JSArgs 1 (struct.get $Proxy $target (local.get $proxy)))))
+    (if (i32.eqz (call $is_object (local.get $keys)))
+      (then
+        (call $throw_type_error
+          (call $new_static_string (i32.const {{ data(str="CreateListFromArrayLike called on non-object") }}) (i32.const {{ data_length() }})))
+      )
+    )
+    (call $object_to_values (local.get $keys))
+  )
+
+  ;; $own_property_entries for proxies, which goes through the ownKeys,
+  ;; getOwnPropertyDescriptor and get traps
+  (func $proxy_property_entries (param $proxy (ref $Proxy)) (param $kind i32) (result (ref $Array))
+    (local $keys (ref $AnyrefArray))
+    (local $entries (ref $ValueList))
+    (local $key anyref)
+    (local $name i32)
+    (local $descriptor anyref)
+    (local $i i32)
+
+    (local.set $keys (struct.get $Array $array (call $proxy_own_keys (local.get $proxy))))
+    (local.set $entries (call $new_value_list))
+
+    (block $break (loop $keys_loop
+      (br_if $break (i32.ge_u (local.get $i) (array.len (local.get $keys))))
+
+      (local.set $key (array.get $AnyrefArray (local.get $keys) (local.get $i)))
+      (if (call $is_string (local.get $key))
+        (then
+          (local.set $name (call $to_property_key (local.get $key)))
+          (local.set $descriptor (call $proxy_get_own_property (local.get $proxy) (local.get $name)))
+          (if (i32.eqz (ref.is_null (local.get $descriptor)))
+            (then
+              (if (call $cast_ref_to_i32_bool
+                    (call $get_property (local.get $descriptor) (i32.const {{ data(str="enumerable") }})))
+                (then
+                  ;; listing only the keys doesn't read the values
+                  (call $value_list_push (local.get $entries)
+                    (if (result anyref) (local.get $kind)
+                      (then
+                        (call $property_entry
+                          (local.get $kind)
+                          (local.get $key)
+                          (call $proxy_get (local.get $proxy) (local.get $name) (local.get $proxy))))
+                      (else (local.get $key))))
+                )
+              )
+            )
+          )
+        )
+      )
+
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $keys_loop)
+    ))
+
+    (call $value_list_to_array (local.get $entries))
+  )
+
+  ;; returns a descriptor object or undefined
+  (func $proxy_get_own_property (param $proxy (ref $Proxy)) (param $name i32) (result anyref)
+    (local $trap anyref)
+    (local $descriptor anyref)
+    (local.set $trap (call $proxy_trap (local.get $proxy) (i32.const {{ data(str="getOwnPropertyDescriptor") }})))
+
+    (if (ref.is_null (local.get $trap))
+      (then
+        (return (call $own_property_descriptor (struct.get $Proxy $target (local.get $proxy)) (local.get $name)))
+      )
+    )
+
+    (local.set $descriptor
+      (call $call_trap (local.get $proxy) (local.get $trap)
+        (array.new_fixed $JSArgs 2
+          (struct.get $Proxy $target (local.get $proxy))
+          (call $key_to_string (local.get $name)))))
+    (if (i32.eqz
+          (i32.or
+            (ref.is_null (local.get $descriptor))
+            (call $is_object (local.get $descriptor))))
+      (then
+        (call $throw_proxy_trap_error
+          (i32.const {{ data(str="getOwnPropertyDescriptor") }})
+          (call $concat_strings
+            (call $concat_strings
+              (call $new_static_string (i32.const {{ data(str="trap returned neither object nor undefined for property '") }}) (i32.const {{ data_length() }}))
+              (call $key_to_string (local.get $name)))
+            (call $new_static_string (i32.const {{ data(str="'") }}) (i32.const {{ data_length() }}))))
+      )
+    )
+    (local.get $descriptor)
+  )
+
+  (func $proxy_define_property (param $proxy (ref $Proxy)) (param $name i32) (param $descriptor anyref) (result i32)
+    (local $trap anyref)
+    (local.set $trap (call $proxy_trap (local.get $proxy) (i32.const {{ data(str="defineProperty") }})))
+
+    (if (ref.is_null (local.get $trap))
+      (then
+        (return
+          (call $define_property
+            (struct.get $Proxy $target (local.get $proxy))
+            (local.get $name)
+            (local.get $descriptor)))
+      )
+    )
+
+    (call $cast_ref_to_i32_bool
+      (call $call_trap (local.get $proxy) (local.get $trap)
+        (array.new_fixed $JSArgs 3
+          (struct.get $Proxy $target (local.get $proxy))
+          (call $key_to_string (local.get $name))
+          (local.get $descriptor))))
+  )
+
+  ;; like $get_prototype, returns a null reference if there is no prototype
+  (func $proxy_get_prototype (param $proxy (ref $Proxy)) (result anyref)
+    (local $trap anyref)
+    (local $prototype anyref)
+    (local.set $trap (call $proxy_trap (local.get $proxy) (i32.const {{ data(str="getPrototypeOf") }})))
+
+    (if (ref.is_null (local.get $trap))
+      (then
+        (return (call $get_prototype (struct.get $Proxy $target (local.get $proxy))))
+      )
+    )
+
+    (local.set $prototype
+      (call $call_trap (local.get $proxy) (local.get $trap)
+        (array.new_fixed $JSArgs 1 (struct.get $Proxy $target (local.get $proxy)))))
+    (if (call $is_null (local.get $prototype))
+      (then (return (ref.null any)))
+    )
+    (if (i32.eqz (call $is_object (local.get $prototype)))
+      (then
+        (call $throw_proxy_trap_error
+          (i32.const {{ data(str="getPrototypeOf") }})
+          (call $new_static_string (i32.const {{ data(str="trap returned neither object nor null") }}) (i32.const {{ data_length() }})))
+      )
+    )
+    (local.get $prototype)
+  )
+
+  (func $proxy_set_prototype (param $proxy (ref $Proxy)) (param $prototype anyref) (result i32)
+    (local $trap anyref)
+    (local.set $trap (call $proxy_trap (local.get $proxy) (i32.const {{ data(str="setPrototypeOf") }})))
+
+    (if (ref.is_null (local.get $trap))
+      (then
+        (return (call $set_prototype (struct.get $Proxy $target (local.get $proxy)) (local.get $prototype)))
+      )
+    )
+
+    (call $cast_ref_to_i32_bool
+      (call $call_trap (local.get $proxy) (local.get $trap)
+        (array.new_fixed $JSArgs 2
+          (struct.get $Proxy $target (local.get $proxy))
+          (local.get $prototype))))
+  )
+
+  (func $proxy_is_extensible (param $proxy (ref $Proxy)) (result i32)
+    (local $trap anyref)
+    (local.set $trap (call $proxy_trap (local.get $proxy) (i32.const {{ data(str="isExtensible") }})))
+
+    (if (ref.is_null (local.get $trap))
+      (then
+        (return (call $is_extensible (struct.get $Proxy $target (local.get $proxy))))
+      )
+    )
+
+    (call $cast_ref_to_i32_bool
+      (call $call_trap (local.get $proxy) (local.get $trap)
+        (array.new_fixed $JSArgs 1 (struct.get $Proxy $target (local.get $proxy)))))
+  )
+
+  (func $proxy_prevent_extensions (param $proxy (ref $Proxy)) (result i32)
+    (local $trap anyref)
+    (local.set $trap (call $proxy_trap (local.get $proxy) (i32.const {{ data(str="preventExtensions") }})))
+
+    (if (ref.is_null (local.get $trap))
+      (then
+        (return (call $prevent_extensions (struct.get $Proxy $target (local.get $proxy))))
+      )
+    )
+
+    (call $cast_ref_to_i32_bool
+      (call $call_trap (local.get $proxy) (local.get $trap)
+        (array.new_fixed $JSArgs 1 (struct.get $Proxy $target (local.get $proxy)))))
+  )
+
+  (func $proxy_call (param $proxy (ref $Proxy)) (param $this anyref) (param $arguments (ref $JSArgs)) (result anyref)
+    (local $trap anyref)
+
+    (if (i32.eqz (struct.get $Proxy $callable (local.get $proxy)))
+      (then
+        (call $throw_type_error
+          (call $new_static_string (i32.const {{ data(str="(intermediate value) is not a function") }}) (i32.const {{ data_length() }})))
+      )
+    )
+    ;; the same as with functions, a proxy read as a method is called with
+    ;; the object it was read from
+    (if (ref.is_null (local.get $this))
+      (then (local.set $this (struct.get $Proxy $this (local.get $proxy))))
+    )
+
+    (local.set $trap (call $proxy_trap (local.get $proxy) (i32.const {{ data(str="apply") }})))
+    (if (ref.is_null (local.get $trap))
+      (then
+        (return
+          (call $call_function
+            (struct.get $Proxy $target (local.get $proxy))
+            (local.get $this)
+            (local.get $arguments)))
+      )
+    )
+
+    (call $call_trap (local.get $proxy) (local.get $trap)
+      (array.new_fixed $JSArgs 3
+        (struct.get $Proxy $target (local.get $proxy))
+        (local.get $this)
+        (call $arguments_to_array (local.get $arguments))))
+  )
+
+  (func $proxy_construct (param $proxy (ref $Proxy)) (param $arguments (ref $JSArgs)) (result anyref)
+    (local $trap anyref)
+    (local $result anyref)
+
+    (if (i32.eqz (struct.get $Proxy $callable (local.get $proxy)))
+      (then
+        (call $throw_type_error
+          (call $new_static_string (i32.const {{ data(str="(intermediate value) is not a constructor") }}) (i32.const {{ data_length() }})))
+      )
+    )
+
+    (local.set $trap (call $proxy_trap (local.get $proxy) (i32.const {{ data(str="construct") }})))
+    (if (ref.is_null (local.get $trap))
+      (then
+        (return (call $construct (struct.get $Proxy $target (local.get $proxy)) (local.get $arguments)))
+      )
+    )
+
+    (local.set $result
+      (call $call_trap (local.get $proxy) (local.get $trap)
+        (array.new_fixed $JSArgs 3
+          (struct.get $Proxy $target (local.get $proxy))
+          (call $arguments_to_array (local.get $arguments))
+          (local.get $proxy))))
+    (if (i32.eqz (call $is_object (local.get $result)))
+      (then
+        (call $throw_proxy_trap_error
+          (i32.const {{ data(str="construct") }})
+          (call $concat_strings
+            (call $concat_strings
+              (call $new_static_string (i32.const {{ data(str="trap returned non-object ('") }}) (i32.const {{ data_length() }}))
+              (call $to_string (local.get $result)))
+            (call $new_static_string (i32.const {{ data(str="')") }}) (i32.const {{ data_length() }}))))
+      )
+    )
+    (local.get $result)
+  )
+
+  ;; [[IsExtensible]] and [[PreventExtensions]], ordinary objects can't be
+  ;; made non-extensible yet, so only proxies can say otherwise
+  (func $is_extensible (param $target anyref) (result i32)
+    (if (ref.test (ref $Proxy) (local.get $target))
+      (then (return (call $proxy_is_extensible (ref.cast (ref $Proxy) (local.get $target)))))
+    )
+    (i32.const 1)
+  )
+
+  (func $prevent_extensions (param $target anyref) (result i32)
+    (if (ref.test (ref $Proxy) (local.get $target))
+      (then (return (call $proxy_prevent_extensions (ref.cast (ref $Proxy) (local.get $target)))))
+    )
+    (i32.const 0)
+  )
+
+  (func $Proxy-constructor
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+
+    (if (i32.eqz (ref.test (ref $Object) (local.get $this)))
+      (then
+        (call $throw_type_error
+          (call $new_static_string (i32.const {{ data(str="Constructor Proxy requires 'new'") }}) (i32.const {{ data_length() }})))
+      )
+    )
+
+    (call $new_proxy
+      (call $get_argument (local.get $arguments) (i32.const 0))
+      (call $get_argument (local.get $arguments) (i32.const 1)))
+  )
+
+  (func $Proxy-revocable
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+
+    (local $proxy (ref $Proxy))
+    (local $environment (ref $Scope))
+    (local $result (ref $Object))
+
+    (local.set $proxy
+      (call $new_proxy
+        (call $get_argument (local.get $arguments) (i32.const 0))
+        (call $get_argument (local.get $arguments) (i32.const 1))))
+
+    ;; the revoke function finds the proxy in its scope
+    (local.set $environment (call $new_environment (local.get $scope) (i32.const 1)))
+    (array.set $AnyrefArray (struct.get $Scope $slots (local.get $environment)) (i32.const 0) (local.get $proxy))
+
+    (local.set $result (call $new_object))
+    (call $set_property (local.get $result) (i32.const {{ data(str="proxy") }}) (local.get $proxy))
+    (call $set_property (local.get $result) (i32.const {{ data(str="revoke") }})
+      (call $new_function (local.get $environment) (ref.func $Proxy-revoke) (ref.null any)))
+    (local.get $result)
+  )
+
+  (func $Proxy-revoke
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+
+    (local $slots (ref $AnyrefArray))
+    (local $proxy anyref)
+
+    (local.set $slots (struct.get $Scope $slots (local.get $scope)))
+    (local.set $proxy (array.get $AnyrefArray (local.get $slots) (i32.const 0)))
+    (if (i32.eqz (ref.is_null (local.get $proxy)))
+      (then
+        (struct.set $Proxy $handler (ref.cast (ref $Proxy) (local.get $proxy)) (ref.null any))
+        (array.set $AnyrefArray (local.get $slots) (i32.const 0) (ref.null any))
+      )
+    )
+
+    (ref.null any)
+  )
+
+  ;; the first argument of most Reflect functions has to be an object
+  (func $reflect_target (param $arguments (ref $JSArgs)) (param $name i32) (result anyref)
+    (local $target anyref)
+    (local.set $target (call $get_argument (local.get $arguments) (i32.const 0)))
+
+    (if (i32.eqz (call $is_object (local.get $target)))
+      (then
+        (call $throw_type_error
+          (call $concat_strings
+            (call $concat_strings
+              (call $new_static_string (i32.const {{ data(str="Reflect.") }}) (i32.const {{ data_length() }}))
+              (call $key_to_string (local.get $name)))
+            (call $new_static_string (i32.const {{ data(str=" called on non-object") }}) (i32.const {{ data_length() }}))))
+      )
+    )
+
+    (local.get $target)
+  )
+
+  ;; the list of arguments for Reflect.apply and Reflect.construct
+  (func $reflect_arguments (param $values anyref) (result (ref $JSArgs))
+    (if (i32.eqz (call $is_object (local.get $values)))
+      (then
+        (call $throw_type_error
+          (call $new_static_string (i32.const {{ data(str="CreateListFromArrayLike called on non-object") }}) (i32.const {{ data_length() }})))
+      )
+    )
+
+    (ref.cast (ref $JSArgs) (struct.get $Array $array (call $object_to_values (local.get $values))))
+  )
+
+  (func $Reflect-apply
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+
+    (local $target anyref)
+    (local.set $target (call $get_argument (local.get $arguments) (i32.const 0)))
+
+    (if (i32.eqz (call $is_callable (local.get $target)))
+      (then
+        (call $throw_type_error
+          (call $concat_strings
+            (call $concat_strings
+              (call $concat_strings
+                (call $concat_strings
+                  (call $new_static_string (i32.const {{ data(str="Function.prototype.apply was called on ") }}) (i32.const {{ data_length() }}))
+                  (call $to_string (local.get $target)))
+                (call $new_static_string (i32.const {{ data(str=", which is a ") }}) (i32.const {{ data_length() }})))
+              (call $type_of (local.get $target)))
+            (call $new_static_string (i32.const {{ data(str=" and not a function") }}) (i32.const {{ data_length() }}))))
+      )
+    )
+
+    (call $call_function
+      (local.get $target)
+      (call $get_argument (local.get $arguments) (i32.const 1))
+      (call $reflect_arguments (call $get_argument (local.get $arguments) (i32.const 2))))
+  )
+
+  ;; TODO: newTarget is ignored, instances always inherit from the target
+  (func $Reflect-construct
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+
+    (local $target anyref)
+    (local.set $target (call $get_argument (local.get $arguments) (i32.const 0)))
+
+    (if (i32.eqz (call $is_callable (local.get $target)))
+      (then
+        (call $throw_type_error
+          (call $concat_strings
+            (call $to_string (local.get $target))
+            (call $new_static_string (i32.const {{ data(str=" is not a constructor") }}) (i32.const {{ data_length() }}))))
+      )
+    )
+
+    (call $construct
+      (local.get $target)
+      (call $reflect_arguments (call $get_argument (local.get $arguments) (i32.const 1))))
+  )
+
+  ;; receivers only make a difference for accessors, which don't exist yet,
+  ;; so they are not passed along
+  (func $Reflect-get
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+
+    (call $get_computed_property
+      (call $reflect_target (local.get $arguments) (i32.const {{ data(str="get") }}))
+      (call $get_argument (local.get $arguments) (i32.const 1)))
+  )
+
+  (func $Reflect-set
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+
+    (local $target anyref)
+    (local.set $target (call $reflect_target (local.get $arguments) (i32.const {{ data(str="set") }})))
+
+    (if (ref.test (ref $Proxy) (local.get $target))
+      (then
+        (return
+          (call $new_boolean
+            (call $proxy_set
+              (ref.cast (ref $Proxy) (local.get $target))
+              (call $to_property_key (call $get_argument (local.get $arguments) (i32.const 1)))
+              (call $get_argument (local.get $arguments) (i32.const 2))
+              (local.get $target))))
+      )
+    )
+
+    (call $set_computed_property
+      (local.get $target)
+      (call $get_argument (local.get $arguments) (i32.const 1))
+      (call $get_argument (local.get $arguments) (i32.const 2)))
+    (global.get $true)
+  )
+
+  (func $Reflect-has
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+
+    (call $new_boolean
+      (call $has_property
+        (call $reflect_target (local.get $arguments) (i32.const {{ data(str="has") }}))
+        (call $to_property_key (call $get_argument (local.get $arguments) (i32.const 1)))))
+  )
+
+  (func $Reflect-deleteProperty
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+
+    (call $new_boolean
+      (call $delete_property
+        (call $reflect_target (local.get $arguments) (i32.const {{ data(str="deleteProperty") }}))
+        (call $to_property_key (call $get_argument (local.get $arguments) (i32.const 1)))))
+  )
+
+  (func $Reflect-ownKeys
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+
+    (call $own_property_keys
+      (call $reflect_target (local.get $arguments) (i32.const {{ data(str="ownKeys") }})))
+  )
+
+  (func $Reflect-getOwnPropertyDescriptor
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+
+    (call $own_property_descriptor
+      (call $reflect_target (local.get $arguments) (i32.const {{ data(str="getOwnPropertyDescriptor") }}))
+      (call $to_property_key (call $get_argument (local.get $arguments) (i32.const 1))))
+  )
+
+  (func $Reflect-defineProperty
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+
+    (local $descriptor anyref)
+    (local.set $descriptor (call $get_argument (local.get $arguments) (i32.const 2)))
+
+    (if (i32.eqz (call $is_object (local.get $descriptor)))
+      (then
+        (call $throw_type_error
+          (call $concat_strings
+            (call $new_static_string (i32.const {{ data(str="Property description must be an object: ") }}) (i32.const {{ data_length() }}))
+            (call $to_string (local.get $descriptor))))
+      )
+    )
+
+    (call $new_boolean
+      (call $define_property
+        (call $reflect_target (local.get $arguments) (i32.const {{ data(str="defineProperty") }}))
+        (call $to_property_key (call $get_argument (local.get $arguments) (i32.const 1)))
+        (local.get $descriptor)))
+  )
+
+  (func $Reflect-getPrototypeOf
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+
+    (local $prototype anyref)
+    (local.set $prototype
+      (call $get_prototype
+        (call $reflect_target (local.get $arguments) (i32.const {{ data(str="getPrototypeOf") }}))))
+
+    (if (ref.is_null (local.get $prototype))
+      (then (return (global.get $null)))
+    )
+    (local.get $prototype)
+  )
+
+  (func $Reflect-setPrototypeOf
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+
+    (local $target anyref)
+    (local $prototype anyref)
+
+    (local.set $target (call $reflect_target (local.get $arguments) (i32.const {{ data(str="setPrototypeOf") }})))
+    (local.set $prototype (call $get_argument (local.get $arguments) (i32.const 1)))
+    (call $check_prototype_argument (local.get $prototype))
+
+    (call $new_boolean (call $set_prototype (local.get $target) (local.get $prototype)))
+  )
+
+  (func $Reflect-isExtensible
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+
+    (call $new_boolean
+      (call $is_extensible
+        (call $reflect_target (local.get $arguments) (i32.const {{ data(str="isExtensible") }}))))
+  )
+
+  (func $Reflect-preventExtensions
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+
+    (call $new_boolean
+      (call $prevent_extensions
+        (call $reflect_target (local.get $arguments) (i32.const {{ data(str="preventExtensions") }}))))
+  )
+
+  (elem declare func $Proxy-constructor)
+  (elem declare func $Proxy-revocable)
+  (elem declare func $Proxy-revoke)
+  (elem declare func $Reflect-apply)
+  (elem declare func $Reflect-construct)
+  (elem declare func $Reflect-get)
+  (elem declare func $Reflect-set)
+  (elem declare func $Reflect-has)
+  (elem declare func $Reflect-deleteProperty)
+  (elem declare func $Reflect-ownKeys)
+  (elem declare func $Reflect-getOwnPropertyDescriptor)
+  (elem declare func $Reflect-defineProperty)
+  (elem declare func $Reflect-getPrototypeOf)
+  (elem declare func $Reflect-setPrototypeOf)
+  (elem declare func $Reflect-isExtensible)
+  (elem declare func $Reflect-preventExtensions)
+
+  (func $install-proxy-globals (param $scope (ref $Scope))
+    (local $proxy (ref $Function))
+    (local $reflect (ref $Object))
+
+    (local.set $proxy (call $new_function (local.get $scope) (ref.func $Proxy-constructor) (ref.null any)))
+    (call $add_method (local.get $proxy) (i32.const {{ data(str="revocable") }}) (ref.func $Proxy-revocable))
+    (call $set_variable (local.get $scope) (i32.const {{ data(str="Proxy") }}) (local.get $proxy))
+
+    (local.set $reflect (call $new_object))
+    (call $add_method (local.get $reflect) (i32.const {{ data(str="apply") }}) (ref.func $Reflect-apply))
+    (call $add_method (local.get $reflect) (i32.const {{ data(str="construct") }}) (ref.func $Reflect-construct))
+    (call $add_method (local.get $reflect) (i32.const {{ data(str="get") }}) (ref.func $Reflect-get))
+    (call $add_method (local.get $reflect) (i32.const {{ data(str="set") }}) (ref.func $Reflect-set))
+    (call $add_method (local.get $reflect) (i32.const {{ data(str="has") }}) (ref.func $Reflect-has))
+    (call $add_method (local.get $reflect) (i32.const {{ data(str="deleteProperty") }}) (ref.func $Reflect-deleteProperty))
+    (call $add_method (local.get $reflect) (i32.const {{ data(str="ownKeys") }}) (ref.func $Reflect-ownKeys))
+    (call $add_method (local.get $reflect) (i32.const {{ data(str="getOwnPropertyDescriptor") }}) (ref.func $Reflect-getOwnPropertyDescriptor))
+    (call $add_method (local.get $reflect) (i32.const {{ data(str="defineProperty") }}) (ref.func $Reflect-defineProperty))
+    (call $add_method (local.get $reflect) (i32.const {{ data(str="getPrototypeOf") }}) (ref.func $Reflect-getPrototypeOf))
+    (call $add_method (local.get $reflect) (i32.const {{ data(str="setPrototypeOf") }}) (ref.func $Reflect-setPrototypeOf))
+    (call $add_method (local.get $reflect) (i32.const {{ data(str="isExtensible") }}) (ref.func $Reflect-isExtensible))
+    (call $add_method (local.get $reflect) (i32.const {{ data(str="preventExtensions") }}) (ref.func $Reflect-preventExtensions))
+    (call $set_to_string_tag (local.get $reflect)
+      (call $new_static_string (i32.const {{ data(str="Reflect") }}) (i32.const {{ data_length() }})))
+    (call $set_variable (local.get $scope) (i32.const {{ data(str="Reflect") }}) (local.get $reflect))
+  )
@@ -0,0 +1,3620 @@
+  ;; The `RegExp` builtin and the regex methods of `String.prototype`
+  ;;
+  ;; Patterns are compiled into a small bytecode, described in src/regexp.rs,
+  ;; and run by a backtracking matcher. Literals are compiled by the Rust
+  ;; compiler ahead of time, patterns created with `new RegExp(...)` go through
+  ;; the port of the same compiler below, so both have to stay in sync.
+  ;;
+  ;; The matcher keeps an explicit stack instead of recursing, with two kinds
+  ;; of entries: choice points to resume from (0, pc, position) and register
+  ;; values to restore when backtracking past them (1, register, old value).
+  ;; Positions are byte offsets into the UTF-8 data, just like in the other
+  ;; string builtins.
+
+  {% set op_match = 0 %}
+  {% set op_char = 1 %}
+  {% set op_any = 2 %}
+  {% set op_any_all = 3 %}
+  {% set op_class = 4 %}
+  {% set op_split = 5 %}
+  {% set op_jmp = 6 %}
+  {% set op_save = 7 %}
+  {% set op_line_start = 8 %}
+  {% set op_line_start_multiline = 9 %}
+  {% set op_line_end = 10 %}
+  {% set op_line_end_multiline = 11 %}
+  {% set op_word_boundary = 12 %}
+  {% set op_not_word_boundary = 13 %}
+  {% set op_backref = 14 %}
+  {% set op_lookahead = 15 %}
+  {% set op_check_progress = 16 %}
+  {% set op_reset = 17 %}
+  {% set op_char_i = 18 %}
+  {% set op_class_i = 19 %}
+  {% set op_backref_i = 20 %}
+  {% set op_mark = 21 %}
+  {% set op_lookbehind = 22 %}
+
+  {% set flag_global = 1 %}
+  {% set flag_ignore_case = 2 %}
+  {% set flag_multiline = 4 %}
+  {% set flag_dot_all = 8 %}
+  {% set flag_unicode = 16 %}
+  {% set flag_sticky = 32 %}
+
+  ;; a growable list of i32s, used for the backtracking stack and by the
+  ;; pattern compiler
+  (type $I32Buffer (struct
+    (field $data (mut (ref $I32Array)))
+    (field $size (mut i32))
+  ))
+
+  ;; a growable list of values, turned into an $Array at the end
+  (type $ValueList (struct
+    (field $data (mut (ref $AnyrefArray)))
+    (field $size (mut i32))
+  ))
+
+  (type $RegExp (sub final $Object (struct
+    (field $properties (mut (ref $HashMap)))
+    (field $prototype (mut anyref))
+    (field $hash (mut i32))
+    ;; the pattern as returned by `source`
+    (field $source anyref)
+    (field $flags i32)
+    (field $program (ref $I32Array))
+    ;; the names of the capture groups or null if there are no named groups
+    (field $group_names (ref null $AnyrefArray))
+  )))
+
+  ;; the iterator returned by String.prototype.matchAll
+  (type $RegExpStringIterator (sub final $Object (struct
+    (field $properties (mut (ref $HashMap)))
+    (field $prototype (mut anyref))
+    (field $hash (mut i32))
+    (field $regexp (ref $RegExp))
+    (field $string anyref)
+    (field $done (mut i32))
+  )))
+
+  (global $regexp-prototype (mut (ref null $Object)) (ref.null $Object))
+  (global $regexp-string-iterator-prototype (mut (ref null $Object)) (ref.null $Object))
+  ;; strings don't have own properties, but they get their methods from here
+  (global $string-prototype (mut (ref null $Object)) (ref.null $Object))
+
+  (func $new_i32_buffer (result (ref $I32Buffer))
+    (struct.new $I32Buffer (array.new_default $I32Array (i32.const 16)) (i32.const 0))
+  )
+
+  (func $i32_buffer_push (param $buffer (ref $I32Buffer)) (param $value i32)
+    (local $data (ref $I32Array))
+    (local $new_data (ref $I32Array))
+    (local $size i32)
+
+    (local.set $data (struct.get $I32Buffer $data (local.get $buffer)))
+    (local.set $size (struct.get $I32Buffer $size (local.get $buffer)))
+    (if (i32.eq (local.get $size) (array.len (local.get $data)))
+      (then
+        (local.set $new_data
+          (array.new_default $I32Array (i32.shl (i32.add (local.get $size) (i32.const 1)) (i32.const 1))))
+        (array.copy $I32Array $I32Array
+          (local.get $new_data)
+          (i32.const 0)
+          (local.get $data)
+          (i32.const 0)
+          (local.get $size))
+        (struct.set $I32Buffer $data (local.get $buffer) (local.get $new_data))
+        (local.set $data (local.get $new_data))
+      )
+    )
+
+    (array.set $I32Array (local.get $data) (local.get $size) (local.get $value))
+    (struct.set $I32Buffer $size (local.get $buffer) (i32.add (local.get $size) (i32.const 1)))
+  )
+
+  (func $i32_buffer_push2 (param $buffer (ref $I32Buffer)) (param $first i32) (param $second i32)
+    (call $i32_buffer_push (local.get $buffer) (local.get $first))
+    (call $i32_buffer_push (local.get $buffer) (local.get $second))
+  )
+
+  (func $i32_buffer_push3 (param $buffer (ref $I32Buffer)) (param $first i32) (param $second i32) (param $third i32)
+    (call $i32_buffer_push (local.get $buffer) (local.get $first))
+    (call $i32_buffer_push (local.get $buffer) (local.get $second))
+    (call $i32_buffer_push (local.get $buffer) (local.get $third))
+  )
+
+  (func $i32_buffer_append (param $buffer (ref $I32Buffer)) (param $other (ref $I32Buffer))
+    (local $data (ref $I32Array))
+    (local $size i32)
+    (local $i i32)
+
+    ;; copied by value first, appending a buffer to itself has to work too
+    (local.set $data (struct.get $I32Buffer $data (local.get $other)))
+    (local.set $size (struct.get $I32Buffer $size (local.get $other)))
+    (block $break (loop $copy
+      (br_if $break (i32.ge_u (local.get $i) (local.get $size)))
+      (call $i32_buffer_push (local.get $buffer) (array.get $I32Array (local.get $data) (local.get $i)))
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $copy)
+    ))
+  )
+
+  (func $i32_buffer_get (param $buffer (ref $I32Buffer)) (param $index i32) (result i32)
+    (array.get $I32Array (struct.get $I32Buffer $data (local.get $buffer)) (local.get $index))
+  )
+
+  (func $i32_buffer_size (param $buffer (ref $I32Buffer)) (result i32)
+    (struct.get $I32Buffer $size (local.get $buffer))
+  )
+
+  (func $i32_buffer_to_array (param $buffer (ref $I32Buffer)) (result (ref $I32Array))
+    (local $result (ref $I32Array))
+    (local.set $result (array.new_default $I32Array (struct.get $I32Buffer $size (local.get $buffer))))
+    (array.copy $I32Array $I32Array
+      (local.get $result)
+      (i32.const 0)
+      (struct.get $I32Buffer $data (local.get $buffer))
+      (i32.const 0)
+      (struct.get $I32Buffer $size (local.get $buffer)))
+    (local.get $result)
+  )
+
+  (func $new_value_list (result (ref $ValueList))
+    (struct.new $ValueList (array.new $AnyrefArray (ref.null any) (i32.const 8)) (i32.const 0))
+  )
+
+  (func $value_list_push (param $list (ref $ValueList)) (param $value anyref)
+    (local $data (ref $AnyrefArray))
+    (local $new_data (ref $AnyrefArray))
+    (local $size i32)
+
+    (local.set $data (struct.get $ValueList $data (local.get $list)))
+    (local.set $size (struct.get $ValueList $size (local.get $list)))
+    (if (i32.eq (local.get $size) (array.len (local.get $data)))
+      (then
+        (local.set $new_data
+          (array.new $AnyrefArray (ref.null any) (i32.shl (i32.add (local.get $size) (i32.const 1)) (i32.const 1))))
+        (array.copy $AnyrefArray $AnyrefArray
+          (local.get $new_data)
+          (i32.const 0)
+          (local.get $data)
+          (i32.const 0)
+          (local.get $size))
+        (struct.set $ValueList $data (local.get $list) (local.get $new_data))
+        (local.set $data (local.get $new_data))
+      )
+    )
+
+    (array.set $AnyrefArray (local.get $data) (local.get $size) (local.get $value))
+    (struct.set $ValueList $size (local.get $list) (i32.add (local.get $size) (i32.const 1)))
+  )
+
+  (func $value_list_to_anyref_array (param $list (ref $ValueList)) (result (ref $AnyrefArray))
+    (local $result (ref $AnyrefArray))
+    (local.set $result
+      (array.new $AnyrefArray (ref.null any) (struct.get $ValueList $size (local.get $list))))
+    (array.copy $AnyrefArray $AnyrefArray
+      (local.get $result)
+      (i32.const 0)
+      (struct.get $ValueList $data (local.get $list))
+      (i32.const 0)
+      (struct.get $ValueList $size (local.get $list)))
+    (local.get $result)
+  )
+
+  (func $value_list_to_array (param $list (ref $ValueList)) (result (ref $Array))
+    (struct.new $Array
+      (call $value_list_to_anyref_array (local.get $list))
+      (ref.null $HashMap)
+      (i32.const 0))
+  )
+
+  ;; decodes the UTF-8 sequence starting at $position
+  (func $decode_code_point (param $data (ref $CharArray)) (param $position i32) (result i32)
+    (local $byte i32)
+    (local.set $byte (array.get_u $CharArray (local.get $data) (local.get $position)))
+
+    (if (i32.lt_u (local.get $byte) (i32.const 0x80))
+      (then (return (local.get $byte)))
+    )
+    (if (i32.lt_u (local.get $byte) (i32.const 0xe0))
+      (then
+        (return
+          (i32.or
+            (i32.shl (i32.and (local.get $byte) (i32.const 0x1f)) (i32.const 6))
+            (i32.and
+              (array.get_u $CharArray (local.get $data) (i32.add (local.get $position) (i32.const 1)))
+              (i32.const 0x3f))))
+      )
+    )
+    (if (i32.lt_u (local.get $byte) (i32.const 0xf0))
+      (then
+        (return
+          (i32.or
+            (i32.or
+              (i32.shl (i32.and (local.get $byte) (i32.const 0x0f)) (i32.const 12))
+              (i32.shl
+                (i32.and
+                  (array.get_u $CharArray (local.get $data) (i32.add (local.get $position) (i32.const 1)))
+                  (i32.const 0x3f))
+                (i32.const 6)))
+            (i32.and
+              (array.get_u $CharArray (local.get $data) (i32.add (local.get $position) (i32.const 2)))
+              (i32.const 0x3f))))
+      )
+    )
+    (i32.or
+      (i32.or
+        (i32.shl (i32.and (local.get $byte) (i32.const 0x07)) (i32.const 18))
+        (i32.shl
+          (i32.and
+            (array.get_u $CharArray (local.get $data) (i32.add (local.get $position) (i32.const 1)))
+            (i32.const 0x3f))
+          (i32.const 12)))
+      (i32.or
+        (i32.shl
+          (i32.and
+            (array.get_u $CharArray (local.get $data) (i32.add (local.get $position) (i32.const 2)))
+            (i32.const 0x3f))
+          (i32.const 6))
+        (i32.and
+          (array.get_u $CharArray (local.get $data) (i32.add (local.get $position) (i32.const 3)))
+          (i32.const 0x3f))))
+  )
+
+  (func $next_code_point_position (param $data (ref $CharArray)) (param $position i32) (result i32)
+    (i32.add
+      (local.get $position)
+      (call $utf8_sequence_length (array.get_u $CharArray (local.get $data) (local.get $position))))
+  )
+
+  (func $previous_code_point_position (param $data (ref $CharArray)) (param $position i32) (result i32)
+    (local.set $position (i32.sub (local.get $position) (i32.const 1)))
+    (block $done (loop $continuation
+      (br_if $done (i32.eqz (local.get $position)))
+      (br_if $done
+        (i32.ne
+          (i32.and (array.get_u $CharArray (local.get $data) (local.get $position)) (i32.const 0xc0))
+          (i32.const 0x80)))
+      (local.set $position (i32.sub (local.get $position) (i32.const 1)))
+      (br $continuation)
+    ))
+    (local.get $position)
+  )
+
+  ;; \n, \r, U+2028 and U+2029
+  (func $regexp_is_line_terminator (param $code_point i32) (result i32)
+    (i32.or
+      (i32.or
+        (i32.eq (local.get $code_point) (i32.const 0x0a))
+        (i32.eq (local.get $code_point) (i32.const 0x0d)))
+      (i32.or
+        (i32.eq (local.get $code_point) (i32.const 0x2028))
+        (i32.eq (local.get $code_point) (i32.const 0x2029))))
+  )
+
+  ;; whether the byte at $position is one of [0-9A-Za-z_], used by \b and \B
+  (func $regexp_is_word_at (param $input (ref $CharArray)) (param $length i32) (param $position i32) (result i32)
+    (local $byte i32)
+    (if (i32.or
+          (i32.lt_s (local.get $position) (i32.const 0))
+          (i32.ge_s (local.get $position) (local.get $length)))
+      (then (return (i32.const 0)))
+    )
+    (local.set $byte (array.get_u $CharArray (local.get $input) (local.get $position)))
+    (i32.or
+      (i32.or
+        (i32.lt_u (i32.sub (local.get $byte) (i32.const 0x30)) (i32.const 10))
+        (i32.eq (local.get $byte) (i32.const 0x5f)))
+      (i32.lt_u
+        (i32.sub (i32.or (local.get $byte) (i32.const 0x20)) (i32.const 0x61))
+        (i32.const 26)))
+  )
+
+  ;; the simple case folding used by the `i` flag, has to stay in sync with
+  ;; canonicalize in src/regexp.rs
+  (func $regexp_canonicalize (param $c i32) (result i32)
+    (if (i32.lt_u (i32.sub (local.get $c) (i32.const 0x61)) (i32.const 26))
+      (then (return (i32.sub (local.get $c) (i32.const 0x20))))
+    )
+    (if (i32.and
+          (i32.lt_u (i32.sub (local.get $c) (i32.const 0xe0)) (i32.const 31))
+          (i32.ne (local.get $c) (i32.const 0xf7)))
+      (then (return (i32.sub (local.get $c) (i32.const 0x20))))
+    )
+    (if (i32.eq (local.get $c) (i32.const 0xff))
+      (then (return (i32.const 0x178)))
+    )
+    (if (i32.and
+          (i32.lt_u (i32.sub (local.get $c) (i32.const 0x3b1)) (i32.const 25))
+          (i32.ne (local.get $c) (i32.const 0x3c2)))
+      (then (return (i32.sub (local.get $c) (i32.const 0x20))))
+    )
+    (if (i32.eq (local.get $c) (i32.const 0x3c2))
+      (then (return (i32.const 0x3a3)))
+    )
+    (if (i32.lt_u (i32.sub (local.get $c) (i32.const 0x430)) (i32.const 32))
+      (then (return (i32.sub (local.get $c) (i32.const 0x20))))
+    )
+    (if (i32.lt_u (i32.sub (local.get $c) (i32.const 0x450)) (i32.const 16))
+      (then (return (i32.sub (local.get $c) (i32.const 0x50))))
+    )
+    (local.get $c)
+  )
+
+  ;; the inverse of $regexp_canonicalize, so [a-z] can match "A" with the `i`
+  ;; flag
+  (func $regexp_lowercase (param $c i32) (result i32)
+    (if (i32.lt_u (i32.sub (local.get $c) (i32.const 0x41)) (i32.const 26))
+      (then (return (i32.add (local.get $c) (i32.const 0x20))))
+    )
+    (if (i32.and
+          (i32.lt_u (i32.sub (local.get $c) (i32.const 0xc0)) (i32.const 31))
+          (i32.ne (local.get $c) (i32.const 0xd7)))
+      (then (return (i32.add (local.get $c) (i32.const 0x20))))
+    )
+    (if (i32.eq (local.get $c) (i32.const 0x178))
+      (then (return (i32.const 0xff)))
+    )
+    (if (i32.and
+          (i32.lt_u (i32.sub (local.get $c) (i32.const 0x391)) (i32.const 25))
+          (i32.ne (local.get $c) (i32.const 0x3a2)))
+      (then (return (i32.add (local.get $c) (i32.const 0x20))))
+    )
+    (if (i32.lt_u (i32.sub (local.get $c) (i32.const 0x410)) (i32.const 32))
+      (then (return (i32.add (local.get $c) (i32.const 0x20))))
+    )
+    (if (i32.lt_u (i32.sub (local.get $c) (i32.const 0x400)) (i32.const 16))
+      (then (return (i32.add (local.get $c) (i32.const 0x50))))
+    )
+    (local.get $c)
+  )
+
+  ;; whether the sorted ranges of a CLASS instruction at $pc contain a code point
+  (func $regexp_class_contains (param $program (ref $I32Array)) (param $pc i32) (param $code_point i32) (result i32)
+    (local $i i32)
+    (local $end i32)
+
+    (local.set $i (i32.add (local.get $pc) (i32.const 3)))
+    (local.set $end
+      (i32.add
+        (local.get $i)
+        (i32.shl (array.get $I32Array (local.get $program) (i32.add (local.get $pc) (i32.const 2))) (i32.const 1))))
+    (block $done (loop $ranges
+      (br_if $done (i32.ge_u (local.get $i) (local.get $end)))
+      (br_if $done (i32.lt_s (local.get $code_point) (array.get $I32Array (local.get $program) (local.get $i))))
+      (if (i32.le_s
+            (local.get $code_point)
+            (array.get $I32Array (local.get $program) (i32.add (local.get $i) (i32.const 1))))
+        (then (return (i32.const 1)))
+      )
+      (local.set $i (i32.add (local.get $i) (i32.const 2)))
+      (br $ranges)
+    ))
+    (i32.const 0)
+  )
+
+  ;; matches the text of a capture group again, returns the position after it
+  ;; or -1. A group that didn't participate in the match matches the empty string
+  (func $regexp_backreference
+    (param $registers (ref $I32Array))
+    (param $group i32)
+    (param $input (ref $CharArray))
+    (param $length i32)
+    (param $position i32)
+    (param $ignore_case i32)
+    (result i32)
+    (local $start i32)
+    (local $end i32)
+
+    (local.set $start (array.get $I32Array (local.get $registers) (i32.shl (local.get $group) (i32.const 1))))
+    (local.set $end
+      (array.get $I32Array (local.get $registers) (i32.add (i32.shl (local.get $group) (i32.const 1)) (i32.const 1))))
+    (if (i32.or (i32.lt_s (local.get $start) (i32.const 0)) (i32.lt_s (local.get $end) (i32.const 0)))
+      (then (return (local.get $position)))
+    )
+
+    (block $done (loop $compare
+      (br_if $done (i32.ge_s (local.get $start) (local.get $end)))
+      (if (i32.ge_s (local.get $position) (local.get $length))
+        (then (return (i32.const -1)))
+      )
+      (if (local.get $ignore_case)
+        (then
+          (if (i32.ne
+                (call $regexp_canonicalize (call $decode_code_point (local.get $input) (local.get $start)))
+                (call $regexp_canonicalize (call $decode_code_point (local.get $input) (local.get $position))))
+            (then (return (i32.const -1)))
+          )
+          (local.set $start (call $next_code_point_position (local.get $input) (local.get $start)))
+          (local.set $position (call $next_code_point_position (local.get $input) (local.get $position)))
+        )
+        (else
+          (if (i32.ne
+                (array.get_u $CharArray (local.get $input) (local.get $start))
+                (array.get_u $CharArray (local.get $input) (local.get $position)))
+            (then (return (i32.const -1)))
+          )
+          (local.set $start (i32.add (local.get $start) (i32.const 1)))
+          (local.set $position (i32.add (local.get $position) (i32.const 1)))
+        )
+      )
+      (br $compare)
+    ))
+    (local.get $position)
+  )
+
+  (func $copy_i32_array (param $array (ref $I32Array)) (result (ref $I32Array))
+    (local $result (ref $I32Array))
+    (local.set $result (array.new_default $I32Array (array.len (local.get $array))))
+    (array.copy $I32Array $I32Array
+      (local.get $result)
+      (i32.const 0)
+      (local.get $array)
+      (i32.const 0)
+      (array.len (local.get $array)))
+    (local.get $result)
+  )
+
+  ;; Finishes a lookaround that was run on $registers, returns whether it
+  ;; succeeded. Lookarounds are atomic, so captures from a positive one are
+  ;; kept, but logged on the stack so backtracking past it restores them
+  (func $regexp_assertion
+    (param $stack (ref $I32Buffer))
+    (param $registers (ref $I32Array))
+    (param $snapshot (ref $I32Array))
+    (param $matched i32)
+    (param $negated i32)
+    (result i32)
+    (local $i i32)
+
+    (if (local.get $negated)
+      (then
+        (array.copy $I32Array $I32Array
+          (local.get $registers)
+          (i32.const 0)
+          (local.get $snapshot)
+          (i32.const 0)
+          (array.len (local.get $snapshot)))
+        (return (i32.eqz (local.get $matched)))
+      )
+    )
+    (if (i32.eqz (local.get $matched))
+      (then (return (i32.const 0)))
+    )
+
+    (block $done (loop $registers
+      (br_if $done (i32.ge_u (local.get $i) (array.len (local.get $snapshot))))
+      (if (i32.ne
+            (array.get $I32Array (local.get $registers) (local.get $i))
+            (array.get $I32Array (local.get $snapshot) (local.get $i)))
+        (then
+          (call $i32_buffer_push3
+            (local.get $stack)
+            (i32.const 1)
+            (local.get $i)
+            (array.get $I32Array (local.get $snapshot) (local.get $i)))
+        )
+      )
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $registers)
+    ))
+    (i32.const 1)
+  )
+
+  ;; Runs the program from $pc at $position. Returns the position where the
+  ;; match ended or -1, in which case all the registers are restored. With
+  ;; $end_at other than -1 the match has to end exactly there, which is how
+  ;; lookbehinds are matched
+  (func $regexp_run
+    (param $program (ref $I32Array))
+    (param $input (ref $CharArray))
+    (param $length i32)
+    (param $pc i32)
+    (param $position i32)
+    (param $registers (ref $I32Array))
+    (param $end_at i32)
+    (result i32)
+    (local $stack (ref $I32Buffer))
+    (local $data (ref $I32Array))
+    (local $marks i32)
+    (local $code_point i32)
+    (local $register i32)
+    (local $start i32)
+    (local $matched i32)
+    (local $snapshot (ref null $I32Array))
+    (local $i i32)
+
+    (local.set $stack (call $new_i32_buffer))
+    ;; loop registers come after the capture registers
+    (local.set $marks (i32.shl (array.get $I32Array (local.get $program) (i32.const 0)) (i32.const 1)))
+
+    (loop $step
+      (block $fail
+        (block $lookbehind
+        (block $mark
+        (block $backref_i
+        (block $class_i
+        (block $char_i
+        (block $reset
+        (block $check_progress
+        (block $lookahead
+        (block $backref
+        (block $not_word_boundary
+        (block $word_boundary
+        (block $line_end_multiline
+        (block $line_end
+        (block $line_start_multiline
+        (block $line_start
+        (block $save
+        (block $jmp
+        (block $split
+        (block $class
+        (block $any_all
+        (block $any
+        (block $char
+        (block $match
+          (br_table
+            $match $char $any $any_all $class $split $jmp $save
+            $line_start $line_start_multiline $line_end $line_end_multiline
+            $word_boundary $not_word_boundary $backref $lookahead $check_progress
+            $reset $char_i $class_i $backref_i $mark $lookbehind $fail
+            (array.get $I32Array (local.get $program) (local.get $pc)))
+        )
+        ;; MATCH
+        (if (i32.or
+              (i32.lt_s (local.get $end_at) (i32.const 0))
+              (i32.eq (local.get $position) (local.get $end_at)))
+          (then (return (local.get $position)))
+        )
+        (br $fail)
+        )
+        ;; CHAR code_point
+        (br_if $fail (i32.ge_s (local.get $position) (local.get $length)))
+        (br_if $fail
+          (i32.ne
+            (call $decode_code_point (local.get $input) (local.get $position))
+            (array.get $I32Array (local.get $program) (i32.add (local.get $pc) (i32.const 1)))))
+        (local.set $position (call $next_code_point_position (local.get $input) (local.get $position)))
+        (local.set $pc (i32.add (local.get $pc) (i32.const 2)))
+        (br $step)
+        )
+        ;; ANY
+        (br_if $fail (i32.ge_s (local.get $position) (local.get $length)))
+        (br_if $fail
+          (call $regexp_is_line_terminator (call $decode_code_point (local.get $input) (local.get $position))))
+        (local.set $position (call $next_code_point_position (local.get $input) (local.get $position)))
+        (local.set $pc (i32.add (local.get $pc) (i32.const 1)))
+        (br $step)
+        )
+        ;; ANY_ALL
+        (br_if $fail (i32.ge_s (local.get $position) (local.get $length)))
+        (local.set $position (call $next_code_point_position (local.get $input) (local.get $position)))
+        (local.set $pc (i32.add (local.get $pc) (i32.const 1)))
+        (br $step)
+        )
+        ;; CLASS negated range_count [from to]*
+        (br_if $fail (i32.ge_s (local.get $position) (local.get $length)))
+        (local.set $matched
+          (call $regexp_class_contains
+            (local.get $program)
+            (local.get $pc)
+            (call $decode_code_point (local.get $input) (local.get $position))))
+        (br_if $fail
+          (i32.eq
+            (local.get $matched)
+            (array.get $I32Array (local.get $program) (i32.add (local.get $pc) (i32.const 1)))))
+        (local.set $position (call $next_code_point_position (local.get $input) (local.get $position)))
+        (local.set $pc
+          (i32.add
+            (i32.add (local.get $pc) (i32.const 3))
+            (i32.shl (array.get $I32Array (local.get $program) (i32.add (local.get $pc) (i32.const 2))) (i32.const 1))))
+        (br $step)
+        )
+        ;; SPLIT preferred alternative
+        (call $i32_buffer_push3
+          (local.get $stack)
+          (i32.const 0)
+          (i32.add
+            (local.get $pc)
+            (array.get $I32Array (local.get $program) (i32.add (local.get $pc) (i32.const 2))))
+          (local.get $position))
+        (local.set $pc
+          (i32.add
+            (local.get $pc)
+            (array.get $I32Array (local.get $program) (i32.add (local.get $pc) (i32.const 1)))))
+        (br $step)
+        )
+        ;; JMP target
+        (local.set $pc
+          (i32.add
+            (local.get $pc)
+            (array.get $I32Array (local.get $program) (i32.add (local.get $pc) (i32.const 1)))))
+        (br $step)
+        )
+        ;; SAVE register
+        (local.set $register (array.get $I32Array (local.get $program) (i32.add (local.get $pc) (i32.const 1))))
+        (call $i32_buffer_push3
+          (local.get $stack)
+          (i32.const 1)
+          (local.get $register)
+          (array.get $I32Array (local.get $registers) (local.get $register)))
+        (array.set $I32Array (local.get $registers) (local.get $register) (local.get $position))
+        (local.set $pc (i32.add (local.get $pc) (i32.const 2)))
+        (br $step)
+        )
+        ;; LINE_START
+        (br_if $fail (local.get $position))
+        (local.set $pc (i32.add (local.get $pc) (i32.const 1)))
+        (br $step)
+        )
+        ;; LINE_START_MULTILINE
+        (if (local.get $position)
+          (then
+            (br_if $fail
+              (i32.eqz
+                (call $regexp_is_line_terminator
+                  (call $decode_code_point
+                    (local.get $input)
+                    (call $previous_code_point_position (local.get $input) (local.get $position))))))
+          )
+        )
+        (local.set $pc (i32.add (local.get $pc) (i32.const 1)))
+        (br $step)
+        )
+        ;; LINE_END
+        (br_if $fail (i32.ne (local.get $position) (local.get $length)))
+        (local.set $pc (i32.add (local.get $pc) (i32.const 1)))
+        (br $step)
+        )
+        ;; LINE_END_MULTILINE
+        (if (i32.ne (local.get $position) (local.get $length))
+          (then
+            (br_if $fail
+              (i32.eqz
+                (call $regexp_is_line_terminator
+                  (call $decode_code_point (local.get $input) (local.get $position)))))
+          )
+        )
+        (local.set $pc (i32.add (local.get $pc) (i32.const 1)))
+        (br $step)
+        )
+        ;; WORD_BOUNDARY
+        (br_if $fail
+          (i32.eq
+            (call $regexp_is_word_at
+              (local.get $input) (local.get $length) (i32.sub (local.get $position) (i32.const 1)))
+            (call $regexp_is_word_at (local.get $input) (local.get $length) (local.get $position))))
+        (local.set $pc (i32.add (local.get $pc) (i32.const 1)))
+        (br $step)
+        )
+        ;; NOT_WORD_BOUNDARY
+        (br_if $fail
+          (i32.ne
+            (call $regexp_is_word_at
+              (local.get $input) (local.get $length) (i32.sub (local.get $position) (i32.const 1)))
+            (call $regexp_is_word_at (local.get $input) (local.get $length) (local.get $position))))
+        (local.set $pc (i32.add (local.get $pc) (i32.const 1)))
+        (br $step)
+        )
+        ;; BACKREF group
+        (local.set $position
+          (call $regexp_backreference
+            (local.get $registers)
+            (array.get $I32Array (local.get $program) (i32.add (local.get $pc) (i32.const 1)))
+            (local.get $input)
+            (local.get $length)
+            (local.get $position)
+            (i32.const 0)))
+        (br_if $fail (i32.lt_s (local.get $position) (i32.const 0)))
+        (local.set $pc (i32.add (local.get $pc) (i32.const 2)))
+        (br $step)
+        )
+        ;; LOOKAHEAD negated length
+        (local.set $snapshot (call $copy_i32_array (local.get $registers)))
+        (local.set $matched
+          (i32.ge_s
+            (call $regexp_run
+              (local.get $program)
+              (local.get $input)
+              (local.get $length)
+              (i32.add (local.get $pc) (i32.const 3))
+              (local.get $position)
+              (local.get $registers)
+              (i32.const -1))
+            (i32.const 0)))
+        (br_if $fail
+          (i32.eqz
+            (call $regexp_assertion
+              (local.get $stack)
+              (local.get $registers)
+              (ref.as_non_null (local.get $snapshot))
+              (local.get $matched)
+              (array.get $I32Array (local.get $program) (i32.add (local.get $pc) (i32.const 1))))))
+        (local.set $pc
+          (i32.add
+            (local.get $pc)
+            (array.get $I32Array (local.get $program) (i32.add (local.get $pc) (i32.const 2)))))
+        (br $step)
+        )
+        ;; CHECK_PROGRESS loop_register
+        (br_if $fail
+          (i32.eq
+            (local.get $position)
+            (array.get $I32Array
+              (local.get $registers)
+              (i32.add
+                (local.get $marks)
+                (array.get $I32Array (local.get $program) (i32.add (local.get $pc) (i32.const 1)))))))
+        (local.set $pc (i32.add (local.get $pc) (i32.const 2)))
+        (br $step)
+        )
+        ;; RESET from to
+        (local.set $i (array.get $I32Array (local.get $program) (i32.add (local.get $pc) (i32.const 1))))
+        (block $done (loop $clear
+          (br_if $done
+            (i32.ge_s
+              (local.get $i)
+              (array.get $I32Array (local.get $program) (i32.add (local.get $pc) (i32.const 2)))))
+          (call $i32_buffer_push3
+            (local.get $stack)
+            (i32.const 1)
+            (local.get $i)
+            (array.get $I32Array (local.get $registers) (local.get $i)))
+          (array.set $I32Array (local.get $registers) (local.get $i) (i32.const -1))
+          (local.set $i (i32.add (local.get $i) (i32.const 1)))
+          (br $clear)
+        ))
+        (local.set $pc (i32.add (local.get $pc) (i32.const 3)))
+        (br $step)
+        )
+        ;; CHAR_I canonicalized_code_point
+        (br_if $fail (i32.ge_s (local.get $position) (local.get $length)))
+        (br_if $fail
+          (i32.ne
+            (call $regexp_canonicalize (call $decode_code_point (local.get $input) (local.get $position)))
+            (array.get $I32Array (local.get $program) (i32.add (local.get $pc) (i32.const 1)))))
+        (local.set $position (call $next_code_point_position (local.get $input) (local.get $position)))
+        (local.set $pc (i32.add (local.get $pc) (i32.const 2)))
+        (br $step)
+        )
+        ;; CLASS_I negated range_count [from to]*
+        (br_if $fail (i32.ge_s (local.get $position) (local.get $length)))
+        (local.set $code_point (call $decode_code_point (local.get $input) (local.get $position)))
+        (local.set $matched
+          (i32.or
+            (call $regexp_class_contains (local.get $program) (local.get $pc) (local.get $code_point))
+            (i32.or
+              (call $regexp_class_contains
+                (local.get $program) (local.get $pc) (call $regexp_canonicalize (local.get $code_point)))
+              (call $regexp_class_contains
+                (local.get $program) (local.get $pc) (call $regexp_lowercase (local.get $code_point))))))
+        (br_if $fail
+          (i32.eq
+            (local.get $matched)
+            (array.get $I32Array (local.get $program) (i32.add (local.get $pc) (i32.const 1)))))
+        (local.set $position (call $next_code_point_position (local.get $input) (local.get $position)))
+        (local.set $pc
+          (i32.add
+            (i32.add (local.get $pc) (i32.const 3))
+            (i32.shl (array.get $I32Array (local.get $program) (i32.add (local.get $pc) (i32.const 2))) (i32.const 1))))
+        (br $step)
+        )
+        ;; BACKREF_I group
+        (local.set $position
+          (call $regexp_backreference
+            (local.get $registers)
+            (array.get $I32Array (local.get $program) (i32.add (local.get $pc) (i32.const 1)))
+            (local.get $input)
+            (local.get $length)
+            (local.get $position)
+            (i32.const 1)))
+        (br_if $fail (i32.lt_s (local.get $position) (i32.const 0)))
+        (local.set $pc (i32.add (local.get $pc) (i32.const 2)))
+        (br $step)
+        )
+        ;; MARK loop_register
+        (local.set $register
+          (i32.add
+            (local.get $marks)
+            (array.get $I32Array (local.get $program) (i32.add (local.get $pc) (i32.const 1)))))
+        (call $i32_buffer_push3
+          (local.get $stack)
+          (i32.const 1)
+          (local.get $register)
+          (array.get $I32Array (local.get $registers) (local.get $register)))
+        (array.set $I32Array (local.get $registers) (local.get $register) (local.get $position))
+        (local.set $pc (i32.add (local.get $pc) (i32.const 2)))
+        (br $step)
+        )
+        ;; LOOKBEHIND negated length, tries every start position going back
+        ;; from the current one, the first one that ends here wins
+        (local.set $snapshot (call $copy_i32_array (local.get $registers)))
+        (local.set $matched (i32.const 0))
+        (local.set $start (local.get $position))
+        (block $done (loop $starts
+          (if (i32.ge_s
+                (call $regexp_run
+                  (local.get $program)
+                  (local.get $input)
+                  (local.get $length)
+                  (i32.add (local.get $pc) (i32.const 3))
+                  (local.get $start)
+                  (local.get $registers)
+                  (local.get $position))
+                (i32.const 0))
+            (then
+              (local.set $matched (i32.const 1))
+              (br $done)
+            )
+          )
+          (br_if $done (i32.eqz (local.get $start)))
+          (local.set $start (call $previous_code_point_position (local.get $input) (local.get $start)))
+          (br $starts)
+        ))
+        (br_if $fail
+          (i32.eqz
+            (call $regexp_assertion
+              (local.get $stack)
+              (local.get $registers)
+              (ref.as_non_null (local.get $snapshot))
+              (local.get $matched)
+              (array.get $I32Array (local.get $program) (i32.add (local.get $pc) (i32.const 1))))))
+        (local.set $pc
+          (i32.add
+            (local.get $pc)
+            (array.get $I32Array (local.get $program) (i32.add (local.get $pc) (i32.const 2)))))
+        (br $step)
+      )
+
+      ;; backtrack to the last choice point, undoing register changes on the way
+      (loop $backtrack
+        (local.set $i (struct.get $I32Buffer $size (local.get $stack)))
+        (if (i32.eqz (local.get $i))
+          (then (return (i32.const -1)))
+        )
+        (local.set $i (i32.sub (local.get $i) (i32.const 3)))
+        (struct.set $I32Buffer $size (local.get $stack) (local.get $i))
+        (local.set $data (struct.get $I32Buffer $data (local.get $stack)))
+        (if (array.get $I32Array (local.get $data) (local.get $i))
+          (then
+            (array.set $I32Array
+              (local.get $registers)
+              (array.get $I32Array (local.get $data) (i32.add (local.get $i) (i32.const 1)))
+              (array.get $I32Array (local.get $data) (i32.add (local.get $i) (i32.const 2))))
+            (br $backtrack)
+          )
+        )
+        (local.set $pc (array.get $I32Array (local.get $data) (i32.add (local.get $i) (i32.const 1))))
+        (local.set $position (array.get $I32Array (local.get $data) (i32.add (local.get $i) (i32.const 2))))
+      )
+      (br $step)
+    )
+    (unreachable)
+  )
+
+  ;; Finds the first match starting at $start or later, or exactly at $start
+  ;; for sticky searches. Returns the capture registers, where group n spans
+  ;; from register 2n to 2n+1, or null if there's no match
+  (func $regexp_search
+    (param $regexp (ref $RegExp))
+    (param $input (ref $CharArray))
+    (param $length i32)
+    (param $start i32)
+    (param $sticky i32)
+    (result (ref null $I32Array))
+    (local $program (ref $I32Array))
+    (local $registers (ref $I32Array))
+    (local $position i32)
+
+    (local.set $program (struct.get $RegExp $program (local.get $regexp)))
+    (local.set $registers
+      (array.new $I32Array
+        (i32.const -1)
+        (i32.add
+          (i32.shl (array.get $I32Array (local.get $program) (i32.const 0)) (i32.const 1))
+          (array.get $I32Array (local.get $program) (i32.const 1)))))
+    (local.set $position (local.get $start))
+
+    (loop $positions
+      (if (i32.ge_s
+            (call $regexp_run
+              (local.get $program)
+              (local.get $input)
+              (local.get $length)
+              (i32.const 2)
+              (local.get $position)
+              (local.get $registers)
+              (i32.const -1))
+            (i32.const 0))
+        (then (return (local.get $registers)))
+      )
+      (if (i32.or (local.get $sticky) (i32.ge_s (local.get $position) (local.get $length)))
+        (then (return (ref.null $I32Array)))
+      )
+      (local.set $position (call $next_code_point_position (local.get $input) (local.get $position)))
+      (br $positions)
+    )
+    (unreachable)
+  )
+
+  ;; Compiling patterns at runtime, a port of src/regexp.rs. The code of each
+  ;; part of the pattern is built in its own $I32Buffer, jumps are relative so
+  ;; buffers can be concatenated as they are
+
+  {% set max_program_size = 1048576 %}
+
+  (type $RegExpParser (struct
+    (field $source anyref)
+    (field $flags_text anyref)
+    (field $data (ref $CharArray))
+    (field $length i32)
+    (field $position (mut i32))
+    (field $flags i32)
+    (field $group_count (mut i32))
+    (field $mark_count (mut i32))
+    ;; names of all the capture groups or nulls for unnamed ones, found before
+    ;; compiling as backreferences can refer to groups that come later
+    (field $group_names (mut (ref $AnyrefArray)))
+    ;; set by $regexp_parse_braced_quantifier, the maximum is -1 for {n,}
+    (field $min (mut i32))
+    (field $max (mut i32))
+  ))
+
+  (func $regexp_parse_error (param $parser (ref $RegExpParser)) (param $message anyref)
+    (local $builder (ref $StringBuilder))
+    (local.set $builder (call $new_string_builder))
+    (call $builder_append_string (local.get $builder)
+      (call $new_static_string (i32.const {{ data(str="Invalid regular expression: /") }}) (i32.const {{ data_length() }})))
+    (call $builder_append_string (local.get $builder) (struct.get $RegExpParser $source (local.get $parser)))
+    (call $builder_append_byte (local.get $builder) (i32.const 0x2f)) ;; /
+    (call $builder_append_string (local.get $builder) (struct.get $RegExpParser $flags_text (local.get $parser)))
+    (call $builder_append_byte (local.get $builder) (i32.const 0x3a)) ;; :
+    (call $builder_append_byte (local.get $builder) (i32.const 0x20))
+    (call $builder_append_string (local.get $builder) (local.get $message))
+    (call $throw_syntax_error (call $builder_to_string (local.get $builder)))
+  )
+
+  (func $regexp_parser_unicode (param $parser (ref $RegExpParser)) (result i32)
+    (i32.ne
+      (i32.and (struct.get $RegExpParser $flags (local.get $parser)) (i32.const {{ flag_unicode }}))
+      (i32.const 0))
+  )
+
+  (func $regexp_parser_ignore_case (param $parser (ref $RegExpParser)) (result i32)
+    (i32.ne
+      (i32.and (struct.get $RegExpParser $flags (local.get $parser)) (i32.const {{ flag_ignore_case }}))
+      (i32.const 0))
+  )
+
+  ;; the code point at the current position or -1 at the end of the pattern
+  (func $regexp_peek (param $parser (ref $RegExpParser)) (result i32)
+    (if (i32.ge_u
+          (struct.get $RegExpParser $position (local.get $parser))
+          (struct.get $RegExpParser $length (local.get $parser)))
+      (then (return (i32.const -1)))
+    )
+    (call $decode_code_point
+      (struct.get $RegExpParser $data (local.get $parser))
+      (struct.get $RegExpParser $position (local.get $parser)))
+  )
+
+  ;; the byte $offset bytes ahead or -1, the grammar only looks ahead for
+  ;; ASCII characters
+  (func $regexp_peek_at (param $parser (ref $RegExpParser)) (param $offset i32) (result i32)
+    (local $position i32)
+    (local.set $position (i32.add (struct.get $RegExpParser $position (local.get $parser)) (local.get $offset)))
+    (if (i32.ge_u (local.get $position) (struct.get $RegExpParser $length (local.get $parser)))
+      (then (return (i32.const -1)))
+    )
+    (array.get_u $CharArray (struct.get $RegExpParser $data (local.get $parser)) (local.get $position))
+  )
+
+  (func $regexp_skip (param $parser (ref $RegExpParser)) (param $count i32)
+    (struct.set $RegExpParser $position (local.get $parser)
+      (i32.add (struct.get $RegExpParser $position (local.get $parser)) (local.get $count)))
+  )
+
+  ;; moves past the code point at the current position
+  (func $regexp_advance (param $parser (ref $RegExpParser))
+    (struct.set $RegExpParser $position (local.get $parser)
+      (call $next_code_point_position
+        (struct.get $RegExpParser $data (local.get $parser))
+        (struct.get $RegExpParser $position (local.get $parser))))
+  )
+
+  (func $regexp_eat (param $parser (ref $RegExpParser)) (param $char i32) (result i32)
+    (if (i32.eq (call $regexp_peek_at (local.get $parser) (i32.const 0)) (local.get $char))
+      (then
+        (call $regexp_skip (local.get $parser) (i32.const 1))
+        (return (i32.const 1))
+      )
+    )
+    (i32.const 0)
+  )
+
+  (func $regexp_check_size (param $parser (ref $RegExpParser)) (param $size i32)
+    (if (i32.gt_u (local.get $size) (i32.const {{ max_program_size }}))
+      (then
+        (call $regexp_parse_error (local.get $parser)
+          (call $new_static_string (i32.const {{ data(str="Regular expression too large") }}) (i32.const {{ data_length() }})))
+      )
+    )
+  )
+
+  (func $regexp_code1 (param $op i32) (result (ref $I32Buffer))
+    (local $code (ref $I32Buffer))
+    (local.set $code (call $new_i32_buffer))
+    (call $i32_buffer_push (local.get $code) (local.get $op))
+    (local.get $code)
+  )
+
+  (func $regexp_code2 (param $op i32) (param $operand i32) (result (ref $I32Buffer))
+    (local $code (ref $I32Buffer))
+    (local.set $code (call $new_i32_buffer))
+    (call $i32_buffer_push2 (local.get $code) (local.get $op) (local.get $operand))
+    (local.get $code)
+  )
+
+  (func $regexp_scan_groups (param $parser (ref $RegExpParser)) (result (ref $AnyrefArray))
+    (local $names (ref $ValueList))
+    (local $in_class i32)
+    (local $i i32)
+    (local $end i32)
+    (local $byte i32)
+
+    (local.set $names (call $new_value_list))
+    ;; group 0 is the whole match
+    (call $value_list_push (local.get $names) (ref.null any))
+
+    (block $done (loop $bytes
+      (br_if $done (i32.ge_u (local.get $i) (struct.get $RegExpParser $length (local.get $parser))))
+      (local.set $byte
+        (array.get_u $CharArray (struct.get $RegExpParser $data (local.get $parser)) (local.get $i)))
+
+      (if (i32.eq (local.get $byte) (i32.const 0x5c)) ;; \
+        (then (local.set $i (i32.add (local.get $i) (i32.const 1))))
+      )
+      (if (i32.eq (local.get $byte) (i32.const 0x5b)) ;; [
+        (then (local.set $in_class (i32.const 1)))
+      )
+      (if (i32.eq (local.get $byte) (i32.const 0x5d)) ;; ]
+        (then (local.set $in_class (i32.const 0)))
+      )
+      (if (i32.and
+            (i32.eq (local.get $byte) (i32.const 0x28)) ;; (
+            (i32.eqz (local.get $in_class)))
+        (then
+          (struct.set $RegExpParser $position (local.get $parser) (local.get $i))
+          (if (i32.ne (call $regexp_peek_at (local.get $parser) (i32.const 1)) (i32.const 0x3f)) ;; ?
+            (then (call $value_list_push (local.get $names) (ref.null any)))
+            (else
+              (if (i32.and
+                    (i32.eq (call $regexp_peek_at (local.get $parser) (i32.const 2)) (i32.const 0x3c)) ;; <
+                    (i32.and
+                      (i32.ne (call $regexp_peek_at (local.get $parser) (i32.const 3)) (i32.const 0x3d)) ;; =
+                      (i32.ne (call $regexp_peek_at (local.get $parser) (i32.const 3)) (i32.const 0x21)))) ;; !
+                (then
+                  (local.set $end (i32.add (local.get $i) (i32.const 3)))
+                  (block $found (loop $name
+                    (br_if $found (i32.ge_u (local.get $end) (struct.get $RegExpParser $length (local.get $parser))))
+                    (br_if $found
+                      (i32.eq
+                        (array.get_u $CharArray (struct.get $RegExpParser $data (local.get $parser)) (local.get $end))
+                        (i32.const 0x3e))) ;; >
+                    (local.set $end (i32.add (local.get $end) (i32.const 1)))
+                    (br $name)
+                  ))
+                  (call $value_list_push (local.get $names)
+                    (call $substring
+                      (struct.get $RegExpParser $source (local.get $parser))
+                      (i32.add (local.get $i) (i32.const 3))
+                      (local.get $end)))
+                )
+              )
+            )
+          )
+        )
+      )
+
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $bytes)
+    ))
+
+    (struct.set $RegExpParser $position (local.get $parser) (i32.const 0))
+    (call $value_list_to_anyref_array (local.get $names))
+  )
+
+  (func $regexp_has_group_names (param $names (ref $AnyrefArray)) (result i32)
+    (local $i i32)
+    (block $done (loop $names
+      (br_if $done (i32.ge_u (local.get $i) (array.len (local.get $names))))
+      (if (i32.eqz (ref.is_null (array.get $AnyrefArray (local.get $names) (local.get $i))))
+        (then (return (i32.const 1)))
+      )
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $names)
+    ))
+    (i32.const 0)
+  )
+
+  (func $regexp_parse_disjunction (param $parser (ref $RegExpParser)) (result (ref $I32Buffer))
+    (local $alternatives (ref $ValueList))
+    (local $alternative (ref $I32Buffer))
+    (local $code (ref $I32Buffer))
+    (local $result (ref $I32Buffer))
+    (local $i i32)
+
+    (local.set $alternatives (call $new_value_list))
+    (call $value_list_push (local.get $alternatives) (call $regexp_parse_alternative (local.get $parser)))
+    (block $done (loop $alternatives
+      (br_if $done (i32.eqz (call $regexp_eat (local.get $parser) (i32.const 0x7c)))) ;; |
+      (call $value_list_push (local.get $alternatives) (call $regexp_parse_alternative (local.get $parser)))
+      (br $alternatives)
+    ))
+
+    ;; a|b|c is compiled as a|(b|c)
+    (local.set $i (i32.sub (struct.get $ValueList $size (local.get $alternatives)) (i32.const 1)))
+    (local.set $code
+      (ref.cast (ref $I32Buffer)
+        (array.get $AnyrefArray (struct.get $ValueList $data (local.get $alternatives)) (local.get $i))))
+    (block $done (loop $join
+      (br_if $done (i32.eqz (local.get $i)))
+      (local.set $i (i32.sub (local.get $i) (i32.const 1)))
+      (local.set $alternative
+        (ref.cast (ref $I32Buffer)
+          (array.get $AnyrefArray (struct.get $ValueList $data (local.get $alternatives)) (local.get $i))))
+
+      (local.set $result (call $new_i32_buffer))
+      (call $i32_buffer_push3 (local.get $result)
+        (i32.const {{ op_split }})
+        (i32.const 3)
+        (i32.add (call $i32_buffer_size (local.get $alternative)) (i32.const 5)))
+      (call $i32_buffer_append (local.get $result) (local.get $alternative))
+      (call $i32_buffer_push2 (local.get $result)
+        (i32.const {{ op_jmp }})
+        (i32.add (call $i32_buffer_size (local.get $code)) (i32.const 2)))
+      (call $i32_buffer_append (local.get $result) (local.get $code))
+      (local.set $code (local.get $result))
+      (br $join)
+    ))
+
+    (local.get $code)
+  )
+
+  (func $regexp_parse_alternative (param $parser (ref $RegExpParser)) (result (ref $I32Buffer))
+    (local $code (ref $I32Buffer))
+    (local $char i32)
+
+    (local.set $code (call $new_i32_buffer))
+    (block $done (loop $terms
+      (local.set $char (call $regexp_peek_at (local.get $parser) (i32.const 0)))
+      (br_if $done (i32.eq (local.get $char) (i32.const -1)))
+      (br_if $done (i32.eq (local.get $char) (i32.const 0x7c))) ;; |
+      (br_if $done (i32.eq (local.get $char) (i32.const 0x29))) ;; )
+      (call $i32_buffer_append (local.get $code) (call $regexp_parse_term (local.get $parser)))
+      (call $regexp_check_size (local.get $parser) (call $i32_buffer_size (local.get $code)))
+      (br $terms)
+    ))
+    (local.get $code)
+  )
+
+  (func $regexp_parse_term (param $parser (ref $RegExpParser)) (result (ref $I32Buffer))
+    (local $char i32)
+    (local $next i32)
+    (local $op i32)
+    (local $negated i32)
+    (local $prefix i32)
+    (local $body (ref $I32Buffer))
+    (local $code (ref $I32Buffer))
+    (local $first_group i32)
+    (local $atom (ref $I32Buffer))
+    (local $multiline i32)
+
+    (local.set $multiline
+      (i32.and (struct.get $RegExpParser $flags (local.get $parser)) (i32.const {{ flag_multiline }})))
+    (local.set $char (call $regexp_peek_at (local.get $parser) (i32.const 0)))
+    (local.set $next (call $regexp_peek_at (local.get $parser) (i32.const 1)))
+
+    (if (i32.eq (local.get $char) (i32.const 0x5e)) ;; ^
+      (then
+        (call $regexp_skip (local.get $parser) (i32.const 1))
+        (return
+          (call $regexp_code1
+            (select
+              (i32.const {{ op_line_start_multiline }})
+              (i32.const {{ op_line_start }})
+              (local.get $multiline))))
+      )
+    )
+    (if (i32.eq (local.get $char) (i32.const 0x24)) ;; $
+      (then
+        (call $regexp_skip (local.get $parser) (i32.const 1))
+        (return
+          (call $regexp_code1
+            (select
+              (i32.const {{ op_line_end_multiline }})
+              (i32.const {{ op_line_end }})
+              (local.get $multiline))))
+      )
+    )
+    (if (i32.eq (local.get $char) (i32.const 0x5c)) ;; \
+      (then
+        (if (i32.eq (local.get $next) (i32.const 0x62)) ;; b
+          (then
+            (call $regexp_skip (local.get $parser) (i32.const 2))
+            (return (call $regexp_code1 (i32.const {{ op_word_boundary }})))
+          )
+        )
+        (if (i32.eq (local.get $next) (i32.const 0x42)) ;; B
+          (then
+            (call $regexp_skip (local.get $parser) (i32.const 2))
+            (return (call $regexp_code1 (i32.const {{ op_not_word_boundary }})))
+          )
+        )
+      )
+    )
+
+    ;; lookarounds: (?=, (?!, (?<= and (?<!
+    (if (i32.and
+          (i32.eq (local.get $char) (i32.const 0x28)) ;; (
+          (i32.eq (local.get $next) (i32.const 0x3f))) ;; ?
+      (then
+        (local.set $op (i32.const -1))
+        (local.set $char (call $regexp_peek_at (local.get $parser) (i32.const 2)))
+        (local.set $next (call $regexp_peek_at (local.get $parser) (i32.const 3)))
+        (if (i32.eq (local.get $char) (i32.const 0x3d)) ;; =
+          (then
+            (local.set $op (i32.const {{ op_lookahead }}))
+            (local.set $negated (i32.const 0))
+            (local.set $prefix (i32.const 3))
+          )
+        )
+        (if (i32.eq (local.get $char) (i32.const 0x21)) ;; !
+          (then
+            (local.set $op (i32.const {{ op_lookahead }}))
+            (local.set $negated (i32.const 1))
+            (local.set $prefix (i32.const 3))
+          )
+        )
+        (if (i32.eq (local.get $char) (i32.const 0x3c)) ;; <
+          (then
+            (if (i32.eq (local.get $next) (i32.const 0x3d)) ;; =
+              (then
+                (local.set $op (i32.const {{ op_lookbehind }}))
+                (local.set $negated (i32.const 0))
+                (local.set $prefix (i32.const 4))
+              )
+            )
+            (if (i32.eq (local.get $next) (i32.const 0x21)) ;; !
+              (then
+                (local.set $op (i32.const {{ op_lookbehind }}))
+                (local.set $negated (i32.const 1))
+                (local.set $prefix (i32.const 4))
+              )
+            )
+          )
+        )
+
+        (if (i32.ge_s (local.get $op) (i32.const 0))
+          (then
+            (call $regexp_skip (local.get $parser) (local.get $prefix))
+            (local.set $body (call $regexp_parse_disjunction (local.get $parser)))
+            (if (i32.eqz (call $regexp_eat (local.get $parser) (i32.const 0x29))) ;; )
+              (then
+                (call $regexp_parse_error (local.get $parser)
+                  (call $new_static_string (i32.const {{ data(str="Unterminated group") }}) (i32.const {{ data_length() }})))
+              )
+            )
+            (local.set $code (call $new_i32_buffer))
+            (call $i32_buffer_push3 (local.get $code)
+              (local.get $op)
+              (local.get $negated)
+              (i32.add (call $i32_buffer_size (local.get $body)) (i32.const 4)))
+            (call $i32_buffer_append (local.get $code) (local.get $body))
+            (call $i32_buffer_push (local.get $code) (i32.const {{ op_match }}))
+            (return (local.get $code))
+          )
+        )
+      )
+    )
+
+    (local.set $first_group (struct.get $RegExpParser $group_count (local.get $parser)))
+    (local.set $atom (call $regexp_parse_atom (local.get $parser)))
+    (call $regexp_parse_quantifier
+      (local.get $parser)
+      (local.get $atom)
+      (local.get $first_group)
+      (struct.get $RegExpParser $group_count (local.get $parser)))
+  )
+
+  ;; a decimal number or -1 if there are no digits, big numbers saturate
+  (func $regexp_parse_decimal (param $parser (ref $RegExpParser)) (result i32)
+    (local $value i32)
+    (local $digit i32)
+    (local $found i32)
+
+    (block $done (loop $digits
+      (local.set $digit (i32.sub (call $regexp_peek_at (local.get $parser) (i32.const 0)) (i32.const 0x30)))
+      (br_if $done (i32.ge_u (local.get $digit) (i32.const 10)))
+      (local.set $found (i32.const 1))
+      (if (i32.gt_u (local.get $value) (i32.const 214748363))
+        (then (local.set $value (i32.const 0x7fffffff)))
+        (else
+          (local.set $value
+            (i32.add (i32.mul (local.get $value) (i32.const 10)) (local.get $digit)))
+        )
+      )
+      (call $regexp_skip (local.get $parser) (i32.const 1))
+      (br $digits)
+    ))
+
+    (if (i32.eqz (local.get $found))
+      (then (return (i32.const -1)))
+    )
+    (local.get $value)
+  )
+
+  ;; reads {n}, {n,} or {n,m} into the $min and $max fields, returns 0 and
+  ;; doesn't move if there's no valid quantifier
+  (func $regexp_parse_braced_quantifier (param $parser (ref $RegExpParser)) (result i32)
+    (local $start i32)
+    (local $min i32)
+    (local $max i32)
+
+    (local.set $start (struct.get $RegExpParser $position (local.get $parser)))
+    (if (i32.eqz (call $regexp_eat (local.get $parser) (i32.const 0x7b))) ;; {
+      (then (return (i32.const 0)))
+    )
+
+    (block $invalid
+      (local.set $min (call $regexp_parse_decimal (local.get $parser)))
+      (br_if $invalid (i32.lt_s (local.get $min) (i32.const 0)))
+
+      (if (call $regexp_eat (local.get $parser) (i32.const 0x7d)) ;; }
+        (then
+          (local.set $max (local.get $min))
+        )
+        (else
+          (br_if $invalid (i32.eqz (call $regexp_eat (local.get $parser) (i32.const 0x2c)))) ;; ,
+          (if (call $regexp_eat (local.get $parser) (i32.const 0x7d)) ;; }
+            (then
+              (local.set $max (i32.const -1))
+            )
+            (else
+              (local.set $max (call $regexp_parse_decimal (local.get $parser)))
+              (br_if $invalid (i32.lt_s (local.get $max) (i32.const 0)))
+              (br_if $invalid (i32.eqz (call $regexp_eat (local.get $parser) (i32.const 0x7d)))) ;; }
+            )
+          )
+        )
+      )
+
+      (struct.set $RegExpParser $min (local.get $parser) (local.get $min))
+      (struct.set $RegExpParser $max (local.get $parser) (local.get $max))
+      (return (i32.const 1))
+    )
+
+    (struct.set $RegExpParser $position (local.get $parser) (local.get $start))
+    (i32.const 0)
+  )
+
+  (func $regexp_parse_quantifier
+    (param $parser (ref $RegExpParser))
+    (param $atom (ref $I32Buffer))
+    (param $first_group i32)
+    (param $last_group i32)
+    (result (ref $I32Buffer))
+    (local $char i32)
+    (local $min i32)
+    (local $max i32)
+    (local $greedy i32)
+    (local $body (ref $I32Buffer))
+    (local $code (ref $I32Buffer))
+    (local $iteration (ref $I32Buffer))
+    (local $optional (ref $I32Buffer))
+    (local $inner (ref $I32Buffer))
+    (local $mark i32)
+    (local $length i32)
+    (local $i i32)
+
+    (local.set $char (call $regexp_peek_at (local.get $parser) (i32.const 0)))
+    (block $quantifier
+      (if (i32.eq (local.get $char) (i32.const 0x2a)) ;; *
+        (then
+          (call $regexp_skip (local.get $parser) (i32.const 1))
+          (local.set $min (i32.const 0))
+          (local.set $max (i32.const -1))
+          (br $quantifier)
+        )
+      )
+      (if (i32.eq (local.get $char) (i32.const 0x2b)) ;; +
+        (then
+          (call $regexp_skip (local.get $parser) (i32.const 1))
+          (local.set $min (i32.const 1))
+          (local.set $max (i32.const -1))
+          (br $quantifier)
+        )
+      )
+      (if (i32.eq (local.get $char) (i32.const 0x3f)) ;; ?
+        (then
+          (call $regexp_skip (local.get $parser) (i32.const 1))
+          (local.set $min (i32.const 0))
+          (local.set $max (i32.const 1))
+          (br $quantifier)
+        )
+      )
+      (if (i32.eq (local.get $char) (i32.const 0x7b)) ;; {
+        (then
+          (if (call $regexp_parse_braced_quantifier (local.get $parser))
+            (then
+              (local.set $min (struct.get $RegExpParser $min (local.get $parser)))
+              (local.set $max (struct.get $RegExpParser $max (local.get $parser)))
+              (br $quantifier)
+            )
+          )
+        )
+      )
+      (return (local.get $atom))
+    )
+
+    (if (i32.and
+          (i32.ge_s (local.get $max) (i32.const 0))
+          (i32.lt_s (local.get $max) (local.get $min)))
+      (then
+        (call $regexp_parse_error (local.get $parser)
+          (call $new_static_string (i32.const {{ data(str="numbers out of order in {} quantifier") }}) (i32.const {{ data_length() }})))
+      )
+    )
+    (local.set $greedy (i32.eqz (call $regexp_eat (local.get $parser) (i32.const 0x3f)))) ;; ?
+
+    ;; groups inside of a repeated atom are cleared on each iteration
+    (local.set $body (call $new_i32_buffer))
+    (if (i32.gt_s (local.get $last_group) (local.get $first_group))
+      (then
+        (call $i32_buffer_push3 (local.get $body)
+          (i32.const {{ op_reset }})
+          (i32.shl (local.get $first_group) (i32.const 1))
+          (i32.shl (local.get $last_group) (i32.const 1)))
+      )
+    )
+    (call $i32_buffer_append (local.get $body) (local.get $atom))
+    (local.set $length (call $i32_buffer_size (local.get $body)))
+
+    ;; checked upfront, so huge counts don't spend ages copying the body
+    (call $regexp_check_size_product (local.get $parser) (local.get $min) (local.get $length))
+    (if (i32.ge_s (local.get $max) (i32.const 0))
+      (then
+        (call $regexp_check_size_product (local.get $parser)
+          (i32.sub (local.get $max) (local.get $min))
+          (i32.add (local.get $length) (i32.const 3)))
+      )
+    )
+
+    (local.set $code (call $new_i32_buffer))
+    (block $done (loop $required
+      (br_if $done (i32.ge_s (local.get $i) (local.get $min)))
+      (call $i32_buffer_append (local.get $code) (local.get $body))
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $required)
+    ))
+
+    (if (i32.lt_s (local.get $max) (i32.const 0))
+      (then
+        (local.set $mark (struct.get $RegExpParser $mark_count (local.get $parser)))
+        (struct.set $RegExpParser $mark_count (local.get $parser) (i32.add (local.get $mark) (i32.const 1)))
+
+        (local.set $iteration (call $new_i32_buffer))
+        (call $i32_buffer_push2 (local.get $iteration) (i32.const {{ op_mark }}) (local.get $mark))
+        (call $i32_buffer_append (local.get $iteration) (local.get $body))
+        (call $i32_buffer_push2 (local.get $iteration) (i32.const {{ op_check_progress }}) (local.get $mark))
+        (local.set $length (call $i32_buffer_size (local.get $iteration)))
+
+        (if (local.get $greedy)
+          (then
+            (call $i32_buffer_push3 (local.get $code)
+              (i32.const {{ op_split }}) (i32.const 3) (i32.add (local.get $length) (i32.const 5)))
+          )
+          (else
+            (call $i32_buffer_push3 (local.get $code)
+              (i32.const {{ op_split }}) (i32.add (local.get $length) (i32.const 5)) (i32.const 3))
+          )
+        )
+        (call $i32_buffer_append (local.get $code) (local.get $iteration))
+        (call $i32_buffer_push2 (local.get $code)
+          (i32.const {{ op_jmp }})
+          (i32.sub (i32.const -3) (local.get $length)))
+      )
+      (else
+        ;; each optional copy wraps the next one, so they're only tried if the
+        ;; previous one matched
+        (local.set $optional (call $new_i32_buffer))
+        (local.set $i (local.get $min))
+        (block $done (loop $optional
+          (br_if $done (i32.ge_s (local.get $i) (local.get $max)))
+          (local.set $inner (call $new_i32_buffer))
+          (call $i32_buffer_append (local.get $inner) (local.get $body))
+          (call $i32_buffer_append (local.get $inner) (local.get $optional))
+          (local.set $length (call $i32_buffer_size (local.get $inner)))
+
+          (local.set $optional (call $new_i32_buffer))
+          (if (local.get $greedy)
+            (then
+              (call $i32_buffer_push3 (local.get $optional)
+                (i32.const {{ op_split }}) (i32.const 3) (i32.add (local.get $length) (i32.const 3)))
+            )
+            (else
+              (call $i32_buffer_push3 (local.get $optional)
+                (i32.const {{ op_split }}) (i32.add (local.get $length) (i32.const 3)) (i32.const 3))
+            )
+          )
+          (call $i32_buffer_append (local.get $optional) (local.get $inner))
+          (local.set $i (i32.add (local.get $i) (i32.const 1)))
+          (br $optional)
+        ))
+        (call $i32_buffer_append (local.get $code) (local.get $optional))
+      )
+    )
+
+    (local.get $code)
+  )
+
+  (func $regexp_check_size_product (param $parser (ref $RegExpParser)) (param $count i32) (param $size i32)
+    (if (i64.gt_u
+          (i64.mul (i64.extend_i32_u (local.get $count)) (i64.extend_i32_u (local.get $size)))
+          (i64.const {{ max_program_size }}))
+      (then
+        (call $regexp_parse_error (local.get $parser)
+          (call $new_static_string (i32.const {{ data(str="Regular expression too large") }}) (i32.const {{ data_length() }})))
+      )
+    )
+  )
+
+  (func $regexp_next_group (param $parser (ref $RegExpParser)) (result i32)
+    (local $index i32)
+    (local.set $index (struct.get $RegExpParser $group_count (local.get $parser)))
+    (struct.set $RegExpParser $group_count (local.get $parser) (i32.add (local.get $index) (i32.const 1)))
+    (local.get $index)
+  )
+
+  ;; reads a group name up to and including the closing >, returns null if
+  ;; the name is not terminated
+  (func $regexp_parse_group_name (param $parser (ref $RegExpParser)) (result (ref null $String))
+    (local $start i32)
+    (local $char i32)
+
+    (local.set $start (struct.get $RegExpParser $position (local.get $parser)))
+    (block $done (loop $name
+      (local.set $char (call $regexp_peek_at (local.get $parser) (i32.const 0)))
+      (if (i32.eq (local.get $char) (i32.const -1))
+        (then (return (ref.null $String)))
+      )
+      (br_if $done (i32.eq (local.get $char) (i32.const 0x3e))) ;; >
+      (call $regexp_advance (local.get $parser))
+      (br $name)
+    ))
+
+    (call $regexp_skip (local.get $parser) (i32.const 1))
+    (call $substring
+      (struct.get $RegExpParser $source (local.get $parser))
+      (local.get $start)
+      (i32.sub (struct.get $RegExpParser $position (local.get $parser)) (i32.const 1)))
+  )
+
+  (func $regexp_parse_atom (param $parser (ref $RegExpParser)) (result (ref $I32Buffer))
+    (local $char i32)
+    (local $index i32)
+    (local $name (ref null $String))
+    (local $body (ref $I32Buffer))
+    (local $code (ref $I32Buffer))
+
+    (local.set $char (call $regexp_peek (local.get $parser)))
+
+    (if (i32.eq (local.get $char) (i32.const 0x2e)) ;; .
+      (then
+        (call $regexp_skip (local.get $parser) (i32.const 1))
+        (return
+          (call $regexp_code1
+            (select
+              (i32.const {{ op_any_all }})
+              (i32.const {{ op_any }})
+              (i32.and (struct.get $RegExpParser $flags (local.get $parser)) (i32.const {{ flag_dot_all }})))))
+      )
+    )
+
+    (if (i32.eq (local.get $char) (i32.const 0x28)) ;; (
+      (then
+        (call $regexp_skip (local.get $parser) (i32.const 1))
+        (local.set $index (i32.const -1))
+        (if (call $regexp_eat (local.get $parser) (i32.const 0x3f)) ;; ?
+          (then
+            (if (i32.eqz (call $regexp_eat (local.get $parser) (i32.const 0x3a))) ;; :
+              (then
+                (if (i32.eqz (call $regexp_eat (local.get $parser) (i32.const 0x3c))) ;; <
+                  (then
+                    (call $regexp_parse_error (local.get $parser)
+                      (call $new_static_string (i32.const {{ data(str="Invalid group") }}) (i32.const {{ data_length() }})))
+                  )
+                )
+                (local.set $name (call $regexp_parse_group_name (local.get $parser)))
+                (if (i32.or
+                      (ref.is_null (local.get $name))
+                      (i32.eqz (call $string_length (local.get $name))))
+                  (then
+                    (call $regexp_parse_error (local.get $parser)
+                      (call $new_static_string (i32.const {{ data(str="Invalid capture group name") }}) (i32.const {{ data_length() }})))
+                  )
+                )
+                (local.set $index (call $regexp_next_group (local.get $parser)))
+              )
+            )
+          )
+          (else
+            (local.set $index (call $regexp_next_group (local.get $parser)))
+          )
+        )
+
+        (local.set $body (call $regexp_parse_disjunction (local.get $parser)))
+        (if (i32.eqz (call $regexp_eat (local.get $parser) (i32.const 0x29))) ;; )
+          (then
+            (call $regexp_parse_error (local.get $parser)
+              (call $new_static_string (i32.const {{ data(str="Unterminated group") }}) (i32.const {{ data_length() }})))
+          )
+        )
+        (if (i32.lt_s (local.get $index) (i32.const 0))
+          (then (return (local.get $body)))
+        )
+        (local.set $code
+          (call $regexp_code2 (i32.const {{ op_save }}) (i32.shl (local.get $index) (i32.const 1))))
+        (call $i32_buffer_append (local.get $code) (local.get $body))
+        (call $i32_buffer_push2 (local.get $code)
+          (i32.const {{ op_save }})
+          (i32.add (i32.shl (local.get $index) (i32.const 1)) (i32.const 1)))
+        (return (local.get $code))
+      )
+    )
+
+    (if (i32.eq (local.get $char) (i32.const 0x5b)) ;; [
+      (then
+        (call $regexp_skip (local.get $parser) (i32.const 1))
+        (return (call $regexp_parse_class (local.get $parser)))
+      )
+    )
+
+    (if (i32.eq (local.get $char) (i32.const 0x5c)) ;; \
+      (then
+        (call $regexp_skip (local.get $parser) (i32.const 1))
+        (return (call $regexp_parse_atom_escape (local.get $parser)))
+      )
+    )
+
+    (if (i32.or
+          (i32.eq (local.get $char) (i32.const 0x2a)) ;; *
+          (i32.or
+            (i32.eq (local.get $char) (i32.const 0x2b)) ;; +
+            (i32.eq (local.get $char) (i32.const 0x3f)))) ;; ?
+      (then
+        (call $regexp_parse_error (local.get $parser)
+          (call $new_static_string (i32.const {{ data(str="Nothing to repeat") }}) (i32.const {{ data_length() }})))
+      )
+    )
+
+    (if (i32.eq (local.get $char) (i32.const 0x7b)) ;; {
+      (then
+        (if (i32.or
+              (call $regexp_parser_unicode (local.get $parser))
+              (call $regexp_parse_braced_quantifier (local.get $parser)))
+          (then
+            (call $regexp_parse_error (local.get $parser)
+              (call $new_static_string (i32.const {{ data(str="Nothing to repeat") }}) (i32.const {{ data_length() }})))
+          )
+        )
+      )
+    )
+
+    (if (i32.and
+          (call $regexp_parser_unicode (local.get $parser))
+          (i32.or
+            (i32.eq (local.get $char) (i32.const 0x5d)) ;; ]
+            (i32.eq (local.get $char) (i32.const 0x7d)))) ;; }
+      (then
+        (call $regexp_parse_error (local.get $parser)
+          (call $new_static_string (i32.const {{ data(str="Lone quantifier brackets") }}) (i32.const {{ data_length() }})))
+      )
+    )
+
+    (call $regexp_advance (local.get $parser))
+    (call $regexp_char_code (local.get $parser) (local.get $char))
+  )
+
+  (func $regexp_char_code (param $parser (ref $RegExpParser)) (param $char i32) (result (ref $I32Buffer))
+    (if (call $regexp_parser_ignore_case (local.get $parser))
+      (then
+        (return (call $regexp_code2 (i32.const {{ op_char_i }}) (call $regexp_canonicalize (local.get $char))))
+      )
+    )
+    (call $regexp_code2 (i32.const {{ op_char }}) (local.get $char))
+  )
+
+  (func $regexp_class_code
+    (param $parser (ref $RegExpParser))
+    (param $negated i32)
+    (param $ranges (ref $I32Buffer))
+    (result (ref $I32Buffer))
+    (local $code (ref $I32Buffer))
+
+    (local.set $code (call $new_i32_buffer))
+    (call $i32_buffer_push3 (local.get $code)
+      (select
+        (i32.const {{ op_class_i }})
+        (i32.const {{ op_class }})
+        (call $regexp_parser_ignore_case (local.get $parser)))
+      (local.get $negated)
+      (i32.shr_u (call $i32_buffer_size (local.get $ranges)) (i32.const 1)))
+    (call $i32_buffer_append (local.get $code) (local.get $ranges))
+    (local.get $code)
+  )
+
+  (func $regexp_backref_code (param $parser (ref $RegExpParser)) (param $group i32) (result (ref $I32Buffer))
+    (call $regexp_code2
+      (select
+        (i32.const {{ op_backref_i }})
+        (i32.const {{ op_backref }})
+        (call $regexp_parser_ignore_case (local.get $parser)))
+      (local.get $group))
+  )
+
+  (func $regexp_parse_atom_escape (param $parser (ref $RegExpParser)) (result (ref $I32Buffer))
+    (local $char i32)
+    (local $start i32)
+    (local $group i32)
+    (local $name (ref null $String))
+    (local $names (ref $AnyrefArray))
+    (local $atom anyref)
+
+    (local.set $char (call $regexp_peek_at (local.get $parser) (i32.const 0)))
+    (if (i32.eq (local.get $char) (i32.const -1))
+      (then
+        (call $regexp_parse_error (local.get $parser)
+          (call $new_static_string (i32.const {{ data(str="\\ at end of pattern") }}) (i32.const {{ data_length() }})))
+      )
+    )
+    (local.set $names (struct.get $RegExpParser $group_names (local.get $parser)))
+
+    ;; backreferences
+    (if (i32.lt_u (i32.sub (local.get $char) (i32.const 0x31)) (i32.const 9))
+      (then
+        (local.set $start (struct.get $RegExpParser $position (local.get $parser)))
+        (local.set $group (call $regexp_parse_decimal (local.get $parser)))
+        (if (i32.lt_u (local.get $group) (array.len (local.get $names)))
+          (then (return (call $regexp_backref_code (local.get $parser) (local.get $group))))
+        )
+        (if (call $regexp_parser_unicode (local.get $parser))
+          (then
+            (call $regexp_parse_error (local.get $parser)
+              (call $new_static_string (i32.const {{ data(str="Invalid escape") }}) (i32.const {{ data_length() }})))
+          )
+        )
+        ;; without the `u` flag it's a legacy octal escape or an identity
+        ;; escape for 8 and 9
+        (struct.set $RegExpParser $position (local.get $parser) (local.get $start))
+        (if (i32.ge_u (local.get $char) (i32.const 0x38))
+          (then
+            (call $regexp_skip (local.get $parser) (i32.const 1))
+            (return (call $regexp_char_code (local.get $parser) (local.get $char)))
+          )
+        )
+        (return
+          (call $regexp_char_code (local.get $parser) (call $regexp_parse_legacy_octal (local.get $parser))))
+      )
+    )
+
+    (if (i32.and
+          (i32.eq (local.get $char) (i32.const 0x6b)) ;; k
+          (i32.or
+            (call $regexp_has_group_names (local.get $names))
+            (call $regexp_parser_unicode (local.get $parser))))
+      (then
+        (call $regexp_skip (local.get $parser) (i32.const 1))
+        (if (call $regexp_eat (local.get $parser) (i32.const 0x3c)) ;; <
+          (then (local.set $name (call $regexp_parse_group_name (local.get $parser))))
+        )
+        (if (ref.is_null (local.get $name))
+          (then
+            (call $regexp_parse_error (local.get $parser)
+              (call $new_static_string (i32.const {{ data(str="Invalid named reference") }}) (i32.const {{ data_length() }})))
+          )
+        )
+        (local.set $group (call $regexp_group_index (local.get $names) (local.get $name)))
+        (if (i32.lt_s (local.get $group) (i32.const 0))
+          (then
+            (call $regexp_parse_error (local.get $parser)
+              (call $new_static_string (i32.const {{ data(str="Invalid named capture referenced") }}) (i32.const {{ data_length() }})))
+          )
+        )
+        (return (call $regexp_backref_code (local.get $parser) (local.get $group)))
+      )
+    )
+
+    (local.set $atom (call $regexp_parse_class_escape (local.get $parser)))
+    (if (ref.test (ref i31) (local.get $atom))
+      (then
+        (return
+          (call $regexp_char_code (local.get $parser) (i31.get_u (ref.cast (ref i31) (local.get $atom)))))
+      )
+    )
+    (call $regexp_class_code (local.get $parser) (i32.const 0) (ref.cast (ref $I32Buffer) (local.get $atom)))
+  )
+
+  ;; the index of a named group or -1
+  (func $regexp_group_index (param $names (ref $AnyrefArray)) (param $name anyref) (result i32)
+    (local $i i32)
+    (local $group_name anyref)
+
+    (block $done (loop $names
+      (br_if $done (i32.ge_u (local.get $i) (array.len (local.get $names))))
+      (local.set $group_name (array.get $AnyrefArray (local.get $names) (local.get $i)))
+      (if (i32.eqz (ref.is_null (local.get $group_name)))
+        (then
+          (if (call $string_equals (local.get $group_name) (local.get $name))
+            (then (return (local.get $i)))
+          )
+        )
+      )
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $names)
+    ))
+    (i32.const -1)
+  )
+
+  (func $regexp_parse_legacy_octal (param $parser (ref $RegExpParser)) (result i32)
+    (local $value i32)
+    (local $digits i32)
+    (local $digit i32)
+
+    (block $done (loop $digits
+      (local.set $digit (i32.sub (call $regexp_peek_at (local.get $parser) (i32.const 0)) (i32.const 0x30)))
+      (br_if $done (i32.ge_u (local.get $digit) (i32.const 8)))
+      (br_if $done (i32.eq (local.get $digits) (i32.const 3)))
+      (br_if $done
+        (i32.gt_u
+          (i32.add (i32.shl (local.get $value) (i32.const 3)) (local.get $digit))
+          (i32.const 0xff)))
+      (local.set $value (i32.add (i32.shl (local.get $value) (i32.const 3)) (local.get $digit)))
+      (local.set $digits (i32.add (local.get $digits) (i32.const 1)))
+      (call $regexp_skip (local.get $parser) (i32.const 1))
+      (br $digits)
+    ))
+    (local.get $value)
+  )
+
+  ;; the value of a hex digit or -1
+  (func $hex_digit_value (param $char i32) (result i32)
+    (if (i32.lt_u (i32.sub (local.get $char) (i32.const 0x30)) (i32.const 10))
+      (then (return (i32.sub (local.get $char) (i32.const 0x30))))
+    )
+    (local.set $char (i32.or (local.get $char) (i32.const 0x20)))
+    (if (i32.lt_u (i32.sub (local.get $char) (i32.const 0x61)) (i32.const 6))
+      (then (return (i32.sub (local.get $char) (i32.const 0x57))))
+    )
+    (i32.const -1)
+  )
+
+  ;; exactly $count hex digits or -1, without moving if they're not there
+  (func $regexp_parse_hex_digits (param $parser (ref $RegExpParser)) (param $count i32) (result i32)
+    (local $value i32)
+    (local $digit i32)
+    (local $i i32)
+
+    (block $done (loop $digits
+      (br_if $done (i32.ge_u (local.get $i) (local.get $count)))
+      (local.set $digit (call $hex_digit_value (call $regexp_peek_at (local.get $parser) (local.get $i))))
+      (if (i32.lt_s (local.get $digit) (i32.const 0))
+        (then (return (i32.const -1)))
+      )
+      (local.set $value (i32.add (i32.shl (local.get $value) (i32.const 4)) (local.get $digit)))
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $digits)
+    ))
+    (call $regexp_skip (local.get $parser) (local.get $count))
+    (local.get $value)
+  )
+
+  ;; \uXXXX, joining surrogate pairs, or \u{X...} with the `u` flag, returns -1
+  ;; for invalid escapes
+  (func $regexp_parse_unicode_escape (param $parser (ref $RegExpParser)) (result i32)
+    (local $value i32)
+    (local $low i32)
+    (local $digit i32)
+    (local $start i32)
+
+    (if (call $regexp_parser_unicode (local.get $parser))
+      (then
+        (if (call $regexp_eat (local.get $parser) (i32.const 0x7b)) ;; {
+          (then
+            (local.set $start (struct.get $RegExpParser $position (local.get $parser)))
+            (block $done (loop $digits
+              (local.set $digit (call $hex_digit_value (call $regexp_peek_at (local.get $parser) (i32.const 0))))
+              (br_if $done (i32.lt_s (local.get $digit) (i32.const 0)))
+              ;; anything past 0x10ffff is invalid, so it can stop growing there
+              (if (i32.le_u (local.get $value) (i32.const 0x10ffff))
+                (then
+                  (local.set $value (i32.add (i32.shl (local.get $value) (i32.const 4)) (local.get $digit)))
+                )
+              )
+              (call $regexp_skip (local.get $parser) (i32.const 1))
+              (br $digits)
+            ))
+            (if (i32.or
+                  (i32.eq (struct.get $RegExpParser $position (local.get $parser)) (local.get $start))
+                  (i32.gt_u (local.get $value) (i32.const 0x10ffff)))
+              (then (return (i32.const -1)))
+            )
+            (if (i32.eqz (call $regexp_eat (local.get $parser) (i32.const 0x7d))) ;; }
+              (then (return (i32.const -1)))
+            )
+            (return (local.get $value))
+          )
+        )
+      )
+    )
+
+    (local.set $value (call $regexp_parse_hex_digits (local.get $parser) (i32.const 4)))
+    (if (i32.and
+          (i32.lt_u (i32.sub (local.get $value) (i32.const 0xd800)) (i32.const 0x400))
+          (i32.and
+            (i32.eq (call $regexp_peek_at (local.get $parser) (i32.const 0)) (i32.const 0x5c)) ;; \
+            (i32.eq (call $regexp_peek_at (local.get $parser) (i32.const 1)) (i32.const 0x75)))) ;; u
+      (then
+        (local.set $start (struct.get $RegExpParser $position (local.get $parser)))
+        (call $regexp_skip (local.get $parser) (i32.const 2))
+        (local.set $low (call $regexp_parse_hex_digits (local.get $parser) (i32.const 4)))
+        (if (i32.lt_u (i32.sub (local.get $low) (i32.const 0xdc00)) (i32.const 0x400))
+          (then
+            (return
+              (i32.add
+                (i32.const 0x10000)
+                (i32.add
+                  (i32.shl (i32.sub (local.get $value) (i32.const 0xd800)) (i32.const 10))
+                  (i32.sub (local.get $low) (i32.const 0xdc00)))))
+          )
+        )
+        (struct.set $RegExpParser $position (local.get $parser) (local.get $start))
+      )
+    )
+    (local.get $value)
+  )
+
+  ;; ^$\.*+?()[]{}|/
+  (func $regexp_is_syntax_character (param $char i32) (result i32)
+    (block $yes
+      (br_if $yes (i32.eq (local.get $char) (i32.const 0x5e)))
+      (br_if $yes (i32.eq (local.get $char) (i32.const 0x24)))
+      (br_if $yes (i32.eq (local.get $char) (i32.const 0x5c)))
+      (br_if $yes (i32.eq (local.get $char) (i32.const 0x2e)))
+      (br_if $yes (i32.eq (local.get $char) (i32.const 0x2a)))
+      (br_if $yes (i32.eq (local.get $char) (i32.const 0x2b)))
+      (br_if $yes (i32.eq (local.get $char) (i32.const 0x3f)))
+      (br_if $yes (i32.eq (local.get $char) (i32.const 0x28)))
+      (br_if $yes (i32.eq (local.get $char) (i32.const 0x29)))
+      (br_if $yes (i32.eq (local.get $char) (i32.const 0x5b)))
+      (br_if $yes (i32.eq (local.get $char) (i32.const 0x5d)))
+      (br_if $yes (i32.eq (local.get $char) (i32.const 0x7b)))
+      (br_if $yes (i32.eq (local.get $char) (i32.const 0x7d)))
+      (br_if $yes (i32.eq (local.get $char) (i32.const 0x7c)))
+      (br_if $yes (i32.eq (local.get $char) (i32.const 0x2f)))
+      (return (i32.const 0))
+    )
+    (i32.const 1)
+  )
+
+  ;; the ranges of \d, \w and \s, or of \D, \W and \S
+  (func $regexp_class_escape_ranges (param $char i32) (result (ref $I32Buffer))
+    (local $ranges (ref $I32Buffer))
+    (local.set $ranges (call $new_i32_buffer))
+
+    (if (i32.eq (i32.or (local.get $char) (i32.const 0x20)) (i32.const 0x64)) ;; d
+      (then
+        (call $i32_buffer_push2 (local.get $ranges) (i32.const 0x30) (i32.const 0x39))
+      )
+    )
+    (if (i32.eq (i32.or (local.get $char) (i32.const 0x20)) (i32.const 0x77)) ;; w
+      (then
+        (call $i32_buffer_push2 (local.get $ranges) (i32.const 0x30) (i32.const 0x39))
+        (call $i32_buffer_push2 (local.get $ranges) (i32.const 0x41) (i32.const 0x5a))
+        (call $i32_buffer_push2 (local.get $ranges) (i32.const 0x5f) (i32.const 0x5f))
+        (call $i32_buffer_push2 (local.get $ranges) (i32.const 0x61) (i32.const 0x7a))
+      )
+    )
+    (if (i32.eq (i32.or (local.get $char) (i32.const 0x20)) (i32.const 0x73)) ;; s
+      (then
+        (call $i32_buffer_push2 (local.get $ranges) (i32.const 0x09) (i32.const 0x0d))
+        (call $i32_buffer_push2 (local.get $ranges) (i32.const 0x20) (i32.const 0x20))
+        (call $i32_buffer_push2 (local.get $ranges) (i32.const 0xa0) (i32.const 0xa0))
+        (call $i32_buffer_push2 (local.get $ranges) (i32.const 0x1680) (i32.const 0x1680))
+        (call $i32_buffer_push2 (local.get $ranges) (i32.const 0x2000) (i32.const 0x200a))
+        (call $i32_buffer_push2 (local.get $ranges) (i32.const 0x2028) (i32.const 0x2029))
+        (call $i32_buffer_push2 (local.get $ranges) (i32.const 0x202f) (i32.const 0x202f))
+        (call $i32_buffer_push2 (local.get $ranges) (i32.const 0x205f) (i32.const 0x205f))
+        (call $i32_buffer_push2 (local.get $ranges) (i32.const 0x3000) (i32.const 0x3000))
+        (call $i32_buffer_push2 (local.get $ranges) (i32.const 0xfeff) (i32.const 0xfeff))
+      )
+    )
+
+    ;; the uppercase letters are the complements
+    (if (i32.eqz (i32.and (local.get $char) (i32.const 0x20)))
+      (then (return (call $regexp_complement (local.get $ranges))))
+    )
+    (local.get $ranges)
+  )
+
+  ;; all the code points not in the sorted ranges
+  (func $regexp_complement (param $ranges (ref $I32Buffer)) (result (ref $I32Buffer))
+    (local $result (ref $I32Buffer))
+    (local $next i32)
+    (local $from i32)
+    (local $i i32)
+
+    (local.set $result (call $new_i32_buffer))
+    (block $done (loop $ranges
+      (br_if $done (i32.ge_u (local.get $i) (call $i32_buffer_size (local.get $ranges))))
+      (local.set $from (call $i32_buffer_get (local.get $ranges) (local.get $i)))
+      (if (i32.gt_u (local.get $from) (local.get $next))
+        (then
+          (call $i32_buffer_push2 (local.get $result)
+            (local.get $next)
+            (i32.sub (local.get $from) (i32.const 1)))
+        )
+      )
+      (local.set $next
+        (i32.add
+          (call $i32_buffer_get (local.get $ranges) (i32.add (local.get $i) (i32.const 1)))
+          (i32.const 1)))
+      (local.set $i (i32.add (local.get $i) (i32.const 2)))
+      (br $ranges)
+    ))
+    (if (i32.le_u (local.get $next) (i32.const 0x10ffff))
+      (then
+        (call $i32_buffer_push2 (local.get $result) (local.get $next) (i32.const 0x10ffff))
+      )
+    )
+    (local.get $result)
+  )
+
+  ;; sorts the ranges and merges the overlapping and adjacent ones
+  (func $regexp_normalize (param $ranges (ref $I32Buffer)) (result (ref $I32Buffer))
+    (local $data (ref $I32Array))
+    (local $count i32)
+    (local $result (ref $I32Buffer))
+    (local $from i32)
+    (local $to i32)
+    (local $last i32)
+    (local $i i32)
+    (local $j i32)
+
+    (local.set $data (struct.get $I32Buffer $data (local.get $ranges)))
+    (local.set $count (i32.shr_u (call $i32_buffer_size (local.get $ranges)) (i32.const 1)))
+
+    ;; insertion sort, classes don't have many ranges
+    (local.set $i (i32.const 1))
+    (block $sorted (loop $sort
+      (br_if $sorted (i32.ge_u (local.get $i) (local.get $count)))
+      (local.set $from (array.get $I32Array (local.get $data) (i32.shl (local.get $i) (i32.const 1))))
+      (local.set $to
+        (array.get $I32Array (local.get $data) (i32.add (i32.shl (local.get $i) (i32.const 1)) (i32.const 1))))
+      (local.set $j (local.get $i))
+      (block $placed (loop $shift
+        (br_if $placed (i32.eqz (local.get $j)))
+        (br_if $placed
+          (i32.le_u
+            (array.get $I32Array (local.get $data) (i32.shl (i32.sub (local.get $j) (i32.const 1)) (i32.const 1)))
+            (local.get $from)))
+        (array.set $I32Array (local.get $data)
+          (i32.shl (local.get $j) (i32.const 1))
+          (array.get $I32Array (local.get $data) (i32.shl (i32.sub (local.get $j) (i32.const 1)) (i32.const 1))))
+        (array.set $I32Array (local.get $data)
+          (i32.add (i32.shl (local.get $j) (i32.const 1)) (i32.const 1))
+          (array.get $I32Array (local.get $data) (i32.sub (i32.shl (local.get $j) (i32.const 1)) (i32.const 1))))
+        (local.set $j (i32.sub (local.get $j) (i32.const 1)))
+        (br $shift)
+      ))
+      (array.set $I32Array (local.get $data) (i32.shl (local.get $j) (i32.const 1)) (local.get $from))
+      (array.set $I32Array (local.get $data) (i32.add (i32.shl (local.get $j) (i32.const 1)) (i32.const 1)) (local.get $to))
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $sort)
+    ))
+
+    (local.set $result (call $new_i32_buffer))
+    (local.set $i (i32.const 0))
+    (block $done (loop $merge
+      (br_if $done (i32.ge_u (local.get $i) (local.get $count)))
+      (local.set $from (array.get $I32Array (local.get $data) (i32.shl (local.get $i) (i32.const 1))))
+      (local.set $to
+        (array.get $I32Array (local.get $data) (i32.add (i32.shl (local.get $i) (i32.const 1)) (i32.const 1))))
+      (local.set $last (i32.sub (call $i32_buffer_size (local.get $result)) (i32.const 1)))
+      (if (i32.and
+            (i32.gt_s (local.get $last) (i32.const 0))
+            (i32.le_u
+              (local.get $from)
+              (i32.add (call $i32_buffer_get (local.get $result) (local.get $last)) (i32.const 1))))
+        (then
+          (if (i32.gt_u (local.get $to) (call $i32_buffer_get (local.get $result) (local.get $last)))
+            (then
+              (array.set $I32Array
+                (struct.get $I32Buffer $data (local.get $result))
+                (local.get $last)
+                (local.get $to))
+            )
+          )
+        )
+        (else
+          (call $i32_buffer_push2 (local.get $result) (local.get $from) (local.get $to))
+        )
+      )
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $merge)
+    ))
+    (local.get $result)
+  )
+
+  ;; The escapes allowed both in and outside of character classes, the
+  ;; backslash is already consumed. Returns an i31 for a single code point or
+  ;; an $I32Buffer of ranges for class escapes like \d
+  (func $regexp_parse_class_escape (param $parser (ref $RegExpParser)) (result anyref)
+    (local $char i32)
+    (local $next i32)
+    (local $value i32)
+    (local $unicode i32)
+
+    (local.set $unicode (call $regexp_parser_unicode (local.get $parser)))
+    (local.set $char (call $regexp_peek (local.get $parser)))
+    (if (i32.eq (local.get $char) (i32.const -1))
+      (then
+        (call $regexp_parse_error (local.get $parser)
+          (call $new_static_string (i32.const {{ data(str="\\ at end of pattern") }}) (i32.const {{ data_length() }})))
+      )
+    )
+    (call $regexp_advance (local.get $parser))
+    (local.set $next (call $regexp_peek_at (local.get $parser) (i32.const 0)))
+
+    (block $found
+      ;; d, D, w, W, s and S
+      (if (i32.or
+            (i32.eq (i32.or (local.get $char) (i32.const 0x20)) (i32.const 0x64))
+            (i32.or
+              (i32.eq (i32.or (local.get $char) (i32.const 0x20)) (i32.const 0x77))
+              (i32.eq (i32.or (local.get $char) (i32.const 0x20)) (i32.const 0x73))))
+        (then (return (call $regexp_class_escape_ranges (local.get $char))))
+      )
+
+      (local.set $value (i32.const 0x09))
+      (br_if $found (i32.eq (local.get $char) (i32.const 0x74))) ;; t
+      (local.set $value (i32.const 0x0a))
+      (br_if $found (i32.eq (local.get $char) (i32.const 0x6e))) ;; n
+      (local.set $value (i32.const 0x0b))
+      (br_if $found (i32.eq (local.get $char) (i32.const 0x76))) ;; v
+      (local.set $value (i32.const 0x0c))
+      (br_if $found (i32.eq (local.get $char) (i32.const 0x66))) ;; f
+      (local.set $value (i32.const 0x0d))
+      (br_if $found (i32.eq (local.get $char) (i32.const 0x72))) ;; r
+
+      ;; \0 not followed by a digit
+      (local.set $value (i32.const 0))
+      (br_if $found
+        (i32.and
+          (i32.eq (local.get $char) (i32.const 0x30))
+          (i32.ge_u (i32.sub (local.get $next) (i32.const 0x30)) (i32.const 10))))
+
+      (if (i32.and
+            (i32.lt_u (i32.sub (local.get $char) (i32.const 0x30)) (i32.const 8))
+            (i32.eqz (local.get $unicode)))
+        (then
+          (call $regexp_skip (local.get $parser) (i32.const -1))
+          (local.set $value (call $regexp_parse_legacy_octal (local.get $parser)))
+          (br $found)
+        )
+      )
+
+      (if (i32.eq (local.get $char) (i32.const 0x63)) ;; c
+        (then
+          (if (i32.lt_u (i32.sub (i32.or (local.get $next) (i32.const 0x20)) (i32.const 0x61)) (i32.const 26))
+            (then
+              (call $regexp_skip (local.get $parser) (i32.const 1))
+              (local.set $value (i32.rem_u (local.get $next) (i32.const 32)))
+              (br $found)
+            )
+          )
+          (if (local.get $unicode)
+            (then
+              (call $regexp_parse_error (local.get $parser)
+                (call $new_static_string (i32.const {{ data(str="Invalid unicode escape") }}) (i32.const {{ data_length() }})))
+            )
+          )
+          ;; a lone \c is a backslash followed by c
+          (call $regexp_skip (local.get $parser) (i32.const -1))
+          (local.set $value (i32.const 0x5c))
+          (br $found)
+        )
+      )
+
+      (if (i32.eq (local.get $char) (i32.const 0x78)) ;; x
+        (then
+          (local.set $value (call $regexp_parse_hex_digits (local.get $parser) (i32.const 2)))
+          (br_if $found (i32.ge_s (local.get $value) (i32.const 0)))
+          (if (local.get $unicode)
+            (then
+              (call $regexp_parse_error (local.get $parser)
+                (call $new_static_string (i32.const {{ data(str="Invalid escape") }}) (i32.const {{ data_length() }})))
+            )
+          )
+          (local.set $value (local.get $char))
+          (br $found)
+        )
+      )
+
+      (if (i32.eq (local.get $char) (i32.const 0x75)) ;; u
+        (then
+          (local.set $value (call $regexp_parse_unicode_escape (local.get $parser)))
+          (br_if $found (i32.ge_s (local.get $value) (i32.const 0)))
+          (if (local.get $unicode)
+            (then
+              (call $regexp_parse_error (local.get $parser)
+                (call $new_static_string (i32.const {{ data(str="Invalid Unicode escape") }}) (i32.const {{ data_length() }})))
+            )
+          )
+          (local.set $value (local.get $char))
+          (br $found)
+        )
+      )
+
+      (local.set $value (local.get $char))
+      (br_if $found (i32.eqz (local.get $unicode)))
+      (if (i32.eq (i32.or (local.get $char) (i32.const 0x20)) (i32.const 0x70)) ;; p or P
+        (then
+          (call $regexp_parse_error (local.get $parser)
+            (call $new_static_string (i32.const {{ data(str="Unicode property escapes are not supported") }}) (i32.const {{ data_length() }})))
+        )
+      )
+      (br_if $found (call $regexp_is_syntax_character (local.get $char)))
+      (br_if $found (i32.eq (local.get $char) (i32.const 0x2d))) ;; -
+      (call $regexp_parse_error (local.get $parser)
+        (call $new_static_string (i32.const {{ data(str="Invalid escape") }}) (i32.const {{ data_length() }})))
+    )
+
+    (ref.i31 (local.get $value))
+  )
+
+  (func $regexp_parse_class_atom (param $parser (ref $RegExpParser)) (result anyref)
+    (local $char i32)
+
+    (local.set $char (call $regexp_peek (local.get $parser)))
+    (if (i32.eq (local.get $char) (i32.const -1))
+      (then
+        (call $regexp_parse_error (local.get $parser)
+          (call $new_static_string (i32.const {{ data(str="Unterminated character class") }}) (i32.const {{ data_length() }})))
+      )
+    )
+
+    (if (i32.eq (local.get $char) (i32.const 0x5c)) ;; \
+      (then
+        (call $regexp_skip (local.get $parser) (i32.const 1))
+        (local.set $char (call $regexp_peek_at (local.get $parser) (i32.const 0)))
+        ;; \b is a backspace in a class
+        (if (i32.eq (local.get $char) (i32.const 0x62))
+          (then
+            (call $regexp_skip (local.get $parser) (i32.const 1))
+            (return (ref.i31 (i32.const 0x08)))
+          )
+        )
+        (if (i32.and
+              (i32.eq (local.get $char) (i32.const 0x2d)) ;; -
+              (call $regexp_parser_unicode (local.get $parser)))
+          (then
+            (call $regexp_skip (local.get $parser) (i32.const 1))
+            (return (ref.i31 (i32.const 0x2d)))
+          )
+        )
+        (return (call $regexp_parse_class_escape (local.get $parser)))
+      )
+    )
+
+    (call $regexp_advance (local.get $parser))
+    (ref.i31 (local.get $char))
+  )
+
+  (func $regexp_add_class_atom (param $ranges (ref $I32Buffer)) (param $atom anyref)
+    (local $char i32)
+    (if (ref.test (ref i31) (local.get $atom))
+      (then
+        (local.set $char (i31.get_u (ref.cast (ref i31) (local.get $atom))))
+        (call $i32_buffer_push2 (local.get $ranges) (local.get $char) (local.get $char))
+        (return)
+      )
+    )
+    (call $i32_buffer_append (local.get $ranges) (ref.cast (ref $I32Buffer) (local.get $atom)))
+  )
+
+  (func $regexp_parse_class (param $parser (ref $RegExpParser)) (result (ref $I32Buffer))
+    (local $negated i32)
+    (local $ranges (ref $I32Buffer))
+    (local $from anyref)
+    (local $to anyref)
+    (local $next i32)
+
+    (local.set $negated (call $regexp_eat (local.get $parser) (i32.const 0x5e))) ;; ^
+    (local.set $ranges (call $new_i32_buffer))
+
+    (block $done (loop $atoms
+      (br_if $done (call $regexp_eat (local.get $parser) (i32.const 0x5d))) ;; ]
+      (local.set $from (call $regexp_parse_class_atom (local.get $parser)))
+
+      (local.set $next (call $regexp_peek_at (local.get $parser) (i32.const 1)))
+      (if (i32.and
+            (i32.eq (call $regexp_peek_at (local.get $parser) (i32.const 0)) (i32.const 0x2d)) ;; -
+            (i32.and
+              (i32.ne (local.get $next) (i32.const 0x5d)) ;; ]
+              (i32.ne (local.get $next) (i32.const -1))))
+        (then
+          (call $regexp_skip (local.get $parser) (i32.const 1))
+          (local.set $to (call $regexp_parse_class_atom (local.get $parser)))
+          (if (i32.and
+                (ref.test (ref i31) (local.get $from))
+                (ref.test (ref i31) (local.get $to)))
+            (then
+              (if (i32.gt_u
+                    (i31.get_u (ref.cast (ref i31) (local.get $from)))
+                    (i31.get_u (ref.cast (ref i31) (local.get $to))))
+                (then
+                  (call $regexp_parse_error (local.get $parser)
+                    (call $new_static_string (i32.const {{ data(str="Range out of order in character class") }}) (i32.const {{ data_length() }})))
+                )
+              )
+              (call $i32_buffer_push2 (local.get $ranges)
+                (i31.get_u (ref.cast (ref i31) (local.get $from)))
+                (i31.get_u (ref.cast (ref i31) (local.get $to))))
+            )
+            (else
+              (if (call $regexp_parser_unicode (local.get $parser))
+                (then
+                  (call $regexp_parse_error (local.get $parser)
+                    (call $new_static_string (i32.const {{ data(str="Invalid character class") }}) (i32.const {{ data_length() }})))
+                )
+              )
+              ;; without the `u` flag [\d-x] is a digit, - or x
+              (call $regexp_add_class_atom (local.get $ranges) (local.get $from))
+              (call $i32_buffer_push2 (local.get $ranges) (i32.const 0x2d) (i32.const 0x2d))
+              (call $regexp_add_class_atom (local.get $ranges) (local.get $to))
+            )
+          )
+          (br $atoms)
+        )
+      )
+
+      (call $regexp_add_class_atom (local.get $ranges) (local.get $from))
+      (br $atoms)
+    ))
+
+    (call $regexp_class_code
+      (local.get $parser)
+      (local.get $negated)
+      (call $regexp_normalize (local.get $ranges)))
+  )
+
+  (func $regexp_parse_flags (param $text anyref) (result i32)
+    (local $flags i32)
+    (local $flag i32)
+    (local $byte i32)
+    (local $i i32)
+
+    (block $done (loop $flags
+      (br_if $done (i32.ge_u (local.get $i) (call $string_length (local.get $text))))
+      (local.set $byte (call $string_byte_at (local.get $text) (local.get $i)))
+      (local.set $flag (i32.const 0))
+      (if (i32.eq (local.get $byte) (i32.const 0x67)) ;; g
+        (then (local.set $flag (i32.const {{ flag_global }})))
+      )
+      (if (i32.eq (local.get $byte) (i32.const 0x69)) ;; i
+        (then (local.set $flag (i32.const {{ flag_ignore_case }})))
+      )
+      (if (i32.eq (local.get $byte) (i32.const 0x6d)) ;; m
+        (then (local.set $flag (i32.const {{ flag_multiline }})))
+      )
+      (if (i32.eq (local.get $byte) (i32.const 0x73)) ;; s
+        (then (local.set $flag (i32.const {{ flag_dot_all }})))
+      )
+      (if (i32.eq (local.get $byte) (i32.const 0x75)) ;; u
+        (then (local.set $flag (i32.const {{ flag_unicode }})))
+      )
+      (if (i32.eq (local.get $byte) (i32.const 0x79)) ;; y
+        (then (local.set $flag (i32.const {{ flag_sticky }})))
+      )
+
+      (if (i32.or
+            (i32.eqz (local.get $flag))
+            (i32.and (local.get $flags) (local.get $flag)))
+        (then
+          (call $throw_syntax_error
+            (call $concat_strings
+              (call $concat_strings
+                (call $new_static_string (i32.const {{ data(str="Invalid flags supplied to RegExp constructor '") }}) (i32.const {{ data_length() }}))
+                (local.get $text))
+              (call $new_static_string (i32.const {{ data(str="'") }}) (i32.const {{ data_length() }}))))
+        )
+      )
+      (local.set $flags (i32.or (local.get $flags) (local.get $flag)))
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $flags)
+    ))
+    (local.get $flags)
+  )
+
+  ;; compiles a pattern only known at runtime, like src/regexp.rs does for
+  ;; literals
+  (func $regexp_compile (param $source anyref) (param $flags_text anyref) (result (ref $RegExp))
+    (local $parser (ref $RegExpParser))
+    (local $code (ref $I32Buffer))
+    (local $program (ref $I32Buffer))
+    (local $names (ref $AnyrefArray))
+
+    (local.set $parser
+      (struct.new $RegExpParser
+        (local.get $source)
+        (local.get $flags_text)
+        (call $string_data (local.get $source))
+        (call $string_length (local.get $source))
+        (i32.const 0)
+        (call $regexp_parse_flags (local.get $flags_text))
+        (i32.const 1)
+        (i32.const 0)
+        (array.new_fixed $AnyrefArray 0)
+        (i32.const 0)
+        (i32.const 0)))
+    (local.set $names (call $regexp_scan_groups (local.get $parser)))
+    (struct.set $RegExpParser $group_names (local.get $parser) (local.get $names))
+
+    (local.set $code (call $regexp_parse_disjunction (local.get $parser)))
+    ;; the only thing that can stop a disjunction early
+    (if (i32.lt_u
+          (struct.get $RegExpParser $position (local.get $parser))
+          (struct.get $RegExpParser $length (local.get $parser)))
+      (then
+        (call $regexp_parse_error (local.get $parser)
+          (call $new_static_string (i32.const {{ data(str="Unmatched ')'") }}) (i32.const {{ data_length() }})))
+      )
+    )
+
+    (local.set $program (call $new_i32_buffer))
+    (call $i32_buffer_push2 (local.get $program)
+      (struct.get $RegExpParser $group_count (local.get $parser))
+      (struct.get $RegExpParser $mark_count (local.get $parser)))
+    (call $i32_buffer_push2 (local.get $program) (i32.const {{ op_save }}) (i32.const 0))
+    (call $i32_buffer_append (local.get $program) (local.get $code))
+    (call $i32_buffer_push3 (local.get $program) (i32.const {{ op_save }}) (i32.const 1) (i32.const {{ op_match }}))
+    (call $regexp_check_size (local.get $parser) (call $i32_buffer_size (local.get $program)))
+
+    (call $new_regexp
+      (call $regexp_escape_source (local.get $source))
+      (struct.get $RegExpParser $flags (local.get $parser))
+      (call $i32_buffer_to_array (local.get $program))
+      (if (result (ref null $AnyrefArray)) (call $regexp_has_group_names (local.get $names))
+        (then (local.get $names))
+        (else (ref.null $AnyrefArray))
+      ))
+  )
+
+  (func $new_regexp
+    (param $source anyref)
+    (param $flags i32)
+    (param $program (ref $I32Array))
+    (param $group_names (ref null $AnyrefArray))
+    (result (ref $RegExp))
+    (local $regexp (ref $RegExp))
+
+    (local.set $regexp
+      (struct.new $RegExp
+        (call $new_hashmap)
+        (global.get $regexp-prototype)
+        (i32.const 0)
+        (local.get $source)
+        (local.get $flags)
+        (local.get $program)
+        (local.get $group_names)))
+    (call $set_property (local.get $regexp) (i32.const {{ data(str="lastIndex") }}) (ref.i31 (i32.const 0)))
+    (local.get $regexp)
+  )
+
+  ;; EscapeRegExpPattern from the spec, so that `/${source}/` is a valid
+  ;; literal again
+  (func $regexp_escape_source (param $source anyref) (result anyref)
+    (local $builder (ref $StringBuilder))
+    (local $length i32)
+    (local $in_class i32)
+    (local $byte i32)
+    (local $i i32)
+
+    (local.set $length (call $string_length (local.get $source)))
+    (if (i32.eqz (local.get $length))
+      (then
+        (return (call $new_static_string (i32.const {{ data(str="(?:)") }}) (i32.const {{ data_length() }})))
+      )
+    )
+
+    (local.set $builder (call $new_string_builder))
+    (block $done (loop $bytes
+      (br_if $done (i32.ge_u (local.get $i) (local.get $length)))
+      (local.set $byte (call $string_byte_at (local.get $source) (local.get $i)))
+      (block $next
+        (if (i32.eq (local.get $byte) (i32.const 0x5c)) ;; \
+          (then
+            ;; escaped characters are copied as they are
+            (call $builder_append_byte (local.get $builder) (local.get $byte))
+            (local.set $i (i32.add (local.get $i) (i32.const 1)))
+            (br_if $next (i32.ge_u (local.get $i) (local.get $length)))
+            (call $builder_append_byte (local.get $builder)
+              (call $string_byte_at (local.get $source) (local.get $i)))
+            (br $next)
+          )
+        )
+        (if (i32.eq (local.get $byte) (i32.const 0x5b)) ;; [
+          (then (local.set $in_class (i32.const 1)))
+        )
+        (if (i32.eq (local.get $byte) (i32.const 0x5d)) ;; ]
+          (then (local.set $in_class (i32.const 0)))
+        )
+        (if (i32.and
+              (i32.eq (local.get $byte) (i32.const 0x2f)) ;; /
+              (i32.eqz (local.get $in_class)))
+          (then
+            (call $builder_append_byte (local.get $builder) (i32.const 0x5c))
+            (call $builder_append_byte (local.get $builder) (i32.const 0x2f))
+            (br $next)
+          )
+        )
+        (if (i32.eq (local.get $byte) (i32.const 0x0a))
+          (then
+            (call $builder_append_byte (local.get $builder) (i32.const 0x5c))
+            (call $builder_append_byte (local.get $builder) (i32.const 0x6e)) ;; n
+            (br $next)
+          )
+        )
+        (if (i32.eq (local.get $byte) (i32.const 0x0d))
+          (then
+            (call $builder_append_byte (local.get $builder) (i32.const 0x5c))
+            (call $builder_append_byte (local.get $builder) (i32.const 0x72)) ;; r
+            (br $next)
+          )
+        )
+        (call $builder_append_byte (local.get $builder) (local.get $byte))
+      )
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $bytes)
+    ))
+    (call $builder_to_string (local.get $builder))
+  )
+
+  (func $regexp_flags_string (param $flags i32) (result (ref $String))
+    (local $builder (ref $StringBuilder))
+    (local.set $builder (call $new_string_builder))
+    (if (i32.and (local.get $flags) (i32.const {{ flag_global }}))
+      (then (call $builder_append_byte (local.get $builder) (i32.const 0x67))) ;; g
+    )
+    (if (i32.and (local.get $flags) (i32.const {{ flag_ignore_case }}))
+      (then (call $builder_append_byte (local.get $builder) (i32.const 0x69))) ;; i
+    )
+    (if (i32.and (local.get $flags) (i32.const {{ flag_multiline }}))
+      (then (call $builder_append_byte (local.get $builder) (i32.const 0x6d))) ;; m
+    )
+    (if (i32.and (local.get $flags) (i32.const {{ flag_dot_all }}))
+      (then (call $builder_append_byte (local.get $builder) (i32.const 0x73))) ;; s
+    )
+    (if (i32.and (local.get $flags) (i32.const {{ flag_unicode }}))
+      (then (call $builder_append_byte (local.get $builder) (i32.const 0x75))) ;; u
+    )
+    (if (i32.and (local.get $flags) (i32.const {{ flag_sticky }}))
+      (then (call $builder_append_byte (local.get $builder) (i32.const 0x79))) ;; y
+    )
+    (call $builder_to_string (local.get $builder))
+  )
+
+  ;; `source`, `flags` and the flag properties are accessors in the spec,
+  ;; $get_property handles them like `length`
+  (func $regexp_accessor (param $target anyref) (param $name i32) (result anyref)
+    (local $regexp (ref $RegExp))
+    (local $flag i32)
+
+    (if (i32.eqz (ref.test (ref $RegExp) (local.get $target)))
+      (then (return (global.get $no-value)))
+    )
+    (local.set $regexp (ref.cast (ref $RegExp) (local.get $target)))
+
+    (if (i32.eq (local.get $name) (i32.const {{ data(str="source") }}))
+      (then (return (struct.get $RegExp $source (local.get $regexp))))
+    )
+    (if (i32.eq (local.get $name) (i32.const {{ data(str="flags") }}))
+      (then (return (call $regexp_flags_string (struct.get $RegExp $flags (local.get $regexp)))))
+    )
+
+    (block $found
+      (local.set $flag (i32.const {{ flag_global }}))
+      (br_if $found (i32.eq (local.get $name) (i32.const {{ data(str="global") }})))
+      (local.set $flag (i32.const {{ flag_ignore_case }}))
+      (br_if $found (i32.eq (local.get $name) (i32.const {{ data(str="ignoreCase") }})))
+      (local.set $flag (i32.const {{ flag_multiline }}))
+      (br_if $found (i32.eq (local.get $name) (i32.const {{ data(str="multiline") }})))
+      (local.set $flag (i32.const {{ flag_dot_all }}))
+      (br_if $found (i32.eq (local.get $name) (i32.const {{ data(str="dotAll") }})))
+      (local.set $flag (i32.const {{ flag_unicode }}))
+      (br_if $found (i32.eq (local.get $name) (i32.const {{ data(str="unicode") }})))
+      (local.set $flag (i32.const {{ flag_sticky }}))
+      (br_if $found (i32.eq (local.get $name) (i32.const {{ data(str="sticky") }})))
+      (return (global.get $no-value))
+    )
+    (call $new_boolean
+      (i32.ne (i32.and (struct.get $RegExp $flags (local.get $regexp)) (local.get $flag)) (i32.const 0)))
+  )
+
+  (func $this_regexp (param $this anyref) (result (ref $RegExp))
+    (if (ref.test (ref $RegExp) (local.get $this))
+      (then (return (ref.cast (ref $RegExp) (local.get $this))))
+    )
+    (call $throw_type_error
+      (call $new_static_string (i32.const {{ data(str="RegExp method called on incompatible receiver") }}) (i32.const {{ data_length() }})))
+    (unreachable)
+  )
+
+  ;; the string argument of a regex method, kept in a $String so its data
+  ;; doesn't have to be copied for every search
+  (func $regexp_input (param $value anyref) (result (ref $String))
+    (local.set $value (call $to_string (local.get $value)))
+    (if (ref.test (ref $String) (local.get $value))
+      (then (return (ref.cast (ref $String) (local.get $value))))
+    )
+    (struct.new $String
+      (call $string_data (local.get $value))
+      (call $string_length (local.get $value)))
+  )
+
+  ;; lastIndex converted with ToLength, clamped to the i32 range
+  (func $regexp_last_index (param $regexp (ref $RegExp)) (result i32)
+    (local $value f64)
+    (local.set $value
+      (call $to_integer_or_infinity
+        (call $get_property (local.get $regexp) (i32.const {{ data(str="lastIndex") }}))))
+    (if (f64.le (local.get $value) (f64.const 0))
+      (then (return (i32.const 0)))
+    )
+    (i32.trunc_sat_f64_u (local.get $value))
+  )
+
+  (func $regexp_set_last_index (param $regexp (ref $RegExp)) (param $index i32)
+    (call $set_property
+      (local.get $regexp)
+      (i32.const {{ data(str="lastIndex") }})
+      (call $new_number (f64.convert_i32_u (local.get $index))))
+  )
+
+  ;; AdvanceStringIndex, used to step over empty matches
+  (func $regexp_advance_index (param $string (ref $String)) (param $index i32) (result i32)
+    (if (i32.ge_u (local.get $index) (struct.get $String $length (local.get $string)))
+      (then (return (i32.add (local.get $index) (i32.const 1))))
+    )
+    (call $next_code_point_position (struct.get $String $data (local.get $string)) (local.get $index))
+  )
+
+  ;; RegExpBuiltinExec from the spec, returns the capture registers or null.
+  ;; Global and sticky regexes start at lastIndex and update it
+  (func $regexp_builtin_exec (param $regexp (ref $RegExp)) (param $string (ref $String)) (result (ref null $I32Array))
+    (local $flags i32)
+    (local $uses_last_index i32)
+    (local $last_index i32)
+    (local $registers (ref null $I32Array))
+
+    (local.set $flags (struct.get $RegExp $flags (local.get $regexp)))
+    (local.set $uses_last_index
+      (i32.and (local.get $flags) (i32.const {{ flag_global + flag_sticky }})))
+    (if (local.get $uses_last_index)
+      (then (local.set $last_index (call $regexp_last_index (local.get $regexp))))
+    )
+
+    (if (i32.le_u (local.get $last_index) (struct.get $String $length (local.get $string)))
+      (then
+        (local.set $registers
+          (call $regexp_search
+            (local.get $regexp)
+            (struct.get $String $data (local.get $string))
+            (struct.get $String $length (local.get $string))
+            (local.get $last_index)
+            (i32.and (local.get $flags) (i32.const {{ flag_sticky }}))))
+      )
+    )
+
+    (if (local.get $uses_last_index)
+      (then
+        (call $regexp_set_last_index
+          (local.get $regexp)
+          (if (result i32) (ref.is_null (local.get $registers))
+            (then (i32.const 0))
+            (else (array.get $I32Array (ref.as_non_null (local.get $registers)) (i32.const 1)))
+          ))
+      )
+    )
+    (local.get $registers)
+  )
+
+  ;; the text of a capture group or undefined if it didn't participate
+  (func $regexp_capture (param $string anyref) (param $registers (ref $I32Array)) (param $group i32) (result anyref)
+    (local $start i32)
+    (local $end i32)
+    (local.set $start (array.get $I32Array (local.get $registers) (i32.shl (local.get $group) (i32.const 1))))
+    (local.set $end
+      (array.get $I32Array (local.get $registers) (i32.add (i32.shl (local.get $group) (i32.const 1)) (i32.const 1))))
+    (if (i32.or (i32.lt_s (local.get $start) (i32.const 0)) (i32.lt_s (local.get $end) (i32.const 0)))
+      (then (return (ref.null any)))
+    )
+    (call $substring (local.get $string) (local.get $start) (local.get $end))
+  )
+
+  ;; the `groups` object, or undefined if there are no named groups
+  (func $regexp_groups (param $regexp (ref $RegExp)) (param $string anyref) (param $registers (ref $I32Array)) (result anyref)
+    (local $names (ref null $AnyrefArray))
+    (local $groups (ref $Object))
+    (local $name anyref)
+    (local $i i32)
+
+    (local.set $names (struct.get $RegExp $group_names (local.get $regexp)))
+    (if (ref.is_null (local.get $names))
+      (then (return (ref.null any)))
+    )
+
+    (local.set $groups (call $new_object))
+    (struct.set $Object $prototype (local.get $groups) (global.get $null))
+    (block $done (loop $names
+      (br_if $done (i32.ge_u (local.get $i) (array.len (ref.as_non_null (local.get $names)))))
+      (local.set $name (array.get $AnyrefArray (ref.as_non_null (local.get $names)) (local.get $i)))
+      (if (i32.eqz (ref.is_null (local.get $name)))
+        (then
+          (call $set_property
+            (local.get $groups)
+            (call $intern_string (local.get $name))
+            (call $regexp_capture (local.get $string) (local.get $registers) (local.get $i)))
+        )
+      )
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $names)
+    ))
+    (local.get $groups)
+  )
+
+  ;; the array returned by exec, with the captures and the index, input and
+  ;; groups properties
+  (func $regexp_match_result (param $regexp (ref $RegExp)) (param $string anyref) (param $registers (ref $I32Array)) (result (ref $Array))
+    (local $result (ref $Array))
+    (local $count i32)
+    (local $i i32)
+
+    (local.set $count (array.get $I32Array (struct.get $RegExp $program (local.get $regexp)) (i32.const 0)))
+    (local.set $result (call $new_array (local.get $count)))
+    (block $done (loop $captures
+      (br_if $done (i32.ge_u (local.get $i) (local.get $count)))
+      (array.set $AnyrefArray
+        (struct.get $Array $array (local.get $result))
+        (local.get $i)
+        (call $regexp_capture (local.get $string) (local.get $registers) (local.get $i)))
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $captures)
+    ))
+
+    (call $set_property (local.get $result) (i32.const {{ data(str="index") }})
+      (call $new_number_i32 (array.get $I32Array (local.get $registers) (i32.const 0))))
+    (call $set_property (local.get $result) (i32.const {{ data(str="input") }}) (local.get $string))
+    (call $set_property (local.get $result) (i32.const {{ data(str="groups") }})
+      (call $regexp_groups (local.get $regexp) (local.get $string) (local.get $registers)))
+    (local.get $result)
+  )
+
+  (func $regexp_exec (param $regexp (ref $RegExp)) (param $string (ref $String)) (result anyref)
+    (local $registers (ref null $I32Array))
+    (local.set $registers (call $regexp_builtin_exec (local.get $regexp) (local.get $string)))
+    (if (ref.is_null (local.get $registers))
+      (then (return (global.get $null)))
+    )
+    (call $regexp_match_result (local.get $regexp) (local.get $string) (ref.as_non_null (local.get $registers)))
+  )
+
+  (func $RegExp-constructor
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (local $pattern anyref)
+    (local $flags anyref)
+    (local $regexp (ref $RegExp))
+
+    (local.set $pattern (call $get_argument (local.get $arguments) (i32.const 0)))
+    (local.set $flags (call $get_argument (local.get $arguments) (i32.const 1)))
+
+    (if (ref.test (ref $RegExp) (local.get $pattern))
+      (then
+        (local.set $regexp (ref.cast (ref $RegExp) (local.get $pattern)))
+        (if (ref.is_null (local.get $flags))
+          (then
+            (return
+              (call $new_regexp
+                (struct.get $RegExp $source (local.get $regexp))
+                (struct.get $RegExp $flags (local.get $regexp))
+                (struct.get $RegExp $program (local.get $regexp))
+                (struct.get $RegExp $group_names (local.get $regexp))))
+          )
+        )
+        ;; the escaped source means the same as the original pattern
+        (local.set $pattern (struct.get $RegExp $source (local.get $regexp)))
+      )
+    )
+
+    (call $regexp_compile
+      (if (result anyref) (ref.is_null (local.get $pattern))
+        (then (call $new_static_string (i32.const {{ data(str="") }}) (i32.const {{ data_length() }})))
+        (else (call $to_string (local.get $pattern)))
+      )
+      (if (result anyref) (ref.is_null (local.get $flags))
+        (then (call $new_static_string (i32.const {{ data(str="") }}) (i32.const {{ data_length() }})))
+        (else (call $to_string (local.get $flags)))
+      ))
+  )
+
+  (func $RegExp-prototype-exec
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+
+    (call $regexp_exec
+      (call $this_regexp (local.get $this))
+      (call $regexp_input (call $get_argument (local.get $arguments) (i32.const 0))))
+  )
+
+  (func $RegExp-prototype-test
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+
+    (call $new_boolean
+      (i32.eqz
+        (ref.is_null
+          (call $regexp_builtin_exec
+            (call $this_regexp (local.get $this))
+            (call $regexp_input (call $get_argument (local.get $arguments) (i32.const 0)))))))
+  )
+
+  (func $RegExp-prototype-toString
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (local $regexp (ref $RegExp))
+
+    (local.set $regexp (call $this_regexp (local.get $this)))
+    (call $concat_strings
+      (call $concat_strings
+        (call $concat_strings
+          (call $new_static_string (i32.const {{ data(str="/") }}) (i32.const {{ data_length() }}))
+          (struct.get $RegExp $source (local.get $regexp)))
+        (call $new_static_string (i32.const {{ data(str="/") }}) (i32.const {{ data_length() }})))
+      (call $regexp_flags_string (struct.get $RegExp $flags (local.get $regexp))))
+  )
+
+  (func $this_string_value (param $this anyref) (result (ref $String))
+    (if (i32.or (ref.is_null (local.get $this)) (ref.test (ref $Null) (local.get $this)))
+      (then
+        (call $throw_type_error
+          (call $new_static_string (i32.const {{ data(str="String.prototype method called on null or undefined") }}) (i32.const {{ data_length() }})))
+      )
+    )
+    (call $regexp_input (local.get $this))
+  )
+
+  ;; the pattern argument of the String.prototype methods, anything but a
+  ;; regex is used as a pattern
+  (func $regexp_argument (param $value anyref) (param $flags anyref) (result (ref $RegExp))
+    (if (ref.test (ref $RegExp) (local.get $value))
+      (then (return (ref.cast (ref $RegExp) (local.get $value))))
+    )
+    (call $regexp_compile
+      (if (result anyref) (ref.is_null (local.get $value))
+        (then (call $new_static_string (i32.const {{ data(str="") }}) (i32.const {{ data_length() }})))
+        (else (call $to_string (local.get $value)))
+      )
+      (local.get $flags))
+  )
+
+  (func $String-prototype-toString
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+
+    (if (i32.eqz (call $is_string (local.get $this)))
+      (then
+        (call $throw_type_error
+          (call $new_static_string (i32.const {{ data(str="String.prototype.toString requires that 'this' be a String") }}) (i32.const {{ data_length() }})))
+      )
+    )
+    (local.get $this)
+  )
+
+  (func $String-prototype-match
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (local $string (ref $String))
+    (local $regexp (ref $RegExp))
+    (local $registers (ref null $I32Array))
+    (local $matches (ref $ValueList))
+
+    (local.set $string (call $this_string_value (local.get $this)))
+    (local.set $regexp
+      (call $regexp_argument
+        (call $get_argument (local.get $arguments) (i32.const 0))
+        (call $new_static_string (i32.const {{ data(str="") }}) (i32.const {{ data_length() }}))))
+
+    (if (i32.eqz (i32.and (struct.get $RegExp $flags (local.get $regexp)) (i32.const {{ flag_global }})))
+      (then (return (call $regexp_exec (local.get $regexp) (local.get $string))))
+    )
+
+    (call $regexp_set_last_index (local.get $regexp) (i32.const 0))
+    (local.set $matches (call $new_value_list))
+    (block $done (loop $matches
+      (local.set $registers (call $regexp_builtin_exec (local.get $regexp) (local.get $string)))
+      (br_if $done (ref.is_null (local.get $registers)))
+      (call $value_list_push (local.get $matches)
+        (call $regexp_capture (local.get $string) (ref.as_non_null (local.get $registers)) (i32.const 0)))
+      (if (i32.eq
+            (array.get $I32Array (ref.as_non_null (local.get $registers)) (i32.const 0))
+            (array.get $I32Array (ref.as_non_null (local.get $registers)) (i32.const 1)))
+        (then
+          (call $regexp_set_last_index
+            (local.get $regexp)
+            (call $regexp_advance_index
+              (local.get $string)
+              (call $regexp_last_index (local.get $regexp))))
+        )
+      )
+      (br $matches)
+    ))
+
+    (if (i32.eqz (struct.get $ValueList $size (local.get $matches)))
+      (then (return (global.get $null)))
+    )
+    (call $value_list_to_array (local.get $matches))
+  )
+
+  (func $String-prototype-matchAll
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (local $string (ref $String))
+    (local $argument anyref)
+    (local $regexp (ref $RegExp))
+    (local $matcher (ref null $RegExp))
+
+    (local.set $string (call $this_string_value (local.get $this)))
+    (local.set $argument (call $get_argument (local.get $arguments) (i32.const 0)))
+
+    (if (ref.test (ref $RegExp) (local.get $argument))
+      (then
+        (local.set $regexp (ref.cast (ref $RegExp) (local.get $argument)))
+        (if (i32.eqz (i32.and (struct.get $RegExp $flags (local.get $regexp)) (i32.const {{ flag_global }})))
+          (then
+            (call $throw_type_error
+              (call $new_static_string (i32.const {{ data(str="String.prototype.matchAll called with a non-global RegExp argument") }}) (i32.const {{ data_length() }})))
+          )
+        )
+        ;; the iterator works on a copy, so the original lastIndex isn't touched
+        (local.set $matcher
+          (call $new_regexp
+            (struct.get $RegExp $source (local.get $regexp))
+            (struct.get $RegExp $flags (local.get $regexp))
+            (struct.get $RegExp $program (local.get $regexp))
+            (struct.get $RegExp $group_names (local.get $regexp))))
+        (call $regexp_set_last_index
+          (ref.as_non_null (local.get $matcher))
+          (call $regexp_last_index (local.get $regexp)))
+      )
+      (else
+        (local.set $matcher
+          (call $regexp_argument
+            (local.get $argument)
+            (call $new_static_string (i32.const {{ data(str="g") }}) (i32.const {{ data_length() }}))))
+      )
+    )
+
+    (struct.new $RegExpStringIterator
+      (call $new_hashmap)
+      (global.get $regexp-string-iterator-prototype)
+      (i32.const 0)
+      (ref.as_non_null (local.get $matcher))
+      (local.get $string)
+      (i32.const 0))
+  )
+
+  (func $RegExpStringIterator-next
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (local $iterator (ref $RegExpStringIterator))
+    (local $regexp (ref $RegExp))
+    (local $string (ref $String))
+    (local $registers (ref null $I32Array))
+
+    (if (i32.eqz (ref.test (ref $RegExpStringIterator) (local.get $this)))
+      (then
+        (call $throw_type_error
+          (call $new_static_string (i32.const {{ data(str="next method called on incompatible receiver") }}) (i32.const {{ data_length() }})))
+      )
+    )
+    (local.set $iterator (ref.cast (ref $RegExpStringIterator) (local.get $this)))
+    (if (struct.get $RegExpStringIterator $done (local.get $iterator))
+      (then (return (call $new_iterator_result (ref.null any) (i32.const 1))))
+    )
+
+    (local.set $regexp (struct.get $RegExpStringIterator $regexp (local.get $iterator)))
+    (local.set $string (ref.cast (ref $String) (struct.get $RegExpStringIterator $string (local.get $iterator))))
+    (local.set $registers (call $regexp_builtin_exec (local.get $regexp) (local.get $string)))
+    (if (ref.is_null (local.get $registers))
+      (then
+        (struct.set $RegExpStringIterator $done (local.get $iterator) (i32.const 1))
+        (return (call $new_iterator_result (ref.null any) (i32.const 1)))
+      )
+    )
+
+    (if (i32.eq
+          (array.get $I32Array (ref.as_non_null (local.get $registers)) (i32.const 0))
+          (array.get $I32Array (ref.as_non_null (local.get $registers)) (i32.const 1)))
+      (then
+        (call $regexp_set_last_index
+          (local.get $regexp)
+          (call $regexp_advance_index (local.get $string) (call $regexp_last_index (local.get $regexp))))
+      )
+    )
+    (call $new_iterator_result
+      (call $regexp_match_result (local.get $regexp) (local.get $string) (ref.as_non_null (local.get $registers)))
+      (i32.const 0))
+  )
+
+  ;; GetSubstitution from the spec, expands $$, $&, $`, $', $n, $nn and
+  ;; $<name> in a replacement string
+  (func $regexp_get_substitution
+    (param $template anyref)
+    (param $string anyref)
+    (param $registers (ref $I32Array))
+    (param $group_count i32)
+    (param $group_names (ref null $AnyrefArray))
+    (result (ref $String))
+    (local $builder (ref $StringBuilder))
+    (local $length i32)
+    (local $byte i32)
+    (local $next i32)
+    (local $group i32)
+    (local $two_digits i32)
+    (local $end i32)
+    (local $i i32)
+
+    (local.set $builder (call $new_string_builder))
+    (local.set $length (call $string_length (local.get $template)))
+    (block $done (loop $bytes
+      (br_if $done (i32.ge_u (local.get $i) (local.get $length)))
+      (local.set $byte (call $string_byte_at (local.get $template) (local.get $i)))
+      (local.set $next (i32.const -1))
+      (if (i32.lt_u (i32.add (local.get $i) (i32.const 1)) (local.get $length))
+        (then
+          (local.set $next (call $string_byte_at (local.get $template) (i32.add (local.get $i) (i32.const 1))))
+        )
+      )
+
+      (block $literal
+        (br_if $literal (i32.ne (local.get $byte) (i32.const 0x24))) ;; $
+
+        (if (i32.eq (local.get $next) (i32.const 0x24)) ;; $
+          (then
+            (call $builder_append_byte (local.get $builder) (i32.const 0x24))
+            (local.set $i (i32.add (local.get $i) (i32.const 2)))
+            (br $bytes)
+          )
+        )
+        (if (i32.eq (local.get $next) (i32.const 0x26)) ;; &
+          (then
+            (call $builder_append_string (local.get $builder)
+              (call $regexp_capture (local.get $string) (local.get $registers) (i32.const 0)))
+            (local.set $i (i32.add (local.get $i) (i32.const 2)))
+            (br $bytes)
+          )
+        )
+        (if (i32.eq (local.get $next) (i32.const 0x60)) ;; `
+          (then
+            (call $builder_append_string (local.get $builder)
+              (call $substring
+                (local.get $string)
+                (i32.const 0)
+                (array.get $I32Array (local.get $registers) (i32.const 0))))
+            (local.set $i (i32.add (local.get $i) (i32.const 2)))
+            (br $bytes)
+          )
+        )
+        (if (i32.eq (local.get $next) (i32.const 0x27)) ;; '
+          (then
+            (call $builder_append_string (local.get $builder)
+              (call $substring
+                (local.get $string)
+                (array.get $I32Array (local.get $registers) (i32.const 1))
+                (call $string_length (local.get $string))))
+            (local.set $i (i32.add (local.get $i) (i32.const 2)))
+            (br $bytes)
+          )
+        )
+
+        (if (i32.lt_u (i32.sub (local.get $next) (i32.const 0x30)) (i32.const 10))
+          (then
+            (local.set $group (i32.sub (local.get $next) (i32.const 0x30)))
+            (local.set $end (i32.add (local.get $i) (i32.const 2)))
+            ;; two digits are only used if they refer to an existing group
+            (if (i32.lt_u (local.get $end) (local.get $length))
+              (then
+                (local.set $two_digits
+                  (i32.sub (call $string_byte_at (local.get $template) (local.get $end)) (i32.const 0x30)))
+                (if (i32.lt_u (local.get $two_digits) (i32.const 10))
+                  (then
+                    (local.set $two_digits
+                      (i32.add (i32.mul (local.get $group) (i32.const 10)) (local.get $two_digits)))
+                    (if (i32.and
+                          (i32.ge_u (local.get $two_digits) (i32.const 1))
+                          (i32.lt_u (local.get $two_digits) (local.get $group_count)))
+                      (then
+                        (local.set $group (local.get $two_digits))
+                        (local.set $end (i32.add (local.get $end) (i32.const 1)))
+                      )
+                    )
+                  )
+                )
+              )
+            )
+            (br_if $literal
+              (i32.or
+                (i32.eqz (local.get $group))
+                (i32.ge_u (local.get $group) (local.get $group_count))))
+            (call $regexp_append_capture (local.get $builder) (local.get $string) (local.get $registers) (local.get $group))
+            (local.set $i (local.get $end))
+            (br $bytes)
+          )
+        )
+
+        (if (i32.eq (local.get $next) (i32.const 0x3c)) ;; <
+          (then
+            (br_if $literal (ref.is_null (local.get $group_names)))
+            (local.set $end (i32.add (local.get $i) (i32.const 2)))
+            (block $found (loop $name
+              (br_if $literal (i32.ge_u (local.get $end) (local.get $length)))
+              (br_if $found
+                (i32.eq (call $string_byte_at (local.get $template) (local.get $end)) (i32.const 0x3e))) ;; >
+              (local.set $end (i32.add (local.get $end) (i32.const 1)))
+              (br $name)
+            ))
+            (local.set $group
+              (call $regexp_group_index
+                (ref.as_non_null (local.get $group_names))
+                (call $substring (local.get $template) (i32.add (local.get $i) (i32.const 2)) (local.get $end))))
+            (if (i32.ge_s (local.get $group) (i32.const 0))
+              (then
+                (call $regexp_append_capture
+                  (local.get $builder) (local.get $string) (local.get $registers) (local.get $group))
+              )
+            )
+            (local.set $i (i32.add (local.get $end) (i32.const 1)))
+            (br $bytes)
+          )
+        )
+      )
+
+      (call $builder_append_byte (local.get $builder) (local.get $byte))
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $bytes)
+    ))
+    (call $builder_to_string (local.get $builder))
+  )
+
+  (func $regexp_append_capture
+    (param $builder (ref $StringBuilder))
+    (param $string anyref)
+    (param $registers (ref $I32Array))
+    (param $group i32)
+    (local $capture anyref)
+    (local.set $capture (call $regexp_capture (local.get $string) (local.get $registers) (local.get $group)))
+    (if (i32.eqz (ref.is_null (local.get $capture)))
+      (then (call $builder_append_string (local.get $builder) (local.get $capture)))
+    )
+  )
+
+  ;; calls a replacer function with the match, the captures, the position,
+  ;; the whole string and the groups object if there are named groups
+  (func $regexp_call_replacer
+    (param $replacer anyref)
+    (param $regexp (ref null $RegExp))
+    (param $string anyref)
+    (param $registers (ref $I32Array))
+    (param $group_count i32)
+    (result anyref)
+    (local $arguments (ref $ValueList))
+    (local $i i32)
+
+    (local.set $arguments (call $new_value_list))
+    (block $done (loop $captures
+      (br_if $done (i32.ge_u (local.get $i) (local.get $group_count)))
+      (call $value_list_push (local.get $arguments)
+        (call $regexp_capture (local.get $string) (local.get $registers) (local.get $i)))
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $captures)
+    ))
+    (call $value_list_push (local.get $arguments)
+      (call $new_number_i32 (array.get $I32Array (local.get $registers) (i32.const 0))))
+    (call $value_list_push (local.get $arguments) (local.get $string))
+    (if (i32.eqz (ref.is_null (local.get $regexp)))
+      (then
+        (if (i32.eqz (ref.is_null (struct.get $RegExp $group_names (ref.as_non_null (local.get $regexp)))))
+          (then
+            (call $value_list_push (local.get $arguments)
+              (call $regexp_groups (ref.as_non_null (local.get $regexp)) (local.get $string) (local.get $registers)))
+          )
+        )
+      )
+    )
+
+    (call $to_string
+      (call $call_function
+        (local.get $replacer)
+        (ref.null any)
+        (ref.cast (ref $JSArgs) (call $value_list_to_anyref_array (local.get $arguments)))))
+  )
+
+  (func $String-prototype-replace
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (local $string (ref $String))
+    (local $pattern anyref)
+    (local $replacement anyref)
+    (local $functional i32)
+    (local $regexp (ref $RegExp))
+    (local $group_count i32)
+    (local $results (ref $ValueList))
+    (local $registers (ref null $I32Array))
+    (local $match (ref $I32Array))
+    (local $builder (ref $StringBuilder))
+    (local $next_position i32)
+    (local $replaced anyref)
+    (local $position i32)
+    (local $i i32)
+
+    (local.set $string (call $this_string_value (local.get $this)))
+    (local.set $pattern (call $get_argument (local.get $arguments) (i32.const 0)))
+    (local.set $replacement (call $get_argument (local.get $arguments) (i32.const 1)))
+    (local.set $functional (ref.test (ref $Function) (local.get $replacement)))
+    (if (i32.eqz (local.get $functional))
+      (then (local.set $replacement (call $to_string (local.get $replacement))))
+    )
+
+    ;; a string pattern only replaces its first occurrence
+    (if (i32.eqz (ref.test (ref $RegExp) (local.get $pattern)))
+      (then
+        (local.set $pattern (call $to_string (local.get $pattern)))
+        (local.set $position (call $string_index_of (local.get $string) (local.get $pattern) (i32.const 0)))
+        (if (i32.lt_s (local.get $position) (i32.const 0))
+          (then (return (local.get $string)))
+        )
+        (local.set $match
+          (array.new_fixed $I32Array 2
+            (local.get $position)
+            (i32.add (local.get $position) (call $string_length (local.get $pattern)))))
+        (if (local.get $functional)
+          (then
+            (local.set $replaced
+              (call $regexp_call_replacer
+                (local.get $replacement)
+                (ref.null $RegExp)
+                (local.get $string)
+                (local.get $match)
+                (i32.const 1)))
+          )
+          (else
+            (local.set $replaced
+              (call $regexp_get_substitution
+                (local.get $replacement)
+                (local.get $string)
+                (local.get $match)
+                (i32.const 1)
+                (ref.null $AnyrefArray)))
+          )
+        )
+        (return
+          (call $concat_strings
+            (call $concat_strings
+              (call $substring (local.get $string) (i32.const 0) (local.get $position))
+              (local.get $replaced))
+            (call $substring
+              (local.get $string)
+              (array.get $I32Array (local.get $match) (i32.const 1))
+              (struct.get $String $length (local.get $string)))))
+      )
+    )
+
+    (local.set $regexp (ref.cast (ref $RegExp) (local.get $pattern)))
+    (local.set $group_count (array.get $I32Array (struct.get $RegExp $program (local.get $regexp)) (i32.const 0)))
+    (if (i32.and (struct.get $RegExp $flags (local.get $regexp)) (i32.const {{ flag_global }}))
+      (then (call $regexp_set_last_index (local.get $regexp) (i32.const 0)))
+    )
+
+    ;; all the matches are found first, as the replacer could change lastIndex
+    (local.set $results (call $new_value_list))
+    (block $done (loop $matches
+      (local.set $registers (call $regexp_builtin_exec (local.get $regexp) (local.get $string)))
+      (br_if $done (ref.is_null (local.get $registers)))
+      (call $value_list_push (local.get $results) (local.get $registers))
+      (br_if $done (i32.eqz (i32.and (struct.get $RegExp $flags (local.get $regexp)) (i32.const {{ flag_global }}))))
+      (if (i32.eq
+            (array.get $I32Array (ref.as_non_null (local.get $registers)) (i32.const 0))
+            (array.get $I32Array (ref.as_non_null (local.get $registers)) (i32.const 1)))
+        (then
+          (call $regexp_set_last_index
+            (local.get $regexp)
+            (call $regexp_advance_index (local.get $string) (call $regexp_last_index (local.get $regexp))))
+        )
+      )
+      (br $matches)
+    ))
+
+    (local.set $builder (call $new_string_builder))
+    (block $done (loop $results
+      (br_if $done (i32.ge_u (local.get $i) (struct.get $ValueList $size (local.get $results))))
+      (local.set $match
+        (ref.cast (ref $I32Array)
+          (array.get $AnyrefArray (struct.get $ValueList $data (local.get $results)) (local.get $i))))
+      (local.set $position (array.get $I32Array (local.get $match) (i32.const 0)))
+
+      (if (local.get $functional)
+        (then
+          (local.set $replaced
+            (call $regexp_call_replacer
+              (local.get $replacement)
+              (local.get $regexp)
+              (local.get $string)
+              (local.get $match)
+              (local.get $group_count)))
+        )
+        (else
+          (local.set $replaced
+            (call $regexp_get_substitution
+              (local.get $replacement)
+              (local.get $string)
+              (local.get $match)
+              (local.get $group_count)
+              (struct.get $RegExp $group_names (local.get $regexp))))
+        )
+      )
+
+      (if (i32.ge_u (local.get $position) (local.get $next_position))
+        (then
+          (call $builder_append_string (local.get $builder)
+            (call $substring (local.get $string) (local.get $next_position) (local.get $position)))
+          (call $builder_append_string (local.get $builder) (local.get $replaced))
+          (local.set $next_position (array.get $I32Array (local.get $match) (i32.const 1)))
+        )
+      )
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $results)
+    ))
+
+    (call $builder_append_string (local.get $builder)
+      (call $substring
+        (local.get $string)
+        (local.get $next_position)
+        (struct.get $String $length (local.get $string))))
+    (call $builder_to_string (local.get $builder))
+  )
+
+  (func $String-prototype-split
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (local $string (ref $String))
+    (local $separator anyref)
+    (local $limit anyref)
+    (local $max i32)
+    (local $parts (ref $ValueList))
+    (local $regexp (ref $RegExp))
+    (local $registers (ref null $I32Array))
+    (local $data (ref $CharArray))
+    (local $size i32)
+    (local $separator_length i32)
+    (local $p i32)
+    (local $q i32)
+    (local $end i32)
+    (local $group_count i32)
+    (local $i i32)
+
+    (local.set $string (call $this_string_value (local.get $this)))
+    (local.set $separator (call $get_argument (local.get $arguments) (i32.const 0)))
+    (local.set $limit (call $get_argument (local.get $arguments) (i32.const 1)))
+    (local.set $data (struct.get $String $data (local.get $string)))
+    (local.set $size (struct.get $String $length (local.get $string)))
+    (local.set $parts (call $new_value_list))
+
+    ;; the limit is converted with ToUint32
+    (local.set $max (i32.const -1))
+    (if (i32.eqz (ref.is_null (local.get $limit)))
+      (then (local.set $max (call $to_int32 (local.get $limit))))
+    )
+    (if (i32.eqz (local.get $max))
+      (then (return (call $new_array (i32.const 0))))
+    )
+
+    (if (ref.is_null (local.get $separator))
+      (then
+        (call $value_list_push (local.get $parts) (local.get $string))
+        (return (call $value_list_to_array (local.get $parts)))
+      )
+    )
+
+    (if (ref.test (ref $RegExp) (local.get $separator))
+      (then
+        (local.set $regexp (ref.cast (ref $RegExp) (local.get $separator)))
+        (local.set $group_count
+          (array.get $I32Array (struct.get $RegExp $program (local.get $regexp)) (i32.const 0)))
+
+        (if (i32.eqz (local.get $size))
+          (then
+            (if (ref.is_null
+                  (call $regexp_search
+                    (local.get $regexp) (local.get $data) (local.get $size) (i32.const 0) (i32.const 1)))
+              (then (call $value_list_push (local.get $parts) (local.get $string)))
+            )
+            (return (call $value_list_to_array (local.get $parts)))
+          )
+        )
+
+        ;; the separator has to match exactly at q, like with the sticky flag
+        (block $done (loop $positions
+          (br_if $done (i32.ge_u (local.get $q) (local.get $size)))
+          (local.set $registers
+            (call $regexp_search
+              (local.get $regexp) (local.get $data) (local.get $size) (local.get $q) (i32.const 1)))
+          (if (ref.is_null (local.get $registers))
+            (then
+              (local.set $q (call $next_code_point_position (local.get $data) (local.get $q)))
+              (br $positions)
+            )
+          )
+          (local.set $end (array.get $I32Array (ref.as_non_null (local.get $registers)) (i32.const 1)))
+          (if (i32.eq (local.get $end) (local.get $p))
+            (then
+              (local.set $q (call $next_code_point_position (local.get $data) (local.get $q)))
+              (br $positions)
+            )
+          )
+
+          (call $value_list_push (local.get $parts)
+            (call $substring (local.get $string) (local.get $p) (local.get $q)))
+          (if (i32.eq (struct.get $ValueList $size (local.get $parts)) (local.get $max))
+            (then (return (call $value_list_to_array (local.get $parts))))
+          )
+          (local.set $p (local.get $end))
+          (local.set $i (i32.const 1))
+          (block $captured (loop $captures
+            (br_if $captured (i32.ge_u (local.get $i) (local.get $group_count)))
+            (call $value_list_push (local.get $parts)
+              (call $regexp_capture (local.get $string) (ref.as_non_null (local.get $registers)) (local.get $i)))
+            (if (i32.eq (struct.get $ValueList $size (local.get $parts)) (local.get $max))
+              (then (return (call $value_list_to_array (local.get $parts))))
+            )
+            (local.set $i (i32.add (local.get $i) (i32.const 1)))
+            (br $captures)
+          ))
+          (local.set $q (local.get $p))
+          (br $positions)
+        ))
+
+        (call $value_list_push (local.get $parts)
+          (call $substring (local.get $string) (local.get $p) (local.get $size)))
+        (return (call $value_list_to_array (local.get $parts)))
+      )
+    )
+
+    (local.set $separator (call $to_string (local.get $separator)))
+    (local.set $separator_length (call $string_length (local.get $separator)))
+
+    ;; an empty separator splits the string into code points
+    (if (i32.eqz (local.get $separator_length))
+      (then
+        (block $done (loop $code_points
+          (br_if $done (i32.ge_u (local.get $p) (local.get $size)))
+          (br_if $done (i32.eq (struct.get $ValueList $size (local.get $parts)) (local.get $max)))
+          (local.set $q (call $next_code_point_position (local.get $data) (local.get $p)))
+          (call $value_list_push (local.get $parts)
+            (call $substring (local.get $string) (local.get $p) (local.get $q)))
+          (local.set $p (local.get $q))
+          (br $code_points)
+        ))
+        (return (call $value_list_to_array (local.get $parts)))
+      )
+    )
+
+    (block $done (loop $separators
+      (local.set $q (call $string_index_of (local.get $string) (local.get $separator) (local.get $p)))
+      (br_if $done (i32.lt_s (local.get $q) (i32.const 0)))
+      (call $value_list_push (local.get $parts)
+        (call $substring (local.get $string) (local.get $p) (local.get $q)))
+      (if (i32.eq (struct.get $ValueList $size (local.get $parts)) (local.get $max))
+        (then (return (call $value_list_to_array (local.get $parts))))
+      )
+      (local.set $p (i32.add (local.get $q) (local.get $separator_length)))
+      (br $separators)
+    ))
+    (call $value_list_push (local.get $parts)
+      (call $substring (local.get $string) (local.get $p) (local.get $size)))
+    (call $value_list_to_array (local.get $parts))
+  )
+
+  (elem declare func $RegExp-constructor)
+  (elem declare func $RegExp-prototype-exec)
+  (elem declare func $RegExp-prototype-test)
+  (elem declare func $RegExp-prototype-toString)
+  (elem declare func $RegExpStringIterator-next)
+  (elem declare func $String-prototype-toString)
+  (elem declare func $String-prototype-match)
+  (elem declare func $String-prototype-matchAll)
+  (elem declare func $String-prototype-replace)
+  (elem declare func $String-prototype-split)
+
+  (func $install-regexp-globals (param $scope (ref $Scope))
+    (local $prototype (ref $Object))
+
+    (local.set $prototype (call $new_object))
+    (call $add_method (local.get $prototype) (i32.const {{ data(str="toString") }}) (ref.func $String-prototype-toString))
+    (call $add_method (local.get $prototype) (i32.const {{ data(str="valueOf") }}) (ref.func $String-prototype-toString))
+    (call $add_method (local.get $prototype) (i32.const {{ data(str="match") }}) (ref.func $String-prototype-match))
+    (call $add_method (local.get $prototype) (i32.const {{ data(str="matchAll") }}) (ref.func $String-prototype-matchAll))
+    (call $add_method (local.get $prototype) (i32.const {{ data(str="replace") }}) (ref.func $String-prototype-replace))
+    (call $add_method (local.get $prototype) (i32.const {{ data(str="split") }}) (ref.func $String-prototype-split))
+    (global.set $string-prototype (local.get $prototype))
+
+    (local.set $prototype (call $new_object))
+    (call $add_method (local.get $prototype) (i32.const {{ data(str="next") }}) (ref.func $RegExpStringIterator-next))
+    (call $add_method
+      (local.get $prototype)
+      (call $well_known_symbol_key (global.get $symbol-iterator))
+      (ref.func $CollectionIterator-iterator))
+    (call $set_to_string_tag (local.get $prototype)
+      (call $new_static_string (i32.const {{ data(str="RegExp String Iterator") }}) (i32.const {{ data_length() }})))
+    (global.set $regexp-string-iterator-prototype (local.get $prototype))
+
+    (local.set $prototype (call $new_object))
+    (call $add_method (local.get $prototype) (i32.const {{ data(str="exec") }}) (ref.func $RegExp-prototype-exec))
+    (call $add_method (local.get $prototype) (i32.const {{ data(str="test") }}) (ref.func $RegExp-prototype-test))
+    (call $add_method (local.get $prototype) (i32.const {{ data(str="toString") }}) (ref.func $RegExp-prototype-toString))
+    (global.set $regexp-prototype (local.get $prototype))
+    (call $install-collection-constructor
+      (local.get $scope) (i32.const {{ data(str="RegExp") }}) (ref.func $RegExp-constructor) (local.get $prototype))
+  )
@@ -0,0 +1,362 @@
+  ;; `process.stdin`, reading the wasi:cli/stdin input stream without blocking
+  ;; the event loop:
+  ;;
+  ;;   read()  - a promise of the next chunk as a Uint8Array, or null at the end
+  ;;   text()  - a promise of everything that's left, as a string
+  ;;   lines() - an async iterator of the remaining lines, without line endings
+  ;;
+  ;; Requests are served in the order they were made from a shared buffer.
+  ;; Whenever the buffer can't satisfy the first one, more data is read from
+  ;; the stream, waiting on its pollable if there's nothing available yet. It's
+  ;; only included when the script mentions both `process` and `stdin`.
+
+  (type $StdinRequest (struct
+    (field $promise (ref $Promise))
+    (field $kind i32)
+  ))
+
+  (global $stdin-read-chunk i32 (i32.const 0))
+  (global $stdin-read-text i32 (i32.const 1))
+  (global $stdin-read-line i32 (i32.const 2))
+
+  ;; the input-stream, taken on first use
+  (global $stdin-stream (mut i32) (i32.const -1))
+  (global $stdin-ended (mut i32) (i32.const 0))
+  ;; waiting on the pollable of the stream
+  (global $stdin-waiting (mut i32) (i32.const 0))
+  ;; bytes read but not handed out yet, the ones before $stdin-buffer-start are
+  ;; already consumed
+  (global $stdin-buffer (mut (ref null $StringBuilder)) (ref.null $StringBuilder))
+  (global $stdin-buffer-start (mut i32) (i32.const 0))
+  ;; pending requests, the ones before $stdin-request-head are served
+  (global $stdin-requests (mut (ref null $ValueList)) (ref.null $ValueList))
+  (global $stdin-request-head (mut i32) (i32.const 0))
+
+  (global $stdin-lines-prototype (mut (ref null $Object)) (ref.null $Object))
+
+  (func $stdin_buffered (result i32)
+    (i32.sub
+      (struct.get $StringBuilder $length (ref.as_non_null (global.get $stdin-buffer)))
+      (global.get $stdin-buffer-start))
+  )
+
+  ;; takes `length` bytes from the buffer, skipping `skip` more after them
+  (func $stdin_take (param $length i32) (param $skip i32) (result (ref $String))
+    (local $buffer (ref $StringBuilder))
+    (local $data (ref $CharArray))
+
+    (local.set $buffer (ref.as_non_null (global.get $stdin-buffer)))
+    (local.set $data (array.new_default $CharArray (local.get $length)))
+    (array.copy $CharArray $CharArray
+      (local.get $data)
+      (i32.const 0)
+      (struct.get $StringBuilder $data (local.get $buffer))
+      (global.get $stdin-buffer-start)
+      (local.get $length))
+    (global.set $stdin-buffer-start
+      (i32.add (global.get $stdin-buffer-start) (i32.add (local.get $length) (local.get $skip))))
+    (if (i32.eq (global.get $stdin-buffer-start) (struct.get $StringBuilder $length (local.get $buffer)))
+      (then
+        (global.set $stdin-buffer-start (i32.const 0))
+        (struct.set $StringBuilder $length (local.get $buffer) (i32.const 0))
+      )
+    )
+    (struct.new $String (local.get $data) (local.get $length))
+  )
+
+  ;; the offset of the next "\n" from the start of the buffered bytes, or -1
+  (func $stdin_find_newline (result i32)
+    (local $data (ref $CharArray))
+    (local $end i32)
+    (local $i i32)
+
+    (local.set $data (struct.get $StringBuilder $data (ref.as_non_null (global.get $stdin-buffer))))
+    (local.set $end (struct.get $StringBuilder $length (ref.as_non_null (global.get $stdin-buffer))))
+    (local.set $i (global.get $stdin-buffer-start))
+    (block $break (loop $find
+      (br_if $break (i32.ge_u (local.get $i) (local.get $end)))
+      (if (i32.eq (array.get_u $CharArray (local.get $data) (local.get $i)) (i32.const 0x0a))
+        (then (return (i32.sub (local.get $i) (global.get $stdin-buffer-start))))
+      )
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $find)
+    ))
+    (i32.const -1)
+  )
+
+  (func $stdin_take_line (param $length i32) (param $skip i32) (result (ref $String))
+    (local $line (ref $String))
+
+    (local.set $line (call $stdin_take (local.get $length) (local.get $skip)))
+    (if (i32.eqz (local.get $length))
+      (then (return (local.get $line)))
+    )
+    ;; "\r\n" line endings
+    (if (i32.eq
+          (array.get_u $CharArray
+            (struct.get $String $data (local.get $line))
+            (i32.sub (local.get $length) (i32.const 1)))
+          (i32.const 0x0d))
+      (then
+        (struct.set $String $length (local.get $line) (i32.sub (local.get $length) (i32.const 1)))
+      )
+    )
+    (local.get $line)
+  )
+
+  ;; settles the request if the buffer has enough for it, returns 1 if it did
+  (func $stdin_serve (param $request (ref $StdinRequest)) (result i32)
+    (local $promise (ref $Promise))
+    (local $kind i32)
+    (local $length i32)
+    (local $newline i32)
+    (local $chunk (ref $String))
+
+    (local.set $promise (struct.get $StdinRequest $promise (local.get $request)))
+    (local.set $kind (struct.get $StdinRequest $kind (local.get $request)))
+    (local.set $length (call $stdin_buffered))
+
+    (if (i32.eq (local.get $kind) (global.get $stdin-read-chunk))
+      (then
+        (if (local.get $length)
+          (then
+            (local.set $chunk (call $stdin_take (local.get $length) (i32.const 0)))
+            (call $resolve-promise
+              (local.get $promise)
+              (call $new_typed_array
+                (i32.const 1)
+                (call $new_array_buffer (struct.get $String $data (local.get $chunk)))
+                (i32.const 0)
+                (local.get $length)))
+            (return (i32.const 1))
+          )
+        )
+        (if (global.get $stdin-ended)
+          (then
+            (call $resolve-promise (local.get $promise) (global.get $null))
+            (return (i32.const 1))
+          )
+        )
+        (return (i32.const 0))
+      )
+    )
+
+    (if (i32.eq (local.get $kind) (global.get $stdin-read-text))
+      (then
+        (if (global.get $stdin-ended)
+          (then
+            (call $resolve-promise (local.get $promise) (call $stdin_take (local.get $length) (i32.const 0)))
+            (return (i32.const 1))
+          )
+        )
+        (return (i32.const 0))
+      )
+    )
+
+    ;; a line, the last one doesn't need a line ending
+    (local.set $newline (call $stdin_find_newline))
+    (if (i32.ne (local.get $newline) (i32.const -1))
+      (then
+        (call $resolve-promise
+          (local.get $promise)
+          (call $new_iterator_result (call $stdin_take_line (local.get $newline) (i32.const 1)) (i32.const 0)))
+        (return (i32.const 1))
+      )
+    )
+    (if (global.get $stdin-ended)
+      (then
+        (call $resolve-promise
+          (local.get $promise)
+          (if (result anyref) (local.get $length)
+            (then (call $new_iterator_result (call $stdin_take_line (local.get $length) (i32.const 0)) (i32.const 0)))
+            (else (call $new_iterator_result (ref.null any) (i32.const 1)))))
+        (return (i32.const 1))
+      )
+    )
+    (i32.const 0)
+  )
+
+  ;; reads whatever is available into the buffer, returns 0 if there was
+  ;; nothing to read yet
+  (func $stdin_fill (result i32)
+    (local $result i32)
+    (local $length i32)
+
+    (if (i32.eq (global.get $stdin-stream) (i32.const -1))
+      (then (global.set $stdin-stream (call $get-stdin)))
+    )
+    ;; result<list<u8>, stream-error>
+    (local.set $result (call $malloc (i32.const 16)))
+    (call $input-stream-read (global.get $stdin-stream) (i64.const 65536) (local.get $result))
+    (if (i32.load8_u (local.get $result))
+      (then
+        ;; a failed read ends the input just like closing it
+        (if (i32.eqz (i32.load8_u offset=4 (local.get $result)))
+          (then (call $drop-io-error (i32.load offset=8 (local.get $result))))
+        )
+        (call $free (local.get $result))
+        (call $drop-input-stream (global.get $stdin-stream))
+        (global.set $stdin-ended (i32.const 1))
+        (return (i32.const 1))
+      )
+    )
+    (local.set $length (i32.load offset=8 (local.get $result)))
+    (call $builder_append_memory
+      (ref.as_non_null (global.get $stdin-buffer))
+      (i32.load offset=4 (local.get $result))
+      (local.get $length))
+    (call $free (i32.load offset=4 (local.get $result)))
+    (call $free (local.get $result))
+    (i32.ne (local.get $length) (i32.const 0))
+  )
+
+  ;; serves as many requests as possible, then waits for more data if any
+  ;; are left
+  (func $stdin_pump
+    (local $requests (ref $ValueList))
+
+    (local.set $requests (ref.as_non_null (global.get $stdin-requests)))
+    (block $done (loop $serve
+      (if (i32.eq (global.get $stdin-request-head) (struct.get $ValueList $size (local.get $requests)))
+        (then
+          (global.set $stdin-request-head (i32.const 0))
+          (struct.set $ValueList $size (local.get $requests) (i32.const 0))
+          (br $done)
+        )
+      )
+      (if (call $stdin_serve
+            (ref.cast (ref $StdinRequest)
+              (call $value_list_get (local.get $requests) (global.get $stdin-request-head))))
+        (then
+          (array.set $AnyrefArray
+            (struct.get $ValueList $data (local.get $requests))
+            (global.get $stdin-request-head)
+            (ref.null any))
+          (global.set $stdin-request-head (i32.add (global.get $stdin-request-head) (i32.const 1)))
+          (br $serve)
+        )
+      )
+      (br_if $serve (call $stdin_fill))
+
+      (if (i32.eqz (global.get $stdin-waiting))
+        (then
+          (global.set $stdin-waiting (i32.const 1))
+          (drop
+            (call $add-pollable
+              (call $input-stream-subscribe (global.get $stdin-stream))
+              (call $new_function
+                (ref.cast (ref $Scope) (global.get $scope))
+                (ref.func $stdin-ready)
+                (ref.null any))))
+        )
+      )
+    ))
+  )
+
+  (func $stdin-ready
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+
+    (global.set $stdin-waiting (i32.const 0))
+    (call $stdin_pump)
+    (ref.null any)
+  )
+
+  (func $stdin_request (param $kind i32) (result (ref $Promise))
+    (local $promise (ref $Promise))
+
+    (if (ref.is_null (global.get $stdin-requests))
+      (then
+        (global.set $stdin-requests (call $new_value_list))
+        (global.set $stdin-buffer (call $new_string_builder))
+      )
+    )
+    (local.set $promise (call $new-promise))
+    (call $value_list_push
+      (ref.as_non_null (global.get $stdin-requests))
+      (struct.new $StdinRequest (local.get $promise) (local.get $kind)))
+    ;; an earlier request is already waiting for data
+    (if (i32.eqz (global.get $stdin-waiting))
+      (then (call $stdin_pump))
+    )
+    (local.get $promise)
+  )
+
+  (func $stdin-read
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (call $stdin_request (global.get $stdin-read-chunk))
+  )
+
+  (func $stdin-text
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (call $stdin_request (global.get $stdin-read-text))
+  )
+
+  (func $stdin-lines
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (local $iterator (ref $Object))
+
+    (local.set $iterator (call $new_object))
+    (struct.set $Object $prototype (local.get $iterator) (global.get $stdin-lines-prototype))
+    (local.get $iterator)
+  )
+
+  (func $StdinLines-next
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (call $stdin_request (global.get $stdin-read-line))
+  )
+
+  ;; [Symbol.asyncIterator], returns the iterator itself
+  (func $StdinLines-iterator
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (local.get $this)
+  )
+
+  (elem declare func $stdin-ready)
+  (elem declare func $stdin-read)
+  (elem declare func $stdin-text)
+  (elem declare func $stdin-lines)
+  (elem declare func $StdinLines-next)
+  (elem declare func $StdinLines-iterator)
+
+  (func $create-stdin-object (result (ref $Object))
+    (local $stdin (ref $Object))
+    (local $prototype (ref $Object))
+
+    (local.set $prototype (call $new_object))
+    (call $add_method (local.get $prototype) (i32.const {{ data(str="next") }}) (ref.func $StdinLines-next))
+    (call $add_method
+      (local.get $prototype)
+      (call $well_known_symbol_key (global.get $symbol-async-iterator))
+      (ref.func $StdinLines-iterator))
+    (global.set $stdin-lines-prototype (local.get $prototype))
+
+    (local.set $stdin (call $new_object))
+    (call $add_method (local.get $stdin) (i32.const {{ data(str="read") }}) (ref.func $stdin-read))
+    (call $add_method (local.get $stdin) (i32.const {{ data(str="text") }}) (ref.func $stdin-text))
+    (call $add_method (local.get $stdin) (i32.const {{ data(str="lines") }}) (ref.func $stdin-lines))
+    (local.get $stdin)
+  )
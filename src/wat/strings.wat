@@ -0,0 +1,793 @@
+  ;; Strings can be either a $StaticString, which points to a string in the data
+  ;; section, or a $String, which holds its own data. The helpers in here work with
+  ;; both of them, so the rest of the runtime doesn't have to care which one it got.
+  ;;
+  ;; A $StaticString always points to the beginning of a string inserted by the
+  ;; compiler, which means its offset can be used directly as a property key
+
+  (func $is_string (param $arg anyref) (result i32)
+    (i32.or
+      (ref.test (ref $StaticString) (local.get $arg))
+      (ref.test (ref $String) (local.get $arg)))
+  )
+
+  (func $string_length (param $str anyref) (result i32)
+    (if (ref.test (ref $StaticString) (local.get $str))
+      (then
+        (return (struct.get $StaticString $length (ref.cast (ref $StaticString) (local.get $str))))
+      )
+    )
+    (struct.get $String $length (ref.cast (ref $String) (local.get $str)))
+  )
+
+  (func $string_byte_at (param $str anyref) (param $index i32) (result i32)
+    (if (ref.test (ref $StaticString) (local.get $str))
+      (then
+        (return
+          (i32.load8_u
+            (i32.add
+              (struct.get $StaticString $offset (ref.cast (ref $StaticString) (local.get $str)))
+              (local.get $index))))
+      )
+    )
+    (array.get_u $CharArray
+      (struct.get $String $data (ref.cast (ref $String) (local.get $str)))
+      (local.get $index))
+  )
+
+  ;; copies bytes of any string into a $CharArray at a given offset
+  (func $copy_string_data (param $str anyref) (param $dest (ref $CharArray)) (param $dest_offset i32)
+    (local $i i32)
+    (local $len i32)
+    (local $offset i32)
+
+    (local.set $len (call $string_length (local.get $str)))
+
+    (if (ref.test (ref $String) (local.get $str))
+      (then
+        (array.copy $CharArray $CharArray
+          (local.get $dest)
+          (local.get $dest_offset)
+          (struct.get $String $data (ref.cast (ref $String) (local.get $str)))
+          (i32.const 0)
+          (local.get $len))
+        (return)
+      )
+    )
+
+    (local.set $offset (struct.get $StaticString $offset (ref.cast (ref $StaticString) (local.get $str))))
+    (block $break (loop $copy
+      (br_if $break (i32.ge_u (local.get $i) (local.get $len)))
+
+      (array.set $CharArray (local.get $dest)
+        (i32.add (local.get $dest_offset) (local.get $i))
+        (i32.load8_u (i32.add (local.get $offset) (local.get $i))))
+
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $copy)
+    ))
+  )
+
+  ;; the bytes of a string, static strings are copied out of the linear memory
+  (func $string_data (param $str anyref) (result (ref $CharArray))
+    (local $data (ref $CharArray))
+
+    (if (ref.test (ref $String) (local.get $str))
+      (then
+        (return (struct.get $String $data (ref.cast (ref $String) (local.get $str))))
+      )
+    )
+
+    (local.set $data (array.new_default $CharArray (call $string_length (local.get $str))))
+    (call $copy_string_data (local.get $str) (local.get $data) (i32.const 0))
+    (local.get $data)
+  )
+
+  ;; a new string with the bytes from $start up to $end
+  (func $substring (param $str anyref) (param $start i32) (param $end i32) (result (ref $String))
+    (local $length i32)
+    (local $data (ref $CharArray))
+    (local $offset i32)
+    (local $i i32)
+
+    (local.set $length (i32.sub (local.get $end) (local.get $start)))
+    (local.set $data (array.new_default $CharArray (local.get $length)))
+
+    (if (ref.test (ref $String) (local.get $str))
+      (then
+        (array.copy $CharArray $CharArray
+          (local.get $data)
+          (i32.const 0)
+          (struct.get $String $data (ref.cast (ref $String) (local.get $str)))
+          (local.get $start)
+          (local.get $length))
+      )
+      (else
+        (local.set $offset
+          (i32.add
+            (struct.get $StaticString $offset (ref.cast (ref $StaticString) (local.get $str)))
+            (local.get $start)))
+        (block $break (loop $copy
+          (br_if $break (i32.ge_u (local.get $i) (local.get $length)))
+          (array.set $CharArray (local.get $data) (local.get $i)
+            (i32.load8_u (i32.add (local.get $offset) (local.get $i))))
+          (local.set $i (i32.add (local.get $i) (i32.const 1)))
+          (br $copy)
+        ))
+      )
+    )
+
+    (struct.new $String (local.get $data) (local.get $length))
+  )
+
+  ;; byte index of the first occurrence of $search at or after $from, or -1
+  (func $string_index_of (param $str anyref) (param $search anyref) (param $from i32) (result i32)
+    (local $length i32)
+    (local $search_length i32)
+    (local $i i32)
+    (local $j i32)
+
+    (local.set $length (call $string_length (local.get $str)))
+    (local.set $search_length (call $string_length (local.get $search)))
+    (local.set $i (local.get $from))
+    (block $not_found (loop $positions
+      (br_if $not_found
+        (i32.gt_s (i32.add (local.get $i) (local.get $search_length)) (local.get $length)))
+      (local.set $j (i32.const 0))
+      (block $mismatch (loop $bytes
+        (if (i32.ge_u (local.get $j) (local.get $search_length))
+          (then (return (local.get $i)))
+        )
+        (br_if $mismatch
+          (i32.ne
+            (call $string_byte_at (local.get $str) (i32.add (local.get $i) (local.get $j)))
+            (call $string_byte_at (local.get $search) (local.get $j))))
+        (local.set $j (i32.add (local.get $j) (i32.const 1)))
+        (br $bytes)
+      ))
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $positions)
+    ))
+    (i32.const -1)
+  )
+
+  (func $concat_strings (param $str1 anyref) (param $str2 anyref) (result (ref $String))
+    (local $len1 i32)
+    (local $total_length i32)
+    (local $data (ref $CharArray))
+
+    (local.set $len1 (call $string_length (local.get $str1)))
+    (local.set $total_length (i32.add (local.get $len1) (call $string_length (local.get $str2))))
+    (local.set $data (array.new_default $CharArray (local.get $total_length)))
+
+    (call $copy_string_data (local.get $str1) (local.get $data) (i32.const 0))
+    (call $copy_string_data (local.get $str2) (local.get $data) (local.get $len1))
+
+    (struct.new $String (local.get $data) (local.get $total_length))
+  )
+
+  (func $string_equals (param $str1 anyref) (param $str2 anyref) (result i32)
+    (local $len i32)
+    (local $i i32)
+
+    ;; static strings are deduplicated, so the same offset means the same string
+    (if (i32.and
+          (ref.test (ref $StaticString) (local.get $str1))
+          (ref.test (ref $StaticString) (local.get $str2)))
+      (then
+        (if (i32.eq
+              (struct.get $StaticString $offset (ref.cast (ref $StaticString) (local.get $str1)))
+              (struct.get $StaticString $offset (ref.cast (ref $StaticString) (local.get $str2))))
+          (then (return (i32.const 1)))
+        )
+      )
+    )
+
+    (local.set $len (call $string_length (local.get $str1)))
+    (if (i32.ne (local.get $len) (call $string_length (local.get $str2)))
+      (then (return (i32.const 0)))
+    )
+
+    (block $break (loop $compare
+      (br_if $break (i32.ge_u (local.get $i) (local.get $len)))
+
+      (if (i32.ne
+            (call $string_byte_at (local.get $str1) (local.get $i))
+            (call $string_byte_at (local.get $str2) (local.get $i)))
+        (then (return (i32.const 0)))
+      )
+
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $compare)
+    ))
+
+    (i32.const 1)
+  )
+
+  ;; FNV-1a
+  (func $string_hash (param $str anyref) (result i32)
+    (local $hash i32)
+    (local $len i32)
+    (local $i i32)
+
+    (local.set $hash (i32.const 0x811c9dc5))
+    (local.set $len (call $string_length (local.get $str)))
+
+    (block $break (loop $hash_loop
+      (br_if $break (i32.ge_u (local.get $i) (local.get $len)))
+
+      (local.set $hash
+        (i32.mul
+          (i32.xor (local.get $hash) (call $string_byte_at (local.get $str) (local.get $i)))
+          (i32.const 0x01000193)))
+
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $hash_loop)
+    ))
+
+    (local.get $hash)
+  )
+
+  ;; Property and variable names are i32 keys. For names known at compile time
+  ;; it's the offset of the string in the data section, but property names can
+  ;; also be computed at runtime, like in `obj["fo" + "o"]`. In order to use
+  ;; the same keys in both cases runtime strings are interned here. Strings that
+  ;; are not found in the data section get negative keys, which are indexes in the
+  ;; $dynamic-strings array (starting from -2, as -1 is used to signal a missing value)
+  (type $InternEntry (struct
+    (field $hash i32)
+    (field $key i32)
+    (field $next (ref null $InternEntry))
+  ))
+
+  (type $InternBuckets (array (mut (ref null $InternEntry))))
+
+  (global $intern-buckets (mut (ref null $InternBuckets)) (ref.null $InternBuckets))
+  (global $dynamic-strings (mut (ref null $AnyrefArray)) (ref.null $AnyrefArray))
+  (global $dynamic-strings-count (mut i32) (i32.const 0))
+
+  (global $static-strings-start i32 (i32.const {{ static_strings_start }}))
+  (global $static-strings-end i32 (i32.const {{ static_strings_end }}))
+
+  (func $intern_insert (param $hash i32) (param $key i32)
+    (local $bucket i32)
+    (local $buckets (ref $InternBuckets))
+
+    (local.set $buckets (ref.as_non_null (global.get $intern-buckets)))
+    (local.set $bucket
+      (i32.and (local.get $hash) (i32.sub (array.len (local.get $buckets)) (i32.const 1))))
+
+    (array.set $InternBuckets (local.get $buckets) (local.get $bucket)
+      (struct.new $InternEntry
+        (local.get $hash)
+        (local.get $key)
+        (array.get $InternBuckets (local.get $buckets) (local.get $bucket))))
+  )
+
+  ;; lazily index all of the static strings, so a runtime string can be matched
+  ;; against them
+  (func $intern_init
+    (local $offset i32)
+    (local $length i32)
+
+    (global.set $intern-buckets
+      (array.new $InternBuckets (ref.null $InternEntry) (i32.const 1024)))
+    (global.set $dynamic-strings
+      (array.new $AnyrefArray (ref.null any) (i32.const 16)))
+
+    (local.set $offset (global.get $static-strings-start))
+    (block $break (loop $scan
+      (br_if $break (i32.ge_u (local.get $offset) (global.get $static-strings-end)))
+
+      (local.set $length (i32.load (local.get $offset)))
+      (local.set $offset (i32.add (local.get $offset) (i32.const 4)))
+
+      (call $intern_insert
+        (call $string_hash (call $new_static_string (local.get $offset) (local.get $length)))
+        (local.get $offset))
+
+      ;; strings are aligned to 4 bytes
+      (local.set $offset
+        (i32.add
+          (local.get $offset)
+          (i32.and (i32.add (local.get $length) (i32.const 3)) (i32.const -4))))
+      (br $scan)
+    ))
+  )
+
+  (func $key_to_string (param $key i32) (result anyref)
+    (if (i32.ge_s (local.get $key) (i32.const 0))
+      (then
+        (return
+          (call $new_static_string
+            (local.get $key)
+            (i32.load (i32.sub (local.get $key) (i32.const 4)))))
+      )
+    )
+
+    (array.get $AnyrefArray
+      (ref.as_non_null (global.get $dynamic-strings))
+      (i32.sub (i32.const -2) (local.get $key)))
+  )
+
+  ;; appends a value to $dynamic-strings and returns its key, the value is
+  ;; either a string or a symbol
+  (func $add_dynamic_key (param $value anyref) (result i32)
+    (local $key i32)
+    (local $strings (ref $AnyrefArray))
+    (local $new_strings (ref $AnyrefArray))
+
+    (if (ref.is_null (global.get $intern-buckets))
+      (then (call $intern_init))
+    )
+
+    (local.set $strings (ref.as_non_null (global.get $dynamic-strings)))
+    (if (i32.ge_u (global.get $dynamic-strings-count) (array.len (local.get $strings)))
+      (then
+        (local.set $new_strings
+          (array.new $AnyrefArray (ref.null any) (i32.mul (array.len (local.get $strings)) (i32.const 2))))
+        (array.copy $AnyrefArray $AnyrefArray
+          (local.get $new_strings)
+          (i32.const 0)
+          (local.get $strings)
+          (i32.const 0)
+          (array.len (local.get $strings)))
+        (global.set $dynamic-strings (local.get $new_strings))
+        (local.set $strings (local.get $new_strings))
+      )
+    )
+
+    (array.set $AnyrefArray (local.get $strings) (global.get $dynamic-strings-count) (local.get $value))
+    (local.set $key (i32.sub (i32.const -2) (global.get $dynamic-strings-count)))
+    (global.set $dynamic-strings-count (i32.add (global.get $dynamic-strings-count) (i32.const 1)))
+    (local.get $key)
+  )
+
+  (func $intern_string (param $str anyref) (result i32)
+    (local $hash i32)
+    (local $entry (ref null $InternEntry))
+    (local $key i32)
+
+    (if (ref.test (ref $StaticString) (local.get $str))
+      (then
+        (return (struct.get $StaticString $offset (ref.cast (ref $StaticString) (local.get $str))))
+      )
+    )
+
+    (if (ref.is_null (global.get $intern-buckets))
+      (then (call $intern_init))
+    )
+
+    (local.set $hash (call $string_hash (local.get $str)))
+    (local.set $entry
+      (array.get $InternBuckets
+        (ref.as_non_null (global.get $intern-buckets))
+        (i32.and
+          (local.get $hash)
+          (i32.sub (array.len (ref.as_non_null (global.get $intern-buckets))) (i32.const 1)))))
+
+    (block $break (loop $search
+      (br_if $break (ref.is_null (local.get $entry)))
+
+      (if (i32.eq (struct.get $InternEntry $hash (local.get $entry)) (local.get $hash))
+        (then
+          (local.set $key (struct.get $InternEntry $key (local.get $entry)))
+          (if (call $string_equals (call $key_to_string (local.get $key)) (local.get $str))
+            (then (return (local.get $key)))
+          )
+        )
+      )
+
+      (local.set $entry (struct.get $InternEntry $next (local.get $entry)))
+      (br $search)
+    ))
+
+    ;; not found, add it to dynamic strings
+    (local.set $key (call $add_dynamic_key (local.get $str)))
+    (call $intern_insert (local.get $hash) (local.get $key))
+    (local.get $key)
+  )
+
+  ;; ToString from the spec, returns a $StaticString or a $String
+  (func $to_string (param $arg anyref) (result anyref)
+    (local $data (ref $AnyrefArray))
+    (local $result anyref)
+    (local $element anyref)
+    (local $i i32)
+
+    (if (call $is_string (local.get $arg))
+      (then (return (local.get $arg)))
+    )
+
+    (if (call $is_number (local.get $arg))
+      (then
+        (return
+          (call $number_to_string (call $number_value (local.get $arg))))
+      )
+    )
+
+    (if (call $is_bigint (local.get $arg))
+      (then
+        (return (call $bigint_to_string (ref.cast (ref $BigInt) (local.get $arg)) (i32.const 10)))
+      )
+    )
+
+    (if (ref.test nullref (local.get $arg))
+      (then
+        (return (call $new_static_string (i32.const {{ data(str="undefined") }}) (i32.const {{ data_length() }})))
+      )
+    )
+
+    (if (ref.test (ref $Boolean) (local.get $arg))
+      (then
+        (if (struct.get $Boolean $value (ref.cast (ref $Boolean) (local.get $arg)))
+          (then (return (call $new_static_string (i32.const {{ data(str="true") }}) (i32.const {{ data_length() }}))))
+        )
+        (return (call $new_static_string (i32.const {{ data(str="false") }}) (i32.const {{ data_length() }})))
+      )
+    )
+
+    (if (ref.test (ref $Null) (local.get $arg))
+      (then
+        (return (call $new_static_string (i32.const {{ data(str="null") }}) (i32.const {{ data_length() }})))
+      )
+    )
+
+    ;; arrays are joined with commas, undefined and null elements are empty
+    (if (ref.test (ref $Array) (local.get $arg))
+      (then
+        (local.set $data (struct.get $Array $array (ref.cast (ref $Array) (local.get $arg))))
+        (local.set $result (call $new_static_string (i32.const {{ data(str="") }}) (i32.const {{ data_length() }})))
+        (block $break (loop $join
+          (br_if $break (i32.ge_u (local.get $i) (array.len (local.get $data))))
+          (if (local.get $i)
+            (then
+              (local.set $result
+                (call $concat_strings
+                  (local.get $result)
+                  (call $new_static_string (i32.const {{ data(str=",") }}) (i32.const {{ data_length() }}))))
+            )
+          )
+          (local.set $element (array.get $AnyrefArray (local.get $data) (local.get $i)))
+          (if (i32.eqz (i32.or (ref.is_null (local.get $element)) (call $is_null (local.get $element))))
+            (then
+              (local.set $result
+                (call $concat_strings (local.get $result) (call $to_string (local.get $element))))
+            )
+          )
+          (local.set $i (i32.add (local.get $i) (i32.const 1)))
+          (br $join)
+        ))
+        (return (local.get $result))
+      )
+    )
+
+    (if (ref.test (ref $Function) (local.get $arg))
+      (then
+        (return (call $new_static_string (i32.const {{ data(str="function () { [native code] }") }}) (i32.const {{ data_length() }})))
+      )
+    )
+
+    (if (call $is_symbol (local.get $arg))
+      (then
+        (call $throw_type_error
+          (call $new_static_string (i32.const {{ data(str="Cannot convert a Symbol value to a string") }}) (i32.const {{ data_length() }})))
+      )
+    )
+
+    ;; other objects go through toString or Symbol.toPrimitive
+    (call $to_string (call $to_primitive (local.get $arg) (i32.const 2)))
+  )
+
+  ;; copies bytes of a $String into linear memory
+  (func $store_string_data (param $str (ref $String)) (param $offset i32)
+    (local $i i32)
+    (local $len i32)
+    (local $data (ref $CharArray))
+
+    (local.set $len (struct.get $String $length (local.get $str)))
+    (local.set $data (struct.get $String $data (local.get $str)))
+    (block $break (loop $copy
+      (br_if $break (i32.ge_u (local.get $i) (local.get $len)))
+
+      (i32.store8
+        (i32.add (local.get $offset) (local.get $i))
+        (array.get_u $CharArray (local.get $data) (local.get $i)))
+
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $copy)
+    ))
+  )
+
+  ;; copies the bytes of a string to a new block of linear memory
+  (func $string_to_memory (param $str anyref) (result i32)
+    (local $length i32)
+    (local $ptr i32)
+
+    (local.set $length (call $string_length (local.get $str)))
+    (local.set $ptr (call $malloc (local.get $length)))
+    (call $store_string_data
+      (struct.new $String (call $string_data (local.get $str)) (local.get $length))
+      (local.get $ptr))
+    (local.get $ptr)
+  )
+
+  (func $builder_append_memory (param $builder (ref $StringBuilder)) (param $ptr i32) (param $length i32)
+    (local $used i32)
+    (local $data (ref $CharArray))
+    (local $i i32)
+
+    (call $builder_reserve (local.get $builder) (local.get $length))
+    (local.set $used (struct.get $StringBuilder $length (local.get $builder)))
+    (local.set $data (struct.get $StringBuilder $data (local.get $builder)))
+    (block $break (loop $copy
+      (br_if $break (i32.ge_u (local.get $i) (local.get $length)))
+      (array.set $CharArray (local.get $data)
+        (i32.add (local.get $used) (local.get $i))
+        (i32.load8_u (i32.add (local.get $ptr) (local.get $i))))
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $copy)
+    ))
+    (struct.set $StringBuilder $length (local.get $builder) (i32.add (local.get $used) (local.get $length)))
+  )
+
+  ;; a string the host allocated with cabi_realloc, the memory is freed
+  (func $take_host_string (param $ptr i32) (param $length i32) (result (ref $String))
+    (local $builder (ref $StringBuilder))
+
+    (local.set $builder (call $new_string_builder))
+    (call $builder_append_memory (local.get $builder) (local.get $ptr) (local.get $length))
+    (call $free (local.get $ptr))
+    (call $builder_to_string (local.get $builder))
+  )
+
+  ;; converts any value to a key that can be used to access a property
+  (func $to_property_key (param $arg anyref) (result i32)
+
+    (if (call $is_string (local.get $arg))
+      (then
+        (return (call $intern_string (local.get $arg)))
+      )
+    )
+
+    (if (call $is_number (local.get $arg))
+      (then
+        (return
+          (call $intern_string
+            (call $number_to_string (call $number_value (local.get $arg)))))
+      )
+    )
+
+    (if (ref.test nullref (local.get $arg))
+      (then
+        (return (i32.const {{ data(str="undefined") }}))
+      )
+    )
+
+    (if (ref.test (ref $Boolean) (local.get $arg))
+      (then
+        (if (struct.get $Boolean $value (ref.cast (ref $Boolean) (local.get $arg)))
+          (then (return (i32.const {{ data(str="true") }})))
+        )
+        (return (i32.const {{ data(str="false") }}))
+      )
+    )
+
+    (if (ref.test (ref $Null) (local.get $arg))
+      (then
+        (return (i32.const {{ data(str="null") }}))
+      )
+    )
+
+    (if (call $is_symbol (local.get $arg))
+      (then
+        (return (call $symbol_key (ref.cast (ref $Symbol) (local.get $arg))))
+      )
+    )
+
+    ;; objects are converted with ToPrimitive, which may also give a symbol
+    (call $to_property_key (call $to_primitive (local.get $arg) (i32.const 2)))
+  )
+
+  ;; Returns an array index represented by the value or -1 if the value
+  ;; is not a valid index
+  (func $to_array_index (param $arg anyref) (result i32)
+    (local $value f64)
+    (local $len i32)
+    (local $i i32)
+    (local $char i32)
+    (local $result i64)
+
+    (if (ref.test (ref i31) (local.get $arg))
+      (then
+        (local.set $len (i31.get_s (ref.cast (ref i31) (local.get $arg))))
+        (if (i32.ge_s (local.get $len) (i32.const 0))
+          (then (return (local.get $len)))
+        )
+        (return (i32.const -1))
+      )
+    )
+
+    (if (ref.test (ref $Number) (local.get $arg))
+      (then
+        (local.set $value (struct.get $Number $value (ref.cast (ref $Number) (local.get $arg))))
+        (if (i32.and
+              (f64.eq (local.get $value) (f64.trunc (local.get $value)))
+              (i32.and
+                (f64.ge (local.get $value) (f64.const 0))
+                (f64.lt (local.get $value) (f64.const 2147483647))))
+          (then (return (i32.trunc_f64_u (local.get $value))))
+        )
+        (return (i32.const -1))
+      )
+    )
+
+    (if (i32.eqz (call $is_string (local.get $arg)))
+      (then (return (i32.const -1)))
+    )
+
+    (local.set $len (call $string_length (local.get $arg)))
+    ;; an empty string or a number with a leading zero is not an index
+    (if (i32.or
+          (i32.eqz (local.get $len))
+          (i32.or
+            (i32.gt_u (local.get $len) (i32.const 10))
+            (i32.and
+              (i32.gt_u (local.get $len) (i32.const 1))
+              (i32.eq (call $string_byte_at (local.get $arg) (i32.const 0)) (i32.const 48)))))
+      (then (return (i32.const -1)))
+    )
+
+    (block $break (loop $parse
+      (br_if $break (i32.ge_u (local.get $i) (local.get $len)))
+
+      (local.set $char (i32.sub (call $string_byte_at (local.get $arg) (local.get $i)) (i32.const 48)))
+      (if (i32.gt_u (local.get $char) (i32.const 9))
+        (then (return (i32.const -1)))
+      )
+      (local.set $result
+        (i64.add
+          (i64.mul (local.get $result) (i64.const 10))
+          (i64.extend_i32_u (local.get $char))))
+
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $parse)
+    ))
+
+    (if (i64.ge_u (local.get $result) (i64.const 2147483647))
+      (then (return (i32.const -1)))
+    )
+    (i32.wrap_i64 (local.get $result))
+  )
+
+  ;; a growable buffer for building strings byte by byte, $length is the number of
+  ;; bytes used, the rest of $data is spare capacity
+  (type $StringBuilder (struct
+    (field $data (mut (ref $CharArray)))
+    (field $length (mut i32))
+  ))
+
+  (func $new_string_builder (result (ref $StringBuilder))
+    (struct.new $StringBuilder
+      (array.new_default $CharArray (i32.const 16))
+      (i32.const 0))
+  )
+
+  ;; makes sure there's space for $extra more bytes in the builder
+  (func $builder_reserve (param $builder (ref $StringBuilder)) (param $extra i32)
+    (local $data (ref $CharArray))
+    (local $new_data (ref $CharArray))
+    (local $needed i32)
+    (local $capacity i32)
+
+    (local.set $data (struct.get $StringBuilder $data (local.get $builder)))
+    (local.set $needed (i32.add (struct.get $StringBuilder $length (local.get $builder)) (local.get $extra)))
+    (local.set $capacity (array.len (local.get $data)))
+    (if (i32.le_u (local.get $needed) (local.get $capacity))
+      (then (return))
+    )
+
+    (local.set $capacity (i32.shl (local.get $capacity) (i32.const 1)))
+    (if (i32.lt_u (local.get $capacity) (local.get $needed))
+      (then (local.set $capacity (local.get $needed)))
+    )
+    (local.set $new_data (array.new_default $CharArray (local.get $capacity)))
+    (array.copy $CharArray $CharArray
+      (local.get $new_data)
+      (i32.const 0)
+      (local.get $data)
+      (i32.const 0)
+      (struct.get $StringBuilder $length (local.get $builder)))
+    (struct.set $StringBuilder $data (local.get $builder) (local.get $new_data))
+  )
+
+  (func $builder_append_byte (param $builder (ref $StringBuilder)) (param $byte i32)
+    (local $length i32)
+
+    (call $builder_reserve (local.get $builder) (i32.const 1))
+    (local.set $length (struct.get $StringBuilder $length (local.get $builder)))
+    (array.set $CharArray
+      (struct.get $StringBuilder $data (local.get $builder))
+      (local.get $length)
+      (local.get $byte))
+    (struct.set $StringBuilder $length (local.get $builder) (i32.add (local.get $length) (i32.const 1)))
+  )
+
+  (func $builder_append_string (param $builder (ref $StringBuilder)) (param $str anyref)
+    (local $length i32)
+    (local $str_length i32)
+
+    (local.set $str_length (call $string_length (local.get $str)))
+    (call $builder_reserve (local.get $builder) (local.get $str_length))
+    (local.set $length (struct.get $StringBuilder $length (local.get $builder)))
+    (call $copy_string_data
+      (local.get $str)
+      (struct.get $StringBuilder $data (local.get $builder))
+      (local.get $length))
+    (struct.set $StringBuilder $length (local.get $builder) (i32.add (local.get $length) (local.get $str_length)))
+  )
+
+  (func $builder_append_bytes (param $builder (ref $StringBuilder)) (param $data (ref $CharArray)) (param $start i32) (param $length i32)
+    (local $used i32)
+
+    (call $builder_reserve (local.get $builder) (local.get $length))
+    (local.set $used (struct.get $StringBuilder $length (local.get $builder)))
+    (array.copy $CharArray $CharArray
+      (struct.get $StringBuilder $data (local.get $builder))
+      (local.get $used)
+      (local.get $data)
+      (local.get $start)
+      (local.get $length))
+    (struct.set $StringBuilder $length (local.get $builder) (i32.add (local.get $used) (local.get $length)))
+  )
+
+  ;; appends a code point (or a lone surrogate) encoded as UTF-8
+  (func $builder_append_code_point (param $builder (ref $StringBuilder)) (param $code_point i32)
+    (if (i32.lt_u (local.get $code_point) (i32.const 0x80))
+      (then
+        (call $builder_append_byte (local.get $builder) (local.get $code_point))
+        (return)
+      )
+    )
+    (if (i32.lt_u (local.get $code_point) (i32.const 0x800))
+      (then
+        (call $builder_append_byte (local.get $builder)
+          (i32.or (i32.const 0xc0) (i32.shr_u (local.get $code_point) (i32.const 6))))
+      )
+      (else
+        (if (i32.lt_u (local.get $code_point) (i32.const 0x10000))
+          (then
+            (call $builder_append_byte (local.get $builder)
+              (i32.or (i32.const 0xe0) (i32.shr_u (local.get $code_point) (i32.const 12))))
+          )
+          (else
+            (call $builder_append_byte (local.get $builder)
+              (i32.or (i32.const 0xf0) (i32.shr_u (local.get $code_point) (i32.const 18))))
+            (call $builder_append_byte (local.get $builder)
+              (i32.or (i32.const 0x80)
+                (i32.and (i32.shr_u (local.get $code_point) (i32.const 12)) (i32.const 0x3f))))
+          )
+        )
+        (call $builder_append_byte (local.get $builder)
+          (i32.or (i32.const 0x80)
+            (i32.and (i32.shr_u (local.get $code_point) (i32.const 6)) (i32.const 0x3f))))
+      )
+    )
+    (call $builder_append_byte (local.get $builder)
+      (i32.or (i32.const 0x80) (i32.and (local.get $code_point) (i32.const 0x3f))))
+  )
+
+  (func $builder_to_string (param $builder (ref $StringBuilder)) (result (ref $String))
+    (local $length i32)
+    (local $data (ref $CharArray))
+
+    (local.set $length (struct.get $StringBuilder $length (local.get $builder)))
+    (local.set $data (array.new_default $CharArray (local.get $length)))
+    (array.copy $CharArray $CharArray
+      (local.get $data)
+      (i32.const 0)
+      (struct.get $StringBuilder $data (local.get $builder))
+      (i32.const 0)
+      (local.get $length))
+    (struct.new $String (local.get $data) (local.get $length))
+  )
@@ -0,0 +1,281 @@
+  ;; The `Symbol` builtin
+  ;;
+  ;; Symbols used as property keys get an i32 key just like strings do: the
+  ;; first time a symbol is used as a key it's appended to $dynamic-strings, so
+  ;; property maps don't have to care whether a key is a string or a symbol
+
+  (type $Symbol (struct
+    ;; a string or undefined
+    (field $description anyref)
+    ;; the property key or -1 if the symbol wasn't used as a key yet
+    (field $key (mut i32))
+    ;; set for symbols created with Symbol.for
+    (field $registered i32)
+    (field $hash (mut i32))
+  ))
+
+  (global $symbol-prototype (mut (ref null $Object)) (ref.null $Object))
+  ;; Symbol.for registry, mapping keys of description strings to symbols
+  (global $symbol-registry (mut (ref null $HashMap)) (ref.null $HashMap))
+
+  (global $symbol-iterator (mut (ref null $Symbol)) (ref.null $Symbol))
+  (global $symbol-async-iterator (mut (ref null $Symbol)) (ref.null $Symbol))
+  (global $symbol-to-primitive (mut (ref null $Symbol)) (ref.null $Symbol))
+  (global $symbol-has-instance (mut (ref null $Symbol)) (ref.null $Symbol))
+  (global $symbol-to-string-tag (mut (ref null $Symbol)) (ref.null $Symbol))
+
+  (func $new_symbol (param $description anyref) (param $registered i32) (result (ref $Symbol))
+    (struct.new $Symbol
+      (local.get $description)
+      (i32.const -1)
+      (local.get $registered)
+      (i32.const 0))
+  )
+
+  (func $is_symbol (param $arg anyref) (result i32)
+    (ref.test (ref $Symbol) (local.get $arg))
+  )
+
+  (func $symbol_key (param $symbol (ref $Symbol)) (result i32)
+    (local $key i32)
+    (local.set $key (struct.get $Symbol $key (local.get $symbol)))
+
+    (if (i32.eq (local.get $key) (i32.const -1))
+      (then
+        (local.set $key (call $add_dynamic_key (local.get $symbol)))
+        (struct.set $Symbol $key (local.get $symbol) (local.get $key))
+      )
+    )
+
+    (local.get $key)
+  )
+
+  ;; the key of a well-known symbol stored in a global
+  (func $well_known_symbol_key (param $symbol (ref null $Symbol)) (result i32)
+    (call $symbol_key (ref.as_non_null (local.get $symbol)))
+  )
+
+  (func $is_symbol_key (param $key i32) (result i32)
+    (if (i32.ge_s (local.get $key) (i32.const -1))
+      (then (return (i32.const 0)))
+    )
+    (call $is_symbol (call $key_to_string (local.get $key)))
+  )
+
+  ;; SymbolDescriptiveString from the spec, ie. Symbol(description)
+  (func $symbol_descriptive_string (param $symbol (ref $Symbol)) (result (ref $String))
+    (local $description anyref)
+    (local.set $description (struct.get $Symbol $description (local.get $symbol)))
+
+    (if (ref.is_null (local.get $description))
+      (then
+        (local.set $description
+          (call $new_static_string (i32.const {{ data(str="") }}) (i32.const {{ data_length() }})))
+      )
+    )
+
+    (call $concat_strings
+      (call $concat_strings
+        (call $new_static_string (i32.const {{ data(str="Symbol(") }}) (i32.const {{ data_length() }}))
+        (local.get $description))
+      (call $new_static_string (i32.const {{ data(str=")") }}) (i32.const {{ data_length() }})))
+  )
+
+  ;; the symbol passed as `this` to a prototype method
+  (func $this_symbol_value (param $this anyref) (result (ref $Symbol))
+    (if (call $is_symbol (local.get $this))
+      (then (return (ref.cast (ref $Symbol) (local.get $this))))
+    )
+    (call $throw_type_error
+      (call $new_static_string (i32.const {{ data(str="Symbol.prototype method called on incompatible receiver") }}) (i32.const {{ data_length() }})))
+    (unreachable)
+  )
+
+  ;; `description` is an accessor in the spec, $get_property handles it like
+  ;; `length`
+  (func $symbol_description (param $target anyref) (result anyref)
+    (if (call $is_symbol (local.get $target))
+      (then
+        (return (struct.get $Symbol $description (ref.cast (ref $Symbol) (local.get $target))))
+      )
+    )
+    (global.get $no-value)
+  )
+
+  (func $Symbol-constructor
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (local $description anyref)
+
+    ;; `new` passes the newly created object as `this`
+    (if (ref.test (ref $Object) (local.get $this))
+      (then
+        (call $throw_type_error
+          (call $new_static_string (i32.const {{ data(str="Symbol is not a constructor") }}) (i32.const {{ data_length() }})))
+      )
+    )
+
+    (local.set $description (call $get_argument (local.get $arguments) (i32.const 0)))
+    (if (i32.eqz (ref.is_null (local.get $description)))
+      (then (local.set $description (call $to_string (local.get $description))))
+    )
+    (call $new_symbol (local.get $description) (i32.const 0))
+  )
+
+  (func $Symbol-for
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (local $description anyref)
+    (local $key i32)
+    (local $symbol anyref)
+
+    (local.set $description (call $to_string (call $get_argument (local.get $arguments) (i32.const 0))))
+    (local.set $key (call $intern_string (local.get $description)))
+
+    (local.set $symbol
+      (call $hashmap_get (ref.as_non_null (global.get $symbol-registry)) (local.get $key)))
+    (if (call $is_no_value_found (local.get $symbol))
+      (then
+        (local.set $symbol (call $new_symbol (local.get $description) (i32.const 1)))
+        (call $hashmap_set
+          (ref.as_non_null (global.get $symbol-registry))
+          (local.get $key)
+          (local.get $symbol))
+      )
+    )
+
+    (local.get $symbol)
+  )
+
+  (func $Symbol-keyFor
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (local $symbol anyref)
+
+    (local.set $symbol (call $get_argument (local.get $arguments) (i32.const 0)))
+    (if (i32.eqz (call $is_symbol (local.get $symbol)))
+      (then
+        (call $throw_type_error
+          (call $new_static_string (i32.const {{ data(str="Symbol.keyFor argument is not a symbol") }}) (i32.const {{ data_length() }})))
+      )
+    )
+
+    (if (struct.get $Symbol $registered (ref.cast (ref $Symbol) (local.get $symbol)))
+      (then
+        (return (struct.get $Symbol $description (ref.cast (ref $Symbol) (local.get $symbol))))
+      )
+    )
+    (ref.null any)
+  )
+
+  (func $Symbol-prototype-toString
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+
+    (call $symbol_descriptive_string (call $this_symbol_value (local.get $this)))
+  )
+
+  ;; also used as Symbol.prototype[Symbol.toPrimitive]
+  (func $Symbol-prototype-valueOf
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+
+    (call $this_symbol_value (local.get $this))
+  )
+
+  (elem declare func $Symbol-constructor)
+  (elem declare func $Symbol-for)
+  (elem declare func $Symbol-keyFor)
+  (elem declare func $Symbol-prototype-toString)
+  (elem declare func $Symbol-prototype-valueOf)
+
+  (func $new_well_known_symbol (param $symbol (ref $Symbol)) (result (ref $Symbol))
+    ;; give it a key right away, the runtime uses them for lookups all the time
+    (drop (call $symbol_key (local.get $symbol)))
+    (local.get $symbol)
+  )
+
+  ;; has to be called before other builtins are installed, as some of them use
+  ;; well-known symbols as keys
+  (func $create-well-known-symbols
+    (global.set $symbol-registry (call $new_hashmap))
+    (global.set $symbol-iterator
+      (call $new_well_known_symbol
+        (call $new_symbol
+          (call $new_static_string (i32.const {{ data(str="Symbol.iterator") }}) (i32.const {{ data_length() }}))
+          (i32.const 0))))
+    (global.set $symbol-async-iterator
+      (call $new_well_known_symbol
+        (call $new_symbol
+          (call $new_static_string (i32.const {{ data(str="Symbol.asyncIterator") }}) (i32.const {{ data_length() }}))
+          (i32.const 0))))
+    (global.set $symbol-to-primitive
+      (call $new_well_known_symbol
+        (call $new_symbol
+          (call $new_static_string (i32.const {{ data(str="Symbol.toPrimitive") }}) (i32.const {{ data_length() }}))
+          (i32.const 0))))
+    (global.set $symbol-has-instance
+      (call $new_well_known_symbol
+        (call $new_symbol
+          (call $new_static_string (i32.const {{ data(str="Symbol.hasInstance") }}) (i32.const {{ data_length() }}))
+          (i32.const 0))))
+    (global.set $symbol-to-string-tag
+      (call $new_well_known_symbol
+        (call $new_symbol
+          (call $new_static_string (i32.const {{ data(str="Symbol.toStringTag") }}) (i32.const {{ data_length() }}))
+          (i32.const 0))))
+  )
+
+  ;; sets obj[Symbol.toStringTag], used by Object.prototype.toString
+  (func $set_to_string_tag (param $target anyref) (param $tag anyref)
+    (call $set_property
+      (local.get $target)
+      (call $well_known_symbol_key (global.get $symbol-to-string-tag))
+      (local.get $tag))
+  )
+
+  (func $install-symbol-globals (param $scope (ref $Scope))
+    (local $symbol (ref $Function))
+    (local $prototype (ref $Object))
+
+    (local.set $prototype (call $new_object))
+    (call $add_method (local.get $prototype) (i32.const {{ data(str="toString") }}) (ref.func $Symbol-prototype-toString))
+    (call $add_method (local.get $prototype) (i32.const {{ data(str="valueOf") }}) (ref.func $Symbol-prototype-valueOf))
+    (call $add_method
+      (local.get $prototype)
+      (call $well_known_symbol_key (global.get $symbol-to-primitive))
+      (ref.func $Symbol-prototype-valueOf))
+    (call $set_to_string_tag (local.get $prototype)
+      (call $new_static_string (i32.const {{ data(str="Symbol") }}) (i32.const {{ data_length() }})))
+    (global.set $symbol-prototype (local.get $prototype))
+
+    (local.set $symbol
+      (call $new_function (local.get $scope) (ref.func $Symbol-constructor) (ref.null any)))
+    (call $set_property (local.get $symbol) (i32.const {{ data(str="prototype") }}) (local.get $prototype))
+    (call $set_property (local.get $prototype) (i32.const {{ data(str="constructor") }}) (local.get $symbol))
+
+    (call $add_method (local.get $symbol) (i32.const {{ data(str="for") }}) (ref.func $Symbol-for))
+    (call $add_method (local.get $symbol) (i32.const {{ data(str="keyFor") }}) (ref.func $Symbol-keyFor))
+    (call $set_property (local.get $symbol) (i32.const {{ data(str="iterator") }}) (global.get $symbol-iterator))
+    (call $set_property (local.get $symbol) (i32.const {{ data(str="asyncIterator") }}) (global.get $symbol-async-iterator))
+    (call $set_property (local.get $symbol) (i32.const {{ data(str="toPrimitive") }}) (global.get $symbol-to-primitive))
+    (call $set_property (local.get $symbol) (i32.const {{ data(str="hasInstance") }}) (global.get $symbol-has-instance))
+    (call $set_property (local.get $symbol) (i32.const {{ data(str="toStringTag") }}) (global.get $symbol-to-string-tag))
+
+    (call $set_variable (local.get $scope) (i32.const {{ data(str="Symbol") }}) (local.get $symbol))
+  )
@@ -8,8 +8,87 @@
 
   ;;(import "wasi:io/poll@0.2.1" "[method]pollable.ready" (func $poll-ready (param i32) (result i32)))
   (import "wasi:io/poll@0.2.1" "poll" (func $poll-many (param i32 i32 i32)))
+  (import "wasi:io/poll@0.2.1" "[resource-drop]pollable" (func $drop-pollable (param i32)))
 
   (import "wasi:clocks/monotonic-clock@0.2.1" "subscribe-duration" (func $subscribe-duration (param i64) (result i32)))
+  (import "wasi:clocks/monotonic-clock@0.2.1" "now" (func $monotonic-now (result i64)))
+{% if date %}
+  ;; only imported when the script uses `Date` or `performance`, see date.wat.
+  ;; The datetime record is written to the memory at the given offset
+  (import "wasi:clocks/wall-clock@0.2.1" "now" (func $wall-clock-now (param i32)))
+{% endif %}
+{% if random and random_seed is not defined %}
+  ;; seeds Math.random when the script uses it, see math.wat
+  (import "wasi:random/random@0.2.1" "get-random-u64" (func $get-random-u64 (result i64)))
+{% endif %}
+{% if crypto %}
+
+  ;; only imported when the script uses `crypto`, see crypto.wat. The list is
+  ;; written to the memory at the given offset
+  (import "wasi:random/random@0.2.1" "get-random-bytes" (func $get-random-bytes (param i64 i32)))
+{% endif %}
+{% if process %}
+
+  ;; only imported when the script uses `process`, see process.wat
+  (import "wasi:cli/environment@0.2.1" "get-environment" (func $get-environment (param i32)))
+  (import "wasi:cli/environment@0.2.1" "get-arguments" (func $get-arguments (param i32)))
+{% endif %}
+{% if filesystem %}
+
+  ;; only imported when the script uses `fs`, see fs.wat
+  (import "wasi:filesystem/preopens@0.2.1" "get-directories" (func $get-directories (param i32)))
+  (import "wasi:filesystem/types@0.2.1" "[method]descriptor.open-at" (func $descriptor-open-at (param i32 i32 i32 i32 i32 i32 i32)))
+  (import "wasi:filesystem/types@0.2.1" "[method]descriptor.read" (func $descriptor-read (param i32 i64 i64 i32)))
+  (import "wasi:filesystem/types@0.2.1" "[method]descriptor.write" (func $descriptor-write (param i32 i32 i32 i64 i32)))
+  (import "wasi:filesystem/types@0.2.1" "[method]descriptor.stat" (func $descriptor-stat (param i32 i32)))
+  (import "wasi:filesystem/types@0.2.1" "[method]descriptor.stat-at" (func $descriptor-stat-at (param i32 i32 i32 i32 i32)))
+  (import "wasi:filesystem/types@0.2.1" "[resource-drop]descriptor" (func $drop-descriptor (param i32)))
+{% endif %}
+{% if fetch %}
+
+  ;; only imported when the script uses fetch, see fetch.wat. Results that
+  ;; don't fit in a single value are written to the memory at the last
+  ;; parameter
+  (import "wasi:http/types@0.2.1" "[constructor]fields" (func $http-new-fields (result i32)))
+  (import "wasi:http/types@0.2.1" "[method]fields.append" (func $http-fields-append (param i32 i32 i32 i32 i32 i32)))
+  (import "wasi:http/types@0.2.1" "[method]fields.entries" (func $http-fields-entries (param i32 i32)))
+  (import "wasi:http/types@0.2.1" "[resource-drop]fields" (func $drop-http-fields (param i32)))
+  (import "wasi:http/types@0.2.1" "[constructor]outgoing-request" (func $http-new-outgoing-request (param i32) (result i32)))
+  (import "wasi:http/types@0.2.1" "[method]outgoing-request.set-method" (func $http-request-set-method (param i32 i32 i32 i32) (result i32)))
+  (import "wasi:http/types@0.2.1" "[method]outgoing-request.set-scheme" (func $http-request-set-scheme (param i32 i32 i32 i32 i32) (result i32)))
+  (import "wasi:http/types@0.2.1" "[method]outgoing-request.set-authority" (func $http-request-set-authority (param i32 i32 i32 i32) (result i32)))
+  (import "wasi:http/types@0.2.1" "[method]outgoing-request.set-path-with-query" (func $http-request-set-path-with-query (param i32 i32 i32 i32) (result i32)))
+  (import "wasi:http/types@0.2.1" "[method]outgoing-request.body" (func $http-outgoing-request-body (param i32 i32)))
+  (import "wasi:http/types@0.2.1" "[resource-drop]outgoing-request" (func $drop-http-outgoing-request (param i32)))
+  (import "wasi:http/types@0.2.1" "[method]outgoing-body.write" (func $http-outgoing-body-write (param i32 i32)))
+  (import "wasi:http/types@0.2.1" "[static]outgoing-body.finish" (func $http-outgoing-body-finish (param i32 i32 i32 i32)))
+  (import "wasi:http/types@0.2.1" "[resource-drop]outgoing-body" (func $drop-http-outgoing-body (param i32)))
+  (import "wasi:http/types@0.2.1" "[method]future-incoming-response.subscribe" (func $http-future-response-subscribe (param i32) (result i32)))
+  (import "wasi:http/types@0.2.1" "[method]future-incoming-response.get" (func $http-future-response-get (param i32 i32)))
+  (import "wasi:http/types@0.2.1" "[resource-drop]future-incoming-response" (func $drop-http-future-response (param i32)))
+  (import "wasi:http/types@0.2.1" "[method]incoming-response.status" (func $http-incoming-response-status (param i32) (result i32)))
+  (import "wasi:http/types@0.2.1" "[method]incoming-response.headers" (func $http-incoming-response-headers (param i32) (result i32)))
+  (import "wasi:http/types@0.2.1" "[method]incoming-response.consume" (func $http-incoming-response-consume (param i32 i32)))
+  (import "wasi:http/types@0.2.1" "[resource-drop]incoming-response" (func $drop-http-incoming-response (param i32)))
+  (import "wasi:http/types@0.2.1" "[method]incoming-body.stream" (func $http-incoming-body-stream (param i32 i32)))
+  (import "wasi:http/types@0.2.1" "[resource-drop]incoming-body" (func $drop-http-incoming-body (param i32)))
+  (import "wasi:http/outgoing-handler@0.2.1" "handle" (func $http-handle (param i32 i32 i32 i32)))
+
+  (import "wasi:io/streams@0.2.1" "[method]output-stream.blocking-write-and-flush" (func $output-stream-blocking-write-and-flush (param i32 i32 i32 i32)))
+  (import "wasi:io/streams@0.2.1" "[resource-drop]output-stream" (func $drop-output-stream (param i32)))
+{% endif %}
+{% if stdin %}
+
+  ;; only imported when the script uses `process.stdin`, see stdin.wat
+  (import "wasi:cli/stdin@0.2.1" "get-stdin" (func $get-stdin (result i32)))
+{% endif %}
+{% if fetch or stdin %}
+
+  (import "wasi:io/streams@0.2.1" "[method]input-stream.read" (func $input-stream-read (param i32 i64 i32)))
+  (import "wasi:io/streams@0.2.1" "[method]input-stream.subscribe" (func $input-stream-subscribe (param i32) (result i32)))
+  (import "wasi:io/streams@0.2.1" "[resource-drop]input-stream" (func $drop-input-stream (param i32)))
+  (import "wasi:io/error@0.2.1" "[resource-drop]error" (func $drop-io-error (param i32)))
+{% endif %}
 
   ;; 64KB
   (memory (export "memory") 1)
@@ -19,35 +98,21 @@
 
   (global $free_memory_offset (mut i32) (i32.const {{free_memory_offset}}))
   (global $scope (mut (ref null $Scope)) (ref.null $Scope))
+  ;; the global object shares its properties with the variables of the root
+  ;; scope, so builtins and top-level `var`s are visible on both
+  (global $global-object (mut (ref null $Object)) (ref.null $Object))
 
   (data (i32.const 0) "\n")
 
   ;; Types that can be passed as reference types:
-  ;; i31ref 0 - false
-  ;; i31ref 1 - true
-  ;; i31ref 2 - null
-  ;; null     - undefined
+  ;; i31ref         - integer numbers that fit in 31 bits
+  ;; (ref $Number)  - all the other numbers
+  ;; $true, $false  - booleans, singletons of $Boolean
+  ;; $null          - null
+  ;; null           - undefined
 
   {{ data_entries }}
 
-  ;; Memory management functions required by the Component Model
-  (func $cabi_realloc (export "cabi_realloc")
-    (param $old_ptr i32) (param $old_size i32) (param $align i32) (param $new_size i32)
-    (result i32)
-    (local $ptr i32)
-    
-    ;; Simple bump allocator for this example
-    (local.set $ptr (i32.const 1024))
-    (local.get $ptr)
-  )
-
-  (func $canonical_abi_free (export "canonical_abi_free")
-    (param $ptr i32) (param $size i32)
-    ;; TODO: implement something sensinle here or find examples on how
-    ;; it's usually done
-  )
-
-
   (type $CharArray (array (mut i8)))
 
   (type $String (struct
@@ -83,10 +148,16 @@
     (field $size (mut i32))
   ))
 
+  (type $AnyrefArray (array (mut anyref)))
+
+  ;; Variables resolved at compile time are stored in $slots, at an index known
+  ;; to the compiler. The hash maps are only created for the global scope and
+  ;; for functions using `eval` or `with`, where variables are looked up by name
   (type $Scope (struct
     (field $parent (mut (ref null $Scope)))
-    (field $variables (mut (ref $HashMap)))
-    (field $var_types (mut (ref $HashMapI32)))
+    (field $slots (ref $AnyrefArray))
+    (field $variables (mut (ref null $HashMap)))
+    (field $var_types (mut (ref null $HashMapI32)))
   ))
 
   (type $JSArgs (array (mut anyref)))
@@ -105,23 +176,41 @@
     (field $func (mut (ref $JSFunc)))
     (field $this (mut anyref)) ;; some functions have a default this
     (field $properties (mut (ref $HashMap)))
+    (field $hash (mut i32))
   ))
 
-  (type $Object (struct
+  ;; builtin objects with internal state (like Map or Set) are subtypes of
+  ;; $Object, so they can be used anywhere a plain object can
+  (type $Object (sub (struct
     (field $properties (mut (ref $HashMap)))
     (field $prototype (mut anyref))
-  ))
+    ;; identity hash, assigned when the object is first used as a Map key
+    (field $hash (mut i32))
+  )))
 
   (type $Number (struct 
     (field $value (mut f64)))
   )
 
-  (type $AnyrefArray (array (mut anyref)))
+  (type $Boolean (struct
+    (field $value i32))
+  )
+
+  (type $Null (struct))
+
+  (global $false (ref $Boolean) (struct.new $Boolean (i32.const 0)))
+  (global $true (ref $Boolean) (struct.new $Boolean (i32.const 1)))
+  (global $null (ref $Null) (struct.new $Null))
+  ;; returned by lookups to differentiate a missing value from one set to undefined
+  (global $no-value (ref $Null) (struct.new $Null))
 
   ;; at the moment it doesn't have to be a struct, but in the future
   ;; we will need support for ptototype and properties and what not
   (type $Array (struct
     (field $array (mut (ref $AnyrefArray)))
+    ;; named properties, like `index` on RegExp matches, created on first use
+    (field $properties (mut (ref null $HashMap)))
+    (field $hash (mut i32))
   ))
 
   (type $PollableFunction
@@ -132,13 +221,6 @@
     )
   )
 
-  (type $Pollable (struct
-    (field $id i32)
-    (field $func (mut anyref))
-  ))
-
-  (type $PollablesArray (array (mut (ref null $Pollable))))
-
   (type $FunctionArray (array (mut (ref null $Function))))
   (rec
     (type $PromisesArray (array (mut (ref null $Promise))))
@@ -153,18 +235,21 @@
       (field $resolved (mut i32))
       (field $errored (mut i32))
       (field $chained-promises (mut (ref $PromisesArray)))
+      (field $hash (mut i32))
     ))
   )
 
-  (global $pollables (mut (ref $PollablesArray))
-      (array.new $PollablesArray (ref.null $Pollable) (i32.const 2)))
+  (type $PromiseResolvers (struct
+    (field $promise (ref $Promise))
+    (field $already-resolved (mut i32))
+  ))
 
-  (func $new-pollable (param $id i32) (param $func anyref) (result (ref $Pollable))
-    (struct.new $Pollable
-      (local.get $id)
-      (local.get $func)
-    )
-  )
+  (type $Microtask (struct
+    (field $kind i32)
+    (field $function anyref)
+    (field $argument anyref)
+    (field $promise (ref null $Promise))
+  ))
 
   (global $promise-prototype (mut (ref null $Object)) (ref.null $Object))
 
@@ -182,6 +267,7 @@
       (i32.const 0)
       (i32.const 0)
       (array.new $PromisesArray (ref.null $Promise) (i32.const 1))
+      (i32.const 0)
     )
   )
 
@@ -224,13 +310,6 @@
     (local.set $new-promise (call $new-promise))
     (local.set $promise (ref.cast (ref $Promise) (local.get $this)))
 
-    (array.len (local.get $arguments))
-    (i32.eqz)
-    (if (then
-      ;; no functions to register, just return an empty promise
-      (return (local.get $new-promise))
-    ))
-
     (array.len (local.get $arguments))
     (i32.const 0)
     (i32.gt_s)
@@ -272,20 +351,22 @@
             )
           )
           (else
-            ;; it errored already, evaluate on-rejected
-            (local.get $scope)
-            (struct.get $Promise $error-result (local.get $promise))
-            (local.get $new-promise)
-            (call $evaluate-rejected)
+            ;; it errored already, so on-rejected runs as a microtask
+            (call $enqueue-microtask
+              (global.get $microtask-rejected)
+              (ref.null any)
+              (struct.get $Promise $error-result (local.get $promise))
+              (local.get $new-promise))
           )
         )
       )
       (else
-        ;; if it's already resolved, evaluate onresolved
-        (local.get $scope)
-        (struct.get $Promise $success-result (local.get $promise))
-        (local.get $new-promise)
-        (call $evaluate-resolved)
+        ;; it's already resolved, so on-resolved runs as a microtask
+        (call $enqueue-microtask
+          (global.get $microtask-fulfilled)
+          (ref.null any)
+          (struct.get $Promise $success-result (local.get $promise))
+          (local.get $new-promise))
       )
     )
 
@@ -309,13 +390,6 @@
     (local.set $new-promise (call $new-promise))
     (local.set $promise (ref.cast (ref $Promise) (local.get $this)))
 
-    (array.len (local.get $arguments))
-    (i32.eqz)
-    (if (then
-      ;; no functions to register, just return an empty promise
-      (return (local.get $new-promise))
-    ))
-
     (array.len (local.get $arguments))
     (i32.const 0)
     (i32.gt_s)
@@ -342,26 +416,29 @@
             )
           )
           (else
-            ;; it errored already, evaluate on-rejected
-            (local.get $scope)
-            (struct.get $Promise $error-result (local.get $promise))
-            (local.get $new-promise)
-            (call $evaluate-rejected)
+            ;; it errored already, so on-rejected runs as a microtask
+            (call $enqueue-microtask
+              (global.get $microtask-rejected)
+              (ref.null any)
+              (struct.get $Promise $error-result (local.get $promise))
+              (local.get $new-promise))
           )
         )
       )
       (else
-        ;; if it's already resolved, evaluate onresolved
-        (local.get $scope)
-        (struct.get $Promise $success-result (local.get $promise))
-        (local.get $new-promise)
-        (call $evaluate-resolved)
+        ;; it's already resolved, so on-resolved runs as a microtask
+        (call $enqueue-microtask
+          (global.get $microtask-fulfilled)
+          (ref.null any)
+          (struct.get $Promise $success-result (local.get $promise))
+          (local.get $new-promise))
       )
     )
 
     (return (local.get $new-promise))
   )
 
+  ;; catch(onRejected) is then(undefined, onRejected)
   (func $Promise-catch
     (type $JSFunc)
     (param $scope (ref $Scope))
@@ -369,38 +446,12 @@
     (param $arguments (ref $JSArgs))
     (result anyref)
 
-    (local $resolver (ref $Function))
-    (local $new-promise (ref $Promise))
-    (local $promise (ref $Promise))
-    (local $callback-anyref anyref)
-    (local $callback (ref $Function))
-    (local $callback-arguments (ref $JSArgs))
-
-    (local.set $new-promise (call $new-promise))
-    (local.set $promise (ref.cast (ref $Promise) (local.get $this)))
-
-    (array.len (local.get $arguments))
-    (i32.eqz)
-    (if (then
-      ;; no functions to register, just return an empty promise
-      (return (local.get $new-promise))
-    ))
-
-    (array.len (local.get $arguments))
-    (i32.const 0)
-    (i32.gt_s)
-    (if (then
-      ;; we have at least one argument, just pass it as a second argument to `then`
-      (ref.null any)
-      (array.get $JSArgs (local.get $arguments) (i32.const 0))
-      (call $create-arguments-2)
-      (local.set $arguments)
-
-      (call $Promise-then (local.get $scope) (local.get $new-promise) (local.get $arguments))
-      (drop)
-    ))
-
-    (return (local.get $new-promise))
+    (call $Promise-then
+      (local.get $scope)
+      (local.get $this)
+      (call $create-arguments-2
+        (ref.null any)
+        (call $get_argument (local.get $arguments) (i32.const 0))))
   )
 
   (func $evaluate-resolved (param $scope (ref $Scope)) (param $previous-result anyref) (param $promise (ref $Promise))
@@ -439,17 +490,10 @@
         )
       )
 
-      ;; reuse $arguments
-      (array.set $JSArgs (local.get $arguments) (i32.const 0) (local.get $result))
-
-      (call $Promise-resolve (local.get $scope) (local.get $promise) (local.get $arguments))
-      (drop)
+      (call $resolve-promise (local.get $promise) (local.get $result))
     catch $JSException
-      (call $create-arguments-1)
-      (local.set $arguments)
-
-      (call $Promise-reject (local.get $scope) (local.get $promise) (local.get $arguments))
-      (drop)
+      (local.set $result)
+      (call $reject-promise (local.get $promise) (local.get $result))
     end
   )
 
@@ -484,27 +528,17 @@
           (call $call_function)
           (local.set $result)
 
-          ;; reuse $arguments
-          (array.set $JSArgs (local.get $arguments) (i32.const 0) (local.get $result))
-
           ;; if there was no errors we resolve and not reject
-          (call $Promise-resolve (local.get $scope) (local.get $promise) (local.get $arguments))
-          (drop)
+          (call $resolve-promise (local.get $promise) (local.get $result))
         )
         (else
-          (array.set $JSArgs (local.get $arguments) (i32.const 0) (local.get $previous-result))
-
           ;; there was no onrejected callback, so we pass further
-          (call $Promise-reject (local.get $scope) (local.get $promise) (local.get $arguments))
-          (drop)
+          (call $reject-promise (local.get $promise) (local.get $previous-result))
         )
       )
     catch $JSException
-      (call $create-arguments-1)
-      (local.set $arguments)
-
-      (call $Promise-reject (local.get $scope) (local.get $promise) (local.get $arguments))
-      (drop)
+      (local.set $result)
+      (call $reject-promise (local.get $promise) (local.get $result))
     end
   )
 
@@ -558,134 +592,177 @@
     (call $add-to-promise-chain (local.get $target-promise) (local.get $promise))
   )
 
-  (func $Promise-resolve
-    (type $JSFunc)
-    (param $scope (ref $Scope))
-    (param $this anyref)
-    (param $arguments (ref $JSArgs))
-    (result anyref)
+  ;; fulfills the promise, its reactions run later as microtasks
+  (func $fulfill-promise (param $promise (ref $Promise)) (param $value anyref)
+    (struct.set $Promise $success-result (local.get $promise) (local.get $value))
+    (struct.set $Promise $resolved (local.get $promise) (i32.const 1))
+    (call $trigger-reactions (local.get $promise) (global.get $microtask-fulfilled) (local.get $value))
+  )
 
-    (local $resolve-result anyref)
-    (local $promise (ref $Promise))
-    (local $current-promise (ref $Promise))
+  (func $reject-promise (param $promise (ref $Promise)) (param $reason anyref)
+    (struct.set $Promise $error-result (local.get $promise) (local.get $reason))
+    (struct.set $Promise $errored (local.get $promise) (i32.const 1))
+    (call $trigger-reactions (local.get $promise) (global.get $microtask-rejected) (local.get $reason))
+  )
+
+  (func $trigger-reactions (param $promise (ref $Promise)) (param $kind i32) (param $value anyref)
     (local $promises (ref $PromisesArray))
     (local $i i32)
-    (local $function (ref $Function))
-    (local $callback-arguments (ref $JSArgs))
-    (local $len i32)
 
-    (local.set $i (i32.const 0))
+    (local.set $promises (struct.get $Promise $chained-promises (local.get $promise)))
+    (block $break (loop $reactions
+      (br_if $break (i32.ge_u (local.get $i) (array.len (local.get $promises))))
+
+      (if (i32.eqz (ref.is_null (array.get $PromisesArray (local.get $promises) (local.get $i))))
+        (then
+          (call $enqueue-microtask
+            (local.get $kind)
+            (ref.null any)
+            (local.get $value)
+            (array.get $PromisesArray (local.get $promises) (local.get $i)))
+        )
+      )
 
-    (ref.cast (ref $Promise) (local.get $this))
-    (local.set $promise)
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $reactions)
+    ))
+  )
 
-    (struct.get $Promise $chained-promises (local.get $promise))
-    (local.set $promises)
+  ;; resolves the promise with a value, which is the steps of Promise Resolve
+  ;; Functions after the "already resolved" check: thenables are adopted by
+  ;; calling their `then` in a microtask, anything else fulfills the promise
+  (func $resolve-promise (param $promise (ref $Promise)) (param $resolution anyref)
+    (local $then anyref)
 
-    (if (i32.eqz (array.len (local.get $arguments)))
+    (if (ref.eq (local.get $promise) (ref.cast eqref (local.get $resolution)))
       (then
-        ;; no arguments, so result is undefined
-        (local.set $resolve-result (ref.null any))
+        (call $reject-promise
+          (local.get $promise)
+          (call $new_error
+            (global.get $type-error-prototype)
+            (call $new_static_string (i32.const {{ data(str="Chaining cycle detected for promise #<Promise>") }}) (i32.const {{ data_length() }}))))
+        (return)
       )
-      (else
-        (array.get $JSArgs (local.get $arguments) (i32.const 0))
-        (local.set $resolve-result)
+    )
+
+    (if (i32.eqz (call $is_object (local.get $resolution)))
+      (then
+        (call $fulfill-promise (local.get $promise) (local.get $resolution))
+        (return)
       )
     )
 
-    ;; TODO: handle errors
-    (local.get $promise)
-    (local.get $resolve-result)
-    (struct.set $Promise $success-result)
+    try
+      (local.set $then (call $get_property (local.get $resolution) (i32.const {{ data(str="then") }})))
+    catch $JSException
+      (local.set $then)
+      (call $reject-promise (local.get $promise) (local.get $then))
+      (return)
+    end
 
-    (local.get $promise)
-    (i32.const 1)
-    (struct.set $Promise $resolved)
+    (if (i32.eqz (ref.test (ref $Function) (local.get $then)))
+      (then
+        (call $fulfill-promise (local.get $promise) (local.get $resolution))
+        (return)
+      )
+    )
 
-    (local.set $len (array.len (local.get $promises)))
-    (block $break (loop $find
-      (br_if $break (i32.ge_u (local.get $i) (local.get $len)))
+    (call $enqueue-microtask
+      (global.get $microtask-thenable)
+      (local.get $then)
+      (local.get $resolution)
+      (local.get $promise))
+  )
 
-      (if (i32.eqz (ref.test nullref (array.get $PromisesArray (local.get $promises) (local.get $i))))
-        (then
-          (array.get $PromisesArray (local.get $promises) (local.get $i))
-          (ref.cast (ref $Promise))
-          (local.set $promise)
+  ;; the resolve and reject functions given to an executor or to a thenable's
+  ;; `then` share $PromiseResolvers through their scope, only the first call of
+  ;; either of them counts
+  (func $create-resolving-functions (param $promise (ref $Promise)) (result (ref $JSArgs))
+    (local $environment (ref $Scope))
 
-          (call $evaluate-resolved (local.get $scope) (local.get $resolve-result) (local.get $promise))
-        ))
+    (local.set $environment (call $new_environment (ref.cast (ref $Scope) (global.get $scope)) (i32.const 1)))
+    (array.set $AnyrefArray (struct.get $Scope $slots (local.get $environment)) (i32.const 0)
+      (struct.new $PromiseResolvers (local.get $promise) (i32.const 0)))
+    (call $create-arguments-2
+      (call $new_function (local.get $environment) (ref.func $promise-resolve-function) (ref.null any))
+      (call $new_function (local.get $environment) (ref.func $promise-reject-function) (ref.null any)))
+  )
 
-      (local.set $i (i32.add (local.get $i) (i32.const 1)))
-      (br $find)
-    ))
- 
-    ;; return undefined
-    (ref.null any)
+  ;; returns the promise if the resolving functions weren't called yet, and
+  ;; marks them as called
+  (func $take-resolvers (param $scope (ref $Scope)) (result (ref null $Promise))
+    (local $shared (ref $PromiseResolvers))
+
+    (local.set $shared
+      (ref.cast (ref $PromiseResolvers)
+        (array.get $AnyrefArray (struct.get $Scope $slots (local.get $scope)) (i32.const 0))))
+    (if (struct.get $PromiseResolvers $already-resolved (local.get $shared))
+      (then (return (ref.null $Promise)))
+    )
+    (struct.set $PromiseResolvers $already-resolved (local.get $shared) (i32.const 1))
+    (struct.get $PromiseResolvers $promise (local.get $shared))
   )
 
-  (func $Promise-reject
+  (func $promise-resolve-function
     (type $JSFunc)
     (param $scope (ref $Scope))
     (param $this anyref)
     (param $arguments (ref $JSArgs))
     (result anyref)
+    (local $promise (ref null $Promise))
 
-    (local $reject-result anyref)
-    (local $promise (ref $Promise))
-    (local $current-promise (ref $Promise))
-    (local $promises (ref $PromisesArray))
-    (local $i i32)
-    (local $function (ref $Function))
-    (local $callback-arguments (ref $JSArgs))
-    (local $len i32)
-
-    (local.set $i (i32.const 0))
-
-    (ref.cast (ref $Promise) (local.get $this))
-    (local.set $promise)
-
-    (struct.get $Promise $chained-promises (local.get $promise))
-    (local.set $promises)
-
-    (if (i32.eqz (array.len (local.get $arguments)))
+    (local.set $promise (call $take-resolvers (local.get $scope)))
+    (if (i32.eqz (ref.is_null (local.get $promise)))
       (then
-        ;; no arguments, so result is undefined
-        (local.set $reject-result (ref.null any))
-      )
-      (else
-        (array.get $JSArgs (local.get $arguments) (i32.const 0))
-        (local.set $reject-result)
+        (call $resolve-promise
+          (ref.as_non_null (local.get $promise))
+          (call $get_argument (local.get $arguments) (i32.const 0)))
       )
     )
 
-    (local.get $promise)
-    (local.get $reject-result)
-    (struct.set $Promise $error-result)
-
-    (local.get $promise)
-    (i32.const 1)
-    (struct.set $Promise $errored)
+    ;; return undefined
+    (ref.null any)
+  )
 
-    (local.set $len (array.len (local.get $promises)))
-    (block $break (loop $find
-      (br_if $break (i32.ge_u (local.get $i) (local.get $len)))
+  (func $promise-reject-function
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (local $promise (ref null $Promise))
 
-      (if (i32.eqz (ref.test nullref (array.get $PromisesArray (local.get $promises) (local.get $i))))
-        (then
-          (array.get $PromisesArray (local.get $promises) (local.get $i))
-          (ref.cast (ref $Promise))
-          (local.set $promise)
+    (local.set $promise (call $take-resolvers (local.get $scope)))
+    (if (i32.eqz (ref.is_null (local.get $promise)))
+      (then
+        (call $reject-promise
+          (ref.as_non_null (local.get $promise))
+          (call $get_argument (local.get $arguments) (i32.const 0)))
+      )
+    )
 
-          (call $evaluate-rejected (local.get $scope) (local.get $reject-result) (local.get $promise))
-        ))
-      
-      (local.set $i (i32.add (local.get $i) (i32.const 1)))
-      (br $find)
-    ))
- 
     ;; return undefined
     (ref.null any)
+  )
+
+  ;; calls an executor, or a thenable's `then`, with resolving functions for
+  ;; the promise, if it throws before they're called the promise is rejected
+  (func $call-with-resolving-functions (param $function (ref $Function)) (param $this anyref) (param $promise (ref $Promise))
+    (local $arguments (ref $JSArgs))
+    (local $error anyref)
 
+    (local.set $arguments (call $create-resolving-functions (local.get $promise)))
+    try
+      (call $call_function (local.get $function) (local.get $this) (local.get $arguments))
+      (drop)
+    catch $JSException
+      (local.set $error)
+      (drop
+        (call $call_function
+          (array.get $JSArgs (local.get $arguments) (i32.const 1))
+          (ref.null any)
+          (call $create-arguments-1 (local.get $error))))
+    end
   )
 
   (func $Promise-constructor
@@ -695,57 +772,163 @@
     (param $arguments (ref $JSArgs))
     (result anyref)
 
-    (local $resolver (ref $Function))
     (local $arg1 anyref)
-    (local $callback-arguments (ref $JSArgs))
     (local $promise (ref $Promise))
-    (local $error anyref)
 
     (local.set $promise (call $new-promise))
 
     (array.len (local.get $arguments))
     (i32.eqz)
     (if (then
-      (call $new_static_string (i32.const {{ data(str="Promise resolver undefined is not a function") }}) (i32.const {{ data_length() }}))
-      (throw $JSException)
+      (call $throw_type_error
+        (call $new_static_string (i32.const {{ data(str="Promise resolver undefined is not a function") }}) (i32.const {{ data_length() }})))
     ))
 
     (array.get $JSArgs (local.get $arguments) (i32.const 0))
     (local.tee $arg1)
     (if (ref.test (ref $Function))
       (then
-        (call $new_function (ref.cast (ref $Scope) (global.get $scope)) (ref.func $Promise-resolve) (local.get $promise))
-        (call $new_function (ref.cast (ref $Scope) (global.get $scope)) (ref.func $Promise-reject) (local.get $promise))
-        (call $create-arguments-2)
-        (local.set $callback-arguments)
-
-        try
+        (call $call-with-resolving-functions
           (ref.cast (ref $Function) (local.get $arg1))
-          (ref.null any) ;; this
-          (local.get $callback-arguments)
-          (call $call_function)
-          (drop)
-        catch $JSException
-          (local.tee $error)
-          (call $create-arguments-1)
-          (local.set $arguments)
-
-          (call $Promise-reject (local.get $scope) (local.get $promise) (local.get $arguments))
-          (drop)
-        end
+          (ref.null any)
+          (local.get $promise))
 
         (return (local.get $promise))
       )
       (else
         ;; TODO: this need to create a dynamic string and convert the argument to string
-        (call $new_static_string (i32.const {{ data(str="Promise resolver is not a function") }}) (i32.const {{ data_length() }}))
-        (throw $JSException)
+        (call $throw_type_error
+          (call $new_static_string (i32.const {{ data(str="Promise resolver is not a function") }}) (i32.const {{ data_length() }})))
       )
     )
 
     (return (ref.null any))
   )
 
+  ;; -- microtasks --
+  ;; promise reactions and queueMicrotask callbacks don't run right away, they
+  ;; are queued and run in order once the script, or the callback of a timer,
+  ;; is done
+
+  (global $microtask-fulfilled i32 (i32.const 0))
+  (global $microtask-rejected i32 (i32.const 1))
+  (global $microtask-callback i32 (i32.const 2))
+  (global $microtask-thenable i32 (i32.const 3))
+
+  (global $microtasks (mut (ref null $ValueList)) (ref.null $ValueList))
+  (global $next-microtask (mut i32) (i32.const 0))
+
+  ;; the meaning of the fields depends on the kind:
+  ;;   fulfilled/rejected - the reactions of $promise run with $argument
+  ;;   callback - $function is called without arguments
+  ;;   thenable - $promise adopts the state of $argument by calling its `then`,
+  ;;              which is $function
+  (func $enqueue-microtask (param $kind i32) (param $function anyref) (param $argument anyref) (param $promise (ref null $Promise))
+    (if (ref.is_null (global.get $microtasks))
+      (then (global.set $microtasks (call $new_value_list)))
+    )
+    (call $value_list_push
+      (ref.as_non_null (global.get $microtasks))
+      (struct.new $Microtask
+        (local.get $kind)
+        (local.get $function)
+        (local.get $argument)
+        (local.get $promise)))
+  )
+
+  (func $run-microtask (param $task (ref $Microtask))
+    (local $kind i32)
+
+    (local.set $kind (struct.get $Microtask $kind (local.get $task)))
+    (if (i32.eq (local.get $kind) (global.get $microtask-fulfilled))
+      (then
+        (call $evaluate-resolved
+          (ref.cast (ref $Scope) (global.get $scope))
+          (struct.get $Microtask $argument (local.get $task))
+          (ref.as_non_null (struct.get $Microtask $promise (local.get $task))))
+        (return)
+      )
+    )
+    (if (i32.eq (local.get $kind) (global.get $microtask-rejected))
+      (then
+        (call $evaluate-rejected
+          (ref.cast (ref $Scope) (global.get $scope))
+          (struct.get $Microtask $argument (local.get $task))
+          (ref.as_non_null (struct.get $Microtask $promise (local.get $task))))
+        (return)
+      )
+    )
+    (if (i32.eq (local.get $kind) (global.get $microtask-thenable))
+      (then
+        (call $call-with-resolving-functions
+          (ref.cast (ref $Function) (struct.get $Microtask $function (local.get $task)))
+          (struct.get $Microtask $argument (local.get $task))
+          (ref.as_non_null (struct.get $Microtask $promise (local.get $task))))
+        (return)
+      )
+    )
+    (drop
+      (call $call_function
+        (ref.cast (ref $Function) (struct.get $Microtask $function (local.get $task)))
+        (ref.null any)
+        (array.new $JSArgs (ref.null any) (i32.const 0))))
+  )
+
+  ;; runs microtasks until the queue is empty, including the ones queued by
+  ;; the microtasks themselves
+  (func $run-microtasks
+    (local $queue (ref $ValueList))
+    (local $task anyref)
+
+    (if (ref.is_null (global.get $microtasks))
+      (then (return))
+    )
+    (local.set $queue (ref.as_non_null (global.get $microtasks)))
+
+    (block $break (loop $run
+      (br_if $break
+        (i32.ge_u (global.get $next-microtask) (struct.get $ValueList $size (local.get $queue))))
+
+      (local.set $task
+        (array.get $AnyrefArray (struct.get $ValueList $data (local.get $queue)) (global.get $next-microtask)))
+      ;; don't keep finished tasks alive
+      (array.set $AnyrefArray
+        (struct.get $ValueList $data (local.get $queue))
+        (global.get $next-microtask)
+        (ref.null any))
+      (global.set $next-microtask (i32.add (global.get $next-microtask) (i32.const 1)))
+
+      (call $run-microtask (ref.cast (ref $Microtask) (local.get $task)))
+      (br $run)
+    ))
+
+    (struct.set $ValueList $size (local.get $queue) (i32.const 0))
+    (global.set $next-microtask (i32.const 0))
+  )
+
+  (func $queueMicrotask
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (local $callback anyref)
+
+    (local.set $callback (call $get_argument (local.get $arguments) (i32.const 0)))
+    (if (i32.eqz (ref.test (ref $Function) (local.get $callback)))
+      (then
+        (call $throw_type_error
+          (call $new_static_string (i32.const {{ data(str='The "callback" argument must be of type function') }}) (i32.const {{ data_length() }})))
+      )
+    )
+    (call $enqueue-microtask
+      (global.get $microtask-callback)
+      (local.get $callback)
+      (ref.null any)
+      (ref.null $Promise))
+    (ref.null any)
+  )
+
   (func $create-arguments-1 (param $arg1 anyref) (result (ref $JSArgs))
     (local $arguments (ref $JSArgs))
     (array.new $JSArgs (ref.null any) (i32.const 1))
@@ -786,6 +969,64 @@
 
   {{additional_functions}}
 
+  {% include "memory.wat" %}
+
+  {% include "strings.wat" %}
+
+  {% include "object.wat" %}
+
+  {% include "error.wat" %}
+
+  {% include "symbol.wat" %}
+
+  {% include "bignum.wat" %}
+
+  {% include "number.wat" %}
+
+  {% include "math.wat" %}
+
+  {% include "json.wat" %}
+
+  {% include "collections.wat" %}
+
+  {% include "regexp.wat" %}
+
+  {% include "date.wat" %}
+
+  {% include "bigint.wat" %}
+
+  {% include "typedarray.wat" %}
+
+  {% include "proxy.wat" %}
+
+  {% include "console.wat" %}
+
+  {% include "promise.wat" %}
+
+  {% include "pollables.wat" %}
+
+  {% include "timers.wat" %}
+{% if fetch %}
+
+  {% include "fetch.wat" %}
+{% endif %}
+{% if process %}
+
+  {% include "process.wat" %}
+{% endif %}
+{% if stdin %}
+
+  {% include "stdin.wat" %}
+{% endif %}
+{% if filesystem %}
+
+  {% include "fs.wat" %}
+{% endif %}
+{% if crypto %}
+
+  {% include "crypto.wat" %}
+{% endif %}
+
   ;; TODO: we could use data from (data) entries for creating strings, but in order
   ;; to do that there would have to be a function with mapping between data labels
   ;; and offsets, cause it's not possible to pass a data label to a function
@@ -919,13 +1160,16 @@
   (func $new_object (result (ref $Object))
     (struct.new $Object
       (call $new_hashmap)
-      (ref.null any)
+      (global.get $object-prototype)
+      (i32.const 0)
     )
   )
 
   (func $new_array (param $size i32) (result (ref $Array))
     (struct.new $Array
       (array.new $AnyrefArray (ref.null any) (local.get $size))
+      (ref.null $HashMap)
+      (i32.const 0)
     )
   )
 
@@ -940,48 +1184,89 @@
       (local.get $function)
       (local.get $this)
       (call $new_hashmap)
+      (i32.const 0)
     )
   )
 
-  (func $new_number (param $number f64) (result (ref $Number))
+  ;; integers that fit in 31 bits are stored unboxed as i31ref, which doesn't
+  ;; need an allocation, everything else (including -0) is boxed in $Number
+  (func $new_number (param $number f64) (result (ref eq))
+    (local $int i32)
+    (local.set $int (i32.trunc_sat_f64_s (local.get $number)))
+    (if (i32.and
+          (i32.and
+            (f64.eq (f64.convert_i32_s (local.get $int)) (local.get $number))
+            (i32.eq (i32.shr_s (i32.shl (local.get $int) (i32.const 1)) (i32.const 1)) (local.get $int)))
+          (i32.or
+            (local.get $int)
+            (i64.eqz (i64.reinterpret_f64 (local.get $number)))))
+      (then
+        (return (ref.i31 (local.get $int)))
+      )
+    )
     (struct.new $Number
       (local.get $number)
     )
   )
 
-  (func $new_boolean (param $bool i32) (result i31ref)
-    (ref.i31 (local.get $bool))
-    return
-  )
-
-  (func $cast_ref_to_i32_bool (param $arg anyref) (result i32)
-    (local $res i32)
-    (if (ref.test nullref (local.get $arg))
+  ;; creates a number from an i32 result, staying unboxed if it fits in 31 bits
+  (func $new_number_i32 (param $number i32) (result (ref eq))
+    (if (i32.eq (i32.shr_s (i32.shl (local.get $number) (i32.const 1)) (i32.const 1)) (local.get $number))
       (then
-        (return (i32.const 0))
+        (return (ref.i31 (local.get $number)))
       )
     )
-    (if (ref.test i31ref (local.get $arg))
-      (then
-        (i31.get_s (ref.cast (ref null i31) (local.get $arg)))
-        (local.set $res)
-        (if (i32.eq (local.get $res) (i32.const 1))
-          ;; boolean true
-          (then
-            (return (i32.const 1))
-          )
-        )
-        ;; anything else is false
+    (struct.new $Number
+      (f64.convert_i32_s (local.get $number))
+    )
+  )
+
+  (func $is_number (param $arg anyref) (result i32)
+    (i32.or
+      (ref.test (ref i31) (local.get $arg))
+      (ref.test (ref $Number) (local.get $arg)))
+  )
+
+  ;; returns the value of a number, the argument has to pass $is_number
+  (func $number_value (param $arg anyref) (result f64)
+    (if (ref.test (ref i31) (local.get $arg))
+      (then
+        (return (f64.convert_i32_s (i31.get_s (ref.cast (ref i31) (local.get $arg)))))
+      )
+    )
+    (struct.get $Number $value (ref.cast (ref $Number) (local.get $arg)))
+  )
+
+  (func $new_boolean (param $bool i32) (result (ref $Boolean))
+    (if (result (ref $Boolean)) (local.get $bool)
+      (then (global.get $true))
+      (else (global.get $false)))
+  )
+
+  (func $cast_ref_to_i32_bool (param $arg anyref) (result i32)
+    (local $value f64)
+    (if (ref.test nullref (local.get $arg))
+      (then
         (return (i32.const 0))
       )
     )
+    (if (ref.test (ref $Boolean) (local.get $arg))
+      (then
+        (return (struct.get $Boolean $value (ref.cast (ref $Boolean) (local.get $arg))))
+      )
+    )
+    (if (ref.test (ref i31) (local.get $arg))
+      (then
+        (return (i32.ne (i31.get_s (ref.cast (ref i31) (local.get $arg))) (i32.const 0)))
+      )
+    )
     (if (ref.test (ref $Number) (local.get $arg))
       (then
-        (return (i32.eqz (f64.eq
-            (struct.get $Number 0 (ref.cast (ref $Number) (local.get $arg)))
-            (f64.const 0)
-          ))
-        )
+        (local.set $value (struct.get $Number 0 (ref.cast (ref $Number) (local.get $arg))))
+        ;; 0, -0 and NaN are falsy
+        (return (i32.and
+          (f64.ne (local.get $value) (f64.const 0))
+          (f64.eq (local.get $value) (local.get $value))))
       )
     )
     (if (ref.test (ref $StaticString) (local.get $arg))
@@ -989,6 +1274,11 @@
         (return (i32.const 1))
       )
     )
+    (if (call $is_bigint (local.get $arg))
+      (then
+        (return (i32.eqz (call $bigint_is_zero (ref.cast (ref $BigInt) (local.get $arg)))))
+      )
+    )
  
     i32.const 0
   )
@@ -1003,11 +1293,45 @@
   (func $new_scope (param $parent (ref null $Scope)) (result (ref $Scope))
     (struct.new $Scope
       (local.get $parent)
+      (array.new_fixed $AnyrefArray 0)
       (call $new_hashmap)
       (call $new_hashmap_i32)
     )
   )
 
+  ;; a scope with a fixed number of slots for captured variables
+  (func $new_environment (param $parent (ref null $Scope)) (param $size i32) (result (ref $Scope))
+    (struct.new $Scope
+      (local.get $parent)
+      (array.new $AnyrefArray (ref.null any) (local.get $size))
+      (ref.null $HashMap)
+      (ref.null $HashMapI32)
+    )
+  )
+
+  ;; a new scope with the same parent and a copy of the slots, for the
+  ;; per-iteration bindings of `for (let ...)` loops
+  (func $copy_environment (param $scope (ref $Scope)) (result (ref $Scope))
+    (local $slots (ref $AnyrefArray))
+    (local $copy (ref $AnyrefArray))
+
+    (local.set $slots (struct.get $Scope $slots (local.get $scope)))
+    (local.set $copy
+      (array.new $AnyrefArray (ref.null any) (array.len (local.get $slots))))
+    (array.copy $AnyrefArray $AnyrefArray
+      (local.get $copy)
+      (i32.const 0)
+      (local.get $slots)
+      (i32.const 0)
+      (array.len (local.get $slots)))
+    (struct.new $Scope
+      (struct.get $Scope $parent (local.get $scope))
+      (local.get $copy)
+      (ref.null $HashMap)
+      (ref.null $HashMapI32)
+    )
+  )
+
   ;; it's similar to set_variable as it writes to a variable on the scope, but
   ;; it checks if a variable is available in the scope first
   (func $assign_variable (param $scope (ref $Scope)) (param $name i32) (param $value anyref)
@@ -1040,9 +1364,12 @@
     (local.set $current_scope (local.get $scope))
     (loop $search_loop
       (local.set $found-value
-        (call $hashmap_get
-          (struct.get $Scope $variables (local.get $current_scope))
-          (local.get $name)
+        (if (result anyref) (ref.is_null (struct.get $Scope $variables (local.get $current_scope)))
+          (then (global.get $no-value))
+          (else
+            (call $hashmap_get
+              (ref.as_non_null (struct.get $Scope $variables (local.get $current_scope)))
+              (local.get $name)))
         )
       )
       (if (call $is_no_value_found (local.get $found-value))
@@ -1050,7 +1377,10 @@
           (local.set $current_scope (struct.get $Scope $parent (local.get $current_scope)))
           (if (ref.is_null (local.get $current_scope))
             (then
-              (throw $JSException (ref.i31 (local.get $name)))
+              ;; sloppy mode lets you assign to an undeclared variable, which
+              ;; creates a property on the global object
+              (call $set_global (local.get $name) (local.get $value))
+              (return)
             )
           )
  
@@ -1060,13 +1390,10 @@
           ;; we found the variable declared on the $current_scope, so
           ;; set the variable on the $current_scope
           (call $set_variable (ref.cast (ref $Scope) (local.get $current_scope)) (local.get $name) (local.get $value))
+          (return)
         )
       )
     )
-
-    ;; TODO: if there's no variable found JS still lets you assign, but
-    ;; it saves it on the global scope
-    (call $set_variable (ref.cast (ref $Scope) (global.get $scope)) (local.get $name) (local.get $value))
   )
 
   ;; TODO: for let and const we need to check if the values already exist
@@ -1074,7 +1401,7 @@
     (local $existing_type i32)
 
     (call $hashmap_get_i32
-      (struct.get $Scope $var_types (local.get $scope))
+      (ref.as_non_null (struct.get $Scope $var_types (local.get $scope)))
       (local.get $name)
     )
     (local.set $existing_type)
@@ -1082,13 +1409,15 @@
     (if (i32.eq (local.get $existing_type) (i32.const 0))
       (then
         ;; 0 means it's a const, we have to throw an error
-        ;; TODO: throw proper eception type
-        (throw $JSException (ref.i31 (i32.const 12)))
+        (call $throw_type_error
+          (call $new_static_string
+            (i32.const {{ data(str="Assignment to constant variable.") }})
+            (i32.const {{ data_length() }})))
       )
     )
 
     (call $hashmap_set
-      (struct.get $Scope $variables (local.get $scope))
+      (ref.as_non_null (struct.get $Scope $variables (local.get $scope)))
       (local.get $name)
       (local.get $value)
     )
@@ -1103,7 +1432,7 @@
     (local $existing_type i32)
 
     (call $hashmap_get_i32
-      (struct.get $Scope $var_types (local.get $scope))
+      (ref.as_non_null (struct.get $Scope $var_types (local.get $scope)))
       (local.get $name)
     )
     (local.set $existing_type)
@@ -1117,12 +1446,12 @@
         ;; -1 means there is no such var in the hashmap, we can declare no matter what
         ;; 2 means var and 3 means param, which are also valid to overwrite
         (call $hashmap_set
-          (struct.get $Scope $variables (local.get $scope))
+          (ref.as_non_null (struct.get $Scope $variables (local.get $scope)))
           (local.get $name)
           (local.get $value)
         )
         (call $hashmap_set_i32
-          (struct.get $Scope $var_types (local.get $scope))
+          (ref.as_non_null (struct.get $Scope $var_types (local.get $scope)))
           (local.get $name)
           (local.get $var_type)
         )
@@ -1130,50 +1459,65 @@
       )
     )
 
-    (if (i32.eq (local.get $existing_type) (i32.const 0))
+    ;; 0 and 1 mean it's a const or a let, which can't be redeclared
+    (call $throw_syntax_error
+      (call $concat_strings
+        (call $concat_strings
+          (call $new_static_string (i32.const {{ data(str="Identifier '") }}) (i32.const {{ data_length() }}))
+          (call $key_to_string (local.get $name)))
+        (call $new_static_string (i32.const {{ data(str="' has already been declared") }}) (i32.const {{ data_length() }}))))
+  )
+
+  (func $is_no_value_found (param $arg anyref) (result i32)
+    (if (ref.test eqref (local.get $arg))
       (then
-        ;; 0 means it's a const, we have to throw an error
-        ;; TODO: throw proper eception type
-        (throw $JSException (ref.i31 (i32.const 10)))
-      )
-    )
+        (return (ref.eq (ref.cast eqref (local.get $arg)) (global.get $no-value)))))
+
+    (return (i32.const 0))
+  )
+
+  (func $get_variable (param $scope (ref $Scope)) (param $name i32) (result anyref)
+    (local $value anyref)
 
-    (if (i32.eq (local.get $existing_type) (i32.const 1))
+    (local.set $value (call $lookup_variable (local.get $scope) (local.get $name)))
+    (if (call $is_no_value_found (local.get $value))
       (then
-        ;; 1 means it's a let, we have to throw an error
-        ;; TODO: throw proper eception type
-        (throw $JSException (ref.i31 (i32.const 11)))
+        (call $throw_reference_error
+          (call $concat_strings
+            (call $key_to_string (local.get $name))
+            (call $new_static_string (i32.const {{ data(str=" is not defined") }}) (i32.const {{ data_length() }}))))
       )
     )
+    (local.get $value)
   )
 
-  (func $is_no_value_found (param $arg anyref) (result i32)
-    (if (ref.test nullref (local.get $arg))
-      (then
-        (return (i32.const 0))))
-
-    (if (ref.test i31ref (local.get $arg))
-      (then
-        (i32.const -1)
-        (i31.get_s (ref.cast i31ref (local.get $arg)))
-        (if (i32.eq)
-          (then
-            (return (i32.const 1))
-          ))))
+  ;; like get_variable, but returns undefined for undeclared variables, used
+  ;; for `typeof name`
+  (func $get_variable_or_undefined (param $scope (ref $Scope)) (param $name i32) (result anyref)
+    (local $value anyref)
 
-    (return (i32.const 0))
+    (local.set $value (call $lookup_variable (local.get $scope) (local.get $name)))
+    (if (call $is_no_value_found (local.get $value))
+      (then (return (ref.null any)))
+    )
+    (local.get $value)
   )
 
-  (func $get_variable (param $scope (ref $Scope)) (param $name i32) (result anyref)
+  ;; finds a variable in the scope chain, falling back to the properties the
+  ;; global object inherits, returns $no-value if there's no such variable
+  (func $lookup_variable (param $scope (ref $Scope)) (param $name i32) (result anyref)
     (local $current_scope (ref null $Scope))
     (local $value anyref)
 
     (local.set $current_scope (local.get $scope))
     (loop $search_loop
       (local.set $value
-        (call $hashmap_get
-          (struct.get $Scope $variables (local.get $current_scope))
-          (local.get $name)
+        (if (result anyref) (ref.is_null (struct.get $Scope $variables (local.get $current_scope)))
+          (then (global.get $no-value))
+          (else
+            (call $hashmap_get
+              (ref.as_non_null (struct.get $Scope $variables (local.get $current_scope)))
+              (local.get $name)))
         )
       )
       (if (call $is_no_value_found (local.get $value))
@@ -1181,7 +1525,12 @@
           (local.set $current_scope (struct.get $Scope $parent (local.get $current_scope)))
           (if (ref.is_null (local.get $current_scope))
             (then
-              (throw $JSException (ref.i31 (local.get $name)))
+              (if (call $has_property (global.get $global-object) (local.get $name))
+                (then
+                  (return (call $get_property (global.get $global-object) (local.get $name)))
+                )
+              )
+              (return (global.get $no-value))
             )
           )
  
@@ -1192,74 +1541,136 @@
         )
       )
     )
-    (throw $JSException (ref.i31 (i32.const 103)))
+    (unreachable)
+  )
+
+  ;; creates or updates a property of the global object, used for top-level
+  ;; `var`s and functions and for implicit globals
+  (func $set_global (param $name i32) (param $value anyref)
+    (call $set_property (global.get $global-object) (local.get $name) (local.get $value))
+  )
+
+  ;; hoists a top-level `var` or function, keeping the value if the global
+  ;; object already has such property
+  (func $declare_global (param $name i32)
+    (if (i32.eqz (call $has_own_property (global.get $global-object) (local.get $name)))
+      (then
+        (call $set_global (local.get $name) (ref.null any))
+      )
+    )
   )
 
-  ;; TODO: this should also handle prototypes
   (func $get_property (param $target anyref) (param $name i32) (result anyref)
     (local $result anyref)
-    (if (ref.test (ref $Object) (local.get $target))
+    (local $current anyref)
+    (local $properties (ref null $HashMap))
+
+    (if (i32.eq (local.get $name) (i32.const {{ data(str="length") }}))
       (then
-        (call $hashmap_get
-          (struct.get $Object $properties (ref.cast (ref $Object) (local.get $target)))
-          (local.get $name)
-        )
-        (local.set $result)
-      )
-      (else
-        ;; TODO: as long as objects like Function and String are just another objects
-        ;; we will have to reimplement a lot of stuff like this. It would be great
-        ;; to research parent and child types
-        (if (ref.test (ref $Function) (local.get $target))
+        (if (ref.test (ref $Array) (local.get $target))
           (then
-            (call $hashmap_get
-              (struct.get $Function $properties (ref.cast (ref $Function) (local.get $target)))
-              (local.get $name)
-            )
-            (local.set $result)
+            (return
+              (call $new_number
+                (f64.convert_i32_u
+                  (array.len (struct.get $Array $array (ref.cast (ref $Array) (local.get $target)))))))
           )
-          (else
-            (if (ref.test (ref $Promise) (local.get $target))
-              (then
-                (call $hashmap_get
-                  (struct.get $Promise $properties (ref.cast (ref $Promise) (local.get $target)))
-                  (local.get $name)
-                )
-                (local.tee $result)
-                (if (ref.test i31ref)
-                  (then
-                    (if (i32.eq 
-                          (i31.get_s (ref.cast i31ref (local.get $result)))
-                          (i32.const -1))
-                      (then
-                        ;; if the result is i31ref -1, it means nothing was found, so
-                        ;; we need to search the $prototype
-                        (ref.cast (ref $Promise) (local.get $target))
-                        (struct.get $Promise $prototype)
-                        (local.get $name)
-                        (call $get_property)
-                        (local.set $result)
-                      )
-                    )
-                  )
-                )
-              )
-            )
+        )
+        (if (call $is_string (local.get $target))
+          (then
+            (return (call $new_number (f64.convert_i32_u (call $string_length (local.get $target)))))
           )
         )
       )
     )
 
-    (if (ref.test i31ref (local.get $result))
+    (if (i32.eq (local.get $name) (i32.const {{ data(str="size") }}))
       (then
-        (if (i32.eq
-              (i31.get_s (ref.cast i31ref (local.get $result)))
-              (i32.const -1))
-          (then
-            (throw $JSException (ref.i31 (i32.const 100)))
-          )
+        (local.set $result (call $collection_size (local.get $target)))
+        (if (i32.eqz (call $is_no_value_found (local.get $result)))
+          (then (return (local.get $result)))
+        )
+      )
+    )
+
+    (if (i32.eq (local.get $name) (i32.const {{ data(str="prototype") }}))
+      (then
+        (local.set $result (call $function_prototype (local.get $target)))
+        (if (i32.eqz (call $is_no_value_found (local.get $result)))
+          (then (return (local.get $result)))
+        )
+      )
+    )
+
+    (if (i32.eq (local.get $name) (i32.const {{ data(str="description") }}))
+      (then
+        (local.set $result (call $symbol_description (local.get $target)))
+        (if (i32.eqz (call $is_no_value_found (local.get $result)))
+          (then (return (local.get $result)))
+        )
+      )
+    )
+
+    (local.set $result (call $regexp_accessor (local.get $target) (local.get $name)))
+    (if (i32.eqz (call $is_no_value_found (local.get $result)))
+      (then (return (local.get $result)))
+    )
+
+    (local.set $result (call $typed_array_accessor (local.get $target) (local.get $name)))
+    (if (i32.eqz (call $is_no_value_found (local.get $result)))
+      (then (return (local.get $result)))
+    )
+
+    (if (i32.or
+          (ref.test nullref (local.get $target))
+          (i32.or
+            (ref.test (ref $Null) (local.get $target))
+            (ref.test (ref $Boolean) (local.get $target))))
+      (then
+        ;; TODO: booleans should use Boolean.prototype
+        (call $throw_type_error
+          (call $property_access_error
+            (call $new_static_string (i32.const {{ data(str="Cannot read properties of ") }}) (i32.const {{ data_length() }}))
+            (local.get $target)
+            (call $new_static_string (i32.const {{ data(str=" (reading '") }}) (i32.const {{ data_length() }}))
+            (local.get $name)))
+      )
+    )
+
+    ;; walk the prototype chain until the property is found
+    (local.set $result (global.get $no-value))
+    (local.set $current (local.get $target))
+    (block $done (loop $lookup
+      (br_if $done (ref.is_null (local.get $current)))
+
+      ;; a proxy anywhere in the chain takes over the rest of the lookup
+      (if (ref.test (ref $Proxy) (local.get $current))
+        (then
+          (local.set $result
+            (call $proxy_get
+              (ref.cast (ref $Proxy) (local.get $current))
+              (local.get $name)
+              (local.get $target)))
+          (br $done)
+        )
+      )
+
+      ;; primitives don't have own properties, but they still have a prototype
+      (local.set $properties (call $get_own_properties (local.get $current)))
+      (if (i32.eqz (ref.is_null (local.get $properties)))
+        (then
+          (local.set $result
+            (call $hashmap_get (ref.as_non_null (local.get $properties)) (local.get $name)))
+          (br_if $done (i32.eqz (call $is_no_value_found (local.get $result))))
         )
       )
+
+      (local.set $current (call $get_prototype (local.get $current)))
+      (br $lookup)
+    ))
+
+    ;; a property that doesn't exist is undefined
+    (if (call $is_no_value_found (local.get $result))
+      (then (return (ref.null any)))
     )
 
     (if (ref.test (ref $Function) (local.get $result))
@@ -1270,11 +1681,30 @@
       )
     )
 
+    (if (ref.test (ref $Proxy) (local.get $result))
+      (then
+        (struct.set $Proxy $this (ref.cast (ref $Proxy) (local.get $result)) (local.get $target))
+      )
+    )
+
     (local.get $result)
     (return)
   )
 
   (func $set_property (param $target anyref) (param $name i32) (param $value anyref)
+    ;; proxies are objects too, so they have to be checked first
+    (if (ref.test (ref $Proxy) (local.get $target))
+      (then
+        (drop
+          (call $proxy_set
+            (ref.cast (ref $Proxy) (local.get $target))
+            (local.get $name)
+            (local.get $value)
+            (local.get $target)))
+        (return)
+      )
+    )
+
     (if (ref.test (ref $Object) (local.get $target))
       (then
         (call $hashmap_set
@@ -1308,76 +1738,158 @@
       )
     )
 
-    (throw $JSException (ref.i31 (i32.const 101)))
+    (if (ref.test (ref $Array) (local.get $target))
+      (then
+        (call $hashmap_set
+          (call $array_properties (ref.cast (ref $Array) (local.get $target)))
+          (local.get $name)
+          (local.get $value)
+        )
+        (return)
+      )
+    )
+
+    (if (i32.or
+          (ref.test nullref (local.get $target))
+          (ref.test (ref $Null) (local.get $target)))
+      (then
+        (call $throw_type_error
+          (call $property_access_error
+            (call $new_static_string (i32.const {{ data(str="Cannot set properties of ") }}) (i32.const {{ data_length() }}))
+            (local.get $target)
+            (call $new_static_string (i32.const {{ data(str=" (setting '") }}) (i32.const {{ data_length() }}))
+            (local.get $name)))
+      )
+    )
+    ;; other primitives can't hold properties, outside of strict mode
+    ;; assignments to them are ignored
+  )
+
+  ;; a message like "Cannot read properties of undefined (reading 'x')"
+  (func $property_access_error (param $prefix anyref) (param $target anyref) (param $action anyref) (param $name i32) (result anyref)
+    (call $concat_strings
+      (call $concat_strings
+        (call $concat_strings
+          (call $concat_strings (local.get $prefix) (call $to_string (local.get $target)))
+          (local.get $action))
+        (call $key_to_string (local.get $name)))
+      (call $new_static_string (i32.const {{ data(str="')") }}) (i32.const {{ data_length() }})))
   )
 
+  ;; Entries are kept in the insertion order, which is also the order in which
+  ;; properties are enumerated. Deleting an entry leaves a null in its place,
+  ;; holes are removed when the entries array is resized
   (func $hashmap_set (param $map (ref $HashMap)) (param $key i32) (param $value anyref)
     (local $entries (ref $EntriesArray))
-    (local $new_entry (ref $HashMapEntry))
-    (local $new_size i32)
-    (local $new_entries (ref $EntriesArray))
+    (local $entry (ref null $HashMapEntry))
+    (local $size i32)
     (local $i i32)
-    (local $found i32)  ;; New local to track if we found the key
 
     (local.set $entries (struct.get $HashMap $entries (local.get $map)))
-    (local.set $new_entry (struct.new $HashMapEntry (local.get $key) (local.get $value)))
-    (local.set $found (i32.const 0))  ;; Initialize found flag to false
+    (local.set $size (struct.get $HashMap $size (local.get $map)))
 
     ;; First, search for existing key
-    (local.set $i (i32.const 0))
-    (loop $search_loop
-      (if (i32.lt_u (local.get $i) (struct.get $HashMap $size (local.get $map)))
+    (block $break (loop $search_loop
+      (br_if $break (i32.ge_u (local.get $i) (local.get $size)))
+
+      (local.set $entry (array.get $EntriesArray (local.get $entries) (local.get $i)))
+      (if (i32.eqz (ref.is_null (local.get $entry)))
         (then
-          (if (i32.eq
-                (struct.get $HashMapEntry $key (array.get $EntriesArray (local.get $entries) (local.get $i)))
-                (local.get $key))
-            (then
-              ;; Key found - update the value
-              (array.set $EntriesArray (local.get $entries) (local.get $i) (local.get $new_entry))
-              (local.set $found (i32.const 1))  ;; Set found flag to true
-            )
-          )
-          (if (i32.eqz (local.get $found))  ;; Only continue searching if not found
+          (if (i32.eq (struct.get $HashMapEntry $key (local.get $entry)) (local.get $key))
             (then
-              (local.set $i (i32.add (local.get $i) (i32.const 1)))
-              (br $search_loop)
+              ;; Key found - update the value in place, so the entry keeps its position
+              (struct.set $HashMapEntry $value (local.get $entry) (local.get $value))
+              (return)
             )
           )
         )
       )
-    )
 
-    ;; If key wasn't found, proceed with insertion
-    (if (i32.eqz (local.get $found))
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $search_loop)
+    ))
+
+    ;; Check if we need to resize
+    (if (i32.ge_u (local.get $size) (array.len (local.get $entries)))
       (then
-        ;; Check if we need to resize
-        (if (i32.ge_u (struct.get $HashMap $size (local.get $map)) (array.len (local.get $entries)))
-          (then
-            (local.set $new_size (i32.mul (array.len (local.get $entries)) (i32.const 2)))
-            (local.set $new_entries (array.new $EntriesArray (ref.null $HashMapEntry) (local.get $new_size)))
+        (call $hashmap_resize (local.get $map))
+        (local.set $entries (struct.get $HashMap $entries (local.get $map)))
+        (local.set $size (struct.get $HashMap $size (local.get $map)))
+      )
+    )
 
-            (local.set $i (i32.const 0))
-            (loop $copy_loop
-              (if (i32.lt_u (local.get $i) (array.len (local.get $entries)))
-                (then
-                  (array.set $EntriesArray (local.get $new_entries) (local.get $i)
-                    (array.get $EntriesArray (local.get $entries) (local.get $i)))
-                  (local.set $i (i32.add (local.get $i) (i32.const 1)))
-                  (br $copy_loop)
-                )
-              )
-            )
+    ;; Add new entry and increment size
+    (array.set $EntriesArray (local.get $entries) (local.get $size)
+      (struct.new $HashMapEntry (local.get $key) (local.get $value)))
+    (struct.set $HashMap $size (local.get $map) (i32.add (local.get $size) (i32.const 1)))
+  )
 
-            (struct.set $HashMap $entries (local.get $map) (local.get $new_entries))
-            (local.set $entries (local.get $new_entries))
-          )
+  ;; doubles the capacity of the entries array, skipping deleted entries
+  (func $hashmap_resize (param $map (ref $HashMap))
+    (local $entries (ref $EntriesArray))
+    (local $new_entries (ref $EntriesArray))
+    (local $entry (ref null $HashMapEntry))
+    (local $size i32)
+    (local $i i32)
+    (local $j i32)
+
+    (local.set $entries (struct.get $HashMap $entries (local.get $map)))
+    (local.set $size (struct.get $HashMap $size (local.get $map)))
+    (local.set $new_entries
+      (array.new $EntriesArray
+        (ref.null $HashMapEntry)
+        (i32.add (i32.mul (array.len (local.get $entries)) (i32.const 2)) (i32.const 1))))
+
+    (block $break (loop $copy_loop
+      (br_if $break (i32.ge_u (local.get $i) (local.get $size)))
+
+      (local.set $entry (array.get $EntriesArray (local.get $entries) (local.get $i)))
+      (if (i32.eqz (ref.is_null (local.get $entry)))
+        (then
+          (array.set $EntriesArray (local.get $new_entries) (local.get $j) (local.get $entry))
+          (local.set $j (i32.add (local.get $j) (i32.const 1)))
         )
+      )
 
-        ;; Add new entry and increment size
-        (array.set $EntriesArray (local.get $entries) (struct.get $HashMap $size (local.get $map)) (local.get $new_entry))
-        (struct.set $HashMap $size (local.get $map) (i32.add (struct.get $HashMap $size (local.get $map)) (i32.const 1)))
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $copy_loop)
+    ))
+
+    (struct.set $HashMap $entries (local.get $map) (local.get $new_entries))
+    (struct.set $HashMap $size (local.get $map) (local.get $j))
+  )
+
+  ;; returns 1 if the key was found and deleted
+  (func $hashmap_delete (param $map (ref $HashMap)) (param $key i32) (result i32)
+    (local $entries (ref $EntriesArray))
+    (local $entry (ref null $HashMapEntry))
+    (local $i i32)
+
+    (local.set $entries (struct.get $HashMap $entries (local.get $map)))
+    (block $break (loop $search_loop
+      (br_if $break (i32.ge_u (local.get $i) (struct.get $HashMap $size (local.get $map))))
+
+      (local.set $entry (array.get $EntriesArray (local.get $entries) (local.get $i)))
+      (if (i32.eqz (ref.is_null (local.get $entry)))
+        (then
+          (if (i32.eq (struct.get $HashMapEntry $key (local.get $entry)) (local.get $key))
+            (then
+              (array.set $EntriesArray (local.get $entries) (local.get $i) (ref.null $HashMapEntry))
+              (return (i32.const 1))
+            )
+          )
+        )
       )
-    )
+
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $search_loop)
+    ))
+
+    (i32.const 0)
+  )
+
+  (func $hashmap_has (param $map (ref $HashMap)) (param $key i32) (result i32)
+    (i32.eqz (call $is_no_value_found (call $hashmap_get (local.get $map) (local.get $key))))
   )
 
   (func $hashmap_set_i32 (param $map (ref $HashMapI32)) (param $key i32) (param $value i32)
@@ -1452,16 +1964,23 @@
   (func $hashmap_get (param $map (ref $HashMap)) (param $key i32) (result anyref)
     (local $i i32)
     (local $entries (ref $EntriesArray))
+    (local $entry (ref null $HashMapEntry))
     (local.set $entries (struct.get $HashMap $entries (local.get $map)))
     (local.set $i (i32.const 0))
     (loop $search_loop
       (if (i32.lt_u (local.get $i) (struct.get $HashMap $size (local.get $map)))
         (then
-          (if (i32.eq
-                (struct.get $HashMapEntry $key (array.get $EntriesArray (local.get $entries) (local.get $i)))
-                (local.get $key))
+          (local.set $entry (array.get $EntriesArray (local.get $entries) (local.get $i)))
+          ;; null entries are the ones that were deleted
+          (if (i32.eqz (ref.is_null (local.get $entry)))
             (then
-              (return (struct.get $HashMapEntry $value (array.get $EntriesArray (local.get $entries) (local.get $i))))
+              (if (i32.eq
+                    (struct.get $HashMapEntry $key (local.get $entry))
+                    (local.get $key))
+                (then
+                  (return (struct.get $HashMapEntry $value (local.get $entry)))
+                )
+              )
             )
           )
           (local.set $i (i32.add (local.get $i) (i32.const 1)))
@@ -1471,8 +1990,8 @@
     )
 
     ;; we need to somehow differentiate between value not being found and the found
-    ;; value being null
-    (ref.i31 (i32.const -1))
+    ;; value being undefined
+    (global.get $no-value)
   )
 
   (func $hashmap_get_i32 (param $map (ref $HashMapI32)) (param $key i32) (result i32)
@@ -1509,6 +2028,13 @@
     (local $js_func (ref $JSFunc))
     (local $current_this anyref)
 
+    (if (ref.test (ref $Proxy) (local.get $func))
+      (then
+        (return
+          (call $proxy_call (ref.cast (ref $Proxy) (local.get $func)) (local.get $this) (local.get $arguments)))
+      )
+    )
+
     (local.set $function (ref.cast (ref $Function) (local.get $func)))
     (local.set $js_func (struct.get $Function $func (local.get $function)))
 
@@ -1535,29 +2061,33 @@
   )
 
   (func $add (param $arg1 anyref) (param $arg2 anyref) (result anyref)
-    (local $num1 (ref $Number))
-    (local $num2 (ref $Number))
     (local $static_str1 (ref $StaticString))
     (local $static_str2 (ref $StaticString))
     (local $str1 (ref $String))
-    (local $str2 (ref $String))
-    (local $result f64)
 
-    ;; TODO: this doesn't take into account casting, it can only add two objects
-    ;; of the same type (and only numbers and strings for now)
+    ;; two 31 bit integers can't overflow i32, the result only has to be boxed
+    ;; if it doesn't fit in 31 bits
     (if (i32.and
-          (ref.test (ref $Number) (local.get $arg1))
-          (ref.test (ref $Number) (local.get $arg2)))
-      (then
-        (local.set $num1 (ref.cast (ref $Number) (local.get $arg1)))
-        (local.set $num2 (ref.cast (ref $Number) (local.get $arg2)))
-        (local.set $result
-          (f64.add
-            (struct.get $Number $value (local.get $num1))
-            (struct.get $Number $value (local.get $num2))
-          )
-        )
-        (return (call $new_number (local.get $result)))
+          (ref.test (ref i31) (local.get $arg1))
+          (ref.test (ref i31) (local.get $arg2)))
+      (then
+        (return
+          (call $new_number_i32
+            (i32.add
+              (i31.get_s (ref.cast (ref i31) (local.get $arg1)))
+              (i31.get_s (ref.cast (ref i31) (local.get $arg2))))))
+      )
+    )
+
+    (if (i32.and
+          (call $is_number (local.get $arg1))
+          (call $is_number (local.get $arg2)))
+      (then
+        (return
+          (call $new_number
+            (f64.add
+              (call $number_value (local.get $arg1))
+              (call $number_value (local.get $arg2)))))
       )
     )
 
@@ -1594,77 +2124,224 @@
       )
     )
 
-    (ref.null any)
+    ;; objects are converted to primitives first, then if any of the operands
+    ;; is a string, both are concatenated as strings, otherwise they're added
+    ;; as numbers
+    (local.set $arg1 (call $to_primitive (local.get $arg1) (i32.const 0)))
+    (local.set $arg2 (call $to_primitive (local.get $arg2) (i32.const 0)))
+    (if (i32.or (call $is_string (local.get $arg1)) (call $is_string (local.get $arg2)))
+      (then
+        (return
+          (call $concat_strings
+            (call $to_string (local.get $arg1))
+            (call $to_string (local.get $arg2))))
+      )
+    )
+
+    (local.set $arg1 (call $to_numeric (local.get $arg1)))
+    (local.set $arg2 (call $to_numeric (local.get $arg2)))
+    (if (i32.or (call $is_bigint (local.get $arg1)) (call $is_bigint (local.get $arg2)))
+      (then
+        (return (call $bigint_add (call $bigint_operands (local.get $arg1) (local.get $arg2))))
+      )
+    )
+    (call $new_number
+      (f64.add (call $number_value (local.get $arg1)) (call $number_value (local.get $arg2))))
   )
 
   (func $div (param $arg1 anyref) (param $arg2 anyref) (result anyref)
-    (local $num1 (ref $Number))
-    (local $num2 (ref $Number))
-    (local $result f64)
+    (local.set $arg1 (call $to_numeric (local.get $arg1)))
+    (local.set $arg2 (call $to_numeric (local.get $arg2)))
+    (if (i32.or (call $is_bigint (local.get $arg1)) (call $is_bigint (local.get $arg2)))
+      (then
+        (return (call $bigint_div (call $bigint_operands (local.get $arg1) (local.get $arg2))))
+      )
+    )
+    (call $new_number
+      (f64.div (call $number_value (local.get $arg1)) (call $number_value (local.get $arg2))))
+  )
 
+  (func $sub (param $arg1 anyref) (param $arg2 anyref) (result anyref)
     (if (i32.and
-          (ref.test (ref $Number) (local.get $arg1))
-          (ref.test (ref $Number) (local.get $arg2)))
-      (then
-        (local.set $num1 (ref.cast (ref $Number) (local.get $arg1)))
-        (local.set $num2 (ref.cast (ref $Number) (local.get $arg2)))
-        (local.set $result
-          (f64.div
-            (struct.get $Number 0 (local.get $num1))
-            (struct.get $Number 0 (local.get $num2))
-          )
-        )
-        (return (call $new_number (local.get $result)))
+          (ref.test (ref i31) (local.get $arg1))
+          (ref.test (ref i31) (local.get $arg2)))
+      (then
+        (return
+          (call $new_number_i32
+            (i32.sub
+              (i31.get_s (ref.cast (ref i31) (local.get $arg1)))
+              (i31.get_s (ref.cast (ref i31) (local.get $arg2))))))
       )
     )
-    (ref.null any)
+    (local.set $arg1 (call $to_numeric (local.get $arg1)))
+    (local.set $arg2 (call $to_numeric (local.get $arg2)))
+    (if (i32.or (call $is_bigint (local.get $arg1)) (call $is_bigint (local.get $arg2)))
+      (then
+        (return (call $bigint_sub (call $bigint_operands (local.get $arg1) (local.get $arg2))))
+      )
+    )
+    (call $new_number
+      (f64.sub (call $number_value (local.get $arg1)) (call $number_value (local.get $arg2))))
   )
 
-  (func $sub (param $arg1 anyref) (param $arg2 anyref) (result anyref)
-    (local $num1 (ref $Number))
-    (local $num2 (ref $Number))
-    (local $result f64)
+  (func $mul (param $arg1 anyref) (param $arg2 anyref) (result anyref)
+    (local.set $arg1 (call $to_numeric (local.get $arg1)))
+    (local.set $arg2 (call $to_numeric (local.get $arg2)))
+    (if (i32.or (call $is_bigint (local.get $arg1)) (call $is_bigint (local.get $arg2)))
+      (then
+        (return (call $bigint_mul (call $bigint_operands (local.get $arg1) (local.get $arg2))))
+      )
+    )
+    (call $new_number
+      (f64.mul (call $number_value (local.get $arg1)) (call $number_value (local.get $arg2))))
+  )
 
-    (if (i32.and
-          (ref.test (ref $Number) (local.get $arg1))
-          (ref.test (ref $Number) (local.get $arg2)))
-      (then
-        (local.set $num1 (ref.cast (ref $Number) (local.get $arg1)))
-        (local.set $num2 (ref.cast (ref $Number) (local.get $arg2)))
-        (local.set $result
-          (f64.sub
-            (struct.get $Number 0 (local.get $num1))
-            (struct.get $Number 0 (local.get $num2))
-          )
-        )
-        (return (call $new_number (local.get $result)))
+  (func $mod (param $arg1 anyref) (param $arg2 anyref) (result anyref)
+    (local.set $arg1 (call $to_numeric (local.get $arg1)))
+    (local.set $arg2 (call $to_numeric (local.get $arg2)))
+    (if (i32.or (call $is_bigint (local.get $arg1)) (call $is_bigint (local.get $arg2)))
+      (then
+        (return (call $bigint_rem (call $bigint_operands (local.get $arg1) (local.get $arg2))))
       )
     )
-    (ref.null any)
+    (call $new_number
+      (call $f64_rem (call $number_value (local.get $arg1)) (call $number_value (local.get $arg2))))
+  )
+
+  (func $exp (param $arg1 anyref) (param $arg2 anyref) (result anyref)
+    (local.set $arg1 (call $to_numeric (local.get $arg1)))
+    (local.set $arg2 (call $to_numeric (local.get $arg2)))
+    (if (i32.or (call $is_bigint (local.get $arg1)) (call $is_bigint (local.get $arg2)))
+      (then
+        (return (call $bigint_exp (call $bigint_operands (local.get $arg1) (local.get $arg2))))
+      )
+    )
+    (call $new_number
+      (call $math_pow (call $number_value (local.get $arg1)) (call $number_value (local.get $arg2))))
+  )
+
+  ;; bitwise operators work on 32 bit integers, the result of >>> is unsigned
+  (func $bitwise_and (param $arg1 anyref) (param $arg2 anyref) (result anyref)
+    (local.set $arg1 (call $to_numeric (local.get $arg1)))
+    (local.set $arg2 (call $to_numeric (local.get $arg2)))
+    (if (i32.or (call $is_bigint (local.get $arg1)) (call $is_bigint (local.get $arg2)))
+      (then
+        (return
+          (call $bigint_bitwise (i32.const 0) (call $bigint_operands (local.get $arg1) (local.get $arg2))))
+      )
+    )
+    (call $new_number_i32
+      (i32.and
+        (call $f64_to_int32 (call $number_value (local.get $arg1)))
+        (call $f64_to_int32 (call $number_value (local.get $arg2)))))
+  )
+
+  (func $bitwise_or (param $arg1 anyref) (param $arg2 anyref) (result anyref)
+    (local.set $arg1 (call $to_numeric (local.get $arg1)))
+    (local.set $arg2 (call $to_numeric (local.get $arg2)))
+    (if (i32.or (call $is_bigint (local.get $arg1)) (call $is_bigint (local.get $arg2)))
+      (then
+        (return
+          (call $bigint_bitwise (i32.const 1) (call $bigint_operands (local.get $arg1) (local.get $arg2))))
+      )
+    )
+    (call $new_number_i32
+      (i32.or
+        (call $f64_to_int32 (call $number_value (local.get $arg1)))
+        (call $f64_to_int32 (call $number_value (local.get $arg2)))))
+  )
+
+  (func $bitwise_xor (param $arg1 anyref) (param $arg2 anyref) (result anyref)
+    (local.set $arg1 (call $to_numeric (local.get $arg1)))
+    (local.set $arg2 (call $to_numeric (local.get $arg2)))
+    (if (i32.or (call $is_bigint (local.get $arg1)) (call $is_bigint (local.get $arg2)))
+      (then
+        (return
+          (call $bigint_bitwise (i32.const 2) (call $bigint_operands (local.get $arg1) (local.get $arg2))))
+      )
+    )
+    (call $new_number_i32
+      (i32.xor
+        (call $f64_to_int32 (call $number_value (local.get $arg1)))
+        (call $f64_to_int32 (call $number_value (local.get $arg2)))))
+  )
+
+  (func $shift_left (param $arg1 anyref) (param $arg2 anyref) (result anyref)
+    (local.set $arg1 (call $to_numeric (local.get $arg1)))
+    (local.set $arg2 (call $to_numeric (local.get $arg2)))
+    (if (i32.or (call $is_bigint (local.get $arg1)) (call $is_bigint (local.get $arg2)))
+      (then
+        (return
+          (call $bigint_shift (call $bigint_operands (local.get $arg1) (local.get $arg2)) (i32.const 1)))
+      )
+    )
+    (call $new_number_i32
+      (i32.shl
+        (call $f64_to_int32 (call $number_value (local.get $arg1)))
+        (call $f64_to_int32 (call $number_value (local.get $arg2)))))
   )
 
-  (func $strict_not_equal (param $arg1 anyref) (param $arg2 anyref) (result i31ref)
+  (func $shift_right (param $arg1 anyref) (param $arg2 anyref) (result anyref)
+    (local.set $arg1 (call $to_numeric (local.get $arg1)))
+    (local.set $arg2 (call $to_numeric (local.get $arg2)))
+    (if (i32.or (call $is_bigint (local.get $arg1)) (call $is_bigint (local.get $arg2)))
+      (then
+        (return
+          (call $bigint_shift (call $bigint_operands (local.get $arg1) (local.get $arg2)) (i32.const 0)))
+      )
+    )
+    (call $new_number_i32
+      (i32.shr_s
+        (call $f64_to_int32 (call $number_value (local.get $arg1)))
+        (call $f64_to_int32 (call $number_value (local.get $arg2)))))
+  )
+
+  (func $unsigned_shift_right (param $arg1 anyref) (param $arg2 anyref) (result anyref)
+    (local.set $arg1 (call $to_numeric (local.get $arg1)))
+    (local.set $arg2 (call $to_numeric (local.get $arg2)))
+    (if (i32.or (call $is_bigint (local.get $arg1)) (call $is_bigint (local.get $arg2)))
+      (then
+        ;; the operands still have to be checked for mixing first
+        (call $bigint_operands (local.get $arg1) (local.get $arg2))
+        (drop)
+        (drop)
+        (call $throw_type_error
+          (call $new_static_string (i32.const {{ data(str="BigInts have no unsigned right shift, use >> instead") }}) (i32.const {{ data_length() }})))
+      )
+    )
+    (call $new_number
+      (f64.convert_i32_u
+        (i32.shr_u
+          (call $f64_to_int32 (call $number_value (local.get $arg1)))
+          (call $f64_to_int32 (call $number_value (local.get $arg2))))))
+  )
+
+  (func $bitwise_not (param $arg anyref) (result anyref)
+    (local.set $arg (call $to_numeric (local.get $arg)))
+    (if (call $is_bigint (local.get $arg))
+      (then (return (call $bigint_not (ref.cast (ref $BigInt) (local.get $arg)))))
+    )
+    (call $new_number_i32
+      (i32.xor (call $f64_to_int32 (call $number_value (local.get $arg))) (i32.const -1)))
+  )
+
+  (func $strict_not_equal (param $arg1 anyref) (param $arg2 anyref) (result (ref $Boolean))
     (return 
-      (ref.i31
+      (call $new_boolean
         (i32.eqz
-          (i31.get_s
-            (ref.cast 
-              (ref null i31)
-              (call $strict_equal (local.get $arg1) (local.get $arg2))))))
+          (struct.get $Boolean $value
+            (call $strict_equal (local.get $arg1) (local.get $arg2)))))
     )
   )
 
-  (func $strict_equal (param $arg1 anyref) (param $arg2 anyref) (result i31ref)
-    (local $num1 (ref $Number))
-    (local $num2 (ref $Number))
-    (local $result f64)
+  (func $strict_equal (param $arg1 anyref) (param $arg2 anyref) (result (ref $Boolean))
 
     ;; if both args are undefined, return true
     (if (i32.and
           (ref.test nullref (local.get $arg1))
           (ref.test nullref (local.get $arg2)))
       (then
-        (return (ref.i31 (i32.const 1)))
+        (return (global.get $true))
       )
     )
 
@@ -1673,35 +2350,35 @@
           (ref.test nullref (local.get $arg1))
           (ref.test nullref (local.get $arg2)))
       (then
-        (return (ref.i31 (i32.const 0)))
+        (return (global.get $false))
       )
     )
 
     (if (i32.and
-          (ref.test (ref $Number) (local.get $arg1))
-          (ref.test (ref $Number) (local.get $arg2)))
+          (ref.test (ref i31) (local.get $arg1))
+          (ref.test (ref i31) (local.get $arg2)))
       (then
-        (local.set $num1 (ref.cast (ref $Number) (local.get $arg1)))
-        (local.set $num2 (ref.cast (ref $Number) (local.get $arg2)))
-        (return 
-          (ref.i31 (f64.eq
-            (struct.get $Number 0 (local.get $num1))
-            (struct.get $Number 0 (local.get $num2))
-          ))
-        )
+        (return
+          (call $new_boolean
+            (ref.eq (ref.cast (ref i31) (local.get $arg1)) (ref.cast (ref i31) (local.get $arg2)))))
       )
     )
 
-    ;; if both args are bool or null and are equal, return true
     (if (i32.and
-          (ref.test i31ref (local.get $arg1))
-          (ref.test i31ref (local.get $arg2)))
+          (call $is_number (local.get $arg1))
+          (call $is_number (local.get $arg2)))
       (then
-        (return (ref.i31 (i32.const 1)))
+        (return 
+          (call $new_boolean (f64.eq
+            (call $number_value (local.get $arg1))
+            (call $number_value (local.get $arg2))
+          ))
+        )
       )
     )
 
-    (ref.i31 (i32.const 0))
+    ;; strings, booleans, null and references
+    (call $new_boolean (call $same_value_non_number (local.get $arg1) (local.get $arg2)))
   )
 
   (func $logical_or (param $arg1 anyref) (param $arg2 anyref) (result anyref)
@@ -1712,16 +2389,10 @@
       )
     )
 
-    ;; if arg1 is not (null or false) we also return arg1
-    (if (ref.test i31ref (local.get $arg1))
+    ;; if arg1 is truthy we also return arg1
+    (if (call $cast_ref_to_i32_bool (local.get $arg1))
       (then
-        (if (i32.eqz (i32.or
-              (i32.eq (i31.get_s (ref.cast (ref null i31) (local.get $arg1))) (i32.const 0))
-              (i32.eq (i31.get_s (ref.cast (ref null i31) (local.get $arg1))) (i32.const 2))))
-          (then
-            (return (local.get $arg1))
-          )
-        )
+        (return (local.get $arg1))
       )
     )
 
@@ -1729,7 +2400,6 @@
     (return (local.get $arg2))
   )
 
-  ;; TODO: handle numbers properly - 0 is false
   (func $logical_and (param $arg1 anyref) (param $arg2 anyref) (result anyref)
     (if (ref.test nullref (local.get $arg1))
       (then
@@ -1738,16 +2408,10 @@
       )
     )
 
-    ;; if arg1 is null or false, we return arg1 too
-    (if (ref.test i31ref (local.get $arg1))
+    ;; if arg1 is falsy, we return arg1 too
+    (if (i32.eqz (call $cast_ref_to_i32_bool (local.get $arg1)))
       (then
-        (if (i32.or
-              (i32.eq (i31.get_s (ref.cast (ref null i31) (local.get $arg1))) (i32.const 0))
-              (i32.eq (i31.get_s (ref.cast (ref null i31) (local.get $arg1))) (i32.const 2)))
-          (then
-            (return (local.get $arg1))
-          )
-        )
+        (return (local.get $arg1))
       )
     )
 
@@ -1755,56 +2419,42 @@
     (return (local.get $arg2))
   )
 
-  (func $logical_not (param $arg anyref) (result i31ref)
+  (func $logical_not (param $arg anyref) (result (ref $Boolean))
+    (call $new_boolean (i32.eqz (call $cast_ref_to_i32_bool (local.get $arg))))
+  )
+
+  (func $type_of (param $arg anyref) (result (ref $StaticString))
     (if (ref.test nullref (local.get $arg))
       (then
-        ;; if arg is undefined we return true
-        (return (ref.i31 (i32.const 1)))
+        (return (call $new_static_string (i32.const {{ data(str="undefined") }}) (i32.const {{ data_length() }})))
       )
     )
 
-    (if (ref.test i31ref (local.get $arg))
+    (if (ref.test (ref $Boolean) (local.get $arg))
       (then
-        (if (i32.or
-              (i32.eq (i31.get_s (ref.cast (ref null i31) (local.get $arg))) (i32.const 0))
-              (i32.eq (i31.get_s (ref.cast (ref null i31) (local.get $arg))) (i32.const 2)))
-          (then
-            ;; if arg is null or false we return 1
-            (return (ref.i31 (i32.const 1)))
-          )
-        )
+        (return (call $new_static_string (i32.const {{ data(str="boolean") }}) (i32.const {{ data_length() }})))
       )
     )
 
-    ;; otherwise we return false
-    (return (ref.i31 (i32.const 0)))
-  )
-
-  (func $type_of (param $arg anyref) (result (ref $StaticString))
-    (if (ref.test nullref (local.get $arg))
+    (if (ref.test (ref $Null) (local.get $arg))
       (then
-        (return (call $new_static_string (i32.const {{ data(str="undefined") }}) (i32.const {{ data_length() }})))
+        (return (call $new_static_string (i32.const {{ data(str="object") }}) (i32.const {{ data_length() }})))
       )
     )
 
-    (if (ref.test i31ref (local.get $arg))
+    (if (call $is_number (local.get $arg))
       (then
-        (if (i32.or
-              (i32.eq (i31.get_s (ref.cast (ref null i31) (local.get $arg))) (i32.const 0))
-              (i32.eq (i31.get_s (ref.cast (ref null i31) (local.get $arg))) (i32.const 1)))
-          (then
-            (return (call $new_static_string (i32.const {{ data(str="boolean") }}) (i32.const {{ data_length() }})))
-          )
-          (else
-            (return (call $new_static_string (i32.const {{ data(str="undefined") }}) (i32.const {{ data_length() }})))
-          )
-        )
-      )
+        (return (call $new_static_string (i32.const {{ data(str="number") }}) (i32.const {{ data_length() }}))))
     )
 
-    (if (ref.test (ref $Number) (local.get $arg))
+    ;; proxies of functions are functions as well
+    (if (ref.test (ref $Proxy) (local.get $arg))
       (then
-        (return (call $new_static_string (i32.const {{ data(str="number") }}) (i32.const {{ data_length() }}))))
+        (if (struct.get $Proxy $callable (ref.cast (ref $Proxy) (local.get $arg)))
+          (then
+            (return (call $new_static_string (i32.const {{ data(str="function") }}) (i32.const {{ data_length() }}))))
+        )
+      )
     )
 
     (if (i32.or 
@@ -1814,7 +2464,7 @@
         (return (call $new_static_string (i32.const {{ data(str="object") }}) (i32.const {{ data_length() }}))))
     )
 
-    (if (ref.test (ref $StaticString) (local.get $arg))
+    (if (call $is_string (local.get $arg))
       (then
         (return (call $new_static_string (i32.const {{ data(str="string") }}) (i32.const {{ data_length() }}))))
     )
@@ -1824,851 +2474,190 @@
         (return (call $new_static_string (i32.const {{ data(str="function") }}) (i32.const {{ data_length() }}))))
     )
 
-    (return (call $new_static_string (i32.const {{ data(str="undefined") }}) (i32.const {{ data_length() }})))
-  )
-
-  (func $less_than (param $arg1 anyref) (param $arg2 anyref) (result i31ref)
-    (local $num1 (ref $Number))
-    (local $num2 (ref $Number))
-    (local $result f64)
+    (if (call $is_symbol (local.get $arg))
+      (then
+        (return (call $new_static_string (i32.const {{ data(str="symbol") }}) (i32.const {{ data_length() }}))))
+    )
 
-    (if (i32.and
-          (ref.test (ref $Number) (local.get $arg1))
-          (ref.test (ref $Number) (local.get $arg2)))
+    (if (call $is_bigint (local.get $arg))
       (then
-        (local.set $num1 (ref.cast (ref $Number) (local.get $arg1)))
-        (local.set $num2 (ref.cast (ref $Number) (local.get $arg2)))
-        (return 
-          (ref.i31 (f64.lt
-            (struct.get $Number 0 (local.get $num1))
-            (struct.get $Number 0 (local.get $num2))
-          ))
-        )
-      )
+        (return (call $new_static_string (i32.const {{ data(str="bigint") }}) (i32.const {{ data_length() }}))))
     )
 
-    (ref.i31 (i32.const 0))
+    (return (call $new_static_string (i32.const {{ data(str="undefined") }}) (i32.const {{ data_length() }})))
   )
 
-  (func $greater_than_or_equal (param $arg1 anyref) (param $arg2 anyref) (result i31ref)
-    (local $num1 (ref $Number))
-    (local $num2 (ref $Number))
-    (local $result f64)
+  ;; compares two strings by their bytes, returns -1, 0 or 1
+  (func $compare_strings (param $str1 anyref) (param $str2 anyref) (result i32)
+    (local $len1 i32)
+    (local $len2 i32)
+    (local $i i32)
+    (local $char1 i32)
+    (local $char2 i32)
 
-    (if (i32.and
-          (ref.test (ref $Number) (local.get $arg1))
-          (ref.test (ref $Number) (local.get $arg2)))
-      (then
-        (local.set $num1 (ref.cast (ref $Number) (local.get $arg1)))
-        (local.set $num2 (ref.cast (ref $Number) (local.get $arg2)))
-        (return 
-          (ref.i31 (f64.ge
-            (struct.get $Number 0 (local.get $num1))
-            (struct.get $Number 0 (local.get $num2))
-          ))
-        )
+    (local.set $len1 (call $string_length (local.get $str1)))
+    (local.set $len2 (call $string_length (local.get $str2)))
+
+    (block $break (loop $compare
+      (br_if $break
+        (i32.or
+          (i32.ge_u (local.get $i) (local.get $len1))
+          (i32.ge_u (local.get $i) (local.get $len2))))
+
+      (local.set $char1 (call $string_byte_at (local.get $str1) (local.get $i)))
+      (local.set $char2 (call $string_byte_at (local.get $str2) (local.get $i)))
+      (if (i32.lt_u (local.get $char1) (local.get $char2))
+        (then (return (i32.const -1)))
+      )
+      (if (i32.gt_u (local.get $char1) (local.get $char2))
+        (then (return (i32.const 1)))
       )
-    )
 
-    (ref.i31 (i32.const 0))
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $compare)
+    ))
+
+    (i32.sub
+      (i32.gt_u (local.get $len1) (local.get $len2))
+      (i32.lt_u (local.get $len1) (local.get $len2)))
   )
 
-  (func $increment_number (param $arg1 anyref) (result anyref)
-    (local $num (ref $Number))
-    (local $result f64)
+  ;; IsLessThan from the spec, returns 1 if arg1 < arg2, 0 if it's not and -1 if
+  ;; the result is undefined, which happens when any of the numbers is NaN
+  (func $is_less_than (param $arg1 anyref) (param $arg2 anyref) (result i32)
+    (local $num1 f64)
+    (local $num2 f64)
 
-    (if (ref.test (ref $Number) (local.get $arg1))
+    (if (i32.and
+          (ref.test (ref i31) (local.get $arg1))
+          (ref.test (ref i31) (local.get $arg2)))
       (then
-        (local.set $num (ref.cast (ref $Number) (local.get $arg1)))
-        (local.set $result
-          (f64.add
-            (struct.get $Number 0 (local.get $num))
-            (f64.const 1)
-          )
-        )
-        (return (call $new_number (local.get $result)))
+        (return
+          (i32.lt_s
+            (i31.get_s (ref.cast (ref i31) (local.get $arg1)))
+            (i31.get_s (ref.cast (ref i31) (local.get $arg2)))))
       )
     )
-    (ref.null any)
-  )
-
-  ;; TODO: can we update in-place?
-  (func $decrement_number (param $arg1 anyref) (result anyref)
-    (local $num (ref $Number))
-    (local $result f64)
 
-    (if (ref.test (ref $Number) (local.get $arg1))
+    (local.set $arg1 (call $to_primitive (local.get $arg1) (i32.const 1)))
+    (local.set $arg2 (call $to_primitive (local.get $arg2) (i32.const 1)))
+    (if (i32.and
+          (call $is_string (local.get $arg1))
+          (call $is_string (local.get $arg2)))
       (then
-        (local.set $num (ref.cast (ref $Number) (local.get $arg1)))
-        (local.set $result
-          (f64.sub
-            (struct.get $Number 0 (local.get $num))
-            (f64.const 1)
-          )
-        )
-        (return (call $new_number (local.get $result)))
+        (return
+          (i32.eq (call $compare_strings (local.get $arg1) (local.get $arg2)) (i32.const -1)))
       )
     )
-    (ref.null any)
-  )
 
-  ;; TODO: fix this
-(func $writeF64AsAscii (param $value f64) (param $offset i32) (result i32)
-  (local $isNegative i32)
-  (local $intPart i64)
-  (local $currentOffset i32)
-  (local $digitCount i32)
-  (local $fracValue f64)
-  
-  ;; Initialize current offset
-  (local.set $currentOffset (local.get $offset))
-  
-  ;; Handle negative numbers
-  (local.set $isNegative 
-    (i32.lt_s 
-      (i32.wrap_i64 
-        (i64.shr_u 
-          (i64.reinterpret_f64 (local.get $value))
-          (i64.const 63)
-        )
-      )
-      (i32.const 0)
-    )
-  )
-  
-  ;; Write minus sign if negative
-  (if (local.get $isNegative)
-    (then
-      ;; Write '-' (ASCII 45)
-      (i32.store8 (local.get $currentOffset) (i32.const 45))
-      (local.set $currentOffset (i32.add (local.get $currentOffset) (i32.const 1)))
-      ;; Make value positive
-      (local.set $value (f64.neg (local.get $value)))
-    )
-  )
-  
-  ;; Get integer part
-  (local.set $intPart (i64.trunc_f64_s (local.get $value)))
-  
-  ;; Write integer part digits in reverse
-  (local.set $digitCount (i32.const 0))
-  (block $break
-    (loop $digit_loop
-      ;; Write current digit
-      (i32.store8 
-        (i32.add 
-          (local.get $currentOffset) 
-          (local.get $digitCount)
-        )
-        (i32.add
-          (i32.wrap_i64 
-            (i64.rem_u 
-              (local.get $intPart)
-              (i64.const 10)
-            )
-          )
-          (i32.const 48)  ;; ASCII '0'
-        )
-      )
-      (local.set $digitCount (i32.add (local.get $digitCount) (i32.const 1)))
-      
-      ;; Update intPart
-      (local.set $intPart (i64.div_u (local.get $intPart) (i64.const 10)))
-      
-      ;; Continue if intPart > 0
-      (br_if $digit_loop (i64.gt_s (local.get $intPart) (i64.const 0)))
-    )
-  )
-  
-  ;; Reverse the digits we just wrote
-  (local.set $currentOffset 
-    (call $reverseBytes 
-      (local.get $currentOffset)
-      (local.get $digitCount)
+    (if (i32.or (call $is_bigint (local.get $arg1)) (call $is_bigint (local.get $arg2)))
+      (then (return (call $bigint_is_less_than (local.get $arg1) (local.get $arg2))))
     )
-  )
-  
-  ;; Get fractional part
-  (local.set $fracValue (f64.sub 
-    (local.get $value)
-    (f64.convert_i64_s (i64.trunc_f64_s (local.get $value)))
-  ))
-  
-  ;; Only write decimal point and fraction if there is a fractional part
-  (if (f64.gt (local.get $fracValue) (f64.const 0))
-    (then
-      ;; Add decimal point
-      (i32.store8 (local.get $currentOffset) (i32.const 46))  ;; ASCII '.'
-      (local.set $currentOffset (i32.add (local.get $currentOffset) (i32.const 1)))
-      
-      ;; Write fractional digits until we hit zero or max precision
-      (local.set $digitCount (i32.const 0))
-      (block $frac_break
-        (loop $frac_loop
-          ;; Break if we've written 16 digits (typical f64 precision limit)
-          (br_if $frac_break (i32.ge_u (local.get $digitCount) (i32.const 16)))
-          
-          ;; Multiply by 10 to get next digit
-          (local.set $fracValue (f64.mul (local.get $fracValue) (f64.const 10)))
-          
-          ;; Get the digit
-          (local.set $intPart (i64.trunc_f64_s (local.get $fracValue)))
-          
-          ;; Write the digit
-          (i32.store8 
-            (local.get $currentOffset)
-            (i32.add
-              (i32.wrap_i64 (local.get $intPart))
-              (i32.const 48)  ;; ASCII '0'
-            )
-          )
-          
-          ;; Update fracValue
-          (local.set $fracValue 
-            (f64.sub 
-              (local.get $fracValue)
-              (f64.convert_i64_s (local.get $intPart))
-            )
-          )
-          
-          ;; Update counters
-          (local.set $currentOffset (i32.add (local.get $currentOffset) (i32.const 1)))
-          (local.set $digitCount (i32.add (local.get $digitCount) (i32.const 1)))
-          
-          ;; Continue if we have more significant digits
-          (br_if $frac_loop (f64.gt (local.get $fracValue) (f64.const 0)))
-        )
-      )
-    )
-  )
-  
-  ;; Return number of bytes written
-  (i32.sub (local.get $currentOffset) (local.get $offset))
-)
 
-;; Helper function to reverse bytes (same as before)
-(func $reverseBytes (param $start i32) (param $length i32) (result i32)
-  (local $left i32)
-  (local $right i32)
-  (local $temp i32)
-  
-  (local.set $left (local.get $start))
-  (local.set $right (i32.sub 
-    (i32.add (local.get $start) (local.get $length))
-    (i32.const 1)
-  ))
-  
-  (block $break
-    (loop $reverse_loop
-      (br_if $break (i32.ge_u (local.get $left) (local.get $right)))
-      
-      ;; Swap bytes
-      (local.set $temp 
-        (i32.load8_u (local.get $left))
-      )
-      (i32.store8 
-        (local.get $left)
-        (i32.load8_u (local.get $right))
-      )
-      (i32.store8 
-        (local.get $right)
-        (local.get $temp)
-      )
-      
-      ;; Update pointers
-      (local.set $left (i32.add (local.get $left) (i32.const 1)))
-      (local.set $right (i32.sub (local.get $right) (i32.const 1)))
-      (br $reverse_loop)
+    (local.set $num1 (call $to_number (local.get $arg1)))
+    (local.set $num2 (call $to_number (local.get $arg2)))
+    (if (i32.or
+          (f64.ne (local.get $num1) (local.get $num1))
+          (f64.ne (local.get $num2) (local.get $num2)))
+      (then (return (i32.const -1)))
     )
+    (f64.lt (local.get $num1) (local.get $num2))
   )
-  
-  (i32.add (local.get $start) (local.get $length))
-)
 
-  (func $log_string (param $str (ref $StaticString))
-    (local.get $str)
-    (call $create-arguments-1)
-    (call $log)
+  (func $less_than (param $arg1 anyref) (param $arg2 anyref) (result (ref $Boolean))
+    (call $new_boolean
+      (i32.eq (call $is_less_than (local.get $arg1) (local.get $arg2)) (i32.const 1)))
   )
 
-  (func $log (param $arguments (ref $JSArgs))
-    (local $i i32)                    ;; loop counter
-    (local $j i32)                    ;; loop counter
-    (local $len i32)                  ;; length of arguments array
-    (local $str_len i32)              ;; length of a processed sring
-    (local $current anyref)           ;; current argument being processed
-    (local $offset i32)               ;; current memory offset for data
-    (local $iovectors_offset i32)     ;; offset for iovectors
-    (local $written_length i32)       ;; length of written data
-    (local $num_val f64)              ;; temporary storage for number value
-    (local $static_str_ref (ref $StaticString))  ;; temporary storage for string reference
-    (local $str_ref (ref $String))  ;; temporary storage for string reference
-    (local $num_ref (ref $Number))    ;; temporary storage for number reference
-    (local $handled i32)
-    (local $i31_ref i31ref)
- 
-    ;; Get length of arguments array
-    (local.set $len 
-      (array.len (ref.cast (ref $JSArgs) (local.get $arguments)))
-    )
- 
-    ;; Set initial offsets
-    (local.set $iovectors_offset 
-      (global.get $free_memory_offset)
-    )
-    ;; set offset for iovectors. we need place for arguments * 2
-    ;; vectors, one for each argument, one for a space after each
-    ;; arguments and one for a new line at the end
-    (local.set $offset 
-      (i32.add 
-        (global.get $free_memory_offset)
-        (i32.mul (local.get $len) (i32.const 16))
-      )
-    )
-    
-    ;; Initialize loop counter
-    (local.set $i (i32.const 0))
-    
-    ;; Main loop through arguments
-    (loop $process_args
-      (block $process_arg
-        ;; Get current argument
-        (local.set $current 
-          (array.get $JSArgs
-            (local.get $arguments)
-            (local.get $i)
-          )
-        )
-
-        ;; argument not handled yet
-        (local.set $handled (i32.const 0))
-       
-        ;; check if it's undefined
-        (if (ref.test nullref (local.get $current))
-          (then
-            ;; Store iovector data
-            (i32.store 
-              (local.get $iovectors_offset)
-              (i32.const {{ data(str="undefined") }})
-            )
-            (i32.store 
-              (i32.add (local.get $iovectors_offset) (i32.const 4))
-              (i32.const {{ data_length() }})
-            )
-            
-            (local.set $iovectors_offset 
-              (i32.add (local.get $iovectors_offset) (i32.const 8))
-            )
-    
-            ;; argument handled
-            (local.set $handled (i32.const 1))
-          )
-        )
-
-        ;; check if it's null, false or a number
-        (if (i32.and
-              (ref.test i31ref (local.get $current))
-              (i32.eqz (ref.test nullref (local.get $current))))
-          (then
-            (local.set $i31_ref
-              (ref.cast i31ref (local.get $current)))
-
-            (if (i32.eq
-              (i31.get_s (ref.cast (ref null i31) (local.get $current)))
-              (i32.const 0))
-              (then
-                ;; Store iovector data
-                (i32.store
-                  (local.get $iovectors_offset)
-                  (i32.const {{ data(str="null") }})
-                )
-                (i32.store 
-                  (i32.add (local.get $iovectors_offset) (i32.const 4))
-                  (i32.const {{ data_length() }})
-                )
-                
-                (local.set $iovectors_offset 
-                  (i32.add (local.get $iovectors_offset) (i32.const 8))
-                )
-    
-                ;; argument handled
-                (local.set $handled (i32.const 1))
-              )
-            )
-
-            (if (i32.eq
-              (i31.get_s (ref.cast (ref null i31) (local.get $current)))
-              (i32.const 1))
-              (then
-                ;; Store iovector data
-                (i32.store
-                  (local.get $iovectors_offset)
-                  (i32.const {{ data(str="true") }})
-                )
-                (i32.store 
-                  (i32.add (local.get $iovectors_offset) (i32.const 4))
-                  (i32.const {{ data_length() }})
-                )
-                
-                (local.set $iovectors_offset 
-                  (i32.add (local.get $iovectors_offset) (i32.const 8))
-                )
-    
-                ;; argument handled
-                (local.set $handled (i32.const 1))
-              )
-            )
-
-            (if (i32.eq
-              (i31.get_s (ref.cast (ref null i31) (local.get $current)))
-              (i32.const 2))
-              (then
-                ;; Store iovector data
-                (i32.store
-                  (local.get $iovectors_offset)
-                  (i32.const {{ data(str="false") }})
-                )
-                (i32.store 
-                  (i32.add (local.get $iovectors_offset) (i32.const 4))
-                  (i32.const {{ data_length() }})
-                )
-                
-                (local.set $iovectors_offset 
-                  (i32.add (local.get $iovectors_offset) (i32.const 8))
-                )
-    
-                ;; argument handled
-              )
-            )
-
-            (local.set $num_val 
-              (f64.convert_i32_s (i31.get_s (ref.cast (ref null i31) (local.get $current))))
-            )
-            
-            ;; Write number to memory and get length
-            (local.set $written_length
-              (call $writeF64AsAscii 
-                (local.get $num_val)
-                (local.get $offset)
-              )
-            )
-            
-            ;; Store iovector data
-            (i32.store (local.get $iovectors_offset) (local.get $offset))
-            (i32.store 
-              (i32.add (local.get $iovectors_offset) (i32.const 4))
-              (local.get $written_length)
-            )
-            
-            ;; Update offsets
-            (local.set $offset 
-              (i32.add (local.get $offset) (local.get $written_length))
-            )
-            (local.set $iovectors_offset 
-              (i32.add (local.get $iovectors_offset) (i32.const 8))
-            )
-            (local.set $handled (i32.const 1))
-          )
-        )
-
-        ;; Check if it's a Number
-        (if (ref.test (ref $Number) (local.get $current))
-          (then
-            ;; Cast to Number and get value
-            (local.set $num_ref
-              (ref.cast (ref $Number) (local.get $current))
-            )
-            (local.set $num_val 
-              (struct.get $Number 0 (local.get $num_ref))
-            )
-            
-            ;; Write number to memory and get length
-            (local.set $written_length
-              (call $writeF64AsAscii 
-                (local.get $num_val)
-                (local.get $offset)
-              )
-            )
-            
-            ;; Store iovector data
-            (i32.store (local.get $iovectors_offset) (local.get $offset))
-            (i32.store 
-              (i32.add (local.get $iovectors_offset) (i32.const 4))
-              (local.get $written_length)
-            )
-            
-            ;; Update offsets
-            (local.set $offset 
-              (i32.add (local.get $offset) (local.get $written_length))
-            )
-            (local.set $iovectors_offset 
-              (i32.add (local.get $iovectors_offset) (i32.const 8))
-            )
-            
-            ;; argument handled
-            (local.set $handled (i32.const 1))
-          )
-        )
-        
-        ;; Check if it's a StaticString
-        (if (ref.test (ref $StaticString) (local.get $current))
-          (then
-            ;; Cast to StaticString
-            (local.set $static_str_ref
-              (ref.cast (ref $StaticString) (local.get $current))
-            )
-            
-            ;; Store iovector data
-            (i32.store 
-              (local.get $iovectors_offset)
-              (struct.get $StaticString $offset (local.get $static_str_ref))
-            )
-            (i32.store 
-              (i32.add (local.get $iovectors_offset) (i32.const 4))
-              (struct.get $StaticString $length (local.get $static_str_ref))
-            )
-            
-            (local.set $iovectors_offset 
-              (i32.add (local.get $iovectors_offset) (i32.const 8))
-            )
-    
-            ;; argument handled
-            (local.set $handled (i32.const 1))
-          )
-        )
-
-        ;; Check if it's a String
-        (if (ref.test (ref $String) (local.get $current))
-          (then
-            (local.set $str_ref 
-              (ref.cast (ref $String) (local.get $current))
-            )
-            
-            (local.set $str_len (struct.get $String $length (local.get $str_ref)))
-            ;; Store iovector data
-            (i32.store 
-              (local.get $iovectors_offset)
-              (local.get $offset)
-            )
-            (i32.store 
-              (i32.add (local.get $iovectors_offset) (i32.const 4))
-              (local.get $str_len)
-            )
-            (local.set $j (i32.const 0))
-            (block $break1
-              (loop $copy1
-                (br_if $break1 (i32.ge_u (local.get $j) (local.get $str_len)))
-
-                (i32.store8 
-                  (i32.add (local.get $offset) (local.get $j))
-                  (array.get_u $CharArray 
-                    (struct.get $String $data (local.get $str_ref))
-                    (local.get $j)
-                  )
-                )
-                
-                (local.set $j (i32.add (local.get $j) (i32.const 1)))
-                (br $copy1)
-              )
-            )
-            
-            ;; Update offset for next write
-            (local.set $offset 
-              (i32.add 
-                (local.get $offset)
-                (struct.get $String $length (local.get $str_ref))
-              )
-            )
-            (local.set $iovectors_offset 
-              (i32.add (local.get $iovectors_offset) (i32.const 8))
-            )
-    
-            ;; argument handled
-            (local.set $handled (i32.const 1))
-          )
-        )
-
-        ;; Increment counter
-        (local.set $i (i32.add (local.get $i) (i32.const 1)))
-
-        ;; Check if we're done processing arguments
-        (br_if $process_arg
-          (i32.ge_u (local.get $i) (local.get $len))
-        )
-
-        ;; after each argument, but the last, we put in a space
-        (i32.store (local.get $iovectors_offset) (i32.const {{ data(str=" ") }}))
-        (i32.store 
-          (i32.add (local.get $iovectors_offset) (i32.const 4))
-          (i32.const {{ data_length() }})
-        )
- 
-        (local.set $iovectors_offset 
-          (i32.add (local.get $iovectors_offset) (i32.const 8))
-        )
- 
-        (br $process_args)
-      ) 
-    )
-
-    ;; put newline at the end
-    (i32.store (local.get $iovectors_offset) (i32.const {{ data(str="\n") }}))
-    (i32.store 
-      (i32.add (local.get $iovectors_offset) (i32.const 4))
-      (i32.const {{ data_length() }})
-    )
-    
-    ;; Call write with all accumulated iovectors
-    (call $write 
-      (i32.const 1)  ;; stdout
-      (global.get $free_memory_offset)  ;; iovectors start
-      (i32.mul (local.get $len) (i32.const 2))  ;; number of iovectors
-      (i32.const 50)  ;; where to write result
-    )
-    drop
+  (func $greater_than (param $arg1 anyref) (param $arg2 anyref) (result (ref $Boolean))
+    (call $new_boolean
+      (i32.eq (call $is_less_than (local.get $arg2) (local.get $arg1)) (i32.const 1)))
   )
 
-  (func $add-pollable (param $pollable (ref $Pollable)) (result i32)
-    (local $len i32)
-    (local $i i32)
-    (local $old_pollables (ref $PollablesArray))
-
-    (local.set $len (array.len (global.get $pollables)))
-    (local.set $i (i32.const 0))
-
-    (block $break (loop $find
-      (br_if $break (i32.ge_u (local.get $i) (local.get $len)))
-      
-      (array.get $PollablesArray (global.get $pollables) (local.get $i))
-      (if (ref.test nullref)
-        (then
-          ;; we found a null value, let's insert here
-          (array.set $PollablesArray
-            (global.get $pollables)
-            (local.get $i)
-            (local.get $pollable))
+  (func $less_than_or_equal (param $arg1 anyref) (param $arg2 anyref) (result (ref $Boolean))
+    (call $new_boolean
+      (i32.eqz (call $is_less_than (local.get $arg2) (local.get $arg1))))
+  )
 
-          (struct.get $Pollable $id (local.get $pollable))
-          (return)
-        ))
-      
-      (local.set $i (i32.add (local.get $i) (i32.const 1)))
-      (br $find)
-    ))
-  
-    ;; we haven't found any free space, let's double the array and try again 
-    (local.set $old_pollables (global.get $pollables))
-    (global.set $pollables
-      (array.new $PollablesArray (ref.null $Pollable) (i32.mul (local.get $len) (i32.const 2))))
+  (func $greater_than_or_equal (param $arg1 anyref) (param $arg2 anyref) (result (ref $Boolean))
+    (call $new_boolean
+      (i32.eqz (call $is_less_than (local.get $arg1) (local.get $arg2))))
+  )
 
-    (array.copy
-      $PollablesArray            ;; dest type
-      $PollablesArray            ;; source type
-      (global.get $pollables)    ;; dest array
-      (i32.const 0)              ;; dest offset
-      (local.get $old_pollables) ;; source array
-      (i32.const 0)              ;; source data offest
-      (local.get $len)           ;; source data length
+  (func $negate (param $arg anyref) (result anyref)
+    (local.set $arg (call $to_numeric (local.get $arg)))
+    (if (call $is_bigint (local.get $arg))
+      (then (return (call $bigint_negate (ref.cast (ref $BigInt) (local.get $arg)))))
     )
- 
-    (call $add-pollable (local.get $pollable))
-  )
-
-  ;; (func $pollables-empty (result i32)
-  ;;   (local $len i32)
-  ;;   (local $i i32)
-
-  ;;   (local.set $len (array.len (global.get $pollables)))
-  ;;   (local.set $i (i32.const 0))
-
-  ;;   (block $break (loop $find
-  ;;     (br_if $break (i32.ge_s (local.get $i) (local.get $len)))
-  ;;     
-  ;;     (array.get $PollablesArray (global.get $pollables) (local.get $i))
-  ;;     (if (i32.eqz (ref.test nullref))
-  ;;       (then
-  ;;         ;; non null value, return false
-  ;;         (return (i32.const 0))
-  ;;       ))
-  ;;     
-  ;;     (local.set $i (i32.add (local.get $i) (i32.const 1)))
-  ;;     (br $find)
-  ;;   ))
-
-  ;;   (return (i32.const 1))
-  ;; )
-
-  (func $find-pollable (param $id i32) (result i32)
-    (local $len i32)
-    (local $i i32)
-    (local $current (ref null $Pollable))
-
-    (local.set $len (array.len (global.get $pollables)))
-    (local.set $i (i32.const 0))
-
-    (block $break (loop $find
-      (br_if $break (i32.ge_u (local.get $i) (local.get $len)))
-      
-      (array.get $PollablesArray (global.get $pollables) (local.get $i))
-      (local.set $current)
-      (if (i32.eqz (ref.test nullref (local.get $current)))
-        (then
-          (if (i32.eq
-            (local.get $id)
-            (struct.get $Pollable $id (local.get $current)))
-              (then
-            (return (local.get $i))))))
-      
-      (local.set $i (i32.add (local.get $i) (i32.const 1)))
-      (br $find)
-    ))
-
-    (return (i32.const -1))
+    (call $new_number (f64.neg (call $number_value (local.get $arg))))
   )
 
-  (func $execute-pollables (param $offset i32)
-    (local $len i32)
-    (local $index i32)
-    (local $i i32)
-    (local $func anyref)
-
-    (local.set $len (i32.load (local.get $offset)))
-    (local.set $offset (i32.add (local.get $offset) (i32.const 4)))
-    (local.set $i (i32.const 0))
-
-    (block $break (loop $find
-      (br_if $break (i32.ge_u (local.get $i) (local.get $len)))
-
-      (i32.load (local.get $offset))
-      (call $find-pollable)
-      (local.set $index)
-
-      (if (i32.ne
-            (local.get $index)
-            (i32.const -1))
-        (then
-          (array.get $PollablesArray (global.get $pollables) (local.get $index))
-          (struct.get $Pollable $func)
-          (local.tee $func)
-          (if (ref.test (ref $Function))
-            (then
-              (local.get $func) ;; function to call
-              (ref.null any) ;; this, TODO: we need to handle this differently for arrow functions or binding
-              (array.new $JSArgs (ref.null any) (i32.const 0))
-              (call $call_function)
-              (drop)
-              (return)
-            )
-          )
-          ;; TODO: handle async variant
-          (throw $JSException (ref.i31 (i32.const 20002)))
-      ))
-
-      (local.set $offset (i32.add (local.get $offset) (i32.const 4)))
-
-      (local.set $i (i32.add (local.get $i) (i32.const 1)))
-      (br $find)
-    ))
+  (func $unary_plus (param $arg anyref) (result anyref)
+    (call $new_number (call $to_number (local.get $arg)))
   )
 
-  (func $clear-pollables (param $offset i32)
-    (local $len i32)
-    (local $index i32)
-    (local $i i32)
-
-    (local.set $len (i32.load (local.get $offset)))
-    (local.set $offset (i32.add (local.get $offset) (i32.const 4)))
-    (local.set $i (i32.const 0))
-
-    (block $break (loop $find
-      (br_if $break (i32.ge_u (local.get $i) (local.get $len)))
-
-      (i32.load (local.get $offset))
-      (call $find-pollable)
-      (local.set $index)
-
-      (if (i32.ne
-            (local.get $index)
-            (i32.const -1))
-        (then
-          (array.set $PollablesArray (global.get $pollables) (local.get $index) (ref.null $Pollable)))
+  (func $increment_number (param $arg1 anyref) (result anyref)
+    (if (ref.test (ref i31) (local.get $arg1))
+      (then
+        (return
+          (call $new_number_i32
+            (i32.add (i31.get_s (ref.cast (ref i31) (local.get $arg1))) (i32.const 1))))
       )
-
-      (local.set $offset (i32.add (local.get $offset) (i32.const 4)))
-
-      (local.set $i (i32.add (local.get $i) (i32.const 1)))
-      (br $find)
-    ))
-  )
-
-  (func $store-pollables (param $offset i32) (result i32)
-    (local $len i32)
-    (local $i i32)
-    (local $current (ref null $Pollable))
-    (local $stored_length i32)
-
-    (local.set $len (array.len (global.get $pollables)))
-    (local.set $i (i32.const 0))
-    (local.set $stored_length (i32.const 0))
-
-    (block $break (loop $find
-      (br_if $break (i32.ge_u (local.get $i) (local.get $len)))
-      
-      (array.get $PollablesArray (global.get $pollables) (local.get $i))
-      (local.set $current)
-      (if (i32.eqz (ref.test nullref (local.get $current)))
-        (then
-          (i32.store (local.get $offset) (struct.get $Pollable $id (local.get $current)))
-          (local.set $offset (i32.add (local.get $offset) (i32.const 4)))
-          (local.set $stored_length (i32.add (local.get $stored_length) (i32.const 1)))
-        )
+    )
+    (local.set $arg1 (call $to_numeric (local.get $arg1)))
+    (if (call $is_bigint (local.get $arg1))
+      (then
+        (return
+          (call $bigint_add (ref.cast (ref $BigInt) (local.get $arg1)) (call $bigint_from_i64 (i64.const 1))))
       )
-      
-      (local.set $i (i32.add (local.get $i) (i32.const 1)))
-      (br $find)
-    ))
-
-    (local.get $stored_length)
+    )
+    (call $new_number (f64.add (call $number_value (local.get $arg1)) (f64.const 1)))
   )
 
-  (func $set-timeout (param $func anyref) (param $duration_arg anyref) (result (ref $Number))
-    (local $duration i64)
-
-    (if (ref.test (ref $Function) (local.get $func))
+  ;; TODO: can we update in-place?
+  (func $decrement_number (param $arg1 anyref) (result anyref)
+    (if (ref.test (ref i31) (local.get $arg1))
       (then
-
-        (if (ref.test (ref $Number) (local.get $duration_arg))
-          (then
-            (ref.cast (ref $Number) (local.get $duration_arg))
-            (struct.get $Number $value)
-            (i64.trunc_f64_s)
-            (i64.const 1000000) ;; javascript uses milliseconds, in here we have nanoseconds
-            (i64.mul)
-            (local.set $duration)
-            ;; TODO: handle strings by converting to $Number
-          )
-          (else
-            (local.set $duration (i64.const 0))
-          ))
-
-        (call $subscribe-duration (local.get $duration))
-        (local.get $func)
-        (call $new-pollable)
-        (call $add-pollable)
-        (f64.convert_i32_s)
-        (call $new_number)
-        ;; return the pollable id
-        (return)
+        (return
+          (call $new_number_i32
+            (i32.sub (i31.get_s (ref.cast (ref i31) (local.get $arg1))) (i32.const 1))))
       )
     )
+    (local.set $arg1 (call $to_numeric (local.get $arg1)))
+    (if (call $is_bigint (local.get $arg1))
+      (then
+        (return
+          (call $bigint_sub (ref.cast (ref $BigInt) (local.get $arg1)) (call $bigint_from_i64 (i64.const 1))))
+      )
+    )
+    (call $new_number (f64.sub (call $number_value (local.get $arg1)) (f64.const 1)))
+  )
+
+  (func $log_string (param $str (ref $StaticString))
+    (local.get $str)
+    (call $create-arguments-1)
+    (call $log)
+  )
 
-    ;; for now I'm just throwing numbers, so I can roughly find out what happened
-    ;; but eventually this should throw actual JS errors
-    (throw $JSException (ref.i31 (i32.const 20001)))
+  ;; console.log, for printing from the runtime itself
+  (func $log (param $arguments (ref $JSArgs))
+    (call $console_print (i32.const 1) (local.get $arguments))
   )
 
   {{init_code}}
 
+  {% if blocking_event_loop %}
+  ;; The event loop for runtimes with a blocking `wasi:io/poll.poll`, like
+  ;; Wasmtime, selected with JAWS_EVENT_LOOP=blocking. It polls until there are
+  ;; no pollables left, running the callbacks of all the ready ones in between
+  (func $event_loop
+    (block $break (loop $poll
+      (br_if $break (i32.eqz (call $poll-pollables)))
+      (call $run-ready-pollables)
+      (br $poll)
+    ))
+  )
+  {% else %}
   ;; This is not how the run loop will run in the future. `poll-many`
   ;; is supposed to wait for the next pollable to resolve, thus blocking
   ;; the execution. In order to do that on the host there has to be a way
@@ -2676,48 +2665,23 @@
   ;; as an WebAssembly import doesn't await it. There are ways around it,
   ;; for example to use asyncify, but `wasm-opt` with asyncify crashes
   ;; when trying to optimize the project (I'm guessing due to exception handling
-  ;; or GC proposals). So instead of running in a loop (like the commented code would suggest)
-  ;; we're calling poll-many and the poll-many polyfill will call main_loop again
+  ;; or GC proposals). So instead of running in a loop we're calling poll-many
+  ;; and the poll-many polyfill will write the result and call main_loop again
   ;;
-  ;; This should be fixed once th code can be executed on a runtime with proper
-  ;; WASI preview2 support
+  ;; Runtimes with proper WASI preview2 support can use the blocking
+  ;; $event_loop instead
   (func $main_loop
-    (local $length i32)
-    (local $offset i32)
-    (;(block $break (loop $main_loop;)
-      (local.set $offset (global.get $free_memory_offset))
-      ;; we don't want the polables memory overwritten, so let's shift the offset by the length of pollables
-      (global.set $free_memory_offset 
-        (i32.add
-          (local.get $offset)
-          (i32.add
-            (i32.mul
-              (i32.load (local.get $offset))
-              (i32.const 4))
-            (i32.const 4)))) ;; we have N 32bit numbers + length
-
-      (call $execute-pollables (local.get $offset))
-      (call $clear-pollables (local.get $offset))
-
-      ;; bring back the original free_memory_offset, so we don't waste memory
-      (global.set $free_memory_offset (local.get $offset))
-
-      (call $store-pollables (global.get $free_memory_offset))
-      (local.set $length)
-
-      (call $poll-many (global.get $free_memory_offset) (local.get $length) (global.get $free_memory_offset))
-
-      ;;(call $clear-pollables (global.get $free_memory_offset))
-    (;  (br_if $break (call $pollables-empty));)
-    (;  (br $main_loop);)
-    (;));)
+    (call $run-ready-pollables)
+    ;; if there are any pollables left, this will call $main_loop again
+    (drop (call $poll-pollables))
   )
-
+  {% endif %}
   (elem declare func $Promise-constructor)
   (elem declare func $Promise-then)
   (elem declare func $Promise-catch)
-  (elem declare func $Promise-reject)
-  (elem declare func $Promise-resolve)
+  (elem declare func $promise-resolve-function)
+  (elem declare func $promise-reject-function)
+  (elem declare func $queueMicrotask)
   (elem declare func $Promise-finally)
 
   (func $install-globals
@@ -2727,27 +2691,72 @@
     (local.tee $scope (call $new_scope (ref.null $Scope)))
     (global.set $scope)
 
+    (call $create-well-known-symbols)
+
+    (global.set $object-prototype (call $create-object-prototype))
+    (global.set $global-object
+      (struct.new $Object
+        (ref.as_non_null (struct.get $Scope $variables (local.get $scope)))
+        (global.get $object-prototype)
+        (i32.const 0)))
+    (call $set_variable (local.get $scope) (i32.const {{ data(str="globalThis") }}) (global.get $global-object))
+    (call $set_variable (local.get $scope) (i32.const {{ data(str="Object") }}) (call $create-object-constructor))
+    (call $install-error-globals (local.get $scope))
+    (call $install-symbol-globals (local.get $scope))
+
     (global.set $promise-prototype (call $create-promise-prototype))
+    (call $set_to_string_tag (global.get $promise-prototype)
+      (call $new_static_string (i32.const {{ data(str="Promise") }}) (i32.const {{ data_length() }})))
 
     ;; Promise
     (call $new_function (local.get $scope) (ref.func $Promise-constructor) (ref.null any))
     (local.set $promise-constructor)
     (call $set_variable (local.get $scope) (i32.const {{ data(str="Promise") }}) (local.get $promise-constructor))
+    (call $install-promise-globals (local.get $scope) (local.get $promise-constructor))
+    (call $set_variable
+      (local.get $scope)
+      (i32.const {{ data(str="queueMicrotask") }})
+      (call $new_function (local.get $scope) (ref.func $queueMicrotask) (ref.null any)))
+
+    (call $install-number-globals (local.get $scope))
+    (call $install-bigint-globals (local.get $scope))
+    (call $set_variable (local.get $scope) (i32.const {{ data(str="Math") }}) (call $create-math-object))
+    (call $set_variable (local.get $scope) (i32.const {{ data(str="JSON") }}) (call $create-json-object))
+    (call $install-collection-globals (local.get $scope))
+    (call $install-regexp-globals (local.get $scope))
+    (call $install-date-globals (local.get $scope))
+    (call $install-typed-array-globals (local.get $scope))
+    (call $install-proxy-globals (local.get $scope))
+    (call $install-console-globals (local.get $scope))
+    (call $install-timer-globals (local.get $scope))
+{% if fetch %}
+    (call $install-fetch-globals (local.get $scope))
+{% endif %}
+{% if process %}
+    (call $install-process-globals (local.get $scope))
+{% endif %}
+{% if filesystem %}
+    (call $install-fs-globals (local.get $scope))
+{% endif %}
+{% if crypto %}
+    (call $install-crypto-globals (local.get $scope))
+{% endif %}
   )
 
   (func $outer_init (result i32)
     (local $error anyref)
     (local $temp_arg anyref)
-    (local $length i32)
     try
       (call $install-globals)
       (call $init)
+      (call $run-microtasks)
 
-      (call $store-pollables (global.get $free_memory_offset))
-      (local.set $length)
-
+      {% if blocking_event_loop %}
+      (call $event_loop)
+      {% else %}
       ;; if there are any pollables, this will call $main_loop, otherwise it will exit
-      (call $poll-many (global.get $free_memory_offset) (local.get $length) (global.get $free_memory_offset))
+      (drop (call $poll-pollables))
+      {% endif %}
 
       (return (i32.const 0))
     catch $JSException
@@ -2770,5 +2779,7 @@
 
   (export "wasi:cli/run@0.2.1#run" (func $outer_init))
   (export "_start" (func $start))
+  {% if not blocking_event_loop %}
   (export "main_loop" (func $main_loop))
+  {% endif %}
 )
@@ -0,0 +1,395 @@
+  ;; Timers: setTimeout, setInterval, clearTimeout and clearInterval.
+  ;;
+  ;; Like libuv, the runtime keeps all of the timers in a single min-heap
+  ;; ordered by the deadline and then by the order in which they were
+  ;; scheduled, and only subscribes one pollable, for the earliest deadline.
+  ;; When it fires, every timer that is due runs in that order, with the
+  ;; microtasks drained after each callback, so timers with equal deadlines
+  ;; behave like in Node and browsers no matter which pollable the host reports.
+
+  (type $Timer (struct
+    (field $id i32)
+    (field $callback anyref)
+    ;; the arguments passed after the delay
+    (field $arguments (ref $JSArgs))
+    ;; the delay in nanoseconds
+    (field $delay i64)
+    (field $repeat i32)
+    (field $deadline (mut i64))
+    ;; tie breaker for equal deadlines, rescheduled intervals get a new one
+    (field $sequence (mut i32))
+    (field $cancelled (mut i32))
+  ))
+
+  (global $timers (mut (ref null $HashMap)) (ref.null $HashMap))
+  (global $timer-heap (mut (ref null $AnyrefArray)) (ref.null $AnyrefArray))
+  (global $timer-heap-size (mut i32) (i32.const 0))
+  (global $next-timer-id (mut i32) (i32.const 1))
+  (global $next-timer-sequence (mut i32) (i32.const 0))
+  ;; the time the due timers started running at, intervals are scheduled
+  ;; again from it like in Node, so the time their callbacks take doesn't add
+  ;; up
+  (global $timer-loop-time (mut i64) (i64.const 0))
+  ;; the key of the pollable for the earliest deadline, -1 if there's none
+  (global $timer-pollable (mut i32) (i32.const -1))
+  (global $timer-pollable-deadline (mut i64) (i64.const 0))
+
+  (func $timer_before (param $a (ref $Timer)) (param $b (ref $Timer)) (result i32)
+    (if (i64.ne (struct.get $Timer $deadline (local.get $a)) (struct.get $Timer $deadline (local.get $b)))
+      (then
+        (return (i64.lt_s (struct.get $Timer $deadline (local.get $a)) (struct.get $Timer $deadline (local.get $b))))
+      )
+    )
+    (i32.lt_s (struct.get $Timer $sequence (local.get $a)) (struct.get $Timer $sequence (local.get $b)))
+  )
+
+  (func $timer_heap_get (param $index i32) (result (ref $Timer))
+    (ref.cast (ref $Timer) (array.get $AnyrefArray (ref.as_non_null (global.get $timer-heap)) (local.get $index)))
+  )
+
+  (func $timer_heap_swap (param $i i32) (param $j i32)
+    (local $heap (ref $AnyrefArray))
+    (local $temp anyref)
+
+    (local.set $heap (ref.as_non_null (global.get $timer-heap)))
+    (local.set $temp (array.get $AnyrefArray (local.get $heap) (local.get $i)))
+    (array.set $AnyrefArray (local.get $heap) (local.get $i) (array.get $AnyrefArray (local.get $heap) (local.get $j)))
+    (array.set $AnyrefArray (local.get $heap) (local.get $j) (local.get $temp))
+  )
+
+  (func $timer_heap_push (param $timer (ref $Timer))
+    (local $heap (ref $AnyrefArray))
+    (local $new_heap (ref $AnyrefArray))
+    (local $index i32)
+    (local $parent i32)
+
+    (if (ref.is_null (global.get $timer-heap))
+      (then (global.set $timer-heap (array.new $AnyrefArray (ref.null any) (i32.const 8))))
+    )
+    (local.set $heap (ref.as_non_null (global.get $timer-heap)))
+    (if (i32.eq (global.get $timer-heap-size) (array.len (local.get $heap)))
+      (then
+        (local.set $new_heap
+          (array.new $AnyrefArray (ref.null any) (i32.shl (global.get $timer-heap-size) (i32.const 1))))
+        (array.copy $AnyrefArray $AnyrefArray
+          (local.get $new_heap)
+          (i32.const 0)
+          (local.get $heap)
+          (i32.const 0)
+          (global.get $timer-heap-size))
+        (global.set $timer-heap (local.get $new_heap))
+        (local.set $heap (local.get $new_heap))
+      )
+    )
+
+    (local.set $index (global.get $timer-heap-size))
+    (array.set $AnyrefArray (local.get $heap) (local.get $index) (local.get $timer))
+    (global.set $timer-heap-size (i32.add (local.get $index) (i32.const 1)))
+
+    ;; sift up
+    (block $done (loop $sift
+      (br_if $done (i32.eqz (local.get $index)))
+      (local.set $parent (i32.shr_u (i32.sub (local.get $index) (i32.const 1)) (i32.const 1)))
+      (br_if $done
+        (i32.eqz
+          (call $timer_before (call $timer_heap_get (local.get $index)) (call $timer_heap_get (local.get $parent)))))
+      (call $timer_heap_swap (local.get $index) (local.get $parent))
+      (local.set $index (local.get $parent))
+      (br $sift)
+    ))
+  )
+
+  (func $timer_heap_pop (result (ref $Timer))
+    (local $top (ref $Timer))
+    (local $size i32)
+    (local $index i32)
+    (local $child i32)
+    (local $smallest i32)
+
+    (local.set $top (call $timer_heap_get (i32.const 0)))
+    (local.set $size (i32.sub (global.get $timer-heap-size) (i32.const 1)))
+    (global.set $timer-heap-size (local.get $size))
+    (call $timer_heap_swap (i32.const 0) (local.get $size))
+    (array.set $AnyrefArray (ref.as_non_null (global.get $timer-heap)) (local.get $size) (ref.null any))
+
+    ;; sift down
+    (local.set $index (i32.const 0))
+    (block $done (loop $sift
+      (local.set $smallest (local.get $index))
+      (local.set $child (i32.add (i32.shl (local.get $index) (i32.const 1)) (i32.const 1)))
+      (if (i32.lt_u (local.get $child) (local.get $size))
+        (then
+          (if (call $timer_before (call $timer_heap_get (local.get $child)) (call $timer_heap_get (local.get $smallest)))
+            (then (local.set $smallest (local.get $child)))
+          )
+        )
+      )
+      (local.set $child (i32.add (local.get $child) (i32.const 1)))
+      (if (i32.lt_u (local.get $child) (local.get $size))
+        (then
+          (if (call $timer_before (call $timer_heap_get (local.get $child)) (call $timer_heap_get (local.get $smallest)))
+            (then (local.set $smallest (local.get $child)))
+          )
+        )
+      )
+      (br_if $done (i32.eq (local.get $smallest) (local.get $index)))
+      (call $timer_heap_swap (local.get $index) (local.get $smallest))
+      (local.set $index (local.get $smallest))
+      (br $sift)
+    ))
+
+    (local.get $top)
+  )
+
+  ;; the earliest timer that wasn't cleared, cleared ones are dropped from the
+  ;; heap lazily
+  (func $next_timer (result (ref null $Timer))
+    (block $done (loop $skip
+      (br_if $done (i32.eqz (global.get $timer-heap-size)))
+      (if (i32.eqz (struct.get $Timer $cancelled (call $timer_heap_get (i32.const 0))))
+        (then (return (call $timer_heap_get (i32.const 0))))
+      )
+      (drop (call $timer_heap_pop))
+      (br $skip)
+    ))
+    (ref.null $Timer)
+  )
+
+  ;; the delay counts from $start
+  (func $schedule_timer (param $timer (ref $Timer)) (param $start i64)
+    (struct.set $Timer $deadline (local.get $timer)
+      (i64.add (local.get $start) (struct.get $Timer $delay (local.get $timer))))
+    (struct.set $Timer $sequence (local.get $timer) (global.get $next-timer-sequence))
+    (global.set $next-timer-sequence (i32.add (global.get $next-timer-sequence) (i32.const 1)))
+    (call $timer_heap_push (local.get $timer))
+  )
+
+  ;; makes sure there's a pollable for the earliest deadline, or none if there
+  ;; are no timers left, so cleared timers don't keep the program running
+  (func $update_timer_pollable
+    (local $next (ref null $Timer))
+    (local $deadline i64)
+    (local $duration i64)
+
+    (local.set $next (call $next_timer))
+    (if (ref.is_null (local.get $next))
+      (then
+        (if (i32.ne (global.get $timer-pollable) (i32.const -1))
+          (then
+            (call $remove-pollable (global.get $timer-pollable))
+            (global.set $timer-pollable (i32.const -1))
+          )
+        )
+        (return)
+      )
+    )
+
+    (local.set $deadline (struct.get $Timer $deadline (local.get $next)))
+    (if (i32.ne (global.get $timer-pollable) (i32.const -1))
+      (then
+        (if (i64.eq (local.get $deadline) (global.get $timer-pollable-deadline))
+          (then (return))
+        )
+        (call $remove-pollable (global.get $timer-pollable))
+      )
+    )
+
+    (local.set $duration (i64.sub (local.get $deadline) (call $monotonic-now)))
+    (if (i64.lt_s (local.get $duration) (i64.const 0))
+      (then (local.set $duration (i64.const 0)))
+    )
+    (global.set $timer-pollable
+      (call $add-pollable
+        (call $subscribe-duration (local.get $duration))
+        (call $new_function (ref.cast (ref $Scope) (global.get $scope)) (ref.func $run-timers) (ref.null any))))
+    (global.set $timer-pollable-deadline (local.get $deadline))
+  )
+
+  ;; called when the timer pollable is ready, runs all of the timers that are
+  ;; due
+  (func $run-timers
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (local $timer (ref null $Timer))
+
+    ;; the pollable that fired was removed, even if the host woke us up too early
+    (global.set $timer-pollable (i32.const -1))
+    (global.set $timer-loop-time (call $monotonic-now))
+
+    (block $done (loop $run
+      (local.set $timer (call $next_timer))
+      (br_if $done (ref.is_null (local.get $timer)))
+      (br_if $done
+        (i64.gt_s (struct.get $Timer $deadline (local.get $timer)) (global.get $timer-loop-time)))
+
+      (drop (call $timer_heap_pop))
+      (if (i32.eqz (struct.get $Timer $repeat (local.get $timer)))
+        (then
+          (struct.set $Timer $cancelled (local.get $timer) (i32.const 1))
+          (drop (call $hashmap_delete
+            (ref.as_non_null (global.get $timers))
+            (struct.get $Timer $id (local.get $timer))))
+        )
+      )
+
+      (drop
+        (call $call_function
+          (struct.get $Timer $callback (local.get $timer))
+          (ref.null any)
+          (struct.get $Timer $arguments (local.get $timer))))
+      (call $run-microtasks)
+
+      ;; an interval is scheduled again unless its callback cleared it
+      (if (i32.and
+            (struct.get $Timer $repeat (local.get $timer))
+            (i32.eqz (struct.get $Timer $cancelled (local.get $timer))))
+        (then (call $schedule_timer (ref.as_non_null (local.get $timer)) (global.get $timer-loop-time)))
+      )
+      (br $run)
+    ))
+
+    (call $update_timer_pollable)
+    (ref.null any)
+  )
+
+  (func $add_timer (param $arguments (ref $JSArgs)) (param $repeat i32) (result anyref)
+    (local $callback anyref)
+    (local $delay f64)
+    (local $extra (ref $JSArgs))
+    (local $count i32)
+    (local $timer (ref $Timer))
+
+    (local.set $callback (call $get_argument (local.get $arguments) (i32.const 0)))
+    (if (i32.eqz (ref.test (ref $Function) (local.get $callback)))
+      (then
+        (call $throw_type_error
+          (call $new_static_string (i32.const {{ data(str='The "callback" argument must be of type function') }}) (i32.const {{ data_length() }})))
+      )
+    )
+
+    ;; like in Node delays outside of 1..2^31-1 milliseconds, including NaN,
+    ;; become 1, so 0 and 1 are the same delay
+    (local.set $delay (call $to_number (call $get_argument (local.get $arguments) (i32.const 1))))
+    (if (i32.eqz
+          (i32.and
+            (f64.ge (local.get $delay) (f64.const 1))
+            (f64.le (local.get $delay) (f64.const 2147483647))))
+      (then (local.set $delay (f64.const 1)))
+    )
+
+    (local.set $count (i32.sub (array.len (local.get $arguments)) (i32.const 2)))
+    (if (i32.lt_s (local.get $count) (i32.const 0))
+      (then (local.set $count (i32.const 0)))
+    )
+    (local.set $extra (array.new $JSArgs (ref.null any) (local.get $count)))
+    (if (local.get $count)
+      (then
+        (array.copy $JSArgs $JSArgs
+          (local.get $extra)
+          (i32.const 0)
+          (local.get $arguments)
+          (i32.const 2)
+          (local.get $count))
+      )
+    )
+
+    (local.set $timer
+      (struct.new $Timer
+        (global.get $next-timer-id)
+        (local.get $callback)
+        (local.get $extra)
+        (i64.trunc_f64_s (f64.mul (local.get $delay) (f64.const 1000000)))
+        (local.get $repeat)
+        (i64.const 0)
+        (i32.const 0)
+        (i32.const 0)))
+    (global.set $next-timer-id (i32.add (global.get $next-timer-id) (i32.const 1)))
+
+    (if (ref.is_null (global.get $timers))
+      (then (global.set $timers (call $new_hashmap)))
+    )
+    (call $hashmap_set
+      (ref.as_non_null (global.get $timers))
+      (struct.get $Timer $id (local.get $timer))
+      (local.get $timer))
+    ;; like Node, the clock is read again for every timer, as the time spent
+    ;; since the task started shouldn't count towards the delay
+    (call $schedule_timer (local.get $timer) (call $monotonic-now))
+    (call $update_timer_pollable)
+
+    (call $new_number_i32 (struct.get $Timer $id (local.get $timer)))
+  )
+
+  (func $setTimeout
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+
+    (call $add_timer (local.get $arguments) (i32.const 0))
+  )
+
+  (func $setInterval
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+
+    (call $add_timer (local.get $arguments) (i32.const 1))
+  )
+
+  ;; clearTimeout and clearInterval are the same function, like in browsers
+  ;; they can clear either kind of timer, unknown ids are ignored
+  (func $clearTimeout
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (local $id anyref)
+    (local $timer anyref)
+
+    (local.set $id (call $get_argument (local.get $arguments) (i32.const 0)))
+    (if (i32.or
+          (ref.is_null (global.get $timers))
+          (i32.eqz (call $is_number (local.get $id))))
+      (then (return (ref.null any)))
+    )
+
+    (local.set $timer
+      (call $hashmap_get
+        (ref.as_non_null (global.get $timers))
+        (i32.trunc_sat_f64_s (call $number_value (local.get $id)))))
+    (if (ref.test (ref $Timer) (local.get $timer))
+      (then
+        (struct.set $Timer $cancelled (ref.cast (ref $Timer) (local.get $timer)) (i32.const 1))
+        (drop (call $hashmap_delete
+          (ref.as_non_null (global.get $timers))
+          (struct.get $Timer $id (ref.cast (ref $Timer) (local.get $timer)))))
+        (call $update_timer_pollable)
+      )
+    )
+
+    (ref.null any)
+  )
+
+  (elem declare func $run-timers)
+  (elem declare func $setTimeout)
+  (elem declare func $setInterval)
+  (elem declare func $clearTimeout)
+
+  (func $install-timer-globals (param $scope (ref $Scope))
+    (call $set_variable (local.get $scope) (i32.const {{ data(str="setTimeout") }})
+      (call $new_function (local.get $scope) (ref.func $setTimeout) (ref.null any)))
+    (call $set_variable (local.get $scope) (i32.const {{ data(str="setInterval") }})
+      (call $new_function (local.get $scope) (ref.func $setInterval) (ref.null any)))
+    (call $set_variable (local.get $scope) (i32.const {{ data(str="clearTimeout") }})
+      (call $new_function (local.get $scope) (ref.func $clearTimeout) (ref.null any)))
+    (call $set_variable (local.get $scope) (i32.const {{ data(str="clearInterval") }})
+      (call $new_function (local.get $scope) (ref.func $clearTimeout) (ref.null any)))
+  )
@@ -0,0 +1,2420 @@
+  ;; `ArrayBuffer`, typed arrays, `DataView`, `TextEncoder` and `TextDecoder`
+  ;;
+  ;; The bytes of an ArrayBuffer live in a GC $CharArray, the same type strings
+  ;; use, so text can be encoded and decoded with a single copy. Typed arrays
+  ;; and DataViews are windows into a buffer: a byte offset and a length. Every
+  ;; element kind is described by its index in `typed_array_names`, elements
+  ;; are read and written as little endian i64 bits and converted from and to
+  ;; JS values in one place, $element_value and $element_bits.
+  ;;
+  ;; Buffers can't be resized, transferred or detached.
+
+  {% set typed_array_names = ["Int8", "Uint8", "Uint8Clamped", "Int16", "Uint16", "Int32", "Uint32", "Float32", "Float64", "BigInt64", "BigUint64"] %}
+  {% set typed_array_sizes = [1, 1, 1, 2, 2, 4, 4, 4, 8, 8, 8] %}
+  {% set kind_uint8 = 1 %}
+  {% set kind_uint8_clamped = 2 %}
+  {% set kind_float32 = 7 %}
+  {% set kind_float64 = 8 %}
+  {% set kind_big_int64 = 9 %}
+
+  (type $ArrayBuffer (sub final $Object (struct
+    (field $properties (mut (ref $HashMap)))
+    (field $prototype (mut anyref))
+    (field $hash (mut i32))
+    (field $data (ref $CharArray))
+  )))
+
+  (type $TypedArray (sub final $Object (struct
+    (field $properties (mut (ref $HashMap)))
+    (field $prototype (mut anyref))
+    (field $hash (mut i32))
+    ;; index of the element type in `typed_array_names`
+    (field $kind i32)
+    (field $buffer (ref $ArrayBuffer))
+    (field $offset i32)
+    ;; number of elements, not bytes
+    (field $length i32)
+  )))
+
+  (type $DataView (sub final $Object (struct
+    (field $properties (mut (ref $HashMap)))
+    (field $prototype (mut anyref))
+    (field $hash (mut i32))
+    (field $buffer (ref $ArrayBuffer))
+    (field $offset i32)
+    (field $length i32)
+  )))
+
+  (type $TypedArrayIterator (sub final $Object (struct
+    (field $properties (mut (ref $HashMap)))
+    (field $prototype (mut anyref))
+    (field $hash (mut i32))
+    (field $array (ref $TypedArray))
+    ;; 0 - keys, 1 - values, 2 - [index, value] pairs
+    (field $kind i32)
+    (field $index (mut i32))
+  )))
+
+  (global $array-buffer-prototype (mut (ref null $Object)) (ref.null $Object))
+  (global $data-view-prototype (mut (ref null $Object)) (ref.null $Object))
+  (global $typed-array-iterator-prototype (mut (ref null $Object)) (ref.null $Object))
+  ;; the prototype of each element kind, they all inherit from the shared
+  ;; %TypedArray%.prototype
+  (global $typed-array-prototypes (mut (ref null $AnyrefArray)) (ref.null $AnyrefArray))
+
+  (func $element_size (param $kind i32) (result i32)
+    {% for size in typed_array_sizes %}
+    (if (i32.eq (local.get $kind) (i32.const {{ loop.index0 }}))
+      (then (return (i32.const {{ size }})))
+    )
+    {% endfor %}
+    (unreachable)
+  )
+
+  (func $typed_array_name (param $kind i32) (result anyref)
+    {% for name in typed_array_names %}
+    (if (i32.eq (local.get $kind) (i32.const {{ loop.index0 }}))
+      (then
+        (return (call $new_static_string (i32.const {{ data(str=name ~ "Array") }}) (i32.const {{ data_length() }})))
+      )
+    )
+    {% endfor %}
+    (unreachable)
+  )
+
+  (func $is_bigint_kind (param $kind i32) (result i32)
+    (i32.ge_u (local.get $kind) (i32.const {{ kind_big_int64 }}))
+  )
+
+  (func $is_float_kind (param $kind i32) (result i32)
+    (i32.or
+      (i32.eq (local.get $kind) (i32.const {{ kind_float32 }}))
+      (i32.eq (local.get $kind) (i32.const {{ kind_float64 }})))
+  )
+
+  ;; reads $size bytes as a little endian number
+  (func $buffer_load (param $data (ref $CharArray)) (param $position i32) (param $size i32) (result i64)
+    (local $result i64)
+
+    (block $break (loop $bytes
+      (br_if $break (i32.eqz (local.get $size)))
+      (local.set $size (i32.sub (local.get $size) (i32.const 1)))
+      (local.set $result
+        (i64.or
+          (i64.shl (local.get $result) (i64.const 8))
+          (i64.extend_i32_u
+            (array.get_u $CharArray (local.get $data) (i32.add (local.get $position) (local.get $size))))))
+      (br $bytes)
+    ))
+    (local.get $result)
+  )
+
+  ;; writes the lowest $size bytes of $bits in little endian order
+  (func $buffer_store (param $data (ref $CharArray)) (param $position i32) (param $size i32) (param $bits i64)
+    (local $i i32)
+
+    (block $break (loop $bytes
+      (br_if $break (i32.ge_u (local.get $i) (local.get $size)))
+      (array.set $CharArray
+        (local.get $data)
+        (i32.add (local.get $position) (local.get $i))
+        (i32.wrap_i64 (local.get $bits)))
+      (local.set $bits (i64.shr_u (local.get $bits) (i64.const 8)))
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $bytes)
+    ))
+  )
+
+  ;; reverses the order of the lowest $size bytes, for big endian DataView access
+  (func $byte_swap (param $bits i64) (param $size i32) (result i64)
+    (local $result i64)
+
+    (block $break (loop $bytes
+      (br_if $break (i32.eqz (local.get $size)))
+      (local.set $result
+        (i64.or
+          (i64.shl (local.get $result) (i64.const 8))
+          (i64.and (local.get $bits) (i64.const 0xff))))
+      (local.set $bits (i64.shr_u (local.get $bits) (i64.const 8)))
+      (local.set $size (i32.sub (local.get $size) (i32.const 1)))
+      (br $bytes)
+    ))
+    (local.get $result)
+  )
+
+  ;; the JS value of an element from its bits, which are zero extended
+  (func $element_value (param $kind i32) (param $bits i64) (result anyref)
+    (block $big_uint64
+      (block $big_int64
+        (block $float64
+          (block $float32
+            (block $uint32
+              (block $int32
+                (block $int16
+                  (block $int8
+                    (block $small_unsigned
+                      (br_table
+                        $int8 $small_unsigned $small_unsigned $int16 $small_unsigned
+                        $int32 $uint32 $float32 $float64 $big_int64 $big_uint64
+                        (local.get $kind))
+                    )
+                    (return (ref.i31 (i32.wrap_i64 (local.get $bits))))
+                  )
+                  (return (call $new_number_i32 (i32.wrap_i64 (i64.extend8_s (local.get $bits)))))
+                )
+                (return (call $new_number_i32 (i32.wrap_i64 (i64.extend16_s (local.get $bits)))))
+              )
+              (return (call $new_number_i32 (i32.wrap_i64 (local.get $bits))))
+            )
+            (return (call $new_number (f64.convert_i64_u (local.get $bits))))
+          )
+          (return
+            (call $new_number (f64.promote_f32 (f32.reinterpret_i32 (i32.wrap_i64 (local.get $bits))))))
+        )
+        (return (call $new_number (f64.reinterpret_i64 (local.get $bits))))
+      )
+      (return (call $bigint_from_i64 (local.get $bits)))
+    )
+    (call $new_bigint (i32.const 0) (call $big_from_u64 (local.get $bits)))
+  )
+
+  ;; converts a JS value to the bits stored for an element, integer kinds wrap
+  ;; around and Uint8Clamped rounds to the nearest value in range
+  (func $element_bits (param $kind i32) (param $value anyref) (result i64)
+    (local $number f64)
+
+    (if (call $is_bigint_kind (local.get $kind))
+      (then (return (call $bigint_to_u64 (call $to_bigint (local.get $value)))))
+    )
+
+    (local.set $number (call $to_number (local.get $value)))
+    (if (i32.eq (local.get $kind) (i32.const {{ kind_float64 }}))
+      (then (return (i64.reinterpret_f64 (local.get $number))))
+    )
+    (if (i32.eq (local.get $kind) (i32.const {{ kind_float32 }}))
+      (then
+        (return (i64.extend_i32_u (i32.reinterpret_f32 (f32.demote_f64 (local.get $number)))))
+      )
+    )
+    (if (i32.eq (local.get $kind) (i32.const {{ kind_uint8_clamped }}))
+      (then
+        (if (f64.ne (local.get $number) (local.get $number))
+          (then (return (i64.const 0)))
+        )
+        ;; ties are rounded to even, just like f64.nearest does
+        (return
+          (i64.trunc_f64_u
+            (f64.nearest
+              (f64.min (f64.max (local.get $number) (f64.const 0)) (f64.const 255)))))
+      )
+    )
+    (i64.extend_i32_u (call $f64_to_int32 (local.get $number)))
+  )
+
+  (func $typed_array_load (param $array (ref $TypedArray)) (param $index i32) (result i64)
+    (local $size i32)
+    (local.set $size (call $element_size (struct.get $TypedArray $kind (local.get $array))))
+    (call $buffer_load
+      (struct.get $ArrayBuffer $data (struct.get $TypedArray $buffer (local.get $array)))
+      (i32.add
+        (struct.get $TypedArray $offset (local.get $array))
+        (i32.mul (local.get $index) (local.get $size)))
+      (local.get $size))
+  )
+
+  (func $typed_array_store (param $array (ref $TypedArray)) (param $index i32) (param $bits i64)
+    (local $size i32)
+    (local.set $size (call $element_size (struct.get $TypedArray $kind (local.get $array))))
+    (call $buffer_store
+      (struct.get $ArrayBuffer $data (struct.get $TypedArray $buffer (local.get $array)))
+      (i32.add
+        (struct.get $TypedArray $offset (local.get $array))
+        (i32.mul (local.get $index) (local.get $size)))
+      (local.get $size)
+      (local.get $bits))
+  )
+
+  ;; ta[index], out of range elements are undefined
+  (func $typed_array_get (param $array (ref $TypedArray)) (param $index i32) (result anyref)
+    (if (i32.ge_u (local.get $index) (struct.get $TypedArray $length (local.get $array)))
+      (then (return (ref.null any)))
+    )
+    (if (i32.eq (struct.get $TypedArray $kind (local.get $array)) (i32.const {{ kind_uint8 }}))
+      (then
+        (return
+          (ref.i31
+            (array.get_u $CharArray
+              (struct.get $ArrayBuffer $data (struct.get $TypedArray $buffer (local.get $array)))
+              (i32.add (struct.get $TypedArray $offset (local.get $array)) (local.get $index)))))
+      )
+    )
+    (call $element_value
+      (struct.get $TypedArray $kind (local.get $array))
+      (call $typed_array_load (local.get $array) (local.get $index)))
+  )
+
+  ;; ta[index] = value, the value is converted even if the index is out of
+  ;; range, but the write is ignored then
+  (func $typed_array_set (param $array (ref $TypedArray)) (param $index i32) (param $value anyref)
+    (local $bits i64)
+    (local.set $bits (call $element_bits (struct.get $TypedArray $kind (local.get $array)) (local.get $value)))
+    (if (i32.lt_u (local.get $index) (struct.get $TypedArray $length (local.get $array)))
+      (then (call $typed_array_store (local.get $array) (local.get $index) (local.get $bits)))
+    )
+  )
+
+  (func $typed_array_to_array (param $array (ref $TypedArray)) (result (ref $Array))
+    (local $result (ref $Array))
+    (local $data (ref $AnyrefArray))
+    (local $i i32)
+
+    (local.set $result (call $new_array (struct.get $TypedArray $length (local.get $array))))
+    (local.set $data (struct.get $Array $array (local.get $result)))
+    (block $break (loop $elements
+      (br_if $break (i32.ge_u (local.get $i) (array.len (local.get $data))))
+      (array.set $AnyrefArray (local.get $data) (local.get $i)
+        (call $typed_array_get (local.get $array) (local.get $i)))
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $elements)
+    ))
+    (local.get $result)
+  )
+
+  ;; ToIndex from the spec, returns -1 for values that are not valid indexes
+  (func $to_index (param $value anyref) (result f64)
+    (local $index f64)
+    (local.set $index (call $to_integer_or_infinity (local.get $value)))
+    (if (i32.or
+          (f64.lt (local.get $index) (f64.const 0))
+          (f64.gt (local.get $index) (f64.const 9007199254740991)))
+      (then (return (f64.const -1)))
+    )
+    (local.get $index)
+  )
+
+  ;; a relative index argument of methods like slice, negative values count
+  ;; from the end
+  (func $relative_index (param $value anyref) (param $length i32) (param $default i32) (result i32)
+    (local $index f64)
+
+    (if (ref.is_null (local.get $value))
+      (then (return (local.get $default)))
+    )
+    (local.set $index (call $to_integer_or_infinity (local.get $value)))
+    (if (f64.lt (local.get $index) (f64.const 0))
+      (then
+        (local.set $index
+          (f64.max
+            (f64.add (local.get $index) (f64.convert_i32_u (local.get $length)))
+            (f64.const 0)))
+      )
+      (else
+        (local.set $index (f64.min (local.get $index) (f64.convert_i32_u (local.get $length))))
+      )
+    )
+    (i32.trunc_f64_u (local.get $index))
+  )
+
+  (func $throw_range_error_with_number (param $message anyref) (param $number f64)
+    (call $throw_range_error
+      (call $concat_strings
+        (local.get $message)
+        (call $to_string (call $new_number (local.get $number)))))
+  )
+
+  (func $new_array_buffer (param $data (ref $CharArray)) (result (ref $ArrayBuffer))
+    (struct.new $ArrayBuffer
+      (call $new_hashmap)
+      (global.get $array-buffer-prototype)
+      (i32.const 0)
+      (local.get $data))
+  )
+
+  (func $new_typed_array (param $kind i32) (param $buffer (ref $ArrayBuffer)) (param $offset i32) (param $length i32) (result (ref $TypedArray))
+    (struct.new $TypedArray
+      (call $new_hashmap)
+      (array.get $AnyrefArray (global.get $typed-array-prototypes) (local.get $kind))
+      (i32.const 0)
+      (local.get $kind)
+      (local.get $buffer)
+      (local.get $offset)
+      (local.get $length))
+  )
+
+  ;; a typed array with a new buffer, the length has to be checked already
+  (func $allocate_typed_array (param $kind i32) (param $length i32) (result (ref $TypedArray))
+    (call $new_typed_array
+      (local.get $kind)
+      (call $new_array_buffer
+        (array.new_default $CharArray
+          (i32.mul (local.get $length) (call $element_size (local.get $kind)))))
+      (i32.const 0)
+      (local.get $length))
+  )
+
+  ;; the length argument of a typed array constructor converted with ToIndex,
+  ;; buffers are limited to 2GB
+  (func $typed_array_length (param $kind i32) (param $value anyref) (result i32)
+    (local $length f64)
+    (local.set $length (call $to_index (local.get $value)))
+    (if (i32.or
+          (f64.lt (local.get $length) (f64.const 0))
+          (f64.gt
+            (f64.mul (local.get $length) (f64.convert_i32_u (call $element_size (local.get $kind))))
+            (f64.const 2147483647)))
+      (then
+        (call $throw_range_error_with_number
+          (call $new_static_string (i32.const {{ data(str="Invalid typed array length: ") }}) (i32.const {{ data_length() }}))
+          (call $to_integer_or_infinity (local.get $value)))
+      )
+    )
+    (i32.trunc_f64_u (local.get $length))
+  )
+
+  (func $check_content_types (param $kind1 i32) (param $kind2 i32)
+    (if (i32.ne (call $is_bigint_kind (local.get $kind1)) (call $is_bigint_kind (local.get $kind2)))
+      (then
+        (call $throw_type_error
+          (call $new_static_string (i32.const {{ data(str="Cannot mix BigInt and other types, use explicit conversions") }}) (i32.const {{ data_length() }})))
+      )
+    )
+  )
+
+  ;; the values of an iterable or an array-like object, strings are iterated
+  ;; and other primitives don't have any values
+  (func $object_to_values (param $source anyref) (result (ref $Array))
+    (local $result (ref $Array))
+    (local $length f64)
+    (local $i i32)
+
+    (if (i32.or
+          (call $is_string (local.get $source))
+          (i32.or
+            (ref.test (ref $Array) (local.get $source))
+            (ref.test (ref $TypedArray) (local.get $source))))
+      (then (return (call $iterable_to_array (local.get $source))))
+    )
+    (if (i32.eqz (call $is_object (local.get $source)))
+      (then (return (call $new_array (i32.const 0))))
+    )
+    (if (ref.test (ref $Function)
+          (call $get_property
+            (local.get $source)
+            (call $well_known_symbol_key (global.get $symbol-iterator))))
+      (then (return (call $iterable_to_array (local.get $source))))
+    )
+
+    (local.set $length
+      (call $to_integer_or_infinity (call $get_property (local.get $source) (i32.const {{ data(str="length") }}))))
+    (local.set $length (f64.min (f64.max (local.get $length) (f64.const 0)) (f64.const 2147483647)))
+    (local.set $result (call $new_array (i32.trunc_f64_u (local.get $length))))
+    (block $break (loop $elements
+      (br_if $break (i32.ge_u (local.get $i) (array.len (struct.get $Array $array (local.get $result)))))
+      (array.set $AnyrefArray (struct.get $Array $array (local.get $result)) (local.get $i)
+        (call $get_computed_property (local.get $source) (call $new_number_i32 (local.get $i))))
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $elements)
+    ))
+    (local.get $result)
+  )
+
+  ;; a new typed array holding the values converted to the element kind
+  (func $typed_array_from_values (param $kind i32) (param $values (ref $Array)) (result (ref $TypedArray))
+    (local $result (ref $TypedArray))
+    (local $data (ref $AnyrefArray))
+    (local $i i32)
+
+    (local.set $data (struct.get $Array $array (local.get $values)))
+    (local.set $result (call $allocate_typed_array (local.get $kind) (array.len (local.get $data))))
+    (block $break (loop $elements
+      (br_if $break (i32.ge_u (local.get $i) (array.len (local.get $data))))
+      (call $typed_array_set (local.get $result) (local.get $i)
+        (array.get $AnyrefArray (local.get $data) (local.get $i)))
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $elements)
+    ))
+    (local.get $result)
+  )
+
+  ;; a typed array viewing an existing buffer, with the same checks and error
+  ;; messages as V8
+  (func $typed_array_from_buffer (param $kind i32) (param $buffer (ref $ArrayBuffer)) (param $byte_offset anyref) (param $length anyref) (result (ref $TypedArray))
+    (local $size f64)
+    (local $offset f64)
+    (local $buffer_length f64)
+    (local $byte_length f64)
+
+    (local.set $size (f64.convert_i32_u (call $element_size (local.get $kind))))
+    (local.set $buffer_length
+      (f64.convert_i32_u (array.len (struct.get $ArrayBuffer $data (local.get $buffer)))))
+
+    (local.set $offset (call $to_index (local.get $byte_offset)))
+    (if (f64.lt (local.get $offset) (f64.const 0))
+      (then
+        (call $throw_range_error_with_number
+          (call $new_static_string (i32.const {{ data(str="Start offset ") }}) (i32.const {{ data_length() }}))
+          (call $to_integer_or_infinity (local.get $byte_offset)))
+      )
+    )
+    (if (f64.ne (call $f64_rem (local.get $offset) (local.get $size)) (f64.const 0))
+      (then
+        (call $throw_range_error
+          (call $concat_strings
+            (call $concat_strings
+              (call $new_static_string (i32.const {{ data(str="start offset of ") }}) (i32.const {{ data_length() }}))
+              (call $typed_array_name (local.get $kind)))
+            (call $concat_strings
+              (call $new_static_string (i32.const {{ data(str=" should be a multiple of ") }}) (i32.const {{ data_length() }}))
+              (call $to_string (call $new_number (local.get $size))))))
+      )
+    )
+
+    (if (ref.is_null (local.get $length))
+      (then
+        (if (f64.ne (call $f64_rem (local.get $buffer_length) (local.get $size)) (f64.const 0))
+          (then
+            (call $throw_range_error
+              (call $concat_strings
+                (call $concat_strings
+                  (call $new_static_string (i32.const {{ data(str="byte length of ") }}) (i32.const {{ data_length() }}))
+                  (call $typed_array_name (local.get $kind)))
+                (call $concat_strings
+                  (call $new_static_string (i32.const {{ data(str=" should be a multiple of ") }}) (i32.const {{ data_length() }}))
+                  (call $to_string (call $new_number (local.get $size))))))
+          )
+        )
+        (local.set $byte_length (f64.sub (local.get $buffer_length) (local.get $offset)))
+        (if (f64.lt (local.get $byte_length) (f64.const 0))
+          (then
+            (call $throw_range_error
+              (call $concat_strings
+                (call $concat_strings
+                  (call $new_static_string (i32.const {{ data(str="Start offset ") }}) (i32.const {{ data_length() }}))
+                  (call $to_string (call $new_number (local.get $offset))))
+                (call $new_static_string (i32.const {{ data(str=" is outside the bounds of the buffer") }}) (i32.const {{ data_length() }}))))
+          )
+        )
+      )
+      (else
+        (local.set $byte_length
+          (f64.mul (call $to_index (local.get $length)) (local.get $size)))
+        (if (i32.or
+              (f64.lt (local.get $byte_length) (f64.const 0))
+              (f64.gt (f64.add (local.get $offset) (local.get $byte_length)) (local.get $buffer_length)))
+          (then
+            (call $throw_range_error_with_number
+              (call $new_static_string (i32.const {{ data(str="Invalid typed array length: ") }}) (i32.const {{ data_length() }}))
+              (call $to_integer_or_infinity (local.get $length)))
+          )
+        )
+      )
+    )
+
+    (call $new_typed_array
+      (local.get $kind)
+      (local.get $buffer)
+      (i32.trunc_f64_u (local.get $offset))
+      (i32.trunc_f64_u (f64.div (local.get $byte_length) (local.get $size))))
+  )
+
+  ;; the shared part of the constructors: new Uint8Array(length),
+  ;; new Uint8Array(buffer, byteOffset, length), new Uint8Array(typedArray) and
+  ;; new Uint8Array(iterableOrArrayLike)
+  (func $construct_typed_array (param $kind i32) (param $this anyref) (param $arguments (ref $JSArgs)) (result (ref $TypedArray))
+    (local $first anyref)
+    (local $source (ref $TypedArray))
+    (local $result (ref $TypedArray))
+    (local $i i32)
+
+    (if (i32.eqz (ref.test (ref $Object) (local.get $this)))
+      (then
+        (call $throw_type_error
+          (call $concat_strings
+            (call $concat_strings
+              (call $new_static_string (i32.const {{ data(str="Constructor ") }}) (i32.const {{ data_length() }}))
+              (call $typed_array_name (local.get $kind)))
+            (call $new_static_string (i32.const {{ data(str=" requires 'new'") }}) (i32.const {{ data_length() }}))))
+      )
+    )
+
+    (local.set $first (call $get_argument (local.get $arguments) (i32.const 0)))
+
+    (if (ref.test (ref $ArrayBuffer) (local.get $first))
+      (then
+        (return
+          (call $typed_array_from_buffer
+            (local.get $kind)
+            (ref.cast (ref $ArrayBuffer) (local.get $first))
+            (call $get_argument (local.get $arguments) (i32.const 1))
+            (call $get_argument (local.get $arguments) (i32.const 2))))
+      )
+    )
+
+    ;; typed arrays are copied element by element, without going through JS values
+    ;; when the kinds are the same
+    (if (ref.test (ref $TypedArray) (local.get $first))
+      (then
+        (local.set $source (ref.cast (ref $TypedArray) (local.get $first)))
+        (call $check_content_types (local.get $kind) (struct.get $TypedArray $kind (local.get $source)))
+        (local.set $result
+          (call $allocate_typed_array (local.get $kind) (struct.get $TypedArray $length (local.get $source))))
+        (block $break (loop $elements
+          (br_if $break (i32.ge_u (local.get $i) (struct.get $TypedArray $length (local.get $source))))
+          (if (i32.eq (struct.get $TypedArray $kind (local.get $source)) (local.get $kind))
+            (then
+              (call $typed_array_store (local.get $result) (local.get $i)
+                (call $typed_array_load (local.get $source) (local.get $i)))
+            )
+            (else
+              (call $typed_array_set (local.get $result) (local.get $i)
+                (call $typed_array_get (local.get $source) (local.get $i)))
+            )
+          )
+          (local.set $i (i32.add (local.get $i) (i32.const 1)))
+          (br $elements)
+        ))
+        (return (local.get $result))
+      )
+    )
+
+    (if (call $is_object (local.get $first))
+      (then
+        (return
+          (call $typed_array_from_values (local.get $kind) (call $object_to_values (local.get $first))))
+      )
+    )
+
+    (call $allocate_typed_array
+      (local.get $kind)
+      (call $typed_array_length (local.get $kind) (local.get $first)))
+  )
+
+  ;; `length`, `byteLength`, `byteOffset` and `buffer` are accessors in the
+  ;; spec, $get_property handles them like `length` on arrays
+  (func $typed_array_accessor (param $target anyref) (param $name i32) (result anyref)
+    (local $array (ref $TypedArray))
+    (local $view (ref $DataView))
+
+    (if (ref.test (ref $TypedArray) (local.get $target))
+      (then
+        (local.set $array (ref.cast (ref $TypedArray) (local.get $target)))
+        (if (i32.eq (local.get $name) (i32.const {{ data(str="length") }}))
+          (then (return (call $new_number_i32 (struct.get $TypedArray $length (local.get $array)))))
+        )
+        (if (i32.eq (local.get $name) (i32.const {{ data(str="byteLength") }}))
+          (then
+            (return
+              (call $new_number_i32
+                (i32.mul
+                  (struct.get $TypedArray $length (local.get $array))
+                  (call $element_size (struct.get $TypedArray $kind (local.get $array))))))
+          )
+        )
+        (if (i32.eq (local.get $name) (i32.const {{ data(str="byteOffset") }}))
+          (then (return (call $new_number_i32 (struct.get $TypedArray $offset (local.get $array)))))
+        )
+        (if (i32.eq (local.get $name) (i32.const {{ data(str="buffer") }}))
+          (then (return (struct.get $TypedArray $buffer (local.get $array))))
+        )
+        (return (global.get $no-value))
+      )
+    )
+
+    (if (ref.test (ref $DataView) (local.get $target))
+      (then
+        (local.set $view (ref.cast (ref $DataView) (local.get $target)))
+        (if (i32.eq (local.get $name) (i32.const {{ data(str="byteLength") }}))
+          (then (return (call $new_number_i32 (struct.get $DataView $length (local.get $view)))))
+        )
+        (if (i32.eq (local.get $name) (i32.const {{ data(str="byteOffset") }}))
+          (then (return (call $new_number_i32 (struct.get $DataView $offset (local.get $view)))))
+        )
+        (if (i32.eq (local.get $name) (i32.const {{ data(str="buffer") }}))
+          (then (return (struct.get $DataView $buffer (local.get $view))))
+        )
+        (return (global.get $no-value))
+      )
+    )
+
+    (if (i32.and
+          (ref.test (ref $ArrayBuffer) (local.get $target))
+          (i32.eq (local.get $name) (i32.const {{ data(str="byteLength") }})))
+      (then
+        (return
+          (call $new_number_i32
+            (array.len (struct.get $ArrayBuffer $data (ref.cast (ref $ArrayBuffer) (local.get $target))))))
+      )
+    )
+
+    (global.get $no-value)
+  )
+
+  ;; the bytes of an ArrayBuffer or a view as the array holding them, the
+  ;; offset of the first byte and the length, so host imports can copy them
+  ;; without an intermediate copy
+  (func $buffer_source_bytes (param $value anyref) (result (ref null $CharArray) i32 i32)
+    (local $array (ref $TypedArray))
+    (local $view (ref $DataView))
+
+    (if (ref.test (ref $ArrayBuffer) (local.get $value))
+      (then
+        (struct.get $ArrayBuffer $data (ref.cast (ref $ArrayBuffer) (local.get $value)))
+        (i32.const 0)
+        (array.len (struct.get $ArrayBuffer $data (ref.cast (ref $ArrayBuffer) (local.get $value))))
+        (return)
+      )
+    )
+    (if (ref.test (ref $TypedArray) (local.get $value))
+      (then
+        (local.set $array (ref.cast (ref $TypedArray) (local.get $value)))
+        (struct.get $ArrayBuffer $data (struct.get $TypedArray $buffer (local.get $array)))
+        (struct.get $TypedArray $offset (local.get $array))
+        (i32.mul
+          (struct.get $TypedArray $length (local.get $array))
+          (call $element_size (struct.get $TypedArray $kind (local.get $array))))
+        (return)
+      )
+    )
+    (if (ref.test (ref $DataView) (local.get $value))
+      (then
+        (local.set $view (ref.cast (ref $DataView) (local.get $value)))
+        (struct.get $ArrayBuffer $data (struct.get $DataView $buffer (local.get $view)))
+        (struct.get $DataView $offset (local.get $view))
+        (struct.get $DataView $length (local.get $view))
+        (return)
+      )
+    )
+    (ref.null $CharArray)
+    (i32.const 0)
+    (i32.const 0)
+  )
+
+  ;; copies bytes to linear memory, for passing them to host imports
+  (func $store_bytes (param $data (ref $CharArray)) (param $start i32) (param $length i32) (param $address i32)
+    (local $i i32)
+    (block $break (loop $bytes
+      (br_if $break (i32.ge_u (local.get $i) (local.get $length)))
+      (i32.store8
+        (i32.add (local.get $address) (local.get $i))
+        (array.get_u $CharArray (local.get $data) (i32.add (local.get $start) (local.get $i))))
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $bytes)
+    ))
+  )
+
+  ;; a Uint8Array with a copy of bytes that a host import wrote to linear memory
+  (func $load_bytes (param $address i32) (param $length i32) (result (ref $TypedArray))
+    (local $data (ref $CharArray))
+    (local $i i32)
+
+    (local.set $data (array.new_default $CharArray (local.get $length)))
+    (block $break (loop $bytes
+      (br_if $break (i32.ge_u (local.get $i) (local.get $length)))
+      (array.set $CharArray (local.get $data) (local.get $i)
+        (i32.load8_u (i32.add (local.get $address) (local.get $i))))
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $bytes)
+    ))
+    (call $new_typed_array
+      (i32.const {{ kind_uint8 }})
+      (call $new_array_buffer (local.get $data))
+      (i32.const 0)
+      (local.get $length))
+  )
+
+  ;; the bytes of an ArrayBuffer, a typed array or a DataView, anything else is
+  ;; converted to a string, used for data passed to the host
+  (func $value_bytes (param $value anyref) (result (ref $CharArray))
+    (local $array (ref $TypedArray))
+    (local $view (ref $DataView))
+    (local $data (ref $CharArray))
+    (local $length i32)
+
+    (if (ref.test (ref $ArrayBuffer) (local.get $value))
+      (then (return (struct.get $ArrayBuffer $data (ref.cast (ref $ArrayBuffer) (local.get $value)))))
+    )
+    (if (ref.test (ref $TypedArray) (local.get $value))
+      (then
+        (local.set $array (ref.cast (ref $TypedArray) (local.get $value)))
+        (local.set $length
+          (i32.mul
+            (struct.get $TypedArray $length (local.get $array))
+            (call $element_size (struct.get $TypedArray $kind (local.get $array)))))
+        (local.set $data (array.new_default $CharArray (local.get $length)))
+        (array.copy $CharArray $CharArray
+          (local.get $data)
+          (i32.const 0)
+          (struct.get $ArrayBuffer $data (struct.get $TypedArray $buffer (local.get $array)))
+          (struct.get $TypedArray $offset (local.get $array))
+          (local.get $length))
+        (return (local.get $data))
+      )
+    )
+    (if (ref.test (ref $DataView) (local.get $value))
+      (then
+        (local.set $view (ref.cast (ref $DataView) (local.get $value)))
+        (local.set $data (array.new_default $CharArray (struct.get $DataView $length (local.get $view))))
+        (array.copy $CharArray $CharArray
+          (local.get $data)
+          (i32.const 0)
+          (struct.get $ArrayBuffer $data (struct.get $DataView $buffer (local.get $view)))
+          (struct.get $DataView $offset (local.get $view))
+          (struct.get $DataView $length (local.get $view)))
+        (return (local.get $data))
+      )
+    )
+    ;; the data of a string can be longer than the string itself
+    (local.set $value (call $to_string (local.get $value)))
+    (local.set $length (call $string_length (local.get $value)))
+    (local.set $data (call $string_data (local.get $value)))
+    (if (i32.eq (array.len (local.get $data)) (local.get $length))
+      (then (return (local.get $data)))
+    )
+    (local.set $data (array.new_default $CharArray (local.get $length)))
+    (call $copy_string_data (local.get $value) (local.get $data) (i32.const 0))
+    (local.get $data)
+  )
+
+  (func $this_typed_array (param $this anyref) (param $method anyref) (result (ref $TypedArray))
+    (if (ref.test (ref $TypedArray) (local.get $this))
+      (then (return (ref.cast (ref $TypedArray) (local.get $this))))
+    )
+    (call $throw_type_error
+      (call $concat_strings
+        (call $concat_strings
+          (call $new_static_string (i32.const {{ data(str="Method %TypedArray%.prototype.") }}) (i32.const {{ data_length() }}))
+          (local.get $method))
+        (call $new_static_string (i32.const {{ data(str=" called on incompatible receiver") }}) (i32.const {{ data_length() }}))))
+    (unreachable)
+  )
+
+  (func $typed_array_callback (param $arguments (ref $JSArgs)) (result (ref $Function))
+    (local $callback anyref)
+    (local.set $callback (call $get_argument (local.get $arguments) (i32.const 0)))
+    (if (i32.eqz (ref.test (ref $Function) (local.get $callback)))
+      (then
+        (call $throw_type_error
+          (call $new_static_string (i32.const {{ data(str="callback is not a function") }}) (i32.const {{ data_length() }})))
+      )
+    )
+    (ref.cast (ref $Function) (local.get $callback))
+  )
+
+  (func $call_typed_array_callback (param $callback (ref $Function)) (param $this_arg anyref) (param $array (ref $TypedArray)) (param $index i32) (result anyref)
+    (call $call_function
+      (local.get $callback)
+      (local.get $this_arg)
+      (array.new_fixed $JSArgs 3
+        (call $typed_array_get (local.get $array) (local.get $index))
+        (call $new_number_i32 (local.get $index))
+        (local.get $array)))
+  )
+
+  ;; forEach, every, some, find and findIndex only differ in what they do with
+  ;; the result of the callback, $mode is the index in `typed_array_predicates`
+  {% set typed_array_predicates = ["forEach", "every", "some", "find", "findIndex"] %}
+  (func $typed_array_iterate (param $this anyref) (param $arguments (ref $JSArgs)) (param $mode i32) (param $name anyref) (result anyref)
+    (local $array (ref $TypedArray))
+    (local $callback (ref $Function))
+    (local $this_arg anyref)
+    (local $result i32)
+    (local $i i32)
+
+    (local.set $array (call $this_typed_array (local.get $this) (local.get $name)))
+    (local.set $callback (call $typed_array_callback (local.get $arguments)))
+    (local.set $this_arg (call $get_argument (local.get $arguments) (i32.const 1)))
+
+    (block $break (loop $elements
+      (br_if $break (i32.ge_u (local.get $i) (struct.get $TypedArray $length (local.get $array))))
+      (local.set $result
+        (call $cast_ref_to_i32_bool
+          (call $call_typed_array_callback
+            (local.get $callback) (local.get $this_arg) (local.get $array) (local.get $i))))
+      ;; every
+      (if (i32.and (i32.eq (local.get $mode) (i32.const 1)) (i32.eqz (local.get $result)))
+        (then (return (global.get $false)))
+      )
+      (if (local.get $result)
+        (then
+          ;; some
+          (if (i32.eq (local.get $mode) (i32.const 2))
+            (then (return (global.get $true)))
+          )
+          ;; find
+          (if (i32.eq (local.get $mode) (i32.const 3))
+            (then (return (call $typed_array_get (local.get $array) (local.get $i))))
+          )
+          ;; findIndex
+          (if (i32.eq (local.get $mode) (i32.const 4))
+            (then (return (call $new_number_i32 (local.get $i))))
+          )
+        )
+      )
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $elements)
+    ))
+
+    (if (i32.eq (local.get $mode) (i32.const 1))
+      (then (return (global.get $true)))
+    )
+    (if (i32.eq (local.get $mode) (i32.const 2))
+      (then (return (global.get $false)))
+    )
+    (if (i32.eq (local.get $mode) (i32.const 4))
+      (then (return (ref.i31 (i32.const -1))))
+    )
+    (ref.null any)
+  )
+
+  {% for name in typed_array_predicates %}
+  (func $TypedArray-prototype-{{ name }}
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+
+    (call $typed_array_iterate
+      (local.get $this)
+      (local.get $arguments)
+      (i32.const {{ loop.index0 }})
+      (call $new_static_string (i32.const {{ data(str=name) }}) (i32.const {{ data_length() }})))
+  )
+  (elem declare func $TypedArray-prototype-{{ name }})
+  {% endfor %}
+
+  (func $TypedArray-prototype-map
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (local $array (ref $TypedArray))
+    (local $callback (ref $Function))
+    (local $result (ref $TypedArray))
+    (local $i i32)
+
+    (local.set $array
+      (call $this_typed_array (local.get $this)
+        (call $new_static_string (i32.const {{ data(str="map") }}) (i32.const {{ data_length() }}))))
+    (local.set $callback (call $typed_array_callback (local.get $arguments)))
+    (local.set $result
+      (call $allocate_typed_array
+        (struct.get $TypedArray $kind (local.get $array))
+        (struct.get $TypedArray $length (local.get $array))))
+
+    (block $break (loop $elements
+      (br_if $break (i32.ge_u (local.get $i) (struct.get $TypedArray $length (local.get $array))))
+      (call $typed_array_set (local.get $result) (local.get $i)
+        (call $call_typed_array_callback
+          (local.get $callback)
+          (call $get_argument (local.get $arguments) (i32.const 1))
+          (local.get $array)
+          (local.get $i)))
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $elements)
+    ))
+    (local.get $result)
+  )
+
+  (func $TypedArray-prototype-filter
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (local $array (ref $TypedArray))
+    (local $callback (ref $Function))
+    (local $kept (ref $Array))
+    (local $count i32)
+    (local $i i32)
+
+    (local.set $array
+      (call $this_typed_array (local.get $this)
+        (call $new_static_string (i32.const {{ data(str="filter") }}) (i32.const {{ data_length() }}))))
+    (local.set $callback (call $typed_array_callback (local.get $arguments)))
+    (local.set $kept (call $new_array (i32.const 0)))
+
+    (block $break (loop $elements
+      (br_if $break (i32.ge_u (local.get $i) (struct.get $TypedArray $length (local.get $array))))
+      (if (call $cast_ref_to_i32_bool
+            (call $call_typed_array_callback
+              (local.get $callback)
+              (call $get_argument (local.get $arguments) (i32.const 1))
+              (local.get $array)
+              (local.get $i)))
+        (then
+          (call $array_set (local.get $kept) (local.get $count)
+            (call $typed_array_get (local.get $array) (local.get $i)))
+          (local.set $count (i32.add (local.get $count) (i32.const 1)))
+        )
+      )
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $elements)
+    ))
+    (call $typed_array_from_values (struct.get $TypedArray $kind (local.get $array)) (local.get $kept))
+  )
+
+  (func $TypedArray-prototype-reduce
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (local $array (ref $TypedArray))
+    (local $callback (ref $Function))
+    (local $accumulator anyref)
+    (local $i i32)
+
+    (local.set $array
+      (call $this_typed_array (local.get $this)
+        (call $new_static_string (i32.const {{ data(str="reduce") }}) (i32.const {{ data_length() }}))))
+    (local.set $callback (call $typed_array_callback (local.get $arguments)))
+
+    (if (i32.ge_u (array.len (local.get $arguments)) (i32.const 2))
+      (then (local.set $accumulator (array.get $JSArgs (local.get $arguments) (i32.const 1))))
+      (else
+        (if (i32.eqz (struct.get $TypedArray $length (local.get $array)))
+          (then
+            (call $throw_type_error
+              (call $new_static_string (i32.const {{ data(str="Reduce of empty array with no initial value") }}) (i32.const {{ data_length() }})))
+          )
+        )
+        (local.set $accumulator (call $typed_array_get (local.get $array) (i32.const 0)))
+        (local.set $i (i32.const 1))
+      )
+    )
+
+    (block $break (loop $elements
+      (br_if $break (i32.ge_u (local.get $i) (struct.get $TypedArray $length (local.get $array))))
+      (local.set $accumulator
+        (call $call_function
+          (local.get $callback)
+          (ref.null any)
+          (array.new_fixed $JSArgs 4
+            (local.get $accumulator)
+            (call $typed_array_get (local.get $array) (local.get $i))
+            (call $new_number_i32 (local.get $i))
+            (local.get $array))))
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $elements)
+    ))
+    (local.get $accumulator)
+  )
+
+  (func $TypedArray-prototype-at
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (local $array (ref $TypedArray))
+    (local $index f64)
+
+    (local.set $array
+      (call $this_typed_array (local.get $this)
+        (call $new_static_string (i32.const {{ data(str="at") }}) (i32.const {{ data_length() }}))))
+    (local.set $index
+      (call $to_integer_or_infinity (call $get_argument (local.get $arguments) (i32.const 0))))
+    (if (f64.lt (local.get $index) (f64.const 0))
+      (then
+        (local.set $index
+          (f64.add (local.get $index) (f64.convert_i32_u (struct.get $TypedArray $length (local.get $array)))))
+      )
+    )
+    (if (i32.or
+          (f64.lt (local.get $index) (f64.const 0))
+          (f64.ge (local.get $index) (f64.convert_i32_u (struct.get $TypedArray $length (local.get $array)))))
+      (then (return (ref.null any)))
+    )
+    (call $typed_array_get (local.get $array) (i32.trunc_f64_u (local.get $index)))
+  )
+
+  (func $TypedArray-prototype-fill
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (local $array (ref $TypedArray))
+    (local $bits i64)
+    (local $i i32)
+    (local $end i32)
+
+    (local.set $array
+      (call $this_typed_array (local.get $this)
+        (call $new_static_string (i32.const {{ data(str="fill") }}) (i32.const {{ data_length() }}))))
+    (local.set $bits
+      (call $element_bits
+        (struct.get $TypedArray $kind (local.get $array))
+        (call $get_argument (local.get $arguments) (i32.const 0))))
+    (local.set $i
+      (call $relative_index
+        (call $get_argument (local.get $arguments) (i32.const 1))
+        (struct.get $TypedArray $length (local.get $array))
+        (i32.const 0)))
+    (local.set $end
+      (call $relative_index
+        (call $get_argument (local.get $arguments) (i32.const 2))
+        (struct.get $TypedArray $length (local.get $array))
+        (struct.get $TypedArray $length (local.get $array))))
+
+    (block $break (loop $elements
+      (br_if $break (i32.ge_u (local.get $i) (local.get $end)))
+      (call $typed_array_store (local.get $array) (local.get $i) (local.get $bits))
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $elements)
+    ))
+    (local.get $array)
+  )
+
+  ;; ta.set(source, offset), the values are read before anything is written,
+  ;; so a source sharing the same buffer is copied correctly
+  (func $TypedArray-prototype-set
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (local $array (ref $TypedArray))
+    (local $source anyref)
+    (local $values (ref $AnyrefArray))
+    (local $offset f64)
+    (local $i i32)
+
+    (local.set $array
+      (call $this_typed_array (local.get $this)
+        (call $new_static_string (i32.const {{ data(str="set") }}) (i32.const {{ data_length() }}))))
+    (local.set $source (call $get_argument (local.get $arguments) (i32.const 0)))
+    (local.set $offset
+      (call $to_integer_or_infinity (call $get_argument (local.get $arguments) (i32.const 1))))
+    (if (f64.lt (local.get $offset) (f64.const 0))
+      (then
+        (call $throw_range_error
+          (call $new_static_string (i32.const {{ data(str="offset is out of bounds") }}) (i32.const {{ data_length() }})))
+      )
+    )
+
+    (if (ref.test (ref $TypedArray) (local.get $source))
+      (then
+        (call $check_content_types
+          (struct.get $TypedArray $kind (local.get $array))
+          (struct.get $TypedArray $kind (ref.cast (ref $TypedArray) (local.get $source))))
+      )
+    )
+    (local.set $values (struct.get $Array $array (call $object_to_values (local.get $source))))
+    (if (f64.gt
+          (f64.add (local.get $offset) (f64.convert_i32_u (array.len (local.get $values))))
+          (f64.convert_i32_u (struct.get $TypedArray $length (local.get $array))))
+      (then
+        (call $throw_range_error
+          (call $new_static_string (i32.const {{ data(str="offset is out of bounds") }}) (i32.const {{ data_length() }})))
+      )
+    )
+
+    (block $break (loop $elements
+      (br_if $break (i32.ge_u (local.get $i) (array.len (local.get $values))))
+      (call $typed_array_set
+        (local.get $array)
+        (i32.add (i32.trunc_f64_u (local.get $offset)) (local.get $i))
+        (array.get $AnyrefArray (local.get $values) (local.get $i)))
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $elements)
+    ))
+    (ref.null any)
+  )
+
+  ;; a new view of the same buffer
+  (func $TypedArray-prototype-subarray
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (local $array (ref $TypedArray))
+    (local $start i32)
+    (local $end i32)
+
+    (local.set $array
+      (call $this_typed_array (local.get $this)
+        (call $new_static_string (i32.const {{ data(str="subarray") }}) (i32.const {{ data_length() }}))))
+    (local.set $start
+      (call $relative_index
+        (call $get_argument (local.get $arguments) (i32.const 0))
+        (struct.get $TypedArray $length (local.get $array))
+        (i32.const 0)))
+    (local.set $end
+      (call $relative_index
+        (call $get_argument (local.get $arguments) (i32.const 1))
+        (struct.get $TypedArray $length (local.get $array))
+        (struct.get $TypedArray $length (local.get $array))))
+    (if (i32.lt_u (local.get $end) (local.get $start))
+      (then (local.set $end (local.get $start)))
+    )
+
+    (call $new_typed_array
+      (struct.get $TypedArray $kind (local.get $array))
+      (struct.get $TypedArray $buffer (local.get $array))
+      (i32.add
+        (struct.get $TypedArray $offset (local.get $array))
+        (i32.mul
+          (local.get $start)
+          (call $element_size (struct.get $TypedArray $kind (local.get $array)))))
+      (i32.sub (local.get $end) (local.get $start)))
+  )
+
+  ;; a copy of a part of the array in a new buffer
+  (func $TypedArray-prototype-slice
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (local $array (ref $TypedArray))
+    (local $result (ref $TypedArray))
+    (local $size i32)
+    (local $start i32)
+    (local $end i32)
+
+    (local.set $array
+      (call $this_typed_array (local.get $this)
+        (call $new_static_string (i32.const {{ data(str="slice") }}) (i32.const {{ data_length() }}))))
+    (local.set $start
+      (call $relative_index
+        (call $get_argument (local.get $arguments) (i32.const 0))
+        (struct.get $TypedArray $length (local.get $array))
+        (i32.const 0)))
+    (local.set $end
+      (call $relative_index
+        (call $get_argument (local.get $arguments) (i32.const 1))
+        (struct.get $TypedArray $length (local.get $array))
+        (struct.get $TypedArray $length (local.get $array))))
+    (if (i32.lt_u (local.get $end) (local.get $start))
+      (then (local.set $end (local.get $start)))
+    )
+
+    (local.set $size (call $element_size (struct.get $TypedArray $kind (local.get $array))))
+    (local.set $result
+      (call $allocate_typed_array
+        (struct.get $TypedArray $kind (local.get $array))
+        (i32.sub (local.get $end) (local.get $start))))
+    (array.copy $CharArray $CharArray
+      (struct.get $ArrayBuffer $data (struct.get $TypedArray $buffer (local.get $result)))
+      (i32.const 0)
+      (struct.get $ArrayBuffer $data (struct.get $TypedArray $buffer (local.get $array)))
+      (i32.add
+        (struct.get $TypedArray $offset (local.get $array))
+        (i32.mul (local.get $start) (local.get $size)))
+      (i32.mul (i32.sub (local.get $end) (local.get $start)) (local.get $size)))
+    (local.get $result)
+  )
+
+  ;; indexOf and includes, $same_value_zero is set for includes, which also
+  ;; finds NaN
+  (func $typed_array_index_of (param $array (ref $TypedArray)) (param $arguments (ref $JSArgs)) (param $same_value_zero i32) (result i32)
+    (local $search anyref)
+    (local $element anyref)
+    (local $i i32)
+
+    (local.set $search (call $get_argument (local.get $arguments) (i32.const 0)))
+    (local.set $i
+      (call $relative_index
+        (call $get_argument (local.get $arguments) (i32.const 1))
+        (struct.get $TypedArray $length (local.get $array))
+        (i32.const 0)))
+
+    (block $break (loop $elements
+      (br_if $break (i32.ge_u (local.get $i) (struct.get $TypedArray $length (local.get $array))))
+      (local.set $element (call $typed_array_get (local.get $array) (local.get $i)))
+      (if (local.get $same_value_zero)
+        (then
+          (if (call $same_value_zero (local.get $element) (local.get $search))
+            (then (return (local.get $i)))
+          )
+        )
+        (else
+          (if (ref.eq (call $strict_equal (local.get $element) (local.get $search)) (global.get $true))
+            (then (return (local.get $i)))
+          )
+        )
+      )
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $elements)
+    ))
+    (i32.const -1)
+  )
+
+  (func $TypedArray-prototype-indexOf
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+
+    (call $new_number_i32
+      (call $typed_array_index_of
+        (call $this_typed_array (local.get $this)
+          (call $new_static_string (i32.const {{ data(str="indexOf") }}) (i32.const {{ data_length() }})))
+        (local.get $arguments)
+        (i32.const 0)))
+  )
+
+  (func $TypedArray-prototype-includes
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+
+    (call $new_boolean
+      (i32.ge_s
+        (call $typed_array_index_of
+          (call $this_typed_array (local.get $this)
+            (call $new_static_string (i32.const {{ data(str="includes") }}) (i32.const {{ data_length() }})))
+          (local.get $arguments)
+          (i32.const 1))
+        (i32.const 0)))
+  )
+
+  (func $TypedArray-prototype-lastIndexOf
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (local $array (ref $TypedArray))
+    (local $search anyref)
+    (local $from f64)
+    (local $i i32)
+
+    (local.set $array
+      (call $this_typed_array (local.get $this)
+        (call $new_static_string (i32.const {{ data(str="lastIndexOf") }}) (i32.const {{ data_length() }}))))
+    (local.set $search (call $get_argument (local.get $arguments) (i32.const 0)))
+    (local.set $from (f64.convert_i32_u (struct.get $TypedArray $length (local.get $array))))
+    (if (i32.ge_u (array.len (local.get $arguments)) (i32.const 2))
+      (then
+        (local.set $from
+          (call $to_integer_or_infinity (array.get $JSArgs (local.get $arguments) (i32.const 1))))
+        (if (f64.lt (local.get $from) (f64.const 0))
+          (then
+            (local.set $from (f64.add (local.get $from) (f64.convert_i32_u (struct.get $TypedArray $length (local.get $array)))))
+          )
+        )
+        (local.set $from
+          (f64.min
+            (f64.add (local.get $from) (f64.const 1))
+            (f64.convert_i32_u (struct.get $TypedArray $length (local.get $array)))))
+      )
+    )
+    (if (f64.lt (local.get $from) (f64.const 0))
+      (then (return (ref.i31 (i32.const -1))))
+    )
+
+    ;; $i is one past the element that's compared
+    (local.set $i (i32.trunc_f64_u (local.get $from)))
+    (block $break (loop $elements
+      (br_if $break (i32.eqz (local.get $i)))
+      (local.set $i (i32.sub (local.get $i) (i32.const 1)))
+      (if (ref.eq
+            (call $strict_equal (call $typed_array_get (local.get $array) (local.get $i)) (local.get $search))
+            (global.get $true))
+        (then (return (call $new_number_i32 (local.get $i))))
+      )
+      (br $elements)
+    ))
+    (ref.i31 (i32.const -1))
+  )
+
+  (func $typed_array_join (param $array (ref $TypedArray)) (param $separator anyref) (result anyref)
+    (local $builder (ref $StringBuilder))
+    (local $i i32)
+
+    (local.set $builder (call $new_string_builder))
+    (block $break (loop $elements
+      (br_if $break (i32.ge_u (local.get $i) (struct.get $TypedArray $length (local.get $array))))
+      (if (local.get $i)
+        (then (call $builder_append_string (local.get $builder) (local.get $separator)))
+      )
+      (call $builder_append_string
+        (local.get $builder)
+        (call $to_string (call $typed_array_get (local.get $array) (local.get $i))))
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $elements)
+    ))
+    (call $builder_to_string (local.get $builder))
+  )
+
+  (func $TypedArray-prototype-join
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (local $separator anyref)
+
+    (local.set $separator (call $get_argument (local.get $arguments) (i32.const 0)))
+    (call $typed_array_join
+      (call $this_typed_array (local.get $this)
+        (call $new_static_string (i32.const {{ data(str="join") }}) (i32.const {{ data_length() }})))
+      (if (result anyref) (ref.is_null (local.get $separator))
+        (then (call $new_static_string (i32.const {{ data(str=",") }}) (i32.const {{ data_length() }})))
+        (else (call $to_string (local.get $separator)))))
+  )
+
+  (func $TypedArray-prototype-toString
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+
+    (call $typed_array_join
+      (call $this_typed_array (local.get $this)
+        (call $new_static_string (i32.const {{ data(str="toString") }}) (i32.const {{ data_length() }})))
+      (call $new_static_string (i32.const {{ data(str=",") }}) (i32.const {{ data_length() }})))
+  )
+
+  (func $TypedArray-prototype-reverse
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (local $array (ref $TypedArray))
+    (local $bits i64)
+    (local $i i32)
+    (local $j i32)
+
+    (local.set $array
+      (call $this_typed_array (local.get $this)
+        (call $new_static_string (i32.const {{ data(str="reverse") }}) (i32.const {{ data_length() }}))))
+    (local.set $j (struct.get $TypedArray $length (local.get $array)))
+    (block $break (loop $elements
+      (local.set $j (i32.sub (local.get $j) (i32.const 1)))
+      (br_if $break (i32.ge_s (local.get $i) (local.get $j)))
+      (local.set $bits (call $typed_array_load (local.get $array) (local.get $i)))
+      (call $typed_array_store (local.get $array) (local.get $i)
+        (call $typed_array_load (local.get $array) (local.get $j)))
+      (call $typed_array_store (local.get $array) (local.get $j) (local.get $bits))
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $elements)
+    ))
+    (local.get $array)
+  )
+
+  (func $new_typed_array_iterator (param $this anyref) (param $kind i32) (param $name anyref) (result (ref $TypedArrayIterator))
+    (struct.new $TypedArrayIterator
+      (call $new_hashmap)
+      (global.get $typed-array-iterator-prototype)
+      (i32.const 0)
+      (call $this_typed_array (local.get $this) (local.get $name))
+      (local.get $kind)
+      (i32.const 0))
+  )
+
+  (func $TypedArray-prototype-keys
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+
+    (call $new_typed_array_iterator
+      (local.get $this)
+      (i32.const 0)
+      (call $new_static_string (i32.const {{ data(str="keys") }}) (i32.const {{ data_length() }})))
+  )
+
+  (func $TypedArray-prototype-values
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+
+    (call $new_typed_array_iterator
+      (local.get $this)
+      (i32.const 1)
+      (call $new_static_string (i32.const {{ data(str="values") }}) (i32.const {{ data_length() }})))
+  )
+
+  (func $TypedArray-prototype-entries
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+
+    (call $new_typed_array_iterator
+      (local.get $this)
+      (i32.const 2)
+      (call $new_static_string (i32.const {{ data(str="entries") }}) (i32.const {{ data_length() }})))
+  )
+
+  (func $TypedArrayIterator-next
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (local $iterator (ref $TypedArrayIterator))
+    (local $array (ref $TypedArray))
+    (local $index i32)
+
+    (if (i32.eqz (ref.test (ref $TypedArrayIterator) (local.get $this)))
+      (then
+        (call $throw_type_error
+          (call $new_static_string (i32.const {{ data(str="next method called on incompatible receiver") }}) (i32.const {{ data_length() }})))
+      )
+    )
+    (local.set $iterator (ref.cast (ref $TypedArrayIterator) (local.get $this)))
+    (local.set $array (struct.get $TypedArrayIterator $array (local.get $iterator)))
+    (local.set $index (struct.get $TypedArrayIterator $index (local.get $iterator)))
+
+    (if (i32.ge_u (local.get $index) (struct.get $TypedArray $length (local.get $array)))
+      (then (return (call $new_iterator_result (ref.null any) (i32.const 1))))
+    )
+    (struct.set $TypedArrayIterator $index (local.get $iterator) (i32.add (local.get $index) (i32.const 1)))
+
+    (if (i32.eqz (struct.get $TypedArrayIterator $kind (local.get $iterator)))
+      (then (return (call $new_iterator_result (call $new_number_i32 (local.get $index)) (i32.const 0))))
+    )
+    (if (i32.eq (struct.get $TypedArrayIterator $kind (local.get $iterator)) (i32.const 1))
+      (then
+        (return
+          (call $new_iterator_result (call $typed_array_get (local.get $array) (local.get $index)) (i32.const 0)))
+      )
+    )
+    (call $new_iterator_result
+      (call $collection_entry
+        (call $new_number_i32 (local.get $index))
+        (call $typed_array_get (local.get $array) (local.get $index)))
+      (i32.const 0))
+  )
+
+  ;; Uint8Array.from(source, mapFn, thisArg)
+  (func $typed_array_from (param $kind i32) (param $arguments (ref $JSArgs)) (result (ref $TypedArray))
+    (local $values (ref $Array))
+    (local $data (ref $AnyrefArray))
+    (local $map anyref)
+    (local $i i32)
+
+    (local.set $map (call $get_argument (local.get $arguments) (i32.const 1)))
+    (if (i32.and
+          (i32.eqz (ref.is_null (local.get $map)))
+          (i32.eqz (ref.test (ref $Function) (local.get $map))))
+      (then
+        (call $throw_type_error
+          (call $new_static_string (i32.const {{ data(str="mapFn is not a function") }}) (i32.const {{ data_length() }})))
+      )
+    )
+
+    (local.set $values (call $object_to_values (call $get_argument (local.get $arguments) (i32.const 0))))
+    (if (ref.test (ref $Function) (local.get $map))
+      (then
+        (local.set $data (struct.get $Array $array (local.get $values)))
+        (block $break (loop $elements
+          (br_if $break (i32.ge_u (local.get $i) (array.len (local.get $data))))
+          (array.set $AnyrefArray (local.get $data) (local.get $i)
+            (call $call_function
+              (local.get $map)
+              (call $get_argument (local.get $arguments) (i32.const 2))
+              (array.new_fixed $JSArgs 2
+                (array.get $AnyrefArray (local.get $data) (local.get $i))
+                (call $new_number_i32 (local.get $i)))))
+          (local.set $i (i32.add (local.get $i) (i32.const 1)))
+          (br $elements)
+        ))
+      )
+    )
+    (call $typed_array_from_values (local.get $kind) (local.get $values))
+  )
+
+  (func $typed_array_of (param $kind i32) (param $arguments (ref $JSArgs)) (result (ref $TypedArray))
+    (local $result (ref $TypedArray))
+    (local $i i32)
+
+    (local.set $result (call $allocate_typed_array (local.get $kind) (array.len (local.get $arguments))))
+    (block $break (loop $elements
+      (br_if $break (i32.ge_u (local.get $i) (array.len (local.get $arguments))))
+      (call $typed_array_set (local.get $result) (local.get $i)
+        (array.get $JSArgs (local.get $arguments) (local.get $i)))
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $elements)
+    ))
+    (local.get $result)
+  )
+
+  {% for name in typed_array_names %}
+  (func ${{ name }}Array-constructor
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+
+    (call $construct_typed_array (i32.const {{ loop.index0 }}) (local.get $this) (local.get $arguments))
+  )
+  (elem declare func ${{ name }}Array-constructor)
+
+  (func ${{ name }}Array-from
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+
+    (call $typed_array_from (i32.const {{ loop.index0 }}) (local.get $arguments))
+  )
+  (elem declare func ${{ name }}Array-from)
+
+  (func ${{ name }}Array-of
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+
+    (call $typed_array_of (i32.const {{ loop.index0 }}) (local.get $arguments))
+  )
+  (elem declare func ${{ name }}Array-of)
+  {% endfor %}
+
+  (func $ArrayBuffer-constructor
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (local $length f64)
+
+    (if (i32.eqz (ref.test (ref $Object) (local.get $this)))
+      (then
+        (call $throw_type_error
+          (call $new_static_string (i32.const {{ data(str="Constructor ArrayBuffer requires 'new'") }}) (i32.const {{ data_length() }})))
+      )
+    )
+    (local.set $length (call $to_index (call $get_argument (local.get $arguments) (i32.const 0))))
+    (if (i32.or
+          (f64.lt (local.get $length) (f64.const 0))
+          (f64.gt (local.get $length) (f64.const 2147483647)))
+      (then
+        (call $throw_range_error
+          (call $new_static_string (i32.const {{ data(str="Invalid array buffer length") }}) (i32.const {{ data_length() }})))
+      )
+    )
+    (call $new_array_buffer (array.new_default $CharArray (i32.trunc_f64_u (local.get $length))))
+  )
+
+  (func $ArrayBuffer-isView
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (local $value anyref)
+
+    (local.set $value (call $get_argument (local.get $arguments) (i32.const 0)))
+    (call $new_boolean
+      (i32.or
+        (ref.test (ref $TypedArray) (local.get $value))
+        (ref.test (ref $DataView) (local.get $value))))
+  )
+
+  (func $ArrayBuffer-prototype-slice
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (local $data (ref $CharArray))
+    (local $result (ref $CharArray))
+    (local $start i32)
+    (local $end i32)
+
+    (if (i32.eqz (ref.test (ref $ArrayBuffer) (local.get $this)))
+      (then
+        (call $throw_type_error
+          (call $new_static_string (i32.const {{ data(str="Method ArrayBuffer.prototype.slice called on incompatible receiver") }}) (i32.const {{ data_length() }})))
+      )
+    )
+    (local.set $data (struct.get $ArrayBuffer $data (ref.cast (ref $ArrayBuffer) (local.get $this))))
+    (local.set $start
+      (call $relative_index
+        (call $get_argument (local.get $arguments) (i32.const 0))
+        (array.len (local.get $data))
+        (i32.const 0)))
+    (local.set $end
+      (call $relative_index
+        (call $get_argument (local.get $arguments) (i32.const 1))
+        (array.len (local.get $data))
+        (array.len (local.get $data))))
+    (if (i32.lt_u (local.get $end) (local.get $start))
+      (then (local.set $end (local.get $start)))
+    )
+
+    (local.set $result (array.new_default $CharArray (i32.sub (local.get $end) (local.get $start))))
+    (array.copy $CharArray $CharArray
+      (local.get $result)
+      (i32.const 0)
+      (local.get $data)
+      (local.get $start)
+      (i32.sub (local.get $end) (local.get $start)))
+    (call $new_array_buffer (local.get $result))
+  )
+
+  (func $DataView-constructor
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (local $buffer (ref $ArrayBuffer))
+    (local $offset f64)
+    (local $length f64)
+    (local $buffer_length f64)
+
+    (if (i32.eqz (ref.test (ref $Object) (local.get $this)))
+      (then
+        (call $throw_type_error
+          (call $new_static_string (i32.const {{ data(str="Constructor DataView requires 'new'") }}) (i32.const {{ data_length() }})))
+      )
+    )
+    (if (i32.eqz (ref.test (ref $ArrayBuffer) (call $get_argument (local.get $arguments) (i32.const 0))))
+      (then
+        (call $throw_type_error
+          (call $new_static_string (i32.const {{ data(str="First argument to DataView constructor must be an ArrayBuffer") }}) (i32.const {{ data_length() }})))
+      )
+    )
+    (local.set $buffer (ref.cast (ref $ArrayBuffer) (call $get_argument (local.get $arguments) (i32.const 0))))
+    (local.set $buffer_length
+      (f64.convert_i32_u (array.len (struct.get $ArrayBuffer $data (local.get $buffer)))))
+
+    (local.set $offset (call $to_index (call $get_argument (local.get $arguments) (i32.const 1))))
+    (if (i32.or
+          (f64.lt (local.get $offset) (f64.const 0))
+          (f64.gt (local.get $offset) (local.get $buffer_length)))
+      (then
+        (call $throw_range_error
+          (call $concat_strings
+            (call $concat_strings
+              (call $new_static_string (i32.const {{ data(str="Start offset ") }}) (i32.const {{ data_length() }}))
+              (call $to_string
+                (call $new_number
+                  (call $to_integer_or_infinity (call $get_argument (local.get $arguments) (i32.const 1))))))
+            (call $new_static_string (i32.const {{ data(str=" is outside the bounds of the buffer") }}) (i32.const {{ data_length() }}))))
+      )
+    )
+
+    (if (ref.is_null (call $get_argument (local.get $arguments) (i32.const 2)))
+      (then (local.set $length (f64.sub (local.get $buffer_length) (local.get $offset))))
+      (else
+        (local.set $length (call $to_index (call $get_argument (local.get $arguments) (i32.const 2))))
+        (if (i32.or
+              (f64.lt (local.get $length) (f64.const 0))
+              (f64.gt (f64.add (local.get $offset) (local.get $length)) (local.get $buffer_length)))
+          (then
+            (call $throw_range_error_with_number
+              (call $new_static_string (i32.const {{ data(str="Invalid DataView length ") }}) (i32.const {{ data_length() }}))
+              (call $to_integer_or_infinity (call $get_argument (local.get $arguments) (i32.const 2))))
+          )
+        )
+      )
+    )
+
+    (struct.new $DataView
+      (call $new_hashmap)
+      (global.get $data-view-prototype)
+      (i32.const 0)
+      (local.get $buffer)
+      (i32.trunc_f64_u (local.get $offset))
+      (i32.trunc_f64_u (local.get $length)))
+  )
+
+  (func $this_data_view (param $this anyref) (result (ref $DataView))
+    (if (ref.test (ref $DataView) (local.get $this))
+      (then (return (ref.cast (ref $DataView) (local.get $this))))
+    )
+    (call $throw_type_error
+      (call $new_static_string (i32.const {{ data(str="DataView method called on incompatible receiver") }}) (i32.const {{ data_length() }})))
+    (unreachable)
+  )
+
+  ;; the position of an element in the buffer of a view, throws if it doesn't fit
+  (func $data_view_position (param $view (ref $DataView)) (param $index anyref) (param $size i32) (result i32)
+    (local $position f64)
+    (local.set $position (call $to_index (local.get $index)))
+    (if (i32.or
+          (f64.lt (local.get $position) (f64.const 0))
+          (f64.gt
+            (f64.add (local.get $position) (f64.convert_i32_u (local.get $size)))
+            (f64.convert_i32_u (struct.get $DataView $length (local.get $view)))))
+      (then
+        (call $throw_range_error
+          (call $new_static_string (i32.const {{ data(str="Offset is outside the bounds of the DataView") }}) (i32.const {{ data_length() }})))
+      )
+    )
+    (i32.add (struct.get $DataView $offset (local.get $view)) (i32.trunc_f64_u (local.get $position)))
+  )
+
+  ;; view.getInt16(byteOffset, littleEndian), big endian is the default
+  (func $data_view_get (param $this anyref) (param $arguments (ref $JSArgs)) (param $kind i32) (result anyref)
+    (local $view (ref $DataView))
+    (local $size i32)
+    (local $position i32)
+    (local $bits i64)
+
+    (local.set $view (call $this_data_view (local.get $this)))
+    (local.set $size (call $element_size (local.get $kind)))
+    (local.set $position
+      (call $data_view_position
+        (local.get $view)
+        (call $get_argument (local.get $arguments) (i32.const 0))
+        (local.get $size)))
+    (local.set $bits
+      (call $buffer_load
+        (struct.get $ArrayBuffer $data (struct.get $DataView $buffer (local.get $view)))
+        (local.get $position)
+        (local.get $size)))
+    (if (i32.eqz (call $cast_ref_to_i32_bool (call $get_argument (local.get $arguments) (i32.const 1))))
+      (then (local.set $bits (call $byte_swap (local.get $bits) (local.get $size))))
+    )
+    (call $element_value (local.get $kind) (local.get $bits))
+  )
+
+  ;; view.setInt16(byteOffset, value, littleEndian)
+  (func $data_view_set (param $this anyref) (param $arguments (ref $JSArgs)) (param $kind i32)
+    (local $view (ref $DataView))
+    (local $size i32)
+    (local $index f64)
+    (local $bits i64)
+
+    (local.set $view (call $this_data_view (local.get $this)))
+    (local.set $size (call $element_size (local.get $kind)))
+    ;; the offset is converted before the value, but checked after it
+    (local.set $index (call $to_index (call $get_argument (local.get $arguments) (i32.const 0))))
+    (local.set $bits
+      (call $element_bits (local.get $kind) (call $get_argument (local.get $arguments) (i32.const 1))))
+    (if (i32.eqz (call $cast_ref_to_i32_bool (call $get_argument (local.get $arguments) (i32.const 2))))
+      (then (local.set $bits (call $byte_swap (local.get $bits) (local.get $size))))
+    )
+    (call $buffer_store
+      (struct.get $ArrayBuffer $data (struct.get $DataView $buffer (local.get $view)))
+      (call $data_view_position (local.get $view) (call $new_number (local.get $index)) (local.get $size))
+      (local.get $size)
+      (local.get $bits))
+  )
+
+  {% for name in typed_array_names %}
+  {% if name != "Uint8Clamped" %}
+  (func $DataView-prototype-get{{ name }}
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+
+    (call $data_view_get (local.get $this) (local.get $arguments) (i32.const {{ loop.index0 }}))
+  )
+  (elem declare func $DataView-prototype-get{{ name }})
+
+  (func $DataView-prototype-set{{ name }}
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+
+    (call $data_view_set (local.get $this) (local.get $arguments) (i32.const {{ loop.index0 }}))
+    (ref.null any)
+  )
+  (elem declare func $DataView-prototype-set{{ name }})
+  {% endif %}
+  {% endfor %}
+
+  (func $TextEncoder-constructor
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+
+    (if (i32.eqz (ref.test (ref $Object) (local.get $this)))
+      (then
+        (call $throw_type_error
+          (call $new_static_string (i32.const {{ data(str="Constructor TextEncoder requires 'new'") }}) (i32.const {{ data_length() }})))
+      )
+    )
+    (local.get $this)
+  )
+
+  ;; strings are already UTF-8, but they can hold lone surrogates, which are
+  ;; encoded as U+FFFD. A surrogate takes 3 bytes, just like the replacement
+  ;; character, so they can be replaced in place.
+  (func $TextEncoder-prototype-encode
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (local $input anyref)
+    (local $data (ref $CharArray))
+    (local $i i32)
+
+    (local.set $input (call $get_argument (local.get $arguments) (i32.const 0)))
+    (local.set $input
+      (if (result anyref) (ref.is_null (local.get $input))
+        (then (call $new_static_string (i32.const {{ data(str="") }}) (i32.const {{ data_length() }})))
+        (else (call $to_string (local.get $input)))))
+
+    (local.set $data (array.new_default $CharArray (call $string_length (local.get $input))))
+    (call $copy_string_data (local.get $input) (local.get $data) (i32.const 0))
+    (block $break (loop $bytes
+      (br_if $break (i32.ge_u (i32.add (local.get $i) (i32.const 2)) (array.len (local.get $data))))
+      (if (i32.and
+            (i32.eq (array.get_u $CharArray (local.get $data) (local.get $i)) (i32.const 0xed))
+            (i32.ge_u (array.get_u $CharArray (local.get $data) (i32.add (local.get $i) (i32.const 1))) (i32.const 0xa0)))
+        (then
+          (array.set $CharArray (local.get $data) (local.get $i) (i32.const 0xef))
+          (array.set $CharArray (local.get $data) (i32.add (local.get $i) (i32.const 1)) (i32.const 0xbf))
+          (array.set $CharArray (local.get $data) (i32.add (local.get $i) (i32.const 2)) (i32.const 0xbd))
+        )
+      )
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $bytes)
+    ))
+
+    (call $new_typed_array
+      (i32.const {{ kind_uint8 }})
+      (call $new_array_buffer (local.get $data))
+      (i32.const 0)
+      (array.len (local.get $data)))
+  )
+
+  ;; encoder.encodeInto(string, uint8Array) writes as many whole characters as
+  ;; fit, `read` counts UTF-16 code units like in other engines
+  (func $TextEncoder-prototype-encodeInto
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (local $input anyref)
+    (local $destination (ref $TypedArray))
+    (local $data (ref $CharArray))
+    (local $result (ref $Object))
+    (local $length i32)
+    (local $sequence i32)
+    (local $read i32)
+    (local $written i32)
+    (local $i i32)
+
+    (local.set $input (call $to_string (call $get_argument (local.get $arguments) (i32.const 0))))
+    (if (i32.eqz (ref.test (ref $TypedArray) (call $get_argument (local.get $arguments) (i32.const 1))))
+      (then
+        (call $throw_type_error
+          (call $new_static_string (i32.const {{ data(str='The "dest" argument must be an instance of Uint8Array.') }}) (i32.const {{ data_length() }})))
+      )
+    )
+    (local.set $destination (ref.cast (ref $TypedArray) (call $get_argument (local.get $arguments) (i32.const 1))))
+    (if (i32.ne (struct.get $TypedArray $kind (local.get $destination)) (i32.const {{ kind_uint8 }}))
+      (then
+        (call $throw_type_error
+          (call $new_static_string (i32.const {{ data(str='The "dest" argument must be an instance of Uint8Array.') }}) (i32.const {{ data_length() }})))
+      )
+    )
+    (local.set $data (struct.get $ArrayBuffer $data (struct.get $TypedArray $buffer (local.get $destination))))
+    (local.set $length (call $string_length (local.get $input)))
+
+    (block $break (loop $characters
+      (br_if $break (i32.ge_u (local.get $i) (local.get $length)))
+      (local.set $sequence
+        (call $utf8_sequence_length (call $string_byte_at (local.get $input) (local.get $i))))
+      (br_if $break
+        (i32.gt_u
+          (i32.add (local.get $written) (local.get $sequence))
+          (struct.get $TypedArray $length (local.get $destination))))
+      (call $copy_string_range
+        (local.get $input)
+        (local.get $i)
+        (local.get $sequence)
+        (local.get $data)
+        (i32.add (struct.get $TypedArray $offset (local.get $destination)) (local.get $written)))
+      (local.set $i (i32.add (local.get $i) (local.get $sequence)))
+      (local.set $written (i32.add (local.get $written) (local.get $sequence)))
+      ;; characters outside of the BMP are two code units
+      (local.set $read
+        (i32.add (local.get $read)
+          (if (result i32) (i32.eq (local.get $sequence) (i32.const 4))
+            (then (i32.const 2))
+            (else (i32.const 1)))))
+      (br $characters)
+    ))
+
+    (local.set $result (call $new_object))
+    (call $set_property (local.get $result) (i32.const {{ data(str="read") }}) (call $new_number_i32 (local.get $read)))
+    (call $set_property (local.get $result) (i32.const {{ data(str="written") }}) (call $new_number_i32 (local.get $written)))
+    (local.get $result)
+  )
+
+  ;; copies $length bytes of a string starting at $start, encoding lone
+  ;; surrogates as U+FFFD
+  (func $copy_string_range (param $str anyref) (param $start i32) (param $length i32) (param $dest (ref $CharArray)) (param $dest_offset i32)
+    (local $i i32)
+
+    ;; 0xed starts a 3 byte sequence, which is a surrogate if the next byte is
+    ;; 0xa0 or more
+    (if (i32.eq (call $string_byte_at (local.get $str) (local.get $start)) (i32.const 0xed))
+      (then
+        (if (i32.ge_u
+              (call $string_byte_at (local.get $str) (i32.add (local.get $start) (i32.const 1)))
+              (i32.const 0xa0))
+          (then
+            (array.set $CharArray (local.get $dest) (local.get $dest_offset) (i32.const 0xef))
+            (array.set $CharArray (local.get $dest) (i32.add (local.get $dest_offset) (i32.const 1)) (i32.const 0xbf))
+            (array.set $CharArray (local.get $dest) (i32.add (local.get $dest_offset) (i32.const 2)) (i32.const 0xbd))
+            (return)
+          )
+        )
+      )
+    )
+    (block $break (loop $bytes
+      (br_if $break (i32.ge_u (local.get $i) (local.get $length)))
+      (array.set $CharArray
+        (local.get $dest)
+        (i32.add (local.get $dest_offset) (local.get $i))
+        (call $string_byte_at (local.get $str) (i32.add (local.get $start) (local.get $i))))
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $bytes)
+    ))
+  )
+
+  ;; new TextDecoder(label, { fatal, ignoreBOM }), only UTF-8 is supported
+  (func $TextDecoder-constructor
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (local $label anyref)
+    (local $options anyref)
+    (local $builder (ref $StringBuilder))
+    (local $byte i32)
+    (local $i i32)
+
+    (if (i32.eqz (ref.test (ref $Object) (local.get $this)))
+      (then
+        (call $throw_type_error
+          (call $new_static_string (i32.const {{ data(str="Constructor TextDecoder requires 'new'") }}) (i32.const {{ data_length() }})))
+      )
+    )
+
+    (local.set $label (call $get_argument (local.get $arguments) (i32.const 0)))
+    (if (i32.eqz (ref.is_null (local.get $label)))
+      (then
+        ;; labels are case-insensitive
+        (local.set $label (call $to_string (local.get $label)))
+        (local.set $builder (call $new_string_builder))
+        (block $break (loop $chars
+          (br_if $break (i32.ge_u (local.get $i) (call $string_length (local.get $label))))
+          (local.set $byte (call $string_byte_at (local.get $label) (local.get $i)))
+          (if (i32.lt_u (i32.sub (local.get $byte) (i32.const 0x41)) (i32.const 26))
+            (then (local.set $byte (i32.or (local.get $byte) (i32.const 0x20))))
+          )
+          (call $builder_append_byte (local.get $builder) (local.get $byte))
+          (local.set $i (i32.add (local.get $i) (i32.const 1)))
+          (br $chars)
+        ))
+        (if (i32.eqz
+              (i32.or
+                (call $string_equals
+                  (call $builder_to_string (local.get $builder))
+                  (call $new_static_string (i32.const {{ data(str="utf-8") }}) (i32.const {{ data_length() }})))
+                (i32.or
+                  (call $string_equals
+                    (call $builder_to_string (local.get $builder))
+                    (call $new_static_string (i32.const {{ data(str="utf8") }}) (i32.const {{ data_length() }})))
+                  (call $string_equals
+                    (call $builder_to_string (local.get $builder))
+                    (call $new_static_string (i32.const {{ data(str="unicode-1-1-utf-8") }}) (i32.const {{ data_length() }}))))))
+          (then
+            (call $throw_range_error
+              (call $concat_strings
+                (call $concat_strings
+                  (call $new_static_string (i32.const {{ data(str='The "') }}) (i32.const {{ data_length() }}))
+                  (local.get $label))
+                (call $new_static_string (i32.const {{ data(str='" encoding is not supported') }}) (i32.const {{ data_length() }}))))
+          )
+        )
+      )
+    )
+
+    (call $set_property
+      (local.get $this)
+      (i32.const {{ data(str="encoding") }})
+      (call $new_static_string (i32.const {{ data(str="utf-8") }}) (i32.const {{ data_length() }})))
+    (local.set $options (call $get_argument (local.get $arguments) (i32.const 1)))
+    (call $set_property
+      (local.get $this)
+      (i32.const {{ data(str="fatal") }})
+      (call $new_boolean
+        (if (result i32) (call $is_object (local.get $options))
+          (then
+            (call $cast_ref_to_i32_bool (call $get_property (local.get $options) (i32.const {{ data(str="fatal") }}))))
+          (else (i32.const 0)))))
+    (call $set_property
+      (local.get $this)
+      (i32.const {{ data(str="ignoreBOM") }})
+      (call $new_boolean
+        (if (result i32) (call $is_object (local.get $options))
+          (then
+            (call $cast_ref_to_i32_bool (call $get_property (local.get $options) (i32.const {{ data(str="ignoreBOM") }}))))
+          (else (i32.const 0)))))
+    (local.get $this)
+  )
+
+  ;; the number of bytes in a valid UTF-8 sequence at $position, or 0 if the
+  ;; bytes there are not a valid sequence. Overlong encodings and surrogates are
+  ;; invalid, like in the WHATWG decoder.
+  (func $utf8_valid_sequence (param $data (ref $CharArray)) (param $position i32) (param $end i32) (result i32)
+    (local $first i32)
+    (local $length i32)
+    (local $lower i32)
+    (local $upper i32)
+    (local $byte i32)
+    (local $i i32)
+
+    (local.set $first (array.get_u $CharArray (local.get $data) (local.get $position)))
+    (if (i32.lt_u (local.get $first) (i32.const 0x80))
+      (then (return (i32.const 1)))
+    )
+
+    (local.set $lower (i32.const 0x80))
+    (local.set $upper (i32.const 0xbf))
+    (block $known
+      (if (i32.and (i32.ge_u (local.get $first) (i32.const 0xc2)) (i32.le_u (local.get $first) (i32.const 0xdf)))
+        (then (local.set $length (i32.const 2)) (br $known))
+      )
+      (if (i32.and (i32.ge_u (local.get $first) (i32.const 0xe0)) (i32.le_u (local.get $first) (i32.const 0xef)))
+        (then
+          (local.set $length (i32.const 3))
+          (if (i32.eq (local.get $first) (i32.const 0xe0))
+            (then (local.set $lower (i32.const 0xa0)))
+          )
+          (if (i32.eq (local.get $first) (i32.const 0xed))
+            (then (local.set $upper (i32.const 0x9f)))
+          )
+          (br $known)
+        )
+      )
+      (if (i32.and (i32.ge_u (local.get $first) (i32.const 0xf0)) (i32.le_u (local.get $first) (i32.const 0xf4)))
+        (then
+          (local.set $length (i32.const 4))
+          (if (i32.eq (local.get $first) (i32.const 0xf0))
+            (then (local.set $lower (i32.const 0x90)))
+          )
+          (if (i32.eq (local.get $first) (i32.const 0xf4))
+            (then (local.set $upper (i32.const 0x8f)))
+          )
+          (br $known)
+        )
+      )
+      (return (i32.const 0))
+    )
+
+    ;; only the second byte has a narrower range
+    (local.set $i (i32.const 1))
+    (block $break (loop $continuation
+      (br_if $break (i32.ge_u (local.get $i) (local.get $length)))
+      (if (i32.ge_u (i32.add (local.get $position) (local.get $i)) (local.get $end))
+        (then (return (i32.const 0)))
+      )
+      (local.set $byte
+        (array.get_u $CharArray (local.get $data) (i32.add (local.get $position) (local.get $i))))
+      (if (i32.or (i32.lt_u (local.get $byte) (local.get $lower)) (i32.gt_u (local.get $byte) (local.get $upper)))
+        (then (return (i32.const 0)))
+      )
+      (local.set $lower (i32.const 0x80))
+      (local.set $upper (i32.const 0xbf))
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br $continuation)
+    ))
+    (local.get $length)
+  )
+
+  ;; decoder.decode(bufferSource), invalid sequences become U+FFFD unless the
+  ;; decoder is fatal. Streaming is not supported.
+  (func $TextDecoder-prototype-decode
+    (type $JSFunc)
+    (param $scope (ref $Scope))
+    (param $this anyref)
+    (param $arguments (ref $JSArgs))
+    (result anyref)
+    (local $input anyref)
+    (local $data (ref null $CharArray))
+    (local $bytes (ref $CharArray))
+    (local $builder (ref $StringBuilder))
+    (local $position i32)
+    (local $end i32)
+    (local $sequence i32)
+    (local $valid_from i32)
+    (local $fatal i32)
+
+    (local.set $input (call $get_argument (local.get $arguments) (i32.const 0)))
+    (if (ref.is_null (local.get $input))
+      (then (return (call $new_static_string (i32.const {{ data(str="") }}) (i32.const {{ data_length() }}))))
+    )
+    (call $buffer_source_bytes (local.get $input))
+    (local.set $end)
+    (local.set $position)
+    (local.set $data)
+    (if (ref.is_null (local.get $data))
+      (then
+        (call $throw_type_error
+          (call $new_static_string (i32.const {{ data(str='The "input" argument must be an instance of ArrayBuffer or ArrayBufferView.') }}) (i32.const {{ data_length() }})))
+      )
+    )
+    (local.set $bytes (ref.as_non_null (local.get $data)))
+    (local.set $end (i32.add (local.get $position) (local.get $end)))
+    (local.set $fatal
+      (call $cast_ref_to_i32_bool (call $get_property (local.get $this) (i32.const {{ data(str="fatal") }}))))
+
+    ;; a byte order mark is skipped
+    (if (i32.and
+          (i32.eqz
+            (call $cast_ref_to_i32_bool (call $get_property (local.get $this) (i32.const {{ data(str="ignoreBOM") }}))))
+          (i32.le_u (i32.add (local.get $position) (i32.const 3)) (local.get $end)))
+      (then
+        (if (i32.and
+              (i32.eq (array.get_u $CharArray (local.get $bytes) (local.get $position)) (i32.const 0xef))
+              (i32.and
+                (i32.eq (array.get_u $CharArray (local.get $bytes) (i32.add (local.get $position) (i32.const 1))) (i32.const 0xbb))
+                (i32.eq (array.get_u $CharArray (local.get $bytes) (i32.add (local.get $position) (i32.const 2))) (i32.const 0xbf))))
+          (then (local.set $position (i32.add (local.get $position) (i32.const 3))))
+        )
+      )
+    )
+
+    ;; valid runs are copied in one go
+    (local.set $builder (call $new_string_builder))
+    (local.set $valid_from (local.get $position))
+    (block $break (loop $sequences
+      (br_if $break (i32.ge_u (local.get $position) (local.get $end)))
+      (local.set $sequence (call $utf8_valid_sequence (local.get $bytes) (local.get $position) (local.get $end)))
+      (if (local.get $sequence)
+        (then (local.set $position (i32.add (local.get $position) (local.get $sequence))))
+        (else
+          (if (local.get $fatal)
+            (then
+              (call $throw_type_error
+                (call $new_static_string (i32.const {{ data(str="The encoded data was not valid for encoding utf-8") }}) (i32.const {{ data_length() }})))
+            )
+          )
+          (call $builder_append_bytes
+            (local.get $builder)
+            (local.get $bytes)
+            (local.get $valid_from)
+            (i32.sub (local.get $position) (local.get $valid_from)))
+          (call $builder_append_code_point (local.get $builder) (i32.const 0xfffd))
+          ;; a maximal prefix of a valid sequence is replaced as a whole
+          (local.set $position
+            (i32.add (local.get $position)
+              (call $utf8_invalid_length (local.get $bytes) (local.get $position) (local.get $end))))
+          (local.set $valid_from (local.get $position))
+        )
+      )
+      (br $sequences)
+    ))
+    (call $builder_append_bytes
+      (local.get $builder)
+      (local.get $bytes)
+      (local.get $valid_from)
+      (i32.sub (local.get $position) (local.get $valid_from)))
+    (call $builder_to_string (local.get $builder))
+  )
+
+  ;; how many bytes of an invalid sequence are replaced by a single U+FFFD: the
+  ;; lead byte and the continuation bytes that were still valid
+  (func $utf8_invalid_length (param $data (ref $CharArray)) (param $position i32) (param $end i32) (result i32)
+    (local $first i32)
+    (local $second i32)
+    (local $length i32)
+
+    (local.set $first (array.get_u $CharArray (local.get $data) (local.get $position)))
+    (if (i32.or (i32.lt_u (local.get $first) (i32.const 0xe0)) (i32.gt_u (local.get $first) (i32.const 0xf4)))
+      (then (return (i32.const 1)))
+    )
+    (if (i32.ge_u (i32.add (local.get $position) (i32.const 1)) (local.get $end))
+      (then (return (i32.const 1)))
+    )
+    (local.set $second
+      (array.get_u $CharArray (local.get $data) (i32.add (local.get $position) (i32.const 1))))
+    (if (i32.or
+          (i32.or
+            (i32.lt_u (local.get $second) (i32.const 0x80))
+            (i32.gt_u (local.get $second) (i32.const 0xbf)))
+          (i32.or
+            (i32.or
+              (i32.and (i32.eq (local.get $first) (i32.const 0xe0)) (i32.lt_u (local.get $second) (i32.const 0xa0)))
+              (i32.and (i32.eq (local.get $first) (i32.const 0xed)) (i32.gt_u (local.get $second) (i32.const 0x9f))))
+            (i32.or
+              (i32.and (i32.eq (local.get $first) (i32.const 0xf0)) (i32.lt_u (local.get $second) (i32.const 0x90)))
+              (i32.and (i32.eq (local.get $first) (i32.const 0xf4)) (i32.gt_u (local.get $second) (i32.const 0x8f))))))
+      (then (return (i32.const 1)))
+    )
+    ;; a 4 byte sequence can still fail at its third byte
+    (local.set $length (i32.const 2))
+    (if (i32.and
+          (i32.ge_u (local.get $first) (i32.const 0xf0))
+          (i32.lt_u (i32.add (local.get $position) (i32.const 2)) (local.get $end)))
+      (then
+        (if (i32.eq
+              (i32.and
+                (array.get_u $CharArray (local.get $data) (i32.add (local.get $position) (i32.const 2)))
+                (i32.const 0xc0))
+              (i32.const 0x80))
+          (then (local.set $length (i32.const 3)))
+        )
+      )
+    )
+    (local.get $length)
+  )
+
+  (elem declare func $TypedArray-prototype-map)
+  (elem declare func $TypedArray-prototype-filter)
+  (elem declare func $TypedArray-prototype-reduce)
+  (elem declare func $TypedArray-prototype-at)
+  (elem declare func $TypedArray-prototype-fill)
+  (elem declare func $TypedArray-prototype-set)
+  (elem declare func $TypedArray-prototype-subarray)
+  (elem declare func $TypedArray-prototype-slice)
+  (elem declare func $TypedArray-prototype-indexOf)
+  (elem declare func $TypedArray-prototype-includes)
+  (elem declare func $TypedArray-prototype-lastIndexOf)
+  (elem declare func $TypedArray-prototype-join)
+  (elem declare func $TypedArray-prototype-toString)
+  (elem declare func $TypedArray-prototype-reverse)
+  (elem declare func $TypedArray-prototype-keys)
+  (elem declare func $TypedArray-prototype-values)
+  (elem declare func $TypedArray-prototype-entries)
+  (elem declare func $TypedArrayIterator-next)
+  (elem declare func $ArrayBuffer-constructor)
+  (elem declare func $ArrayBuffer-isView)
+  (elem declare func $ArrayBuffer-prototype-slice)
+  (elem declare func $DataView-constructor)
+  (elem declare func $TextEncoder-constructor)
+  (elem declare func $TextEncoder-prototype-encode)
+  (elem declare func $TextEncoder-prototype-encodeInto)
+  (elem declare func $TextDecoder-constructor)
+  (elem declare func $TextDecoder-prototype-decode)
+
+  (func $install-typed-array-globals (param $scope (ref $Scope))
+    (local $shared (ref $Object))
+    (local $prototype (ref $Object))
+    (local $constructor anyref)
+    (local $values (ref $Function))
+
+    (local.set $prototype (call $new_object))
+    (call $add_method (local.get $prototype) (i32.const {{ data(str="slice") }}) (ref.func $ArrayBuffer-prototype-slice))
+    (call $set_to_string_tag (local.get $prototype)
+      (call $new_static_string (i32.const {{ data(str="ArrayBuffer") }}) (i32.const {{ data_length() }})))
+    (global.set $array-buffer-prototype (local.get $prototype))
+    (call $install-collection-constructor
+      (local.get $scope) (i32.const {{ data(str="ArrayBuffer") }}) (ref.func $ArrayBuffer-constructor) (local.get $prototype))
+    (local.set $constructor (call $get_variable (local.get $scope) (i32.const {{ data(str="ArrayBuffer") }})))
+    (call $add_method (local.get $constructor) (i32.const {{ data(str="isView") }}) (ref.func $ArrayBuffer-isView))
+
+    (local.set $prototype (call $new_object))
+    (call $add_method (local.get $prototype) (i32.const {{ data(str="next") }}) (ref.func $TypedArrayIterator-next))
+    (call $add_method
+      (local.get $prototype)
+      (call $well_known_symbol_key (global.get $symbol-iterator))
+      (ref.func $CollectionIterator-iterator))
+    (global.set $typed-array-iterator-prototype (local.get $prototype))
+
+    ;; %TypedArray%.prototype
+    (local.set $shared (call $new_object))
+    {% for name in typed_array_predicates %}
+    (call $add_method (local.get $shared) (i32.const {{ data(str=name) }}) (ref.func $TypedArray-prototype-{{ name }}))
+    {% endfor %}
+    (call $add_method (local.get $shared) (i32.const {{ data(str="map") }}) (ref.func $TypedArray-prototype-map))
+    (call $add_method (local.get $shared) (i32.const {{ data(str="filter") }}) (ref.func $TypedArray-prototype-filter))
+    (call $add_method (local.get $shared) (i32.const {{ data(str="reduce") }}) (ref.func $TypedArray-prototype-reduce))
+    (call $add_method (local.get $shared) (i32.const {{ data(str="at") }}) (ref.func $TypedArray-prototype-at))
+    (call $add_method (local.get $shared) (i32.const {{ data(str="fill") }}) (ref.func $TypedArray-prototype-fill))
+    (call $add_method (local.get $shared) (i32.const {{ data(str="set") }}) (ref.func $TypedArray-prototype-set))
+    (call $add_method (local.get $shared) (i32.const {{ data(str="subarray") }}) (ref.func $TypedArray-prototype-subarray))
+    (call $add_method (local.get $shared) (i32.const {{ data(str="slice") }}) (ref.func $TypedArray-prototype-slice))
+    (call $add_method (local.get $shared) (i32.const {{ data(str="indexOf") }}) (ref.func $TypedArray-prototype-indexOf))
+    (call $add_method (local.get $shared) (i32.const {{ data(str="includes") }}) (ref.func $TypedArray-prototype-includes))
+    (call $add_method (local.get $shared) (i32.const {{ data(str="lastIndexOf") }}) (ref.func $TypedArray-prototype-lastIndexOf))
+    (call $add_method (local.get $shared) (i32.const {{ data(str="join") }}) (ref.func $TypedArray-prototype-join))
+    (call $add_method (local.get $shared) (i32.const {{ data(str="toString") }}) (ref.func $TypedArray-prototype-toString))
+    (call $add_method (local.get $shared) (i32.const {{ data(str="reverse") }}) (ref.func $TypedArray-prototype-reverse))
+    (call $add_method (local.get $shared) (i32.const {{ data(str="keys") }}) (ref.func $TypedArray-prototype-keys))
+    (call $add_method (local.get $shared) (i32.const {{ data(str="entries") }}) (ref.func $TypedArray-prototype-entries))
+    ;; %TypedArray%.prototype[Symbol.iterator] is the same function as values
+    (local.set $values
+      (call $new_function (local.get $scope) (ref.func $TypedArray-prototype-values) (ref.null any)))
+    (call $set_property (local.get $shared) (i32.const {{ data(str="values") }}) (local.get $values))
+    (call $set_property
+      (local.get $shared)
+      (call $well_known_symbol_key (global.get $symbol-iterator))
+      (local.get $values))
+
+    (global.set $typed-array-prototypes
+      (array.new $AnyrefArray (ref.null any) (i32.const {{ typed_array_names | length }})))
+    {% for name in typed_array_names %}
+    (local.set $prototype (call $new_object))
+    (struct.set $Object $prototype (local.get $prototype) (local.get $shared))
+    (call $set_property
+      (local.get $prototype)
+      (i32.const {{ data(str="BYTES_PER_ELEMENT") }})
+      (ref.i31 (i32.const {{ typed_array_sizes[loop.index0] }})))
+    (call $set_to_string_tag (local.get $prototype)
+      (call $new_static_string (i32.const {{ data(str=name ~ "Array") }}) (i32.const {{ data_length() }})))
+    (array.set $AnyrefArray (global.get $typed-array-prototypes) (i32.const {{ loop.index0 }}) (local.get $prototype))
+    (call $install-collection-constructor
+      (local.get $scope) (i32.const {{ data(str=name ~ "Array") }}) (ref.func ${{ name }}Array-constructor) (local.get $prototype))
+    (local.set $constructor (call $get_variable (local.get $scope) (i32.const {{ data(str=name ~ "Array") }})))
+    (call $set_property
+      (local.get $constructor)
+      (i32.const {{ data(str="BYTES_PER_ELEMENT") }})
+      (ref.i31 (i32.const {{ typed_array_sizes[loop.index0] }})))
+    (call $add_method (local.get $constructor) (i32.const {{ data(str="from") }}) (ref.func ${{ name }}Array-from))
+    (call $add_method (local.get $constructor) (i32.const {{ data(str="of") }}) (ref.func ${{ name }}Array-of))
+    {% endfor %}
+
+    (local.set $prototype (call $new_object))
+    {% for name in typed_array_names %}
+    {% if name != "Uint8Clamped" %}
+    (call $add_method (local.get $prototype) (i32.const {{ data(str="get" ~ name) }}) (ref.func $DataView-prototype-get{{ name }}))
+    (call $add_method (local.get $prototype) (i32.const {{ data(str="set" ~ name) }}) (ref.func $DataView-prototype-set{{ name }}))
+    {% endif %}
+    {% endfor %}
+    (call $set_to_string_tag (local.get $prototype)
+      (call $new_static_string (i32.const {{ data(str="DataView") }}) (i32.const {{ data_length() }})))
+    (global.set $data-view-prototype (local.get $prototype))
+    (call $install-collection-constructor
+      (local.get $scope) (i32.const {{ data(str="DataView") }}) (ref.func $DataView-constructor) (local.get $prototype))
+
+    (local.set $prototype (call $new_object))
+    (call $add_method (local.get $prototype) (i32.const {{ data(str="encode") }}) (ref.func $TextEncoder-prototype-encode))
+    (call $add_method (local.get $prototype) (i32.const {{ data(str="encodeInto") }}) (ref.func $TextEncoder-prototype-encodeInto))
+    (call $set_property
+      (local.get $prototype)
+      (i32.const {{ data(str="encoding") }})
+      (call $new_static_string (i32.const {{ data(str="utf-8") }}) (i32.const {{ data_length() }})))
+    (call $set_to_string_tag (local.get $prototype)
+      (call $new_static_string (i32.const {{ data(str="TextEncoder") }}) (i32.const {{ data_length() }})))
+    (call $install-collection-constructor
+      (local.get $scope) (i32.const {{ data(str="TextEncoder") }}) (ref.func $TextEncoder-constructor) (local.get $prototype))
+
+    (local.set $prototype (call $new_object))
+    (call $add_method (local.get $prototype) (i32.const {{ data(str="decode") }}) (ref.func $TextDecoder-prototype-decode))
+    (call $set_to_string_tag (local.get $prototype)
+      (call $new_static_string (i32.const {{ data(str="TextDecoder") }}) (i32.const {{ data_length() }})))
+    (call $install-collection-constructor
+      (local.get $scope) (i32.const {{ data(str="TextDecoder") }}) (ref.func $TextDecoder-constructor) (local.get $prototype))
+  )
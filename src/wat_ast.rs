@@ -1,7 +1,9 @@
-use std::collections::{HashMap, HashSet, VecDeque};
-use std::fmt::{self, write};
+// The instruction set is meant to cover whatever the translator may need, so not
+// every variant or helper is used at any given time
+#![allow(dead_code, clippy::vec_box)]
 
-use boa_ast::statement::LabelledItem;
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
 
 #[derive(Debug, Clone)]
 pub enum WatInstruction {
@@ -407,7 +409,7 @@ impl WatFunction {
         let name = format!("{name}-{counter}");
         self.locals.insert(name.clone(), r#type);
 
-        return name;
+        name
     }
 
     pub fn add_instruction(&mut self, instruction: Box<WatInstruction>) {
@@ -488,7 +490,7 @@ impl fmt::Display for WatModule {
 
         // Function declarations
         for function in &self.functions {
-            write!(f, "(elem declare func ${})\n", function.name)?;
+            writeln!(f, "(elem declare func ${})", function.name)?;
         }
 
         // Functions
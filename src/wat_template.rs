@@ -1,25 +1,97 @@
 use std::{
-    collections::{BTreeMap, HashMap, HashSet},
-    rc::Rc,
+    collections::HashMap,
     sync::{
         atomic::{AtomicI32, Ordering},
         Arc, Mutex,
     },
 };
 
-use boa_interner::{Interner, JStrRef};
 use tera::{from_value, to_value, Context, Function, Tera, Value};
 
 use crate::WasmTranslator;
 
+// Runtime parts that are big enough to deserve their own file. Each of them is
+// included in the main template with `{% include "name.wat" %}`
+const INCLUDED_TEMPLATES: &[(&str, &str)] = &[
+    ("memory.wat", include_str!("wat/memory.wat")),
+    ("strings.wat", include_str!("wat/strings.wat")),
+    ("object.wat", include_str!("wat/object.wat")),
+    ("error.wat", include_str!("wat/error.wat")),
+    ("symbol.wat", include_str!("wat/symbol.wat")),
+    ("bignum.wat", include_str!("wat/bignum.wat")),
+    ("number.wat", include_str!("wat/number.wat")),
+    ("math.wat", include_str!("wat/math.wat")),
+    ("json.wat", include_str!("wat/json.wat")),
+    ("collections.wat", include_str!("wat/collections.wat")),
+    ("regexp.wat", include_str!("wat/regexp.wat")),
+    ("date.wat", include_str!("wat/date.wat")),
+    ("bigint.wat", include_str!("wat/bigint.wat")),
+    ("typedarray.wat", include_str!("wat/typedarray.wat")),
+    ("proxy.wat", include_str!("wat/proxy.wat")),
+    ("console.wat", include_str!("wat/console.wat")),
+    ("promise.wat", include_str!("wat/promise.wat")),
+    ("pollables.wat", include_str!("wat/pollables.wat")),
+    ("timers.wat", include_str!("wat/timers.wat")),
+    ("fetch.wat", include_str!("wat/fetch.wat")),
+    ("process.wat", include_str!("wat/process.wat")),
+    ("fs.wat", include_str!("wat/fs.wat")),
+    ("stdin.wat", include_str!("wat/stdin.wat")),
+    ("crypto.wat", include_str!("wat/crypto.wat")),
+];
+
+fn escape_wat_string(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            c if (c as u32) < 0x20 || c as u32 == 0x7f => {
+                escaped.push_str(&format!("\\{:02x}", c as u32))
+            }
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+// Strings passed to the `data` function come straight from the template, so any
+// escapes like `\n` are still in their source form
+fn unescape_template_string(value: &str) -> String {
+    let mut result = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => result.push('\n'),
+            Some('t') => result.push('\t'),
+            Some('r') => result.push('\r'),
+            Some(c) => result.push(c),
+            None => result.push('\\'),
+        }
+    }
+    result
+}
+
+// Each string is prefixed with its length as a 32 bit little endian number, so
+// the runtime can turn an offset (which is also used as a property key) back into
+// a string
 fn render_data_entries(data_entries: &HashMap<i32, String>) -> String {
     data_entries
         .iter()
         .map(|(offset, value)| {
+            let length: String = (value.len() as u32)
+                .to_le_bytes()
+                .iter()
+                .map(|b| format!("\\{b:02x}"))
+                .collect();
             format!(
-                "(data $d{offset} (i32.const {offset}) \"{value}\")\n",
+                "(data $d{offset} (i32.const {prefix_offset}) \"{length}{value}\")\n",
                 offset = offset,
-                value = value
+                prefix_offset = offset - 4,
+                value = escape_wat_string(value)
             )
         })
         .collect()
@@ -36,6 +108,7 @@ fn data(
                     Ok(v) => {
                         // if the value does not exist insert any i32, we will fix it on the second
                         // run
+                        let v = unescape_template_string(&v);
                         last_data_entry_length.store(v.len() as i32, Ordering::Relaxed);
                         let value = interner.lock().unwrap().entry(v).or_insert(0).to_string();
                         Ok(to_value(value).unwrap())
@@ -59,6 +132,69 @@ fn data_length(last_data_entry_length: Arc<AtomicI32>) -> impl Function {
     )
 }
 
+// How the generated module waits for pollables, chosen with the JAWS_EVENT_LOOP
+// environment variable
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum EventLoop {
+    // `poll` returns right away and the host calls the exported `main_loop`
+    // once a pollable is ready, this is what `run.js` implements
+    #[default]
+    Polyfill,
+    // `run` loops on a blocking `poll` until there are no pollables left, for
+    // native WASIp2 runtimes
+    Blocking,
+}
+
+impl EventLoop {
+    pub fn from_env() -> anyhow::Result<Self> {
+        match std::env::var("JAWS_EVENT_LOOP") {
+            Err(_) => Ok(Self::default()),
+            Ok(value) => match value.as_str() {
+                "" | "polyfill" => Ok(Self::Polyfill),
+                "blocking" => Ok(Self::Blocking),
+                _ => Err(anyhow::anyhow!(
+                    "JAWS_EVENT_LOOP must be either \"polyfill\" or \"blocking\", got \"{value}\""
+                )),
+            },
+        }
+    }
+}
+
+// The parts of the runtime that are only emitted when needed
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TemplateOptions {
+    pub event_loop: EventLoop,
+    // fetch, Headers and Response, which need the wasi:http imports
+    pub fetch: bool,
+    // the `process` global, which needs wasi:cli/environment
+    pub process: bool,
+    // the `fs` global, which needs wasi:filesystem
+    pub filesystem: bool,
+    // `process.stdin`, which needs wasi:cli/stdin, only with `process`
+    pub stdin: bool,
+    // the current time for `Date` and `performance`, which needs
+    // wasi:clocks/wall-clock
+    pub date: bool,
+    // the `crypto` global, which needs wasi:random/random.get-random-bytes
+    pub crypto: bool,
+    // Math.random, which is seeded with wasi:random/random.get-random-u64
+    pub random: bool,
+    // a fixed seed for Math.random instead of one from wasi:random
+    pub random_seed: Option<u64>,
+}
+
+// The seed for Math.random, fixed at compile time with the JAWS_RANDOM_SEED
+// environment variable so load tests can be reproduced
+pub fn random_seed_from_env() -> anyhow::Result<Option<u64>> {
+    match std::env::var("JAWS_RANDOM_SEED") {
+        Err(_) => Ok(None),
+        Ok(value) if value.is_empty() => Ok(None),
+        Ok(value) => value.parse().map(Some).map_err(|_| {
+            anyhow::anyhow!("JAWS_RANDOM_SEED must be an unsigned 64-bit integer, got \"{value}\"")
+        }),
+    }
+}
+
 // TODO: using Tera's functions we could avoid defining data beforehand (like listing each string
 // that will be needed for WAT code). If data definition rendering is split from the rest of the
 // rendering there could be a function like data("am arbitrary string") that inserts the string
@@ -67,15 +203,34 @@ pub fn generate_wat_template(
     additional_functions: impl Into<String>,
     init_code: impl Into<String>,
     translator: &mut WasmTranslator,
+    options: TemplateOptions,
 ) -> String {
     let template = std::include_str!("wat/template.wat");
     let mut tera = Tera::default();
-    tera.add_raw_template("module", template).unwrap();
+    let mut templates = INCLUDED_TEMPLATES.to_vec();
+    templates.push(("module", template));
+    tera.add_raw_templates(templates).unwrap();
     let mut context = Context::new();
     context.insert("init_code", &init_code.into());
     context.insert("data_entries", "");
     context.insert("free_memory_offset", "");
+    context.insert("static_strings_start", "");
+    context.insert("static_strings_end", "");
     context.insert("additional_functions", &additional_functions.into());
+    context.insert(
+        "blocking_event_loop",
+        &(options.event_loop == EventLoop::Blocking),
+    );
+    context.insert("fetch", &options.fetch);
+    context.insert("process", &options.process);
+    context.insert("filesystem", &options.filesystem);
+    context.insert("stdin", &(options.process && options.stdin));
+    context.insert("date", &options.date);
+    context.insert("crypto", &options.crypto);
+    context.insert("random", &options.random);
+    if let Some(seed) = options.random_seed {
+        context.insert("random_seed", &seed);
+    }
     let mapping = Arc::new(Mutex::new(HashMap::new()));
 
     let last_data_entry_length = Arc::new(AtomicI32::new(0));
@@ -104,6 +259,11 @@ pub fn generate_wat_template(
     drop(locked);
     let offset = translator.data_offset + (4 - translator.data_offset % 4);
     context.insert("free_memory_offset", &offset.to_string());
+    context.insert(
+        "static_strings_start",
+        &crate::STATIC_STRINGS_START.to_string(),
+    );
+    context.insert("static_strings_end", &translator.data_offset.to_string());
 
     tera.render("module", &context).unwrap()
 }
@@ -0,0 +1,55 @@
+// closures created in a loop capture the bindings of their own iteration
+let a = [];
+let i = 0;
+while (i < 3) {
+  let j = i;
+  a[i] = function () {
+    return j;
+  };
+  i++;
+}
+console.log(a[0](), a[1](), a[2]());
+
+// `for (let ...)` gets a new binding for each iteration, copied before the
+// final expression runs
+let b = [];
+for (let k = 0; k < 3; k++) {
+  b[k] = () => k;
+}
+console.log(b[0](), b[1](), b[2]());
+
+// closures from the same iteration share the binding
+let getters = [];
+let setters = [];
+for (let n = 0; n < 2; n++) {
+  getters[n] = () => n;
+  setters[n] = (value) => {
+    n = value;
+  };
+}
+setters[0](10);
+console.log(getters[0](), getters[1]());
+
+// bindings of nested blocks and functions get their own environments too
+function counters() {
+  let result = [];
+  let total = 0;
+  for (let x = 1; x <= 3; x++) {
+    let doubled = x * 2;
+    {
+      let label = "c" + x;
+      result[x - 1] = () => label + ":" + doubled + ":" + total;
+    }
+    total += x;
+  }
+  return result;
+}
+let c = counters();
+console.log(c[0](), c[1](), c[2]());
+
+// variables declared with var are shared between iterations
+var d = [];
+for (var m = 0; m < 3; m++) {
+  d[m] = () => m;
+}
+console.log(d[0](), d[1](), d[2]());
@@ -0,0 +1,38 @@
+// assigning to a constant throws a TypeError and leaves the value unchanged
+const limit = 1;
+try {
+  limit = 2;
+} catch (e) {
+  console.log(e instanceof TypeError, e.message);
+}
+console.log(limit);
+
+// updates are assignments too
+try {
+  limit++;
+} catch (e) {
+  console.log(e instanceof TypeError);
+}
+
+// including constants captured by closures
+function counter() {
+  const count = 0;
+  return () => {
+    count += 1;
+    return count;
+  };
+}
+let increment = counter();
+try {
+  increment();
+} catch (e) {
+  console.log(e.name, e.message);
+}
+
+// a const in a loop body is a new binding in every iteration
+let i = 0;
+while (i < 2) {
+  const value = i * 10;
+  console.log(value);
+  i++;
+}
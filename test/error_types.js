@@ -0,0 +1,51 @@
+// errors thrown by the runtime are instances of the Error subclasses
+let cyclic = {};
+cyclic.self = cyclic;
+try {
+  JSON.stringify(cyclic);
+} catch (e) {
+  console.log(typeof e, e instanceof TypeError, e instanceof Error, e.name);
+}
+
+try {
+  undefinedVariable;
+} catch (e) {
+  console.log(e instanceof ReferenceError, e.message);
+}
+
+try {
+  JSON.parse("{");
+} catch (e) {
+  console.log(e instanceof SyntaxError, e.name);
+}
+
+try {
+  (1).toFixed(200);
+} catch (e) {
+  console.log(e instanceof RangeError, e.name);
+}
+
+let nothing = null;
+try {
+  let x = nothing.x;
+} catch (e) {
+  console.log(e instanceof TypeError, e instanceof RangeError);
+}
+
+// the constructors can be used directly
+let error = new Error("boom", { cause: 42 });
+console.log(error.message, error.cause, "" + error);
+let typeError = new TypeError("bad");
+console.log(typeError.toString(), typeError instanceof Error, typeError.constructor === TypeError);
+console.log(Object.getPrototypeOf(TypeError.prototype) === Error.prototype);
+console.log(new RangeError().toString(), RangeError.prototype.name);
+
+try {
+  throw new SyntaxError("custom");
+} catch (e) {
+  console.log(e.name, e.message);
+}
+
+Promise.any([Promise.reject(1)]).catch((e) => {
+  console.log(e instanceof AggregateError, e instanceof Error, e.errors);
+});
@@ -0,0 +1,12 @@
+// functions declared at the top level are properties of the global object, so
+// they can be called with arguments of any type through it
+function sq(x) {
+  return x * x;
+}
+function half(x) {
+  return x / 2;
+}
+console.log(sq(3), half(5));
+console.log(globalThis.sq("4"), this.sq(true), globalThis.half("x"));
+var f = globalThis.sq;
+console.log(f(null), typeof globalThis.half);
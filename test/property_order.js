@@ -0,0 +1,22 @@
+// own keys are listed with array indexes first, in ascending order, then the
+// other strings and then symbols, both in insertion order
+let o = { b: 1, a: 2, c: 3, 10: 4, 2: 5 };
+console.log(Object.keys(o));
+console.log(Object.entries(o));
+console.log(Object.values(o));
+console.log(JSON.stringify(o));
+console.log(o);
+let p = {};
+p.x = 1;
+p[5] = 2;
+p["01"] = 3;
+p[1] = 4;
+p[Symbol.iterator] = 5;
+p.y = 6;
+p[4294967294] = 7;
+console.log(Object.keys(p), Reflect.ownKeys(p).length);
+console.log(p);
+delete p[1];
+console.log(Object.keys(p));
+let copy = Object.assign({}, { z: 1, 3: 2, 1: 3 });
+console.log(copy, JSON.stringify(JSON.parse('{"b":1,"7":2,"a":3,"0":4}')));
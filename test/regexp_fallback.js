@@ -0,0 +1,17 @@
+// literals the ahead of time compiler can't handle are compiled at runtime,
+// which throws a SyntaxError when they're evaluated instead of failing the
+// compilation. Node supports property escapes, so its output differs
+try {
+  /\p{L}/u.test("a");
+} catch (e) {
+  console.log(e instanceof SyntaxError, e.message);
+}
+
+try {
+  /(?:a{1000}){1000}/.test("a");
+} catch (e) {
+  console.log(e instanceof SyntaxError, e.message);
+}
+
+// the rest of the script still works
+console.log(/a+/.test("caat"));
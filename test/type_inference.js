@@ -0,0 +1,26 @@
+// numbers and booleans kept in unboxed locals, and known functions with
+// typed parameters and returns
+function main() {
+  function sq(x) {
+    return x * x;
+  }
+  function fib(n) {
+    if (n < 2) {
+      return n;
+    }
+    return fib(n - 1) + fib(n - 2);
+  }
+  let total = 0;
+  let i = 0;
+  let even = true;
+  while (i < 5) {
+    total += sq(i);
+    even = !even;
+    i++;
+  }
+  console.log(total, even, fib(15));
+  let mixed = 1;
+  mixed = mixed + "1";
+  console.log(mixed);
+}
+main();
@@ -0,0 +1,147 @@
+//! Compiles every script in `test/` and checks that the generated module is
+//! valid WebAssembly. Running the modules needs a runtime with WASM GC and
+//! exception handling (see test/README.md), so the expected output of a
+//! fixture is kept next to it in a `.expected` file and compared by hand.
+//!
+//! Which WASI imports a module gets only depends on the compiler, so the tests
+//! at the bottom check that the optional ones are emitted only for scripts
+//! using them.
+use std::{
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+use wasmparser::{Validator, WasmFeatures};
+
+fn fixtures() -> Vec<PathBuf> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("test");
+    let mut scripts: Vec<PathBuf> = fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "js"))
+        .collect();
+    scripts.sort();
+    scripts
+}
+
+// compiles a script to WAT, `name` picks the directory it's written to
+fn generate(name: &str, source: &[u8]) -> Result<String, String> {
+    let jaws_dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join(name);
+    fs::create_dir_all(jaws_dir.join("wat")).unwrap();
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_jaws"))
+        .env("JAWS_DIR", &jaws_dir)
+        .stdin(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(source).unwrap();
+    let output = child.wait_with_output().unwrap();
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).to_string());
+    }
+    Ok(fs::read_to_string(jaws_dir.join("wat/generated.wat")).unwrap())
+}
+
+fn compile(script: &Path) -> Result<Vec<u8>, String> {
+    let name = script.file_stem().unwrap().to_string_lossy().to_string();
+    let wat = generate(&name, &fs::read(script).unwrap())?;
+    let wasm = wat::parse_str(&wat).map_err(|error| error.to_string())?;
+    Validator::new_with_features(WasmFeatures::all())
+        .validate_all(&wasm)
+        .map_err(|error| error.to_string())?;
+    Ok(wasm)
+}
+
+#[test]
+fn fixtures_compile_to_valid_modules() {
+    let failures: Vec<String> = fixtures()
+        .iter()
+        .filter_map(|script| {
+            compile(script)
+                .err()
+                .map(|error| format!("{}: {error}", script.display()))
+        })
+        .collect();
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
+
+// the WASI functions imported by a script, like "wasi:clocks/wall-clock now"
+fn wasi_imports(name: &str, source: &str) -> Vec<String> {
+    let wat = generate(name, source.as_bytes()).unwrap();
+    wat.lines()
+        .filter_map(|line| line.trim().strip_prefix("(import \"wasi:"))
+        .map(|import| {
+            let mut parts = import.split('"');
+            let module = parts.next().unwrap();
+            let function = parts.nth(1).unwrap();
+            format!("wasi:{} {function}", module.split('@').next().unwrap())
+        })
+        .collect()
+}
+
+fn imports(imports: &[String], prefix: &str) -> bool {
+    imports.iter().any(|import| import.starts_with(prefix))
+}
+
+#[test]
+fn wall_clock_is_only_imported_for_date() {
+    let unused = wasi_imports("no_date", "console.log(1);");
+    assert!(!imports(&unused, "wasi:clocks/wall-clock"));
+    let date = wasi_imports("date", "console.log(new Date().getTime() > 0);");
+    assert!(imports(&date, "wasi:clocks/wall-clock now"));
+    let performance = wasi_imports("performance", "console.log(performance.timeOrigin);");
+    assert!(imports(&performance, "wasi:clocks/wall-clock now"));
+}
+
+#[test]
+fn random_seed_is_only_imported_for_math_random() {
+    let unused = wasi_imports("no_random", "console.log(Math.floor(1.5));");
+    assert!(!imports(&unused, "wasi:random/random get-random-u64"));
+    let random = wasi_imports("random", "console.log(Math.random() < 1);");
+    assert!(imports(&random, "wasi:random/random get-random-u64"));
+}
+
+#[test]
+fn http_is_only_imported_for_fetch() {
+    let unused = wasi_imports("no_fetch", "console.log(1);");
+    assert!(!imports(&unused, "wasi:http/"));
+    let fetch = wasi_imports("fetch", "fetch('http://127.0.0.1:8080/text');");
+    assert!(imports(&fetch, "wasi:http/outgoing-handler handle"));
+    assert!(imports(
+        &fetch,
+        "wasi:http/types [constructor]outgoing-request"
+    ));
+}
+
+#[test]
+fn environment_and_filesystem_are_only_imported_when_mentioned() {
+    let unused = wasi_imports("no_process", "console.log(1);");
+    assert!(!imports(&unused, "wasi:cli/environment"));
+    assert!(!imports(&unused, "wasi:filesystem/"));
+    let process = wasi_imports("process", "console.log(process.argv.length);");
+    assert!(imports(&process, "wasi:cli/environment get-arguments"));
+    assert!(!imports(&process, "wasi:filesystem/"));
+    let fs = wasi_imports("fs", "console.log(fs.existsSync('a'));");
+    assert!(imports(&fs, "wasi:filesystem/preopens get-directories"));
+    assert!(!imports(&fs, "wasi:cli/environment"));
+}
+
+#[test]
+fn stdin_is_only_imported_when_mentioned() {
+    let process = wasi_imports("process_without_stdin", "console.log(process.argv.length);");
+    assert!(!imports(&process, "wasi:cli/stdin"));
+    let stdin = wasi_imports("stdin", "process.stdin.on('data', (chunk) => {});");
+    assert!(imports(&stdin, "wasi:cli/stdin get-stdin"));
+}
+
+#[test]
+fn random_bytes_are_only_imported_for_crypto() {
+    let unused = wasi_imports("no_crypto", "console.log(Math.random() < 1);");
+    assert!(!imports(&unused, "wasi:random/random get-random-bytes"));
+    let crypto = wasi_imports("crypto", "console.log(crypto.randomUUID().length);");
+    assert!(imports(&crypto, "wasi:random/random get-random-bytes"));
+    assert!(!imports(&crypto, "wasi:random/random get-random-u64"));
+}